@@ -0,0 +1,169 @@
+//! Storage backends bundle the three storages an instance needs (chain CAS, DHT CAS and EAV)
+//! and can be looked up by name through a StorageBackendRegistry.
+//! This way a conductor can select the backend from its configuration and new persistent
+//! backends only need to implement the CAS and EAV traits and get registered under a name.
+
+use crate::{
+    cas::{file::FilesystemStorage, format::ContentFormat, memory::MemoryStorage},
+    eav::{file::EavFileStorage, memory::EavMemoryStorage},
+    path::create_path_if_not_exists,
+};
+use holochain_core_types::{
+    cas::storage::ContentAddressableStorage, eav::EntityAttributeValueStorage,
+    error::HolochainError,
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+pub const MEMORY_BACKEND: &str = "memory";
+pub const FILE_BACKEND: &str = "file";
+pub const FILE_MESSAGE_PACK_BACKEND: &str = "file_msgpack";
+
+/// The set of storages an instance gets spawned with.
+#[derive(Clone, Debug)]
+pub struct StorageBackend {
+    pub chain_storage: Arc<RwLock<ContentAddressableStorage>>,
+    pub dht_storage: Arc<RwLock<ContentAddressableStorage>>,
+    pub eav_storage: Arc<RwLock<EntityAttributeValueStorage>>,
+}
+
+/// Constructs a StorageBackend. Gets passed the configured path, if any.
+pub type StorageBackendFactory =
+    Arc<Box<Fn(Option<&Path>) -> Result<StorageBackend, HolochainError> + Send + Sync>>;
+
+/// Maps backend names (as used in the conductor config) to factories.
+#[derive(Clone)]
+pub struct StorageBackendRegistry {
+    factories: HashMap<String, StorageBackendFactory>,
+}
+
+impl StorageBackendRegistry {
+    /// Creates a registry that knows the backends implemented in this crate,
+    /// i.e. "memory", "file" and "file_msgpack".
+    pub fn new() -> Self {
+        let mut registry = StorageBackendRegistry {
+            factories: HashMap::new(),
+        };
+        registry.register(MEMORY_BACKEND, |_| Ok(memory_backend()));
+        registry.register(FILE_BACKEND, |path| {
+            let path = path.ok_or_else(|| {
+                HolochainError::ConfigError("File storage backend needs a path".to_string())
+            })?;
+            file_backend(path)
+        });
+        registry.register(FILE_MESSAGE_PACK_BACKEND, |path| {
+            let path = path.ok_or_else(|| {
+                HolochainError::ConfigError("File storage backend needs a path".to_string())
+            })?;
+            file_backend_with_format(path, ContentFormat::MessagePack)
+        });
+        registry
+    }
+
+    /// Registers a factory under the given name.
+    /// Replaces any factory that was registered under the same name before.
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(Option<&Path>) -> Result<StorageBackend, HolochainError> + Send + Sync + 'static,
+    {
+        self.factories
+            .insert(name.to_string(), Arc::new(Box::new(factory)));
+    }
+
+    /// Names of all registered backends, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.factories.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Constructs the backend registered under the given name.
+    /// Returns an error if there is no such backend or if the factory fails.
+    pub fn create(
+        &self,
+        name: &str,
+        path: Option<&Path>,
+    ) -> Result<StorageBackend, HolochainError> {
+        let factory = self.factories.get(name).ok_or_else(|| {
+            HolochainError::ConfigError(format!(
+                "Unknown storage backend \"{}\". Known backends are: {}",
+                name,
+                self.names().join(", ")
+            ))
+        })?;
+        factory(path)
+    }
+}
+
+/// Transient memory storages. Chain and DHT get separate CAS instances.
+pub fn memory_backend() -> StorageBackend {
+    StorageBackend {
+        chain_storage: Arc::new(RwLock::new(MemoryStorage::new())),
+        dht_storage: Arc::new(RwLock::new(MemoryStorage::new())),
+        eav_storage: Arc::new(RwLock::new(EavMemoryStorage::new())),
+    }
+}
+
+/// Persistent file storages in the sub-directories "cas" and "eav" of the given path.
+/// Chain and DHT share the same file CAS.
+pub fn file_backend<P: AsRef<Path>>(path: P) -> Result<StorageBackend, HolochainError> {
+    file_backend_with_format(path, ContentFormat::Json)
+}
+
+/// Like file_backend(), but the CAS writes content in the given format.
+pub fn file_backend_with_format<P: AsRef<Path>>(
+    path: P,
+    format: ContentFormat,
+) -> Result<StorageBackend, HolochainError> {
+    let base_path: PathBuf = path.as_ref().into();
+    let cas_path = base_path.join("cas");
+    let eav_path = base_path.join("eav");
+    create_path_if_not_exists(&cas_path)?;
+    create_path_if_not_exists(&eav_path)?;
+
+    let file_storage = Arc::new(RwLock::new(
+        FilesystemStorage::new(&cas_path)?.with_format(format),
+    ));
+    Ok(StorageBackend {
+        chain_storage: file_storage.clone(),
+        dht_storage: file_storage,
+        eav_storage: Arc::new(RwLock::new(EavFileStorage::new(eav_path)?)),
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    extern crate tempfile;
+
+    use self::tempfile::tempdir;
+    use super::*;
+
+    #[test]
+    fn registry_knows_default_backends() {
+        let registry = StorageBackendRegistry::new();
+        assert_eq!(
+            registry.names(),
+            vec![
+                FILE_BACKEND.to_string(),
+                FILE_MESSAGE_PACK_BACKEND.to_string(),
+                MEMORY_BACKEND.to_string()
+            ]
+        );
+        assert!(registry.create(MEMORY_BACKEND, None).is_ok());
+        assert!(registry.create(FILE_BACKEND, None).is_err());
+
+        let dir = tempdir().unwrap();
+        assert!(registry.create(FILE_BACKEND, Some(dir.path())).is_ok());
+    }
+
+    #[test]
+    fn registry_creates_registered_backend() {
+        let mut registry = StorageBackendRegistry::new();
+        assert!(registry.create("custom", None).is_err());
+        registry.register("custom", |_| Ok(memory_backend()));
+        assert!(registry.create("custom", None).is_ok());
+    }
+}
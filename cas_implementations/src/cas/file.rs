@@ -1,3 +1,4 @@
+use crate::cas::format::ContentFormat;
 use holochain_core_types::{
     cas::{
         content::{Address, AddressableContent, Content},
@@ -6,7 +7,8 @@ use holochain_core_types::{
     error::HolochainError,
 };
 use std::{
-    fs::{create_dir_all, read_to_string, write},
+    collections::BTreeSet,
+    fs::{create_dir_all, read, read_dir, remove_file, write},
     path::{Path, PathBuf},
     sync::{Arc, RwLock},
 };
@@ -17,6 +19,8 @@ use uuid::Uuid;
 pub struct FilesystemStorage {
     /// path to the directory where content will be saved to disk
     dir_path: PathBuf,
+    /// format new content gets written in
+    format: ContentFormat,
     id: Uuid,
     lock: Arc<RwLock<()>>,
 }
@@ -33,18 +37,37 @@ impl FilesystemStorage {
 
         Ok(FilesystemStorage {
             dir_path,
+            format: ContentFormat::default(),
             id: Uuid::new_v4(),
             lock: Arc::new(RwLock::new(())),
         })
     }
 
-    /// builds an absolute path for an AddressableContent address
-    fn address_to_path(&self, address: &Address) -> PathBuf {
-        // using .txt extension because content is arbitrary and controlled by the
-        // AddressableContent trait implementation
+    /// Writes new content in the given format instead of JSON.
+    /// Content that is already on disk stays readable, whatever format it got written in.
+    pub fn with_format(mut self, format: ContentFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// builds an absolute path for an AddressableContent address stored in the given format
+    fn address_to_path(&self, address: &Address, format: ContentFormat) -> PathBuf {
+        // the JSON format uses the .txt extension because content is arbitrary and
+        // controlled by the AddressableContent trait implementation
         self.dir_path
             .join(address.to_string())
-            .with_extension("txt")
+            .with_extension(format.extension())
+    }
+
+    /// reads the content stored under the given address, in whichever format it is stored
+    fn read_content(&self, address: &Address) -> Result<Option<Content>, HolochainError> {
+        for format in ContentFormat::all().iter() {
+            let path = self.address_to_path(address, *format);
+            if path.is_file() {
+                return Ok(Some(format.decode(&read(path)?)?));
+            }
+        }
+        Ok(None)
     }
 }
 
@@ -55,26 +78,72 @@ impl ContentAddressableStorage for FilesystemStorage {
         // @see https://github.com/holochain/holochain-rust/issues/248
         create_dir_all(&self.dir_path)?;
 
-        write(
-            self.address_to_path(&content.address()),
-            content.content().to_string(),
-        )?;
+        // content the configured format can't reproduce exactly gets stored as JSON
+        let (format, bytes) = match self.format.encode(&content.content())? {
+            Some(bytes) => (self.format, bytes),
+            None => (
+                ContentFormat::Json,
+                String::from(content.content()).into_bytes(),
+            ),
+        };
+        write(self.address_to_path(&content.address(), format), bytes)?;
 
         Ok(())
     }
 
     fn contains(&self, address: &Address) -> Result<bool, HolochainError> {
         let _guard = self.lock.read()?;
-        Ok(Path::new(&self.address_to_path(address)).is_file())
+        Ok(ContentFormat::all()
+            .iter()
+            .any(|format| self.address_to_path(address, *format).is_file()))
     }
 
     fn fetch(&self, address: &Address) -> Result<Option<Content>, HolochainError> {
         let _guard = self.lock.read()?;
-        if self.contains(&address)? {
-            Ok(Some(read_to_string(self.address_to_path(address))?.into()))
-        } else {
-            Ok(None)
+        self.read_content(address)
+    }
+
+    fn fetch_many(&self, addresses: &[Address]) -> Result<Vec<Option<Content>>, HolochainError> {
+        let _guard = self.lock.read()?;
+        addresses
+            .iter()
+            .map(|address| self.read_content(address))
+            .collect()
+    }
+
+    fn addresses(&self) -> Result<Vec<Address>, HolochainError> {
+        let _guard = self.lock.read()?;
+        // nothing has been added yet if the directory was never created
+        if !self.dir_path.is_dir() {
+            return Ok(Vec::new());
+        }
+        // content might be stored in more than one format after the format got changed
+        let mut addresses = BTreeSet::new();
+        for dir_entry in read_dir(&self.dir_path)? {
+            let path = dir_entry?.path();
+            let known_extension = path.extension().map_or(false, |ext| {
+                ContentFormat::all()
+                    .iter()
+                    .any(|format| ext == format.extension())
+            });
+            if path.is_file() && known_extension {
+                if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    addresses.insert(Address::from(stem));
+                }
+            }
+        }
+        Ok(addresses.into_iter().collect())
+    }
+
+    fn remove(&mut self, address: &Address) -> Result<(), HolochainError> {
+        let _guard = self.lock.write()?;
+        for format in ContentFormat::all().iter() {
+            let path = self.address_to_path(address, *format);
+            if path.is_file() {
+                remove_file(path)?;
+            }
         }
+        Ok(())
     }
 
     fn get_id(&self) -> Uuid {
@@ -87,12 +156,15 @@ pub mod tests {
     extern crate tempfile;
 
     use self::tempfile::{tempdir, TempDir};
-    use crate::cas::file::FilesystemStorage;
+    use crate::cas::{file::FilesystemStorage, format::ContentFormat};
     use holochain_core_types::{
         cas::{
-            content::{ExampleAddressableContent, OtherExampleAddressableContent},
-            storage::StorageTestSuite,
+            content::{
+                AddressableContent, ExampleAddressableContent, OtherExampleAddressableContent,
+            },
+            storage::{ContentAddressableStorage, StorageTestSuite},
         },
+        entry::{test_entry, Entry},
         json::RawString,
     };
 
@@ -113,4 +185,73 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn file_addresses_test() {
+        let (mut cas, _dir) = test_file_cas();
+        assert_eq!(cas.addresses().unwrap(), Vec::new());
+
+        let foo =
+            ExampleAddressableContent::try_from_content(&RawString::from("foo").into()).unwrap();
+        let bar =
+            ExampleAddressableContent::try_from_content(&RawString::from("bar").into()).unwrap();
+        cas.add(&foo).unwrap();
+        cas.add(&bar).unwrap();
+
+        let mut addresses = cas.addresses().unwrap();
+        addresses.sort();
+        let mut expected = vec![foo.address(), bar.address()];
+        expected.sort();
+        assert_eq!(addresses, expected);
+    }
+
+    #[test]
+    fn message_pack_content_keeps_its_address() {
+        let (mut json_cas, _json_dir) = test_file_cas();
+        let (cas, msgpack_dir) = test_file_cas();
+        let mut msgpack_cas = cas.with_format(ContentFormat::MessagePack);
+        let entry = test_entry();
+        json_cas.add(&entry).unwrap();
+        msgpack_cas.add(&entry).unwrap();
+
+        assert!(msgpack_dir
+            .path()
+            .join(entry.address().to_string())
+            .with_extension("msgpack")
+            .is_file());
+        let fetched = Entry::try_from_content(
+            &msgpack_cas
+                .fetch(&entry.address())
+                .unwrap()
+                .expect("entry should be stored"),
+        )
+        .unwrap();
+        assert_eq!(fetched, entry);
+        assert_eq!(fetched.address(), entry.address());
+        assert_eq!(
+            msgpack_cas.addresses().unwrap(),
+            json_cas.addresses().unwrap()
+        );
+        assert_eq!(
+            Entry::try_from_content(&json_cas.fetch(&entry.address()).unwrap().unwrap())
+                .unwrap()
+                .address(),
+            fetched.address()
+        );
+    }
+
+    #[test]
+    fn content_stays_readable_after_format_change() {
+        let (mut json_cas, dir) = test_file_cas();
+        let entry = test_entry();
+        json_cas.add(&entry).unwrap();
+
+        let msgpack_cas = FilesystemStorage::new(dir.path())
+            .unwrap()
+            .with_format(ContentFormat::MessagePack);
+        assert!(msgpack_cas.contains(&entry.address()).unwrap());
+        assert_eq!(
+            msgpack_cas.fetch(&entry.address()).unwrap(),
+            Some(entry.content())
+        );
+    }
 }
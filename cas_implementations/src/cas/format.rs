@@ -0,0 +1,97 @@
+//! Formats a FilesystemStorage can write content to disk in.
+//! Addresses are always defined over the JSON content, so the format only changes
+//! the bytes on disk, never what callers store and fetch.
+
+use holochain_core_types::{cas::content::Content, error::HolochainError};
+use rmp_serde;
+use serde_json::{self, Value};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentFormat {
+    /// The JSON content as is
+    Json,
+    /// MessagePack encoding of the JSON content, more compact for small entries
+    MessagePack,
+}
+
+impl Default for ContentFormat {
+    fn default() -> Self {
+        ContentFormat::Json
+    }
+}
+
+impl ContentFormat {
+    /// All formats, so content written in another format than the current one can still be read.
+    pub fn all() -> [ContentFormat; 2] {
+        [ContentFormat::Json, ContentFormat::MessagePack]
+    }
+
+    /// File extension of content stored in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ContentFormat::Json => "txt",
+            ContentFormat::MessagePack => "msgpack",
+        }
+    }
+
+    /// Encodes the given content.
+    /// Returns None if decoding would not give back the exact same content, e.g. because
+    /// of whitespace in the JSON. Such content has to be stored as JSON.
+    pub fn encode(&self, content: &Content) -> Result<Option<Vec<u8>>, HolochainError> {
+        match self {
+            ContentFormat::Json => Ok(Some(String::from(content.clone()).into_bytes())),
+            ContentFormat::MessagePack => {
+                let value: Value = match serde_json::from_str(&String::from(content.clone())) {
+                    Ok(value) => value,
+                    Err(_) => return Ok(None),
+                };
+                let bytes = rmp_serde::to_vec(&value)
+                    .map_err(|e| HolochainError::SerializationError(e.to_string()))?;
+                if &self.decode(&bytes)? == content {
+                    Ok(Some(bytes))
+                } else {
+                    Ok(None)
+                }
+            }
+        }
+    }
+
+    /// Decodes content that got encoded in this format.
+    pub fn decode(&self, bytes: &[u8]) -> Result<Content, HolochainError> {
+        match self {
+            ContentFormat::Json => String::from_utf8(bytes.to_vec())
+                .map(Content::from)
+                .map_err(|e| HolochainError::SerializationError(e.to_string())),
+            ContentFormat::MessagePack => {
+                let value: Value = rmp_serde::from_slice(bytes)
+                    .map_err(|e| HolochainError::SerializationError(e.to_string()))?;
+                serde_json::to_string(&value)
+                    .map(Content::from)
+                    .map_err(|e| HolochainError::SerializationError(e.to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core_types::{cas::content::AddressableContent, entry::test_entry};
+
+    #[test]
+    fn message_pack_round_trips_content() {
+        let content = test_entry().content();
+        let bytes = ContentFormat::MessagePack
+            .encode(&content)
+            .unwrap()
+            .expect("entry content should be encodable");
+        assert_eq!(ContentFormat::MessagePack.decode(&bytes).unwrap(), content);
+    }
+
+    #[test]
+    fn message_pack_refuses_content_it_cannot_reproduce() {
+        let content = Content::from(String::from(r#"{"a":  1}"#));
+        assert_eq!(ContentFormat::MessagePack.encode(&content).unwrap(), None);
+    }
+}
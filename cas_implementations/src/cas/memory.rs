@@ -49,6 +49,25 @@ impl ContentAddressableStorage for MemoryStorage {
         Ok(map.get(address).cloned())
     }
 
+    fn fetch_many(&self, addresses: &[Address]) -> Result<Vec<Option<Content>>, HolochainError> {
+        let map = self.storage.read()?;
+        Ok(addresses
+            .iter()
+            .map(|address| map.get(address).cloned())
+            .collect())
+    }
+
+    fn addresses(&self) -> Result<Vec<Address>, HolochainError> {
+        let map = self.storage.read()?;
+        Ok(map.keys().cloned().collect())
+    }
+
+    fn remove(&mut self, address: &Address) -> Result<(), HolochainError> {
+        let mut map = self.storage.write()?;
+        map.remove(address);
+        Ok(())
+    }
+
     fn get_id(&self) -> Uuid {
         self.id
     }
@@ -9,8 +9,14 @@
 extern crate holochain_core_types;
 
 extern crate glob;
+extern crate rmp_serde;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
 extern crate uuid;
 
+pub mod backend;
 pub mod cas;
 pub mod eav;
 pub mod path;
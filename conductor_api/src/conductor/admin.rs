@@ -6,10 +6,24 @@ use crate::{
     },
     error::HolochainInstanceError,
 };
-use holochain_core_types::{cas::content::AddressableContent, error::HolochainError};
-use json_patch;
+use holochain_cas_implementations::cas::format::ContentFormat;
+use holochain_core_types::{
+    cas::content::{Address, AddressableContent},
+    entry::cap_entries::CapTokenGrant,
+    error::HolochainError,
+};
 use std::{fs, path::PathBuf, sync::Arc};
 
+/// Gets called by long running admin operations after each step with
+/// (step, total, message), step counting from 1 up to total, so callers can show progress.
+pub type ProgressCallback = Arc<Fn(usize, usize, &str) + Send + Sync>;
+
+fn report_progress(progress: &Option<ProgressCallback>, step: usize, total: usize, message: &str) {
+    if let Some(callback) = progress {
+        callback(step, total, message);
+    }
+}
+
 pub trait ConductorAdmin {
     fn install_dna_from_file(
         &mut self,
@@ -18,6 +32,14 @@ pub trait ConductorAdmin {
         copy: bool,
         properties: Option<&serde_json::Value>,
     ) -> Result<(), HolochainError>;
+    fn install_dna_from_file_with_progress(
+        &mut self,
+        path: PathBuf,
+        id: String,
+        copy: bool,
+        properties: Option<&serde_json::Value>,
+        progress: Option<ProgressCallback>,
+    ) -> Result<(), HolochainError>;
     fn uninstall_dna(&mut self, id: &String) -> Result<(), HolochainError>;
     fn add_instance(
         &mut self,
@@ -49,6 +71,16 @@ pub trait ConductorAdmin {
         caller_id: &String,
         callee_id: &String,
     ) -> Result<(), HolochainError>;
+    fn mint_capability(
+        &mut self,
+        instance_id: &String,
+        grant: CapTokenGrant,
+    ) -> Result<Address, HolochainInstanceError>;
+    fn revoke_capability(
+        &mut self,
+        instance_id: &String,
+        token: &Address,
+    ) -> Result<(), HolochainInstanceError>;
 }
 
 impl ConductorAdmin for Conductor {
@@ -67,6 +99,25 @@ impl ConductorAdmin for Conductor {
         copy: bool,
         properties: Option<&serde_json::Value>,
     ) -> Result<(), HolochainError> {
+        self.install_dna_from_file_with_progress(path, id, copy, properties, None)
+    }
+
+    /// Same as install_dna_from_file() but reports its progress to the given callback.
+    fn install_dna_from_file_with_progress(
+        &mut self,
+        path: PathBuf,
+        id: String,
+        copy: bool,
+        properties: Option<&serde_json::Value>,
+        progress: Option<ProgressCallback>,
+    ) -> Result<(), HolochainError> {
+        // applying properties only counts as a step if there are any
+        let steps = if properties.is_some() { 4 } else { 3 };
+        let mut step = 0;
+        let mut report = |message: &str| {
+            step += 1;
+            report_progress(&progress, step, steps, message);
+        };
         let path_string = path
             .to_str()
             .ok_or(HolochainError::ConfigError("invalid path".into()))?;
@@ -78,6 +129,7 @@ impl ConductorAdmin for Conductor {
                     e.to_string()
                 ))
             })?;
+        report("Loaded DNA file");
 
         if let Some(props) = properties {
             if !copy {
@@ -85,13 +137,15 @@ impl ConductorAdmin for Conductor {
                     "Cannot install DNA with properties unless copy flag is true".into(),
                 ));
             }
-            json_patch::merge(&mut dna.properties, &props);
+            dna = dna.with_property_overrides(props.clone());
+            report("Applied properties");
         }
 
         let config_path = match copy {
             true => self.save_dna(&dna)?,
             false => PathBuf::from(path_string),
         };
+        report("Stored DNA file");
         let config_path_str = config_path
             .to_str()
             .ok_or(HolochainError::ConfigError("invalid path".into()))?;
@@ -107,6 +161,7 @@ impl ConductorAdmin for Conductor {
         new_config.check_consistency()?;
         self.config = new_config;
         self.save_config()?;
+        report("Saved conductor config");
         notify(format!("Installed DNA from {} as \"{}\"", path_string, id));
         Ok(())
     }
@@ -175,7 +230,21 @@ impl ConductorAdmin for Conductor {
                         format!("invalid path {:?}", storage_path).into(),
                     ))?
                     .into(),
+                format: ContentFormat::Json,
             },
+            max_concurrent_validations: None,
+            dht_cache_ttl_secs: None,
+            network_fetch_retries: None,
+            audit_log: None,
+            publish_batch_window_ms: None,
+            zome_call_cache: None,
+            wasm_module_cache_size: None,
+            max_headers_per_entry: None,
+            reject_dna_without_zomes: false,
+            validation_cache: None,
+            link_index_size: None,
+            profile_zome_calls: false,
+            validate_chain_on_load: false,
         };
         new_config.instances.push(new_instance);
         new_config.check_consistency()?;
@@ -237,6 +306,7 @@ impl ConductorAdmin for Conductor {
         self.config = new_config;
         self.save_config()?;
         self.start_interface_by_id(&interface.id)?;
+        self.update_ui_dna_interfaces();
         Ok(())
     }
 
@@ -265,6 +335,7 @@ impl ConductorAdmin for Conductor {
         self.save_config()?;
 
         let _ = self.stop_interface_by_id(id);
+        self.update_ui_dna_interfaces();
 
         notify(format!("Removed interface \"{}\".", id));
         Ok(())
@@ -312,6 +383,7 @@ impl ConductorAdmin for Conductor {
 
         let _ = self.stop_interface_by_id(interface_id);
         self.start_interface_by_id(interface_id)?;
+        self.update_ui_dna_interfaces();
 
         Ok(())
     }
@@ -360,6 +432,7 @@ impl ConductorAdmin for Conductor {
 
         let _ = self.stop_interface_by_id(interface_id);
         self.start_interface_by_id(interface_id)?;
+        self.update_ui_dna_interfaces();
 
         Ok(())
     }
@@ -488,6 +561,38 @@ impl ConductorAdmin for Conductor {
 
         Ok(())
     }
+
+    /// Issues a capability token for the zome functions of the given instance by
+    /// committing the grant to the instance's source chain. Returns the token.
+    fn mint_capability(
+        &mut self,
+        instance_id: &String,
+        grant: CapTokenGrant,
+    ) -> Result<Address, HolochainInstanceError> {
+        let instance = self.instances.get(instance_id)?;
+        let token = instance.read().unwrap().mint_capability(grant)?;
+        notify(format!(
+            "Minted capability {} for instance '{}'",
+            token, instance_id
+        ));
+        Ok(token)
+    }
+
+    /// Revokes a capability token previously minted for the given instance.
+    /// Zome calls presenting that token get rejected afterwards.
+    fn revoke_capability(
+        &mut self,
+        instance_id: &String,
+        token: &Address,
+    ) -> Result<(), HolochainInstanceError> {
+        let instance = self.instances.get(instance_id)?;
+        instance.read().unwrap().revoke_capability(token)?;
+        notify(format!(
+            "Revoked capability {} of instance '{}'",
+            token, instance_id
+        ));
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -502,7 +607,7 @@ pub mod tests {
     };
     use holochain_common::paths::DNA_EXTENSION;
     use holochain_core_types::{agent::AgentId, dna::Dna, json::JsonString};
-    use std::{convert::TryFrom, env::current_dir, fs::File, io::Read};
+    use std::{convert::TryFrom, env::current_dir, fs::File, io::Read, sync::Mutex};
 
     pub fn test_dna_loader() -> DnaLoader {
         let loader = Box::new(|_: &PathBuf| {
@@ -728,6 +833,63 @@ id = 'new-dna'"#,
         assert_eq!(config_contents, toml,);
     }
 
+    #[test]
+    fn test_install_dna_from_file_with_progress() {
+        let test_name = "test_install_dna_from_file_with_progress";
+        let mut conductor = create_test_conductor(test_name, 3012);
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded_events = events.clone();
+        let progress: ProgressCallback = Arc::new(move |step, total, message| {
+            recorded_events
+                .lock()
+                .unwrap()
+                .push((step, total, message.to_string()))
+        });
+
+        assert_eq!(
+            conductor.install_dna_from_file_with_progress(
+                PathBuf::from("new-dna.dna.json"),
+                String::from("new-dna"),
+                false,
+                None,
+                Some(progress.clone()),
+            ),
+            Ok(()),
+        );
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                (1, 3, String::from("Loaded DNA file")),
+                (2, 3, String::from("Stored DNA file")),
+                (3, 3, String::from("Saved conductor config")),
+            ]
+        );
+
+        events.lock().unwrap().clear();
+        assert_eq!(
+            conductor.install_dna_from_file_with_progress(
+                PathBuf::from("new-dna.dna.json"),
+                String::from("new-dna-with-props"),
+                true,
+                Some(&json!({"propertyKey": "value"})),
+                Some(progress),
+            ),
+            Ok(()),
+        );
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                (1, 4, String::from("Loaded DNA file")),
+                (2, 4, String::from("Applied properties")),
+                (3, 4, String::from("Stored DNA file")),
+                (4, 4, String::from("Saved conductor config")),
+            ]
+        );
+    }
+
     #[test]
     fn test_install_dna_from_file_and_copy() {
         let test_name = "test_install_dna_from_file_and_copy";
@@ -1047,6 +1209,11 @@ type = 'websocket'"#,
             driver: InterfaceDriver::Http { port: 8080 },
             admin: false,
             instances: Vec::new(),
+            max_zome_call_threads: None,
+            max_queued_zome_calls: None,
+            allowed_functions: Vec::new(),
+            shutdown_grace_period_ms: None,
+            request_log: None,
         };
 
         assert_eq!(conductor.add_interface(interface_config), Ok(()),);
@@ -1286,6 +1453,7 @@ type = 'websocket'"#,
             public_address: AgentId::generate_fake("new").address().to_string(),
             key_file: String::from("new-test-path"),
             holo_remote_key: None,
+            local_signing_fallback: None,
         };
 
         assert_eq!(conductor.add_agent(agent_config), Ok(()),);
@@ -1,21 +1,36 @@
 use crate::{
     config::{
         serialize_configuration, Configuration, InterfaceConfiguration, InterfaceDriver,
-        StorageConfiguration,
+        DEFAULT_MAX_QUEUED_ZOME_CALLS,
     },
     context_builder::ContextBuilder,
     error::HolochainInstanceError,
     logger::DebugLogger,
     Holochain,
 };
+use holochain_cas_implementations::backend::StorageBackendRegistry;
 use holochain_common::paths::DNA_EXTENSION;
 use holochain_core::{
-    logger::{ChannelLogger, Logger},
+    context::Context,
+    logger::{ChannelLogger, Logger, SimpleLogger},
+    network::direct_message::DirectMessageOverflow,
+    nucleus::audit::{FileAuditSink, ZomeCallAuditLog},
     signal::Signal,
 };
 use holochain_core_types::{
-    agent::AgentId, cas::content::AddressableContent, dna::Dna, error::HolochainError,
-    json::JsonString,
+    agent::AgentId,
+    backoff::DEFAULT_MAX_DELAY,
+    cas::{
+        content::{Address, AddressableContent},
+        storage::ContentAddressableStorage,
+    },
+    dna::Dna,
+    eav::{
+        Attribute, EaviQuery, EntityAttributeValueIndex, EntityAttributeValueStorage, IndexFilter,
+    },
+    entry::{entry_type::AppEntryType, Entry},
+    error::HolochainError,
+    json::{JsonString, RawString},
 };
 use holochain_dpki::{key_blob::KeyBlob, key_bundle::KeyBundle};
 use holochain_sodium::secbuf::SecBuf;
@@ -27,21 +42,27 @@ use std::{
     convert::TryFrom,
     fs::{self, File},
     io::prelude::*,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{
-        mpsc::{channel, Sender, SyncSender},
+        mpsc::{channel, SendError, Sender, SyncSender},
         Arc, Mutex, RwLock,
     },
     thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use holochain_net::{
     ipc::spawn::{ipc_spawn, SpawnResult},
     p2p_config::P2pConfig,
 };
-use interface::{ConductorApiBuilder, InstanceMap, Interface};
+use interface::{ConductorApiBuilder, InstanceMap, Interface, ZomeCallTracker};
+use interface_impls::bind_retry::{BindRetry, DEFAULT_BIND_BACKOFF};
+use request_log::{FileRequestLogSink, RequestLog};
 use static_file_server::StaticServer;
 
+/// How often await_instance_ready() checks the state of the instance.
+const INSTANCE_READY_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 lazy_static! {
     /// This is a global and mutable Conductor singleton.
     /// (Ok, not really. I've made Conductor::from_config public again so holochain_nodejs
@@ -80,10 +101,13 @@ pub struct Conductor {
     agent_keys: HashMap<String, Arc<Mutex<KeyBundle>>>,
     pub(in crate::conductor) config: Configuration,
     pub(in crate::conductor) static_servers: HashMap<String, StaticServer>,
-    pub(in crate::conductor) interface_threads: HashMap<String, Sender<()>>,
+    pub(in crate::conductor) interface_threads: HashMap<String, InterfaceThread>,
     pub key_loader: KeyLoader,
     pub(in crate::conductor) dna_loader: DnaLoader,
     pub(in crate::conductor) ui_dir_copier: UiDirCopier,
+    /// Storage backends that instance storage configurations can select by name.
+    /// Register additional backends here before calling load_config().
+    pub storage_backends: StorageBackendRegistry,
     signal_tx: Option<SignalSender>,
     logger: DebugLogger,
     p2p_config: Option<P2pConfig>,
@@ -100,6 +124,38 @@ impl Drop for Conductor {
     }
 }
 
+/// Kill switch and thread handle of a running interface.
+pub(in crate::conductor) struct InterfaceThread {
+    kill_switch: Sender<()>,
+    handle: thread::JoinHandle<Result<(), String>>,
+    zome_calls: ZomeCallTracker,
+    shutdown_grace_period: Option<Duration>,
+}
+
+impl InterfaceThread {
+    /// Gives running zome calls the configured grace period to finish, then fires
+    /// the kill switch.
+    fn stop(&self, id: &str) -> Result<(), SendError<()>> {
+        notify(format!("Stopping interface {}", id));
+        Self::wait_for_zome_calls(id, &self.zome_calls, self.shutdown_grace_period);
+        self.kill_switch.send(())
+    }
+
+    /// Rejects further zome calls and waits for the running ones to finish within the
+    /// given grace period. Without a grace period, it does not wait at all.
+    fn wait_for_zome_calls(id: &str, zome_calls: &ZomeCallTracker, grace_period: Option<Duration>) {
+        if let Some(grace_period) = grace_period {
+            if !zome_calls.close_and_wait(grace_period) {
+                notify(format!(
+                    "Interface {} stopped with {} zome calls still running",
+                    id,
+                    zome_calls.running()
+                ));
+            }
+        }
+    }
+}
+
 type SignalSender = SyncSender<Signal>;
 pub type KeyLoader = Arc<Box<FnMut(&PathBuf) -> Result<KeyBundle, HolochainError> + Send + Sync>>;
 pub type DnaLoader = Arc<Box<FnMut(&PathBuf) -> Result<Dna, HolochainError> + Send + Sync>>;
@@ -111,6 +167,127 @@ pub fn notify(msg: String) {
     println!("{}", msg);
 }
 
+/// Checks if all entries in the source chain of the given instance still validate
+/// against its DNA, which might have changed since they got committed.
+/// Logs a warning for every entry that does not.
+fn warn_about_invalid_chain_entries(instance_id: &String, instance: &Holochain) {
+    let context = instance.context();
+    match instance.validate_chain_against_dna() {
+        Ok(invalid_entries) => {
+            for (address, error) in invalid_entries {
+                context.log(format!(
+                    "warn/conductor: Entry {} in the source chain of instance \"{}\" does not validate against the DNA: {}",
+                    address, instance_id, error
+                ));
+            }
+        }
+        Err(error) => context.log(format!(
+            "warn/conductor: Could not validate source chain of instance \"{}\": {}",
+            instance_id, error
+        )),
+    }
+}
+
+/// Compiles the wasm of all zomes of the given instance into its wasm module cache
+/// so that first calls don't have to wait for it.
+/// Logs a warning for every zome whose wasm does not compile.
+fn prewarm_wasm_modules(instance_id: &String, instance: &Holochain) -> Result<(), HolochainError> {
+    let context = instance.context();
+    let cache = context.wasm_module_cache.as_ref().ok_or_else(|| {
+        HolochainError::ErrorGeneric(format!(
+            "Instance \"{}\" has no wasm module cache configured",
+            instance_id
+        ))
+    })?;
+    let dna = context
+        .state()
+        .and_then(|state| state.nucleus().dna())
+        .ok_or_else(|| {
+            HolochainError::ErrorGeneric(format!("Instance \"{}\" has no DNA", instance_id))
+        })?;
+    for (zome_name, zome) in dna.zomes.iter() {
+        if let Err(error) = cache.get_or_compile(&zome.code.code) {
+            context.log(format!(
+                "warn/conductor: Could not compile wasm of zome \"{}\" of instance \"{}\": {}",
+                zome_name, instance_id, error
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn self_test_error(step: &str, error: HolochainError) -> HolochainError {
+    HolochainError::ErrorGeneric(format!("Storage self test failed to {}: {}", step, error))
+}
+
+/// Adds the given entry to the given CAS, reads it back and removes it again.
+fn self_test_cas(
+    name: &str,
+    cas: &Arc<RwLock<ContentAddressableStorage>>,
+    entry: &Entry,
+) -> Result<(), HolochainError> {
+    let mut cas = cas.write()?;
+    cas.add(entry)
+        .map_err(|e| self_test_error(&format!("write to {}", name), e))?;
+    let content = cas
+        .fetch(&entry.address())
+        .map_err(|e| self_test_error(&format!("read from {}", name), e))?;
+    if content != Some(entry.content()) {
+        return Err(self_test_error(
+            &format!("read from {}", name),
+            HolochainError::ErrorGeneric(String::from("content read back differs from written")),
+        ));
+    }
+    cas.remove(&entry.address())
+        .map_err(|e| self_test_error(&format!("clean up {}", name), e))
+}
+
+/// Checks that the storages of the given context work by writing to and reading from
+/// each of them. Every self test writes an EAV record indexed by the time of the test
+/// and queries it back. EAV storage is append only, so these small records stay behind,
+/// one per self test.
+fn self_test_storage(context: &Context) -> Result<(), HolochainError> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+    let entry = Entry::App(
+        AppEntryType::from("%self_test"),
+        RawString::from(format!("self test {}", nanos)).into(),
+    );
+    self_test_cas("chain storage", &context.chain_storage, &entry)?;
+    self_test_cas("DHT storage", &context.dht_storage, &entry)?;
+
+    let address = Address::from("%self_test");
+    let eavi = EntityAttributeValueIndex::new_with_index(
+        &address,
+        &Attribute::SelfTest,
+        &address,
+        nanos as i64,
+    )?;
+    let mut eav = context.eav_storage.write()?;
+    // the storage moves the index on if another record has it already
+    let written = eav
+        .add_eavi(&eavi)
+        .map_err(|e| self_test_error("write to EAV storage", e))?
+        .unwrap_or(eavi);
+    let found = eav
+        .fetch_eavi(&EaviQuery::new(
+            Some(address.clone()).into(),
+            Some(Attribute::SelfTest).into(),
+            Some(address.clone()).into(),
+            IndexFilter::Range(Some(written.index()), Some(written.index())),
+        ))
+        .map_err(|e| self_test_error("query EAV storage", e))?;
+    if !found.contains(&written) {
+        return Err(self_test_error(
+            "query EAV storage",
+            HolochainError::ErrorGeneric(String::from("written record not found")),
+        ));
+    }
+    Ok(())
+}
+
 impl Conductor {
     pub fn from_config(config: Configuration) -> Self {
         let rules = config.logger.rules.clone();
@@ -124,6 +301,7 @@ impl Conductor {
             key_loader: Arc::new(Box::new(Self::load_key)),
             dna_loader: Arc::new(Box::new(Self::load_dna)),
             ui_dir_copier: Arc::new(Box::new(Self::copy_ui_dir)),
+            storage_backends: StorageBackendRegistry::new(),
             signal_tx: None,
             logger: DebugLogger::new(rules),
             p2p_config: None,
@@ -159,10 +337,27 @@ impl Conductor {
             .collect()
     }
 
+    /// Stops all interfaces. Their running zome calls get their grace periods at the same
+    /// time, so stopping takes as long as the longest grace period, not their sum.
     pub fn stop_all_interfaces(&mut self) {
-        for (id, kill_switch) in self.interface_threads.iter() {
-            notify(format!("Stopping interface {}", id));
-            let _ = kill_switch.send(()).map_err(|err| {
+        let waits: Vec<_> = self
+            .interface_threads
+            .iter()
+            .map(|(id, interface_thread)| {
+                notify(format!("Stopping interface {}", id));
+                let id = id.clone();
+                let zome_calls = interface_thread.zome_calls.clone();
+                let grace_period = interface_thread.shutdown_grace_period;
+                thread::spawn(move || {
+                    InterfaceThread::wait_for_zome_calls(&id, &zome_calls, grace_period)
+                })
+            })
+            .collect();
+        for wait in waits {
+            let _ = wait.join();
+        }
+        for interface_thread in self.interface_threads.values() {
+            let _ = interface_thread.kill_switch.send(()).map_err(|err| {
                 let message = format!("Error stopping interface: {}", err);
                 notify(message.clone());
                 err
@@ -172,15 +367,14 @@ impl Conductor {
 
     pub fn stop_interface_by_id(&mut self, id: &String) -> Result<(), HolochainError> {
         {
-            let kill_switch =
+            let interface_thread =
                 self.interface_threads
                     .get(id)
                     .ok_or(HolochainError::ErrorGeneric(format!(
                         "Interface {} not found.",
                         id
                     )))?;
-            notify(format!("Stopping interface {}", id));
-            kill_switch.send(()).map_err(|err| {
+            interface_thread.stop(id).map_err(|err| {
                 let message = format!("Error stopping interface: {}", err);
                 notify(message.clone());
                 HolochainError::ErrorGeneric(message)
@@ -197,6 +391,21 @@ impl Conductor {
             .and_then(|config| self.start_interface(&config))
     }
 
+    /// Hands the current config of their DNA interface to the UI servers, so that UIs see
+    /// changes made through the admin API, e.g. added instances, without a restart.
+    pub(in crate::conductor) fn update_ui_dna_interfaces(&self) {
+        for ui_interface in self.config.ui_interfaces.iter() {
+            if let Some(server) = self.static_servers.get(&ui_interface.id) {
+                server.update_dna_interface(
+                    ui_interface
+                        .dna_interface
+                        .as_ref()
+                        .and_then(|interface_id| self.config.interface_by_id(interface_id)),
+                );
+            }
+        }
+    }
+
     pub fn start_all_static_servers(&mut self) -> Result<(), String> {
         notify("Starting all servers".into());
         self.static_servers.iter_mut().for_each(|(id, server)| {
@@ -236,12 +445,98 @@ impl Conductor {
         &self.instances
     }
 
-    /// Stop and clear all instances
+    /// Compiles the wasm of all zomes of the given instance ahead of its first calls.
+    /// This already happens when the config gets loaded, so it is only needed
+    /// if the cache might have dropped modules since.
+    /// Fails if the instance does not exist or has no wasm module cache configured.
+    pub fn prewarm(&self, instance_id: &String) -> Result<(), HolochainError> {
+        let instance = self.instances.get(instance_id).ok_or_else(|| {
+            HolochainError::ErrorGeneric(format!("Instance \"{}\" not found", instance_id))
+        })?;
+        let instance = instance.read().unwrap();
+        prewarm_wasm_modules(instance_id, &instance)
+    }
+
+    /// Checks that the storage of the given instance actually works, beyond the instance
+    /// being up: writes a temporary entry to its chain and DHT CAS, reads it back and
+    /// removes it, then writes and queries an EAV record.
+    /// Fails with the step that went wrong, e.g. if the disk is full.
+    pub fn self_test(&self, instance_id: &String) -> Result<(), HolochainError> {
+        let instance = self.instances.get(instance_id).ok_or_else(|| {
+            HolochainError::ErrorGeneric(format!("Instance \"{}\" not found", instance_id))
+        })?;
+        let context = instance.read().unwrap().context().clone();
+        self_test_storage(&context)
+    }
+
+    /// Blocks until the given instance has its DNA loaded and its network initialized,
+    /// so that callers don't have to guess how long to sleep after starting the conductor.
+    /// Fails with HolochainError::Timeout if the instance is not ready within `timeout`
+    /// and right away if the instance does not exist.
+    pub fn await_instance_ready(
+        &self,
+        instance_id: &String,
+        timeout: Duration,
+    ) -> Result<(), HolochainError> {
+        let instance = self.instances.get(instance_id).ok_or_else(|| {
+            HolochainError::ErrorGeneric(format!("Instance \"{}\" not found", instance_id))
+        })?;
+        let started = Instant::now();
+        loop {
+            let state = instance
+                .read()
+                .unwrap()
+                .state()
+                .map_err(|error| HolochainError::ErrorGeneric(error.to_string()))?;
+            if state.nucleus().dna().is_some() && state.network().initialized().is_ok() {
+                return Ok(());
+            }
+            if started.elapsed() >= timeout {
+                return Err(HolochainError::Timeout);
+            }
+            thread::sleep(INSTANCE_READY_POLL_INTERVAL);
+        }
+    }
+
+    /// Shuts down everything this conductor runs and only returns once all of it is down:
+    /// interfaces first so that no new calls come in, then UI servers, then instances which
+    /// leave their networks, and finally the networking process if this conductor spawned it.
+    /// Waits for the interface threads, so it must not be called from within an interface.
     pub fn shutdown(&mut self) {
-        let _ = self
-            .stop_all_instances()
-            .map_err(|error| notify(format!("Error during shutdown: {}", error)));
+        self.stop_and_clear_all();
+        if let Some(mut network_spawn) = self.network_spawn.take() {
+            notify("Stopping networking process".into());
+            if let Some(kill) = network_spawn.kill.take() {
+                kill();
+            }
+            // the next load_config() has to spawn a new one
+            self.p2p_config = None;
+        }
+    }
+
+    /// Stops all interfaces, UI servers and instances and clears the instances.
+    fn stop_and_clear_all(&mut self) {
         self.stop_all_interfaces();
+        for (id, interface_thread) in self.interface_threads.drain() {
+            if interface_thread.handle.join().is_err() {
+                notify(format!("Interface {} panicked", id));
+            }
+        }
+        for (id, server) in self.static_servers.iter_mut() {
+            if server.is_running() {
+                let _ = server.stop().map_err(|error| {
+                    notify(format!("Error stopping UI interface {}: {}", id, error))
+                });
+            }
+        }
+        for (id, instance) in self.instances.iter() {
+            notify(format!("Shutting down instance \"{}\"...", id));
+            let _ = instance
+                .write()
+                .unwrap()
+                .shutdown()
+                .map_err(|error| notify(format!("Error during shutdown: {}", error)));
+        }
         self.instances = HashMap::new();
     }
 
@@ -313,11 +608,17 @@ impl Conductor {
                 }
             }
         };
-        P2pConfig::new_ipc_uri(
+        let mut p2p_config = P2pConfig::new_ipc_uri(
             uri,
             &net_config.bootstrap_nodes,
             net_config.networking_config_file,
-        )
+        );
+        p2p_config.max_peers = net_config.max_peers;
+        p2p_config.publish_interval_ms = net_config.publish_interval_ms;
+        if let Some(max_delay_ms) = self.config.retry_max_delay_ms {
+            p2p_config.backend_config["reconnectMaxDelayMs"] = json!(max_delay_ms);
+        }
+        p2p_config
     }
 
     /// Tries to create all instances configured in the given Configuration object.
@@ -339,7 +640,7 @@ impl Conductor {
         }
 
         let config = self.config.clone();
-        self.shutdown();
+        self.stop_and_clear_all();
 
         for id in config.instance_ids_sorted_by_bridge_dependencies()? {
             let instance = self
@@ -351,10 +652,23 @@ impl Conductor {
                     )
                 })?;
 
+            let validate_chain_on_load = config
+                .instance_by_id(&id)
+                .map(|instance_config| instance_config.validate_chain_on_load)
+                .unwrap_or(false);
+            if validate_chain_on_load {
+                warn_about_invalid_chain_entries(&id, &instance);
+            }
+            if instance.context().wasm_module_cache.is_some() {
+                if let Err(error) = prewarm_wasm_modules(&id, &instance) {
+                    instance.context().log(format!("warn/conductor: {}", error));
+                }
+            }
             self.instances
                 .insert(id.clone(), Arc::new(RwLock::new(instance)));
         }
 
+        let bind_retry = self.bind_retry();
         for ui_interface_config in config.ui_interfaces.clone() {
             notify(format!("adding ui interface {}", &ui_interface_config.id));
             let bundle_config =
@@ -375,7 +689,9 @@ impl Conductor {
                     ui_interface_config,
                     bundle_config,
                     connected_dna_interface,
-                ),
+                )
+                .with_dna_interfaces(config.interfaces.clone())
+                .with_bind_retry(bind_retry),
             );
         }
 
@@ -426,11 +742,111 @@ impl Conductor {
                 };
 
                 // Storage:
-                if let StorageConfiguration::File { path } = instance_config.storage {
-                    context_builder = context_builder.with_file_storage(path).map_err(|hc_err| {
-                        format!("Error creating context: {}", hc_err.to_string())
-                    })?
-                };
+                let storage_backend = self
+                    .storage_backends
+                    .create(
+                        instance_config.storage.backend_name(),
+                        instance_config.storage.path().map(Path::new),
+                    )
+                    .map_err(|hc_err| format!("Error creating context: {}", hc_err.to_string()))?;
+                context_builder = context_builder.with_storage_backend(storage_backend);
+
+                if let Some(max) = instance_config.max_concurrent_validations {
+                    context_builder = context_builder.with_max_concurrent_validations(max);
+                }
+
+                if let Some(ttl) = instance_config.dht_cache_ttl_secs {
+                    context_builder = context_builder.with_dht_cache_ttl(Duration::from_secs(ttl));
+                }
+
+                if let Some(retries) = instance_config.network_fetch_retries {
+                    context_builder = context_builder.with_network_fetch_retries(retries);
+                }
+
+                if let Some(ref audit_config) = instance_config.audit_log {
+                    let mut audit_log =
+                        ZomeCallAuditLog::new(Arc::new(FileAuditSink::new(&audit_config.path)));
+                    if audit_config.raw_arguments {
+                        audit_log = audit_log.with_raw_arguments();
+                    }
+                    context_builder = context_builder.with_zome_call_audit_log(audit_log);
+                }
+
+                if let Some(window) = instance_config.publish_batch_window_ms {
+                    context_builder =
+                        context_builder.with_publish_batch_window(Duration::from_millis(window));
+                }
+
+                if let Some(ref cache_config) = instance_config.zome_call_cache {
+                    context_builder = context_builder.with_zome_call_cache(
+                        cache_config.max_entries,
+                        Duration::from_secs(cache_config.ttl_secs),
+                    );
+                }
+
+                if let Some(ref cache_config) = instance_config.validation_cache {
+                    context_builder = context_builder.with_validation_cache(
+                        cache_config.max_entries,
+                        Duration::from_secs(cache_config.ttl_secs),
+                        Duration::from_secs(cache_config.failure_ttl_secs),
+                    );
+                }
+
+                if instance_config.profile_zome_calls {
+                    context_builder = context_builder.with_zome_call_profiler();
+                }
+
+                if let Some(max_bases) = instance_config.link_index_size {
+                    context_builder = context_builder.with_link_index(max_bases);
+                }
+
+                if let Some(max_modules) = instance_config.wasm_module_cache_size {
+                    context_builder = context_builder.with_wasm_module_cache(max_modules);
+                }
+
+                if let Some(max_headers) = instance_config.max_headers_per_entry {
+                    context_builder = context_builder.with_max_headers_per_entry(max_headers);
+                }
+
+                if let Some(max_size) = config
+                    .network
+                    .as_ref()
+                    .and_then(|network| network.max_message_size)
+                {
+                    context_builder = context_builder.with_network_max_message_size(max_size);
+                }
+
+                if let Some(window) = config
+                    .network
+                    .as_ref()
+                    .and_then(|network| network.direct_message_replay_window_secs)
+                {
+                    context_builder = context_builder
+                        .with_direct_message_replay_window(Duration::from_secs(window));
+                }
+
+                if let Some(network) = config.network.as_ref() {
+                    if let Some(max_in_flight) = network.max_in_flight_direct_messages {
+                        context_builder = context_builder.with_max_in_flight_direct_messages(
+                            max_in_flight,
+                            network
+                                .direct_message_overflow
+                                .unwrap_or(DirectMessageOverflow::Reject),
+                        );
+                    }
+                    if let Some(max_queued) = network.max_queued_direct_messages {
+                        context_builder =
+                            context_builder.with_max_queued_direct_messages(max_queued);
+                    }
+                }
+
+                if let Some(max_size) = config
+                    .network
+                    .as_ref()
+                    .and_then(|network| network.max_validation_package_size)
+                {
+                    context_builder = context_builder.with_max_validation_package_size(max_size);
+                }
 
                 if config.logger.logger_type == "debug" {
                     context_builder = context_builder.with_logger(Arc::new(Mutex::new(
@@ -445,6 +861,26 @@ impl Conductor {
                     // !!!!!!!!!!!!!!!!!!!!!!!
                     // Holo closed-alpha hack:
                     // !!!!!!!!!!!!!!!!!!!!!!!
+                    if Some(true) == agent_config.local_signing_fallback {
+                        let logger: Arc<Mutex<Logger>> = if config.logger.logger_type == "debug" {
+                            Arc::new(Mutex::new(ChannelLogger::new(
+                                instance_config.id.clone(),
+                                self.logger.get_sender(),
+                            )))
+                        } else {
+                            Arc::new(Mutex::new(SimpleLogger {}))
+                        };
+                        api_builder = api_builder
+                            .with_local_signing_fallback(
+                                self.get_keybundle_for_agent(&instance_config.agent)?,
+                                &agent_id,
+                                logger,
+                            )
+                            .map_err(|error| error.to_string())?;
+                    }
+                    if let Some(max_bytes) = self.config.signing_service_max_payload_bytes {
+                        api_builder = api_builder.with_max_signing_payload_bytes(max_bytes);
+                    }
                     api_builder = api_builder.with_outsource_signing_callback(
                         agent_id.clone(),
                         self.config
@@ -494,6 +930,12 @@ impl Conductor {
                         dna_config.file
                     ))
                 })?;
+                if let Err(error) = dna.check_has_zomes() {
+                    if instance_config.reject_dna_without_zomes {
+                        return Err(error.to_string());
+                    }
+                    notify(format!("warn/conductor: Instance \"{}\": {}", id, error));
+                }
 
                 Holochain::new(dna, Arc::new(context)).map_err(|hc_err| hc_err.to_string())
             })
@@ -617,7 +1059,11 @@ impl Conductor {
         Ok(())
     }
 
-    fn make_interface_handler(&self, interface_config: &InterfaceConfiguration) -> IoHandler {
+    fn make_interface_handler(
+        &self,
+        interface_config: &InterfaceConfiguration,
+        zome_call_tracker: ZomeCallTracker,
+    ) -> IoHandler {
         let instance_ids: Vec<String> = interface_config
             .instances
             .iter()
@@ -631,7 +1077,36 @@ impl Conductor {
             .map(|(id, val)| (id.clone(), val.clone()))
             .collect();
 
-        let mut conductor_api_builder = ConductorApiBuilder::new()
+        let mut conductor_api_builder = ConductorApiBuilder::new();
+        if let Some(max_threads) = interface_config.max_zome_call_threads {
+            conductor_api_builder = conductor_api_builder.with_zome_call_limit(
+                max_threads,
+                interface_config
+                    .max_queued_zome_calls
+                    .unwrap_or(DEFAULT_MAX_QUEUED_ZOME_CALLS),
+            );
+        }
+        if let Some(ref request_log_config) = interface_config.request_log {
+            let logger: Arc<Mutex<Logger>> = if self.config.logger.logger_type == "debug" {
+                Arc::new(Mutex::new(ChannelLogger::new(
+                    String::from("conductor"),
+                    self.logger.get_sender(),
+                )))
+            } else {
+                Arc::new(Mutex::new(SimpleLogger {}))
+            };
+            let request_log = RequestLog::new(
+                Arc::new(FileRequestLogSink::new(&request_log_config.path)),
+                interface_config.id.clone(),
+                interface_config.driver.transport().to_string(),
+            )
+            .with_logger(logger);
+            // has to come before with_instances() to get the methods named after instances
+            conductor_api_builder = conductor_api_builder.with_request_log(request_log);
+        }
+        conductor_api_builder = conductor_api_builder
+            .with_zome_call_tracker(zome_call_tracker)
+            .with_allowed_functions(interface_config.allowed_functions.clone())
             .with_instances(instance_subset)
             .with_instance_configs(self.config.instances.clone());
 
@@ -643,14 +1118,37 @@ impl Conductor {
         conductor_api_builder.spawn()
     }
 
-    fn spawn_interface_thread(&self, interface_config: InterfaceConfiguration) -> Sender<()> {
-        let dispatcher = self.make_interface_handler(&interface_config);
+    /// Retry behaviour for binding interface ports as configured with interface_bind_retries,
+    /// interface_bind_jitter and retry_max_delay_ms.
+    pub(in crate::conductor) fn bind_retry(&self) -> BindRetry {
+        let max_delay = self
+            .config
+            .retry_max_delay_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_MAX_DELAY);
+        self.config
+            .interface_bind_retries
+            .map(|retries| {
+                BindRetry::new(retries, DEFAULT_BIND_BACKOFF)
+                    .with_jitter(self.config.interface_bind_jitter)
+                    .with_max_delay(max_delay)
+            })
+            .unwrap_or_default()
+    }
+
+    fn spawn_interface_thread(&self, interface_config: InterfaceConfiguration) -> InterfaceThread {
+        let zome_calls = ZomeCallTracker::new();
+        let shutdown_grace_period = interface_config
+            .shutdown_grace_period_ms
+            .map(Duration::from_millis);
+        let dispatcher = self.make_interface_handler(&interface_config, zome_calls.clone());
+        let bind_retry = self.bind_retry();
         let log_sender = self.logger.get_sender();
-        let (tx, rx) = channel();
-        thread::Builder::new()
+        let (kill_switch, rx) = channel();
+        let handle = thread::Builder::new()
             .name(format!("conductor-interface: {}", interface_config.id))
             .spawn(move || {
-                let iface = make_interface(&interface_config);
+                let iface = make_interface(&interface_config, bind_retry);
                 iface.run(dispatcher, rx).map_err(|error| {
                     let message = format!(
                         "err/conductor: Error running interface '{}': {}",
@@ -661,7 +1159,12 @@ impl Conductor {
                 })
             })
             .expect("Could not spawn thread for interface");
-        tx
+        InterfaceThread {
+            kill_switch,
+            handle,
+            zome_calls,
+            shutdown_grace_period,
+        }
     }
 
     pub fn dna_dir_path(&self) -> PathBuf {
@@ -724,11 +1227,16 @@ impl Conductor {
 }
 
 /// This can eventually be dependency injected for third party Interface definitions
-fn make_interface(interface_config: &InterfaceConfiguration) -> Box<Interface> {
+fn make_interface(
+    interface_config: &InterfaceConfiguration,
+    bind_retry: BindRetry,
+) -> Box<Interface> {
     use interface_impls::{http::HttpInterface, websocket::WebsocketInterface};
     match interface_config.driver {
         InterfaceDriver::Websocket { port } => Box::new(WebsocketInterface::new(port)),
-        InterfaceDriver::Http { port } => Box::new(HttpInterface::new(port)),
+        InterfaceDriver::Http { port } => {
+            Box::new(HttpInterface::new(port).with_bind_retry(bind_retry))
+        }
         _ => unimplemented!(),
     }
 }
@@ -745,11 +1253,22 @@ pub mod tests {
     use super::*;
     extern crate tempfile;
     use crate::config::load_configuration;
+    use config::RequestLogConfiguration;
     use holochain_core::{action::Action, signal::signal_channel};
-    use holochain_core_types::{cas::content::Address, dna, json::RawString};
+    use holochain_core_types::{
+        cas::{
+            content::{Address, Content},
+            storage::ExampleContentAddressableStorage,
+        },
+        dna::{self, capabilities::CapabilityType},
+        entry::cap_entries::CapTokenGrant,
+        json::RawString,
+    };
     use holochain_dpki::{key_bundle::KeyBundle, SEED_SIZE};
     use holochain_sodium::secbuf::SecBuf;
     use holochain_wasm_utils::wasm_target_dir;
+    use interface::PERMISSION_DENIED_ERROR_CODE;
+    use request_log::RequestLogRecord;
     use std::{
         fs::{File, OpenOptions},
         io::Write,
@@ -757,12 +1276,14 @@ pub mod tests {
 
     use self::tempfile::tempdir;
     use test_utils::*;
+    use uuid::Uuid;
 
     pub fn test_dna_loader() -> DnaLoader {
         let loader = Box::new(|path: &PathBuf| {
             Ok(match path.to_str().unwrap().as_ref() {
                 "bridge/callee.dna" => callee_dna(),
                 "bridge/caller.dna" => caller_dna(),
+                "empty.dna" => Dna::new(),
                 _ => Dna::try_from(JsonString::from(example_dna_string())).unwrap(),
             })
         })
@@ -975,6 +1496,48 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn stopping_interfaces_waits_for_zome_calls_in_flight_at_once() {
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        config.interfaces[0].driver = InterfaceDriver::Websocket { port: 4011 };
+        config.interfaces[1].driver = InterfaceDriver::Http { port: 4012 };
+        for interface in config.interfaces.iter_mut() {
+            interface.shutdown_grace_period_ms = Some(400);
+        }
+        let mut conductor = Conductor::from_config(config);
+        conductor.dna_loader = test_dna_loader();
+        conductor.key_loader = test_key_loader();
+        conductor.load_config().expect("Test config must be sane");
+        conductor.start_all_interfaces();
+
+        // a zome call in flight on every interface that does not finish in time
+        let trackers: Vec<ZomeCallTracker> = conductor
+            .interface_threads
+            .values()
+            .map(|interface_thread| interface_thread.zome_calls.clone())
+            .collect();
+        assert_eq!(trackers.len(), 2);
+        let calls: Vec<_> = trackers
+            .iter()
+            .map(|tracker| tracker.start().unwrap())
+            .collect();
+
+        let started = Instant::now();
+        conductor.stop_all_interfaces();
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(400));
+        assert!(elapsed < Duration::from_millis(800));
+        for tracker in trackers.iter() {
+            assert_eq!(tracker.running(), 1);
+            assert!(tracker.start().is_err());
+        }
+
+        drop(calls);
+        for tracker in trackers.iter() {
+            assert_eq!(tracker.running(), 0);
+        }
+    }
+
     #[test]
     fn test_conductor_load_config() {
         let mut conductor = test_conductor();
@@ -985,6 +1548,24 @@ pub mod tests {
         conductor.stop_all_instances().unwrap();
     }
 
+    #[test]
+    fn test_network_reconnects_honor_retry_max_delay() {
+        let toml = r#"
+    agents = []
+    retry_max_delay_ms = 1234
+
+    [network]
+    n3h_ipc_uri = "tcp://127.0.0.1:0"
+    "#;
+        let config = load_configuration::<Configuration>(toml).unwrap();
+        let mut conductor = Conductor::from_config(config);
+        let p2p_config = conductor.initialize_p2p_config();
+        assert_eq!(
+            p2p_config.backend_config["reconnectMaxDelayMs"],
+            json!(1234)
+        );
+    }
+
     //#[test]
     // Default config path ~/.holochain/conductor/conductor-config.toml won't work in CI
     fn _test_conductor_save_and_load_config_default_location() {
@@ -1127,6 +1708,26 @@ pub mod tests {
 
         (i64.const 10)
     )
+
+    (func
+        (export "hello_privately")
+        (param $allocation i64)
+        (result i64)
+
+        ;; This writes "Holo World" into memory
+        (i64.store (i32.const 0) (i64.const 72))
+        (i64.store (i32.const 1) (i64.const 111))
+        (i64.store (i32.const 2) (i64.const 108))
+        (i64.store (i32.const 3) (i64.const 111))
+        (i64.store (i32.const 4) (i64.const 32))
+        (i64.store (i32.const 5) (i64.const 87))
+        (i64.store (i32.const 6) (i64.const 111))
+        (i64.store (i32.const 7) (i64.const 114))
+        (i64.store (i32.const 8) (i64.const 108))
+        (i64.store (i32.const 9) (i64.const 100))
+
+        (i64.const 10)
+    )
 )
                 "#
         .to_string()
@@ -1152,6 +1753,12 @@ pub mod tests {
             .unwrap()
             .functions
             .push("hello".into());
+        // not public, callers need a capability token
+        dna.zomes.get_mut("greeter").unwrap().add_fn_declaration(
+            String::from("hello_privately"),
+            vec![],
+            vec![],
+        );
         dna
     }
 
@@ -1195,6 +1802,260 @@ pub mod tests {
         assert_eq!(result, JsonString::from(RawString::from("Holo World")));
     }
 
+    #[test]
+    fn interface_request_log_records_calls_to_instance_methods() {
+        let conductor = test_conductor();
+        let log_dir = tempdir().unwrap();
+        let log_path = log_dir.path().join("requests.log");
+        let mut interface_config = conductor.config.interfaces[0].clone();
+        interface_config.request_log = Some(RequestLogConfiguration {
+            path: log_path.to_str().unwrap().to_string(),
+        });
+        let handler = conductor.make_interface_handler(&interface_config, ZomeCallTracker::new());
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": "0",
+            "method": "test-instance-1/greeter/hello",
+            "params": {}
+        });
+        handler.handle_request_sync(&request.to_string()).unwrap();
+
+        let log = fs::read_to_string(&log_path).expect("Request must have been logged");
+        let records: Vec<RequestLogRecord> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].method, "test-instance-1/greeter/hello");
+        assert_eq!(records[0].instance, Some("test-instance-1".to_string()));
+    }
+
+    #[test]
+    fn interface_calls_get_checked_against_their_cap_token() {
+        let mut conductor = test_conductor();
+        conductor
+            .start_all_instances()
+            .expect("Instances must be spawnable");
+        let instance = conductor.instances["test-instance-1"].clone();
+        let grant = CapTokenGrant::create(CapabilityType::Transferable, None).unwrap();
+        let token = instance.read().unwrap().mint_capability(grant).unwrap();
+        let interface_config = conductor.config.interfaces[0].clone();
+        let handler = conductor.make_interface_handler(&interface_config, ZomeCallTracker::new());
+
+        let call = |method: &str, params: serde_json::Value| {
+            let request = json!({"jsonrpc": "2.0", "id": "0", "method": method, "params": params});
+            let response = handler.handle_request_sync(&request.to_string()).unwrap();
+            let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+            match response.get("error") {
+                Some(error) => Err(error["code"].as_i64().unwrap()),
+                None => Ok(response["result"].clone()),
+            }
+        };
+        let call_with_token = |cap_token: &Address| {
+            call(
+                "call",
+                json!({
+                    "instance_id": "test-instance-1",
+                    "zome": "greeter",
+                    "function": "hello_privately",
+                    "cap_token": cap_token.to_string(),
+                    "caller": "some agent",
+                    "params": {}
+                }),
+            )
+        };
+        let instance_method_with_token = |cap_token: &Address| {
+            call(
+                "test-instance-1/greeter/hello_privately",
+                json!({"cap_token": cap_token.to_string(), "caller": "some agent"}),
+            )
+        };
+
+        assert_eq!(call_with_token(&token), Ok(json!("Holo World")));
+        assert_eq!(instance_method_with_token(&token), Ok(json!("Holo World")));
+
+        let unknown_token = Address::from("unknown token");
+        assert_eq!(
+            call_with_token(&unknown_token),
+            Err(PERMISSION_DENIED_ERROR_CODE)
+        );
+        assert_eq!(
+            instance_method_with_token(&unknown_token),
+            Err(PERMISSION_DENIED_ERROR_CODE)
+        );
+        assert_eq!(
+            call(
+                "test-instance-1/greeter/hello_privately",
+                json!({"caller": "some agent"})
+            ),
+            Err(PERMISSION_DENIED_ERROR_CODE)
+        );
+
+        instance.read().unwrap().revoke_capability(&token).unwrap();
+        assert_eq!(call_with_token(&token), Err(PERMISSION_DENIED_ERROR_CODE));
+        assert_eq!(
+            instance_method_with_token(&token),
+            Err(PERMISSION_DENIED_ERROR_CODE)
+        );
+    }
+
+    #[test]
+    fn prewarm_compiles_zome_wasm_before_first_call() {
+        let toml = test_toml().replace(
+            r#"id = "test-instance-1"
+    dna = "bridge-callee"
+    agent = "test-agent-1"
+"#,
+            r#"id = "test-instance-1"
+    dna = "bridge-callee"
+    agent = "test-agent-1"
+    wasm_module_cache_size = 10
+"#,
+        );
+        let config = load_configuration::<Configuration>(&toml).unwrap();
+        let mut conductor = Conductor::from_config(config);
+        conductor.dna_loader = test_dna_loader();
+        conductor.key_loader = test_key_loader();
+        conductor.load_config().expect("Test config must be sane");
+        conductor
+            .start_all_instances()
+            .expect("Instances must be spawnable");
+
+        let instance_id = String::from("test-instance-1");
+        conductor.prewarm(&instance_id).unwrap();
+        let instance = conductor.instances[&instance_id].clone();
+        let cache = instance
+            .read()
+            .unwrap()
+            .context()
+            .wasm_module_cache
+            .clone()
+            .expect("cache should be set");
+        let compilations = cache.compilations();
+        assert_eq!(cache.len(), 1);
+
+        let result = instance
+            .write()
+            .unwrap()
+            .call(
+                "greeter",
+                Some(dna::capabilities::CapabilityCall::new(
+                    Address::from("fake_token"),
+                    None,
+                )),
+                "hello",
+                "{}",
+            )
+            .unwrap();
+        assert_eq!(result, JsonString::from(RawString::from("Holo World")));
+        assert_eq!(cache.compilations(), compilations);
+
+        assert!(conductor.prewarm(&String::from("test-instance-2")).is_err());
+    }
+
+    /// CAS that fails to store anything, like one on a full disk
+    #[derive(Clone, Debug)]
+    struct FailingContentStorage {
+        inner: ExampleContentAddressableStorage,
+    }
+
+    impl ContentAddressableStorage for FailingContentStorage {
+        fn add(&mut self, _content: &AddressableContent) -> Result<(), HolochainError> {
+            Err(HolochainError::ErrorGeneric(String::from(
+                "No space left on device",
+            )))
+        }
+        fn contains(&self, address: &Address) -> Result<bool, HolochainError> {
+            self.inner.contains(address)
+        }
+        fn fetch(&self, address: &Address) -> Result<Option<Content>, HolochainError> {
+            self.inner.fetch(address)
+        }
+        fn addresses(&self) -> Result<Vec<Address>, HolochainError> {
+            self.inner.addresses()
+        }
+        fn remove(&mut self, address: &Address) -> Result<(), HolochainError> {
+            self.inner.remove(address)
+        }
+        fn get_id(&self) -> Uuid {
+            self.inner.get_id()
+        }
+    }
+
+    #[test]
+    fn self_test_passes_on_healthy_instance() {
+        let conductor = test_conductor();
+        conductor
+            .self_test(&String::from("test-instance-1"))
+            .expect("Self test should pass on memory storage");
+        assert!(conductor
+            .self_test(&String::from("no-such-instance"))
+            .is_err());
+    }
+
+    #[test]
+    fn self_test_reports_failing_storage() {
+        let mut context = ContextBuilder::new()
+            .with_conductor_api(test_utils::mock_signing::mock_conductor_api(
+                AgentId::generate_fake("alice"),
+            ))
+            .spawn();
+        assert!(self_test_storage(&context).is_ok());
+        // every self test writes its own EAV record
+        assert!(self_test_storage(&context).is_ok());
+        let eav_records = context
+            .eav_storage
+            .read()
+            .unwrap()
+            .fetch_eavi(&EaviQuery::new(
+                None.into(),
+                Some(Attribute::SelfTest).into(),
+                None.into(),
+                IndexFilter::Range(None, None),
+            ))
+            .unwrap();
+        assert_eq!(eav_records.len(), 2);
+
+        context.dht_storage = Arc::new(RwLock::new(FailingContentStorage {
+            inner: ExampleContentAddressableStorage::new().unwrap(),
+        }));
+        match self_test_storage(&context) {
+            Err(HolochainError::ErrorGeneric(message)) => assert_eq!(
+                message,
+                "Storage self test failed to write to DHT storage: No space left on device"
+            ),
+            other => panic!("Expected self test to fail, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn await_instance_ready_returns_once_network_is_up() {
+        let conductor = test_conductor();
+        let started = Instant::now();
+        assert_eq!(
+            conductor
+                .await_instance_ready(&String::from("test-instance-1"), Duration::from_secs(10)),
+            Ok(())
+        );
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // an instance that left its network never gets ready again
+        conductor
+            .instances
+            .get("test-instance-2")
+            .unwrap()
+            .write()
+            .unwrap()
+            .shutdown()
+            .unwrap();
+        assert_eq!(
+            conductor
+                .await_instance_ready(&String::from("test-instance-2"), Duration::from_millis(100)),
+            Err(HolochainError::Timeout)
+        );
+    }
+
     #[test]
     fn fails_if_key_address_does_not_match() {
         // Config with well formatted public address but differing to the deterministic key
@@ -1229,4 +2090,121 @@ pub mod tests {
         );
     }
 
+    fn empty_dna_toml(reject_dna_without_zomes: bool) -> String {
+        format!(
+            r#"
+    [[agents]]
+    id = "test-agent-1"
+    name = "Holo Tester 1"
+    public_address = "{}"
+    key_file = "holo_tester1.key"
+
+    [[dnas]]
+    id = "empty-dna"
+    file = "empty.dna"
+
+    [[instances]]
+    id = "empty-instance"
+    dna = "empty-dna"
+    agent = "test-agent-1"
+    reject_dna_without_zomes = {}
+    [instances.storage]
+    type = "memory"
+    "#,
+            test_keybundle(1).get_id(),
+            reject_dna_without_zomes
+        )
+    }
+
+    #[test]
+    fn dna_without_zomes_gets_rejected_if_configured() {
+        let config = load_configuration::<Configuration>(&empty_dna_toml(false)).unwrap();
+        let mut conductor = Conductor::from_config(config);
+        conductor.dna_loader = test_dna_loader();
+        conductor.key_loader = test_key_loader();
+        // only warns by default
+        assert_eq!(conductor.load_config(), Ok(()));
+        assert!(conductor.instances.contains_key("empty-instance"));
+
+        let config = load_configuration::<Configuration>(&empty_dna_toml(true)).unwrap();
+        let mut conductor = Conductor::from_config(config);
+        conductor.dna_loader = test_dna_loader();
+        conductor.key_loader = test_key_loader();
+        assert_eq!(
+            conductor.load_config(),
+            Err(String::from(
+                "Error while trying to create instance \"empty-instance\": Invalid DNA: DNA has no zomes, so it has no functions to call"
+            ))
+        );
+    }
+
+    #[test]
+    fn shutdown_frees_ports() {
+        let ui_dir = tempdir().unwrap();
+        let config = load_configuration::<Configuration>(&format!(
+            r#"
+                [[agents]]
+                id = "test-agent-2"
+                name = "Holo Tester 2"
+                public_address = "{}"
+                key_file = "holo_tester2.key"
+
+                [[dnas]]
+                id = "test-dna"
+                file = "app_spec.dna.json"
+                hash = "Qm328wyq38924y"
+
+                [[instances]]
+                id = "test-instance"
+                dna = "test-dna"
+                agent = "test-agent-2"
+                [instances.storage]
+                type = "memory"
+
+                [[interfaces]]
+                id = "http-interface"
+                [interfaces.driver]
+                type = "http"
+                port = 4010
+                [[interfaces.instances]]
+                id = "test-instance"
+
+                [[ui_bundles]]
+                id = "bundle"
+                root_dir = "{}"
+
+                [[ui_interfaces]]
+                id = "ui-interface"
+                bundle = "bundle"
+                port = 3040
+                dna_interface = "http-interface"
+                "#,
+            test_keybundle(2).get_id(),
+            ui_dir.path().display()
+        ))
+        .unwrap();
+        let mut conductor = Conductor::from_config(config);
+        conductor.dna_loader = test_dna_loader();
+        conductor.key_loader = test_key_loader();
+        conductor.load_config().expect("Test config must be sane");
+        conductor.start_all_instances().unwrap();
+        conductor.start_all_interfaces();
+        conductor.start_all_static_servers().unwrap();
+
+        // give the interface thread time to bind its port
+        thread::sleep(Duration::from_millis(500));
+        assert!(std::net::TcpListener::bind("0.0.0.0:4010").is_err());
+        assert!(std::net::TcpListener::bind("127.0.0.1:3040").is_err());
+
+        conductor.shutdown();
+
+        assert!(conductor.instances().is_empty());
+        assert!(conductor.interface_threads.is_empty());
+        assert!(!conductor.static_servers["ui-interface"].is_running());
+        assert!(std::net::TcpListener::bind("127.0.0.1:3040").is_ok());
+        // the HTTP server releases its socket from its own event loop
+        assert!(BindRetry::new(10, Duration::from_millis(50))
+            .bind(|| std::net::TcpListener::bind("0.0.0.0:4010"))
+            .is_ok());
+    }
 }
@@ -58,6 +58,7 @@ impl ConductorUiAdmin for Conductor {
             id: id.to_string(),
             root_dir: path_string.into(),
             hash: None,
+            fallback_dir: None,
         };
 
         let mut new_config = self.config.clone();
@@ -114,13 +115,20 @@ impl ConductorUiAdmin for Conductor {
         new_config.check_consistency()?;
         self.config = new_config;
         self.save_config()?;
+        let bind_retry = self.bind_retry();
+        let connected_dna_interface = new_interface
+            .dna_interface
+            .as_ref()
+            .and_then(|interface_id| self.config.interface_by_id(interface_id));
         self.static_servers.insert(
             new_interface.id.clone(),
             StaticServer::from_configs(
                 new_interface.clone(),
                 self.config.ui_bundle_by_id(&new_interface.bundle).unwrap(),
-                None,
-            ),
+                connected_dna_interface,
+            )
+            .with_dna_interfaces(self.config.interfaces.clone())
+            .with_bind_retry(bind_retry),
         );
         Ok(())
     }
@@ -172,7 +180,8 @@ impl ConductorUiAdmin for Conductor {
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use conductor::{admin::tests::*, base::UiDirCopier};
+    use conductor::{admin::tests::*, base::UiDirCopier, ConductorAdmin};
+    use reqwest;
     use std::{fs::File, io::Read};
 
     pub fn test_ui_copier() -> UiDirCopier {
@@ -324,6 +333,8 @@ id = 'test-bundle-id'"#,
                 port: 4000,
                 bundle: "test-bundle-id".into(),
                 dna_interface: None,
+                request_timeout_ms: None,
+                max_restarts: None,
             }),
             Err(HolochainError::ErrorGeneric(
                 "UI bundle configuration test-bundle-id not found, mentioned in UI interface test-ui-interface-id".into()
@@ -346,6 +357,8 @@ id = 'test-bundle-id'"#,
                 port: 4000,
                 bundle: "test-bundle-id".into(),
                 dna_interface: None,
+                request_timeout_ms: None,
+                max_restarts: None,
             }),
             Ok(())
         );
@@ -410,6 +423,8 @@ port = 4000"#,
                 port: 4000,
                 bundle: "test-bundle-id".into(),
                 dna_interface: None,
+                request_timeout_ms: None,
+                max_restarts: None,
             }),
             Ok(())
         );
@@ -469,6 +484,8 @@ root_dir = '.'"#,
                 port: 4000,
                 bundle: "test-bundle-id".into(),
                 dna_interface: None,
+                request_timeout_ms: None,
+                max_restarts: None,
             }),
             Ok(())
         );
@@ -479,6 +496,78 @@ root_dir = '.'"#,
         );
     }
 
+    #[test]
+    fn test_ui_interface_sees_changes_of_its_dna_interface() {
+        let test_name = "test_ui_interface_sees_changes_of_its_dna_interface";
+        let mut conductor = create_test_conductor(test_name, 3051);
+        assert_eq!(
+            conductor.install_ui_bundle_from_file(
+                PathBuf::from("."),
+                &"test-bundle-id".to_string(),
+                false
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            conductor.add_ui_interface(UiInterfaceConfiguration {
+                id: "test-ui-interface-id".into(),
+                port: 3052,
+                bundle: "test-bundle-id".into(),
+                dna_interface: Some("websocket interface".into()),
+                request_timeout_ms: None,
+                max_restarts: None,
+            }),
+            Ok(())
+        );
+        assert_eq!(
+            conductor.start_ui_interface(&"test-ui-interface-id".to_string()),
+            Ok(())
+        );
+        let served_instances = || -> usize {
+            let dna_connections: serde_json::Value =
+                reqwest::get("http://localhost:3052/_dna_connections.json")
+                    .expect("Could not make request")
+                    .json()
+                    .expect("response body is not valid json");
+            dna_connections["dna_interface"]["instances"]
+                .as_array()
+                .expect("UI interface should be connected")
+                .len()
+        };
+        assert_eq!(served_instances(), 2);
+
+        assert_eq!(
+            conductor.add_instance(
+                &String::from("new-instance"),
+                &String::from("test-dna"),
+                &String::from("test-agent-1")
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            conductor.add_instance_to_interface(
+                &String::from("websocket interface"),
+                &String::from("new-instance")
+            ),
+            Ok(())
+        );
+        assert_eq!(served_instances(), 3);
+
+        assert_eq!(
+            conductor.remove_instance_from_interface(
+                &String::from("websocket interface"),
+                &String::from("new-instance")
+            ),
+            Ok(())
+        );
+        assert_eq!(served_instances(), 2);
+
+        assert_eq!(
+            conductor.stop_ui_interface(&"test-ui-interface-id".to_string()),
+            Ok(())
+        );
+    }
+
     #[test]
     fn test_stop_ui_interface() {
         let test_name = "test_stop_ui_interface";
@@ -500,6 +589,8 @@ root_dir = '.'"#,
                 port: 4001,
                 bundle: "test-bundle-id".into(),
                 dna_interface: None,
+                request_timeout_ms: None,
+                max_restarts: None,
             }),
             Ok(())
         );
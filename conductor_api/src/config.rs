@@ -12,8 +12,14 @@ use crate::logger::LogRules;
 /// * bridges, which are
 use boolinator::*;
 use directories;
+use holochain_cas_implementations::{
+    backend::{FILE_BACKEND, FILE_MESSAGE_PACK_BACKEND, MEMORY_BACKEND},
+    cas::format::ContentFormat,
+};
+use holochain_core::network::direct_message::DirectMessageOverflow;
 use holochain_core_types::{
     agent::{AgentId, Base32},
+    backoff::Jitter,
     dna::Dna,
     error::{HcResult, HolochainError},
     json::JsonString,
@@ -67,6 +73,28 @@ pub struct Configuration {
     /// If set, all agents with holo_remote_key = true will be emulated by asking for signatures
     /// over this websocket.
     pub signing_service_uri: Option<String>,
+
+    /// Maximum size in bytes of payloads that get sent to the signing service.
+    /// Bigger ones get rejected. Optional, defaults to DEFAULT_MAX_SIGNING_PAYLOAD_BYTES.
+    #[serde(default)]
+    pub signing_service_max_payload_bytes: Option<usize>,
+
+    /// Number of times binding the port of an HTTP or UI interface gets retried if it is
+    /// still in use, e.g. after a restart. Optional, binding fails right away if not set.
+    #[serde(default)]
+    pub interface_bind_retries: Option<usize>,
+
+    /// Randomization of the time waited between bind retries: "none", "full" or "equal".
+    /// Optional, defaults to "none".
+    #[serde(default)]
+    pub interface_bind_jitter: Jitter,
+
+    /// Longest time in milliseconds waited between two retries, like those of binding
+    /// interface ports or reconnecting to n3h, no matter how many attempts failed before.
+    /// Optional, defaults to DEFAULT_MAX_DELAY (30 seconds) for interface ports and
+    /// 500 milliseconds for n3h connections.
+    #[serde(default)]
+    pub retry_max_delay_ms: Option<u64>,
 }
 
 pub fn default_persistence_dir() -> PathBuf {
@@ -196,6 +224,31 @@ impl Configuration {
         self.ui_bundles.iter().find(|ic| &ic.id == id).cloned()
     }
 
+    /// Returns every interface with the instances it exposes and the DNAs they run,
+    /// i.e. everything a client can reach on this conductor.
+    /// Instances and DNAs missing from the config are left out.
+    pub fn routing(&self) -> Vec<InterfaceRoutes> {
+        self.interfaces
+            .iter()
+            .map(|interface| InterfaceRoutes {
+                interface_id: interface.id.clone(),
+                driver: interface.driver.clone(),
+                admin: interface.admin,
+                instances: interface
+                    .instances
+                    .iter()
+                    .filter_map(|reference| self.instance_by_id(&reference.id))
+                    .map(|instance| InstanceRoute {
+                        dna_hash: self.dna_by_id(&instance.dna).and_then(|dna| dna.hash),
+                        instance_id: instance.id,
+                        dna_id: instance.dna,
+                        agent_id: instance.agent,
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
     /// Returns all defined instance IDs
     pub fn instance_ids(&self) -> Vec<String> {
         self.instances
@@ -313,6 +366,11 @@ pub struct AgentConfiguration {
     /// If set to true conductor will ignore key_file and instead use the remote signer
     /// accessible through signing_service_uri to request signatures.
     pub holo_remote_key: Option<bool>,
+    /// Only used together with holo_remote_key. If set to true the key in key_file gets used
+    /// to sign whenever the signing service can't be reached. Optional, off if not set.
+    /// The instances of the agent fail to start if that key does not belong to public_address.
+    #[serde(default)]
+    pub local_signing_fallback: Option<bool>,
 }
 
 impl From<AgentConfiguration> for AgentId {
@@ -351,6 +409,101 @@ pub struct InstanceConfiguration {
     pub dna: String,
     pub agent: String,
     pub storage: StorageConfiguration,
+    /// Maximum number of validation callbacks this instance runs at the same time.
+    /// Validations beyond that get queued. Optional, unlimited if not set.
+    #[serde(default)]
+    pub max_concurrent_validations: Option<usize>,
+    /// Number of seconds after which entries this instance holds for other agents
+    /// get removed from its DHT shard. Optional, held entries are kept if not set.
+    /// Has no effect with storages that keep the source chain and the DHT shard in the
+    /// same place (like "file"), since held entries can't be told apart from the
+    /// agent's own there.
+    /// Expiry times are only kept in memory: entries held before a restart of the
+    /// conductor don't expire anymore, only those received after it do.
+    #[serde(default)]
+    pub dht_cache_ttl_secs: Option<u64>,
+    /// How often network fetches done by zome calls get retried after transient
+    /// network errors before the zome call fails. Optional, not retried if not set.
+    #[serde(default)]
+    pub network_fetch_retries: Option<usize>,
+    /// Optional file that gets an audit record of every zome call of this instance appended.
+    #[serde(default)]
+    pub audit_log: Option<AuditLogConfiguration>,
+    /// Number of milliseconds during which authored entries get collected and then
+    /// published together. Entries committed several times within the window get
+    /// published once. Optional, entries get published right after commit if not set.
+    /// The pending batch gets published when the instance shuts down, but it is not
+    /// persisted, so it gets lost if the conductor dies before the window has passed.
+    #[serde(default)]
+    pub publish_batch_window_ms: Option<u64>,
+    /// Reuses results of zome functions declared as cacheable for calls with identical
+    /// arguments. Optional, every call runs the zome function if not set.
+    #[serde(default)]
+    pub zome_call_cache: Option<ZomeCallCacheConfiguration>,
+    /// Maximum number of compiled wasm modules kept for reuse. The modules of all zomes
+    /// get compiled when the instance is loaded. Optional, wasm gets compiled for every
+    /// call if not set.
+    #[serde(default)]
+    pub wasm_module_cache_size: Option<usize>,
+    /// Maximum number of headers get_entry returns per entry. Entries with more
+    /// headers get the newest ones and a flag marking them as truncated.
+    /// Optional, all headers get returned if not set.
+    #[serde(default)]
+    pub max_headers_per_entry: Option<usize>,
+    /// Fail to start this instance if its DNA has no zomes instead of only warning
+    /// about it, since such an instance can't do anything. Defaults to false.
+    #[serde(default)]
+    pub reject_dna_without_zomes: bool,
+    /// Reuses the outcome of validating an entry when the same entry with the same header
+    /// gets held again. Optional, every hold runs the validation if not set.
+    #[serde(default)]
+    pub validation_cache: Option<ValidationCacheConfiguration>,
+    /// Maximum number of bases whose links get kept indexed in memory for fast
+    /// get_links lookups. Optional, every get_links queries the storage if not set.
+    #[serde(default)]
+    pub link_index_size: Option<usize>,
+    /// Record the fuel every zome call consumes, per zome function, and report it as
+    /// metric. Fuel counts calls of Zome API functions, weighted by how expensive they are
+    /// for the host. Defaults to false.
+    #[serde(default)]
+    pub profile_zome_calls: bool,
+    /// Re-validate every entry of the source chain against the DNA when the instance gets
+    /// loaded and log a warning for each one that does not validate anymore.
+    /// Loading takes longer the longer the chain is, since every entry runs through its
+    /// validation callback. Defaults to false.
+    #[serde(default)]
+    pub validate_chain_on_load: bool,
+}
+
+/// Where and how an instance writes its zome call audit records.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct AuditLogConfiguration {
+    pub path: String,
+    /// Store call arguments as passed instead of only their hashes. Defaults to false.
+    #[serde(default)]
+    pub raw_arguments: bool,
+}
+
+/// Size and lifetime of an instance's zome call cache.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ZomeCallCacheConfiguration {
+    /// Maximum number of stored results.
+    pub max_entries: usize,
+    /// Number of seconds a stored result can be reused.
+    pub ttl_secs: u64,
+}
+
+/// Size and lifetimes of an instance's validation cache.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ValidationCacheConfiguration {
+    /// Maximum number of stored validation outcomes.
+    pub max_entries: usize,
+    /// Number of seconds a passed validation can be reused.
+    pub ttl_secs: u64,
+    /// Number of seconds a failed validation can be reused.
+    /// Defaults to 0, so failed entries get validated again every time.
+    #[serde(default)]
+    pub failure_ttl_secs: u64,
 }
 
 /// This configures the Content Addressable Storage (CAS) that
@@ -359,12 +512,51 @@ pub struct InstanceConfiguration {
 /// * memory
 /// * file
 ///
-/// Projected are various DB adapters.
+/// Any other backend that got registered with the conductor's
+/// [StorageBackendRegistry](../../holochain_cas_implementations/backend/struct.StorageBackendRegistry.html)
+/// can be selected by name with the custom type, e.g. for DB adapters.
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum StorageConfiguration {
     Memory,
-    File { path: String },
+    File {
+        path: String,
+        /// Format content gets written to disk in, "json" (default) or "messagepack".
+        /// Addresses don't depend on it.
+        #[serde(default)]
+        format: ContentFormat,
+    },
+    Custom {
+        backend: String,
+        path: Option<String>,
+    },
+}
+
+impl StorageConfiguration {
+    /// Name under which the selected backend is registered.
+    pub fn backend_name(&self) -> &str {
+        match self {
+            StorageConfiguration::Memory => MEMORY_BACKEND,
+            StorageConfiguration::File {
+                format: ContentFormat::Json,
+                ..
+            } => FILE_BACKEND,
+            StorageConfiguration::File {
+                format: ContentFormat::MessagePack,
+                ..
+            } => FILE_MESSAGE_PACK_BACKEND,
+            StorageConfiguration::Custom { backend, .. } => backend,
+        }
+    }
+
+    /// Path the backend should store its data in, if it is a persistent one.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            StorageConfiguration::Memory => None,
+            StorageConfiguration::File { path, .. } => Some(path),
+            StorageConfiguration::Custom { path, .. } => path.as_ref().map(String::as_str),
+        }
+    }
 }
 
 /// Here, interfaces are user facing and make available zome functions to
@@ -387,8 +579,45 @@ pub struct InterfaceConfiguration {
     pub admin: bool,
     #[serde(default)]
     pub instances: Vec<InstanceReferenceConfiguration>,
+    /// Maximum number of zome calls that get executed concurrently for requests
+    /// coming in over this interface. Unbounded if not set. Optional.
+    #[serde(default)]
+    pub max_zome_call_threads: Option<usize>,
+    /// Number of zome calls that may wait for a free thread once `max_zome_call_threads`
+    /// is reached. Further calls get rejected with a "server busy" error.
+    /// Defaults to DEFAULT_MAX_QUEUED_ZOME_CALLS. Optional.
+    #[serde(default)]
+    pub max_queued_zome_calls: Option<usize>,
+    /// Zome functions that may be called over this interface.
+    /// Calls to any other function get rejected with a permission error.
+    /// All functions are callable if empty. Optional.
+    #[serde(default)]
+    pub allowed_functions: Vec<ZomeFunctionReference>,
+    /// Milliseconds that running zome calls get to finish when the interface gets stopped.
+    /// New calls are rejected in the meantime. Stopped without waiting if not set. Optional.
+    #[serde(default)]
+    pub shutdown_grace_period_ms: Option<u64>,
+    /// File that gets a record of every zome call request coming in over this interface
+    /// appended. Optional, requests are not logged if not set.
+    #[serde(default)]
+    pub request_log: Option<RequestLogConfiguration>,
 }
 
+/// Where an interface writes its request log records.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct RequestLogConfiguration {
+    pub path: String,
+}
+
+/// Names a zome function, e.g. in the allow-list of an interface.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ZomeFunctionReference {
+    pub zome: String,
+    pub function: String,
+}
+
+pub const DEFAULT_MAX_QUEUED_ZOME_CALLS: usize = 100;
+
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum InterfaceDriver {
@@ -398,11 +627,41 @@ pub enum InterfaceDriver {
     Custom(toml::value::Value),
 }
 
+impl InterfaceDriver {
+    /// Name of the transport this driver uses, as in the `type` field of the config.
+    pub fn transport(&self) -> &'static str {
+        match self {
+            InterfaceDriver::Websocket { .. } => "websocket",
+            InterfaceDriver::Http { .. } => "http",
+            InterfaceDriver::DomainSocket { .. } => "domainsocket",
+            InterfaceDriver::Custom(_) => "custom",
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct InstanceReferenceConfiguration {
     pub id: String,
 }
 
+/// An interface and the instances that can be reached through it,
+/// see Configuration::routing().
+#[derive(Serialize, PartialEq, Debug, Clone)]
+pub struct InterfaceRoutes {
+    pub interface_id: String,
+    pub driver: InterfaceDriver,
+    pub admin: bool,
+    pub instances: Vec<InstanceRoute>,
+}
+
+#[derive(Serialize, PartialEq, Debug, Clone)]
+pub struct InstanceRoute {
+    pub instance_id: String,
+    pub dna_id: String,
+    pub dna_hash: Option<String>,
+    pub agent_id: String,
+}
+
 /// A bridge enables an instance to call zome functions of another instance.
 /// It is basically an internal interface.
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
@@ -431,6 +690,10 @@ pub struct UiBundleConfiguration {
     pub root_dir: String,
     #[serde(default)]
     pub hash: Option<String>,
+    /// Directory to serve instead of root_dir if that does not exist or is empty,
+    /// e.g. a placeholder page while the UI is still being built. (Optional)
+    #[serde(default)]
+    pub fallback_dir: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
@@ -448,6 +711,17 @@ pub struct UiInterfaceConfiguration {
     /// (Optional)
     #[serde(default)]
     pub dna_interface: Option<String>,
+
+    /// Milliseconds a client gets to send a request and have it answered before the
+    /// connection gets closed with a 408 response. Protects the server against clients
+    /// that stall mid-request. No timeout if not set. (Optional)
+    #[serde(default)]
+    pub request_timeout_ms: Option<u64>,
+
+    /// How often the server gets restarted after it panicked before it stays down.
+    /// No restarts if not set. (Optional)
+    #[serde(default)]
+    pub max_restarts: Option<u32>,
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
@@ -473,6 +747,44 @@ pub struct NetworkConfig {
     /// filepath to the json file holding the network settings for n3h
     #[serde(default)]
     pub networking_config_file: Option<String>,
+    /// Maximum size in bytes of a published entry. Bigger entries get split into
+    /// several messages and reassembled by the receiving nodes. Default is no limit.
+    #[serde(default)]
+    pub max_message_size: Option<usize>,
+    /// Direct messages from other nodes whose timestamp is off by more than this many
+    /// seconds get rejected, as do messages that were already received.
+    /// Default is 300.
+    #[serde(default)]
+    pub direct_message_replay_window_secs: Option<u64>,
+    /// Maximum number of direct messages each instance may have waiting for a response
+    /// at the same time. Default is no limit.
+    #[serde(default)]
+    pub max_in_flight_direct_messages: Option<usize>,
+    /// What happens to direct messages sent beyond `max_in_flight_direct_messages`:
+    /// "queue" makes them wait for a free slot (but still time out), "reject" makes sending
+    /// fail right away. Default is "reject".
+    #[serde(default)]
+    pub direct_message_overflow: Option<DirectMessageOverflow>,
+    /// Maximum number of direct messages that wait for a free slot with
+    /// `direct_message_overflow = "queue"`. Sending more fails right away. Default is 1000.
+    #[serde(default)]
+    pub max_queued_direct_messages: Option<usize>,
+    /// Maximum size in bytes of the network message carrying a validation package another
+    /// node sends when asked for one. Bigger packages get rejected before they get parsed,
+    /// together with the entry they were requested for. Default is no limit.
+    #[serde(default)]
+    pub max_validation_package_size: Option<usize>,
+    /// Maximum number of peers each instance accepts network connections from.
+    /// Idle peers get dropped to make room for new ones. Default is no limit.
+    #[serde(default)]
+    pub max_peers: Option<usize>,
+    /// Number of milliseconds between the publish rounds in which instances hand the data
+    /// they published to the network. This only delays publishing: data waiting for the
+    /// next round is kept in memory and gets lost if the conductor dies before it.
+    /// Rounds can also be started with the `admin/instance/trigger_publish` admin function,
+    /// and start early once 1000 publishes are waiting. Default is to publish right away.
+    #[serde(default)]
+    pub publish_interval_ms: Option<u64>,
 }
 
 // note that this behaviour is documented within
@@ -677,6 +989,14 @@ pub mod tests {
                 networking_config_file: Some(String::from(
                     "/Users/cnorris/.holochain/network_config.json"
                 )),
+                max_message_size: None,
+                direct_message_replay_window_secs: None,
+                max_in_flight_direct_messages: None,
+                direct_message_overflow: None,
+                max_queued_direct_messages: None,
+                max_validation_package_size: None,
+                max_peers: None,
+                publish_interval_ms: None,
             }
         );
     }
@@ -1014,6 +1334,86 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_routing() {
+        let toml = r#"
+    [[agents]]
+    id = "test agent"
+    name = "Holo Tester 1"
+    public_address = "HoloTester1-------------------------------------------------------------------------AHi1"
+    key_file = "holo_tester.key"
+
+    [[dnas]]
+    id = "app spec rust"
+    file = "app_spec.dna.json"
+    hash = "Qm328wyq38924y"
+
+    [[dnas]]
+    id = "chat"
+    file = "chat.dna.json"
+
+    [[instances]]
+    id = "app spec instance"
+    dna = "app spec rust"
+    agent = "test agent"
+    [instances.storage]
+    type = "memory"
+
+    [[instances]]
+    id = "chat instance"
+    dna = "chat"
+    agent = "test agent"
+    [instances.storage]
+    type = "memory"
+
+    [[interfaces]]
+    id = "websocket interface"
+    admin = true
+    [interfaces.driver]
+    type = "websocket"
+    port = 8888
+    [[interfaces.instances]]
+    id = "app spec instance"
+
+    [[interfaces]]
+    id = "http interface"
+    [interfaces.driver]
+    type = "http"
+    port = 4000
+    [[interfaces.instances]]
+    id = "chat instance"
+    "#;
+        let config = load_configuration::<Configuration>(toml).unwrap();
+
+        assert_eq!(
+            config.routing(),
+            vec![
+                InterfaceRoutes {
+                    interface_id: "websocket interface".to_string(),
+                    driver: InterfaceDriver::Websocket { port: 8888 },
+                    admin: true,
+                    instances: vec![InstanceRoute {
+                        instance_id: "app spec instance".to_string(),
+                        dna_id: "app spec rust".to_string(),
+                        dna_hash: Some("Qm328wyq38924y".to_string()),
+                        agent_id: "test agent".to_string(),
+                    }],
+                },
+                InterfaceRoutes {
+                    interface_id: "http interface".to_string(),
+                    driver: InterfaceDriver::Http { port: 4000 },
+                    admin: false,
+                    instances: vec![InstanceRoute {
+                        instance_id: "chat instance".to_string(),
+                        dna_id: "chat".to_string(),
+                        dna_hash: None,
+                        agent_id: "test agent".to_string(),
+                    }],
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_bridge_dependencies() {
         let toml = bridges_config(
@@ -1131,4 +1531,57 @@ pub mod tests {
             Err("DNA Interface configuration \"<not existant>\" not found, mentioned in UI interface \"ui-interface-1\"".to_string())
         );
     }
+
+    #[test]
+    fn test_load_custom_storage_config() {
+        let toml = r#"
+    type = "custom"
+    backend = "sqlite"
+    path = "app_spec_storage.db"
+    "#;
+        let storage = load_configuration::<StorageConfiguration>(toml).unwrap();
+        assert_eq!(
+            storage,
+            StorageConfiguration::Custom {
+                backend: "sqlite".to_string(),
+                path: Some("app_spec_storage.db".to_string()),
+            }
+        );
+        assert_eq!(storage.backend_name(), "sqlite");
+        assert_eq!(storage.path(), Some("app_spec_storage.db"));
+
+        let storage = load_configuration::<StorageConfiguration>(r#"type = "memory""#).unwrap();
+        assert_eq!(storage.backend_name(), "memory");
+        assert_eq!(storage.path(), None);
+    }
+
+    #[test]
+    fn test_load_file_storage_format_config() {
+        let storage = load_configuration::<StorageConfiguration>(
+            r#"
+    type = "file"
+    path = "app_spec_storage"
+    "#,
+        )
+        .unwrap();
+        assert_eq!(storage.backend_name(), "file");
+
+        let storage = load_configuration::<StorageConfiguration>(
+            r#"
+    type = "file"
+    path = "app_spec_storage"
+    format = "messagepack"
+    "#,
+        )
+        .unwrap();
+        assert_eq!(
+            storage,
+            StorageConfiguration::File {
+                path: "app_spec_storage".to_string(),
+                format: ContentFormat::MessagePack,
+            }
+        );
+        assert_eq!(storage.backend_name(), "file_msgpack");
+        assert_eq!(storage.path(), Some("app_spec_storage"));
+    }
 }
@@ -1,13 +1,25 @@
 use holochain_cas_implementations::{
-    cas::{file::FilesystemStorage, memory::MemoryStorage},
-    eav::{file::EavFileStorage, memory::EavMemoryStorage},
-    path::create_path_if_not_exists,
+    backend::{file_backend, StorageBackend},
+    cas::memory::MemoryStorage,
+    eav::memory::EavMemoryStorage,
 };
 
 use holochain_core::{
     context::Context,
     logger::{Logger, SimpleLogger},
+    network::{
+        direct_message::{
+            DirectMessageLimit, DirectMessageOverflow, DEFAULT_MAX_QUEUED_DIRECT_MESSAGES,
+        },
+        publish_batch::PublishBatcher,
+    },
+    nucleus::{
+        audit::ZomeCallAuditLog, call_cache::ZomeCallCache, profiler::ZomeCallProfiler,
+        ribosome::module_cache::WasmModuleCache, validation_cache::ValidationCache,
+        validation_limiter::ValidationLimiter,
+    },
     persister::SimplePersister,
+    reduce_profiler::ReduceProfiler,
     signal::SignalSender,
 };
 use holochain_core_types::{
@@ -17,8 +29,9 @@ use holochain_core_types::{
 use holochain_net::p2p_config::P2pConfig;
 use jsonrpc_ws_server::jsonrpc_core::IoHandler;
 use std::{
-    path::{Path, PathBuf},
+    path::Path,
     sync::{Arc, Mutex, RwLock},
+    time::Duration,
 };
 
 /// This type helps building [context objects](struct.Context.html) that need to be
@@ -41,6 +54,22 @@ pub struct ContextBuilder {
     p2p_config: Option<P2pConfig>,
     conductor_api: Option<Arc<RwLock<IoHandler>>>,
     signal_tx: Option<SignalSender>,
+    max_concurrent_validations: Option<usize>,
+    dht_cache_ttl: Option<Duration>,
+    network_fetch_retries: usize,
+    network_max_message_size: Option<usize>,
+    direct_message_replay_window: Option<Duration>,
+    direct_message_limit: Option<DirectMessageLimit>,
+    max_validation_package_size: Option<usize>,
+    zome_call_audit_log: Option<ZomeCallAuditLog>,
+    publish_batch_window: Option<Duration>,
+    zome_call_cache: Option<ZomeCallCache>,
+    wasm_module_cache: Option<WasmModuleCache>,
+    max_headers_per_entry: Option<usize>,
+    validation_cache: Option<ValidationCache>,
+    link_index_size: Option<usize>,
+    zome_call_profiler: Option<ZomeCallProfiler>,
+    reduce_profiler: Option<ReduceProfiler>,
 }
 
 impl ContextBuilder {
@@ -54,6 +83,22 @@ impl ContextBuilder {
             p2p_config: None,
             conductor_api: None,
             signal_tx: None,
+            max_concurrent_validations: None,
+            dht_cache_ttl: None,
+            network_fetch_retries: 0,
+            network_max_message_size: None,
+            direct_message_replay_window: None,
+            direct_message_limit: None,
+            max_validation_package_size: None,
+            zome_call_audit_log: None,
+            publish_batch_window: None,
+            zome_call_cache: None,
+            wasm_module_cache: None,
+            max_headers_per_entry: None,
+            validation_cache: None,
+            link_index_size: None,
+            zome_call_profiler: None,
+            reduce_profiler: None,
         }
     }
 
@@ -77,19 +122,18 @@ impl ContextBuilder {
     /// Sets all three storages, chain, DHT and EAV storage, to persistent file based implementations.
     /// Chain and DHT storages get set to the same file CAS.
     /// Returns an error if no file storage could be spawned on the given path.
-    pub fn with_file_storage<P: AsRef<Path>>(mut self, path: P) -> Result<Self, HolochainError> {
-        let base_path: PathBuf = path.as_ref().into();
-        let cas_path = base_path.join("cas");
-        let eav_path = base_path.join("eav");
-        create_path_if_not_exists(&cas_path)?;
-        create_path_if_not_exists(&eav_path)?;
+    pub fn with_file_storage<P: AsRef<Path>>(self, path: P) -> Result<Self, HolochainError> {
+        Ok(self.with_storage_backend(file_backend(path)?))
+    }
 
-        let file_storage = Arc::new(RwLock::new(FilesystemStorage::new(&cas_path)?));
-        let eav_storage = Arc::new(RwLock::new(EavFileStorage::new(eav_path)?));
-        self.chain_storage = Some(file_storage.clone());
-        self.dht_storage = Some(file_storage);
-        self.eav_storage = Some(eav_storage);
-        Ok(self)
+    /// Sets all three storages, chain, DHT and EAV storage, to the ones of the given backend.
+    /// Backends are typically created by name through a
+    /// [StorageBackendRegistry](../../holochain_cas_implementations/backend/struct.StorageBackendRegistry.html).
+    pub fn with_storage_backend(mut self, backend: StorageBackend) -> Self {
+        self.chain_storage = Some(backend.chain_storage);
+        self.dht_storage = Some(backend.dht_storage);
+        self.eav_storage = Some(backend.eav_storage);
+        self
     }
 
     /// Sets the network config.
@@ -113,10 +157,163 @@ impl ContextBuilder {
         self
     }
 
+    /// Limits the number of validation callbacks the instance runs concurrently.
+    /// Further validations get queued until a running one finishes.
+    pub fn with_max_concurrent_validations(mut self, max_concurrent_validations: usize) -> Self {
+        self.max_concurrent_validations = Some(max_concurrent_validations);
+        self
+    }
+
+    /// Lets entries that the instance holds for other agents expire after the given time.
+    /// Entries in the chain storage are kept regardless, which means nothing expires
+    /// if chain and DHT share their storage.
+    pub fn with_dht_cache_ttl(mut self, dht_cache_ttl: Duration) -> Self {
+        self.dht_cache_ttl = Some(dht_cache_ttl);
+        self
+    }
+
+    /// Lets network fetches that zome calls trigger (i.e. gets of entries not held locally)
+    /// be retried up to the given number of times if they fail with a transient network error.
+    pub fn with_network_fetch_retries(mut self, network_fetch_retries: usize) -> Self {
+        self.network_fetch_retries = network_fetch_retries;
+        self
+    }
+
+    /// Makes the instance publish entries that serialize to more than the given
+    /// number of bytes in several fragments, which receiving nodes reassemble.
+    pub fn with_network_max_message_size(mut self, network_max_message_size: usize) -> Self {
+        self.network_max_message_size = Some(network_max_message_size);
+        self
+    }
+
+    /// Makes the instance reject direct messages whose timestamp is off by more than the
+    /// given window, instead of the default of five minutes.
+    pub fn with_direct_message_replay_window(mut self, window: Duration) -> Self {
+        self.direct_message_replay_window = Some(window);
+        self
+    }
+
+    /// Caps the number of custom direct messages that wait for a response at the same time.
+    /// Messages sent beyond that either wait for a free slot or fail right away,
+    /// depending on `overflow`. At most DEFAULT_MAX_QUEUED_DIRECT_MESSAGES wait,
+    /// see with_max_queued_direct_messages().
+    pub fn with_max_in_flight_direct_messages(
+        mut self,
+        max_in_flight: usize,
+        overflow: DirectMessageOverflow,
+    ) -> Self {
+        self.direct_message_limit = Some(DirectMessageLimit {
+            max_in_flight,
+            overflow,
+            max_queued: DEFAULT_MAX_QUEUED_DIRECT_MESSAGES,
+        });
+        self
+    }
+
+    /// Sets how many direct messages may wait for a free slot before sending fails
+    /// right away, if they get queued.
+    /// Has to be called after with_max_in_flight_direct_messages() to have an effect.
+    pub fn with_max_queued_direct_messages(mut self, max_queued: usize) -> Self {
+        if let Some(ref mut limit) = self.direct_message_limit {
+            limit.max_queued = max_queued;
+        }
+        self
+    }
+
+    /// Makes the instance reject validation packages from other nodes that arrive in network
+    /// messages of more than the given number of bytes, before parsing them.
+    pub fn with_max_validation_package_size(mut self, max_size: usize) -> Self {
+        self.max_validation_package_size = Some(max_size);
+        self
+    }
+
+    /// Makes the instance append a record of every zome call it executes to the given audit log.
+    pub fn with_zome_call_audit_log(mut self, zome_call_audit_log: ZomeCallAuditLog) -> Self {
+        self.zome_call_audit_log = Some(zome_call_audit_log);
+        self
+    }
+
+    /// Makes the instance publish authored entries in batches, collecting all entries
+    /// committed within the given window after the first one. Each distinct entry of a
+    /// batch gets published once.
+    pub fn with_publish_batch_window(mut self, publish_batch_window: Duration) -> Self {
+        self.publish_batch_window = Some(publish_batch_window);
+        self
+    }
+
+    /// Lets results of zome functions that are declared as cacheable be reused for calls
+    /// with identical arguments. At most `max_entries` results are kept, each for at most `ttl`.
+    pub fn with_zome_call_cache(mut self, max_entries: usize, ttl: Duration) -> Self {
+        self.zome_call_cache = Some(ZomeCallCache::new(max_entries, ttl));
+        self
+    }
+
+    /// Keeps up to `max_modules` compiled wasm modules around so that calls
+    /// don't have to compile the zome's wasm every time.
+    pub fn with_wasm_module_cache(mut self, max_modules: usize) -> Self {
+        self.wasm_module_cache = Some(WasmModuleCache::new(max_modules));
+        self
+    }
+
+    /// Makes get_entry return at most `max_headers` headers per entry, the newest ones,
+    /// and flag the result as truncated if there are more.
+    pub fn with_max_headers_per_entry(mut self, max_headers: usize) -> Self {
+        self.max_headers_per_entry = Some(max_headers);
+        self
+    }
+
+    /// Lets repeated holds of the same entry with the same header reuse the outcome of
+    /// its validation. At most `max_entries` outcomes are kept, passed validations for at
+    /// most `ttl` and failed ones for at most `failure_ttl`.
+    pub fn with_validation_cache(
+        mut self,
+        max_entries: usize,
+        ttl: Duration,
+        failure_ttl: Duration,
+    ) -> Self {
+        self.validation_cache = Some(ValidationCache::new(max_entries, ttl, failure_ttl));
+        self
+    }
+
+    /// Keeps the links of up to `max_bases` recently queried bases indexed in memory
+    /// so that get_links does not have to query the meta storage every time.
+    pub fn with_link_index(mut self, max_bases: usize) -> Self {
+        self.link_index_size = Some(max_bases);
+        self
+    }
+
+    /// Records the fuel every zome call consumes, per zome function.
+    pub fn with_zome_call_profiler(mut self) -> Self {
+        self.zome_call_profiler = Some(ZomeCallProfiler::new());
+        self
+    }
+
+    /// Records the type of the last reduced action and how long each state slice took
+    /// to reduce it.
+    pub fn with_reduce_profiler(mut self) -> Self {
+        self.reduce_profiler = Some(ReduceProfiler::new());
+        self
+    }
+
     /// Actually creates the context.
     /// Defaults to memory storages, an in-memory network config and a fake agent called "alice".
     /// The logger gets set to SimpleLogger.
     /// The persister gets set to SimplePersister based on the chain storage.
+    /// Validations are not limited unless with_max_concurrent_validations() was called.
+    /// Held entries don't expire unless with_dht_cache_ttl() was called.
+    /// Failed network fetches are not retried unless with_network_fetch_retries() was called.
+    /// Direct messages in flight are not limited unless with_max_in_flight_direct_messages()
+    /// was called.
+    /// Validation packages are not limited unless with_max_validation_package_size() was called.
+    /// Zome calls are not audited unless with_zome_call_audit_log() was called.
+    /// Entries get published right after commit unless with_publish_batch_window() was called.
+    /// Zome call results are not cached unless with_zome_call_cache() was called.
+    /// Wasm gets compiled for every call unless with_wasm_module_cache() was called.
+    /// get_entry returns all headers unless with_max_headers_per_entry() was called.
+    /// Held entries always get validated unless with_validation_cache() was called.
+    /// Links are not indexed in memory unless with_link_index() was called.
+    /// Zome calls are not profiled unless with_zome_call_profiler() was called.
+    /// Reducers are not timed unless with_reduce_profiler() was called.
     pub fn spawn(self) -> Context {
         let chain_storage = self
             .chain_storage
@@ -127,7 +324,7 @@ impl ContextBuilder {
         let eav_storage = self
             .eav_storage
             .unwrap_or(Arc::new(RwLock::new(EavMemoryStorage::new())));
-        Context::new(
+        let mut context = Context::new(
             self.agent_id.unwrap_or(AgentId::generate_fake("alice")),
             self.logger.unwrap_or(Arc::new(Mutex::new(SimpleLogger {}))),
             Arc::new(Mutex::new(SimplePersister::new(chain_storage.clone()))),
@@ -138,7 +335,26 @@ impl ContextBuilder {
                 .unwrap_or(P2pConfig::new_with_unique_memory_backend()),
             self.conductor_api,
             self.signal_tx,
-        )
+        );
+        context.validation_limiter = ValidationLimiter::new(self.max_concurrent_validations);
+        context.dht_cache_ttl = self.dht_cache_ttl;
+        context.network_fetch_retries = self.network_fetch_retries;
+        context.network_max_message_size = self.network_max_message_size;
+        if let Some(window) = self.direct_message_replay_window {
+            context.direct_message_replay_window = window;
+        }
+        context.direct_message_limit = self.direct_message_limit;
+        context.max_validation_package_size = self.max_validation_package_size;
+        context.zome_call_audit_log = self.zome_call_audit_log;
+        context.publish_batcher = self.publish_batch_window.map(PublishBatcher::new);
+        context.zome_call_cache = self.zome_call_cache;
+        context.wasm_module_cache = self.wasm_module_cache;
+        context.max_headers_per_entry = self.max_headers_per_entry;
+        context.validation_cache = self.validation_cache;
+        context.link_index_size = self.link_index_size;
+        context.zome_call_profiler = self.zome_call_profiler;
+        context.reduce_profiler = self.reduce_profiler;
+        context
     }
 }
 
@@ -147,6 +363,12 @@ mod tests {
     use super::*;
     extern crate tempfile;
     use self::tempfile::tempdir;
+    use crate::config::StorageConfiguration;
+    use holochain_cas_implementations::{
+        backend::StorageBackendRegistry, cas::format::ContentFormat,
+    };
+    use holochain_core::{nucleus::audit::MemoryAuditSink, state::State};
+    use holochain_core_types::{cas::content::AddressableContent, entry::test_entry};
     use holochain_net::p2p_config::P2pBackendKind;
     use test_utils::mock_signing::mock_conductor_api;
 
@@ -180,6 +402,152 @@ mod tests {
         assert_eq!(context.p2p_config, net);
     }
 
+    /// Turns on one optional feature of the context
+    type Configure = fn(ContextBuilder) -> ContextBuilder;
+    /// Tells if the feature is turned on in the context, as configured by the Configure above
+    type IsConfigured = fn(&Context) -> bool;
+
+    fn spawn(builder: ContextBuilder) -> Context {
+        builder
+            .with_conductor_api(mock_conductor_api(AgentId::generate_fake("alice")))
+            .spawn()
+    }
+
+    fn assert_off_unless_configured(name: &str, configure: Configure, is_configured: IsConfigured) {
+        assert!(
+            !is_configured(&spawn(ContextBuilder::new())),
+            "{} is configured by default",
+            name
+        );
+        assert!(
+            is_configured(&spawn(configure(ContextBuilder::new()))),
+            "{} did not get configured",
+            name
+        );
+    }
+
+    #[test]
+    fn optional_features_are_off_unless_configured() {
+        assert_off_unless_configured(
+            "max_concurrent_validations",
+            |builder| builder.with_max_concurrent_validations(4),
+            |context| context.validation_limiter.max_running() == Some(4),
+        );
+        assert_off_unless_configured(
+            "dht_cache_ttl",
+            |builder| builder.with_dht_cache_ttl(Duration::from_secs(3600)),
+            |context| context.dht_cache_ttl == Some(Duration::from_secs(3600)),
+        );
+        assert_off_unless_configured(
+            "network_fetch_retries",
+            |builder| builder.with_network_fetch_retries(3),
+            |context| context.network_fetch_retries == 3,
+        );
+        assert_off_unless_configured(
+            "network_max_message_size",
+            |builder| builder.with_network_max_message_size(64 * 1024),
+            |context| context.network_max_message_size == Some(64 * 1024),
+        );
+        assert_off_unless_configured(
+            "max_validation_package_size",
+            |builder| builder.with_max_validation_package_size(1024 * 1024),
+            |context| context.max_validation_package_size == Some(1024 * 1024),
+        );
+        assert_off_unless_configured(
+            "direct_message_replay_window",
+            |builder| builder.with_direct_message_replay_window(Duration::from_secs(30)),
+            |context| context.direct_message_replay_window == Duration::from_secs(30),
+        );
+        assert_off_unless_configured(
+            "max_in_flight_direct_messages",
+            |builder| builder.with_max_in_flight_direct_messages(10, DirectMessageOverflow::Queue),
+            |context| {
+                context.direct_message_limit
+                    == Some(DirectMessageLimit {
+                        max_in_flight: 10,
+                        overflow: DirectMessageOverflow::Queue,
+                        max_queued: DEFAULT_MAX_QUEUED_DIRECT_MESSAGES,
+                    })
+            },
+        );
+        assert_off_unless_configured(
+            "max_queued_direct_messages",
+            |builder| {
+                builder
+                    .with_max_in_flight_direct_messages(10, DirectMessageOverflow::Queue)
+                    .with_max_queued_direct_messages(5)
+            },
+            |context| {
+                context
+                    .direct_message_limit
+                    .as_ref()
+                    .map(|limit| limit.max_queued)
+                    == Some(5)
+            },
+        );
+        assert_off_unless_configured(
+            "zome_call_audit_log",
+            |builder| {
+                builder.with_zome_call_audit_log(ZomeCallAuditLog::new(Arc::new(
+                    MemoryAuditSink::new(),
+                )))
+            },
+            |context| context.zome_call_audit_log.is_some(),
+        );
+        assert_off_unless_configured(
+            "publish_batch_window",
+            |builder| builder.with_publish_batch_window(Duration::from_millis(200)),
+            |context| {
+                context
+                    .publish_batcher
+                    .as_ref()
+                    .map(|batcher| batcher.window())
+                    == Some(Duration::from_millis(200))
+            },
+        );
+        assert_off_unless_configured(
+            "zome_call_cache",
+            |builder| builder.with_zome_call_cache(100, Duration::from_secs(30)),
+            |context| context.zome_call_cache.is_some(),
+        );
+        assert_off_unless_configured(
+            "wasm_module_cache",
+            |builder| builder.with_wasm_module_cache(20),
+            |context| context.wasm_module_cache.is_some(),
+        );
+        assert_off_unless_configured(
+            "max_headers_per_entry",
+            |builder| builder.with_max_headers_per_entry(50),
+            |context| context.max_headers_per_entry == Some(50),
+        );
+        assert_off_unless_configured(
+            "validation_cache",
+            |builder| {
+                builder.with_validation_cache(
+                    100,
+                    Duration::from_secs(600),
+                    Duration::from_secs(10),
+                )
+            },
+            |context| context.validation_cache.is_some(),
+        );
+        assert_off_unless_configured(
+            "link_index",
+            |builder| builder.with_link_index(1000),
+            |context| context.link_index_size == Some(1000),
+        );
+        assert_off_unless_configured(
+            "zome_call_profiler",
+            |builder| builder.with_zome_call_profiler(),
+            |context| context.zome_call_profiler.is_some(),
+        );
+        assert_off_unless_configured(
+            "reduce_profiler",
+            |builder| builder.with_reduce_profiler(),
+            |context| context.reduce_profiler.is_some(),
+        );
+    }
+
     #[test]
     fn smoke_tests() {
         let _ = ContextBuilder::new()
@@ -194,4 +562,42 @@ mod tests {
             .with_conductor_api(mock_conductor_api(AgentId::generate_fake("alice")))
             .spawn();
     }
+
+    #[test]
+    fn file_storage_backend_persists_across_contexts() {
+        let temp = tempdir().expect("test was supposed to create temp dir");
+        let storage_config = StorageConfiguration::File {
+            path: String::from(temp.path().to_str().expect("temp dir could not be string")),
+            format: ContentFormat::Json,
+        };
+        let registry = StorageBackendRegistry::new();
+        let spawn_context = || {
+            let backend = registry
+                .create(
+                    storage_config.backend_name(),
+                    storage_config.path().map(Path::new),
+                )
+                .expect("File backend should get created with tempdir");
+            ContextBuilder::new()
+                .with_storage_backend(backend)
+                .with_conductor_api(mock_conductor_api(AgentId::generate_fake("alice")))
+                .spawn()
+        };
+
+        let entry = test_entry();
+        let context = spawn_context();
+        context.dht_storage.write().unwrap().add(&entry).unwrap();
+        drop(context);
+
+        let state = State::new(Arc::new(spawn_context()));
+        assert_eq!(state.dht().stats().unwrap().entry_count, 1);
+        assert!(state
+            .agent()
+            .chain_store()
+            .content_storage()
+            .read()
+            .unwrap()
+            .contains(&entry.address())
+            .unwrap());
+    }
 }
@@ -3,31 +3,166 @@ use holochain_core_types::{
     agent::AgentId, cas::content::AddressableContent, error::HolochainError,
 };
 
+/// Payloads bigger than this (in bytes) don't get sent to the signing service
+/// unless configured otherwise with signing_service_max_payload_bytes.
+pub const DEFAULT_MAX_SIGNING_PAYLOAD_BYTES: usize = 1024 * 1024;
+
+/// Sends requests to the signing service.
+/// Implemented for reqwest::Client, so a client configured with proxies or custom
+/// TLS settings can be used. Tests can supply a mock instead.
+pub trait SigningServiceClient: Send + Sync {
+    /// POSTs the given body to the URL and returns the text of a successful response.
+    /// Has to return an IoError if the service could not be reached.
+    fn post(&self, url: reqwest::Url, body: String) -> Result<String, HolochainError>;
+}
+
+impl SigningServiceClient for reqwest::Client {
+    fn post(&self, url: reqwest::Url, body: String) -> Result<String, HolochainError> {
+        // failing to send means the service is unreachable, which is reported as IoError
+        let mut response = reqwest::Client::post(self, url)
+            .body(body)
+            .send()
+            .map_err(|e| {
+                HolochainError::IoError(format!("Error during signing request: {:?}", e))
+            })?;
+        response
+            .status()
+            .is_success()
+            .ok_or(HolochainError::new(&format!(
+                "Status of response from signing service is not success, but: {:?}",
+                response.status()
+            )))?;
+        response
+            .text()
+            .map_err(|_| HolochainError::new("Signing service response has no text"))
+    }
+}
+
+/// Asks the signing service at the given URI to sign the payload for the given agent,
+/// using a default reqwest client.
+/// Payloads bigger than DEFAULT_MAX_SIGNING_PAYLOAD_BYTES get rejected without
+/// contacting the service.
+/// Returns an IoError if the service could not be reached.
 pub fn request_signing_service(
     agent_id: &AgentId,
     payload: &String,
     signing_service_uri: &String,
 ) -> Result<String, HolochainError> {
+    request_signing_service_with_client(
+        &reqwest::Client::new(),
+        agent_id,
+        payload,
+        signing_service_uri,
+        DEFAULT_MAX_SIGNING_PAYLOAD_BYTES,
+    )
+}
+
+/// Like [request_signing_service](fn.request_signing_service.html), but sends the
+/// request with the given client and rejects payloads bigger than max_payload_bytes.
+pub fn request_signing_service_with_client(
+    client: &SigningServiceClient,
+    agent_id: &AgentId,
+    payload: &String,
+    signing_service_uri: &String,
+    max_payload_bytes: usize,
+) -> Result<String, HolochainError> {
+    if payload.len() > max_payload_bytes {
+        return Err(HolochainError::ErrorGeneric(format!(
+            "Payload of {} bytes exceeds the signing service limit of {} bytes",
+            payload.len(),
+            max_payload_bytes
+        )));
+    }
     let body_json = json!({"agent_id": agent_id.address(), "payload": payload});
     let body = serde_json::to_string(&body_json).unwrap();
-    let client = reqwest::Client::new();
     let url = reqwest::Url::parse(signing_service_uri).map_err(|_| {
         HolochainError::ConfigError(format!(
             "Can't parse signing service URI: '{}'",
             signing_service_uri
         ))
     })?;
-    let mut response = client.post(url).body(body).send().map_err(|e| {
-        HolochainError::ErrorGeneric(format!("Error during signing request: {:?}", e))
-    })?;
-    response
-        .status()
-        .is_success()
-        .ok_or(HolochainError::new(&format!(
-            "Status of response from signing service is not success, but: {:?}",
-            response.status()
-        )))?;
-    response
-        .text()
-        .map_err(|_| HolochainError::new("Signing service response has no text"))
+    client.post(url, body)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    // nothing listens on port 1, so every request that gets sent fails with an IoError
+    fn unreachable_uri() -> String {
+        String::from("http://127.0.0.1:1/")
+    }
+
+    /// Records the requests it gets and answers them with a fixed signature.
+    struct MockClient {
+        requests: std::sync::Mutex<Vec<(String, String)>>,
+    }
+
+    impl SigningServiceClient for MockClient {
+        fn post(&self, url: reqwest::Url, body: String) -> Result<String, HolochainError> {
+            self.requests.lock().unwrap().push((url.to_string(), body));
+            Ok(String::from("fake-signature"))
+        }
+    }
+
+    #[test]
+    fn request_gets_sent_with_the_given_client() {
+        let agent_id = AgentId::generate_fake("holo_tester1");
+        let client = MockClient {
+            requests: std::sync::Mutex::new(Vec::new()),
+        };
+        let signature = request_signing_service_with_client(
+            &client,
+            &agent_id,
+            &String::from("some payload"),
+            &String::from("http://signing.service/sign"),
+            DEFAULT_MAX_SIGNING_PAYLOAD_BYTES,
+        );
+        assert_eq!(signature, Ok(String::from("fake-signature")));
+
+        let requests = client.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        let (ref url, ref body) = requests[0];
+        assert_eq!(url, "http://signing.service/sign");
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(body).unwrap(),
+            json!({"agent_id": agent_id.address(), "payload": "some payload"})
+        );
+    }
+
+    #[test]
+    fn over_limit_payload_gets_rejected_before_sending() {
+        let agent_id = AgentId::generate_fake("holo_tester1");
+        let payload = String::from("0123456789a");
+        let client = reqwest::Client::new();
+        match request_signing_service_with_client(
+            &client,
+            &agent_id,
+            &payload,
+            &unreachable_uri(),
+            10,
+        ) {
+            Err(HolochainError::ErrorGeneric(message)) => {
+                assert!(message.contains("exceeds the signing service limit of 10 bytes"))
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn under_limit_payload_gets_sent() {
+        let agent_id = AgentId::generate_fake("holo_tester1");
+        let payload = String::from("0123456789");
+        let client = reqwest::Client::new();
+        match request_signing_service_with_client(
+            &client,
+            &agent_id,
+            &payload,
+            &unreachable_uri(),
+            10,
+        ) {
+            Err(HolochainError::IoError(_)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
 }
@@ -85,20 +85,33 @@ use crate::error::{HolochainInstanceError, HolochainResult};
 use holochain_core::{
     context::Context,
     instance::Instance,
+    network::actions::shutdown_network::shutdown_network,
     nucleus::{
+        actions::build_validation_package::build_validation_package_for_address,
         call_zome_function,
         ribosome::{run_dna, WasmCallData},
         ZomeFnCall,
     },
     persister::{Persister, SimplePersister},
     state::State,
+    workflows::{
+        capability_grant::{mint_capability, revoke_capability},
+        validate_chain::validate_chain_against_dna,
+    },
 };
 use holochain_core_types::{
+    cas::content::Address,
     dna::{capabilities::CapabilityCall, Dna},
+    entry::cap_entries::CapTokenGrant,
     error::HolochainError,
     json::JsonString,
+    validation::ValidationPackage,
 };
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
+
+/// How long shutdown() waits for background work of the instance, like holding
+/// entries received from the network, before it leaves the network anyway.
+pub const BACKGROUND_TASKS_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// contains a Holochain application instance
 pub struct Holochain {
@@ -178,14 +191,36 @@ impl Holochain {
     }
 
     /// deactivate the Holochain instance
+    /// Publishes entries that are still waiting for their publish batch.
     pub fn stop(&mut self) -> Result<(), HolochainInstanceError> {
         if !self.active {
             return Err(HolochainInstanceError::InstanceNotActiveYet);
         }
+        if let Some(ref publish_batcher) = self.context.publish_batcher {
+            publish_batcher.flush(&self.context);
+        }
         self.active = false;
         Ok(())
     }
 
+    /// Stops the instance if it is active, waits for its background tasks
+    /// and leaves its network.
+    /// Returns once the network connection is down. The instance can't
+    /// reach the network anymore afterwards.
+    pub fn shutdown(&mut self) -> Result<(), HolochainInstanceError> {
+        if self.active {
+            self.stop()?;
+        }
+        let context = self.context();
+        if context
+            .join_all_background(BACKGROUND_TASKS_SHUTDOWN_TIMEOUT)
+            .is_err()
+        {
+            context.log("warn/conductor: Shutting down with background tasks still running");
+        }
+        Ok(context.block_on(shutdown_network(context))?)
+    }
+
     /// call a function in a zome
     pub fn call(
         &mut self,
@@ -202,6 +237,45 @@ impl Holochain {
         Ok(context.block_on(call_zome_function(zome_call, context))?)
     }
 
+    /// (re-)build the validation package for the entry with the given address.
+    /// Does not author or publish anything, meant for debugging validation.
+    pub fn validation_package(
+        &self,
+        entry_address: &Address,
+    ) -> HolochainResult<ValidationPackage> {
+        let context = self.context();
+        Ok(context.block_on(build_validation_package_for_address(
+            entry_address,
+            context.clone(),
+        ))?)
+    }
+
+    /// commit a capability grant to the instance's source chain and return its token
+    pub fn mint_capability(&self, grant: CapTokenGrant) -> HolochainResult<Address> {
+        let context = self.context();
+        Ok(context.block_on(mint_capability(grant, context))?)
+    }
+
+    /// revoke the capability grant with the given token,
+    /// zome calls presenting that token get rejected afterwards
+    pub fn revoke_capability(&self, token: &Address) -> HolochainResult<()> {
+        let context = self.context();
+        Ok(context.block_on(revoke_capability(token, context))?)
+    }
+
+    /// validates all app entries of the source chain against the current DNA again
+    /// and returns those that don't validate anymore
+    pub fn validate_chain_against_dna(&self) -> HolochainResult<Vec<(Address, HolochainError)>> {
+        let context = self.context();
+        Ok(context.block_on(validate_chain_against_dna(context))?)
+    }
+
+    /// hand the data this instance published to the network right away
+    /// instead of waiting for the next publish round
+    pub fn trigger_publish(&self) -> HolochainResult<()> {
+        Ok(self.state()?.network().trigger_publish()?)
+    }
+
     /// checks to see if an instance is active
     pub fn active(&self) -> bool {
         self.active
@@ -229,7 +303,7 @@ mod tests {
         logger::{test_logger, TestLogger},
         signal::{signal_channel, SignalReceiver},
     };
-    use holochain_core_types::{cas::content::Address, dna::Dna, json::RawString};
+    use holochain_core_types::{dna::Dna, json::RawString};
     use holochain_wasm_utils::wasm_target_dir;
     use std::sync::{Arc, Mutex};
     use test_utils::{
@@ -406,6 +480,27 @@ mod tests {
         assert!(!hc.active());
     }
 
+    #[test]
+    fn can_shutdown() {
+        let dna = Dna::new();
+        let (context, _, _) = test_context("bob");
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        assert_eq!(
+            hc.context().state().unwrap().network().initialized(),
+            Ok(())
+        );
+        hc.start().unwrap();
+
+        assert_eq!(hc.shutdown(), Ok(()));
+        assert!(!hc.active());
+        assert_eq!(
+            hc.context().state().unwrap().network().initialized(),
+            Err(HolochainError::NetworkNotInitialized)
+        );
+        // nothing left to shut down
+        assert_eq!(hc.shutdown(), Ok(()));
+    }
+
     #[test]
     fn can_call() {
         let wat = r#"
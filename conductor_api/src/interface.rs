@@ -1,26 +1,42 @@
-use crate::holo_signing_service::request_signing_service;
+use crate::holo_signing_service::{
+    request_signing_service_with_client, SigningServiceClient, DEFAULT_MAX_SIGNING_PAYLOAD_BYTES,
+};
 use base64;
-use holochain_core::state::State;
+use error::HolochainInstanceError;
+use holochain_core::{
+    background_tasks::{BackgroundTasks, TaskKind, TrackedTask},
+    context::Context,
+    logger::Logger,
+    state::State,
+};
 use holochain_core_types::{
-    agent::AgentId, cas::content::Address, dna::capabilities::CapabilityCall,
+    agent::AgentId,
+    cas::content::Address,
+    dna::capabilities::{CapabilityCall, CapabilityType},
+    entry::cap_entries::{CapTokenGrant, GrantedFunction},
+    error::{DnaError, HolochainError, RibosomeErrorCode},
 };
 use holochain_dpki::key_bundle::KeyBundle;
 use holochain_sodium::secbuf::SecBuf;
+use metrics::{render_metrics, InstanceStatsCache};
+use request_log::{RequestLog, RequestTarget};
 use Holochain;
 
-use jsonrpc_ws_server::jsonrpc_core::{self, types::params::Params, IoHandler, Value};
+use jsonrpc_ws_server::jsonrpc_core::{self, types::params::Params, ErrorCode, IoHandler, Value};
 use serde_json;
 use std::{
     collections::HashMap,
     convert::TryFrom,
     path::PathBuf,
-    sync::{mpsc::Receiver, Arc, Mutex, RwLock},
+    sync::{mpsc::Receiver, Arc, Condvar, Mutex, RwLock},
+    time::{Duration, Instant},
 };
 
-use conductor::{ConductorAdmin, ConductorUiAdmin, CONDUCTOR};
+use conductor::{admin::ProgressCallback, ConductorAdmin, ConductorUiAdmin, CONDUCTOR};
 use config::{
     AgentConfiguration, Bridge, DnaConfiguration, InstanceConfiguration, InterfaceConfiguration,
-    InterfaceDriver, UiBundleConfiguration, UiInterfaceConfiguration,
+    InterfaceDriver, InterfaceRoutes, UiBundleConfiguration, UiInterfaceConfiguration,
+    ZomeFunctionReference,
 };
 use serde_json::map::Map;
 
@@ -31,6 +47,241 @@ pub trait DispatchRpc {
     fn handler(self) -> IoHandler;
 }
 
+/// JSON-RPC error code returned for zome calls rejected by a ZomeCallLimiter
+pub const SERVER_BUSY_ERROR_CODE: i64 = -32001;
+
+/// JSON-RPC error code returned for zome calls coming in while the interface shuts down
+pub const SHUTTING_DOWN_ERROR_CODE: i64 = -32007;
+
+/// JSON-RPC error codes returned by the `call` method so clients can tell
+/// failed zome calls apart, see [zome_call_error](fn.zome_call_error.html)
+pub const ZOME_NOT_FOUND_ERROR_CODE: i64 = -32002;
+pub const FUNCTION_NOT_FOUND_ERROR_CODE: i64 = -32003;
+pub const DESERIALIZATION_FAILED_ERROR_CODE: i64 = -32004;
+pub const VALIDATION_FAILED_ERROR_CODE: i64 = -32005;
+
+/// JSON-RPC error code returned for calls to zome functions that are not in the
+/// allow-list of the interface, see [ConductorApiBuilder::with_allowed_functions],
+/// and for calls whose capability token does not grant access to the function
+pub const PERMISSION_DENIED_ERROR_CODE: i64 = -32006;
+
+/// JSON-RPC error code returned for zome calls whose arguments are missing inputs
+/// or don't match the input types declared for the function in the DNA
+pub const INVALID_ARGUMENTS_ERROR_CODE: i64 = -32008;
+
+/// Token presented by zome calls that come without a `cap_token`.
+/// It grants nothing, so such calls can only reach public functions.
+const NO_CAP_TOKEN: &str = "fake_token";
+
+/// The capability presented with a zome call request through the optional params
+/// `cap_token`, a token returned by `admin/capability/mint`, and `caller`, the address
+/// of the agent presenting it, which transferable and assigned grants require.
+/// Both get removed from the given params.
+fn take_capability_call(
+    params_map: &mut Map<String, Value>,
+) -> Result<CapabilityCall, jsonrpc_core::Error> {
+    let mut take_string = |key: &str| match params_map.remove(key) {
+        None => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(_) => Err(jsonrpc_core::Error::invalid_params(format!(
+            "`{}` is not a valid json string",
+            key
+        ))),
+    };
+    let cap_token = take_string("cap_token")?.unwrap_or_else(|| String::from(NO_CAP_TOKEN));
+    let caller = take_string("caller")?.map(Address::from);
+    Ok(CapabilityCall::new(Address::from(cap_token), caller))
+}
+
+fn invalid_arguments_error(zome: &str, detail: String) -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        code: ErrorCode::ServerError(INVALID_ARGUMENTS_ERROR_CODE),
+        message: format!("Invalid arguments for zome '{}': {}", zome, detail),
+        data: None,
+    }
+}
+
+fn permission_denied_error(zome: &str, function: &str) -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        code: ErrorCode::ServerError(PERMISSION_DENIED_ERROR_CODE),
+        message: format!(
+            "Permission denied: {}/{} can't be called over this interface",
+            zome, function
+        ),
+        data: None,
+    }
+}
+
+/// Maps the error of a failed zome call to a JSON-RPC error.
+/// Unknown zomes and functions, arguments the zome function could not deserialize,
+/// failed validations and failed capability checks get their own error codes.
+/// All other errors are reported as internal errors.
+pub fn zome_call_error(error: HolochainInstanceError) -> jsonrpc_core::Error {
+    let code = match error {
+        HolochainInstanceError::InternalFailure(ref hc_error) => match hc_error {
+            HolochainError::Dna(DnaError::ZomeNotFound(_)) => Some(ZOME_NOT_FOUND_ERROR_CODE),
+            HolochainError::Dna(DnaError::ZomeFunctionNotFound(_)) => {
+                Some(FUNCTION_NOT_FOUND_ERROR_CODE)
+            }
+            HolochainError::SerializationError(_)
+            | HolochainError::Ribosome(RibosomeErrorCode::ArgumentDeserializationFailed) => {
+                Some(DESERIALIZATION_FAILED_ERROR_CODE)
+            }
+            HolochainError::ValidationFailed(_) => Some(VALIDATION_FAILED_ERROR_CODE),
+            HolochainError::CapabilityCheckFailed => Some(PERMISSION_DENIED_ERROR_CODE),
+            _ => None,
+        },
+        _ => None,
+    };
+    match code {
+        Some(code) => jsonrpc_core::Error {
+            code: ErrorCode::ServerError(code),
+            message: error.to_string(),
+            data: None,
+        },
+        None => {
+            let mut internal_error = jsonrpc_core::Error::internal_error();
+            internal_error.message = error.to_string();
+            internal_error
+        }
+    }
+}
+
+#[derive(Default)]
+struct ZomeCallLimiterState {
+    running: usize,
+    queued: usize,
+}
+
+/// Bounds the number of zome calls that get executed concurrently.
+/// Calls exceeding `max_running` wait until a running call finishes, as long as
+/// there are less than `max_queued` calls waiting already. If the queue is full
+/// too, acquire() fails immediately so the interface can report that it is busy.
+#[derive(Clone)]
+pub struct ZomeCallLimiter {
+    state: Arc<(Mutex<ZomeCallLimiterState>, Condvar)>,
+    max_running: usize,
+    max_queued: usize,
+}
+
+/// Held while a zome call is running. Frees the slot for the next call on drop.
+pub struct ZomeCallPermit {
+    limiter: ZomeCallLimiter,
+}
+
+impl ZomeCallLimiter {
+    pub fn new(max_running: usize, max_queued: usize) -> Self {
+        ZomeCallLimiter {
+            state: Arc::new((Mutex::new(ZomeCallLimiterState::default()), Condvar::new())),
+            max_running: max_running.max(1),
+            max_queued,
+        }
+    }
+
+    /// Blocks until a slot is free and returns a permit for it,
+    /// or returns an error right away if too many calls are queued already.
+    pub fn acquire(&self) -> Result<ZomeCallPermit, jsonrpc_core::Error> {
+        let (ref lock, ref condvar) = *self.state;
+        let mut state = lock.lock().unwrap();
+        if state.running >= self.max_running {
+            if state.queued >= self.max_queued {
+                return Err(jsonrpc_core::Error {
+                    code: ErrorCode::ServerError(SERVER_BUSY_ERROR_CODE),
+                    message: String::from("Server busy: too many zome calls pending"),
+                    data: None,
+                });
+            }
+            state.queued += 1;
+            while state.running >= self.max_running {
+                state = condvar.wait(state).unwrap();
+            }
+            state.queued -= 1;
+        }
+        state.running += 1;
+        Ok(ZomeCallPermit {
+            limiter: self.clone(),
+        })
+    }
+
+    /// Number of zome calls currently holding a permit
+    pub fn running(&self) -> usize {
+        self.state.0.lock().unwrap().running
+    }
+
+    /// Number of zome calls currently waiting for a permit
+    pub fn queued(&self) -> usize {
+        self.state.0.lock().unwrap().queued
+    }
+}
+
+impl Drop for ZomeCallPermit {
+    fn drop(&mut self) {
+        let (ref lock, ref condvar) = *self.limiter.state;
+        lock.lock().unwrap().running -= 1;
+        condvar.notify_one();
+    }
+}
+
+#[derive(Default)]
+struct ZomeCallTrackerState {
+    total: u64,
+    closed: bool,
+}
+
+/// Keeps track of the zome calls an interface is executing, so that stopping the
+/// interface can give them time to finish, see close_and_wait().
+/// The calls are registered as BackgroundTasks of their own.
+#[derive(Clone, Default)]
+pub struct ZomeCallTracker {
+    calls: BackgroundTasks,
+    state: Arc<Mutex<ZomeCallTrackerState>>,
+}
+
+/// Held while a zome call is running. Marks the call as finished on drop.
+pub struct TrackedZomeCall {
+    _task: TrackedTask,
+}
+
+impl ZomeCallTracker {
+    pub fn new() -> Self {
+        ZomeCallTracker::default()
+    }
+
+    /// Registers a zome call that is about to start.
+    /// Fails once close_and_wait() got called.
+    pub fn start(&self) -> Result<TrackedZomeCall, jsonrpc_core::Error> {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return Err(jsonrpc_core::Error {
+                code: ErrorCode::ServerError(SHUTTING_DOWN_ERROR_CODE),
+                message: String::from("Interface is shutting down"),
+                data: None,
+            });
+        }
+        state.total += 1;
+        Ok(TrackedZomeCall {
+            _task: self.calls.track(TaskKind::ZomeCall),
+        })
+    }
+
+    /// Number of zome calls currently running.
+    pub fn running(&self) -> usize {
+        self.calls.running()
+    }
+
+    /// Number of zome calls started since the tracker got created.
+    pub fn total(&self) -> u64 {
+        self.state.lock().unwrap().total
+    }
+
+    /// Rejects all further zome calls and blocks until the running ones have finished
+    /// or the grace period has passed. Returns false in the latter case.
+    pub fn close_and_wait(&self, grace_period: Duration) -> bool {
+        self.state.lock().unwrap().closed = true;
+        self.calls.join_all(grace_period).is_ok()
+    }
+}
+
 macro_rules! conductor_call {
     ( |$conductor:ident| $call_expr:expr ) => {
         match * CONDUCTOR.lock().unwrap() {
@@ -66,6 +317,7 @@ macro_rules! conductor_call {
 ///
 /// Examples for method names are:
 /// {instance_id}/{zome}/{cap}/{func} -> a zome call
+/// call                              -> a zome call with instance, zome and function as params
 /// info/list_instances               -> Map of InstanceConfigs, keyed by ID
 /// admin/...                         -> see [with_admin_dna_functions]
 ///
@@ -78,6 +330,14 @@ pub struct ConductorApiBuilder {
     instances: InstanceMap,
     instance_configs: HashMap<String, InstanceConfiguration>,
     io: Box<IoHandler>,
+    zome_call_limiter: Option<ZomeCallLimiter>,
+    zome_call_tracker: ZomeCallTracker,
+    allowed_functions: Arc<Vec<ZomeFunctionReference>>,
+    request_log: Option<RequestLog>,
+    signing_fallback: Option<(Arc<Mutex<KeyBundle>>, Arc<Mutex<Logger>>)>,
+    max_signing_payload_bytes: usize,
+    /// Only built once an outsourced signing callback needs it, if none got set
+    signing_service_client: Option<Arc<SigningServiceClient>>,
 }
 
 impl ConductorApiBuilder {
@@ -86,15 +346,244 @@ impl ConductorApiBuilder {
             instances: HashMap::new(),
             instance_configs: HashMap::new(),
             io: Box::new(IoHandler::new()),
+            zome_call_limiter: None,
+            zome_call_tracker: ZomeCallTracker::new(),
+            allowed_functions: Arc::new(Vec::new()),
+            request_log: None,
+            signing_fallback: None,
+            max_signing_payload_bytes: DEFAULT_MAX_SIGNING_PAYLOAD_BYTES,
+            signing_service_client: None,
+        }
+    }
+
+    /// Routes all zome calls of instances added after this call through a
+    /// [ZomeCallLimiter](struct.ZomeCallLimiter.html) with the given bounds.
+    /// Has to be called before with_instances() / with_named_instance() to have an effect.
+    pub fn with_zome_call_limit(mut self, max_threads: usize, max_queued: usize) -> Self {
+        self.zome_call_limiter = Some(ZomeCallLimiter::new(max_threads, max_queued));
+        self
+    }
+
+    /// Counts the zome calls of instances added after this call in the given tracker,
+    /// so the owner of the interface can wait for them when shutting it down.
+    /// Has to be called before with_instances() / with_named_instance() to have an effect.
+    pub fn with_zome_call_tracker(mut self, zome_call_tracker: ZomeCallTracker) -> Self {
+        self.zome_call_tracker = zome_call_tracker;
+        self
+    }
+
+    /// Only lets zome functions in the given list be called, all others get rejected
+    /// with PERMISSION_DENIED_ERROR_CODE before reaching the instance.
+    /// An empty list allows all functions.
+    /// Has to be called before with_instances() / with_named_instance() to have an effect
+    /// on the methods named after instances.
+    pub fn with_allowed_functions(mut self, allowed_functions: Vec<ZomeFunctionReference>) -> Self {
+        self.allowed_functions = Arc::new(allowed_functions);
+        self
+    }
+
+    /// Records every zome call request in the given log, with how long it took and
+    /// whether it failed, also if it got rejected before reaching the instance.
+    /// Has to be called before with_instances() / with_named_instance() to have an effect
+    /// on the methods named after instances.
+    pub fn with_request_log(mut self, request_log: RequestLog) -> Self {
+        self.request_log = Some(request_log);
+        self
+    }
+
+    /// Hands the outcome of a request that was started at the given time to the log.
+    fn log_request(
+        request_log: &Option<RequestLog>,
+        method: &str,
+        target: RequestTarget,
+        started: Instant,
+        result: &Result<Value, jsonrpc_core::Error>,
+    ) {
+        if let Some(ref request_log) = request_log {
+            if let Err(error) = request_log.record(method, target, started.elapsed(), result) {
+                request_log.log_error(error);
+            }
+        }
+    }
+
+    fn is_allowed(allowed_functions: &[ZomeFunctionReference], zome: &str, function: &str) -> bool {
+        allowed_functions.is_empty()
+            || allowed_functions
+                .iter()
+                .any(|allowed| allowed.zome == zome && allowed.function == function)
+    }
+
+    /// Lets the outsourced signing callback sign with the given local key instead
+    /// if the signing service can't be reached. Every fallback gets logged to the given logger.
+    /// Has to be called before with_outsource_signing_callback() to have an effect.
+    /// Fails if the key is not the one of the given agent, since its signatures would
+    /// not verify against the agent's public address.
+    pub fn with_local_signing_fallback(
+        mut self,
+        keybundle: Arc<Mutex<KeyBundle>>,
+        agent_id: &AgentId,
+        logger: Arc<Mutex<Logger>>,
+    ) -> Result<Self, HolochainError> {
+        let key_id = keybundle.lock().unwrap().get_id();
+        if key_id != agent_id.pub_sign_key {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Local signing fallback key {} does not match the public address {} of agent {}",
+                key_id, agent_id.pub_sign_key, agent_id.nick
+            )));
         }
+        self.signing_fallback = Some((keybundle, logger));
+        Ok(self)
+    }
+
+    /// Rejects signing requests for payloads bigger than the given number of bytes
+    /// instead of sending them to the signing service.
+    /// Has to be called before with_outsource_signing_callback() to have an effect.
+    pub fn with_max_signing_payload_bytes(mut self, max_signing_payload_bytes: usize) -> Self {
+        self.max_signing_payload_bytes = max_signing_payload_bytes;
+        self
+    }
+
+    /// Sends signing requests with the given client instead of a default reqwest client,
+    /// e.g. one configured with a proxy or custom TLS settings.
+    /// Has to be called before with_outsource_signing_callback() to have an effect.
+    pub fn with_signing_service_client(
+        mut self,
+        signing_service_client: Arc<SigningServiceClient>,
+    ) -> Self {
+        self.signing_service_client = Some(signing_service_client);
+        self
     }
 
     /// Finish the building and retrieve the populated handler
     pub fn spawn(mut self) -> IoHandler {
         self.setup_info_api();
+        self.setup_call_api();
+        self.setup_metrics_api();
         *self.io
     }
 
+    /// Adds a "call" method that calls the zome function given by the params
+    /// `instance_id`, `zome` and `function` with the optional `params` as arguments.
+    /// Functions that are not public need the optional `cap_token` and `caller` params,
+    /// see [take_capability_call](fn.take_capability_call.html).
+    /// Arguments that don't match the inputs declared in the DNA are rejected with
+    /// INVALID_ARGUMENTS_ERROR_CODE before the zome gets called.
+    /// Failures are reported with the error codes of [zome_call_error](fn.zome_call_error.html).
+    fn setup_call_api(&mut self) {
+        let instances = self.instances.clone();
+        let zome_call_limiter = self.zome_call_limiter.clone();
+        let zome_call_tracker = self.zome_call_tracker.clone();
+        let allowed_functions = self.allowed_functions.clone();
+        let request_log = self.request_log.clone();
+
+        let call = move |params: Params| -> Result<Value, jsonrpc_core::Error> {
+            let mut params_map = Self::unwrap_params_map(params)?;
+            let instance_id = Self::get_as_string("instance_id", &params_map)?;
+            let zome = Self::get_as_string("zome", &params_map)?;
+            let function = Self::get_as_string("function", &params_map)?;
+            let cap = take_capability_call(&mut params_map)?;
+            if !Self::is_allowed(&allowed_functions, &zome, &function) {
+                return Err(permission_denied_error(&zome, &function));
+            }
+            let args = params_map
+                .get("params")
+                .cloned()
+                .unwrap_or(Value::Object(Map::new()));
+
+            let hc_lock = instances.get(&instance_id).ok_or_else(|| {
+                jsonrpc_core::Error::invalid_params(format!("unknown instance `{}`", instance_id))
+            })?;
+
+            // Report unknown zomes and functions before waiting for a slot
+            {
+                let hc = hc_lock.read().unwrap();
+                let dna = hc
+                    .state()
+                    .map_err(zome_call_error)?
+                    .nucleus()
+                    .dna()
+                    .ok_or(HolochainInstanceError::from(HolochainError::DnaMissing))
+                    .map_err(zome_call_error)?;
+                dna.get_function_with_zome_name(&zome, &function)
+                    .map_err(|e| zome_call_error(HolochainError::Dna(e).into()))?
+                    .validate_arguments(&args)
+                    .map_err(|detail| invalid_arguments_error(&zome, detail))?;
+            }
+
+            let _permit = match zome_call_limiter {
+                Some(ref limiter) => Some(limiter.acquire()?),
+                None => None,
+            };
+            let _tracked = zome_call_tracker.start()?;
+            let args_string = serde_json::to_string(&args).map_err(|e| {
+                zome_call_error(HolochainError::SerializationError(e.to_string()).into())
+            })?;
+            let response = hc_lock
+                .write()
+                .unwrap()
+                .call(&zome, Some(cap), &function, &args_string)
+                .map_err(zome_call_error)?;
+            Ok(Value::String(response.to_string()))
+        };
+        self.io.add_method("call", move |params| {
+            let started = Instant::now();
+            let target = match request_log {
+                Some(_) => Self::call_target(&params),
+                None => RequestTarget::default(),
+            };
+            let result = call(params);
+            Self::log_request(&request_log, "call", target, started, &result);
+            result
+        });
+    }
+
+    /// Instance, zome and function named in the params of a "call" request.
+    fn call_target(params: &Params) -> RequestTarget {
+        let param = |key: &str| match params {
+            Params::Map(map) => map.get(key).and_then(Value::as_str).map(String::from),
+            _ => None,
+        };
+        RequestTarget {
+            instance: param("instance_id"),
+            zome: param("zome"),
+            function: param("function"),
+        }
+    }
+
+    /// Adds a "metrics" method that returns the counters and gauges of this interface
+    /// and its instances in the Prometheus text format, see [metrics](../metrics/index.html).
+    /// It only reads, so it is not subject to allowed functions or zome call limits.
+    /// The contexts of the instances are looked up on every request, so that restarted
+    /// instances are reported with their current state. A scrape never waits for an instance
+    /// that is locked, e.g. by a running zome call, but reports the context it last saw
+    /// for it instead, or leaves the instance out until it saw one.
+    fn setup_metrics_api(&mut self) {
+        let instances = self.instances.clone();
+        let zome_call_limiter = self.zome_call_limiter.clone();
+        let zome_call_tracker = self.zome_call_tracker.clone();
+        let stats_cache = InstanceStatsCache::default();
+        let last_contexts: Mutex<HashMap<String, Arc<Context>>> = Mutex::new(HashMap::new());
+
+        self.io.add_method("metrics", move |_| {
+            let mut last_contexts = last_contexts.lock().unwrap();
+            for (id, hc_lock) in instances.iter() {
+                if let Ok(hc) = hc_lock.try_read() {
+                    last_contexts.insert(id.clone(), hc.context().clone());
+                }
+            }
+            let contexts: Vec<(String, Arc<Context>)> = last_contexts
+                .iter()
+                .map(|(id, context)| (id.clone(), context.clone()))
+                .collect();
+            Ok(Value::String(render_metrics(
+                &contexts,
+                zome_call_limiter.as_ref(),
+                &zome_call_tracker,
+                &stats_cache,
+            )))
+        });
+    }
+
     /// Adds a "info/instances" method that returns a JSON object describing all registered
     /// instances we have a config for.
     fn setup_info_api(&mut self) {
@@ -161,23 +650,70 @@ impl ConductorApiBuilder {
             Some(dna) => {
                 for (zome_name, zome) in dna.zomes {
                     for fn_decl in zome.fn_declarations {
-                        let func_name = String::from(fn_decl.name);
+                        let func_name = fn_decl.name.clone();
                         let zome_name = zome_name.clone();
                         let method_name = format!("{}/{}/{}", instance_name, zome_name, func_name);
                         let hc_lock_inner = hc_lock.clone();
-                        self.io.add_method(&method_name, move |params| {
+                        let zome_call_limiter = self.zome_call_limiter.clone();
+                        let zome_call_tracker = self.zome_call_tracker.clone();
+                        let allowed =
+                            Self::is_allowed(&self.allowed_functions, &zome_name, &func_name);
+                        let request_log = self.request_log.clone();
+                        let target = RequestTarget {
+                            instance: Some(instance_name.clone()),
+                            zome: Some(zome_name.clone()),
+                            function: Some(func_name.clone()),
+                        };
+                        let logged_method_name = method_name.clone();
+                        let call = move |params: Params| -> Result<Value, jsonrpc_core::Error> {
+                            let (params, cap) = match params {
+                                Params::Map(mut params_map) => {
+                                    let cap = take_capability_call(&mut params_map)?;
+                                    (Params::Map(params_map), cap)
+                                }
+                                params => (
+                                    params,
+                                    CapabilityCall::new(Address::from(NO_CAP_TOKEN), None),
+                                ),
+                            };
+                            if !allowed {
+                                return Err(permission_denied_error(&zome_name, &func_name));
+                            }
+                            let args = serde_json::to_value(&params).map_err(|e| {
+                                zome_call_error(
+                                    HolochainError::SerializationError(e.to_string()).into(),
+                                )
+                            })?;
+                            fn_decl
+                                .validate_arguments(&args)
+                                .map_err(|detail| invalid_arguments_error(&zome_name, detail))?;
+                            let _permit = match zome_call_limiter {
+                                Some(ref limiter) => Some(limiter.acquire()?),
+                                None => None,
+                            };
+                            let _tracked = zome_call_tracker.start()?;
                             let mut hc = hc_lock_inner.write().unwrap();
-                            let params_string = serde_json::to_string(&params)
-                                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
-                            let response = hc
-                                .call(
-                                    &zome_name,
-                                    Some(CapabilityCall::new(Address::from("fake_token"), None)),
-                                    &func_name,
-                                    &params_string,
+                            let params_string = serde_json::to_string(&params).map_err(|e| {
+                                zome_call_error(
+                                    HolochainError::SerializationError(e.to_string()).into(),
                                 )
-                                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+                            })?;
+                            let response = hc
+                                .call(&zome_name, Some(cap), &func_name, &params_string)
+                                .map_err(zome_call_error)?;
                             Ok(Value::String(response.to_string()))
+                        };
+                        self.io.add_method(&method_name, move |params| {
+                            let started = Instant::now();
+                            let result = call(params);
+                            Self::log_request(
+                                &request_log,
+                                &logged_method_name,
+                                target.clone(),
+                                started,
+                                &result,
+                            );
+                            result
                         })
                     }
                 }
@@ -263,6 +799,8 @@ impl ConductorApiBuilder {
     ///     Params:
     ///     * `id`: [string] internal handle/name of the newly created DNA config
     ///     * `path`: [string] local file path to DNA file
+    ///     Returns the steps the installation went through as `progress`, an array of
+    ///     `{"step": ..., "total": ..., "message": ...}` objects in order.
     ///
     ///  * `admin/dna/uninstall`
     ///     Uninstalls a DNA from the conductor config. Recursively also removes (and stops)
@@ -297,6 +835,13 @@ impl ConductorApiBuilder {
     ///     Params:
     ///     * `id`: [string] Which instance to stop?
     ///
+    ///  * `admin/instance/trigger_publish`
+    ///     Makes a running instance hand the data it published to the network right away
+    ///     instead of waiting for the next publish round (see `publish_interval_ms` of the
+    ///     network config). Does nothing if no publish interval is configured.
+    ///     Params:
+    ///     * `id`: [string] Which instance should publish?
+    ///
     ///  * `admin/instance/list`
     ///     Returns an array of all instances that are configured.
     ///
@@ -313,6 +858,9 @@ impl ConductorApiBuilder {
     ///     * `admin`: [bool] Grant access to (these) admin functions?
     ///     * `type`: [string] Either "websocket" or "http"
     ///     * `port`:  [number] Port to bind the server to.
+    ///     * `allowed_functions`: [array] Optional list of objects with `zome` and `function`
+    ///       naming the only zome functions that may be called over this interface.
+    ///       All functions are callable if not given.
     ///
     ///  * `admin/interface/remove`
     ///     Remove an interface from config. This automatically stops the interface as well.
@@ -336,6 +884,11 @@ impl ConductorApiBuilder {
     ///  * `admin/interface/list`
     ///     Returns an array of all DNA/zome interfaces.
     ///
+    ///  * `admin/interface/routing`
+    ///     Returns an array of all DNA/zome interfaces, each with its driver (type and port)
+    ///     and the instances it exposes together with the DNA and agent they run.
+    ///     Lets a UI find out everything it can call in one request.
+    ///
     ///  * `admin/agent/add`
     ///     Add an agent to the conductor configuration that can be used with instances.
     ///     Params:
@@ -371,6 +924,32 @@ impl ConductorApiBuilder {
     ///  * `admin/bridge/list`
     ///     Returns an array of all bridges.
     ///
+    ///  * `admin/capability/mint`
+    ///     Commits a capability grant to the source chain of the given instance and
+    ///     returns its token, to be used as `cap_token` in zome calls.
+    ///     Zome calls presenting a transferable or assigned token need to name their
+    ///     `caller` too.
+    ///     Params:
+    ///     * `instance_id`: ID of the instance the capability grants access to
+    ///     * `type`: `public`, `transferable` or `assigned`
+    ///     * `assignees`: [array of strings] Agent addresses, only for `assigned` grants
+    ///     * `functions`: [array of objects] Zome functions the grant gives access to,
+    ///        given as `{"zome": ..., "function": ...}`. Minting fails if the DNA does not
+    ///        declare all of them. (Optional)
+    ///
+    ///  * `admin/capability/revoke`
+    ///     Revokes a capability token. Zome calls presenting it get rejected afterwards.
+    ///     Params:
+    ///     * `instance_id`: ID of the instance the capability was minted for
+    ///     * `token`: The token returned by `admin/capability/mint`
+    ///
+    ///  * `debug/validation_package`
+    ///     Builds the validation package for an entry of the given instance's source chain
+    ///     and returns it. Nothing gets authored or published.
+    ///     Params:
+    ///     * `instance_id`: ID of the instance that holds the entry
+    ///     * `entry_address`: Address of the entry to build the validation package for
+    ///
     pub fn with_admin_dna_functions(mut self) -> Self {
         self.io
             .add_method("admin/dna/install_from_file", move |params| {
@@ -379,13 +958,23 @@ impl ConductorApiBuilder {
                 let path = Self::get_as_string("path", &params_map)?;
                 let copy = Self::get_as_bool("copy", &params_map).unwrap_or(false);
                 let properties = params_map.get("properties");
-                conductor_call!(|c| c.install_dna_from_file(
+                let events = Arc::new(Mutex::new(Vec::new()));
+                let recorded_events = events.clone();
+                let progress: ProgressCallback = Arc::new(move |step, total, message| {
+                    recorded_events
+                        .lock()
+                        .unwrap()
+                        .push(json!({"step": step, "total": total, "message": message}))
+                });
+                conductor_call!(|c| c.install_dna_from_file_with_progress(
                     PathBuf::from(path),
                     id.to_string(),
                     copy,
-                    properties
+                    properties,
+                    Some(progress)
                 ))?;
-                Ok(json!({"success": true}))
+                let progress = events.lock().unwrap().clone();
+                Ok(json!({"success": true, "progress": progress}))
             });
 
         self.io.add_method("admin/dna/uninstall", move |params| {
@@ -436,6 +1025,24 @@ impl ConductorApiBuilder {
             Ok(json!({"success": true}))
         });
 
+        self.io
+            .add_method("admin/instance/trigger_publish", move |params| {
+                let params_map = Self::unwrap_params_map(params)?;
+                let id = Self::get_as_string("id", &params_map)?;
+                // Only hold the conductor lock while looking up the instance
+                let instance = conductor_call!(|c| c
+                    .instances()
+                    .get(&id)
+                    .cloned()
+                    .ok_or(format!("Instance {} not found", id)))?;
+                instance.read().unwrap().trigger_publish().map_err(|e| {
+                    let mut error = jsonrpc_core::Error::internal_error();
+                    error.message = e.to_string();
+                    error
+                })?;
+                Ok(json!({"success": true}))
+            });
+
         self.io.add_method("admin/instance/list", move |_params| {
             let instances = conductor_call!(
                 |c| Ok(c.config().instances) as Result<Vec<InstanceConfiguration>, String>
@@ -493,6 +1100,15 @@ impl ConductorApiBuilder {
                     "`port` has to be a 16bit integer",
                 ))
             })?;
+            let allowed_functions = match params_map.get("allowed_functions") {
+                Some(value) => serde_json::from_value::<Vec<ZomeFunctionReference>>(value.clone())
+                    .map_err(|_| {
+                        jsonrpc_core::Error::invalid_params(String::from(
+                            "`allowed_functions` has to be an array of objects with `zome` and `function`",
+                        ))
+                    })?,
+                None => Vec::new(),
+            };
 
             let new_interface = InterfaceConfiguration {
                 id: id.to_string(),
@@ -507,6 +1123,11 @@ impl ConductorApiBuilder {
                     }
                 },
                 instances: Vec::new(),
+                max_zome_call_threads: None,
+                max_queued_zome_calls: None,
+                allowed_functions,
+                shutdown_grace_period_ms: None,
+                request_log: None,
             };
 
             conductor_call!(|c| c.add_interface(new_interface))?;
@@ -546,6 +1167,15 @@ impl ConductorApiBuilder {
                 .map_err(|_| jsonrpc_core::Error::internal_error())?)
         });
 
+        self.io
+            .add_method("admin/interface/routing", move |_params| {
+                let routing = conductor_call!(
+                    |c| Ok(c.config().routing()) as Result<Vec<InterfaceRoutes>, String>
+                )?;
+                Ok(serde_json::to_value(routing)
+                    .map_err(|_| jsonrpc_core::Error::internal_error())?)
+            });
+
         self.io.add_method("admin/agent/add", move |params| {
             let params_map = Self::unwrap_params_map(params)?;
             let id = Self::get_as_string("id", &params_map)?;
@@ -559,6 +1189,7 @@ impl ConductorApiBuilder {
                 public_address,
                 key_file,
                 holo_remote_key: None,
+                local_signing_fallback: None,
             };
             conductor_call!(|c| c.add_agent(agent))?;
             Ok(json!({"success": true}))
@@ -607,6 +1238,75 @@ impl ConductorApiBuilder {
             Ok(serde_json::to_value(bridges).map_err(|_| jsonrpc_core::Error::internal_error())?)
         });
 
+        self.io.add_method("admin/capability/mint", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let instance_id = Self::get_as_string("instance_id", &params_map)?;
+            let cap_type_string = Self::get_as_string("type", &params_map)?;
+            let cap_type: CapabilityType =
+                serde_json::from_value(json!(cap_type_string)).map_err(|_| {
+                    jsonrpc_core::Error::invalid_params(String::from(
+                        "`type` has to be either `public`, `transferable` or `assigned`",
+                    ))
+                })?;
+            let assignees = match params_map.get("assignees") {
+                Some(value) => Some(
+                    serde_json::from_value::<Vec<Address>>(value.clone()).map_err(|_| {
+                        jsonrpc_core::Error::invalid_params(String::from(
+                            "`assignees` has to be an array of addresses",
+                        ))
+                    })?,
+                ),
+                None => None,
+            };
+            let functions = match params_map.get("functions") {
+                Some(value) => serde_json::from_value::<Vec<GrantedFunction>>(value.clone())
+                    .map_err(|_| {
+                        jsonrpc_core::Error::invalid_params(String::from(
+                            "`functions` has to be an array of objects with `zome` and `function`",
+                        ))
+                    })?,
+                None => Vec::new(),
+            };
+            let grant = CapTokenGrant::create(cap_type, assignees)
+                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?
+                .with_functions(functions);
+            let token = conductor_call!(|c| c.mint_capability(&instance_id, grant))?;
+            Ok(json!({ "token": token }))
+        });
+
+        self.io
+            .add_method("admin/capability/revoke", move |params| {
+                let params_map = Self::unwrap_params_map(params)?;
+                let instance_id = Self::get_as_string("instance_id", &params_map)?;
+                let token = Self::get_as_string("token", &params_map)?;
+                conductor_call!(|c| c.revoke_capability(&instance_id, &Address::from(token)))?;
+                Ok(json!({"success": true}))
+            });
+
+        self.io
+            .add_method("debug/validation_package", move |params| {
+                let params_map = Self::unwrap_params_map(params)?;
+                let instance_id = Self::get_as_string("instance_id", &params_map)?;
+                let entry_address = Self::get_as_string("entry_address", &params_map)?;
+                // Only hold the conductor lock while looking up the instance
+                let instance = conductor_call!(|c| c
+                    .instances()
+                    .get(&instance_id)
+                    .cloned()
+                    .ok_or(format!("Instance {} not found", instance_id)))?;
+                let validation_package = instance
+                    .read()
+                    .unwrap()
+                    .validation_package(&Address::from(entry_address))
+                    .map_err(|e| {
+                        let mut error = jsonrpc_core::Error::internal_error();
+                        error.message = e.to_string();
+                        error
+                    })?;
+                Ok(serde_json::to_value(validation_package)
+                    .map_err(|_| jsonrpc_core::Error::internal_error())?)
+            });
+
         self
     }
 
@@ -639,6 +1339,8 @@ impl ConductorApiBuilder {
     ///     - `port` Port to host the HTTP server on
     ///     - `bundle` UI bundle to serve on this port
     ///     - `dna_interface` DNA interface this UI can connect to (Optional)
+    ///     - `request_timeout_ms` Time a client gets to send a request and have it
+    ///        answered (Optional)
     ///     
     /// - `admin/ui_interface/remove`
     ///     Remove an interface by ID
@@ -697,12 +1399,22 @@ impl ConductorApiBuilder {
             })?;
             let bundle = Self::get_as_string("bundle", &params_map)?;
             let dna_interface = Self::get_as_string("dna_interface", &params_map).ok();
+            let request_timeout_ms = match Self::get_as_int("request_timeout_ms", &params_map) {
+                Ok(timeout) => Some(u64::try_from(timeout).map_err(|_| {
+                    jsonrpc_core::Error::invalid_params(String::from(
+                        "`request_timeout_ms` can't be negative",
+                    ))
+                })?),
+                Err(_) => None,
+            };
 
             conductor_call!(|c| c.add_ui_interface(UiInterfaceConfiguration {
                 id,
                 port,
                 bundle,
-                dna_interface
+                dna_interface,
+                request_timeout_ms,
+                max_restarts: None,
             }))?;
             Ok(json!({"success": true}))
         });
@@ -746,25 +1458,29 @@ impl ConductorApiBuilder {
         self
     }
 
+    /// Signs the payload with the given key and returns the base64 encoded signature.
+    fn sign_with_keybundle(keybundle: &Arc<Mutex<KeyBundle>>, payload: &String) -> String {
+        // Convert payload string into a SecBuf
+        let mut message = SecBuf::with_insecure_from_string(payload.clone());
+
+        // Get write lock on the key since we need a mutuble reference to lock the
+        // secure memory the key is in:
+        let mut message_signature = keybundle
+            .lock()
+            .unwrap()
+            .sign(&mut message)
+            .expect("Failed to sign with keybundle.");
+
+        let message_signature = message_signature.read_lock();
+        // Return as base64 encoded string
+        base64::encode(&**message_signature)
+    }
+
     pub fn with_agent_signature_callback(mut self, keybundle: Arc<Mutex<KeyBundle>>) -> Self {
         self.io.add_method("agent/sign", move |params| {
             let params_map = Self::unwrap_params_map(params)?;
             let payload = Self::get_as_string("payload", &params_map)?;
-            // Convert payload string into a SecBuf
-            let mut message = SecBuf::with_insecure_from_string(payload.clone());
-
-            // Get write lock on the key since we need a mutuble reference to lock the
-            // secure memory the key is in:
-            let mut message_signature = keybundle
-                .lock()
-                .unwrap()
-                .sign(&mut message)
-                .expect("Failed to sign with keybundle.");
-
-            let message_signature = message_signature.read_lock();
-            // Return as base64 encoded string
-            let signature = base64::encode(&**message_signature);
-
+            let signature = Self::sign_with_keybundle(&keybundle, &payload);
             Ok(json!({"payload": payload, "signature": signature}))
         });
         self
@@ -777,16 +1493,39 @@ impl ConductorApiBuilder {
     ) -> Self {
         let agent_id = agent_id.clone();
         let signing_service_uri = signing_service_uri.clone();
+        let signing_fallback = self.signing_fallback.clone();
+        let max_signing_payload_bytes = self.max_signing_payload_bytes;
+        let signing_service_client = self
+            .signing_service_client
+            .clone()
+            .unwrap_or_else(|| Arc::new(reqwest::Client::new()));
 
         self.io.add_method("agent/sign", move |params| {
             let params_map = Self::unwrap_params_map(params)?;
             let payload = Self::get_as_string("payload", &params_map)?;
 
-            let signature = request_signing_service(&agent_id, &payload, &signing_service_uri)
-                .map_err(|holochain_error| {
+            let signature = match (
+                request_signing_service_with_client(
+                    &*signing_service_client,
+                    &agent_id,
+                    &payload,
+                    &signing_service_uri,
+                    max_signing_payload_bytes,
+                ),
+                &signing_fallback,
+            ) {
+                (Err(HolochainError::IoError(error)), Some((keybundle, logger))) => {
+                    logger.lock().unwrap().log(format!(
+                        "warn/conductor: Signing service {} unreachable, signing with local key instead: {}",
+                        signing_service_uri, error
+                    ));
+                    Self::sign_with_keybundle(keybundle, &payload)
+                }
+                (result, _) => result.map_err(|holochain_error| {
                     println!("Error in signing hack: {:?}", holochain_error);
                     jsonrpc_core::Error::internal_error()
-                })?;
+                })?,
+            };
 
             Ok(json!({"payload": payload, "signature": signature}))
         });
@@ -803,7 +1542,7 @@ pub mod tests {
     use super::*;
     use crate::{conductor::tests::test_conductor, config::Configuration};
 
-    fn example_config_and_instances() -> (Configuration, InstanceMap) {
+    pub fn example_config_and_instances() -> (Configuration, InstanceMap) {
         let conductor = test_conductor();
         let holochain = conductor
             .instances()
@@ -841,6 +1580,93 @@ pub mod tests {
         assert!(!result.contains(r#""test-instance-2//test""#));
     }
 
+    #[test]
+    fn test_zome_call_limiter_caps_concurrent_calls() {
+        let limiter = ZomeCallLimiter::new(3, 20);
+        let running = Arc::new(Mutex::new(0));
+        let max_seen = Arc::new(Mutex::new(0));
+
+        let handles: Vec<_> = (0..12)
+            .map(|_| {
+                let limiter = limiter.clone();
+                let running = running.clone();
+                let max_seen = max_seen.clone();
+                std::thread::spawn(move || {
+                    let _permit = limiter.acquire().expect("queue should not overflow");
+                    {
+                        let mut running = running.lock().unwrap();
+                        *running += 1;
+                        let mut max_seen = max_seen.lock().unwrap();
+                        *max_seen = std::cmp::max(*max_seen, *running);
+                    }
+                    std::thread::sleep(std::time::Duration::from_millis(20));
+                    *running.lock().unwrap() -= 1;
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert!(*max_seen.lock().unwrap() <= 3);
+        assert_eq!(limiter.running(), 0);
+        assert_eq!(limiter.queued(), 0);
+    }
+
+    #[test]
+    fn test_zome_call_limiter_rejects_when_queue_is_full() {
+        let limiter = ZomeCallLimiter::new(1, 1);
+        let permit = limiter.acquire().unwrap();
+
+        let queued_limiter = limiter.clone();
+        let queued = std::thread::spawn(move || queued_limiter.acquire().map(|_| ()));
+        while limiter.queued() < 1 {
+            std::thread::yield_now();
+        }
+
+        let busy = limiter.acquire();
+        assert_eq!(
+            busy.err().map(|error| error.code),
+            Some(ErrorCode::ServerError(SERVER_BUSY_ERROR_CODE))
+        );
+
+        drop(permit);
+        assert!(queued.join().unwrap().is_ok());
+        assert_eq!(limiter.running(), 0);
+    }
+
+    #[test]
+    fn test_zome_call_tracker_waits_for_running_calls() {
+        let tracker = ZomeCallTracker::new();
+        let finished = Arc::new(Mutex::new(false));
+
+        let call = tracker.start().unwrap();
+        let finished_clone = finished.clone();
+        let running_call = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            *finished_clone.lock().unwrap() = true;
+            drop(call);
+        });
+
+        assert!(tracker.close_and_wait(Duration::from_secs(5)));
+        assert!(*finished.lock().unwrap());
+        assert_eq!(tracker.running(), 0);
+        assert_eq!(
+            tracker.start().err().map(|error| error.code),
+            Some(ErrorCode::ServerError(SHUTTING_DOWN_ERROR_CODE))
+        );
+        running_call.join().unwrap();
+    }
+
+    #[test]
+    fn test_zome_call_tracker_gives_up_after_grace_period() {
+        let tracker = ZomeCallTracker::new();
+        let _call = tracker.start().unwrap();
+        assert!(!tracker.close_and_wait(Duration::from_millis(50)));
+        assert_eq!(tracker.running(), 1);
+    }
+
     #[test]
     fn test_named_instances() {
         let (config, instances) = example_config_and_instances();
@@ -861,6 +1687,254 @@ pub mod tests {
         assert!(!result.contains(r#""test-instance-1//test""#));
     }
 
+    fn call_error_code(handler: &IoHandler, params: serde_json::Value) -> Option<i64> {
+        let request = json!({"jsonrpc": "2.0", "id": "0", "method": "call", "params": params});
+        let response_str = handler
+            .handle_request_sync(&request.to_string())
+            .expect("Invalid call to handler");
+        serde_json::from_str::<serde_json::Value>(&response_str).expect("Response not valid JSON")
+            ["error"]["code"]
+            .as_i64()
+    }
+
+    #[test]
+    fn test_call_reports_unknown_zome_and_function() {
+        let (config, instances) = example_config_and_instances();
+        let handler = ConductorApiBuilder::new()
+            .with_instances(instances.clone())
+            .with_instance_configs(config.instances)
+            .spawn();
+
+        assert_eq!(
+            call_error_code(
+                &handler,
+                json!({"instance_id": "test-instance-1", "zome": "no-such-zome", "function": "hello"})
+            ),
+            Some(ZOME_NOT_FOUND_ERROR_CODE)
+        );
+        assert_eq!(
+            call_error_code(
+                &handler,
+                json!({"instance_id": "test-instance-1", "zome": "greeter", "function": "no-such-fn"})
+            ),
+            Some(FUNCTION_NOT_FOUND_ERROR_CODE)
+        );
+        assert_eq!(
+            call_error_code(
+                &handler,
+                json!({"instance_id": "no-such-instance", "zome": "greeter", "function": "hello"})
+            ),
+            Some(-32602)
+        );
+    }
+
+    #[test]
+    fn test_call_rejects_arguments_not_matching_declared_inputs() {
+        let conductor = test_conductor();
+        let mut instances = InstanceMap::new();
+        instances.insert(
+            "test-instance-2".into(),
+            conductor
+                .instances()
+                .get("test-instance-2")
+                .unwrap()
+                .clone(),
+        );
+        let handler = ConductorApiBuilder::new()
+            .with_instances(instances)
+            .with_instance_configs(conductor.config().instances)
+            .spawn();
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": "0",
+            "method": "call",
+            "params": {"instance_id": "test-instance-2", "zome": "", "function": "test", "params": {}}
+        });
+        let response: serde_json::Value =
+            serde_json::from_str(&handler.handle_request_sync(&request.to_string()).unwrap())
+                .unwrap();
+        assert_eq!(
+            response["error"]["code"].as_i64(),
+            Some(INVALID_ARGUMENTS_ERROR_CODE)
+        );
+        assert_eq!(
+            response["error"]["message"].as_str(),
+            Some(
+                "Invalid arguments for zome '': Missing argument 'post' of type string for 'test'"
+            )
+        );
+
+        assert_eq!(
+            call_error_code(
+                &handler,
+                json!({"instance_id": "test-instance-2", "zome": "", "function": "test", "params": {"post": 42}})
+            ),
+            Some(INVALID_ARGUMENTS_ERROR_CODE)
+        );
+
+        // methods named after the instance validate the same way
+        let response_str = handler
+            .handle_request_sync(&create_call_str("test-instance-2//test", None))
+            .unwrap();
+        let response: serde_json::Value = serde_json::from_str(&response_str).unwrap();
+        assert_eq!(
+            response["error"]["code"].as_i64(),
+            Some(INVALID_ARGUMENTS_ERROR_CODE)
+        );
+    }
+
+    #[test]
+    fn test_metrics_reflect_activity() {
+        let (config, instances) = example_config_and_instances();
+        let handler = ConductorApiBuilder::new()
+            .with_zome_call_limit(2, 10)
+            .with_instances(instances.clone())
+            .with_instance_configs(config.instances)
+            .spawn();
+
+        assert_eq!(
+            call_error_code(
+                &handler,
+                json!({"instance_id": "test-instance-1", "zome": "greeter", "function": "hello"})
+            ),
+            None
+        );
+
+        let response_str = handler
+            .handle_request_sync(&create_call_str("metrics", None))
+            .unwrap();
+        let response: serde_json::Value = serde_json::from_str(&response_str).unwrap();
+        let metrics = response["result"]
+            .as_str()
+            .expect("metrics should be a string");
+
+        // every line is a comment or a sample with a numeric value
+        for line in metrics.lines() {
+            if line.starts_with("# HELP ") || line.starts_with("# TYPE ") {
+                continue;
+            }
+            let mut parts = line.rsplitn(2, ' ');
+            let value = parts.next().unwrap();
+            let name = parts.next().expect("sample line without value");
+            assert!(value.parse::<u64>().is_ok(), "bad value in: {}", line);
+            assert!(name.starts_with("holochain_"), "bad name in: {}", line);
+        }
+
+        let chain_length = instances["test-instance-1"]
+            .read()
+            .unwrap()
+            .context()
+            .state()
+            .unwrap()
+            .agent()
+            .iter_chain()
+            .count();
+        assert!(metrics.contains("holochain_interface_zome_calls_total 1\n"));
+        assert!(metrics.contains("holochain_interface_zome_calls_running 0\n"));
+        assert!(metrics.contains("holochain_interface_zome_calls_queued 0\n"));
+        assert!(metrics.contains(&format!(
+            "holochain_chain_length{{instance=\"test-instance-1\"}} {}\n",
+            chain_length
+        )));
+        assert!(metrics.contains("# TYPE holochain_dht_entries gauge\n"));
+        assert!(metrics.contains("holochain_validations_running{instance=\"test-instance-1\"} 0\n"));
+    }
+
+    #[test]
+    fn test_metrics_dont_wait_for_locked_instances() {
+        let (config, instances) = example_config_and_instances();
+        let handler = ConductorApiBuilder::new()
+            .with_instances(instances.clone())
+            .with_instance_configs(config.instances)
+            .spawn();
+        let scrape = || {
+            let response_str = handler
+                .handle_request_sync(&create_call_str("metrics", None))
+                .unwrap();
+            let response: serde_json::Value = serde_json::from_str(&response_str).unwrap();
+            response["result"].as_str().unwrap().to_string()
+        };
+        let instance_metric = "holochain_chain_length{instance=\"test-instance-1\"}";
+        assert!(scrape().contains(instance_metric));
+
+        // like a running zome call does
+        let _locked = instances["test-instance-1"].write().unwrap();
+        assert!(scrape().contains(instance_metric));
+    }
+
+    #[test]
+    fn test_call_rejects_functions_not_allowed() {
+        let (config, instances) = example_config_and_instances();
+        let handler = ConductorApiBuilder::new()
+            .with_allowed_functions(vec![ZomeFunctionReference {
+                zome: String::from("greeter"),
+                function: String::from("hello"),
+            }])
+            .with_instances(instances.clone())
+            .with_instance_configs(config.instances)
+            .spawn();
+
+        assert_eq!(
+            call_error_code(
+                &handler,
+                json!({"instance_id": "test-instance-1", "zome": "greeter", "function": "hello"})
+            ),
+            None
+        );
+        assert_eq!(
+            call_error_code(
+                &handler,
+                json!({"instance_id": "test-instance-1", "zome": "greeter", "function": "no-such-fn"})
+            ),
+            Some(PERMISSION_DENIED_ERROR_CODE)
+        );
+        // rejected before the instance gets looked up
+        assert_eq!(
+            call_error_code(
+                &handler,
+                json!({"instance_id": "no-such-instance", "zome": "other", "function": "hello"})
+            ),
+            Some(PERMISSION_DENIED_ERROR_CODE)
+        );
+    }
+
+    #[test]
+    fn test_zome_call_error_codes() {
+        let code = |error: HolochainError| match zome_call_error(error.into()).code {
+            ErrorCode::ServerError(code) => Some(code),
+            _ => None,
+        };
+        assert_eq!(
+            code(HolochainError::Dna(DnaError::ZomeNotFound("zome".into()))),
+            Some(ZOME_NOT_FOUND_ERROR_CODE)
+        );
+        assert_eq!(
+            code(HolochainError::Dna(DnaError::ZomeFunctionNotFound(
+                "fn".into()
+            ))),
+            Some(FUNCTION_NOT_FOUND_ERROR_CODE)
+        );
+        assert_eq!(
+            code(HolochainError::SerializationError("bad args".into())),
+            Some(DESERIALIZATION_FAILED_ERROR_CODE)
+        );
+        assert_eq!(
+            code(HolochainError::Ribosome(
+                RibosomeErrorCode::ArgumentDeserializationFailed
+            )),
+            Some(DESERIALIZATION_FAILED_ERROR_CODE)
+        );
+        assert_eq!(
+            code(HolochainError::ValidationFailed("invalid entry".into())),
+            Some(VALIDATION_FAILED_ERROR_CODE)
+        );
+        assert_eq!(
+            zome_call_error(HolochainError::Timeout.into()).code,
+            ErrorCode::InternalError
+        );
+    }
+
     /// The below test cannot be extented to test the other RPC methods due to the singleton design of the conductor
     /// It may be worth removing this test but I have included it as an example of testing the responses for the
     /// other rpc methods if this becomes possible in the future
@@ -883,4 +1957,58 @@ pub mod tests {
             r#"[{"id":"test-instance-1","dna":"bridge-callee","agent":"test-agent-1"}]"#
         );
     }
+
+    #[test]
+    fn test_outsourced_signing_falls_back_to_local_key() {
+        use crate::conductor::tests::test_keybundle;
+        use holochain_core::logger::test_logger;
+
+        let agent_id = AgentId::new("holo_tester1", test_keybundle(1).get_id());
+        // nothing listens on port 1, so the signing service is unreachable
+        let unreachable_uri = String::from("http://127.0.0.1:1/");
+        let sign_request = json!({
+            "jsonrpc": "2.0",
+            "id": "0",
+            "method": "agent/sign",
+            "params": {"payload": "some payload"},
+        })
+        .to_string();
+
+        let handler = ConductorApiBuilder::new()
+            .with_outsource_signing_callback(agent_id.clone(), unreachable_uri.clone())
+            .spawn();
+        let response_str = handler
+            .handle_request_sync(&sign_request)
+            .expect("Invalid call to handler");
+        assert!(response_str.contains("error"));
+
+        let keybundle = Arc::new(Mutex::new(test_keybundle(1)));
+        let logger = test_logger();
+        let other_agent_id = AgentId::new("holo_tester2", test_keybundle(2).get_id());
+        assert!(ConductorApiBuilder::new()
+            .with_local_signing_fallback(keybundle.clone(), &other_agent_id, logger.clone())
+            .is_err());
+        let handler = ConductorApiBuilder::new()
+            .with_local_signing_fallback(keybundle.clone(), &agent_id, logger.clone())
+            .unwrap()
+            .with_outsource_signing_callback(agent_id, unreachable_uri)
+            .spawn();
+        let response_str = handler
+            .handle_request_sync(&sign_request)
+            .expect("Invalid call to handler");
+        let response: serde_json::Value = serde_json::from_str(&response_str).unwrap();
+        assert_eq!(
+            response["result"]["signature"],
+            json!(ConductorApiBuilder::sign_with_keybundle(
+                &keybundle,
+                &String::from("some payload")
+            ))
+        );
+        assert!(logger
+            .lock()
+            .unwrap()
+            .log
+            .iter()
+            .any(|message| message.starts_with("warn/conductor: Signing service")));
+    }
 }
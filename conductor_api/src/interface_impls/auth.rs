@@ -0,0 +1,39 @@
+//! Helpers for authenticating clients of interfaces.
+
+use holochain_sodium::{hash, secbuf::SecBuf};
+
+/// Hash of the given credential, so that credentials of any length can be compared
+/// as buffers of the same length without revealing how long they are.
+fn credential_hash(credential: &str) -> SecBuf {
+    let mut input = SecBuf::with_insecure_from_string(credential.to_string());
+    let mut output = SecBuf::with_insecure(hash::BYTES512);
+    hash::sha512(&mut input, &mut output).expect("sha512 can't fail");
+    output
+}
+
+/// True if the credential a client presented, e.g. a bearer token or a password,
+/// matches the configured one.
+/// Compares in constant time instead of with `==`, which returns at the first
+/// differing byte and so would let a client find the credential byte by byte
+/// by timing its attempts. Both get hashed first so their lengths don't matter.
+pub fn credentials_match(presented: &str, configured: &str) -> bool {
+    credential_hash(presented).constant_time_eq(&mut credential_hash(configured))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn credentials_match_regardless_of_mismatch_position() {
+        let configured = "s3cret-token";
+        assert!(credentials_match("s3cret-token", configured));
+        assert!(!credentials_match("x3cret-token", configured));
+        assert!(!credentials_match("s3crex-token", configured));
+        assert!(!credentials_match("s3cret-tokex", configured));
+        assert!(!credentials_match("s3cret-toke", configured));
+        assert!(!credentials_match("s3cret-tokenn", configured));
+        assert!(!credentials_match("", configured));
+        assert!(credentials_match("", ""));
+    }
+}
@@ -0,0 +1,124 @@
+use holochain_core_types::backoff::{Backoff, Jitter, DEFAULT_MAX_DELAY};
+use std::{
+    io::{self, ErrorKind},
+    thread,
+    time::Duration,
+};
+
+/// Time to wait before the first retry. Every further retry waits one step longer,
+/// up to DEFAULT_MAX_DELAY.
+pub const DEFAULT_BIND_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Retries binding a port that is still in use, for instance by the socket of a
+/// conductor that just got restarted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BindRetry {
+    retries: usize,
+    backoff: Backoff,
+}
+
+impl BindRetry {
+    pub fn new(retries: usize, backoff: Duration) -> Self {
+        BindRetry {
+            retries,
+            backoff: Backoff::linear(backoff, DEFAULT_MAX_DELAY),
+        }
+    }
+
+    /// Randomizes the time waited before each retry.
+    pub fn with_jitter(mut self, jitter: Jitter) -> Self {
+        self.backoff = self.backoff.with_jitter(jitter);
+        self
+    }
+
+    /// Caps the time waited before each retry.
+    pub fn with_max_delay(mut self, max: Duration) -> Self {
+        self.backoff = self.backoff.with_max_delay(max);
+        self
+    }
+
+    /// Binds once and fails right away.
+    pub fn none() -> Self {
+        BindRetry::new(0, DEFAULT_BIND_BACKOFF)
+    }
+
+    /// Calls the given bind function until it succeeds or fails with an error other
+    /// than AddrInUse. Returns the last error once all retries are used up.
+    pub fn bind<T, F>(&self, mut bind: F) -> io::Result<T>
+    where
+        F: FnMut() -> io::Result<T>,
+    {
+        let mut attempt = 0;
+        loop {
+            match bind() {
+                Err(ref error)
+                    if error.kind() == ErrorKind::AddrInUse && attempt < self.retries =>
+                {
+                    attempt += 1;
+                    thread::sleep(self.backoff.delay(attempt as u32));
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Default for BindRetry {
+    fn default() -> Self {
+        BindRetry::none()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn bind_succeeds_once_port_gets_released() {
+        let first = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = first.local_addr().unwrap();
+        assert_eq!(
+            TcpListener::bind(addr).unwrap_err().kind(),
+            ErrorKind::AddrInUse
+        );
+
+        let release = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            drop(first);
+        });
+        let second = BindRetry::new(10, Duration::from_millis(50))
+            .bind(|| TcpListener::bind(addr))
+            .expect("port should have been released within the retry window");
+        assert_eq!(second.local_addr().unwrap(), addr);
+        release.join().unwrap();
+    }
+
+    #[test]
+    fn bind_returns_error_after_retries() {
+        let first = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = first.local_addr().unwrap();
+        let mut attempts = 0;
+        let result = BindRetry::new(2, Duration::from_millis(10)).bind(|| {
+            attempts += 1;
+            TcpListener::bind(addr)
+        });
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::AddrInUse);
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn bind_retries_with_jitter() {
+        let first = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = first.local_addr().unwrap();
+        let mut attempts = 0;
+        let result = BindRetry::new(3, Duration::from_millis(10))
+            .with_jitter(Jitter::Full)
+            .bind(|| {
+                attempts += 1;
+                TcpListener::bind(addr)
+            });
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::AddrInUse);
+        assert_eq!(attempts, 4);
+    }
+}
@@ -1,24 +1,204 @@
 use interface::Interface;
-use jsonrpc_http_server::{jsonrpc_core::IoHandler, ServerBuilder};
-use std::sync::mpsc::Receiver;
+use interface_impls::bind_retry::BindRetry;
+use jsonrpc_http_server::{
+    hyper::{header::HeaderValue, Body, Method, Request, StatusCode},
+    jsonrpc_core::IoHandler,
+    RequestMiddlewareAction, Response, ServerBuilder,
+};
+use metrics::PROMETHEUS_CONTENT_TYPE;
+use serde_json::{self, Value};
+use std::{net::SocketAddr, sync::mpsc::Receiver};
+
+/// Path under which HTTP interfaces answer GET requests with the result of the
+/// "metrics" method, so that Prometheus can scrape it without going through JSON-RPC
+pub const METRICS_ROUTE: &str = "/metrics";
+
+fn metrics_response(handler: &IoHandler) -> Response {
+    let request = json!({"jsonrpc": "2.0", "id": 0, "method": "metrics"}).to_string();
+    let metrics = handler
+        .handle_request_sync(&request)
+        .and_then(|response| serde_json::from_str::<Value>(&response).ok())
+        .and_then(|response| response["result"].as_str().map(String::from));
+    match metrics {
+        Some(metrics) => Response {
+            code: StatusCode::OK,
+            content_type: HeaderValue::from_static(PROMETHEUS_CONTENT_TYPE),
+            content: metrics,
+        },
+        None => Response::internal_error("Could not collect metrics"),
+    }
+}
 
 pub struct HttpInterface {
     port: u16,
+    bind_retry: BindRetry,
 }
 
 impl HttpInterface {
     pub fn new(port: u16) -> Self {
-        HttpInterface { port }
+        HttpInterface {
+            port,
+            bind_retry: BindRetry::none(),
+        }
+    }
+
+    /// Waits for the port to become free according to the given BindRetry
+    /// instead of failing right away if it is still in use.
+    pub fn with_bind_retry(mut self, bind_retry: BindRetry) -> Self {
+        self.bind_retry = bind_retry;
+        self
     }
 }
 
 impl Interface for HttpInterface {
     fn run(&self, handler: IoHandler, kill_switch: Receiver<()>) -> Result<(), String> {
         let url = format!("0.0.0.0:{}", self.port);
-        let _server = ServerBuilder::new(handler)
-            .start_http(&url.parse().expect("Invalid URL!"))
+        let addr: SocketAddr = url.parse().expect("Invalid URL!");
+        // The server binds the port itself, so retry starting the server instead of
+        // probing the port first, which another process could take in between.
+        let _server = self
+            .bind_retry
+            .bind(|| {
+                let metrics_handler = handler.clone();
+                ServerBuilder::new(handler.clone())
+                    .request_middleware(move |request: Request<Body>| {
+                        if request.method() == Method::GET && request.uri().path() == METRICS_ROUTE
+                        {
+                            RequestMiddlewareAction::from(metrics_response(&metrics_handler))
+                        } else {
+                            RequestMiddlewareAction::from(request)
+                        }
+                    })
+                    .start_http(&addr)
+            })
             .map_err(|e| e.to_string())?;
         let _ = kill_switch.recv();
         Ok(())
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use interface::{
+        tests::example_config_and_instances, ConductorApiBuilder, FUNCTION_NOT_FOUND_ERROR_CODE,
+    };
+    use request_log::{MemoryRequestLogSink, RequestLog, RequestStatus};
+    use reqwest;
+    use std::{
+        net::TcpListener,
+        sync::{mpsc::channel, Arc},
+        thread,
+        time::Duration,
+    };
+
+    #[test]
+    fn serves_metrics_for_scraping() {
+        let handler = ConductorApiBuilder::new().spawn();
+        let (kill_switch_tx, kill_switch) = channel();
+        let server = thread::spawn(move || HttpInterface::new(3402).run(handler, kill_switch));
+
+        let url = format!("http://localhost:3402{}", METRICS_ROUTE);
+        let mut response = None;
+        for _ in 0..50 {
+            if let Ok(ok) = reqwest::get(&url) {
+                response = Some(ok);
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        let mut response = response.expect("HTTP interface did not come up");
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(
+            response.headers()[reqwest::header::CONTENT_TYPE],
+            PROMETHEUS_CONTENT_TYPE
+        );
+        let body = response.text().unwrap();
+        assert!(body.contains("# TYPE holochain_interface_zome_calls_total counter\n"));
+        assert!(body.contains("holochain_interface_zome_calls_total 0\n"));
+
+        kill_switch_tx.send(()).unwrap();
+        assert_eq!(server.join().unwrap(), Ok(()));
+    }
+
+    #[test]
+    fn starts_once_the_port_gets_released() {
+        let blocker = TcpListener::bind("0.0.0.0:3404").unwrap();
+        let handler = ConductorApiBuilder::new().spawn();
+        let (kill_switch_tx, kill_switch) = channel();
+        let server = thread::spawn(move || {
+            HttpInterface::new(3404)
+                .with_bind_retry(BindRetry::new(50, Duration::from_millis(20)))
+                .run(handler, kill_switch)
+        });
+        thread::sleep(Duration::from_millis(200));
+        drop(blocker);
+
+        let url = format!("http://localhost:3404{}", METRICS_ROUTE);
+        let mut response = None;
+        for _ in 0..50 {
+            if let Ok(ok) = reqwest::get(&url) {
+                response = Some(ok);
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        let response = response.expect("HTTP interface did not come up");
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+        kill_switch_tx.send(()).unwrap();
+        assert_eq!(server.join().unwrap(), Ok(()));
+    }
+
+    #[test]
+    fn logs_zome_call_requests() {
+        let (config, instances) = example_config_and_instances();
+        let sink = MemoryRequestLogSink::new();
+        let handler = ConductorApiBuilder::new()
+            .with_request_log(RequestLog::new(
+                Arc::new(sink.clone()),
+                "http interface".to_string(),
+                "http".to_string(),
+            ))
+            .with_instances(instances)
+            .with_instance_configs(config.instances)
+            .spawn();
+        let (kill_switch_tx, kill_switch) = channel();
+        let server = thread::spawn(move || HttpInterface::new(3403).run(handler, kill_switch));
+
+        let client = reqwest::Client::new();
+        let post = |method: &str, params: Value| {
+            let request = json!({"jsonrpc": "2.0", "id": "0", "method": method, "params": params});
+            for _ in 0..50 {
+                if let Ok(response) = client.post("http://localhost:3403").json(&request).send() {
+                    return response;
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+            panic!("HTTP interface did not come up");
+        };
+        post("test-instance-1/greeter/hello", json!({}));
+        post(
+            "call",
+            json!({"instance_id": "test-instance-1", "zome": "greeter", "function": "no-such-fn"}),
+        );
+
+        let records = sink.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].method, "test-instance-1/greeter/hello");
+        assert_eq!(records[0].interface, "http interface");
+        assert_eq!(records[0].transport, "http");
+        assert_eq!(records[0].function, Some("hello".to_string()));
+        assert_eq!(records[0].status, RequestStatus::Success);
+        assert_eq!(records[1].method, "call");
+        assert_eq!(records[1].instance, Some("test-instance-1".to_string()));
+        assert_eq!(records[1].function, Some("no-such-fn".to_string()));
+        match records[1].status {
+            RequestStatus::Error { code, .. } => assert_eq!(code, FUNCTION_NOT_FOUND_ERROR_CODE),
+            ref status => panic!("unexpected status {:?}", status),
+        }
+
+        kill_switch_tx.send(()).unwrap();
+        assert_eq!(server.join().unwrap(), Ok(()));
+    }
+}
@@ -1,3 +1,5 @@
+pub mod auth;
+pub mod bind_retry;
 pub mod http;
 pub mod websocket;
 
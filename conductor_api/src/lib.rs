@@ -122,6 +122,7 @@ extern crate directories;
 extern crate hyper;
 extern crate hyper_staticfile;
 extern crate json_patch;
+extern crate mime_guess;
 #[cfg(test)]
 extern crate reqwest;
 extern crate tokio;
@@ -130,6 +131,8 @@ extern crate tokio;
 extern crate pretty_assertions;
 extern crate base64;
 extern crate rpassword;
+#[cfg(test)]
+extern crate uuid;
 
 pub mod conductor;
 pub mod config;
@@ -141,6 +144,8 @@ pub mod interface;
 pub mod interface_impls;
 pub mod key_loaders;
 pub mod logger;
+pub mod metrics;
+pub mod request_log;
 pub mod static_file_server;
 
 pub use crate::holochain::Holochain;
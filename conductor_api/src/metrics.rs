@@ -0,0 +1,377 @@
+//! Renders the counters and gauges of an interface and its instances in the
+//! Prometheus text exposition format, so they can be scraped from the "metrics" method
+//! of the conductor API or from GET /metrics on HTTP interfaces.
+//! See https://prometheus.io/docs/instrumenting/exposition_formats/
+use holochain_core::{context::Context, dht::dht_store::DhtStats};
+use interface::{ZomeCallLimiter, ZomeCallTracker};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Content type of the text exposition format
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// How long the chain length and DHT stats of an instance get reused across scrapes
+/// before they are computed again
+pub const INSTANCE_STATS_MAX_AGE: Duration = Duration::from_secs(30);
+
+/// Chain length and DHT stats of one instance.
+/// Both have to walk the whole source chain or shard, so they are cached.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InstanceStats {
+    pub chain_length: Option<u64>,
+    pub dht: Option<DhtStats>,
+}
+
+impl InstanceStats {
+    fn compute(context: &Context) -> Self {
+        // Only hold the state lock long enough to grab the slices, not while walking them
+        let slices = context.state().map(|state| (state.agent(), state.dht()));
+        match slices {
+            Some((agent, dht)) => InstanceStats {
+                chain_length: Some(agent.iter_chain().count() as u64),
+                dht: dht.stats().ok(),
+            },
+            None => InstanceStats::default(),
+        }
+    }
+}
+
+/// Caches the stats of each instance for a maximum age, so that frequent scrapes
+/// don't keep walking the storages. Clones share the same cache.
+#[derive(Clone)]
+pub struct InstanceStatsCache {
+    max_age: Duration,
+    stats: Arc<Mutex<HashMap<String, (Instant, InstanceStats)>>>,
+}
+
+impl Default for InstanceStatsCache {
+    fn default() -> Self {
+        InstanceStatsCache::new(INSTANCE_STATS_MAX_AGE)
+    }
+}
+
+impl InstanceStatsCache {
+    pub fn new(max_age: Duration) -> Self {
+        InstanceStatsCache {
+            max_age,
+            stats: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Stats of the given instance, computed from its context if there are no cached ones
+    /// or they are older than the maximum age.
+    pub fn get(&self, instance_id: &str, context: &Context) -> InstanceStats {
+        if let Some((computed, stats)) = self.stats.lock().unwrap().get(instance_id) {
+            if computed.elapsed() < self.max_age {
+                return stats.clone();
+            }
+        }
+        // Computed without holding the cache lock, so other scrapes are not blocked
+        let stats = InstanceStats::compute(context);
+        self.stats
+            .lock()
+            .unwrap()
+            .insert(instance_id.to_string(), (Instant::now(), stats.clone()));
+        stats
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+}
+
+impl MetricKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+        }
+    }
+}
+
+/// A single value of a metric with its labels, e.g. `instance="app"`
+pub type Sample = (Vec<(&'static str, String)>, u64);
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Collects metrics and writes them as Prometheus text.
+#[derive(Default)]
+pub struct PrometheusWriter {
+    output: String,
+}
+
+impl PrometheusWriter {
+    pub fn new() -> Self {
+        PrometheusWriter::default()
+    }
+
+    /// Adds a metric with its HELP and TYPE lines, followed by one line per sample.
+    /// Metrics without samples are left out.
+    pub fn metric(&mut self, name: &str, kind: MetricKind, help: &str, samples: &[Sample]) {
+        if samples.is_empty() {
+            return;
+        }
+        self.output.push_str(&format!(
+            "# HELP {} {}\n# TYPE {} {}\n",
+            name,
+            help,
+            name,
+            kind.as_str()
+        ));
+        for (labels, value) in samples {
+            if labels.is_empty() {
+                self.output.push_str(&format!("{} {}\n", name, value));
+            } else {
+                let labels: Vec<String> = labels
+                    .iter()
+                    .map(|(key, value)| format!("{}=\"{}\"", key, escape_label_value(value)))
+                    .collect();
+                self.output
+                    .push_str(&format!("{}{{{}}} {}\n", name, labels.join(","), value));
+            }
+        }
+    }
+
+    pub fn into_string(self) -> String {
+        self.output
+    }
+}
+
+fn per_instance<F>(contexts: &[(String, Arc<Context>)], value: F) -> Vec<Sample>
+where
+    F: Fn(&Context) -> Option<u64>,
+{
+    contexts
+        .iter()
+        .filter_map(|(id, context)| {
+            value(context).map(|value| (vec![("instance", id.clone())], value))
+        })
+        .collect()
+}
+
+fn per_dht_shard(
+    instance_stats: &[(String, InstanceStats)],
+    value: fn(&DhtStats) -> usize,
+) -> Vec<Sample> {
+    instance_stats
+        .iter()
+        .filter_map(|(id, stats)| {
+            stats
+                .dht
+                .as_ref()
+                .map(|dht| (vec![("instance", id.clone())], value(dht) as u64))
+        })
+        .collect()
+}
+
+/// Renders the metrics of an interface with the given zome call limiter and tracker
+/// and of the given instances.
+/// Chain lengths and DHT stats are taken from the given cache.
+pub fn render_metrics(
+    contexts: &[(String, Arc<Context>)],
+    zome_call_limiter: Option<&ZomeCallLimiter>,
+    zome_call_tracker: &ZomeCallTracker,
+    stats_cache: &InstanceStatsCache,
+) -> String {
+    let mut writer = PrometheusWriter::new();
+    writer.metric(
+        "holochain_interface_zome_calls_total",
+        MetricKind::Counter,
+        "Zome calls started through this interface.",
+        &[(Vec::new(), zome_call_tracker.total())],
+    );
+    writer.metric(
+        "holochain_interface_zome_calls_running",
+        MetricKind::Gauge,
+        "Zome calls of this interface that are currently running.",
+        &[(Vec::new(), zome_call_tracker.running() as u64)],
+    );
+    if let Some(limiter) = zome_call_limiter {
+        writer.metric(
+            "holochain_interface_zome_calls_queued",
+            MetricKind::Gauge,
+            "Zome calls of this interface waiting for a free thread.",
+            &[(Vec::new(), limiter.queued() as u64)],
+        );
+    }
+
+    let instance_stats: Vec<_> = contexts
+        .iter()
+        .map(|(id, context)| (id.clone(), stats_cache.get(id, context)))
+        .collect();
+    let chain_lengths: Vec<Sample> = instance_stats
+        .iter()
+        .filter_map(|(id, stats)| {
+            stats
+                .chain_length
+                .map(|length| (vec![("instance", id.clone())], length))
+        })
+        .collect();
+    writer.metric(
+        "holochain_chain_length",
+        MetricKind::Gauge,
+        "Number of headers in the source chain.",
+        &chain_lengths,
+    );
+    writer.metric(
+        "holochain_dht_entries",
+        MetricKind::Gauge,
+        "Distinct items in the content storage of the local DHT shard.",
+        &per_dht_shard(&instance_stats, |stats| stats.entry_count),
+    );
+    writer.metric(
+        "holochain_dht_content_bytes",
+        MetricKind::Gauge,
+        "Serialized size of all items in the local DHT shard.",
+        &per_dht_shard(&instance_stats, |stats| stats.content_bytes),
+    );
+    writer.metric(
+        "holochain_dht_meta_records",
+        MetricKind::Gauge,
+        "EAV records in the meta storage of the local DHT shard.",
+        &per_dht_shard(&instance_stats, |stats| stats.meta_count),
+    );
+    writer.metric(
+        "holochain_validations_running",
+        MetricKind::Gauge,
+        "Validation callbacks that are currently running.",
+        &per_instance(contexts, |context| {
+            Some(context.validation_limiter.running() as u64)
+        }),
+    );
+    writer.metric(
+        "holochain_wasm_module_compilations_total",
+        MetricKind::Counter,
+        "Wasm binaries the wasm module cache had to compile.",
+        &per_instance(contexts, |context| {
+            context
+                .wasm_module_cache
+                .as_ref()
+                .map(|cache| cache.compilations() as u64)
+        }),
+    );
+    writer.metric(
+        "holochain_zome_call_cache_hits_total",
+        MetricKind::Counter,
+        "Zome calls answered from the zome call cache.",
+        &per_instance(contexts, |context| {
+            context
+                .zome_call_cache
+                .as_ref()
+                .map(|cache| cache.hits() as u64)
+        }),
+    );
+    let fuel: Vec<Sample> = contexts
+        .iter()
+        .filter_map(|(id, context)| {
+            context
+                .zome_call_profiler
+                .as_ref()
+                .map(|profiler| (id, profiler.profiles()))
+        })
+        .flat_map(|(id, profiles)| {
+            profiles
+                .into_iter()
+                .map(move |(zome_name, fn_name, profile)| {
+                    (
+                        vec![
+                            ("instance", id.clone()),
+                            ("zome", zome_name),
+                            ("function", fn_name),
+                        ],
+                        profile.total_fuel,
+                    )
+                })
+        })
+        .collect();
+    writer.metric(
+        "holochain_zome_call_fuel_total",
+        MetricKind::Counter,
+        "Fuel consumed by the calls of each zome function, if profiled.",
+        &fuel,
+    );
+    writer.into_string()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use context_builder::ContextBuilder;
+    use interface::tests::example_config_and_instances;
+
+    #[test]
+    fn writes_text_exposition_format() {
+        let mut writer = PrometheusWriter::new();
+        writer.metric(
+            "test_total",
+            MetricKind::Counter,
+            "A test counter.",
+            &[(Vec::new(), 3)],
+        );
+        writer.metric(
+            "test_gauge",
+            MetricKind::Gauge,
+            "A test gauge.",
+            &[
+                (vec![("instance", String::from("a"))], 1),
+                (vec![("instance", String::from("b\"c"))], 2),
+            ],
+        );
+        writer.metric("test_empty", MetricKind::Gauge, "Left out.", &[]);
+        assert_eq!(
+            writer.into_string(),
+            r#"# HELP test_total A test counter.
+# TYPE test_total counter
+test_total 3
+# HELP test_gauge A test gauge.
+# TYPE test_gauge gauge
+test_gauge{instance="a"} 1
+test_gauge{instance="b\"c"} 2
+"#
+        );
+    }
+
+    #[test]
+    fn instance_stats_get_cached_until_max_age() {
+        let (_, instances) = example_config_and_instances();
+        let context = instances["test-instance-1"]
+            .read()
+            .unwrap()
+            .context()
+            .clone();
+        // has no state, so its stats are empty
+        let stateless_context = ContextBuilder::new().spawn();
+        let stats = InstanceStats::compute(&context);
+        assert!(stats.chain_length.is_some());
+        assert!(stats.dht.is_some());
+        assert_eq!(
+            InstanceStats::compute(&stateless_context),
+            InstanceStats::default()
+        );
+
+        let cache = InstanceStatsCache::new(Duration::from_secs(3600));
+        assert_eq!(cache.get("app", &context), stats);
+        assert_eq!(cache.get("app", &stateless_context), stats);
+        assert_eq!(
+            cache.get("other", &stateless_context),
+            InstanceStats::default()
+        );
+
+        let expiring_cache = InstanceStatsCache::new(Duration::from_secs(0));
+        assert_eq!(expiring_cache.get("app", &context), stats);
+        assert_eq!(
+            expiring_cache.get("app", &stateless_context),
+            InstanceStats::default()
+        );
+    }
+}
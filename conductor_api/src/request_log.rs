@@ -0,0 +1,171 @@
+//! Optional log of the zome calls that come in over an interface.
+//! Unlike the zome call audit log of an instance, it is kept per interface and records
+//! what the client sent and got back: the JSON-RPC method, the transport, how long the
+//! request took and the error code it failed with, including calls that got rejected
+//! before reaching the instance.
+
+use holochain_core::logger::{Logger, SimpleLogger};
+use holochain_core_types::{error::HolochainError, time::Iso8601};
+use jsonrpc_ws_server::jsonrpc_core::{self, Value};
+use serde_json;
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RequestStatus {
+    Success,
+    /// JSON-RPC error code and message of the response
+    Error {
+        code: i64,
+        message: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RequestLogRecord {
+    pub timestamp: Iso8601,
+    pub interface: String,
+    /// Driver of the interface, e.g. "http" or "websocket"
+    pub transport: String,
+    pub method: String,
+    /// Instance, zome and function called, None if the request did not name them
+    pub instance: Option<String>,
+    pub zome: Option<String>,
+    pub function: Option<String>,
+    pub duration_ms: u64,
+    pub status: RequestStatus,
+}
+
+/// Destination of request log records.
+pub trait RequestLogSink: Send + Sync {
+    fn append(&self, record: RequestLogRecord) -> Result<(), HolochainError>;
+}
+
+/// Keeps records in memory. Clones share the same records.
+#[derive(Clone, Default)]
+pub struct MemoryRequestLogSink {
+    records: Arc<Mutex<Vec<RequestLogRecord>>>,
+}
+
+impl MemoryRequestLogSink {
+    pub fn new() -> Self {
+        MemoryRequestLogSink::default()
+    }
+
+    pub fn records(&self) -> Vec<RequestLogRecord> {
+        self.records.lock().unwrap().clone()
+    }
+}
+
+impl RequestLogSink for MemoryRequestLogSink {
+    fn append(&self, record: RequestLogRecord) -> Result<(), HolochainError> {
+        self.records.lock().unwrap().push(record);
+        Ok(())
+    }
+}
+
+/// Appends records as JSON, one per line, to the given file.
+/// The file gets created if it does not exist.
+pub struct FileRequestLogSink {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileRequestLogSink {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        FileRequestLogSink {
+            path: path.as_ref().into(),
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+impl RequestLogSink for FileRequestLogSink {
+    fn append(&self, record: RequestLogRecord) -> Result<(), HolochainError> {
+        let line = serde_json::to_string(&record)?;
+        let _guard = self.lock.lock().unwrap();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+}
+
+/// The instance, zome and function a request is about.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RequestTarget {
+    pub instance: Option<String>,
+    pub zome: Option<String>,
+    pub function: Option<String>,
+}
+
+/// Turns handled requests of one interface into records and hands them to the sink.
+#[derive(Clone)]
+pub struct RequestLog {
+    sink: Arc<RequestLogSink>,
+    interface: String,
+    transport: String,
+    logger: Arc<Mutex<Logger>>,
+}
+
+impl RequestLog {
+    pub fn new(sink: Arc<RequestLogSink>, interface: String, transport: String) -> Self {
+        RequestLog {
+            sink,
+            interface,
+            transport,
+            logger: Arc::new(Mutex::new(SimpleLogger {})),
+        }
+    }
+
+    /// Reports records that could not be written to the given logger.
+    pub fn with_logger(mut self, logger: Arc<Mutex<Logger>>) -> Self {
+        self.logger = logger;
+        self
+    }
+
+    /// Reports that a record could not be handed to the sink.
+    pub fn log_error(&self, error: HolochainError) {
+        self.logger.lock().unwrap().log(format!(
+            "err/conductor: Could not write request log of interface '{}': {}",
+            self.interface, error
+        ));
+    }
+
+    pub fn record(
+        &self,
+        method: &str,
+        target: RequestTarget,
+        duration: Duration,
+        result: &Result<Value, jsonrpc_core::Error>,
+    ) -> Result<(), HolochainError> {
+        let duration_since_epoch = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("System time must not be before UNIX EPOCH");
+        let status = match result {
+            Ok(_) => RequestStatus::Success,
+            Err(error) => RequestStatus::Error {
+                code: error.code.code(),
+                message: error.message.clone(),
+            },
+        };
+        self.sink.append(RequestLogRecord {
+            timestamp: Iso8601::from(duration_since_epoch.as_secs()),
+            interface: self.interface.clone(),
+            transport: self.transport.clone(),
+            method: method.to_string(),
+            instance: target.instance,
+            zome: target.zome,
+            function: target.function,
+            duration_ms: duration.as_secs() * 1000 + u64::from(duration.subsec_millis()),
+            status,
+        })
+    }
+}
@@ -3,23 +3,51 @@ use config::{InterfaceConfiguration, UiBundleConfiguration, UiInterfaceConfigura
 use error::HolochainResult;
 use holochain_core_types::error::HolochainError;
 use hyper::{
-    http::{response::Builder, uri},
+    header::{HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE, VARY},
+    http::{response::Builder, uri, StatusCode},
     rt::Future,
-    server::Server,
+    server::{
+        conn::{Connection, Http},
+        Server,
+    },
+    service::Service,
     Body, Request, Response,
 };
-use hyper_staticfile::{Static, StaticFuture};
+use hyper_staticfile::{ResolveResult, Static};
+use interface_impls::bind_retry::BindRetry;
+use mime_guess;
 use std::{
+    any::Any,
     io::Error,
-    sync::mpsc::{channel, Sender},
-    thread,
+    net::{SocketAddr, TcpListener},
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, RecvTimeoutError, Sender},
+        Arc, Mutex, RwLock,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 use tokio::{
-    prelude::{future, Async, Poll},
+    net::{TcpListener as AsyncTcpListener, TcpStream},
+    prelude::{future, Async, Poll, Stream},
+    reactor::Handle,
     runtime::Runtime,
+    timer::Delay,
 };
 
 const DNA_CONFIG_ROUTE: &str = "/_dna_connections.json";
+const DNA_CONFIG_ID_PARAM: &str = "id";
+
+/// Written to connections whose request did not get answered in time.
+const REQUEST_TIMEOUT_RESPONSE: &[u8] =
+    b"HTTP/1.1 408 Request Timeout\r\nconnection: close\r\ncontent-length: 0\r\n\r\n";
+
+/// Content encodings of precompressed files we look for, in order of preference,
+/// with the extension of the precompressed sibling of the requested file.
+const PRECOMPRESSED_ENCODINGS: [(&str, &str); 2] = [("br", "br"), ("gzip", "gz")];
 
 fn redirect_request_to_root<T>(req: &mut Request<T>) {
     let mut original_parts: uri::Parts = req.uri().to_owned().into();
@@ -27,6 +55,162 @@ fn redirect_request_to_root<T>(req: &mut Request<T>) {
     *req.uri_mut() = uri::Uri::from_parts(original_parts).unwrap();
 }
 
+/// True if the given Accept-Encoding header value accepts the given encoding.
+fn accepts_encoding(accept_encoding: &str, encoding: &str) -> bool {
+    accept_encoding.split(',').any(|coding| {
+        let mut params = coding.split(';').map(str::trim);
+        let name_matches = params
+            .next()
+            .map(|name| name.eq_ignore_ascii_case(encoding))
+            .unwrap_or(false);
+        // a quality of 0 means the encoding is not acceptable
+        name_matches
+            && params.all(|param| {
+                let mut key_value = param.splitn(2, '=');
+                match (key_value.next(), key_value.next()) {
+                    (Some("q"), Some(quality)) => quality
+                        .parse::<f32>()
+                        .map(|quality| quality > 0.0)
+                        .unwrap_or(false),
+                    _ => true,
+                }
+            })
+    })
+}
+
+/// Returns the content encoding and the URI path of a precompressed sibling of the
+/// requested file if there is one the client accepts.
+fn precompressed_variant<T>(root: &Path, req: &Request<T>) -> Option<(&'static str, String)> {
+    let accept_encoding = req.headers().get(ACCEPT_ENCODING)?.to_str().ok()?;
+    let path = req.uri().path();
+    if path.ends_with('/') || path.split('/').any(|segment| segment == "..") {
+        return None;
+    }
+    PRECOMPRESSED_ENCODINGS
+        .iter()
+        .filter(|(encoding, _)| accepts_encoding(accept_encoding, encoding))
+        .map(|(encoding, extension)| (*encoding, format!("{}.{}", path, extension)))
+        .find(|(_, variant_path)| root.join(variant_path.trim_start_matches('/')).is_file())
+}
+
+/// Serves the given precompressed variant of the requested file with the content type
+/// of the requested file. Falls back to the requested file if the variant can't be served.
+fn serve_precompressed(
+    static_: Static,
+    mut req: Request<Body>,
+    encoding: &'static str,
+    variant_path: String,
+) -> StaticResponseFuture {
+    let content_type = mime_guess::guess_mime_type_opt(req.uri().path())
+        .and_then(|mime| HeaderValue::from_str(mime.as_ref()).ok());
+    let mut variant_parts: uri::Parts = req.uri().to_owned().into();
+    variant_parts.path_and_query = variant_path.parse().ok();
+    match uri::Uri::from_parts(variant_parts) {
+        Ok(variant_uri) => *req.uri_mut() = variant_uri,
+        Err(_) => return serve_static(static_, req),
+    }
+    Box::new(static_.serve(req).map(move |mut response| {
+        if response.status() == StatusCode::OK {
+            let headers = response.headers_mut();
+            headers.insert(CONTENT_ENCODING, HeaderValue::from_static(encoding));
+            headers.insert(VARY, HeaderValue::from_static("accept-encoding"));
+            match content_type {
+                Some(content_type) => headers.insert(CONTENT_TYPE, content_type),
+                None => headers.remove(CONTENT_TYPE),
+            };
+        }
+        response
+    }))
+}
+
+/// Serves the requested file, or the root if there is no such file.
+fn serve_static(static_: Static, mut req: Request<Body>) -> StaticResponseFuture {
+    Box::new(
+        hyper_staticfile::resolve(&static_.root, &req).and_then(move |result| {
+            if let ResolveResult::NotFound = result {
+                // redirect all not-found routes to the root
+                // this allows virtual routes on the front end
+                redirect_request_to_root(&mut req);
+            }
+            static_.serve(req)
+        }),
+    )
+}
+
+/// Decodes the percent-encoded bytes and the pluses standing for spaces in a query
+/// string component. Malformed escapes are kept as they are.
+fn percent_decode(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes[i] {
+            b'%' => component
+                .get(i + 1..i + 3)
+                .filter(|hex| hex.bytes().all(|digit| digit.is_ascii_hexdigit()))
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match (bytes[i], escaped) {
+            (_, Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', None) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, None) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Returns the percent-decoded value of the first query parameter with the given name.
+fn query_param(query: Option<&str>, name: &str) -> Option<String> {
+    query?
+        .split('&')
+        .filter_map(|pair| {
+            let mut key_value = pair.splitn(2, '=');
+            match (key_value.next(), key_value.next()) {
+                (Some(key), Some(value)) if percent_decode(key) == name => {
+                    Some(percent_decode(value))
+                }
+                _ => None,
+            }
+        })
+        .next()
+}
+
+/// Picks the DNA interface a request to the DNA config route asks for.
+/// Without an id query parameter that is the interface the UI interface is connected to.
+/// Returns an error if the given id does not match any of the available interfaces.
+fn select_dna_interface(
+    query: Option<&str>,
+    default: &Option<InterfaceConfiguration>,
+    available: &[InterfaceConfiguration],
+) -> Result<Option<InterfaceConfiguration>, String> {
+    match query_param(query, DNA_CONFIG_ID_PARAM) {
+        None => Ok(default.clone()),
+        Some(id) => available
+            .iter()
+            .find(|interface| interface.id == id)
+            .cloned()
+            .map(Some)
+            .ok_or_else(|| format!("No DNA interface with id \"{}\"", id)),
+    }
+}
+
+fn dna_connections_not_found_response(message: &str) -> Response<Body> {
+    Builder::new()
+        .status(StatusCode::NOT_FOUND)
+        .body(json!({ "error": message }).to_string().into())
+        .expect("unable to build response")
+}
+
 fn dna_connections_response(config: &Option<InterfaceConfiguration>) -> Response<Body> {
     let interface = match config {
         Some(config) => json!(config),
@@ -37,9 +221,21 @@ fn dna_connections_response(config: &Option<InterfaceConfiguration>) -> Response
         .expect("unable to build response")
 }
 
+fn internal_server_error_response() -> Response<Body> {
+    Builder::new()
+        .status(StatusCode::INTERNAL_SERVER_ERROR)
+        .body(Body::empty())
+        .expect("unable to build response")
+}
+
+/// Future of a static file response. The file body gets streamed in chunks
+/// once the response is ready, so it is never held in memory as a whole.
+type StaticResponseFuture = Box<Future<Item = Response<Body>, Error = Error> + Send>;
+
 enum MainFuture {
-    Static(StaticFuture<Body>),
+    Static(StaticResponseFuture),
     Config(Option<InterfaceConfiguration>),
+    ConfigNotFound(String),
 }
 
 impl Future for MainFuture {
@@ -49,63 +245,262 @@ impl Future for MainFuture {
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
         match *self {
             MainFuture::Config(ref config) => Ok(Async::Ready(dna_connections_response(config))),
+            MainFuture::ConfigNotFound(ref message) => {
+                Ok(Async::Ready(dna_connections_not_found_response(message)))
+            }
             MainFuture::Static(ref mut future) => future.poll(),
         }
     }
 }
 
+/// Handle to the config of the DNA interface a UI interface is connected to,
+/// shared between a StaticServer and its services so it can be updated while running.
+type SharedDnaInterface = Arc<RwLock<Option<InterfaceConfiguration>>>;
+
 /// Hyper `Service` implementation that serves all requests.
 struct StaticService {
     static_: Static,
-    dna_interface_config: Option<InterfaceConfiguration>,
+    dna_interface_config: SharedDnaInterface,
+    available_dna_interfaces: Vec<InterfaceConfiguration>,
 }
 
 impl StaticService {
-    fn new(path: &String, dna_interface_config: &Option<InterfaceConfiguration>) -> Self {
+    fn new(
+        path: &String,
+        dna_interface_config: &SharedDnaInterface,
+        available_dna_interfaces: &[InterfaceConfiguration],
+    ) -> Self {
         StaticService {
             static_: Static::new(path),
-            dna_interface_config: dna_interface_config.to_owned(),
+            dna_interface_config: dna_interface_config.clone(),
+            available_dna_interfaces: available_dna_interfaces.to_owned(),
         }
     }
 }
 
-impl hyper::service::Service for StaticService {
+impl Service for StaticService {
     type ReqBody = Body;
     type ResBody = Body;
     type Error = Error;
     type Future = MainFuture;
 
-    fn call(&mut self, mut req: Request<Body>) -> MainFuture {
+    fn call(&mut self, req: Request<Body>) -> MainFuture {
         match req.uri().path() {
-            DNA_CONFIG_ROUTE => MainFuture::Config(self.dna_interface_config.clone()),
+            DNA_CONFIG_ROUTE => match select_dna_interface(
+                req.uri().query(),
+                &self.dna_interface_config.read().unwrap(),
+                &self.available_dna_interfaces,
+            ) {
+                Ok(config) => MainFuture::Config(config),
+                Err(message) => MainFuture::ConfigNotFound(message),
+            },
             _ => {
-                MainFuture::Static(
-                    hyper_staticfile::resolve(&self.static_.root, &req)
-                        .map(|result| {
-                            match result {
-                                hyper_staticfile::ResolveResult::NotFound => {
-                                    // redirect all not-found routes to the root
-                                    // this allows virtual routes on the front end
-                                    redirect_request_to_root(&mut req);
-                                    self.static_.serve(req)
-                                }
-                                _ => self.static_.serve(req),
-                            }
-                        })
-                        .wait()
-                        .unwrap(),
-                )
+                let static_ = Static::new(self.static_.root.clone());
+                let response = match precompressed_variant(&self.static_.root, &req) {
+                    Some((encoding, variant_path)) => {
+                        serve_precompressed(static_, req, encoding, variant_path)
+                    }
+                    None => serve_static(static_, req),
+                };
+                MainFuture::Static(Box::new(response.or_else(|error| {
+                    // answer with an error response instead of dropping the
+                    // connection so that IO errors don't take down the service
+                    notify(format!("err/static_file_server: {}", error));
+                    Ok::<_, Error>(internal_server_error_response())
+                })))
             }
         }
     }
 }
 
+/// Wraps a StaticService and records when it has produced a response.
+struct TimedService {
+    inner: StaticService,
+    responded: Arc<AtomicBool>,
+}
+
+impl Service for TimedService {
+    type ReqBody = Body;
+    type ResBody = Body;
+    type Error = Error;
+    type Future = StaticResponseFuture;
+
+    fn call(&mut self, req: Request<Body>) -> StaticResponseFuture {
+        let responded = self.responded.clone();
+        Box::new(
+            self.inner
+                .call(req)
+                .inspect(move |_| responded.store(true, Ordering::SeqCst)),
+        )
+    }
+}
+
+/// Serves the single request of a connection and answers with 408 Request Timeout
+/// if it did not get read and answered before the deadline.
+/// Once the response is ready, its body gets streamed without a time limit.
+struct TimedConnection {
+    connection: Option<Connection<TcpStream, TimedService>>,
+    responded: Arc<AtomicBool>,
+    deadline: Delay,
+}
+
+impl Future for TimedConnection {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        match self.connection.as_mut().map(|connection| connection.poll()) {
+            Some(Ok(Async::NotReady)) => (),
+            None | Some(Ok(Async::Ready(()))) => return Ok(Async::Ready(())),
+            Some(Err(error)) => {
+                notify(format!("err/static_file_server: {}", error));
+                return Err(());
+            }
+        }
+        if self.responded.load(Ordering::SeqCst) {
+            return Ok(Async::NotReady);
+        }
+        if let Ok(Async::NotReady) = self.deadline.poll() {
+            return Ok(Async::NotReady);
+        }
+
+        // Nothing has been written to the connection before the response is ready,
+        // so we can still answer it ourselves.
+        notify("warn/static_file_server: Request timed out".to_string());
+        let io = self.connection.take().unwrap().into_parts().io;
+        tokio::spawn(tokio::io::write_all(io, REQUEST_TIMEOUT_RESPONSE).then(|_| Ok(())));
+        Ok(Async::Ready(()))
+    }
+}
+
+type ServerFuture = Box<Future<Item = (), Error = ()> + Send>;
+
+fn serve<F>(listener: TcpListener, new_service: F) -> Result<ServerFuture, String>
+where
+    F: Fn() -> StaticService + Send + Sync + 'static,
+{
+    let server = Server::from_tcp(listener).map_err(|e| e.to_string())?;
+    Ok(Box::new(
+        server
+            .serve(move || future::ok::<_, Error>(new_service()))
+            .map_err(|e| notify(format!("server error: {}", e))),
+    ))
+}
+
+/// Like serve() but every connection has to send its request and get it answered
+/// within the given timeout, so stalled clients can't tie up the server.
+/// Connections are not kept alive since they could stall between requests.
+fn serve_with_request_timeout<F>(
+    listener: TcpListener,
+    new_service: F,
+    timeout: Duration,
+) -> Result<ServerFuture, String>
+where
+    F: Fn() -> StaticService + Send + Sync + 'static,
+{
+    let listener =
+        AsyncTcpListener::from_std(listener, &Handle::default()).map_err(|e| e.to_string())?;
+    let mut http = Http::new();
+    http.keep_alive(false);
+    Ok(Box::new(
+        listener
+            .incoming()
+            .then(move |socket| {
+                match socket {
+                    Ok(socket) => {
+                        let responded = Arc::new(AtomicBool::new(false));
+                        let service = TimedService {
+                            inner: new_service(),
+                            responded: responded.clone(),
+                        };
+                        tokio::spawn(TimedConnection {
+                            connection: Some(http.serve_connection(socket, service)),
+                            responded,
+                            deadline: Delay::new(Instant::now() + timeout),
+                        });
+                    }
+                    Err(error) => notify(format!("err/static_file_server: {}", error)),
+                }
+                Ok::<_, ()>(())
+            })
+            .for_each(|_| Ok(())),
+    ))
+}
+
+/// True if the given directory does not exist, can't be read or has no entries.
+/// An empty path stands for the current directory, like it does for Static.
+fn is_missing_or_empty(dir: &str) -> bool {
+    let dir = if dir.is_empty() { "." } else { dir };
+    std::fs::read_dir(dir)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(true)
+}
+
+/// Returns the directory to serve for the given bundle: its root dir, or its fallback dir
+/// if one is set and the root dir is missing or empty.
+fn served_dir(bundle_config: &UiBundleConfiguration) -> String {
+    match bundle_config.fallback_dir {
+        Some(ref fallback_dir) if is_missing_or_empty(&bundle_config.root_dir) => {
+            notify(format!(
+                "warn/static_file_server: \"{}\" does not exist or is empty, \
+                 serving fallback \"{}\" instead",
+                bundle_config.root_dir, fallback_dir
+            ));
+            fallback_dir.clone()
+        }
+        _ => bundle_config.root_dir.clone(),
+    }
+}
+
+/// Creates the services that answer the requests of one connection.
+type ServiceFactory = Arc<Fn() -> StaticService + Send + Sync>;
+
+/// Builds the server future from the listener, the service factory and the request timeout.
+/// Only replaced in tests to make the server thread fail.
+type ServeFn = Arc<
+    Fn(TcpListener, ServiceFactory, Option<Duration>) -> Result<ServerFuture, String> + Send + Sync,
+>;
+
+fn serve_listener(
+    listener: TcpListener,
+    new_service: ServiceFactory,
+    request_timeout: Option<Duration>,
+) -> Result<ServerFuture, String> {
+    let new_service = move || new_service();
+    match request_timeout {
+        Some(timeout) => serve_with_request_timeout(listener, new_service, timeout),
+        None => serve(listener, new_service),
+    }
+}
+
+/// How often the server thread checks if the server stopped on its own,
+/// e.g. because it panicked on a worker thread of its runtime.
+const SERVER_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Message of a caught panic, if it was created with a message.
+fn panic_message(panic: Box<Any + Send>) -> String {
+    match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => match panic.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown panic".to_string(),
+        },
+    }
+}
+
 pub struct StaticServer {
     shutdown_signal: Option<Sender<()>>,
+    server_thread: Option<JoinHandle<()>>,
     config: UiInterfaceConfiguration,
     bundle_config: UiBundleConfiguration,
-    connected_dna_interface: Option<InterfaceConfiguration>,
-    running: bool,
+    connected_dna_interface: SharedDnaInterface,
+    available_dna_interfaces: Vec<InterfaceConfiguration>,
+    bind_retry: BindRetry,
+    /// set to false by the server thread if it stops on its own, e.g. after a panic
+    /// while setting up or serving
+    running: Arc<AtomicBool>,
+    last_error: Arc<Mutex<Option<String>>>,
+    serve_fn: ServeFn,
 }
 
 impl StaticServer {
@@ -116,49 +511,154 @@ impl StaticServer {
     ) -> Self {
         StaticServer {
             shutdown_signal: None,
+            server_thread: None,
             config,
             bundle_config,
-            connected_dna_interface,
-            running: false,
+            connected_dna_interface: Arc::new(RwLock::new(connected_dna_interface)),
+            available_dna_interfaces: Vec::new(),
+            bind_retry: BindRetry::none(),
+            running: Arc::new(AtomicBool::new(false)),
+            last_error: Arc::new(Mutex::new(None)),
+            serve_fn: Arc::new(serve_listener),
         }
     }
 
+    /// Sets the DNA interfaces a UI can select with the id query parameter of
+    /// the DNA config route. Without, only the connected interface is served.
+    /// Admin interfaces are left out so UIs can't pick up access to the admin functions.
+    pub fn with_dna_interfaces(mut self, dna_interfaces: Vec<InterfaceConfiguration>) -> Self {
+        self.available_dna_interfaces = dna_interfaces
+            .into_iter()
+            .filter(|interface| !interface.admin)
+            .collect();
+        self
+    }
+
+    /// Makes start() wait for the port to become free according to the given BindRetry
+    /// instead of failing right away if it is still in use.
+    pub fn with_bind_retry(mut self, bind_retry: BindRetry) -> Self {
+        self.bind_retry = bind_retry;
+        self
+    }
+
+    /// Replaces the config of the connected DNA interface, e.g. after its port changed.
+    /// A running server serves the new config from the DNA config route right away.
+    pub fn update_dna_interface(&self, new_config: Option<InterfaceConfiguration>) {
+        *self.connected_dna_interface.write().unwrap() = new_config;
+    }
+
+    /// False once the server thread stopped, also if it did not get stopped through stop()
+    /// but died on its own.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Why the server thread failed most recently, e.g. the message it panicked with.
+    /// Kept across restarts and stop().
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+
     pub fn start(&mut self) -> HolochainResult<()> {
-        let addr = ([127, 0, 0, 1], self.config.port).into();
+        let addr: SocketAddr = ([127, 0, 0, 1], self.config.port).into();
+        let listener = self
+            .bind_retry
+            .bind(|| TcpListener::bind(addr))
+            .map_err(|e| HolochainError::IoError(e.to_string()))?;
 
         let (tx, rx) = channel::<()>();
         self.shutdown_signal = Some(tx);
-        let static_path = self.bundle_config.root_dir.to_owned();
-        let dna_interfaces = self.connected_dna_interface.to_owned();
+        let static_path = served_dir(&self.bundle_config);
+        let dna_interface = self.connected_dna_interface.clone();
+        let available_dna_interfaces = self.available_dna_interfaces.to_owned();
+        let request_timeout = self.config.request_timeout_ms.map(Duration::from_millis);
+        let max_restarts = self.config.max_restarts.unwrap_or(0);
+        let serve_fn = self.serve_fn.clone();
+        let running = self.running.clone();
+        let last_error = self.last_error.clone();
 
         notify(format!(
             "About to serve path \"{}\" at http://{}",
-            &self.bundle_config.root_dir, &addr
+            &static_path, &addr
         ));
-        self.running = true;
-
-        let _server = thread::spawn(move || {
-            let server = Server::bind(&addr)
-                .serve(move || {
-                    future::ok::<_, Error>(StaticService::new(&static_path, &dna_interfaces))
-                })
-                .map_err(|e| notify(format!("server error: {}", e)));
-
-            notify(format!("Listening on http://{}", addr));
-            let mut rt = Runtime::new().unwrap();
-            rt.spawn(server);
-            let _ = rx.recv();
-        });
+        self.running.store(true, Ordering::SeqCst);
+
+        self.server_thread = Some(thread::spawn(move || {
+            let new_service: ServiceFactory = Arc::new(move || {
+                StaticService::new(&static_path, &dna_interface, &available_dna_interfaces)
+            });
+            let record_error = |error: String| {
+                notify(format!("server error: {}", error));
+                *last_error.lock().unwrap() = Some(error);
+            };
+            let mut restarts = 0;
+            loop {
+                // Ok(None) once stop() got called, Ok(Some(_)) if the server stopped on its own
+                let result =
+                    panic::catch_unwind(AssertUnwindSafe(|| -> Result<Option<String>, String> {
+                        let listener = listener.try_clone().map_err(|e| e.to_string())?;
+                        let server = serve_fn(listener, new_service.clone(), request_timeout)?;
+                        notify(format!("Listening on http://{}", addr));
+                        let mut rt = Runtime::new().map_err(|e| e.to_string())?;
+                        let (stopped_tx, stopped_rx) = channel::<String>();
+                        rt.spawn(AssertUnwindSafe(server).catch_unwind().then(move |result| {
+                            let _ = stopped_tx.send(match result {
+                                Ok(_) => "server stopped unexpectedly".to_string(),
+                                Err(panic) => {
+                                    format!("server panicked: {}", panic_message(panic))
+                                }
+                            });
+                            Ok::<(), ()>(())
+                        }));
+                        loop {
+                            match rx.recv_timeout(SERVER_CHECK_INTERVAL) {
+                                Err(RecvTimeoutError::Timeout) => (),
+                                // stop() got called or the StaticServer dropped
+                                _ => return Ok(None),
+                            }
+                            if let Ok(error) = stopped_rx.try_recv() {
+                                return Ok(Some(error));
+                            }
+                        }
+                    }));
+                let error = match result {
+                    Ok(Ok(None)) => break,
+                    Ok(Err(error)) => {
+                        record_error(error);
+                        break;
+                    }
+                    Ok(Ok(Some(error))) => error,
+                    Err(panic) => format!("server thread panicked: {}", panic_message(panic)),
+                };
+                record_error(error);
+                if restarts >= max_restarts {
+                    break;
+                }
+                restarts += 1;
+                notify(format!(
+                    "Restarting server at http://{} ({}/{})",
+                    addr, restarts, max_restarts
+                ));
+            }
+            running.store(false, Ordering::SeqCst);
+        }));
         Ok(())
     }
 
+    /// Signals the server thread to shut down and waits until it is done,
+    /// so the port is free again once this returns.
     pub fn stop(&mut self) -> HolochainResult<()> {
         match self.shutdown_signal.clone() {
             Some(shutdown_signal) => {
-                shutdown_signal
-                    .send(())
-                    .map_err(|e| HolochainError::ErrorGeneric(e.to_string()))?;
-                self.running = false;
+                // fails if the server thread already stopped on its own,
+                // which is fine since there is nothing to signal then
+                let _ = shutdown_signal.send(());
+                if let Some(server_thread) = self.server_thread.take() {
+                    server_thread.join().map_err(|_| {
+                        HolochainError::ErrorGeneric("server thread panicked".into())
+                    })?;
+                }
+                self.running.store(false, Ordering::SeqCst);
                 self.shutdown_signal = None;
                 Ok(())
             }
@@ -172,6 +672,12 @@ pub mod tests {
     use super::*;
     use crate::config::InterfaceDriver;
     use reqwest;
+    extern crate tempfile;
+    use self::tempfile::tempdir;
+    use std::{
+        io::{Read, Write},
+        sync::atomic::AtomicUsize,
+    };
 
     #[test]
     pub fn test_build_server() {
@@ -179,6 +685,7 @@ pub mod tests {
             id: "bundle id".to_string(),
             root_dir: "".to_string(),
             hash: None,
+            fallback_dir: None,
         };
 
         let test_config = UiInterfaceConfiguration {
@@ -186,6 +693,8 @@ pub mod tests {
             bundle: "a bundle".to_string(),
             port: 3000,
             dna_interface: Some("interface".to_string()),
+            request_timeout_ms: None,
+            max_restarts: None,
         };
 
         let test_dna_interface = InterfaceConfiguration {
@@ -193,15 +702,26 @@ pub mod tests {
             admin: true,
             driver: InterfaceDriver::Http { port: 3000 },
             instances: Vec::new(),
+            max_zome_call_threads: None,
+            max_queued_zome_calls: None,
+            allowed_functions: Vec::new(),
+            shutdown_grace_period_ms: None,
+            request_log: None,
         };
 
+        let other_dna_interface = test_interface("other", 3001);
+
         let mut static_server = StaticServer::from_configs(
             test_config,
             test_bundle_config,
             Some(test_dna_interface.clone()),
-        );
+        )
+        .with_dna_interfaces(vec![
+            test_dna_interface.clone(),
+            other_dna_interface.clone(),
+        ]);
         assert_eq!(static_server.start(), Ok(()));
-        assert_eq!(static_server.running, true);
+        assert!(static_server.is_running());
 
         let get_result: serde_json::Value =
             reqwest::get("http://localhost:3000/_dna_connections.json")
@@ -211,7 +731,486 @@ pub mod tests {
 
         assert_eq!(get_result, json!({ "dna_interface": test_dna_interface }));
 
+        let get_result: serde_json::Value =
+            reqwest::get("http://localhost:3000/_dna_connections.json?id=other")
+                .expect("Could not make request")
+                .json()
+                .expect("response body is not valid json");
+        assert_eq!(get_result, json!({ "dna_interface": other_dna_interface }));
+
+        // admin interfaces can't be selected
+        let response = reqwest::get("http://localhost:3000/_dna_connections.json?id=interface")
+            .expect("Could not make request");
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        let response = reqwest::get("http://localhost:3000/_dna_connections.json?id=unknown")
+            .expect("Could not make request");
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        assert_eq!(static_server.stop(), Ok(()));
+        assert!(!static_server.is_running());
+    }
+
+    fn test_interface(id: &str, port: u16) -> InterfaceConfiguration {
+        InterfaceConfiguration {
+            id: id.to_string(),
+            admin: false,
+            driver: InterfaceDriver::Websocket { port },
+            instances: Vec::new(),
+            max_zome_call_threads: None,
+            max_queued_zome_calls: None,
+            allowed_functions: Vec::new(),
+            shutdown_grace_period_ms: None,
+            request_log: None,
+        }
+    }
+
+    #[test]
+    pub fn test_select_dna_interface_defaults_to_connected_interface() {
+        let connected = test_interface("connected", 3001);
+        let available = vec![connected.clone(), test_interface("other", 3002)];
+        assert_eq!(
+            select_dna_interface(None, &Some(connected.clone()), &available),
+            Ok(Some(connected.clone()))
+        );
+        assert_eq!(
+            select_dna_interface(Some("foo=bar"), &Some(connected.clone()), &available),
+            Ok(Some(connected))
+        );
+        assert_eq!(select_dna_interface(None, &None, &available), Ok(None));
+    }
+
+    #[test]
+    pub fn test_select_dna_interface_by_id() {
+        let connected = test_interface("connected", 3001);
+        let other = test_interface("other", 3002);
+        let available = vec![connected.clone(), other.clone()];
+        assert_eq!(
+            select_dna_interface(Some("id=other"), &Some(connected.clone()), &available),
+            Ok(Some(other.clone()))
+        );
+        assert_eq!(
+            select_dna_interface(Some("foo=bar&id=other"), &None, &available),
+            Ok(Some(other))
+        );
+    }
+
+    #[test]
+    pub fn test_query_params_get_percent_decoded() {
+        assert_eq!(
+            query_param(Some("id=my%20interface%2F1"), "id"),
+            Some("my interface/1".to_string())
+        );
+        assert_eq!(
+            query_param(Some("foo=bar&%69d=my+interface"), "id"),
+            Some("my interface".to_string())
+        );
+        assert_eq!(
+            query_param(Some("id=100%25%zz%"), "id"),
+            Some("100%%zz%".to_string())
+        );
+    }
+
+    #[test]
+    pub fn test_select_dna_interface_with_unknown_id() {
+        let connected = test_interface("connected", 3001);
+        assert_eq!(
+            select_dna_interface(Some("id=unknown"), &Some(connected.clone()), &[connected]),
+            Err("No DNA interface with id \"unknown\"".to_string())
+        );
+        let response = dna_connections_not_found_response("No DNA interface with id \"unknown\"");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    pub fn test_update_dna_interface_while_running() {
+        let mut static_server = StaticServer::from_configs(
+            UiInterfaceConfiguration {
+                id: "updated interface".to_string(),
+                bundle: "updated bundle".to_string(),
+                port: 3033,
+                dna_interface: Some("connected".to_string()),
+                request_timeout_ms: None,
+                max_restarts: None,
+            },
+            UiBundleConfiguration {
+                id: "updated bundle".to_string(),
+                root_dir: "".to_string(),
+                hash: None,
+                fallback_dir: None,
+            },
+            Some(test_interface("connected", 3001)),
+        );
+        assert_eq!(static_server.start(), Ok(()));
+
+        let get_dna_connections = || -> serde_json::Value {
+            reqwest::get("http://localhost:3033/_dna_connections.json")
+                .expect("Could not make request")
+                .json()
+                .expect("response body is not valid json")
+        };
+        assert_eq!(
+            get_dna_connections(),
+            json!({ "dna_interface": test_interface("connected", 3001) })
+        );
+
+        let reconfigured = test_interface("connected", 3005);
+        static_server.update_dna_interface(Some(reconfigured.clone()));
+        assert_eq!(
+            get_dna_connections(),
+            json!({ "dna_interface": reconfigured })
+        );
+
+        static_server.update_dna_interface(None);
+        assert_eq!(get_dna_connections(), json!({ "dna_interface": null }));
+
+        assert_eq!(static_server.stop(), Ok(()));
+    }
+
+    #[test]
+    pub fn test_large_files_get_streamed() {
+        let root_dir = tempdir().expect("Could not create temp dir");
+        let content: Vec<u8> = (0..16 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        std::fs::write(root_dir.path().join("large.bin"), &content)
+            .expect("Could not write test file");
+
+        let mut static_server = StaticServer::from_configs(
+            UiInterfaceConfiguration {
+                id: "large files".to_string(),
+                bundle: "large bundle".to_string(),
+                port: 3030,
+                dna_interface: None,
+                request_timeout_ms: None,
+                max_restarts: None,
+            },
+            UiBundleConfiguration {
+                id: "large bundle".to_string(),
+                root_dir: root_dir.path().to_str().unwrap().to_string(),
+                hash: None,
+                fallback_dir: None,
+            },
+            None,
+        );
+        assert_eq!(static_server.start(), Ok(()));
+
+        let mut response =
+            reqwest::get("http://localhost:3030/large.bin").expect("Could not make request");
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let mut first_chunk = vec![0; 64 * 1024];
+        response
+            .read_exact(&mut first_chunk)
+            .expect("Could not read first chunk");
+        assert_eq!(first_chunk[..], content[..first_chunk.len()]);
+
+        // the large response is still in flight but other requests get served
+        let get_result: serde_json::Value =
+            reqwest::get("http://localhost:3030/_dna_connections.json")
+                .expect("Could not make request")
+                .json()
+                .expect("response body is not valid json");
+        assert_eq!(get_result, json!({ "dna_interface": null }));
+
+        let mut rest = Vec::new();
+        response
+            .read_to_end(&mut rest)
+            .expect("Could not read rest of the response");
+        first_chunk.append(&mut rest);
+        assert_eq!(first_chunk, content);
+
+        assert_eq!(static_server.stop(), Ok(()));
+    }
+
+    #[test]
+    pub fn test_io_errors_get_answered_with_500() {
+        let root_dir = tempdir().expect("Could not create temp dir");
+        // a file can't be read as a directory, so resolving anything in it fails
+        let root_file = root_dir.path().join("not_a_directory");
+        std::fs::write(&root_file, "").expect("Could not write test file");
+
+        let mut static_server = StaticServer::from_configs(
+            UiInterfaceConfiguration {
+                id: "broken root".to_string(),
+                bundle: "broken bundle".to_string(),
+                port: 3031,
+                dna_interface: None,
+                request_timeout_ms: None,
+                max_restarts: None,
+            },
+            UiBundleConfiguration {
+                id: "broken bundle".to_string(),
+                root_dir: root_file.to_str().unwrap().to_string(),
+                hash: None,
+                fallback_dir: None,
+            },
+            None,
+        );
+        assert_eq!(static_server.start(), Ok(()));
+
+        // the server stays up after the first error
+        for _ in 0..2 {
+            let response =
+                reqwest::get("http://localhost:3031/index.html").expect("Could not make request");
+            assert_eq!(
+                response.status(),
+                reqwest::StatusCode::INTERNAL_SERVER_ERROR
+            );
+        }
+
+        assert_eq!(static_server.stop(), Ok(()));
+    }
+
+    #[test]
+    pub fn test_stalled_requests_get_aborted_after_timeout() {
+        let mut static_server = StaticServer::from_configs(
+            UiInterfaceConfiguration {
+                id: "timeout".to_string(),
+                bundle: "timeout bundle".to_string(),
+                port: 3034,
+                dna_interface: None,
+                request_timeout_ms: Some(300),
+                max_restarts: None,
+            },
+            UiBundleConfiguration {
+                id: "timeout bundle".to_string(),
+                root_dir: "".to_string(),
+                hash: None,
+                fallback_dir: None,
+            },
+            None,
+        );
+        assert_eq!(static_server.start(), Ok(()));
+
+        // a client that sends only part of the request head and then stalls
+        let mut stream = std::net::TcpStream::connect("127.0.0.1:3034").expect("Could not connect");
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        let started = Instant::now();
+        stream
+            .write_all(b"GET /_dna_connections.json HTTP/1.1\r\nHost: local")
+            .expect("Could not write partial request");
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .expect("Connection did not get closed");
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        assert!(
+            response.starts_with("HTTP/1.1 408 Request Timeout"),
+            "unexpected response: {}",
+            response
+        );
+
+        // requests that get sent in time still get served
+        let get_result: serde_json::Value =
+            reqwest::get("http://localhost:3034/_dna_connections.json")
+                .expect("Could not make request")
+                .json()
+                .expect("response body is not valid json");
+        assert_eq!(get_result, json!({ "dna_interface": null }));
+
+        assert_eq!(static_server.stop(), Ok(()));
+    }
+
+    #[test]
+    pub fn test_fallback_dir_gets_served_if_root_dir_is_missing() {
+        let temp_dir = tempdir().expect("Could not create temp dir");
+        let root_dir = temp_dir.path().join("missing");
+        let fallback_dir = tempdir().expect("Could not create temp dir");
+        std::fs::write(fallback_dir.path().join("index.html"), "fallback")
+            .expect("Could not write file");
+        let bundle_config = UiBundleConfiguration {
+            id: "fallback bundle".to_string(),
+            root_dir: root_dir.to_str().unwrap().to_string(),
+            hash: None,
+            fallback_dir: Some(fallback_dir.path().to_str().unwrap().to_string()),
+        };
+        let interface_config = UiInterfaceConfiguration {
+            id: "fallback".to_string(),
+            bundle: "fallback bundle".to_string(),
+            port: 3035,
+            dna_interface: None,
+            request_timeout_ms: None,
+            max_restarts: None,
+        };
+        let get_index = || -> String {
+            reqwest::get("http://localhost:3035/index.html")
+                .expect("Could not make request")
+                .text()
+                .expect("Could not read response body")
+        };
+
+        let mut static_server =
+            StaticServer::from_configs(interface_config.clone(), bundle_config.clone(), None);
+        assert_eq!(static_server.start(), Ok(()));
+        assert_eq!(get_index(), "fallback");
+        assert_eq!(static_server.stop(), Ok(()));
+
+        // the fallback gets ignored once the root dir has content
+        std::fs::create_dir(&root_dir).expect("Could not create dir");
+        std::fs::write(root_dir.join("index.html"), "primary").expect("Could not write file");
+        let mut static_server = StaticServer::from_configs(interface_config, bundle_config, None);
+        assert_eq!(static_server.start(), Ok(()));
+        assert_eq!(get_index(), "primary");
+        assert_eq!(static_server.stop(), Ok(()));
+    }
+
+    #[test]
+    pub fn test_server_thread_panics_get_caught() {
+        let root_dir = tempdir().expect("Could not create temp dir");
+        std::fs::write(root_dir.path().join("index.html"), "index").expect("Could not write file");
+        let bundle_config = UiBundleConfiguration {
+            id: "panicking bundle".to_string(),
+            root_dir: root_dir.path().to_str().unwrap().to_string(),
+            hash: None,
+            fallback_dir: None,
+        };
+        let interface_config = UiInterfaceConfiguration {
+            id: "panicking".to_string(),
+            bundle: "panicking bundle".to_string(),
+            port: 3036,
+            dna_interface: None,
+            request_timeout_ms: None,
+            max_restarts: None,
+        };
+
+        // without restarts the server stays down after a panic while serving a request
+        let mut static_server =
+            StaticServer::from_configs(interface_config.clone(), bundle_config.clone(), None);
+        static_server.serve_fn = Arc::new(
+            |listener: TcpListener, _: ServiceFactory, timeout: Option<Duration>| {
+                serve_listener(listener, Arc::new(|| panic!("injected panic")), timeout)
+            },
+        );
+        assert_eq!(static_server.start(), Ok(()));
+        let _ = reqwest::get("http://localhost:3036/index.html");
+        let started = Instant::now();
+        while static_server.is_running() && started.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!static_server.is_running());
+        assert_eq!(
+            static_server.last_error(),
+            Some("server panicked: injected panic".to_string())
+        );
+        assert_eq!(static_server.stop(), Ok(()));
+
+        // with a restart the server comes back up after the first panic
+        let mut static_server = StaticServer::from_configs(
+            UiInterfaceConfiguration {
+                max_restarts: Some(1),
+                ..interface_config
+            },
+            bundle_config,
+            None,
+        );
+        let services = Arc::new(AtomicUsize::new(0));
+        static_server.serve_fn = Arc::new(
+            move |listener: TcpListener, new_service: ServiceFactory, timeout: Option<Duration>| {
+                let services = services.clone();
+                let panicking_service: ServiceFactory = Arc::new(move || {
+                    if services.fetch_add(1, Ordering::SeqCst) == 0 {
+                        panic!("injected panic")
+                    }
+                    new_service()
+                });
+                serve_listener(listener, panicking_service, timeout)
+            },
+        );
+        assert_eq!(static_server.start(), Ok(()));
+        let _ = reqwest::get("http://localhost:3036/index.html");
+        let started = Instant::now();
+        let mut response = None;
+        while response.is_none() && started.elapsed() < Duration::from_secs(5) {
+            response = reqwest::get("http://localhost:3036/index.html")
+                .and_then(|mut response| response.text())
+                .ok();
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(response, Some("index".to_string()));
+        assert!(static_server.is_running());
+        assert_eq!(
+            static_server.last_error(),
+            Some("server panicked: injected panic".to_string())
+        );
+        assert_eq!(static_server.stop(), Ok(()));
+        assert!(!static_server.is_running());
+    }
+
+    #[test]
+    pub fn test_accepts_encoding() {
+        assert!(accepts_encoding("gzip, deflate", "gzip"));
+        assert!(accepts_encoding("br;q=1.0, GZIP;q=0.5", "gzip"));
+        assert!(!accepts_encoding("gzip;q=0", "gzip"));
+        assert!(!accepts_encoding("deflate, x-gzip", "gzip"));
+    }
+
+    #[test]
+    pub fn test_precompressed_files_get_served() {
+        let root_dir = tempdir().expect("Could not create temp dir");
+        let plain = "console.log('plain')";
+        let compressed: Vec<u8> = vec![0x1f, 0x8b, 0x08, 0x00, 1, 2, 3, 4];
+        std::fs::write(root_dir.path().join("app.js"), plain).expect("Could not write file");
+        std::fs::write(root_dir.path().join("app.js.gz"), &compressed)
+            .expect("Could not write file");
+
+        let mut static_server = StaticServer::from_configs(
+            UiInterfaceConfiguration {
+                id: "precompressed".to_string(),
+                bundle: "precompressed bundle".to_string(),
+                port: 3032,
+                dna_interface: None,
+                request_timeout_ms: None,
+                max_restarts: None,
+            },
+            UiBundleConfiguration {
+                id: "precompressed bundle".to_string(),
+                root_dir: root_dir.path().to_str().unwrap().to_string(),
+                hash: None,
+                fallback_dir: None,
+            },
+            None,
+        );
+        assert_eq!(static_server.start(), Ok(()));
+
+        // the client must not decompress on its own so we can compare the raw bytes
+        let client = reqwest::Client::builder()
+            .gzip(false)
+            .build()
+            .expect("Could not build client");
+        let mut response = client
+            .get("http://localhost:3032/app.js")
+            .header(reqwest::header::ACCEPT_ENCODING, "br;q=0, gzip")
+            .send()
+            .expect("Could not make request");
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(
+            response.headers().get(reqwest::header::CONTENT_ENCODING),
+            Some(&reqwest::header::HeaderValue::from_static("gzip"))
+        );
+        assert_eq!(
+            response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .map(|content_type| content_type.to_str().unwrap().to_string()),
+            Some(mime_guess::guess_mime_type("app.js").to_string())
+        );
+        let mut body = Vec::new();
+        response.read_to_end(&mut body).unwrap();
+        assert_eq!(body, compressed);
+
+        let mut response = client
+            .get("http://localhost:3032/app.js")
+            .header(reqwest::header::ACCEPT_ENCODING, "identity")
+            .send()
+            .expect("Could not make request");
+        assert_eq!(
+            response.headers().get(reqwest::header::CONTENT_ENCODING),
+            None
+        );
+        let mut body = String::new();
+        response.read_to_string(&mut body).unwrap();
+        assert_eq!(body, plain);
+
         assert_eq!(static_server.stop(), Ok(()));
-        assert_eq!(static_server.running, false);
     }
 }
@@ -2,7 +2,8 @@ use crate::{
     agent::state::AgentState,
     context::Context,
     network::{
-        direct_message::DirectMessage, entry_with_header::EntryWithHeader, state::NetworkState,
+        direct_message::DirectMessageEnvelope, entry_with_header::EntryWithHeader,
+        state::NetworkState,
     },
     nucleus::{
         state::{NucleusState, ValidationResult},
@@ -111,12 +112,21 @@ pub enum Action {
     /// Create a network proxy instance from the given [NetworkSettings](struct.NetworkSettings.html)
     InitNetwork(NetworkSettings),
 
+    /// Leaves the network of the instance's DNA and stops the network proxy instance.
+    ShutdownNetwork,
+
     /// Makes the network PUT the given entry to the DHT.
     /// Distinguishes between different entry types and does
     /// the right thing respectively.
     /// (only publish for AppEntryType, publish and publish_meta for links etc)
     Publish(Address),
 
+    /// Makes the network PUT all given entries to the DHT with one reduction,
+    /// still sending each entry in its own network messages.
+    /// Dispatched instead of single Publish actions if the instance batches publishes
+    /// (see [PublishBatcher](../network/publish_batch/struct.PublishBatcher.html)).
+    PublishBatch(Vec<Address>),
+
     /// Fetch an Entry on the network by address
     FetchEntry(GetEntryKey),
 
@@ -156,14 +166,16 @@ pub enum Action {
     /// Triggered when we got an answer to our initial DM.
     ResolveDirectConnection(String),
 
-    /// Makes the network module DM the source of the given entry
-    /// and prepare for receiveing an answer
-    GetValidationPackage(ChainHeader),
+    /// Makes the network module DM the source of the entry of the given header
+    /// and prepare for receiveing an answer.
+    /// The envelope holds the signed RequestValidationPackage message.
+    GetValidationPackage((ChainHeader, DirectMessageEnvelope)),
 
     /// Updates the state to hold the response that we got for
     /// our previous request for a validation package.
     /// Triggered from the network handler when we get the response.
-    HandleGetValidationPackage((Address, Option<ValidationPackage>)),
+    /// Holds an error instead if the response got rejected, e.g. for being too big.
+    HandleGetValidationPackage((Address, Result<Option<ValidationPackage>, HolochainError>)),
 
     /// Updates the state to hold the response that we got for
     /// our previous custom direct message.
@@ -223,6 +235,9 @@ pub struct GetLinksKey {
     /// The link tag
     pub tag: String,
 
+    /// Only targets of this entry type get requested, all if None
+    pub target_type: Option<String>,
+
     /// A unique ID that is used to pair the eventual result to this request
     pub id: String,
 }
@@ -234,6 +249,10 @@ pub struct GetEntryKey {
     /// The address of the entry to get
     pub address: Address,
 
+    /// Version of the entry the requester already has, see
+    /// FetchEntryData::if_newer_than. None for unconditional gets.
+    pub if_newer_than: Option<Address>,
+
     /// A unique ID that is used to pair the eventual result to this request
     pub id: String,
 }
@@ -245,8 +264,10 @@ pub struct DirectMessageData {
     /// The address of the node to send a message to
     pub address: Address,
 
-    /// The message itself
-    pub message: DirectMessage,
+    /// The message itself, signed for the node to send it to.
+    /// Signing happens before dispatching the action since it needs a round-trip
+    /// to the conductor, which must not happen in a reducer.
+    pub envelope: DirectMessageEnvelope,
 
     /// A unique message ID that is used to identify the response and attribute
     /// it to the right context
@@ -287,6 +308,7 @@ pub mod tests {
     pub fn test_action() -> Action {
         Action::FetchEntry(GetEntryKey {
             address: expected_entry_address(),
+            if_newer_than: None,
             id: String::from("test-id"),
         })
     }
@@ -305,6 +327,7 @@ pub mod tests {
     pub fn test_action_wrapper_get() -> ActionWrapper {
         ActionWrapper::new(Action::FetchEntry(GetEntryKey {
             address: expected_entry_address(),
+            if_newer_than: None,
             id: snowflake::ProcessUniqueId::new().to_string(),
         }))
     }
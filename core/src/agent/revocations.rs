@@ -0,0 +1,97 @@
+//! Index of the capability grants revoked in the agent's source chain.
+//! A grant is revoked by a deletion entry that links to it
+//! (see workflows::capability_grant::revoke_capability).
+//! Capability checks ask the index instead of scanning the chain every time. It remembers
+//! the chain top it was built for and only walks the headers committed since then,
+//! so a check costs the number of new headers instead of the length of the chain.
+
+use crate::agent::state::AgentState;
+use holochain_core_types::{
+    cas::content::{Address, AddressableContent},
+    entry::entry_type::EntryType,
+};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+#[derive(Default)]
+struct IndexContent {
+    /// chain top the revoked grants were collected up to
+    indexed_top: Option<Address>,
+    revoked: HashSet<Address>,
+}
+
+/// Revoked grants by address. Clones share the same content.
+#[derive(Clone, Default)]
+pub struct RevocationIndex {
+    content: Arc<Mutex<IndexContent>>,
+}
+
+impl RevocationIndex {
+    pub fn new() -> Self {
+        RevocationIndex::default()
+    }
+
+    /// True if the source chain of the given agent state revokes the grant with the given
+    /// address. Indexes the headers committed since the last check first.
+    pub fn is_revoked(&self, agent: &AgentState, grant: &Address) -> bool {
+        let mut content = self.content.lock().unwrap();
+        let top = agent.top_chain_header().map(|header| header.address());
+        if top != content.indexed_top {
+            let mut revoked = HashSet::new();
+            let mut reached_indexed_top = false;
+            for header in agent.iter_chain() {
+                if Some(header.address()) == content.indexed_top {
+                    reached_indexed_top = true;
+                    break;
+                }
+                if *header.entry_type() == EntryType::Deletion {
+                    revoked.extend(header.link_crud());
+                }
+            }
+            // A chain that does not contain the indexed top replaced the indexed one
+            if reached_indexed_top {
+                content.revoked.extend(revoked);
+            } else {
+                content.revoked = revoked;
+            }
+            content.indexed_top = top;
+        }
+        content.revoked.contains(grant)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        instance::tests::test_instance_and_context,
+        workflows::capability_grant::{mint_capability, revoke_capability},
+    };
+    use holochain_core_types::{
+        dna::capabilities::CapabilityType, entry::cap_entries::CapTokenGrant,
+    };
+
+    #[test]
+    fn follows_the_chain_it_gets_asked_about() {
+        let dna = test_utils::create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (_, context) =
+            test_instance_and_context(dna, None).expect("Could not initialize test instance");
+        let grant = CapTokenGrant::create(CapabilityType::Transferable, None).unwrap();
+        let token = context
+            .block_on(mint_capability(grant, &context))
+            .expect("Could not mint capability");
+        let before_revocation = context.state().unwrap().agent();
+        let index = RevocationIndex::new();
+        assert!(!index.is_revoked(&before_revocation, &token));
+
+        context
+            .block_on(revoke_capability(&token, &context))
+            .expect("Could not revoke capability");
+        assert!(index.is_revoked(&context.state().unwrap().agent(), &token));
+
+        // a chain that does not contain the indexed top, e.g. an older one, gets indexed anew
+        assert!(!index.is_revoked(&before_revocation, &token));
+    }
+}
@@ -102,6 +102,51 @@ impl AgentState {
             .iter_type(&self.top_chain_header(), &entry.entry_type())
             .find(|h| h.entry_address() == &entry.address())
     }
+
+    /// Compares this agent's source chain with the one of the given state, header by
+    /// header from the genesis on. Headers are considered the same if their addresses are.
+    pub fn diff_chain(&self, other: &AgentState) -> ChainDiff {
+        let mut ours: Vec<ChainHeader> = self.iter_chain().collect();
+        let mut theirs: Vec<ChainHeader> = other.iter_chain().collect();
+        ours.reverse();
+        theirs.reverse();
+        let common_prefix_len = ours
+            .iter()
+            .zip(theirs.iter())
+            .take_while(|(our_header, their_header)| {
+                our_header.address() == their_header.address()
+            })
+            .count();
+        ChainDiff {
+            common_prefix_len,
+            ours: ours.split_off(common_prefix_len),
+            theirs: theirs.split_off(common_prefix_len),
+        }
+    }
+}
+
+/// Result of comparing two source chains, see [AgentState::diff_chain](struct.AgentState.html#method.diff_chain).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChainDiff {
+    /// number of headers, counted from the genesis, that both chains have in common
+    pub common_prefix_len: usize,
+    /// headers of the compared chain that follow the common part, oldest first
+    pub ours: Vec<ChainHeader>,
+    /// headers of the other chain that follow the common part, oldest first
+    pub theirs: Vec<ChainHeader>,
+}
+
+impl ChainDiff {
+    /// True if both chains consist of the same headers.
+    pub fn is_identical(&self) -> bool {
+        self.ours.is_empty() && self.theirs.is_empty()
+    }
+
+    /// True if both chains have headers the other one doesn't have,
+    /// as opposed to one being a prefix of the other.
+    pub fn is_fork(&self) -> bool {
+        !self.ours.is_empty() && !self.theirs.is_empty()
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, DefaultJson)]
@@ -276,11 +321,12 @@ pub mod tests {
     };
     use holochain_core_types::{
         cas::content::AddressableContent,
-        chain_header::{test_chain_header, ChainHeader},
+        chain_header::{test_chain_header, test_provenances, ChainHeader},
         entry::{expected_entry_address, test_entry, Entry},
         error::HolochainError,
         json::JsonString,
         signature::Signature,
+        time::test_iso_8601,
     };
     use serde_json;
     use std::{
@@ -408,6 +454,63 @@ pub mod tests {
         );
     }
 
+    /// adds a header for an entry with the given content on top of `previous`
+    fn add_header(
+        chain_store: &ChainStore,
+        content: &str,
+        previous: &Option<ChainHeader>,
+    ) -> ChainHeader {
+        let entry = Entry::App("testEntryType".into(), JsonString::from(content));
+        let header = ChainHeader::new(
+            &entry.entry_type(),
+            &entry.address(),
+            &test_provenances("sig"),
+            &previous.as_ref().map(|header| header.address()),
+            &None,
+            &None,
+            &test_iso_8601(),
+        );
+        chain_store
+            .content_storage()
+            .write()
+            .unwrap()
+            .add(&header)
+            .expect("could not add header to cas");
+        header
+    }
+
+    #[test]
+    fn test_diff_chain() {
+        let chain_store = test_chain_store();
+        let genesis = add_header(&chain_store, "genesis", &None);
+        let first = add_header(&chain_store, "first", &Some(genesis.clone()));
+        let second = add_header(&chain_store, "second", &Some(first.clone()));
+        let ours_third = add_header(&chain_store, "ours third", &Some(second.clone()));
+        let theirs_third = add_header(&chain_store, "theirs third", &Some(second.clone()));
+        let theirs_fourth = add_header(&chain_store, "theirs fourth", &Some(theirs_third.clone()));
+
+        let ours = AgentState::new_with_top_chain_header(chain_store.clone(), ours_third.clone());
+        let theirs =
+            AgentState::new_with_top_chain_header(chain_store.clone(), theirs_fourth.clone());
+
+        let diff = ours.diff_chain(&theirs);
+        assert_eq!(
+            diff,
+            ChainDiff {
+                common_prefix_len: 3,
+                ours: vec![ours_third],
+                theirs: vec![theirs_third, theirs_fourth],
+            }
+        );
+        assert!(diff.is_fork());
+
+        let prefix = AgentState::new_with_top_chain_header(chain_store.clone(), second.clone());
+        let diff = prefix.diff_chain(&ours);
+        assert_eq!(diff.common_prefix_len, 3);
+        assert!(!diff.is_fork());
+        assert!(ours.diff_chain(&ours).is_identical());
+    }
+
     #[test]
     fn test_create_new_chain_header() {
         let agent_state = test_agent_state();
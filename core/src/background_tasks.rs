@@ -0,0 +1,210 @@
+use holochain_core_types::error::{HcResult, HolochainError};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Number of finished tasks that are kept for background_task_report().
+const FINISHED_TASKS_KEPT: usize = 100;
+
+/// What a background task does.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum TaskKind {
+    HoldEntry,
+    HoldLink,
+    RemoveLink,
+    /// A zome call an interface executes, see BackgroundTasks::track()
+    ZomeCall,
+    Other(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum TaskStatus {
+    Running,
+    Completed,
+    /// Failed with the given error, or panicked.
+    Failed(String),
+}
+
+/// Diagnostic information about a tracked task.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TaskInfo {
+    pub id: u64,
+    pub kind: TaskKind,
+    /// Time since the task got spawned.
+    pub age: Duration,
+    pub status: TaskStatus,
+}
+
+struct RunningTask {
+    kind: TaskKind,
+    started: Instant,
+}
+
+struct FinishedTask {
+    id: u64,
+    kind: TaskKind,
+    started: Instant,
+    status: TaskStatus,
+}
+
+#[derive(Default)]
+struct BackgroundTasksState {
+    next_id: u64,
+    running: HashMap<u64, RunningTask>,
+    /// most recently finished tasks, oldest first
+    finished: VecDeque<FinishedTask>,
+}
+
+/// Keeps track of the threads an instance spawns for background work, like holding
+/// entries received from the network, so that shutdown can wait for them.
+/// Work running on threads spawned elsewhere, like zome calls, can be tracked, too.
+/// Finished tasks remove themselves.
+///
+/// Clones share the same set of tasks, so every clone of a context sees all of them.
+#[derive(Clone, Default)]
+pub struct BackgroundTasks {
+    state: Arc<(Mutex<BackgroundTasksState>, Condvar)>,
+}
+
+/// Deregisters a task when its thread ends, even if it panicked,
+/// and records how it ended.
+struct TaskGuard {
+    tasks: BackgroundTasks,
+    id: u64,
+    /// None until the task returned
+    result: Option<HcResult<()>>,
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        let status = match self.result.take() {
+            Some(Ok(())) => TaskStatus::Completed,
+            Some(Err(error)) => TaskStatus::Failed(error.to_string()),
+            None => TaskStatus::Failed("task panicked".to_string()),
+        };
+        let (ref lock, ref condvar) = *self.tasks.state;
+        let mut state = lock.lock().unwrap();
+        if let Some(task) = state.running.remove(&self.id) {
+            state.finished.push_back(FinishedTask {
+                id: self.id,
+                kind: task.kind,
+                started: task.started,
+                status,
+            });
+            while state.finished.len() > FINISHED_TASKS_KEPT {
+                state.finished.pop_front();
+            }
+        }
+        condvar.notify_all();
+    }
+}
+
+/// Held while work tracked with BackgroundTasks::track() is running.
+/// The task counts as completed on drop, or as failed if the thread is panicking.
+pub struct TrackedTask {
+    guard: TaskGuard,
+}
+
+impl Drop for TrackedTask {
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            self.guard.result = Some(Ok(()));
+        }
+    }
+}
+
+impl BackgroundTasks {
+    pub fn new() -> Self {
+        BackgroundTasks::default()
+    }
+
+    /// Runs the given function in a new thread and tracks it until it returns.
+    /// An error it returns shows up in report().
+    pub fn spawn<F>(&self, kind: TaskKind, f: F)
+    where
+        F: FnOnce() -> HcResult<()> + Send + 'static,
+    {
+        let mut guard = self.register(kind);
+        thread::spawn(move || {
+            guard.result = Some(f());
+        });
+    }
+
+    /// Tracks work running on the current thread until the returned TrackedTask
+    /// gets dropped.
+    pub fn track(&self, kind: TaskKind) -> TrackedTask {
+        TrackedTask {
+            guard: self.register(kind),
+        }
+    }
+
+    fn register(&self, kind: TaskKind) -> TaskGuard {
+        let (ref lock, _) = *self.state;
+        let mut state = lock.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        // Registering the task before spawning makes sure it is tracked
+        // before it can deregister itself.
+        state.running.insert(
+            id,
+            RunningTask {
+                kind,
+                started: Instant::now(),
+            },
+        );
+        TaskGuard {
+            tasks: self.clone(),
+            id,
+            result: None,
+        }
+    }
+
+    /// Number of tracked tasks that have not finished yet.
+    pub fn running(&self) -> usize {
+        self.state.0.lock().unwrap().running.len()
+    }
+
+    /// Lists the running tasks and the most recently finished ones, in the order
+    /// they got spawned.
+    pub fn report(&self) -> Vec<TaskInfo> {
+        let state = self.state.0.lock().unwrap();
+        let mut report: Vec<TaskInfo> = state
+            .running
+            .iter()
+            .map(|(id, task)| TaskInfo {
+                id: *id,
+                kind: task.kind.clone(),
+                age: task.started.elapsed(),
+                status: TaskStatus::Running,
+            })
+            .chain(state.finished.iter().map(|task| TaskInfo {
+                id: task.id,
+                kind: task.kind.clone(),
+                age: task.started.elapsed(),
+                status: task.status.clone(),
+            }))
+            .collect();
+        report.sort_by_key(|task| task.id);
+        report
+    }
+
+    /// Blocks until all tracked tasks have finished, including the ones they spawned
+    /// in the meantime. Fails with HolochainError::Timeout if some are still running
+    /// after the given timeout.
+    pub fn join_all(&self, timeout: Duration) -> HcResult<()> {
+        let deadline = Instant::now() + timeout;
+        let (ref lock, ref condvar) = *self.state;
+        let mut state = lock.lock().unwrap();
+        while !state.running.is_empty() {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(HolochainError::Timeout);
+            }
+            state = condvar.wait_timeout(state, deadline - now).unwrap().0;
+        }
+        Ok(())
+    }
+}
@@ -1,8 +1,20 @@
 use crate::{
     action::ActionWrapper,
+    agent::revocations::RevocationIndex,
+    background_tasks::{BackgroundTasks, TaskInfo, TaskKind},
     instance::Observer,
     logger::Logger,
+    network::{
+        direct_message::{DirectMessageLimit, DEFAULT_REPLAY_WINDOW},
+        publish_batch::PublishBatcher,
+    },
+    nucleus::{
+        audit::ZomeCallAuditLog, call_cache::ZomeCallCache, profiler::ZomeCallProfiler,
+        ribosome::module_cache::WasmModuleCache, validation_cache::ValidationCache,
+        validation_limiter::ValidationLimiter,
+    },
     persister::Persister,
+    reduce_profiler::ReduceProfiler,
     signal::{Signal, SignalSender},
     state::State,
 };
@@ -54,6 +66,54 @@ pub struct Context {
     pub p2p_config: P2pConfig,
     pub conductor_api: Arc<RwLock<IoHandler>>,
     pub signal_tx: Option<SyncSender<Signal>>,
+    /// Bounds the number of concurrently running validation callbacks. Unlimited by default.
+    pub validation_limiter: ValidationLimiter,
+    /// Capability grants revoked in the agent's source chain.
+    pub revocations: RevocationIndex,
+    /// Time-to-live of entries held for others in the local DHT shard. Kept forever if None.
+    pub dht_cache_ttl: Option<Duration>,
+    /// How often network fetches of entries that are not held locally (as done by get_entry
+    /// and the other zome API functions) get retried after a transient network error.
+    /// Not retried by default.
+    pub network_fetch_retries: usize,
+    /// Entries bigger than this (in bytes, serialized) get published in several fragments.
+    /// No limit if None.
+    pub network_max_message_size: Option<usize>,
+    /// Direct messages whose timestamp is further off than this get rejected as replays.
+    pub direct_message_replay_window: Duration,
+    /// Caps how many custom direct messages may wait for a response at the same time.
+    /// No limit if None.
+    pub direct_message_limit: Option<DirectMessageLimit>,
+    /// Validation packages sources respond with get rejected before they get parsed if the
+    /// raw network message carrying them is bigger than this (in bytes). No limit if None.
+    pub max_validation_package_size: Option<usize>,
+    /// Gets a record of every zome call this instance executes. No audit trail if None.
+    pub zome_call_audit_log: Option<ZomeCallAuditLog>,
+    /// Collects authored entries and publishes them in batches. Every commit gets
+    /// published right away if None.
+    pub publish_batcher: Option<PublishBatcher>,
+    /// Serves repeated calls to cacheable zome functions from stored results.
+    /// Every call runs the Ribosome if None.
+    pub zome_call_cache: Option<ZomeCallCache>,
+    /// Keeps compiled wasm modules for reuse by later calls.
+    /// Zome wasm gets compiled for every call if None.
+    pub wasm_module_cache: Option<WasmModuleCache>,
+    /// Maximum number of headers get_entry returns per entry, newest first.
+    /// All headers get returned if None.
+    pub max_headers_per_entry: Option<usize>,
+    /// Serves repeated holds of the same entry with the same header from stored
+    /// validation outcomes. Every hold runs the validation if None.
+    pub validation_cache: Option<ValidationCache>,
+    /// Maximum number of bases whose links the DHT keeps indexed in memory.
+    /// Every get_links queries the meta storage if None.
+    pub link_index_size: Option<usize>,
+    /// Gets the fuel every zome call of this instance consumed. Not profiled if None.
+    pub zome_call_profiler: Option<ZomeCallProfiler>,
+    /// Gets the last reduced action type and how long each state slice took to reduce it.
+    /// Reducers are not timed if None.
+    pub reduce_profiler: Option<ReduceProfiler>,
+    /// Threads spawned with spawn_tracked() that have not finished yet.
+    background_tasks: BackgroundTasks,
 }
 
 impl Context {
@@ -111,6 +171,24 @@ impl Context {
             eav_storage: eav,
             p2p_config,
             conductor_api: Self::test_check_conductor_api(conductor_api, agent_id),
+            validation_limiter: ValidationLimiter::new(None),
+            revocations: RevocationIndex::new(),
+            dht_cache_ttl: None,
+            network_fetch_retries: 0,
+            network_max_message_size: None,
+            direct_message_replay_window: DEFAULT_REPLAY_WINDOW,
+            direct_message_limit: None,
+            zome_call_audit_log: None,
+            publish_batcher: None,
+            zome_call_cache: None,
+            wasm_module_cache: None,
+            max_headers_per_entry: None,
+            max_validation_package_size: None,
+            validation_cache: None,
+            link_index_size: None,
+            zome_call_profiler: None,
+            reduce_profiler: None,
+            background_tasks: BackgroundTasks::new(),
         }
     }
 
@@ -138,6 +216,24 @@ impl Context {
             eav_storage: eav,
             p2p_config,
             conductor_api: Self::test_check_conductor_api(None, agent_id),
+            validation_limiter: ValidationLimiter::new(None),
+            revocations: RevocationIndex::new(),
+            dht_cache_ttl: None,
+            network_fetch_retries: 0,
+            network_max_message_size: None,
+            direct_message_replay_window: DEFAULT_REPLAY_WINDOW,
+            direct_message_limit: None,
+            zome_call_audit_log: None,
+            publish_batcher: None,
+            zome_call_cache: None,
+            wasm_module_cache: None,
+            max_headers_per_entry: None,
+            max_validation_package_size: None,
+            validation_cache: None,
+            link_index_size: None,
+            zome_call_profiler: None,
+            reduce_profiler: None,
+            background_tasks: BackgroundTasks::new(),
         })
     }
 
@@ -151,6 +247,27 @@ impl Context {
         logger.log(msg.into());
     }
 
+    /// Runs the given function in a background thread that shutdown waits for,
+    /// see join_all_background().
+    pub fn spawn_tracked<F>(&self, kind: TaskKind, f: F)
+    where
+        F: FnOnce() -> HcResult<()> + Send + 'static,
+    {
+        self.background_tasks.spawn(kind, f)
+    }
+
+    /// Lists the threads started with spawn_tracked() that are still running or
+    /// finished recently, with how they ended. Meant for diagnosing stuck gossip.
+    pub fn background_task_report(&self) -> Vec<TaskInfo> {
+        self.background_tasks.report()
+    }
+
+    /// Blocks until all threads started with spawn_tracked() have finished, or fails
+    /// with HolochainError::Timeout once the given timeout has passed.
+    pub fn join_all_background(&self, timeout: Duration) -> HcResult<()> {
+        self.background_tasks.join_all(timeout)
+    }
+
     pub fn set_state(&mut self, state: Arc<RwLock<State>>) {
         self.state = Some(state);
     }
@@ -312,7 +429,9 @@ pub fn test_memory_network_config(network_name: Option<&str>) -> P2pConfig {
 pub mod tests {
     use self::tempfile::tempdir;
     use super::*;
-    use crate::{logger::test_logger, persister::SimplePersister, state::State};
+    use crate::{
+        background_tasks::TaskStatus, logger::test_logger, persister::SimplePersister, state::State,
+    };
     use holochain_cas_implementations::{cas::file::FilesystemStorage, eav::file::EavFileStorage};
     use holochain_core_types::agent::AgentId;
     use std::sync::{Arc, Mutex, RwLock};
@@ -354,6 +473,67 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn join_all_background_waits_for_tracked_tasks() {
+        let context = crate::instance::tests::test_context("alice", None);
+        let finished = Arc::new(Mutex::new(0));
+        for _ in 0..3 {
+            let finished = finished.clone();
+            context.spawn_tracked(TaskKind::Other("test".to_string()), move || {
+                std::thread::sleep(Duration::from_millis(100));
+                *finished.lock().unwrap() += 1;
+                Ok(())
+            });
+        }
+        assert_eq!(context.join_all_background(Duration::from_secs(5)), Ok(()));
+        assert_eq!(*finished.lock().unwrap(), 3);
+        assert_eq!(context.background_tasks.running(), 0);
+    }
+
+    #[test]
+    fn background_task_report_shows_failed_tasks() {
+        let context = crate::instance::tests::test_context("alice", None);
+        context.spawn_tracked(TaskKind::HoldEntry, || {
+            Err(HolochainError::ErrorGeneric("could not hold".to_string()))
+        });
+        context.spawn_tracked(TaskKind::HoldLink, || {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(())
+        });
+        std::thread::sleep(Duration::from_millis(100));
+
+        let report = context.background_task_report();
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].kind, TaskKind::HoldEntry);
+        assert_eq!(
+            report[0].status,
+            TaskStatus::Failed("could not hold".to_string())
+        );
+        assert_eq!(report[1].kind, TaskKind::HoldLink);
+        assert_eq!(report[1].status, TaskStatus::Running);
+        assert!(report[1].age >= Duration::from_millis(100));
+
+        assert_eq!(context.join_all_background(Duration::from_secs(5)), Ok(()));
+        assert_eq!(
+            context.background_task_report()[1].status,
+            TaskStatus::Completed
+        );
+    }
+
+    #[test]
+    fn join_all_background_times_out() {
+        let context = crate::instance::tests::test_context("alice", None);
+        context.spawn_tracked(TaskKind::Other("test".to_string()), || {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(())
+        });
+        assert_eq!(
+            context.join_all_background(Duration::from_millis(10)),
+            Err(HolochainError::Timeout)
+        );
+        assert_eq!(context.join_all_background(Duration::from_secs(5)), Ok(()));
+    }
+
     #[test]
     #[should_panic]
     #[cfg(not(windows))] // RwLock does not panic on windows since mutexes are recursive
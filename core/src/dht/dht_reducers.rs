@@ -59,10 +59,17 @@ pub(crate) fn reduce_hold_entry(
     action_wrapper: &ActionWrapper,
 ) -> Option<DhtStore> {
     match action_wrapper.action().clone() {
-        Action::Commit((entry, _)) => reduce_store_entry_common(context, old_store, &entry),
+        Action::Commit((entry, _)) => {
+            reduce_store_entry_common(context, old_store, &entry).map(|mut state| {
+                state.mark_as_authored(&entry.address());
+                state
+            })
+        }
         Action::Hold(EntryWithHeader { entry, header }) => {
-            reduce_store_entry_common(context.clone(), old_store, &entry).and_then(|state| {
+            reduce_store_entry_common(context.clone(), old_store, &entry).and_then(|mut state| {
                 state.add_header_for_entry(&entry, &header).ok()?;
+                state.mark_as_cached(&entry.address());
+                evict_expired_entries(&context, &mut state);
                 Some(state)
             })
         }
@@ -70,6 +77,24 @@ pub(crate) fn reduce_hold_entry(
     }
 }
 
+/// Holding new entries is when the cache grows, so that is when expired ones get swept.
+fn evict_expired_entries(context: &Arc<Context>, store: &mut DhtStore) {
+    match store.evict_expired_entries() {
+        Ok(evicted) => {
+            if !evicted.is_empty() {
+                context.log(format!(
+                    "debug/dht: evicted {} expired cache entries",
+                    evicted.len()
+                ));
+            }
+        }
+        Err(err) => context.log(format!(
+            "err/dht: evicting expired cache entries failed: {:?}",
+            err
+        )),
+    }
+}
+
 fn reduce_store_entry_common(
     context: Arc<Context>,
     old_store: &DhtStore,
@@ -136,6 +161,9 @@ pub(crate) fn reduce_add_link(
         eav.map(|e| {
             let storage = new_store.meta_storage();
             let result = storage.write().unwrap().add_eavi(&e);
+            if result.is_ok() {
+                new_store.index_link(&e);
+            }
             new_store
                 .actions_mut()
                 .insert(action_wrapper.clone(), result.map(|_| link.base().clone()));
@@ -173,6 +201,9 @@ pub(crate) fn reduce_remove_link(
         eav.map(|e| {
             let storage = new_store.meta_storage();
             let result = storage.write().unwrap().add_eavi(&e);
+            if result.is_ok() {
+                new_store.index_link(&e);
+            }
             new_store
                 .actions_mut()
                 .insert(action_wrapper.clone(), result.map(|_| link.base().clone()));
@@ -344,7 +375,7 @@ pub mod tests {
     use crate::{
         action::{Action, ActionWrapper},
         dht::{
-            dht_reducers::{reduce, reduce_hold_entry},
+            dht_reducers::{reduce, reduce_add_link, reduce_hold_entry, reduce_remove_link},
             dht_store::DhtStore,
         },
         instance::tests::test_context,
@@ -355,7 +386,7 @@ pub mod tests {
         cas::content::AddressableContent,
         chain_header::test_chain_header,
         eav::{Attribute, EavFilter, EaviQuery, IndexFilter},
-        entry::{test_entry, test_sys_entry, Entry},
+        entry::{test_entry, test_entry_a, test_entry_b, test_sys_entry, Entry},
         link::Link,
     };
     use std::{
@@ -536,6 +567,52 @@ pub mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn link_index_serves_repeated_get_links_from_memory() {
+        let context = test_context("bob", None);
+        let store = DhtStore::new(context.dht_storage.clone(), context.eav_storage.clone())
+            .with_link_index(Some(10));
+        let base = test_entry();
+        for entry in [&base, &test_entry_a(), &test_entry_b()].iter() {
+            context.dht_storage.write().unwrap().add(*entry).unwrap();
+        }
+
+        let add_link = |store: &DhtStore, target: &Entry| {
+            let link = Link::new(&base.address(), &target.address(), "test-tag");
+            let action = ActionWrapper::new(Action::AddLink(link));
+            reduce_add_link(context.clone(), store, &action).unwrap()
+        };
+        let targets = |store: &DhtStore| -> Vec<_> {
+            store
+                .get_links(base.address(), "test-tag".to_string())
+                .unwrap()
+                .into_iter()
+                .map(|eavi| eavi.value())
+                .collect()
+        };
+
+        let store = add_link(&store, &test_entry_a());
+        assert_eq!(targets(&store), vec![test_entry_a().address()]);
+        assert_eq!(targets(&store), vec![test_entry_a().address()]);
+        let index = store.link_index().unwrap().clone();
+        assert_eq!(index.scans(), 1);
+        assert_eq!(index.len(), 1);
+
+        // links added or removed after the base got indexed are applied to the index
+        let store = add_link(&store, &test_entry_b());
+        let mut expected = vec![test_entry_a().address(), test_entry_b().address()];
+        expected.sort();
+        let mut found = targets(&store);
+        found.sort();
+        assert_eq!(found, expected);
+
+        let link = Link::new(&base.address(), &test_entry_a().address(), "test-tag");
+        let action = ActionWrapper::new(Action::RemoveLink(link));
+        let store = reduce_remove_link(context.clone(), &store, &action).unwrap();
+        assert_eq!(targets(&store), vec![test_entry_b().address()]);
+        assert_eq!(index.scans(), 1);
+    }
+
     #[test]
     pub fn reduce_hold_test() {
         let context = test_context("bill", None);
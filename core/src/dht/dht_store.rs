@@ -1,7 +1,8 @@
-use crate::action::ActionWrapper;
+use crate::{action::ActionWrapper, dht::link_index::LinkIndex};
+use chrono::{offset::Utc, DateTime};
 use holochain_core_types::{
     cas::{
-        content::{Address, AddressableContent},
+        content::{Address, AddressableContent, Content},
         storage::ContentAddressableStorage,
     },
     chain_header::ChainHeader,
@@ -15,9 +16,40 @@ use holochain_core_types::{
 
 use std::{
     collections::{BTreeSet, HashMap},
+    fmt::Debug,
     sync::{Arc, RwLock},
+    time::Duration,
 };
 
+/// Source of the current time for expiring cached entries.
+/// Exists so tests can control the time.
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Clock reading the system time.
+#[derive(Clone, Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Size figures of the local DHT shard, as returned by [DhtStore::stats](struct.DhtStore.html#method.stats).
+/// Since the content storage is content addressable, storing the same content twice
+/// does not increase these numbers.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DhtStats {
+    /// number of distinct items (entries, headers, ...) in the content storage
+    pub entry_count: usize,
+    /// sum of the serialized sizes of all items in the content storage
+    pub content_bytes: usize,
+    /// number of EAV records in the meta storage
+    pub meta_count: usize,
+}
+
 /// The state-slice for the DHT.
 /// Holds the agent's local shard and interacts with the network module
 #[derive(Clone, Debug)]
@@ -27,6 +59,16 @@ pub struct DhtStore {
     meta_storage: Arc<RwLock<EntityAttributeValueStorage>>,
 
     actions: HashMap<ActionWrapper, Result<Address, HolochainError>>,
+
+    // Expiry of entries held as cache for others, see with_cache_ttl()
+    cache_ttl: Option<Duration>,
+    cache_expiries: HashMap<Address, DateTime<Utc>>,
+    // Source chain storage of the agent, whose entries never expire, see with_chain_storage()
+    chain_storage: Option<Arc<RwLock<ContentAddressableStorage>>>,
+    clock: Arc<Clock>,
+
+    // Serves get_links from memory if set, see with_link_index()
+    link_index: Option<LinkIndex>,
 }
 
 impl PartialEq for DhtStore {
@@ -37,6 +79,7 @@ impl PartialEq for DhtStore {
         let other_meta = &other.meta_storage.clone();
 
         self.actions == other.actions
+            && self.cache_expiries == other.cache_expiries
             && (*content.read().unwrap()).get_id() == (*other_content.read().unwrap()).get_id()
             && *meta.read().unwrap() == *other_meta.read().unwrap()
     }
@@ -53,14 +96,146 @@ impl DhtStore {
             content_storage,
             meta_storage,
             actions: HashMap::new(),
+            cache_ttl: None,
+            cache_expiries: HashMap::new(),
+            chain_storage: None,
+            clock: Arc::new(SystemClock),
+            link_index: None,
         }
     }
 
+    /// Sets the time-to-live of entries this node holds for others.
+    /// Expired ones get removed from the content storage by evict_expired_entries().
+    /// Entries in the source chain storage (see with_chain_storage()) never expire.
+    /// None (the default) keeps held entries indefinitely.
+    /// Expiry times are not persisted, so entries held before the store got restored
+    /// from a snapshot are kept indefinitely as well.
+    pub fn with_cache_ttl(mut self, cache_ttl: Option<Duration>) -> Self {
+        self.cache_ttl = cache_ttl;
+        self
+    }
+
+    /// Keeps the links of up to `max_bases` recently queried bases in memory so that
+    /// get_links does not have to query the meta storage for them every time.
+    /// None (the default) queries the meta storage for every call.
+    pub fn with_link_index(mut self, max_bases: Option<usize>) -> Self {
+        self.link_index = max_bases.map(LinkIndex::new);
+        self
+    }
+
+    pub fn link_index(&self) -> Option<&LinkIndex> {
+        self.link_index.as_ref()
+    }
+
+    /// Keeps the link index consistent with a link record that got written to the
+    /// meta storage.
+    pub(crate) fn index_link(&self, eavi: &EntityAttributeValueIndex) {
+        if let Some(ref link_index) = self.link_index {
+            link_index.record(eavi);
+        }
+    }
+
+    /// Sets the storage of the agent's source chain. Entries found in it are never
+    /// expired, also after a restart.
+    /// If it is the same storage as the content storage, held and committed entries
+    /// can't be told apart, so no entry ever expires.
+    pub fn with_chain_storage(
+        mut self,
+        chain_storage: Arc<RwLock<ContentAddressableStorage>>,
+    ) -> Self {
+        self.chain_storage = Some(chain_storage);
+        self
+    }
+
+    /// True if the given entry is (or may be) part of the agent's source chain and
+    /// therefore must not be removed from the content storage.
+    fn is_in_source_chain(&self, address: &Address) -> bool {
+        let chain_storage = match self.chain_storage {
+            Some(ref chain_storage) => chain_storage,
+            None => return false,
+        };
+        // locks get taken one after the other since both may be the same storage
+        let chain_id = chain_storage.read().map(|storage| storage.get_id());
+        let content_id = self.content_storage.read().map(|storage| storage.get_id());
+        match (chain_id, content_id) {
+            (Ok(chain_id), Ok(content_id)) if chain_id != content_id => chain_storage
+                .read()
+                .map(|storage| storage.contains(address).unwrap_or(true))
+                .unwrap_or(true),
+            _ => true,
+        }
+    }
+
+    /// Replaces the clock used to compute and check expiry times.
+    pub fn with_clock(mut self, clock: Arc<Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn cache_ttl(&self) -> Option<Duration> {
+        self.cache_ttl
+    }
+
+    /// Starts the TTL for an entry held for others.
+    /// Does nothing without TTL or if the entry is in the source chain storage.
+    pub(crate) fn mark_as_cached(&mut self, address: &Address) {
+        if self.cache_ttl.is_none() || self.is_in_source_chain(address) {
+            return;
+        }
+        let maybe_expiry = self.cache_ttl.and_then(|ttl| {
+            chrono::Duration::from_std(ttl)
+                .ok()
+                .and_then(|ttl| self.clock.now().checked_add_signed(ttl))
+        });
+        if let Some(expiry) = maybe_expiry {
+            self.cache_expiries.insert(address.clone(), expiry);
+        }
+    }
+
+    /// Exempts an entry committed by this agent from expiry, even if it was held before.
+    pub(crate) fn mark_as_authored(&mut self, address: &Address) {
+        self.cache_expiries.remove(address);
+    }
+
+    /// True if the given entry is held as cache and its TTL has passed.
+    pub fn is_expired(&self, address: &Address) -> bool {
+        self.cache_expiries
+            .get(address)
+            .map_or(false, |expiry| *expiry <= self.clock.now())
+    }
+
+    /// Removes all cached entries whose TTL has passed from the content storage
+    /// and returns their addresses.
+    /// Entries that are in the source chain storage only stop being tracked, they
+    /// don't get removed.
+    /// Their metadata stays in the meta storage since that is append only.
+    pub fn evict_expired_entries(&mut self) -> Result<Vec<Address>, HolochainError> {
+        let expired: Vec<Address> = self
+            .cache_expiries
+            .keys()
+            .filter(|address| self.is_expired(address))
+            .cloned()
+            .collect();
+        let mut evicted = Vec::new();
+        for address in expired {
+            self.cache_expiries.remove(&address);
+            if self.is_in_source_chain(&address) {
+                continue;
+            }
+            self.content_storage.write()?.remove(&address)?;
+            evicted.push(address);
+        }
+        Ok(evicted)
+    }
+
     pub fn get_links(
         &self,
         address: Address,
         tag: String,
     ) -> Result<BTreeSet<EntityAttributeValueIndex>, HolochainError> {
+        if let Some(ref link_index) = self.link_index {
+            return link_index.get_links(&self.meta_storage, &address, &tag);
+        }
         let filtered = self.meta_storage.read()?.fetch_eavi(&EaviQuery::new(
             Some(address).into(),
             EavFilter::multiple(vec![
@@ -80,10 +255,71 @@ impl DhtStore {
             .collect())
     }
 
+    /// Returns the links with the given tag that point to the given target and were not removed.
+    /// The base of each link is the entity of the returned EAVs.
+    pub fn get_links_to(
+        &self,
+        target: Address,
+        tag: String,
+    ) -> Result<BTreeSet<EntityAttributeValueIndex>, HolochainError> {
+        let filtered = self.meta_storage.read()?.fetch_eavi(&EaviQuery::new(
+            None.into(),
+            EavFilter::multiple(vec![
+                Attribute::LinkTag(tag.clone()),
+                Attribute::RemovedLink(tag),
+            ]),
+            Some(target).into(),
+            IndexFilter::LatestByAttribute,
+        ))?;
+
+        Ok(filtered
+            .into_iter()
+            .filter(|eav| match eav.attribute() {
+                Attribute::LinkTag(_) => true,
+                _ => false,
+            })
+            .collect())
+    }
+
+    /// Returns all links from the given base that were not removed as (tag, target) pairs,
+    /// regardless of their tag.
+    pub fn get_all_links(
+        &self,
+        address: Address,
+    ) -> Result<Vec<(String, Address)>, HolochainError> {
+        let tags: BTreeSet<String> = self
+            .meta_storage
+            .read()?
+            .fetch_eavi(&EaviQuery::new(
+                Some(address.clone()).into(),
+                EavFilter::predicate(|attribute| match attribute {
+                    Attribute::LinkTag(_) => true,
+                    _ => false,
+                }),
+                None.into(),
+                IndexFilter::Range(None, None),
+            ))?
+            .into_iter()
+            .filter_map(|eavi| match eavi.attribute() {
+                Attribute::LinkTag(tag) => Some(tag),
+                _ => None,
+            })
+            .collect();
+
+        let mut links = Vec::new();
+        for tag in tags {
+            for eavi in self.get_links(address.clone(), tag.clone())? {
+                links.push((tag.clone(), eavi.value()));
+            }
+        }
+        Ok(links)
+    }
+
     /// Get all headers for an entry by first looking in the DHT meta store
     /// for header addresses, then resolving them with the DHT CAS
     pub fn get_headers(&self, entry_address: Address) -> Result<Vec<ChainHeader>, HolochainError> {
-        self.meta_storage()
+        let header_addresses: Vec<Address> = self
+            .meta_storage()
             .read()
             .unwrap()
             // fetch all EAV references to chain headers for this entry
@@ -96,17 +332,37 @@ impl DhtStore {
             .into_iter()
             // get the header addresses
             .map(|eavi| eavi.value())
-            // fetch the header content from CAS
-            .map(|a| self.content_storage().read().unwrap().fetch(&a))
-            // rearrange
-            .collect::<Result<Vec<Option<_>>, _>>()
-            .map(|r| {
-                r.into_iter()
-                    // ignore None values
-                    .flatten()
-                    .map(|content| ChainHeader::try_from_content(&content))
-                    .collect::<Result<Vec<_>, _>>()
-            })?
+            .collect();
+        self.content_storage()
+            .read()
+            .unwrap()
+            // fetch all header contents from CAS in one batch
+            .fetch_many(&header_addresses)?
+            .into_iter()
+            // ignore None values
+            .flatten()
+            .map(|content| ChainHeader::try_from_content(&content))
+            .collect()
+    }
+
+    /// True if the given header is held for the entry. Looks up that one header instead
+    /// of resolving all headers of the entry, which might be lots for updated entries.
+    pub fn holds_header(
+        &self,
+        entry_address: Address,
+        header: &ChainHeader,
+    ) -> Result<bool, HolochainError> {
+        Ok(!self
+            .meta_storage()
+            .read()
+            .unwrap()
+            .fetch_eavi(&EaviQuery::new(
+                Some(entry_address).into(),
+                Some(Attribute::EntryHeader).into(),
+                Some(header.address()).into(),
+                IndexFilter::LatestByAttribute,
+            ))?
+            .is_empty())
     }
 
     /// Add an entry and header to the CAS and EAV, respectively
@@ -125,6 +381,66 @@ impl DhtStore {
         Ok(())
     }
 
+    /// Computes entry count, content size and meta record count of the local shard
+    /// by walking the underlying storages.
+    /// Like iter_contents(), the content storage is only locked for one item at a time,
+    /// so that commits don't have to wait for the whole walk.
+    pub fn stats(&self) -> Result<DhtStats, HolochainError> {
+        let mut stats = DhtStats::default();
+        for item in self.iter_contents() {
+            let (_, content) = item?;
+            stats.entry_count += 1;
+            stats.content_bytes += String::from(content).len();
+        }
+        stats.meta_count = self
+            .meta_storage
+            .read()?
+            .fetch_eavi(&EaviQuery::new(
+                None.into(),
+                None.into(),
+                None.into(),
+                IndexFilter::Range(None, None),
+            ))?
+            .len();
+        Ok(stats)
+    }
+
+    /// Streams all items of the content storage together with their address, e.g. to
+    /// replicate or back up the local shard.
+    /// The set of addresses gets read once at the start, each content only when the
+    /// iterator reaches it. Items removed in the meantime (e.g. evicted cache entries) are
+    /// skipped, items added later are not part of the iteration. The storage is only
+    /// locked for reading one item at a time.
+    pub fn iter_contents(
+        &self,
+    ) -> impl Iterator<Item = Result<(Address, Content), HolochainError>> {
+        let content_storage = self.content_storage.clone();
+        let (addresses, error) = match self
+            .content_storage
+            .read()
+            .map_err(HolochainError::from)
+            .and_then(|storage| storage.addresses())
+        {
+            Ok(addresses) => (addresses, None),
+            Err(error) => (Vec::new(), Some(error)),
+        };
+        error
+            .into_iter()
+            .map(Err)
+            .chain(
+                addresses
+                    .into_iter()
+                    .filter_map(move |address| match content_storage.read() {
+                        Ok(storage) => match storage.fetch(&address) {
+                            Ok(Some(content)) => Some(Ok((address, content))),
+                            Ok(None) => None,
+                            Err(error) => Some(Err(error)),
+                        },
+                        Err(error) => Some(Err(error.into())),
+                    }),
+            )
+    }
+
     // Getters (for reducers)
     // =======
     pub(crate) fn content_storage(&self) -> Arc<RwLock<ContentAddressableStorage>> {
@@ -147,9 +463,30 @@ impl DhtStore {
 pub mod tests {
     use super::*;
     use holochain_core_types::{
-        cas::storage::ExampleContentAddressableStorage, chain_header::test_chain_header_with_sig,
-        eav::ExampleEntityAttributeValueStorage, entry::test_entry,
+        cas::storage::ExampleContentAddressableStorage,
+        chain_header::test_chain_header_with_sig,
+        eav::ExampleEntityAttributeValueStorage,
+        entry::{test_entry, test_entry_b, test_entry_c},
     };
+    use std::sync::Mutex;
+
+    #[derive(Debug)]
+    struct MockClock {
+        now: Mutex<DateTime<Utc>>,
+    }
+
+    impl MockClock {
+        fn advance(&self, duration: Duration) {
+            let mut now = self.now.lock().unwrap();
+            *now = *now + chrono::Duration::from_std(duration).unwrap();
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> DateTime<Utc> {
+            *self.now.lock().unwrap()
+        }
+    }
 
     #[test]
     fn get_headers_roundtrip() {
@@ -165,6 +502,217 @@ pub mod tests {
         store.add_header_for_entry(&entry, &header1).unwrap();
         store.add_header_for_entry(&entry, &header2).unwrap();
         let headers = store.get_headers(entry.address()).unwrap();
-        assert_eq!(headers, vec![header1, header2]);
+        assert_eq!(headers, vec![header1.clone(), header2]);
+
+        assert!(store.holds_header(entry.address(), &header1).unwrap());
+        let header3 = test_chain_header_with_sig("sig3");
+        assert!(!store.holds_header(entry.address(), &header3).unwrap());
+    }
+
+    #[test]
+    fn stats_count_deduplicated_content() {
+        let store = DhtStore::new(
+            Arc::new(RwLock::new(
+                ExampleContentAddressableStorage::new().unwrap(),
+            )),
+            Arc::new(RwLock::new(ExampleEntityAttributeValueStorage::new())),
+        );
+        assert_eq!(store.stats().unwrap(), DhtStats::default());
+
+        let entry = test_entry();
+        let other_entry = test_entry_b();
+        {
+            let content_storage = store.content_storage();
+            let mut content_storage = content_storage.write().unwrap();
+            content_storage.add(&entry).unwrap();
+            content_storage.add(&other_entry).unwrap();
+            // adding the same content again must not count twice
+            content_storage.add(&entry).unwrap();
+        }
+        let header = test_chain_header_with_sig("sig1");
+        store.add_header_for_entry(&entry, &header).unwrap();
+
+        let stats = store.stats().unwrap();
+        assert_eq!(stats.entry_count, 3);
+        let expected_bytes = String::from(entry.content()).len()
+            + String::from(other_entry.content()).len()
+            + String::from(header.content()).len();
+        assert_eq!(stats.content_bytes, expected_bytes);
+        assert_eq!(stats.meta_count, 1);
+    }
+
+    #[test]
+    fn iter_contents_yields_every_item_once() {
+        let store = DhtStore::new(
+            Arc::new(RwLock::new(
+                ExampleContentAddressableStorage::new().unwrap(),
+            )),
+            Arc::new(RwLock::new(ExampleEntityAttributeValueStorage::new())),
+        );
+        assert_eq!(store.iter_contents().count(), 0);
+
+        let entries = vec![test_entry(), test_entry_b(), test_entry_c()];
+        {
+            let content_storage = store.content_storage();
+            let mut content_storage = content_storage.write().unwrap();
+            for entry in entries.iter() {
+                content_storage.add(entry).unwrap();
+            }
+            content_storage.add(&entries[0]).unwrap();
+        }
+        let header = test_chain_header_with_sig("sig1");
+        store.add_header_for_entry(&entries[0], &header).unwrap();
+
+        let mut contents: Vec<(Address, Content)> =
+            store.iter_contents().collect::<Result<_, _>>().unwrap();
+        contents.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut expected: Vec<(Address, Content)> = entries
+            .iter()
+            .map(|entry| (entry.address(), entry.content()))
+            .chain(vec![(header.address(), header.content())])
+            .collect();
+        expected.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(contents, expected);
+    }
+
+    #[test]
+    fn iter_contents_skips_items_removed_while_iterating() {
+        let store = DhtStore::new(
+            Arc::new(RwLock::new(
+                ExampleContentAddressableStorage::new().unwrap(),
+            )),
+            Arc::new(RwLock::new(ExampleEntityAttributeValueStorage::new())),
+        );
+        let content_storage = store.content_storage();
+        content_storage.write().unwrap().add(&test_entry()).unwrap();
+        content_storage
+            .write()
+            .unwrap()
+            .add(&test_entry_b())
+            .unwrap();
+
+        let mut contents = store.iter_contents();
+        let (first, _) = contents.next().unwrap().unwrap();
+        let other = if first == test_entry().address() {
+            test_entry_b().address()
+        } else {
+            test_entry().address()
+        };
+        content_storage.write().unwrap().remove(&other).unwrap();
+        assert!(contents.next().is_none());
+    }
+
+    fn store_with_ttl(
+        chain_storage: Arc<RwLock<ContentAddressableStorage>>,
+        content_storage: Arc<RwLock<ContentAddressableStorage>>,
+        clock: Arc<MockClock>,
+    ) -> DhtStore {
+        DhtStore::new(
+            content_storage,
+            Arc::new(RwLock::new(ExampleEntityAttributeValueStorage::new())),
+        )
+        .with_chain_storage(chain_storage)
+        .with_cache_ttl(Some(Duration::from_secs(60)))
+        .with_clock(clock)
+    }
+
+    fn new_cas() -> Arc<RwLock<ContentAddressableStorage>> {
+        Arc::new(RwLock::new(
+            ExampleContentAddressableStorage::new().unwrap(),
+        ))
+    }
+
+    #[test]
+    fn evicts_expired_cache_entries_only() {
+        let clock = Arc::new(MockClock {
+            now: Mutex::new(Utc::now()),
+        });
+        let chain_storage = new_cas();
+        let mut store = store_with_ttl(chain_storage.clone(), new_cas(), clock.clone());
+
+        let cached = test_entry();
+        let authored = test_entry_b();
+        let held_then_authored = test_entry_c();
+        {
+            let content_storage = store.content_storage();
+            let mut content_storage = content_storage.write().unwrap();
+            content_storage.add(&cached).unwrap();
+            content_storage.add(&authored).unwrap();
+            content_storage.add(&held_then_authored).unwrap();
+        }
+        chain_storage.write().unwrap().add(&authored).unwrap();
+        store.mark_as_cached(&cached.address());
+        store.mark_as_authored(&authored.address());
+        store.mark_as_cached(&authored.address());
+        store.mark_as_cached(&held_then_authored.address());
+        store.mark_as_authored(&held_then_authored.address());
+
+        clock.advance(Duration::from_secs(30));
+        assert!(!store.is_expired(&cached.address()));
+        assert_eq!(store.evict_expired_entries(), Ok(Vec::new()));
+
+        clock.advance(Duration::from_secs(31));
+        assert!(store.is_expired(&cached.address()));
+        assert_eq!(store.evict_expired_entries(), Ok(vec![cached.address()]));
+
+        let content_storage = store.content_storage();
+        let content_storage = content_storage.read().unwrap();
+        assert_eq!(content_storage.contains(&cached.address()), Ok(false));
+        assert_eq!(content_storage.contains(&authored.address()), Ok(true));
+        assert_eq!(
+            content_storage.contains(&held_then_authored.address()),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn restarted_node_does_not_evict_its_own_chain_entries() {
+        let clock = Arc::new(MockClock {
+            now: Mutex::new(Utc::now()),
+        });
+        let own = test_entry();
+        let held = test_entry_b();
+
+        // chain and DHT in separate storages: the chain storage tells own entries apart
+        let chain_storage = new_cas();
+        let content_storage = new_cas();
+        chain_storage.write().unwrap().add(&own).unwrap();
+        content_storage.write().unwrap().add(&own).unwrap();
+        content_storage.write().unwrap().add(&held).unwrap();
+        // a fresh store, as after a restart, has no memory of what got committed before
+        let mut store = store_with_ttl(chain_storage.clone(), content_storage, clock.clone());
+        store.mark_as_cached(&own.address());
+        store.mark_as_cached(&held.address());
+        clock.advance(Duration::from_secs(61));
+        assert_eq!(store.evict_expired_entries(), Ok(vec![held.address()]));
+        assert_eq!(
+            chain_storage.read().unwrap().contains(&own.address()),
+            Ok(true)
+        );
+        assert_eq!(
+            store
+                .content_storage()
+                .read()
+                .unwrap()
+                .contains(&own.address()),
+            Ok(true)
+        );
+
+        // chain and DHT sharing one storage: nothing gets removed from it
+        let shared_storage = new_cas();
+        shared_storage.write().unwrap().add(&own).unwrap();
+        let mut store = store_with_ttl(
+            shared_storage.clone(),
+            shared_storage.clone(),
+            clock.clone(),
+        );
+        store.mark_as_cached(&own.address());
+        clock.advance(Duration::from_secs(61));
+        assert_eq!(store.evict_expired_entries(), Ok(Vec::new()));
+        assert_eq!(
+            shared_storage.read().unwrap().contains(&own.address()),
+            Ok(true)
+        );
+        assert!(!store.is_expired(&own.address()));
     }
 }
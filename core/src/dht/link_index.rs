@@ -0,0 +1,133 @@
+//! Optional in-memory index of the links held in the local DHT shard, by base address.
+//! get_links has to query the meta storage for every call otherwise, which is a lot of
+//! repeated scanning for frequently accessed bases.
+//! A base gets indexed the first time its links are asked for, by loading all its link
+//! records at once. Link records written through the DHT reducers afterwards are applied
+//! to the index, so it only stays consistent if nothing else writes links into the
+//! meta storage. The least recently used bases get dropped once the index is full.
+
+use crate::lru_cache::LruCache;
+use holochain_core_types::{
+    cas::content::Address,
+    eav::{
+        Attribute, EavFilter, EaviQuery, EntityAttributeValueIndex, EntityAttributeValueStorage,
+        IndexFilter,
+    },
+    error::HolochainError,
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex, RwLock},
+};
+
+/// Live links of a base: tag -> target -> record that added the link
+type BaseLinks = HashMap<String, HashMap<Address, EntityAttributeValueIndex>>;
+
+/// Applies a link record to the links of its base.
+/// Records have to be applied in the order of their index.
+fn apply(links: &mut BaseLinks, eavi: &EntityAttributeValueIndex) {
+    match eavi.attribute() {
+        Attribute::LinkTag(tag) => {
+            links
+                .entry(tag)
+                .or_insert_with(HashMap::new)
+                .insert(eavi.value(), eavi.clone());
+        }
+        Attribute::RemovedLink(tag) => {
+            if let Some(targets) = links.get_mut(&tag) {
+                targets.remove(&eavi.value());
+            }
+        }
+        _ => (),
+    }
+}
+
+#[derive(Debug)]
+struct IndexContent {
+    bases: LruCache<Address, BaseLinks>,
+    scans: usize,
+}
+
+/// Bounded index of links by base. Clones share the same content.
+#[derive(Clone, Debug)]
+pub struct LinkIndex {
+    max_bases: usize,
+    content: Arc<Mutex<IndexContent>>,
+}
+
+impl LinkIndex {
+    /// Creates an index that keeps the links of at most `max_bases` bases.
+    pub fn new(max_bases: usize) -> Self {
+        LinkIndex {
+            max_bases,
+            content: Arc::new(Mutex::new(IndexContent {
+                bases: LruCache::new(max_bases),
+                scans: 0,
+            })),
+        }
+    }
+
+    /// Returns the records of the live links with the given tag from the given base,
+    /// like DhtStore::get_links. Indexes the base first if it is not indexed yet.
+    pub fn get_links(
+        &self,
+        meta_storage: &Arc<RwLock<EntityAttributeValueStorage>>,
+        base: &Address,
+        tag: &str,
+    ) -> Result<BTreeSet<EntityAttributeValueIndex>, HolochainError> {
+        let mut content = self.content.lock().unwrap();
+        if !content.bases.contains_key(base) {
+            let records = meta_storage.read()?.fetch_eavi(&EaviQuery::new(
+                Some(base.clone()).into(),
+                EavFilter::predicate(|attribute| match attribute {
+                    Attribute::LinkTag(_) | Attribute::RemovedLink(_) => true,
+                    _ => false,
+                }),
+                None.into(),
+                IndexFilter::Range(None, None),
+            ))?;
+            content.scans += 1;
+            let mut links = BaseLinks::new();
+            // the set is ordered by index
+            for eavi in records.iter() {
+                apply(&mut links, eavi);
+            }
+            if self.max_bases == 0 {
+                return Ok(Self::links_with_tag(&links, tag));
+            }
+            content.bases.insert(base.clone(), links);
+        }
+        let links = content.bases.get(base).expect("base got indexed above");
+        Ok(Self::links_with_tag(links, tag))
+    }
+
+    fn links_with_tag(links: &BaseLinks, tag: &str) -> BTreeSet<EntityAttributeValueIndex> {
+        links
+            .get(tag)
+            .map(|targets| targets.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Applies the given link record to the index if its base is indexed.
+    /// Has to be called for every link record written to the meta storage.
+    pub fn record(&self, eavi: &EntityAttributeValueIndex) {
+        let mut content = self.content.lock().unwrap();
+        if let Some(links) = content.bases.peek_mut(&eavi.entity()) {
+            apply(links, eavi);
+        }
+    }
+
+    /// Number of indexed bases.
+    pub fn len(&self) -> usize {
+        self.content.lock().unwrap().bases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of times the index had to query the meta storage for the links of a base.
+    pub fn scans(&self) -> usize {
+        self.content.lock().unwrap().scans
+    }
+}
@@ -464,9 +464,17 @@ pub mod tests {
         dna: Dna,
         name: &str,
         network_name: Option<&str>,
+    ) -> Result<(Instance, Arc<Context>), String> {
+        test_instance_with_context(dna, test_context(name, network_name))
+    }
+
+    /// create a test instance from the given context, e.g. one with non-default settings
+    #[cfg_attr(tarpaulin, skip)]
+    pub fn test_instance_with_context(
+        dna: Dna,
+        context: Arc<Context>,
     ) -> Result<(Instance, Arc<Context>), String> {
         // Create instance and plug in our DNA
-        let context = test_context(name, network_name);
         let mut instance = Instance::new(context.clone());
         let context = instance.initialize(Some(dna.clone()), context.clone())?;
 
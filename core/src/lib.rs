@@ -22,15 +22,18 @@ extern crate pretty_assertions;
 
 pub mod action;
 pub mod agent;
+pub mod background_tasks;
 pub mod context;
 pub mod dht;
 pub mod instance;
 #[cfg(test)]
 pub mod link_tests;
 pub mod logger;
+pub mod lru_cache;
 pub mod network;
 pub mod nucleus;
 pub mod persister;
+pub mod reduce_profiler;
 pub mod signal;
 pub mod state;
 pub mod workflows;
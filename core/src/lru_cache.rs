@@ -0,0 +1,115 @@
+//! Bounded map that drops its least recently used entries once it is full.
+//! Shared by the optional caches of the context (zome call results, validation outcomes,
+//! compiled wasm modules and the link index), which wrap it in a Mutex together with
+//! their own counters.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+};
+
+#[derive(Debug)]
+pub struct LruCache<K, V> {
+    capacity: usize,
+    values: HashMap<K, V>,
+    /// keys in order of use, least recently used first
+    order: VecDeque<K>,
+}
+
+impl<K: Clone + Eq + Hash, V> LruCache<K, V> {
+    /// Creates a cache that keeps at most `capacity` entries.
+    /// A capacity of 0 stores nothing.
+    pub fn new(capacity: usize) -> Self {
+        LruCache {
+            capacity,
+            values: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Returns the value stored for the given key and marks it as most recently used.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        if !self.values.contains_key(key) {
+            return None;
+        }
+        self.touch(key);
+        self.values.get(key)
+    }
+
+    /// Returns the value stored for the given key without changing the order of use.
+    pub fn peek_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.values.get_mut(key)
+    }
+
+    /// Stores the value as the most recently used one and drops the least recently
+    /// used entries beyond the capacity.
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.values.insert(key.clone(), value).is_some() {
+            self.order.retain(|existing| existing != &key);
+        }
+        self.order.push_back(key);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.values.remove(&oldest);
+            }
+        }
+    }
+
+    fn touch(&mut self, key: &K) {
+        self.order.retain(|existing| existing != key);
+        self.order.push_back(key.clone());
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.values.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_least_recently_used_entries() {
+        let mut cache = LruCache::new(2);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        // using a makes b the least recently used one
+        assert_eq!(cache.get(&"a"), Some(&1));
+        cache.insert("c", 3);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"a"), Some(&1));
+        assert_eq!(cache.get(&"c"), Some(&3));
+    }
+
+    #[test]
+    fn replacing_a_value_keeps_one_entry() {
+        let mut cache = LruCache::new(2);
+        cache.insert("a", 1);
+        cache.insert("a", 2);
+        cache.insert("b", 3);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&"a"), Some(&2));
+    }
+
+    #[test]
+    fn stores_nothing_without_capacity() {
+        let mut cache = LruCache::new(0);
+        cache.insert("a", 1);
+        assert!(cache.is_empty());
+    }
+}
@@ -2,7 +2,7 @@ use crate::{
     action::{Action, ActionWrapper, DirectMessageData},
     context::Context,
     instance::dispatch_action,
-    network::direct_message::{CustomDirectMessage, DirectMessage},
+    network::direct_message::{CustomDirectMessage, DirectMessage, DirectMessageEnvelope},
 };
 use futures::{
     future::Future,
@@ -23,9 +23,10 @@ pub async fn custom_send(
 ) -> Result<String, HolochainError> {
     let id = ProcessUniqueId::new().to_string();
     let direct_message = DirectMessage::Custom(custom_direct_message);
+    let envelope = DirectMessageEnvelope::new(direct_message, &to_agent.to_string(), &context)?;
     let direct_message_data = DirectMessageData {
         address: to_agent,
-        message: direct_message,
+        envelope,
         msg_id: id.clone(),
         is_response: false,
     };
@@ -8,10 +8,23 @@ use futures::{
     task::{LocalWaker, Poll},
 };
 use holochain_core_types::{
-    cas::content::Address, entry::EntryWithMeta, error::HcResult, time::Timeout,
+    cas::content::Address,
+    entry::EntryWithMeta,
+    error::{HcResult, HolochainError},
+    time::Timeout,
 };
 use std::{pin::Pin, sync::Arc, thread};
 
+/// Answer of the network to a GET entry request
+#[derive(Clone, Debug, PartialEq)]
+pub enum FetchedEntry {
+    /// The entry with its meta, None if the network does not have it
+    Entry(Option<EntryWithMeta>),
+    /// The version the request named as already known is still the latest one,
+    /// see get_entry_if_newer_than()
+    NotModified,
+}
+
 /// FetchEntry Action Creator
 /// This is the network version of get_entry that makes the network module start
 /// a look-up process.
@@ -22,8 +35,28 @@ pub async fn get_entry(
     address: Address,
     timeout: Timeout,
 ) -> HcResult<Option<EntryWithMeta>> {
+    match await!(get_entry_if_newer_than(context, address, None, timeout))? {
+        FetchedEntry::Entry(maybe_entry_with_meta) => Ok(maybe_entry_with_meta),
+        // Holders only answer NotModified to requests that name a known version
+        FetchedEntry::NotModified => Err(HolochainError::ErrorGeneric(
+            "Got NotModified for an unconditional get".to_string(),
+        )),
+    }
+}
+
+/// Conditional version of get_entry().
+/// If a known version is given, the holder answers with FetchedEntry::NotModified
+/// instead of sending the entry again, as long as the requested address is that version,
+/// is live and has not been updated.
+pub async fn get_entry_if_newer_than(
+    context: Arc<Context>,
+    address: Address,
+    if_newer_than: Option<Address>,
+    timeout: Timeout,
+) -> HcResult<FetchedEntry> {
     let key = GetEntryKey {
         address: address,
+        if_newer_than,
         id: snowflake::ProcessUniqueId::new().to_string(),
     };
 
@@ -44,7 +77,7 @@ pub async fn get_entry(
     })
 }
 
-/// GetEntryFuture resolves to a HcResult<Entry>.
+/// GetEntryFuture resolves to a HcResult<FetchedEntry>.
 /// Tracks the state of the network module
 pub struct GetEntryFuture {
     context: Arc<Context>,
@@ -52,7 +85,7 @@ pub struct GetEntryFuture {
 }
 
 impl Future for GetEntryFuture {
-    type Output = HcResult<Option<EntryWithMeta>>;
+    type Output = HcResult<FetchedEntry>;
 
     fn poll(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
         let state = self.context.state().unwrap().network();
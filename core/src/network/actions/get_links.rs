@@ -2,27 +2,40 @@ use crate::{
     action::{Action, ActionWrapper, GetLinksKey},
     context::Context,
     instance::dispatch_action,
+    network::actions::get_entry::get_entry,
+    nucleus::actions::get_entry::get_entry_from_dht,
 };
 use futures::{
-    future::Future,
+    future::{join_all, Future},
     task::{LocalWaker, Poll},
 };
-use holochain_core_types::{cas::content::Address, error::HcResult, time::Timeout};
+use holochain_core_types::{
+    cas::content::Address,
+    entry::Entry,
+    error::{HcResult, HolochainError},
+    time::Timeout,
+};
+use holochain_wasm_utils::api_serialization::get_links::{GetLinksOptions, LinksStatusRequestKind};
 use snowflake::ProcessUniqueId;
 use std::{pin::Pin, sync::Arc, thread};
 
 /// GetLinks Action Creator
 /// This is the network version of get_links that makes the network module start
 /// a look-up process.
+/// If a target type is given, only targets of that entry type get returned.
+/// The nodes holding the links leave out the targets they know to be of another type,
+/// the remaining ones get checked here, see filter_by_entry_type().
 pub async fn get_links(
     context: Arc<Context>,
     address: Address,
     tag: String,
+    target_type: Option<String>,
     timeout: Timeout,
 ) -> HcResult<Vec<Address>> {
     let key = GetLinksKey {
         base_address: address.clone(),
         tag: tag.clone(),
+        target_type: target_type.clone(),
         id: ProcessUniqueId::new().to_string(),
     };
     let action_wrapper = ActionWrapper::new(Action::GetLinks(key.clone()));
@@ -30,16 +43,116 @@ pub async fn get_links(
 
     let key_inner = key.clone();
     let context_inner = context.clone();
+    let timeout_inner = timeout.clone();
     let _ = thread::spawn(move || {
-        thread::sleep(timeout.into());
+        thread::sleep(timeout_inner.into());
         let action_wrapper = ActionWrapper::new(Action::GetLinksTimeout(key_inner));
         dispatch_action(context_inner.action_channel(), action_wrapper.clone());
     });
 
-    await!(GetLinksFuture {
+    let targets = await!(GetLinksFuture {
         context: context.clone(),
         key
-    })
+    })?;
+    match target_type {
+        Some(entry_type) => {
+            await!(filter_by_entry_type(context, targets, entry_type, timeout))
+        }
+        None => Ok(targets),
+    }
+}
+
+/// Keeps the targets whose entry is of the given type.
+/// Holders of the links can only filter the targets they hold themselves, so every
+/// target gets checked again, looking in the local DHT shard first and then asking
+/// the network, for all targets at once. Targets whose entry can't be found are left out
+/// since their type is unknown. If a look-up fails, e.g. times out, the whole call fails
+/// instead of leaving out a target that might be of the given type.
+async fn filter_by_entry_type(
+    context: Arc<Context>,
+    targets: Vec<Address>,
+    entry_type: String,
+    timeout: Timeout,
+) -> HcResult<Vec<Address>> {
+    let lookups = targets
+        .into_iter()
+        .map(|target| Box::pin(target_entry(context.clone(), target, timeout.clone())));
+    let mut filtered = Vec::new();
+    for lookup in await!(join_all(lookups)) {
+        let (target, entry) = lookup?;
+        if entry.map_or(false, |entry| entry.entry_type().to_string() == entry_type) {
+            filtered.push(target);
+        }
+    }
+    Ok(filtered)
+}
+
+/// The given target together with its entry, from the local DHT shard or else the network.
+async fn target_entry(
+    context: Arc<Context>,
+    target: Address,
+    timeout: Timeout,
+) -> HcResult<(Address, Option<Entry>)> {
+    let entry = match get_entry_from_dht(&context, &target)? {
+        Some(entry) => Some(entry),
+        None => await!(get_entry(context.clone(), target.clone(), timeout))?
+            .map(|entry_with_meta| entry_with_meta.entry),
+    };
+    Ok((target, entry))
+}
+
+/// Returns every link from the given base as (tag, target) pairs, across all tags.
+/// Since the network can only be asked for the links of one tag at a time, this looks
+/// at the links held in the local DHT shard only, so the timeout of the options is not used.
+pub fn get_all_links(
+    context: &Arc<Context>,
+    base: Address,
+    options: &GetLinksOptions,
+) -> HcResult<Vec<(String, Address)>> {
+    if options.status_request != LinksStatusRequestKind::Live {
+        return Err(HolochainError::NotImplemented(
+            "get links status request other than Live".to_string(),
+        ));
+    }
+    if options.sources {
+        return Err(HolochainError::NotImplemented(
+            "get links retrieve sources".to_string(),
+        ));
+    }
+    context
+        .state()
+        .ok_or_else(|| HolochainError::ErrorGeneric("Context not initialized".to_string()))?
+        .dht()
+        .get_all_links(base)
+}
+
+/// Returns the bases of all links with the given tag that point to the given target.
+/// Like get_all_links, this only looks at the links held in the local DHT shard,
+/// so the timeout of the options is not used.
+pub fn get_links_to(
+    context: &Arc<Context>,
+    target: Address,
+    tag: String,
+    options: &GetLinksOptions,
+) -> HcResult<Vec<Address>> {
+    if options.status_request != LinksStatusRequestKind::Live {
+        return Err(HolochainError::NotImplemented(
+            "get links status request other than Live".to_string(),
+        ));
+    }
+    if options.sources {
+        return Err(HolochainError::NotImplemented(
+            "get links retrieve sources".to_string(),
+        ));
+    }
+    Ok(context
+        .state()
+        .ok_or_else(|| HolochainError::ErrorGeneric("Context not initialized".to_string()))?
+        .dht()
+        .get_links_to(target, tag)?
+        .into_iter()
+        .map(|eavi| eavi.entity())
+        .collect())
 }
 
 /// GetLinksFuture resolves to a HcResult<Vec<Address>>.
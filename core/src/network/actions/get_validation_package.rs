@@ -2,6 +2,10 @@ use crate::{
     action::{Action, ActionWrapper},
     context::Context,
     instance::dispatch_action,
+    network::{
+        direct_message::{DirectMessage, DirectMessageEnvelope},
+        reducers::get_validation_package::validation_package_source,
+    },
 };
 use futures::{
     future::Future,
@@ -24,7 +28,13 @@ pub async fn get_validation_package(
     context: &Arc<Context>,
 ) -> HcResult<Option<ValidationPackage>> {
     let entry_address = header.entry_address().clone();
-    let action_wrapper = ActionWrapper::new(Action::GetValidationPackage(header));
+    let source = validation_package_source(&header)?;
+    let envelope = DirectMessageEnvelope::new(
+        DirectMessage::RequestValidationPackage(entry_address.clone()),
+        &source.to_string(),
+        context,
+    )?;
+    let action_wrapper = ActionWrapper::new(Action::GetValidationPackage((header, envelope)));
     dispatch_action(context.action_channel(), action_wrapper.clone());
     await!(GetValidationPackageFuture {
         context: context.clone(),
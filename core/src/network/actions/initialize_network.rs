@@ -2,7 +2,7 @@ use crate::{
     action::{Action, ActionWrapper, NetworkSettings},
     context::{get_dna_and_agent, Context},
     instance::dispatch_action,
-    network::actions::publish::publish,
+    network::actions::{publish::publish, ActionResponse},
 };
 use futures::{
     task::{LocalWaker, Poll},
@@ -26,6 +26,7 @@ pub async fn initialize_network(context: &Arc<Context>) -> HcResult<()> {
 
     await!(InitNetworkFuture {
         context: context.clone(),
+        action: action_wrapper,
     })?;
 
     await!(publish(agent_id.clone().into(), context))?;
@@ -49,11 +50,15 @@ pub async fn initialize_network_with_spoofed_dna(
 
     await!(InitNetworkFuture {
         context: context.clone(),
+        action: action_wrapper,
     })
 }
 
+/// InitNetworkFuture resolves to the result of the InitNetwork reducer,
+/// i.e. fails if the network could not be created.
 pub struct InitNetworkFuture {
     context: Arc<Context>,
+    action: ActionWrapper,
 }
 
 impl Future for InitNetworkFuture {
@@ -66,13 +71,9 @@ impl Future for InitNetworkFuture {
         //
         lw.wake();
         if let Some(state) = self.context.state() {
-            if state.network().network.is_some()
-                || state.network().dna_address.is_some()
-                || state.network().agent_id.is_some()
-            {
-                Poll::Ready(Ok(()))
-            } else {
-                Poll::Pending
+            match state.network().actions().get(&self.action) {
+                Some(ActionResponse::InitNetwork(result)) => Poll::Ready(result.clone()),
+                _ => Poll::Pending,
             }
         } else {
             Poll::Pending
@@ -4,12 +4,16 @@ pub mod get_links;
 pub mod get_validation_package;
 pub mod initialize_network;
 pub mod publish;
+pub mod shutdown_network;
 
 use holochain_core_types::{cas::content::Address, error::HcResult};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ActionResponse {
+    InitNetwork(HcResult<()>),
     Publish(HcResult<Address>),
+    PublishBatch(HcResult<Vec<Address>>),
     RespondFetch(HcResult<()>),
     RespondGetLinks(HcResult<()>),
+    ShutdownNetwork(HcResult<()>),
 }
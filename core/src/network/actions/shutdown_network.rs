@@ -0,0 +1,122 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    context::Context,
+    instance::dispatch_action,
+    network::{actions::ActionResponse, state::NetworkState},
+};
+use futures::{
+    task::{LocalWaker, Poll},
+    Future,
+};
+use holochain_core_types::error::{HcResult, HolochainError};
+use holochain_net::connection::{
+    json_protocol::{JsonProtocol, TrackDnaData},
+    net_connection::NetSend,
+};
+use std::{pin::Pin, sync::Arc};
+
+/// Leaves the network of the given state's DNA and stops its network proxy object.
+/// Stopping joins the network thread, which might be waiting for the action channel
+/// to take an action it dispatched, so this must not run inside a reducer.
+fn stop_network(network_state: &NetworkState) -> HcResult<()> {
+    let network = match network_state.network {
+        Some(ref network) => network.clone(),
+        None => return Ok(()),
+    };
+    let mut network = network.lock().unwrap();
+    if let (Some(dna_address), Some(agent_id)) = (
+        network_state.dna_address.clone(),
+        network_state.agent_id.clone(),
+    ) {
+        let json = JsonProtocol::UntrackDna(TrackDnaData {
+            dna_address,
+            agent_id,
+        });
+        network.send(json.into()).map_err(|error| {
+            HolochainError::IoError(format!("Could not untrack DNA: {}", error))
+        })?;
+    }
+    network
+        .stop()
+        .map_err(|error| HolochainError::IoError(format!("Could not stop network: {}", error)))
+}
+
+/// Publishes the pending publish batch, if any, then leaves the network of the
+/// instance's DNA and stops the network proxy object and removes it from the state.
+/// Resolves once the network connection is down. Does nothing if the network
+/// was not initialized.
+pub async fn shutdown_network(context: &Arc<Context>) -> HcResult<()> {
+    // publish what still waits for its batch while the network is up
+    if let Some(ref publish_batcher) = context.publish_batcher {
+        publish_batcher.flush(context);
+    }
+    let stopped = match context.state() {
+        Some(state) => stop_network(&state.network()),
+        None => Ok(()),
+    };
+    if let Err(ref error) = stopped {
+        context.log(format!("err/net/shutdown: {}", error));
+    }
+
+    let action_wrapper = ActionWrapper::new(Action::ShutdownNetwork);
+    dispatch_action(context.action_channel(), action_wrapper.clone());
+
+    await!(ShutdownNetworkFuture {
+        context: context.clone(),
+        action: action_wrapper,
+    })?;
+    stopped
+}
+
+/// ShutdownNetworkFuture resolves to the result of the ShutdownNetwork reducer.
+pub struct ShutdownNetworkFuture {
+    context: Arc<Context>,
+    action: ActionWrapper,
+}
+
+impl Future for ShutdownNetworkFuture {
+    type Output = HcResult<()>;
+
+    fn poll(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        //
+        // TODO: connect the waker to state updates for performance reasons
+        // See: https://github.com/holochain/holochain-rust/issues/314
+        //
+        lw.wake();
+        if let Some(state) = self.context.state() {
+            match state.network().actions().get(&self.action) {
+                Some(ActionResponse::ShutdownNetwork(result)) => Poll::Ready(result.clone()),
+                _ => Poll::Pending,
+            }
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::nucleus::actions::tests::{instance_by_name, test_dna};
+    use holochain_net::connection::protocol::Protocol;
+
+    #[test]
+    fn shutdown_network_stops_the_network() {
+        let netname = Some("shutdown_network_stops_the_network");
+        let (_instance, context) = instance_by_name("alice", test_dna(), netname);
+        let network = context
+            .state()
+            .unwrap()
+            .network()
+            .network
+            .clone()
+            .expect("Network must be initialized");
+
+        assert_eq!(context.block_on(shutdown_network(&context)), Ok(()));
+        assert_eq!(
+            context.state().unwrap().network().initialized(),
+            Err(HolochainError::NetworkNotInitialized)
+        );
+        assert!(network.lock().unwrap().send(Protocol::P2pReady).is_err());
+    }
+}
@@ -1,6 +1,48 @@
+use crate::context::Context;
 use holochain_core_types::{
-    cas::content::Address, error::HolochainError, json::JsonString, validation::ValidationPackage,
+    cas::content::Address, error::HolochainError, hash::HashString, json::JsonString,
+    validation::ValidationPackage,
 };
+use holochain_dpki::utils;
+use holochain_sodium::secbuf::SecBuf;
+use multihash::Hash;
+use snowflake::ProcessUniqueId;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// How far the timestamp of a received direct message may be off from our clock.
+pub const DEFAULT_REPLAY_WINDOW: Duration = Duration::from_secs(300);
+
+/// Maximum number of nonces a ReplayGuard remembers per sender.
+pub const DEFAULT_SEEN_NONCE_CAPACITY: usize = 1000;
+
+/// Maximum number of direct messages that wait for a free slot with
+/// DirectMessageOverflow::Queue.
+pub const DEFAULT_MAX_QUEUED_DIRECT_MESSAGES: usize = 1000;
+
+/// What happens to a direct message that gets sent while the maximum number of
+/// direct messages is already waiting for a response.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DirectMessageOverflow {
+    /// The message waits until one of the others got answered or timed out.
+    /// It still times out after the timeout given to send. Sending fails right away
+    /// if the queue is full, see DirectMessageLimit::max_queued.
+    Queue,
+    /// Sending fails right away.
+    Reject,
+}
+
+/// Caps the number of custom direct messages that wait for a response at the same time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DirectMessageLimit {
+    pub max_in_flight: usize,
+    pub overflow: DirectMessageOverflow,
+    /// Maximum number of messages waiting for a free slot with DirectMessageOverflow::Queue
+    pub max_queued: usize,
+}
 
 /// This is direct message that got created by the zome code through hdk::send().
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, DefaultJson)]
@@ -33,3 +75,234 @@ pub enum DirectMessage {
     /// "I can't"
     ValidationPackage(Option<ValidationPackage>),
 }
+
+/// What actually gets sent over the network for a DirectMessage.
+/// The nonce and the timestamp let the receiver tell a fresh message
+/// from one that got recorded and sent again by a relay.
+/// The sender signs them together with the recipient and the message, so a relay
+/// can neither replay a message with a fresh nonce nor redirect it to someone else.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct DirectMessageEnvelope {
+    pub nonce: String,
+    /// Milliseconds since the unix epoch, taken by the sender.
+    pub timestamp: u64,
+    pub message: DirectMessage,
+    /// Base64 signature of the sender's agent key over signed_payload().
+    pub signature: String,
+}
+
+impl DirectMessageEnvelope {
+    /// Wraps the given message for the given recipient and signs it with the agent key
+    /// of the given context.
+    pub fn new(
+        message: DirectMessage,
+        to_agent_id: &str,
+        context: &Context,
+    ) -> Result<Self, HolochainError> {
+        let nonce = ProcessUniqueId::new().to_string();
+        let timestamp = now_millis();
+        let signature = context.sign(signed_payload(&nonce, timestamp, to_agent_id, &message))?;
+        Ok(DirectMessageEnvelope {
+            nonce,
+            timestamp,
+            message,
+            signature,
+        })
+    }
+
+    /// Checks that the envelope got signed by the given sender for the given recipient.
+    pub fn verify(&self, from_agent_id: &str, to_agent_id: &str) -> Result<(), HolochainError> {
+        let signature_bytes = base64::decode(&self.signature).map_err(|_| {
+            HolochainError::ErrorGeneric("Direct message signature is not base64".to_string())
+        })?;
+        let mut signature_buf = SecBuf::with_insecure(signature_bytes.len());
+        signature_buf
+            .write(0, signature_bytes.as_slice())
+            .expect("SecBuf must be writeable");
+        let mut payload_buf = SecBuf::with_insecure_from_string(signed_payload(
+            &self.nonce,
+            self.timestamp,
+            to_agent_id,
+            &self.message,
+        ));
+        if utils::verify(
+            from_agent_id.to_string(),
+            &mut payload_buf,
+            &mut signature_buf,
+        )? {
+            Ok(())
+        } else {
+            Err(HolochainError::ErrorGeneric(format!(
+                "Direct message with nonce {} is not signed by {}",
+                self.nonce, from_agent_id
+            )))
+        }
+    }
+}
+
+/// What the sender of a direct message signs: nonce, timestamp, recipient and
+/// the hash of the message.
+fn signed_payload(
+    nonce: &str,
+    timestamp: u64,
+    to_agent_id: &str,
+    message: &DirectMessage,
+) -> String {
+    let message_json = serde_json::to_string(message).expect("DirectMessage must serialize");
+    let message_hash = HashString::encode_from_str(&message_json, Hash::SHA2256);
+    format!("{}:{}:{}:{}", nonce, timestamp, to_agent_id, message_hash)
+}
+
+/// Milliseconds since the unix epoch
+pub fn now_millis() -> u64 {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    since_epoch.as_secs() * 1000 + u64::from(since_epoch.subsec_millis())
+}
+
+/// Nonces of the messages one sender sent within the replay window.
+#[derive(Default)]
+struct SeenNonces {
+    seen: HashSet<(u64, String)>,
+    /// the same nonces in the order they were received, oldest first
+    order: VecDeque<(u64, String)>,
+}
+
+/// Rejects direct messages that were received before or that are too old to tell.
+/// Messages are identified by sender, timestamp and nonce, since nonces are only
+/// unique per sending process.
+/// Nonces are forgotten once their message falls out of the window. While `capacity`
+/// nonces of a sender within the window are remembered, further messages of that sender
+/// get rejected, so a flood of messages can't push out nonces of messages that could
+/// still be replayed. Other senders are not affected by one sender reaching its capacity.
+pub struct ReplayGuard {
+    window: Duration,
+    capacity: usize,
+    seen: HashMap<String, SeenNonces>,
+}
+
+impl ReplayGuard {
+    pub fn new(window: Duration, capacity: usize) -> Self {
+        ReplayGuard {
+            window,
+            capacity,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Checks a message received from the given agent at the given time
+    /// (in milliseconds since the unix epoch) and remembers it if it is fresh.
+    pub fn check(
+        &mut self,
+        from_agent_id: &str,
+        envelope: &DirectMessageEnvelope,
+        now: u64,
+    ) -> Result<(), HolochainError> {
+        let window = self.window.as_secs() * 1000 + u64::from(self.window.subsec_millis());
+        let skew = if envelope.timestamp > now {
+            envelope.timestamp - now
+        } else {
+            now - envelope.timestamp
+        };
+        if skew > window {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Direct message timestamp {} is outside of the replay window",
+                envelope.timestamp
+            )));
+        }
+
+        for nonces in self.seen.values_mut() {
+            while let Some((timestamp, _)) = nonces.order.front().cloned() {
+                if timestamp + window >= now {
+                    break;
+                }
+                let key = nonces.order.pop_front().unwrap();
+                nonces.seen.remove(&key);
+            }
+        }
+        self.seen.retain(|_, nonces| !nonces.order.is_empty());
+
+        let nonces = self.seen.entry(from_agent_id.to_string()).or_default();
+        let key = (envelope.timestamp, envelope.nonce.clone());
+        if nonces.seen.contains(&key) {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Direct message with nonce {} is a replay",
+                envelope.nonce
+            )));
+        }
+        if nonces.order.len() >= self.capacity {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Too many direct messages from {} within the replay window (maximum is {})",
+                from_agent_id, self.capacity
+            )));
+        }
+        nonces.seen.insert(key.clone());
+        nonces.order.push_back(key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn envelope(timestamp: u64) -> DirectMessageEnvelope {
+        DirectMessageEnvelope {
+            nonce: ProcessUniqueId::new().to_string(),
+            timestamp,
+            message: DirectMessage::RequestValidationPackage(Address::from("QmReplay")),
+            signature: String::new(),
+        }
+    }
+
+    #[test]
+    fn rejects_replayed_direct_messages() {
+        let mut guard = ReplayGuard::new(DEFAULT_REPLAY_WINDOW, DEFAULT_SEEN_NONCE_CAPACITY);
+        let now = now_millis();
+        let message = envelope(now);
+
+        assert_eq!(guard.check("alice", &message, now), Ok(()));
+        assert_eq!(
+            guard.check("alice", &message, now + 10),
+            Err(HolochainError::ErrorGeneric(format!(
+                "Direct message with nonce {} is a replay",
+                message.nonce
+            )))
+        );
+        assert_eq!(guard.check("alice", &envelope(now), now + 20), Ok(()));
+        // the same nonce coming from someone else is a different message
+        assert_eq!(guard.check("bob", &message, now + 30), Ok(()));
+    }
+
+    #[test]
+    fn rejects_direct_messages_outside_of_window() {
+        let mut guard = ReplayGuard::new(Duration::from_secs(10), DEFAULT_SEEN_NONCE_CAPACITY);
+        let now = 1_000_000;
+
+        assert!(guard.check("alice", &envelope(now - 10_001), now).is_err());
+        assert!(guard.check("alice", &envelope(now + 10_001), now).is_err());
+        assert_eq!(guard.check("alice", &envelope(now - 10_000), now), Ok(()));
+    }
+
+    #[test]
+    fn rejects_direct_messages_beyond_capacity_per_sender() {
+        let mut guard = ReplayGuard::new(Duration::from_secs(10), 2);
+        let now = 1_000_000;
+        let first = envelope(now);
+        guard.check("alice", &first, now).unwrap();
+        guard.check("mallory", &envelope(now), now).unwrap();
+        guard.check("mallory", &envelope(now), now).unwrap();
+
+        assert!(guard.check("mallory", &envelope(now), now).is_err());
+        // the flood neither pushed out the first nonce nor keeps others from sending
+        assert!(guard.check("alice", &first, now).is_err());
+        assert_eq!(guard.check("alice", &envelope(now), now), Ok(()));
+        assert_eq!(guard.check("bob", &envelope(now), now), Ok(()));
+
+        // nonces out of the window make room again
+        let later = now + 10_001;
+        assert_eq!(guard.check("mallory", &envelope(later), later), Ok(()));
+        assert_eq!(guard.seen.len(), 1);
+    }
+}
@@ -0,0 +1,360 @@
+//! Entries whose serialization exceeds the configured maximum message size
+//! (see Context::network_max_message_size) get published as several EntryFragments.
+//! The receiving node collects them in a FragmentBuffer and passes the reassembled
+//! EntryWithHeader on to the hold workflow once all fragments have arrived.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Time after which an incomplete set of fragments gets discarded.
+pub const DEFAULT_FRAGMENT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Biggest content a fragmented message may add up to once reassembled.
+pub const MAX_REASSEMBLED_SIZE: usize = 16 * 1024 * 1024;
+
+/// Smallest fragment fragment_content() creates, big enough for any escaped char.
+const MIN_FRAGMENT_SIZE: usize = 6;
+
+/// Space left for the message around the data of a fragment (protocol fields, addresses,
+/// agent id and fragment position) when computing how many fragments a message can have.
+/// The publishing node measures the actual size of its messages.
+pub const ENVELOPE_ALLOWANCE: usize = 1024;
+
+/// Maximum number of fragments accepted per message if no maximum message size is set,
+/// in which case this node does not fragment anything itself.
+pub const DEFAULT_MAX_FRAGMENT_COUNT: usize = 1024;
+
+/// Maximum number of messages that wait for more fragments at the same time.
+pub const DEFAULT_MAX_PENDING_MESSAGES: usize = 100;
+
+/// Highest fragment count a message can legitimately have if fragments are sent in
+/// messages of at most `max_message_size` bytes and the message is at most
+/// MAX_REASSEMBLED_SIZE big.
+/// Fragmented content is JSON, which at most doubles in size when escaped as fragment data.
+pub fn max_fragment_count(max_message_size: Option<usize>) -> usize {
+    match max_message_size {
+        Some(max_size) => {
+            let data_size = max_size
+                .saturating_sub(ENVELOPE_ALLOWANCE)
+                .max(MIN_FRAGMENT_SIZE);
+            (2 * MAX_REASSEMBLED_SIZE + data_size - 1) / data_size
+        }
+        None => DEFAULT_MAX_FRAGMENT_COUNT,
+    }
+}
+
+/// One part of a message that was too big to be sent at once.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EntryFragment {
+    /// shared by all fragments of the same message
+    pub id: String,
+    /// position of this fragment, starting at 0
+    pub index: usize,
+    /// number of fragments the message got split into
+    pub count: usize,
+    pub data: String,
+}
+
+/// Number of bytes the given char takes in a JSON string.
+fn escaped_len(c: char) -> usize {
+    match c {
+        '"' | '\\' | '\u{8}' | '\u{c}' | '\n' | '\r' | '\t' => 2,
+        c if (c as u32) < 0x20 => 6,
+        c => c.len_utf8(),
+    }
+}
+
+/// Splits the given content into fragments whose data takes at most `max_size` bytes
+/// once serialized, i.e. with quotes and backslashes escaped.
+/// Content is only split at char boundaries, so fragments can come out slightly smaller.
+pub fn fragment_content(id: &str, content: &str, max_size: usize) -> Vec<EntryFragment> {
+    let max_size = max_size.max(MIN_FRAGMENT_SIZE);
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    let mut chunk_size = 0;
+    for c in content.chars() {
+        if chunk_size + escaped_len(c) > max_size {
+            chunks.push(std::mem::replace(&mut chunk, String::new()));
+            chunk_size = 0;
+        }
+        chunk.push(c);
+        chunk_size += escaped_len(c);
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    let count = chunks.len();
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, data)| EntryFragment {
+            id: id.to_string(),
+            index,
+            count,
+            data,
+        })
+        .collect()
+}
+
+/// Tells fragmented messages apart. Fragment ids are only unique within the process
+/// that sent them, so messages of different nodes are told apart by the agent
+/// that provides them and the address of their entry too.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct MessageKey {
+    provider_agent_id: String,
+    entry_address: Address,
+    id: String,
+}
+
+struct PendingMessage {
+    parts: Vec<Option<String>>,
+    received: usize,
+    first_received: Instant,
+}
+
+/// Collects fragments until their message is complete.
+/// Fragments may arrive in any order. Messages that are still incomplete after the
+/// timeout get dropped, as do fragments that don't fit the message they claim to be part of.
+/// Since fragments come from other nodes, the fragment count of a message and the number
+/// of incomplete messages are limited. Fragments beyond these limits get dropped.
+pub struct FragmentBuffer {
+    pending: HashMap<MessageKey, PendingMessage>,
+    timeout: Duration,
+    max_count: usize,
+    max_pending: usize,
+}
+
+impl FragmentBuffer {
+    pub fn new(timeout: Duration) -> Self {
+        FragmentBuffer {
+            pending: HashMap::new(),
+            timeout,
+            max_count: DEFAULT_MAX_FRAGMENT_COUNT,
+            max_pending: DEFAULT_MAX_PENDING_MESSAGES,
+        }
+    }
+
+    /// Sets the maximum number of fragments a message may claim to consist of,
+    /// see max_fragment_count().
+    pub fn with_max_count(mut self, max_count: usize) -> Self {
+        self.max_count = max_count;
+        self
+    }
+
+    /// Sets the maximum number of messages that wait for more fragments at the same time.
+    pub fn with_max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending;
+        self
+    }
+
+    /// Adds a fragment the given agent published for the given entry and returns the
+    /// reassembled content if this was the last missing fragment of its message.
+    pub fn add(
+        &mut self,
+        provider_agent_id: &str,
+        entry_address: &Address,
+        fragment: EntryFragment,
+        now: Instant,
+    ) -> Option<String> {
+        self.discard_expired(now);
+        if fragment.count == 0
+            || fragment.index >= fragment.count
+            || fragment.count > self.max_count
+        {
+            return None;
+        }
+        let key = MessageKey {
+            provider_agent_id: provider_agent_id.to_string(),
+            entry_address: entry_address.clone(),
+            id: fragment.id.clone(),
+        };
+        if !self.pending.contains_key(&key) && self.pending.len() >= self.max_pending {
+            return None;
+        }
+        let message = self
+            .pending
+            .entry(key.clone())
+            .or_insert_with(|| PendingMessage {
+                parts: vec![None; fragment.count],
+                received: 0,
+                first_received: now,
+            });
+        if message.parts.len() != fragment.count {
+            // inconsistent fragment count, the message can't be reassembled
+            self.pending.remove(&key);
+            return None;
+        }
+        if message.parts[fragment.index].is_none() {
+            message.parts[fragment.index] = Some(fragment.data);
+            message.received += 1;
+        }
+        if message.received < message.parts.len() {
+            return None;
+        }
+        self.pending
+            .remove(&key)
+            .map(|message| message.parts.into_iter().flatten().collect())
+    }
+
+    /// Drops all messages whose first fragment arrived longer than the timeout ago.
+    /// Returns the number of dropped messages.
+    pub fn discard_expired(&mut self, now: Instant) -> usize {
+        let timeout = self.timeout;
+        let before = self.pending.len();
+        self.pending
+            .retain(|_, message| now.duration_since(message.first_received) < timeout);
+        before - self.pending.len()
+    }
+
+    /// Number of messages that are waiting for more fragments.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::network::entry_with_header::EntryWithHeader;
+    use holochain_core_types::{
+        cas::content::AddressableContent,
+        chain_header::test_chain_header,
+        entry::{entry_type::test_app_entry_type, Entry},
+        json::{JsonString, RawString},
+    };
+
+    fn big_entry_with_header() -> EntryWithHeader {
+        let value = "große Einträge ".repeat(200);
+        EntryWithHeader {
+            entry: Entry::App(
+                test_app_entry_type(),
+                JsonString::from(RawString::from(value)),
+            ),
+            header: test_chain_header(),
+        }
+    }
+
+    #[test]
+    fn oversized_entry_gets_reassembled() {
+        let entry_with_header = big_entry_with_header();
+        let content = serde_json::to_string(&entry_with_header).unwrap();
+        let mut fragments = fragment_content("msg-1", &content, 256);
+        assert!(fragments.len() > 1);
+        // the quotes of the JSON content get escaped in the serialized fragment
+        let serialized_data_size =
+            |fragment: &EntryFragment| serde_json::to_string(&fragment.data).unwrap().len() - 2;
+        assert!(fragments
+            .iter()
+            .all(|fragment| serialized_data_size(fragment) <= 256));
+
+        // fragments arrive in reverse order, interleaved with another message
+        fragments.reverse();
+        let now = Instant::now();
+        let address = entry_with_header.entry.address();
+        let mut buffer = FragmentBuffer::new(DEFAULT_FRAGMENT_TIMEOUT);
+        let other = fragment_content("msg-2", "other message", 5);
+        assert_eq!(buffer.add("alice", &address, other[0].clone(), now), None);
+
+        let last = fragments.pop().unwrap();
+        for fragment in fragments {
+            assert_eq!(buffer.add("alice", &address, fragment, now), None);
+        }
+        let reassembled = buffer
+            .add("alice", &address, last, now)
+            .expect("message should be complete");
+        let reassembled: EntryWithHeader = serde_json::from_str(&reassembled).unwrap();
+        assert_eq!(reassembled, entry_with_header);
+        assert_eq!(buffer.pending(), 1);
+    }
+
+    #[test]
+    fn incomplete_messages_time_out() {
+        let fragments = fragment_content("msg-1", "some content to split", 4);
+        let start = Instant::now();
+        let address = Address::from("entry");
+        let mut buffer = FragmentBuffer::new(Duration::from_secs(10));
+        assert_eq!(
+            buffer.add("alice", &address, fragments[0].clone(), start),
+            None
+        );
+        assert_eq!(
+            buffer.add("alice", &address, fragments[1].clone(), start),
+            None
+        );
+        assert_eq!(buffer.pending(), 1);
+
+        let later = start + Duration::from_secs(11);
+        assert_eq!(buffer.discard_expired(later), 1);
+        assert_eq!(buffer.pending(), 0);
+
+        // the remaining fragments alone don't make up the message anymore
+        for fragment in fragments.into_iter().skip(2) {
+            assert_eq!(buffer.add("alice", &address, fragment, later), None);
+        }
+        assert_eq!(buffer.pending(), 1);
+    }
+
+    #[test]
+    fn fragments_beyond_the_limits_get_dropped() {
+        let now = Instant::now();
+        let address = Address::from("entry");
+        let mut buffer = FragmentBuffer::new(DEFAULT_FRAGMENT_TIMEOUT)
+            .with_max_count(max_fragment_count(Some(1024 * 1024)))
+            .with_max_pending(2);
+        assert_eq!(max_fragment_count(Some(1024 * 1024)), 33);
+
+        // a peer claiming a huge count doesn't make us allocate for it
+        let oversized = EntryFragment {
+            id: String::from("huge"),
+            index: 0,
+            count: usize::max_value(),
+            data: String::from("data"),
+        };
+        assert_eq!(buffer.add("alice", &address, oversized, now), None);
+        let just_too_many = EntryFragment {
+            id: String::from("too many"),
+            index: 0,
+            count: 34,
+            data: String::from("data"),
+        };
+        assert_eq!(buffer.add("alice", &address, just_too_many, now), None);
+        assert_eq!(buffer.pending(), 0);
+
+        // only two incomplete messages get buffered
+        let first = fragment_content("msg-1", "first message", 4);
+        let second = fragment_content("msg-2", "second message", 4);
+        let third = fragment_content("msg-3", "third message", 4);
+        assert_eq!(buffer.add("alice", &address, first[0].clone(), now), None);
+        assert_eq!(buffer.add("alice", &address, second[0].clone(), now), None);
+        assert_eq!(buffer.add("alice", &address, third[0].clone(), now), None);
+        assert_eq!(buffer.pending(), 2);
+
+        // fragments of buffered messages are still accepted
+        let mut reassembled = None;
+        for fragment in first.into_iter().skip(1) {
+            reassembled = buffer.add("alice", &address, fragment, now);
+        }
+        assert_eq!(reassembled, Some(String::from("first message")));
+        assert_eq!(buffer.pending(), 1);
+    }
+
+    #[test]
+    fn fragments_of_different_providers_dont_mix() {
+        let now = Instant::now();
+        let address = Address::from("entry");
+        let mut buffer = FragmentBuffer::new(DEFAULT_FRAGMENT_TIMEOUT);
+        // both nodes happen to use the same process unique id
+        let from_alice = fragment_content("msg-1", "alice's message", 6);
+        let from_bob = fragment_content("msg-1", "bob's message", 6);
+        assert_eq!(buffer.add("bob", &address, from_bob[0].clone(), now), None);
+
+        let mut reassembled = None;
+        for fragment in from_alice {
+            reassembled = buffer.add("alice", &address, fragment, now);
+        }
+        assert_eq!(reassembled, Some(String::from("alice's message")));
+        assert_eq!(buffer.pending(), 1);
+    }
+}
@@ -10,6 +10,50 @@ use holochain_net::connection::json_protocol::{
 };
 use std::{collections::BTreeSet, convert::TryInto, sync::Arc};
 
+/// Keeps the targets whose entry is of the given type.
+/// Targets whose entry we don't hold are kept since we can't tell their type.
+/// This only saves sending targets we know don't match, the requesting node checks
+/// the type of every target it gets, see network::actions::get_links.
+fn filter_by_entry_type(
+    context: &Arc<Context>,
+    targets: Vec<Address>,
+    entry_type: &str,
+) -> Vec<Address> {
+    targets
+        .into_iter()
+        .filter(
+            |target| match nucleus::actions::get_entry::get_entry_from_dht(context, target) {
+                Ok(Some(entry)) => entry.entry_type().to_string() == entry_type,
+                _ => true,
+            },
+        )
+        .collect()
+}
+
+/// The targets of the links a GET META request asks for, if it asks for links.
+fn links_for_fetch_meta(
+    fetch_meta_data: &FetchMetaData,
+    context: &Arc<Context>,
+) -> Option<Vec<Address>> {
+    let tag = match fetch_meta_data.attribute.as_str().try_into() {
+        Ok(Attribute::LinkTag(tag)) => tag,
+        _ => return None,
+    };
+    let links = context
+        .state()
+        .unwrap()
+        .dht()
+        .get_links(Address::from(fetch_meta_data.entry_address.clone()), tag)
+        .unwrap_or(BTreeSet::new())
+        .into_iter()
+        .map(|eav| eav.value())
+        .collect::<Vec<_>>();
+    Some(match fetch_meta_data.target_type {
+        Some(ref entry_type) => filter_by_entry_type(context, links, entry_type),
+        None => links,
+    })
+}
+
 /// The network has requested a DHT entry from us.
 /// Lets try to get it and trigger a response.
 pub fn handle_fetch_entry(get_dht_data: FetchEntryData, context: Arc<Context>) {
@@ -34,19 +78,7 @@ pub fn handle_fetch_entry_result(dht_data: FetchEntryResultData, context: Arc<Co
 }
 
 pub fn handle_fetch_meta(fetch_meta_data: FetchMetaData, context: Arc<Context>) {
-    if let Ok(Attribute::LinkTag(tag)) = fetch_meta_data.attribute.as_str().try_into() {
-        let links = context
-            .state()
-            .unwrap()
-            .dht()
-            .get_links(
-                Address::from(fetch_meta_data.entry_address.clone()),
-                tag.clone(),
-            )
-            .unwrap_or(BTreeSet::new())
-            .into_iter()
-            .map(|eav| eav.value())
-            .collect::<Vec<_>>();
+    if let Some(links) = links_for_fetch_meta(&fetch_meta_data, &context) {
         let action_wrapper = ActionWrapper::new(Action::RespondGetLinks((fetch_meta_data, links)));
         dispatch_action(context.action_channel(), action_wrapper.clone());
     }
@@ -59,3 +91,69 @@ pub fn handle_fetch_meta_result(dht_meta_data: FetchMetaResultData, context: Arc
         dispatch_action(context.action_channel(), action_wrapper.clone());
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::nucleus::actions::tests::instance;
+    use holochain_core_types::{
+        cas::content::AddressableContent,
+        eav::EntityAttributeValueIndex,
+        entry::{test_entry, test_entry_b, test_entry_c, test_entry_unique},
+    };
+
+    #[test]
+    fn links_get_filtered_by_target_type() {
+        let (_instance, context) = instance(Some("links_get_filtered_by_target_type"));
+        let base = test_entry_unique();
+        let targets = vec![test_entry(), test_entry_b(), test_entry_c()];
+        for entry in [&base].iter().cloned().chain(targets.iter()) {
+            context.dht_storage.write().unwrap().add(entry).unwrap();
+        }
+        for target in targets.iter() {
+            let eavi = EntityAttributeValueIndex::new(
+                &base.address(),
+                &Attribute::LinkTag("test-tag".to_string()),
+                &target.address(),
+            )
+            .unwrap();
+            context
+                .eav_storage
+                .write()
+                .unwrap()
+                .add_eavi(&eavi)
+                .unwrap();
+        }
+
+        let fetch_meta_data = |target_type: Option<&str>| FetchMetaData {
+            requester_agent_id: "bob".to_string(),
+            request_id: "request".to_string(),
+            dna_address: "QmDna".into(),
+            entry_address: base.address(),
+            attribute: "link__test-tag".to_string(),
+            target_type: target_type.map(String::from),
+        };
+        let sorted = |mut addresses: Vec<Address>| {
+            addresses.sort();
+            addresses
+        };
+
+        assert_eq!(
+            links_for_fetch_meta(&fetch_meta_data(None), &context).map(sorted),
+            Some(sorted(
+                targets.iter().map(|entry| entry.address()).collect()
+            ))
+        );
+        assert_eq!(
+            links_for_fetch_meta(&fetch_meta_data(Some("testEntryTypeB")), &context).map(sorted),
+            Some(sorted(vec![
+                test_entry_b().address(),
+                test_entry_c().address()
+            ]))
+        );
+        assert_eq!(
+            links_for_fetch_meta(&fetch_meta_data(Some("testEntryType")), &context),
+            Some(vec![test_entry().address()])
+        );
+    }
+}
@@ -6,6 +6,8 @@ use crate::{
     context::Context,
     network::{
         actions::publish::publish,
+        direct_message::{ReplayGuard, DEFAULT_SEEN_NONCE_CAPACITY},
+        fragment::{max_fragment_count, FragmentBuffer, DEFAULT_FRAGMENT_TIMEOUT},
         handler::{get::*, send::*, store::*},
     },
 };
@@ -13,8 +15,13 @@ use holochain_core_types::{
     cas::content::{Address, AddressableContent},
     hash::HashString,
 };
-use holochain_net::connection::{json_protocol::JsonProtocol, net_connection::NetHandler};
-use std::{convert::TryFrom, sync::Arc};
+use holochain_net::connection::{
+    json_protocol::JsonProtocol, net_connection::NetHandler, protocol::Protocol,
+};
+use std::{
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+};
 
 // FIXME: Temporary hack to ignore messages incorrectly sent to us by the networking
 // module that aren't really meant for us
@@ -53,12 +60,25 @@ fn is_my_id(context: &Arc<Context>, agent_id: &str) -> bool {
 /// has to handle.
 pub fn create_handler(c: &Arc<Context>) -> NetHandler {
     let context = c.clone();
+    let fragments = Arc::new(Mutex::new(
+        FragmentBuffer::new(DEFAULT_FRAGMENT_TIMEOUT)
+            .with_max_count(max_fragment_count(context.network_max_message_size)),
+    ));
+    let replay_guard = Arc::new(Mutex::new(ReplayGuard::new(
+        context.direct_message_replay_window,
+        DEFAULT_SEEN_NONCE_CAPACITY,
+    )));
     Box::new(move |message| {
         let message = message.unwrap();
         // context.log(format!(
         //   "trace/net/handle:({}): {:?}",
         //   context.agent_id.nick, message
         // ));
+        if let Protocol::Json(ref raw_message) = message {
+            if reject_oversized_validation_package(raw_message.as_ref(), &context) {
+                return Ok(());
+            }
+        }
         let maybe_json_msg = JsonProtocol::try_from(message);
         if let Err(_) = maybe_json_msg {
             return Ok(());
@@ -82,7 +102,7 @@ pub fn create_handler(c: &Arc<Context>) -> NetHandler {
                     "debug/net/handle: HandleStoreEntry: {:?}",
                     dht_entry_data
                 ));
-                handle_store_entry(dht_entry_data, context.clone())
+                handle_store_entry(dht_entry_data, context.clone(), &fragments)
             }
             JsonProtocol::HandleStoreMeta(dht_meta_data) => {
                 if !is_my_dna(&context, &dht_meta_data.dna_address) {
@@ -174,7 +194,7 @@ pub fn create_handler(c: &Arc<Context>) -> NetHandler {
                     "debug/net/handle: HandleSendMessage: {:?}",
                     message_data
                 ));
-                handle_send_message(message_data, context.clone())
+                handle_send_message(message_data, context.clone(), &replay_guard)
             }
             JsonProtocol::SendMessageResult(message_data) => {
                 if !is_my_dna(&context, &message_data.dna_address) {
@@ -188,7 +208,7 @@ pub fn create_handler(c: &Arc<Context>) -> NetHandler {
                     "debug/net/handle: SendMessageResult: {:?}",
                     message_data
                 ));
-                handle_send_message_result(message_data, context.clone())
+                handle_send_message_result(message_data, context.clone(), &replay_guard)
             }
             JsonProtocol::PeerConnected(peer_data) => {
                 // ignore peer connection of myself
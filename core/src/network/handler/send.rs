@@ -2,22 +2,127 @@ use crate::{
     action::{Action, ActionWrapper},
     context::Context,
     instance::dispatch_action,
-    network::direct_message::DirectMessage,
+    network::direct_message::{now_millis, DirectMessage, DirectMessageEnvelope, ReplayGuard},
     workflows::{
         handle_custom_direct_message::handle_custom_direct_message,
         respond_validation_package_request::respond_validation_package_request,
     },
 };
-use holochain_core_types::cas::content::Address;
-use std::{sync::Arc, thread};
+use holochain_core_types::{cas::content::Address, error::HolochainError};
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+};
 
 use holochain_net::connection::json_protocol::MessageData;
 
+/// Unpacks the DirectMessage sent with the given network message.
+/// Returns None if it can't be parsed, is not signed by its sender for us
+/// or if the replay guard rejects it.
+fn open_envelope(
+    message_data: &MessageData,
+    context: &Arc<Context>,
+    replay_guard: &Arc<Mutex<ReplayGuard>>,
+) -> Option<DirectMessage> {
+    let envelope: DirectMessageEnvelope = match serde_json::from_value(message_data.content.clone())
+    {
+        Ok(envelope) => envelope,
+        Err(error) => {
+            context.log(format!(
+                "err/net: Could not parse direct message: {}",
+                error
+            ));
+            return None;
+        }
+    };
+    let my_id = context.agent_id.pub_sign_key.clone();
+    let checked = envelope
+        .verify(&message_data.from_agent_id, &my_id)
+        .and_then(|_| {
+            replay_guard
+                .lock()
+                .unwrap()
+                .check(&message_data.from_agent_id, &envelope, now_millis())
+        });
+    if let Err(error) = checked {
+        context.log(format!(
+            "warning/net: Rejected direct message from {}: {}",
+            message_data.from_agent_id, error
+        ));
+        return None;
+    }
+    Some(envelope.message)
+}
+
+/// The fields of a raw network message needed to tell whether it answers one of our
+/// direct messages. Everything else gets skipped without being parsed into values.
+#[derive(Deserialize)]
+struct MessageHeader {
+    method: String,
+    #[serde(rename = "_id", default)]
+    request_id: String,
+    #[serde(rename = "fromAgentId", default)]
+    from_agent_id: String,
+}
+
+/// Rejects the given raw network message if it is a response to one of our validation
+/// package requests and bigger than the context allows. This gets checked before the
+/// message gets parsed, so a source can't make us allocate arbitrarily big packages.
+/// The request then fails and its connection gets resolved.
+/// Returns true if the message got rejected and must not be handled any further.
+pub fn reject_oversized_validation_package(raw_message: &str, context: &Arc<Context>) -> bool {
+    let max_size = match context.max_validation_package_size {
+        Some(max_size) => max_size,
+        None => return false,
+    };
+    if raw_message.len() <= max_size {
+        return false;
+    }
+    let header: MessageHeader = match serde_json::from_str(raw_message) {
+        Ok(header) => header,
+        Err(_) => return false,
+    };
+    if header.method != "sendMessageResult" {
+        return false;
+    }
+    let initial_message = context.state().and_then(|state| {
+        state
+            .network()
+            .direct_message_connections
+            .get(&header.request_id)
+            .cloned()
+    });
+    let address = match initial_message {
+        Some(DirectMessage::RequestValidationPackage(address)) => address,
+        _ => return false,
+    };
+
+    let error = HolochainError::ErrorGeneric(format!(
+        "Validation package from {} is {} bytes, more than the maximum of {}",
+        header.from_agent_id,
+        raw_message.len(),
+        max_size
+    ));
+    context.log(format!("warning/net: {}", error));
+    let action_wrapper =
+        ActionWrapper::new(Action::HandleGetValidationPackage((address, Err(error))));
+    dispatch_action(context.action_channel(), action_wrapper);
+    let action_wrapper = ActionWrapper::new(Action::ResolveDirectConnection(header.request_id));
+    dispatch_action(context.action_channel(), action_wrapper);
+    true
+}
+
 /// We got a ProtocolWrapper::SendMessage, this means somebody initiates message roundtrip
 /// -> we are being called
-pub fn handle_send_message(message_data: MessageData, context: Arc<Context>) {
-    let message: DirectMessage =
-        serde_json::from_str(&serde_json::to_string(&message_data.content).unwrap()).unwrap();
+pub fn handle_send_message(
+    message_data: MessageData,
+    context: Arc<Context>,
+    replay_guard: &Arc<Mutex<ReplayGuard>>,
+) {
+    let message = match open_envelope(&message_data, &context, replay_guard) {
+        Some(message) => message,
+        None => return,
+    };
 
     match message {
         DirectMessage::Custom(custom_direct_message) => {
@@ -54,10 +159,11 @@ pub fn handle_send_message(message_data: MessageData, context: Arc<Context>) {
 
 /// We got a JsonProtocol::HandleSendMessageResult.
 /// This means somebody has responded to our message that we called and this is the answer
-pub fn handle_send_message_result(message_data: MessageData, context: Arc<Context>) {
-    let response: DirectMessage =
-        serde_json::from_str(&serde_json::to_string(&message_data.content).unwrap()).unwrap();
-
+pub fn handle_send_message_result(
+    message_data: MessageData,
+    context: Arc<Context>,
+    replay_guard: &Arc<Mutex<ReplayGuard>>,
+) {
     let initial_message = context
         .state()
         .unwrap()
@@ -67,6 +173,11 @@ pub fn handle_send_message_result(message_data: MessageData, context: Arc<Contex
         .get(&message_data.request_id)
         .cloned();
 
+    let response = match open_envelope(&message_data, &context, replay_guard) {
+        Some(response) => response,
+        None => return,
+    };
+
     match response {
         DirectMessage::Custom(custom_direct_message) => {
             if initial_message.is_none() {
@@ -98,7 +209,7 @@ pub fn handle_send_message_result(message_data: MessageData, context: Arc<Contex
 
             let action_wrapper = ActionWrapper::new(Action::HandleGetValidationPackage((
                 address.clone(),
-                maybe_validation_package.clone(),
+                Ok(maybe_validation_package.clone()),
             )));
             dispatch_action(context.action_channel(), action_wrapper.clone());
 
@@ -108,3 +219,111 @@ pub fn handle_send_message_result(message_data: MessageData, context: Arc<Contex
         }
     };
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        instance::tests::{test_context, test_context_and_logger, test_instance_with_context},
+        network::handler::create_handler,
+    };
+    use holochain_core_types::cas::content::AddressableContent;
+    use holochain_net::connection::{json_protocol::JsonProtocol, net_connection::NetHandler};
+    use snowflake::ProcessUniqueId;
+    use test_utils::create_test_dna_with_wat;
+
+    /// Hands the given envelope to the handler as a direct message from `from` to `to`.
+    fn deliver(
+        handler: &mut NetHandler,
+        envelope: &DirectMessageEnvelope,
+        from: &Arc<Context>,
+        to: &Arc<Context>,
+    ) {
+        let dna_address = to.state().unwrap().nucleus().dna().unwrap().address();
+        let message_data = MessageData {
+            request_id: ProcessUniqueId::new().to_string(),
+            dna_address,
+            to_agent_id: to.agent_id.pub_sign_key.clone(),
+            from_agent_id: from.agent_id.pub_sign_key.clone(),
+            content: serde_json::to_value(envelope).unwrap(),
+        };
+        handler(Ok(JsonProtocol::HandleSendMessage(message_data).into())).unwrap();
+    }
+
+    #[test]
+    fn handler_rejects_replayed_and_forged_direct_messages() {
+        let alice = test_context("alice", None);
+        let (bob, logger) = test_context_and_logger("bob", None);
+        let dna = create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (instance, bob) = test_instance_with_context(dna, bob).unwrap();
+        let bob = instance.initialize_context(bob);
+        let mut handler = create_handler(&bob);
+        // a ValidationPackage as initial message only gets logged when it gets handled
+        let handled = || {
+            logger
+                .lock()
+                .unwrap()
+                .log
+                .iter()
+                .filter(|line| line.contains("ValidationPackage as initial message"))
+                .count()
+        };
+        let message = DirectMessage::ValidationPackage(None);
+
+        let envelope =
+            DirectMessageEnvelope::new(message.clone(), &bob.agent_id.pub_sign_key, &alice)
+                .unwrap();
+        deliver(&mut handler, &envelope, &alice, &bob);
+        assert_eq!(handled(), 1);
+
+        // relayed again as it is
+        deliver(&mut handler, &envelope, &alice, &bob);
+        assert_eq!(handled(), 1);
+
+        // relayed again with a fresh nonce and timestamp
+        let mut refreshed = envelope.clone();
+        refreshed.nonce = ProcessUniqueId::new().to_string();
+        refreshed.timestamp = now_millis();
+        deliver(&mut handler, &refreshed, &alice, &bob);
+        assert_eq!(handled(), 1);
+
+        // signed for someone else
+        let redirected = DirectMessageEnvelope::new(message, "someone else", &alice).unwrap();
+        deliver(&mut handler, &redirected, &alice, &bob);
+        assert_eq!(handled(), 1);
+    }
+
+    #[test]
+    fn only_oversized_validation_package_responses_get_rejected() {
+        let mut context = test_context("alice", None);
+        Arc::get_mut(&mut context)
+            .unwrap()
+            .max_validation_package_size = Some(64);
+        let padding = "x".repeat(128);
+
+        let small_response = r#"{"method":"sendMessageResult","_id":"req"}"#;
+        assert!(!reject_oversized_validation_package(
+            small_response,
+            &context
+        ));
+
+        let other_message = format!(
+            r#"{{"method":"handleStoreEntry","_id":"req","padding":"{}"}}"#,
+            padding
+        );
+        assert!(!reject_oversized_validation_package(
+            &other_message,
+            &context
+        ));
+
+        // not a response to one of our validation package requests
+        let unknown_response = format!(
+            r#"{{"method":"sendMessageResult","_id":"req","padding":"{}"}}"#,
+            padding
+        );
+        assert!(!reject_oversized_validation_package(
+            &unknown_response,
+            &context
+        ));
+    }
+}
@@ -1,6 +1,10 @@
 use crate::{
+    background_tasks::TaskKind,
     context::Context,
-    network::entry_with_header::EntryWithHeader,
+    network::{
+        entry_with_header::EntryWithHeader,
+        fragment::{EntryFragment, FragmentBuffer},
+    },
     workflows::{
         hold_entry::hold_entry_workflow, hold_link::hold_link_workflow,
         remove_link::remove_link_workflow,
@@ -8,17 +12,50 @@ use crate::{
 };
 use holochain_core_types::eav::Attribute;
 use holochain_net::connection::json_protocol::{DhtMetaData, EntryData};
-use std::{sync::Arc, thread};
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
 /// The network requests us to store (i.e. hold) the given entry.
-pub fn handle_store_entry(dht_data: EntryData, context: Arc<Context>) {
-    let entry_with_header: EntryWithHeader =
-        serde_json::from_str(&serde_json::to_string(&dht_data.entry_content).unwrap()).unwrap();
-    thread::spawn(move || {
-        match context.block_on(hold_entry_workflow(entry_with_header, context.clone())) {
-            Err(error) => context.log(format!("err/net/dht: {}", error)),
-            _ => (),
+/// Fragments of entries that were too big for a single message get collected in the
+/// given buffer and the entry gets held once all its fragments have arrived.
+pub fn handle_store_entry(
+    dht_data: EntryData,
+    context: Arc<Context>,
+    fragments: &Arc<Mutex<FragmentBuffer>>,
+) {
+    let entry_content =
+        match serde_json::from_value::<EntryFragment>(dht_data.entry_content.clone()) {
+            Ok(fragment) => match fragments.lock().unwrap().add(
+                &dht_data.provider_agent_id,
+                &dht_data.entry_address,
+                fragment,
+                Instant::now(),
+            ) {
+                Some(content) => content,
+                None => return,
+            },
+            Err(_) => serde_json::to_string(&dht_data.entry_content).unwrap(),
+        };
+    let entry_with_header: EntryWithHeader = match serde_json::from_str(&entry_content) {
+        Ok(entry_with_header) => entry_with_header,
+        Err(error) => {
+            context.log(format!(
+                "err/net/dht: HandleStoreEntry: could not parse entry: {}",
+                error
+            ));
+            return;
+        }
+    };
+    context.clone().spawn_tracked(TaskKind::HoldEntry, move || {
+        let result = context
+            .block_on(hold_entry_workflow(entry_with_header, context.clone()))
+            .map(|_| ());
+        if let Err(ref error) = result {
+            context.log(format!("err/net/dht: {}", error));
         }
+        result
     });
 }
 
@@ -37,11 +74,12 @@ pub fn handle_store_meta(dht_meta_data: DhtMetaData, context: Arc<Context>) {
                 .expect("dht_meta_data should be EntryWithHeader"),
         )
         .expect("dht_meta_data should be EntryWithHeader");
-        thread::spawn(move || {
-            match context.block_on(hold_link_workflow(&entry_with_header, &context.clone())) {
-                Err(error) => context.log(format!("err/net/dht: {}", error)),
-                _ => (),
+        context.clone().spawn_tracked(TaskKind::HoldLink, move || {
+            let result = context.block_on(hold_link_workflow(&entry_with_header, &context.clone()));
+            if let Err(ref error) = result {
+                context.log(format!("err/net/dht: {}", error));
             }
+            result
         });
     } else if attr == Attribute::LinkRemove.to_string() {
         context.log("debug/net/handle: HandleStoreMeta: got LINK REMOVAL. processing...");
@@ -53,13 +91,16 @@ pub fn handle_store_meta(dht_meta_data: DhtMetaData, context: Arc<Context>) {
                 .expect("dht_meta_data should be EntryWithHader"),
         )
         .expect("dht_meta_data should be EntryWithHader");
-        thread::spawn(move || {
-            if let Err(error) =
-                context.block_on(remove_link_workflow(&entry_with_header, &context.clone()))
-            {
-                context.log(format!("err/net/dht: {}", error))
-            }
-        });
+        context
+            .clone()
+            .spawn_tracked(TaskKind::RemoveLink, move || {
+                let result =
+                    context.block_on(remove_link_workflow(&entry_with_header, &context.clone()));
+                if let Err(ref error) = result {
+                    context.log(format!("err/net/dht: {}", error))
+                }
+                result
+            });
     } else if attr == Attribute::CrudStatus.to_string() {
         context.log("debug/net/handle: HandleStoreMeta: got CRUD status. processing...");
     // FIXME: block_on hold crud_status metadata in DHT?
@@ -1,7 +1,9 @@
 pub mod actions;
 pub mod direct_message;
 pub mod entry_with_header;
+pub mod fragment;
 pub mod handler;
+pub mod publish_batch;
 pub mod reducers;
 pub mod state;
 #[cfg(test)]
@@ -11,39 +13,52 @@ pub mod test_utils;
 pub mod tests {
     use crate::{
         agent::{actions::commit::commit_entry, state::create_new_chain_header},
-        instance::tests::test_instance_and_context_by_name,
+        dht::actions::add_link::add_link,
+        instance::{
+            tests::{test_context, test_instance_and_context_by_name, test_instance_with_context},
+            Instance,
+        },
         network::{
             actions::{
-                get_entry::get_entry, get_links::get_links,
-                get_validation_package::get_validation_package, publish::publish,
+                get_entry::{get_entry, get_entry_if_newer_than, FetchedEntry},
+                get_links::{get_all_links, get_links, get_links_to},
+                get_validation_package::get_validation_package,
+                publish::publish,
             },
-            test_utils::test_wat_always_valid,
+            test_utils::{shared_storage_contexts, test_wat_always_valid, NetworkSpy},
         },
         workflows::{author_entry::author_entry, get_entry_result::get_entry_result_workflow},
     };
     use holochain_core_types::{
         cas::content::{Address, AddressableContent},
-        crud_status::CrudStatus,
-        entry::{entry_type::test_app_entry_type, test_entry, Entry, EntryWithMeta},
-        link::link_data::LinkData,
+        crud_status::{create_crud_status_eav, CrudStatus},
+        eav::{Attribute, EntityAttributeValueIndex},
+        entry::{
+            entry_type::{test_app_entry_type, test_app_entry_type_b},
+            test_entry, Entry, EntryWithMeta,
+        },
+        error::HolochainError,
+        link::{link_data::LinkData, Link},
         time::Timeout,
     };
+    use holochain_net::connection::{
+        json_protocol::{JsonProtocol, MessageData},
+        net_connection::NetSend,
+    };
     use holochain_wasm_utils::api_serialization::get_entry::{
         GetEntryArgs, GetEntryOptions, GetEntryResultType,
     };
+    use std::sync::Arc;
     use test_utils::*;
 
     // TODO: Should wait for a success or saturation response from the network module after Publish
     #[test]
     #[ignore]
     fn get_entry_roundtrip() {
-        let netname = Some("get_entry_roundtrip");
         let mut dna = create_test_dna_with_wat("test_zome", "test_cap", None);
-        dna.uuid = netname.unwrap().to_string();
-        let (_, context1) =
-            test_instance_and_context_by_name(dna.clone(), "alice1", netname).unwrap();
-        let (_, context2) =
-            test_instance_and_context_by_name(dna.clone(), "bob1", netname).unwrap();
+        dna.uuid = "get_entry_roundtrip".to_string();
+        let contexts = shared_storage_contexts(dna, &["alice1", "bob1"]);
+        let (context1, context2) = (contexts[0].clone(), contexts[1].clone());
 
         // Create Entry & metadata
         let entry = test_entry();
@@ -84,13 +99,10 @@ pub mod tests {
 
     #[test]
     fn get_entry_results_roundtrip() {
-        let netname = Some("get_entry_results_roundtrip");
         let mut dna = create_test_dna_with_wat("test_zome", "test_cap", None);
-        dna.uuid = netname.unwrap().to_string();
-        let (_, context1) =
-            test_instance_and_context_by_name(dna.clone(), "alex", netname).unwrap();
-        let (_, context2) =
-            test_instance_and_context_by_name(dna.clone(), "billy", netname).unwrap();
+        dna.uuid = "get_entry_results_roundtrip".to_string();
+        let contexts = shared_storage_contexts(dna, &["alex", "billy"]);
+        let (context1, context2) = (contexts[0].clone(), contexts[1].clone());
 
         // Create Entry & crud-status metadata, and store it.
         let entry = test_entry();
@@ -122,6 +134,76 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn holders_answer_not_modified_for_known_versions() {
+        let mut dna = create_test_dna_with_wat("test_zome", "test_cap", None);
+        dna.uuid = "holders_answer_not_modified_for_known_versions".to_string();
+        let contexts = shared_storage_contexts(dna, &["alex4", "billy4"]);
+        let context2 = contexts[1].clone();
+
+        // Held by both nodes through the shared storage, so it does not matter which one answers
+        let entry = test_entry();
+        context2.dht_storage.write().unwrap().add(&entry).unwrap();
+        context2
+            .eav_storage
+            .write()
+            .unwrap()
+            .add_eavi(&create_crud_status_eav(&entry.address(), CrudStatus::Live).unwrap())
+            .unwrap();
+
+        let fetch = |if_newer_than: Option<Address>| {
+            context2
+                .block_on(get_entry_if_newer_than(
+                    context2.clone(),
+                    entry.address(),
+                    if_newer_than,
+                    Timeout::new(1000),
+                ))
+                .expect("get_entry_if_newer_than() should succeed")
+        };
+        let expected_entry = FetchedEntry::Entry(Some(EntryWithMeta {
+            entry: entry.clone(),
+            crud_status: CrudStatus::Live,
+            maybe_crud_link: None,
+        }));
+        assert_eq!(fetch(Some(entry.address())), FetchedEntry::NotModified);
+        assert_eq!(fetch(None), expected_entry);
+        assert_eq!(fetch(Some(Address::from("older version"))), expected_entry);
+    }
+
+    #[test]
+    fn shared_storage_contexts_see_each_others_data() {
+        let mut dna = create_test_dna_with_wat("test_zome", "test_cap", None);
+        dna.uuid = "shared_storage_contexts_see_each_others_data".to_string();
+        let contexts = shared_storage_contexts(dna, &["alice", "bob", "carol"]);
+        assert_eq!(contexts.len(), 3);
+
+        let entry = test_entry();
+        contexts[0]
+            .dht_storage
+            .write()
+            .unwrap()
+            .add(&entry)
+            .unwrap();
+
+        for context in contexts.iter() {
+            assert!(context
+                .state()
+                .unwrap()
+                .dht()
+                .content_storage()
+                .read()
+                .unwrap()
+                .contains(&entry.address())
+                .unwrap());
+        }
+        // source chains are not shared
+        assert_ne!(
+            contexts[1].state().unwrap().agent().top_chain_header(),
+            contexts[2].state().unwrap().agent().top_chain_header()
+        );
+    }
+
     #[test]
     fn get_non_existant_entry() {
         let netname = Some("get_non_existant_entry");
@@ -174,15 +256,173 @@ pub mod tests {
         assert_eq!(entry_with_meta.crud_status, CrudStatus::Live);
     }
 
+    #[test]
+    fn get_entry_before_network_is_initialized() {
+        let netname = Some("get_entry_before_network_is_initialized");
+        // without DNA, the instance never gets to initialize its network
+        let mut instance = Instance::new(test_context("alice", netname));
+        let context = instance.initialize_without_dna(test_context("alice", netname));
+
+        let result = context.block_on(get_entry(
+            context.clone(),
+            test_entry().address(),
+            Default::default(),
+        ));
+        assert_eq!(result, Err(HolochainError::NetworkNotInitialized));
+    }
+
+    #[test]
+    fn get_all_links_returns_links_of_all_tags() {
+        let netname = Some("get_all_links_returns_links_of_all_tags");
+        let dna = create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (_, context) = test_instance_and_context_by_name(dna, "alice1", netname).unwrap();
+
+        let mut addresses = Vec::new();
+        for i in 0..3 {
+            let entry = Entry::App(test_app_entry_type(), format!("entry{} value", i).into());
+            let address = context
+                .block_on(commit_entry(entry, None, &context))
+                .expect("Could not commit entry for testing");
+            addresses.push(address);
+        }
+        let friend = Link::new(&addresses[0], &addresses[1], "friend");
+        let follows = Link::new(&addresses[0], &addresses[2], "follows");
+        assert!(context.block_on(add_link(&friend, &context)).is_ok());
+        assert!(context.block_on(add_link(&follows, &context)).is_ok());
+
+        let links = get_all_links(&context, addresses[0].clone(), &Default::default())
+            .expect("Could not get links");
+        assert_eq!(
+            links,
+            vec![
+                ("follows".to_string(), addresses[2].clone()),
+                ("friend".to_string(), addresses[1].clone()),
+            ]
+        );
+        assert_eq!(
+            get_all_links(&context, addresses[1].clone(), &Default::default()),
+            Ok(Vec::new())
+        );
+    }
+
+    #[test]
+    fn get_links_to_returns_bases_of_links_to_target() {
+        let netname = Some("get_links_to_returns_bases_of_links_to_target");
+        let dna = create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (_, context) = test_instance_and_context_by_name(dna, "alice1", netname).unwrap();
+
+        let mut addresses = Vec::new();
+        for i in 0..4 {
+            let entry = Entry::App(test_app_entry_type(), format!("entry{} value", i).into());
+            let address = context
+                .block_on(commit_entry(entry, None, &context))
+                .expect("Could not commit entry for testing");
+            addresses.push(address);
+        }
+        let target = addresses[0].clone();
+        let link1 = Link::new(&addresses[1], &target, "friend");
+        let link2 = Link::new(&addresses[2], &target, "friend");
+        let other_tag = Link::new(&addresses[3], &target, "follows");
+        assert!(context.block_on(add_link(&link1, &context)).is_ok());
+        assert!(context.block_on(add_link(&link2, &context)).is_ok());
+        assert!(context.block_on(add_link(&other_tag, &context)).is_ok());
+
+        let mut bases = get_links_to(
+            &context,
+            target.clone(),
+            "friend".to_string(),
+            &Default::default(),
+        )
+        .expect("Could not get links");
+        bases.sort();
+        let mut expected = vec![addresses[1].clone(), addresses[2].clone()];
+        expected.sort();
+        assert_eq!(bases, expected);
+        assert_eq!(
+            get_links_to(
+                &context,
+                addresses[1].clone(),
+                "friend".to_string(),
+                &Default::default()
+            ),
+            Ok(Vec::new())
+        );
+    }
+
+    #[test]
+    fn published_entries_wait_for_publish_round() {
+        let netname = Some("published_entries_wait_for_publish_round");
+        let wat = &test_wat_always_valid();
+        let mut dna = create_test_dna_with_wat("test_zome", "test_cap", Some(wat));
+        dna.uuid = netname.unwrap().to_string();
+        let mut context = (*test_context("alice1", netname)).clone();
+        context.p2p_config.publish_interval_ms = Some(3_600_000);
+        let (_, context1) = test_instance_with_context(dna.clone(), Arc::new(context)).unwrap();
+        let (_, context2) =
+            test_instance_and_context_by_name(dna.clone(), "bob1", netname).unwrap();
+        let spy = NetworkSpy::new(netname, dna.address());
+        let held_by_bob = || {
+            context2
+                .state()
+                .unwrap()
+                .dht()
+                .content_storage()
+                .read()
+                .unwrap()
+                .contains(&test_entry().address())
+                .unwrap()
+        };
+
+        context1
+            .block_on(author_entry(&test_entry(), None, &context1))
+            .unwrap();
+        // a direct message sent after the publish gets past it while the publish is held back
+        let network_state = context1.state().unwrap().network();
+        network_state
+            .network
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .send(
+                JsonProtocol::SendMessage(MessageData {
+                    dna_address: dna.address(),
+                    request_id: "after publish".to_string(),
+                    to_agent_id: "network-spy".to_string(),
+                    from_agent_id: network_state.agent_id.clone().unwrap(),
+                    content: serde_json::Value::from("after publish"),
+                })
+                .into(),
+            )
+            .unwrap();
+        assert!(spy.wait_for_direct_message("after publish"));
+        assert!(spy.stored_entries(&test_entry().address()).is_empty());
+        assert!(!held_by_bob());
+
+        context1
+            .state()
+            .unwrap()
+            .network()
+            .trigger_publish()
+            .unwrap();
+        let mut tries = 0;
+        while !held_by_bob() && tries < 50 {
+            tries += 1;
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        assert!(held_by_bob());
+        let published = spy.wait_for_stored_entries(&test_entry().address(), 1);
+        assert_eq!(published.len(), 1);
+    }
+
     #[test]
     fn get_validation_package_roundtrip() {
-        let netname = Some("get_validation_package_roundtrip");
         let wat = &test_wat_always_valid();
 
         let mut dna = create_test_dna_with_wat("test_zome", "test_cap", Some(wat));
-        dna.uuid = netname.unwrap().to_string();
-        let (_, context1) =
-            test_instance_and_context_by_name(dna.clone(), "alice1", netname).unwrap();
+        dna.uuid = "get_validation_package_roundtrip".to_string();
+        let contexts = shared_storage_contexts(dna, &["alice1", "bob1"]);
+        let (context1, context2) = (contexts[0].clone(), contexts[1].clone());
 
         let entry = test_entry();
         context1
@@ -194,8 +434,6 @@ pub mod tests {
             .get_most_recent_header_for_entry(&entry)
             .expect("There must be a header in the author's source chain after commit");
 
-        let (_, context2) =
-            test_instance_and_context_by_name(dna.clone(), "bob1", netname).unwrap();
         let result = context2.block_on(get_validation_package(header.clone(), &context2));
 
         assert!(result.is_ok());
@@ -209,15 +447,12 @@ pub mod tests {
     #[test]
     #[ignore]
     fn get_links_roundtrip() {
-        let netname = Some("get_links_roundtrip");
         let wat = &test_wat_always_valid();
 
         let mut dna = create_test_dna_with_wat("test_zome", "test_cap", Some(wat));
-        dna.uuid = netname.unwrap().to_string();
-        let (_, context1) =
-            test_instance_and_context_by_name(dna.clone(), "alex2", netname).unwrap();
-        let (_, context2) =
-            test_instance_and_context_by_name(dna.clone(), "billy2", netname).unwrap();
+        dna.uuid = "get_links_roundtrip".to_string();
+        let contexts = shared_storage_contexts(dna, &["alex2", "billy2"]);
+        let (context1, context2) = (contexts[0].clone(), contexts[1].clone());
 
         let mut entry_addresses: Vec<Address> = Vec::new();
         for i in 0..3 {
@@ -258,6 +493,7 @@ pub mod tests {
             context2.clone(),
             entry_addresses[0].clone(),
             String::from("test-tag"),
+            None,
             Default::default(),
         ));
 
@@ -270,4 +506,82 @@ pub mod tests {
                 && (links[1] == entry_addresses[1] || links[1] == entry_addresses[2])
         );
     }
+
+    #[test]
+    fn get_links_filters_targets_by_type() {
+        let netname = Some("get_links_filters_targets_by_type");
+        let wat = &test_wat_always_valid();
+
+        let mut dna = create_test_dna_with_wat("test_zome", "test_cap", Some(wat));
+        dna.uuid = netname.unwrap().to_string();
+        let (_, context1) =
+            test_instance_and_context_by_name(dna.clone(), "alex3", netname).unwrap();
+        let (_, context2) =
+            test_instance_and_context_by_name(dna.clone(), "billy3", netname).unwrap();
+
+        let base = Entry::App(test_app_entry_type(), "base".into());
+        let matching = Entry::App(test_app_entry_type(), "matching target".into());
+        for entry in [&base, &matching].iter() {
+            context1
+                .block_on(commit_entry((*entry).clone(), None, &context1))
+                .expect("Could not commit entry for testing");
+            context1
+                .block_on(publish(entry.address(), &context1))
+                .expect("Could not publish entry for testing");
+        }
+        // only held by the first node, so the network can't find it for the second
+        let other_type = Entry::App(test_app_entry_type_b(), "other target".into());
+        context1
+            .dht_storage
+            .write()
+            .unwrap()
+            .add(&other_type)
+            .unwrap();
+        // nobody holds it, so its type is unknown
+        let unknown = Entry::App(test_app_entry_type(), "unknown target".into());
+
+        // Links held by both nodes, so it doesn't matter which one answers the request
+        for context in [&context1, &context2].iter() {
+            for target in [&matching, &other_type, &unknown].iter() {
+                let eavi = EntityAttributeValueIndex::new(
+                    &base.address(),
+                    &Attribute::LinkTag("test-tag".to_string()),
+                    &target.address(),
+                )
+                .unwrap();
+                context
+                    .eav_storage
+                    .write()
+                    .unwrap()
+                    .add_eavi(&eavi)
+                    .unwrap();
+            }
+        }
+
+        let get_targets = |target_type: Option<String>| {
+            let mut links = context2
+                .block_on(get_links(
+                    context2.clone(),
+                    base.address(),
+                    String::from("test-tag"),
+                    target_type,
+                    Timeout::new(1000),
+                ))
+                .expect("get_links() should succeed");
+            links.sort();
+            links
+        };
+
+        let mut all_targets = vec![matching.address(), other_type.address(), unknown.address()];
+        all_targets.sort();
+        assert_eq!(get_targets(None), all_targets);
+        assert_eq!(
+            get_targets(Some(test_app_entry_type().to_string())),
+            vec![matching.address()]
+        );
+        assert_eq!(
+            get_targets(Some(test_app_entry_type_b().to_string())),
+            Vec::<Address>::new()
+        );
+    }
 }
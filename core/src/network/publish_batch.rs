@@ -0,0 +1,226 @@
+//! Optional batching of publishes.
+//! If the context has a PublishBatcher set, authored entries don't get published right
+//! after they got committed. Their addresses are collected instead and published with
+//! one Action::PublishBatch once the batch window has passed. An entry that gets committed
+//! several times within the window only gets published once. Every distinct entry still
+//! takes its own network messages, since the network protocol has no message that carries
+//! several entries.
+//! What gets published does not change, only when.
+//! Pending batches are only kept in memory. Shutting down the network
+//! (see shutdown_network()) publishes them first, but the entries of a batch that is
+//! pending when the process dies don't get published until they get committed again.
+
+use crate::{
+    action::{Action, ActionWrapper},
+    context::Context,
+    instance::dispatch_action_and_wait,
+};
+use holochain_core_types::cas::content::Address;
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+/// Collects addresses to publish. Clones share the same batch.
+#[derive(Clone, Debug)]
+pub struct PublishBatcher {
+    window: Duration,
+    pending: Arc<Mutex<Vec<Address>>>,
+}
+
+impl PublishBatcher {
+    pub fn new(window: Duration) -> Self {
+        PublishBatcher {
+            window,
+            pending: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Addresses that wait for the current batch to get published.
+    pub fn pending(&self) -> Vec<Address> {
+        self.pending.lock().unwrap().clone()
+    }
+
+    /// Adds the given address to the current batch.
+    /// The first address of a batch starts a timer that flushes the batch after the window.
+    pub fn add(&self, address: Address, context: &Arc<Context>) {
+        let mut pending = self.pending.lock().unwrap();
+        let starts_batch = pending.is_empty();
+        if !pending.contains(&address) {
+            pending.push(address);
+        }
+        if starts_batch {
+            let batcher = self.clone();
+            let context = context.clone();
+            thread::spawn(move || {
+                thread::sleep(batcher.window);
+                batcher.flush(&context);
+            });
+        }
+    }
+
+    /// Publishes all addresses of the current batch with one Action::PublishBatch
+    /// and blocks until it got processed. Gets called by shutdown_network() so that
+    /// no commit stays unpublished.
+    /// Returns the number of published addresses.
+    pub fn flush(&self, context: &Arc<Context>) -> usize {
+        let addresses: Vec<Address> = self.pending.lock().unwrap().drain(..).collect();
+        if addresses.is_empty() {
+            return 0;
+        }
+        let count = addresses.len();
+        context.log(format!(
+            "debug/network/publish_batch: publishing batch of {} entries",
+            count
+        ));
+        dispatch_action_and_wait(
+            context.clone(),
+            ActionWrapper::new(Action::PublishBatch(addresses)),
+        );
+        count
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        network::{
+            actions::{shutdown_network::shutdown_network, ActionResponse},
+            test_utils::NetworkSpy,
+        },
+        nucleus::actions::tests::*,
+        workflows::author_entry::author_entry,
+    };
+    use holochain_core_types::{
+        cas::content::AddressableContent,
+        entry::{entry_type::test_app_entry_type, Entry},
+        json::RawString,
+    };
+    use std::time::Instant;
+
+    fn context_with_batcher(context: &Arc<Context>, window: Duration) -> Arc<Context> {
+        let mut context = (**context).clone();
+        context.publish_batcher = Some(PublishBatcher::new(window));
+        Arc::new(context)
+    }
+
+    fn author_entries(context: &Arc<Context>, count: usize) -> Vec<Address> {
+        (0..count)
+            .map(|i| {
+                let entry = Entry::App(
+                    test_app_entry_type(),
+                    RawString::from(format!("batched entry {}", i)).into(),
+                );
+                context
+                    .block_on(author_entry(&entry, None, context))
+                    .expect("Could not author entry")
+            })
+            .collect()
+    }
+
+    /// All network actions of the given context that published something.
+    fn publish_actions(context: &Arc<Context>) -> Vec<(Action, ActionResponse)> {
+        context
+            .state()
+            .unwrap()
+            .network()
+            .actions()
+            .iter()
+            .filter(|(action_wrapper, _)| match action_wrapper.action() {
+                Action::Publish(_) | Action::PublishBatch(_) => true,
+                _ => false,
+            })
+            .map(|(action_wrapper, response)| (action_wrapper.action().clone(), response.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn commits_within_window_get_published_in_one_batch() {
+        let netname = Some("commits_within_window_get_published_in_one_batch");
+        let dna = test_dna();
+        let (_instance, context) = instance_by_name("alice", dna.clone(), netname);
+        let context = context_with_batcher(&context, Duration::from_secs(2));
+        let spy = NetworkSpy::new(netname, dna.address());
+        let published_before = publish_actions(&context).len();
+
+        let addresses = author_entries(&context, 3);
+        // committing the first entry again within the window does not add it again
+        assert_eq!(author_entries(&context, 1), vec![addresses[0].clone()]);
+        assert_eq!(
+            context.publish_batcher.as_ref().unwrap().pending(),
+            addresses
+        );
+
+        let start = Instant::now();
+        while publish_actions(&context).len() == published_before
+            && start.elapsed() < Duration::from_secs(10)
+        {
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        let published = publish_actions(&context);
+        assert_eq!(published.len(), published_before + 1);
+        assert!(published.contains(&(
+            Action::PublishBatch(addresses.clone()),
+            ActionResponse::PublishBatch(Ok(addresses))
+        )));
+        assert!(context
+            .publish_batcher
+            .as_ref()
+            .unwrap()
+            .pending()
+            .is_empty());
+        for address in addresses.iter() {
+            assert_eq!(spy.wait_for_stored_entries(address, 1).len(), 1);
+        }
+    }
+
+    #[test]
+    fn flush_publishes_pending_commits() {
+        let netname = Some("flush_publishes_pending_commits");
+        let (_instance, context) = instance_by_name("alice", test_dna(), netname);
+        let context = context_with_batcher(&context, Duration::from_secs(3600));
+        let published_before = publish_actions(&context).len();
+
+        let addresses = author_entries(&context, 2);
+        assert_eq!(publish_actions(&context).len(), published_before);
+
+        let batcher = context.publish_batcher.clone().unwrap();
+        assert_eq!(batcher.flush(&context), 2);
+        assert!(publish_actions(&context).contains(&(
+            Action::PublishBatch(addresses.clone()),
+            ActionResponse::PublishBatch(Ok(addresses))
+        )));
+        assert_eq!(batcher.flush(&context), 0);
+    }
+
+    #[test]
+    fn shutting_down_the_network_publishes_pending_commits() {
+        let netname = Some("shutting_down_the_network_publishes_pending_commits");
+        let dna = test_dna();
+        let (_instance, context) = instance_by_name("alice", dna.clone(), netname);
+        let context = context_with_batcher(&context, Duration::from_secs(3600));
+        let spy = NetworkSpy::new(netname, dna.address());
+
+        let addresses = author_entries(&context, 2);
+        context
+            .block_on(shutdown_network(&context))
+            .expect("Could not shut down network");
+
+        assert!(context
+            .publish_batcher
+            .as_ref()
+            .unwrap()
+            .pending()
+            .is_empty());
+        for address in addresses.iter() {
+            assert_eq!(spy.wait_for_stored_entries(address, 1).len(), 1);
+        }
+    }
+}
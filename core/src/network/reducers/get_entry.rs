@@ -20,6 +20,7 @@ fn reduce_fetch_entry_inner(
             request_id: key.id.clone(),
             dna_address: network_state.dna_address.clone().unwrap(),
             entry_address: key.address.clone(),
+            if_newer_than: key.if_newer_than.clone(),
         }),
     )
 }
@@ -34,7 +35,10 @@ pub fn reduce_get_entry(
 
     let result = match reduce_fetch_entry_inner(network_state, &key) {
         Ok(()) => None,
-        Err(err) => Some(Err(err)),
+        Err(err) => {
+            network_state.set_last_error(err.clone());
+            Some(Err(err))
+        }
     };
 
     network_state
@@ -87,6 +91,7 @@ mod tests {
         let entry = test_entry();
         let key = GetEntryKey {
             address: entry.address(),
+            if_newer_than: None,
             id: snowflake::ProcessUniqueId::new().to_string(),
         };
         let action_wrapper = ActionWrapper::new(Action::FetchEntry(key.clone()));
@@ -99,9 +104,7 @@ mod tests {
             .map(|result| result.clone());
         assert_eq!(
             maybe_get_entry_result,
-            Some(Some(Err(HolochainError::ErrorGeneric(
-                "Network not initialized".to_string()
-            ))))
+            Some(Some(Err(HolochainError::NetworkNotInitialized)))
         );
     }
 
@@ -125,6 +128,7 @@ mod tests {
         let entry = test_entry();
         let key = GetEntryKey {
             address: entry.address(),
+            if_newer_than: None,
             id: snowflake::ProcessUniqueId::new().to_string(),
         };
         let action_wrapper = ActionWrapper::new(Action::FetchEntry(key.clone()));
@@ -165,6 +169,7 @@ mod tests {
         let entry = test_entry();
         let key = GetEntryKey {
             address: entry.address(),
+            if_newer_than: None,
             id: "req_alice_1".to_string(),
         };
         let action_wrapper = ActionWrapper::new(Action::FetchEntry(key.clone()));
@@ -207,6 +212,7 @@ mod tests {
         };
         let new_key = GetEntryKey {
             address: entry.address(),
+            if_newer_than: None,
             id: "req_alice_2".to_string(),
         };
         let dht_data = DhtData {
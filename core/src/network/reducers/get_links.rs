@@ -21,6 +21,7 @@ fn reduce_get_links_inner(
             dna_address: network_state.dna_address.clone().unwrap(),
             entry_address: HashString::from(key.base_address.clone()),
             attribute: format!("link__{}", key.tag),
+            target_type: key.target_type.clone(),
         }),
     )
 }
@@ -35,7 +36,10 @@ pub fn reduce_get_links(
 
     let result = match reduce_get_links_inner(network_state, &key) {
         Ok(()) => None,
-        Err(err) => Some(Err(err)),
+        Err(err) => {
+            network_state.set_last_error(err.clone());
+            Some(Err(err))
+        }
     };
 
     network_state.get_links_results.insert(key.clone(), result);
@@ -81,6 +85,7 @@ mod tests {
         let key = GetLinksKey {
             base_address: entry.address(),
             tag: tag.clone(),
+            target_type: None,
             id: snowflake::ProcessUniqueId::new().to_string(),
         };
         let action_wrapper = ActionWrapper::new(Action::GetLinks(key.clone()));
@@ -93,9 +98,7 @@ mod tests {
             .map(|result| result.clone());
         assert_eq!(
             maybe_get_links_result,
-            Some(Some(Err(HolochainError::ErrorGeneric(
-                "Network not initialized".to_string()
-            ))))
+            Some(Some(Err(HolochainError::NetworkNotInitialized)))
         );
     }
 
@@ -123,6 +126,7 @@ mod tests {
         let key = GetLinksKey {
             base_address: entry.address(),
             tag: tag.clone(),
+            target_type: None,
             id: snowflake::ProcessUniqueId::new().to_string(),
         };
         let action_wrapper = ActionWrapper::new(Action::GetLinks(key.clone()));
@@ -162,6 +166,7 @@ mod tests {
         let key = GetLinksKey {
             base_address: entry.address(),
             tag: tag.clone(),
+            target_type: None,
             id: snowflake::ProcessUniqueId::new().to_string(),
         };
         let action_wrapper = ActionWrapper::new(Action::GetLinks(key.clone()));
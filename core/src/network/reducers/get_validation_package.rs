@@ -1,24 +1,32 @@
 use crate::{
     action::ActionWrapper,
     context::Context,
-    network::{direct_message::DirectMessage, reducers::send_message, state::NetworkState},
+    network::{direct_message::DirectMessageEnvelope, reducers::send_message, state::NetworkState},
+};
+use holochain_core_types::{
+    cas::content::Address, chain_header::ChainHeader, error::HolochainError,
 };
-use holochain_core_types::{chain_header::ChainHeader, error::HolochainError};
 use std::sync::Arc;
 
-fn inner(network_state: &mut NetworkState, header: &ChainHeader) -> Result<(), HolochainError> {
-    network_state.initialized()?;
-
-    let source_address = &header
+/// The agent that authored the entry of the given header, which we ask for the
+/// validation package.
+pub fn validation_package_source(header: &ChainHeader) -> Result<Address, HolochainError> {
+    header
         .provenances()
         .first()
-        .ok_or(HolochainError::ErrorGeneric(
-            "No source found in ChainHeader".to_string(),
-        ))?
-        .0;
-    let direct_message = DirectMessage::RequestValidationPackage(header.entry_address().clone());
+        .map(|provenance| provenance.0.clone())
+        .ok_or_else(|| HolochainError::ErrorGeneric("No source found in ChainHeader".to_string()))
+}
+
+fn inner(
+    network_state: &mut NetworkState,
+    header: &ChainHeader,
+    envelope: &DirectMessageEnvelope,
+) -> Result<(), HolochainError> {
+    network_state.initialized()?;
 
-    send_message(network_state, source_address, direct_message)
+    let source_address = validation_package_source(header)?;
+    send_message(network_state, &source_address, envelope.clone())
 }
 
 pub fn reduce_get_validation_package(
@@ -27,10 +35,10 @@ pub fn reduce_get_validation_package(
     action_wrapper: &ActionWrapper,
 ) {
     let action = action_wrapper.action();
-    let header = unwrap_to!(action => crate::action::Action::GetValidationPackage);
+    let (header, envelope) = unwrap_to!(action => crate::action::Action::GetValidationPackage);
     let entry_address = header.entry_address().clone();
 
-    let result = match inner(network_state, header) {
+    let result = match inner(network_state, header, envelope) {
         Ok(()) => None,
         Err(err) => Some(Err(err)),
     };
@@ -42,11 +42,19 @@ pub fn reduce_handle_get_links_result(
     ));
 
     let result = reduce_handle_get_links_result_inner(network_state, dht_meta_data);
-    let key = GetLinksKey {
-        base_address: Address::from(dht_meta_data.entry_address.clone()),
-        tag: tag.clone(),
-        id: dht_meta_data.request_id.clone(),
-    };
+    // The result does not tell which target type the request was filtered by,
+    // but the request id is unique so the pending request can be found by it.
+    let key = network_state
+        .get_links_results
+        .keys()
+        .find(|key| key.id == dht_meta_data.request_id)
+        .cloned()
+        .unwrap_or_else(|| GetLinksKey {
+            base_address: Address::from(dht_meta_data.entry_address.clone()),
+            tag: tag.clone(),
+            target_type: None,
+            id: dht_meta_data.request_id.clone(),
+        });
 
     network_state.get_links_results.insert(key, Some(result));
 }
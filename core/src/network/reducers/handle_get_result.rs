@@ -1,18 +1,21 @@
 use crate::{
     action::{ActionWrapper, GetEntryKey},
     context::Context,
-    network::state::NetworkState,
+    network::{actions::get_entry::FetchedEntry, state::NetworkState},
 };
-use holochain_core_types::{cas::content::Address, entry::EntryWithMeta, error::HolochainError};
+use holochain_core_types::{cas::content::Address, error::HolochainError};
 use holochain_net::connection::json_protocol::FetchEntryResultData;
 use std::sync::Arc;
 
 fn reduce_handle_get_result_inner(
     network_state: &mut NetworkState,
     dht_data: &FetchEntryResultData,
-) -> Result<Option<EntryWithMeta>, HolochainError> {
+) -> Result<FetchedEntry, HolochainError> {
     network_state.initialized()?;
 
+    if dht_data.not_modified {
+        return Ok(FetchedEntry::NotModified);
+    }
     let res = serde_json::from_str(&serde_json::to_string(&dht_data.entry_content).unwrap());
     if let Err(_) = res {
         return Err(HolochainError::ErrorGeneric(
@@ -20,7 +23,7 @@ fn reduce_handle_get_result_inner(
                 .to_string(),
         ));
     }
-    Ok(res.unwrap())
+    Ok(FetchedEntry::Entry(res.unwrap()))
 }
 
 pub fn reduce_handle_get_result(
@@ -33,10 +36,18 @@ pub fn reduce_handle_get_result(
 
     let result = reduce_handle_get_result_inner(network_state, dht_data);
 
-    let key = GetEntryKey {
-        address: Address::from(dht_data.entry_address.clone()),
-        id: dht_data.request_id.clone(),
-    };
+    // The result does not tell which version the request named as known,
+    // but the request id is unique so the pending request can be found by it.
+    let key = network_state
+        .get_entry_with_meta_results
+        .keys()
+        .find(|key| key.id == dht_data.request_id)
+        .cloned()
+        .unwrap_or_else(|| GetEntryKey {
+            address: Address::from(dht_data.entry_address.clone()),
+            if_newer_than: None,
+            id: dht_data.request_id.clone(),
+        });
 
     network_state
         .get_entry_with_meta_results
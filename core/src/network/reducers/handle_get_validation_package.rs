@@ -7,10 +7,9 @@ pub fn reduce_handle_get_validation_package(
     action_wrapper: &ActionWrapper,
 ) {
     let action = action_wrapper.action();
-    let (address, maybe_validation_package) =
-        unwrap_to!(action => crate::action::Action::HandleGetValidationPackage);
+    let (address, result) = unwrap_to!(action => crate::action::Action::HandleGetValidationPackage);
 
     network_state
         .get_validation_package_results
-        .insert(address.clone(), Some(Ok(maybe_validation_package.clone())));
+        .insert(address.clone(), Some(result.clone()));
 }
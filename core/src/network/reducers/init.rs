@@ -1,8 +1,12 @@
 use crate::{
-    action::{Action, ActionWrapper},
+    action::{Action, ActionWrapper, NetworkSettings},
     context::Context,
-    network::{handler::create_handler, state::NetworkState},
+    network::{
+        actions::ActionResponse, handler::create_handler,
+        reducers::publish::resend_pending_publishes, state::NetworkState,
+    },
 };
+use holochain_core_types::error::HolochainError;
 use holochain_net::{
     connection::{
         json_protocol::{JsonProtocol, TrackDnaData},
@@ -12,15 +16,13 @@ use holochain_net::{
 };
 use std::sync::{Arc, Mutex};
 
-pub fn reduce_init(
-    context: Arc<Context>,
+fn reduce_init_inner(
+    context: &Arc<Context>,
     state: &mut NetworkState,
-    action_wrapper: &ActionWrapper,
-) {
-    let action = action_wrapper.action();
-    let network_settings = unwrap_to!(action => Action::InitNetwork);
-    let mut network =
-        P2pNetwork::new(create_handler(&context), &network_settings.p2p_config).unwrap();
+    network_settings: &NetworkSettings,
+) -> Result<(), HolochainError> {
+    let mut network = P2pNetwork::new(create_handler(context), &network_settings.p2p_config)
+        .map_err(|error| HolochainError::IoError(format!("Could not create network: {}", error)))?;
 
     // Configure network logger
     // Enable this for debugging network
@@ -38,10 +40,74 @@ pub fn reduce_init(
         agent_id: network_settings.agent_id.clone(),
     });
 
-    let _ = network.send(json.into()).and_then(|_| {
-        state.network = Some(Arc::new(Mutex::new(network)));
-        state.dna_address = Some(network_settings.dna_address.clone());
-        state.agent_id = Some(network_settings.agent_id.clone());
-        Ok(())
-    });
+    network
+        .send(json.into())
+        .map_err(|error| HolochainError::IoError(format!("Could not track DNA: {}", error)))?;
+
+    state.network = Some(Arc::new(Mutex::new(network)));
+    state.dna_address = Some(network_settings.dna_address.clone());
+    state.agent_id = Some(network_settings.agent_id.clone());
+    Ok(())
+}
+
+pub fn reduce_init(
+    context: Arc<Context>,
+    state: &mut NetworkState,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let network_settings = unwrap_to!(action => Action::InitNetwork);
+
+    let result = reduce_init_inner(&context, state, network_settings);
+    match result {
+        Ok(()) => resend_pending_publishes(&context, state),
+        Err(ref error) => {
+            context.log(format!("err/net/init: {}", error));
+            state.set_last_error(error.clone());
+        }
+    }
+    state
+        .actions
+        .insert(action_wrapper.clone(), ActionResponse::InitNetwork(result));
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        action::{Action, ActionWrapper, NetworkSettings},
+        instance::tests::test_context,
+        state::test_store,
+    };
+    use holochain_core_types::error::HolochainError;
+    use holochain_net::p2p_config::{P2pBackendKind, P2pConfig};
+
+    #[test]
+    pub fn reduce_init_failure_sets_last_error() {
+        let context = test_context("alice", None);
+        let store = test_store(context.clone());
+        assert!(store.network().last_error().is_none());
+
+        let action_wrapper = ActionWrapper::new(Action::InitNetwork(NetworkSettings {
+            p2p_config: P2pConfig::new(
+                P2pBackendKind::IPC,
+                r#"{"socketType": "carrier-pigeon"}"#,
+                Some(P2pConfig::default_end_user_config()),
+            ),
+            dna_address: "reduce_init_failure_sets_last_error".into(),
+            agent_id: String::from("alice"),
+        }));
+        let store = store.reduce(context.clone(), action_wrapper);
+
+        assert!(store.network().initialized().is_err());
+        match store.network().last_error() {
+            Some((_, HolochainError::IoError(message))) => assert!(
+                message.contains("Could not create network")
+                    && message.contains("unexpected socketType"),
+                "unexpected error message: {}",
+                message
+            ),
+            other => panic!("expected an IoError, got {:?}", other),
+        }
+    }
 }
@@ -11,12 +11,13 @@ pub mod resolve_direct_connection;
 pub mod respond_get;
 pub mod respond_get_links;
 pub mod send_direct_message;
+pub mod shutdown;
 
 use crate::{
     action::{Action, ActionWrapper, NetworkReduceFn},
     context::Context,
     network::{
-        direct_message::DirectMessage,
+        direct_message::DirectMessageEnvelope,
         reducers::{
             get_entry::{reduce_get_entry, reduce_get_entry_timeout},
             get_links::{reduce_get_links, reduce_get_links_timeout},
@@ -26,11 +27,12 @@ use crate::{
             handle_get_result::reduce_handle_get_result,
             handle_get_validation_package::reduce_handle_get_validation_package,
             init::reduce_init,
-            publish::reduce_publish,
+            publish::{reduce_publish, reduce_publish_batch},
             resolve_direct_connection::reduce_resolve_direct_connection,
             respond_get::reduce_respond_fetch_data,
             respond_get_links::reduce_respond_get_links,
             send_direct_message::{reduce_send_direct_message, reduce_send_direct_message_timeout},
+            shutdown::reduce_shutdown,
         },
         state::NetworkState,
     },
@@ -57,11 +59,13 @@ fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<NetworkReduceFn> {
         Action::HandleGetValidationPackage(_) => Some(reduce_handle_get_validation_package),
         Action::InitNetwork(_) => Some(reduce_init),
         Action::Publish(_) => Some(reduce_publish),
+        Action::PublishBatch(_) => Some(reduce_publish_batch),
         Action::ResolveDirectConnection(_) => Some(reduce_resolve_direct_connection),
         Action::RespondFetch(_) => Some(reduce_respond_fetch_data),
         Action::RespondGetLinks(_) => Some(reduce_respond_get_links),
         Action::SendDirectMessage(_) => Some(reduce_send_direct_message),
         Action::SendDirectMessageTimeout(_) => Some(reduce_send_direct_message_timeout),
+        Action::ShutdownNetwork => Some(reduce_shutdown),
         _ => None,
     }
 }
@@ -98,28 +102,27 @@ pub fn send(
                 .send(json_message.into())
                 .map_err(|error| HolochainError::IoError(error.to_string()))
         })
-        .ok_or(HolochainError::ErrorGeneric(
-            "Network not intialized".to_string(),
-        ))?
+        .ok_or(HolochainError::NetworkNotInitialized)?
 }
 
-/// Sends the given DirectMessage to the node given by to_agent_id.
+/// Sends the given signed DirectMessage to the node given by to_agent_id.
 /// This creates a transient connection as every node-to-node communication follows a
 /// request-response pattern. This function therefore logs the open connection
 /// (expecting a response) in network_state.direct_message_connections.
 pub fn send_message(
     network_state: &mut NetworkState,
     to_agent_id: &Address,
-    message: DirectMessage,
+    envelope: DirectMessageEnvelope,
 ) -> Result<(), HolochainError> {
     let id = ProcessUniqueId::new().to_string();
+    let message = envelope.message.clone();
 
     let data = MessageData {
         request_id: id.clone(),
         dna_address: network_state.dna_address.clone().unwrap(),
         to_agent_id: to_agent_id.to_string(),
         from_agent_id: network_state.agent_id.clone().unwrap(),
-        content: serde_json::from_str(&serde_json::to_string(&message).unwrap()).unwrap(),
+        content: serde_json::to_value(envelope).unwrap(),
     };
 
     let _ = send(network_state, JsonProtocol::SendMessage(data))?;
@@ -4,6 +4,7 @@ use crate::{
     network::{
         actions::ActionResponse,
         entry_with_header::{fetch_entry_with_header, EntryWithHeader},
+        fragment::{fragment_content, EntryFragment},
         reducers::send,
         state::NetworkState,
     },
@@ -19,23 +20,69 @@ use holochain_core_types::{
 use holochain_net::connection::json_protocol::{DhtMetaData, EntryData, JsonProtocol};
 use std::sync::Arc;
 
-/// Send to network a PublishDhtData message
+/// The PublishEntry messages that publish the given serialized entry.
+/// If the content is bigger than `max_message_size` it gets split into several messages
+/// holding one EntryFragment each, none of which is bigger than `max_message_size`
+/// once serialized.
+fn publish_entry_messages(
+    dna_address: &Address,
+    provider_agent_id: &str,
+    entry_address: &Address,
+    content: &str,
+    max_message_size: Option<usize>,
+) -> Vec<JsonProtocol> {
+    let message = |entry_content| {
+        JsonProtocol::PublishEntry(EntryData {
+            dna_address: dna_address.clone(),
+            provider_agent_id: provider_agent_id.to_string(),
+            entry_address: entry_address.clone(),
+            entry_content,
+        })
+    };
+    match max_message_size {
+        Some(max_size) if content.len() > max_size => {
+            let id = snowflake::ProcessUniqueId::new().to_string();
+            // size of a message around empty fragment data, with the widest possible
+            // fragment position
+            let envelope = message(
+                serde_json::to_value(EntryFragment {
+                    id: id.clone(),
+                    index: content.len(),
+                    count: content.len(),
+                    data: String::new(),
+                })
+                .unwrap(),
+            );
+            let envelope_size = serde_json::to_string(&envelope).unwrap().len();
+            fragment_content(&id, content, max_size.saturating_sub(envelope_size))
+                .iter()
+                .map(|fragment| message(serde_json::to_value(fragment).unwrap()))
+                .collect()
+        }
+        _ => vec![message(serde_json::from_str(content).unwrap())],
+    }
+}
+
+/// Send to network a PublishDhtData message.
+/// If the serialized entry is bigger than the context's network_max_message_size
+/// it gets sent as several messages holding one EntryFragment each.
 fn publish_entry(
+    context: &Arc<Context>,
     network_state: &mut NetworkState,
     entry_with_header: &EntryWithHeader,
 ) -> Result<(), HolochainError> {
-    send(
-        network_state,
-        JsonProtocol::PublishEntry(EntryData {
-            dna_address: network_state.dna_address.clone().unwrap(),
-            provider_agent_id: network_state.agent_id.clone().unwrap(),
-            entry_address: entry_with_header.entry.address().clone(),
-            entry_content: serde_json::from_str(
-                &serde_json::to_string(&entry_with_header).unwrap(),
-            )
-            .unwrap(),
-        }),
-    )
+    let content = serde_json::to_string(&entry_with_header).unwrap();
+    let messages = publish_entry_messages(
+        &network_state.dna_address.clone().unwrap(),
+        &network_state.agent_id.clone().unwrap(),
+        &entry_with_header.entry.address(),
+        &content,
+        context.network_max_message_size,
+    );
+    for message in messages {
+        send(network_state, message)?;
+    }
+    Ok(())
 }
 
 /// Send to network:
@@ -130,40 +177,92 @@ fn reduce_publish_inner(
     let (crud_status, maybe_crud_link) = get_entry_crud_meta_from_dht(context, address.clone())?
         .expect("Entry should have crud-status metadata in DHT.");
     match entry_with_header.entry.entry_type() {
-        EntryType::AgentId => publish_entry(network_state, &entry_with_header).and_then(|_| {
-            publish_crud_meta(
-                network_state,
-                entry_with_header.entry.address(),
-                crud_status,
-                maybe_crud_link,
-            )
-        }),
-        EntryType::App(_) => publish_entry(network_state, &entry_with_header).and_then(|_| {
-            publish_crud_meta(
-                network_state,
-                entry_with_header.entry.address(),
-                crud_status,
-                maybe_crud_link,
-            )
-        }),
-        EntryType::LinkAdd => publish_entry(network_state, &entry_with_header)
+        EntryType::AgentId => {
+            publish_entry(context, network_state, &entry_with_header).and_then(|_| {
+                publish_crud_meta(
+                    network_state,
+                    entry_with_header.entry.address(),
+                    crud_status,
+                    maybe_crud_link,
+                )
+            })
+        }
+        EntryType::App(_) => {
+            publish_entry(context, network_state, &entry_with_header).and_then(|_| {
+                publish_crud_meta(
+                    network_state,
+                    entry_with_header.entry.address(),
+                    crud_status,
+                    maybe_crud_link,
+                )
+            })
+        }
+        EntryType::LinkAdd => publish_entry(context, network_state, &entry_with_header)
             .and_then(|_| publish_link_meta(context, network_state, &entry_with_header)),
-        EntryType::LinkRemove => publish_entry(network_state, &entry_with_header)
+        EntryType::LinkRemove => publish_entry(context, network_state, &entry_with_header)
             .and_then(|_| publish_link_meta(context, network_state, &entry_with_header)),
-        EntryType::Deletion => publish_entry(network_state, &entry_with_header).and_then(|_| {
-            publish_crud_meta(
-                network_state,
-                entry_with_header.entry.address(),
-                crud_status,
-                maybe_crud_link,
-            )
-        }),
+        EntryType::Deletion => {
+            publish_entry(context, network_state, &entry_with_header).and_then(|_| {
+                publish_crud_meta(
+                    network_state,
+                    entry_with_header.entry.address(),
+                    crud_status,
+                    maybe_crud_link,
+                )
+            })
+        }
         _ => Err(HolochainError::NotImplemented(
             "reduce_publish_inner".into(),
         )),
     }
 }
 
+/// Returns true if the given publish error is due to the network not being reachable,
+/// i.e. it makes sense to try again after the network got (re-)initialized.
+fn is_network_error(network_state: &NetworkState, error: &HolochainError) -> bool {
+    match error {
+        HolochainError::IoError(_) => true,
+        _ => network_state.initialized().is_err(),
+    }
+}
+
+/// Tries to publish the given address and queues it in `pending_publishes` if that failed
+/// because of the network. Removes it from the queue if it went through.
+/// An address that is queued already moves to the end, so the queue holds each address
+/// only once, no matter how often its publish failed.
+fn publish_or_queue(
+    context: &Arc<Context>,
+    network_state: &mut NetworkState,
+    address: &Address,
+) -> Result<(), HolochainError> {
+    let result = reduce_publish_inner(context, network_state, address);
+    network_state
+        .pending_publishes
+        .retain(|pending| pending != address);
+    if let Err(ref error) = result {
+        if is_network_error(network_state, error) {
+            network_state.pending_publishes.push(address.clone());
+        }
+    }
+    result
+}
+
+/// Re-sends all publishes that were queued while the network was unavailable.
+/// Since publishing reads the entry and its meta data from storage at the time of
+/// sending, each address goes out once with its latest state.
+/// Gets called after the network got (re-)initialized.
+pub fn resend_pending_publishes(context: &Arc<Context>, network_state: &mut NetworkState) {
+    let pending = network_state.pending_publishes.clone();
+    for address in pending {
+        if let Err(error) = publish_or_queue(context, network_state, &address) {
+            context.log(format!(
+                "debug/reduce/publish: Could not re-send pending publish of {}: {}",
+                address, error
+            ));
+        }
+    }
+}
+
 pub fn reduce_publish(
     context: Arc<Context>,
     network_state: &mut NetworkState,
@@ -172,7 +271,10 @@ pub fn reduce_publish(
     let action = action_wrapper.action();
     let address = unwrap_to!(action => crate::action::Action::Publish);
 
-    let result = reduce_publish_inner(&context, network_state, &address);
+    let result = publish_or_queue(&context, network_state, &address);
+    if let Err(ref error) = result {
+        network_state.set_last_error(error.clone());
+    }
     network_state.actions.insert(
         action_wrapper.clone(),
         ActionResponse::Publish(match result {
@@ -182,15 +284,73 @@ pub fn reduce_publish(
     );
 }
 
+/// Publishes all addresses of the batch, each with its own network messages.
+/// Addresses that could not be published because of the network get queued
+/// like single publishes.
+pub fn reduce_publish_batch(
+    context: Arc<Context>,
+    network_state: &mut NetworkState,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let addresses = unwrap_to!(action => crate::action::Action::PublishBatch);
+
+    let mut first_error = None;
+    for address in addresses {
+        if let Err(error) = publish_or_queue(&context, network_state, address) {
+            network_state.set_last_error(error.clone());
+            first_error = first_error.or(Some(error));
+        }
+    }
+    network_state.actions.insert(
+        action_wrapper.clone(),
+        ActionResponse::PublishBatch(match first_error {
+            None => Ok(addresses.clone()),
+            Some(e) => Err(HolochainError::ErrorGeneric(e.to_string())),
+        }),
+    );
+}
+
 #[cfg(test)]
 mod tests {
 
+    use super::*;
     use crate::{
-        action::{Action, ActionWrapper},
+        action::{Action, NetworkSettings},
+        context::test_memory_network_config,
         instance::tests::test_context,
+        network::test_utils::NetworkSpy,
+        nucleus::actions::tests::{instance_by_name, test_dna},
         state::test_store,
+        workflows::author_entry::author_entry,
+    };
+    use holochain_core_types::{
+        chain_header::test_chain_header,
+        crud_status::create_crud_status_eav,
+        entry::{entry_type::test_app_entry_type, test_entry, test_entry_b},
+        json::RawString,
     };
-    use holochain_core_types::{cas::content::AddressableContent, entry::test_entry};
+
+    #[test]
+    pub fn fragmented_publishes_fit_the_message_size() {
+        let value = "\"quoted\" große Einträge ".repeat(100);
+        let entry_with_header = EntryWithHeader {
+            entry: Entry::App(test_app_entry_type(), RawString::from(value).into()),
+            header: test_chain_header(),
+        };
+        let content = serde_json::to_string(&entry_with_header).unwrap();
+        let messages = publish_entry_messages(
+            &Address::from("dna"),
+            "alice",
+            &entry_with_header.entry.address(),
+            &content,
+            Some(512),
+        );
+        assert!(messages.len() > 1);
+        for message in messages {
+            assert!(serde_json::to_string(&message).unwrap().len() <= 512);
+        }
+    }
 
     #[test]
     pub fn reduce_publish_test() {
@@ -203,4 +363,91 @@ mod tests {
         store.reduce(context.clone(), action_wrapper);
     }
 
+    #[test]
+    pub fn reduce_publish_queues_until_network_is_initialized() {
+        let netname = Some("reduce_publish_queues_until_network_is_initialized");
+        let dna = test_dna();
+        let (_instance, context) = instance_by_name("alice", dna.clone(), netname);
+        let entry = Entry::App(
+            test_app_entry_type(),
+            RawString::from("published after reconnect").into(),
+        );
+        let address = context
+            .block_on(author_entry(&entry, None, &context))
+            .expect("Could not author entry");
+        let spy = NetworkSpy::new(netname, dna.address());
+
+        // a store that is not connected to the network yet,
+        // reading the entry from the instance's chain and DHT
+        let store = test_store(context.clone());
+        let action_wrapper = ActionWrapper::new(Action::Publish(address.clone()));
+        let store = store.reduce(context.clone(), action_wrapper);
+        assert_eq!(store.network().pending_publishes, vec![address.clone()]);
+
+        let action_wrapper = ActionWrapper::new(Action::InitNetwork(NetworkSettings {
+            p2p_config: test_memory_network_config(netname),
+            dna_address: dna.address(),
+            agent_id: String::from("alice-reconnected"),
+        }));
+        let store = store.reduce(context.clone(), action_wrapper);
+        assert!(store.network().initialized().is_ok());
+        assert!(store.network().pending_publishes.is_empty());
+        // waits for a second copy, which must never arrive
+        assert_eq!(spy.wait_for_stored_entries(&address, 2).len(), 1);
+    }
+
+    #[test]
+    pub fn pending_publishes_get_sent_once_per_address() {
+        let netname = Some("pending_publishes_get_sent_once_per_address");
+        let dna = test_dna();
+        let (_instance, context) = instance_by_name("alice", dna.clone(), netname);
+        let entry = Entry::App(
+            test_app_entry_type(),
+            RawString::from("published once after reconnect").into(),
+        );
+        let address = context
+            .block_on(author_entry(&entry, None, &context))
+            .expect("Could not author entry");
+        let other_address = test_entry_b().address();
+        let spy = NetworkSpy::new(netname, dna.address());
+
+        // a store that is not connected to the network yet,
+        // reading the entry from the instance's chain and DHT
+        let mut store = test_store(context.clone());
+        let action_wrapper = ActionWrapper::new(Action::Publish(address.clone()));
+        store = store.reduce(context.clone(), action_wrapper);
+        // the entry gets modified during the outage and published again
+        let modified_eav = create_crud_status_eav(&address, CrudStatus::Modified).unwrap();
+        context
+            .eav_storage
+            .write()
+            .unwrap()
+            .add_eavi(&modified_eav)
+            .unwrap();
+        for address in &[other_address.clone(), address.clone()] {
+            let action_wrapper = ActionWrapper::new(Action::Publish(address.clone()));
+            store = store.reduce(context.clone(), action_wrapper);
+        }
+        assert_eq!(
+            store.network().pending_publishes,
+            vec![other_address, address.clone()]
+        );
+
+        let action_wrapper = ActionWrapper::new(Action::InitNetwork(NetworkSettings {
+            p2p_config: test_memory_network_config(netname),
+            dna_address: dna.address(),
+            agent_id: String::from("alice-reconnected"),
+        }));
+        let store = store.reduce(context.clone(), action_wrapper);
+        // waits for a second copy, which must never arrive
+        assert_eq!(spy.wait_for_stored_entries(&address, 2).len(), 1);
+        let crud_status_meta = spy.stored_meta(&address, &Attribute::CrudStatus.to_string());
+        assert_eq!(crud_status_meta.len(), 1);
+        assert_eq!(
+            crud_status_meta[0].content_list,
+            vec![serde_json::to_value(CrudStatus::Modified).unwrap()]
+        );
+        // test_entry_b() never got committed, so its publish fails for good and gets dropped
+        assert_eq!(store.network().pending_publishes, Vec::<Address>::new());
+    }
 }
@@ -1,8 +1,12 @@
-use crate::{action::ActionWrapper, context::Context, network::state::NetworkState};
+use crate::{
+    action::ActionWrapper,
+    context::Context,
+    network::{reducers::send_direct_message::send_queued_direct_messages, state::NetworkState},
+};
 use std::sync::Arc;
 
 pub fn reduce_resolve_direct_connection(
-    _context: Arc<Context>,
+    context: Arc<Context>,
     network_state: &mut NetworkState,
     action_wrapper: &ActionWrapper,
 ) {
@@ -10,4 +14,5 @@ pub fn reduce_resolve_direct_connection(
     let id = unwrap_to!(action => crate::action::Action::ResolveDirectConnection);
 
     network_state.direct_message_connections.remove(id);
+    send_queued_direct_messages(&context, network_state);
 }
@@ -3,14 +3,28 @@ use crate::{
     context::Context,
     network::{actions::ActionResponse, reducers::send, state::NetworkState},
 };
-use holochain_core_types::{entry::EntryWithMeta, error::HolochainError};
+use holochain_core_types::{crud_status::CrudStatus, entry::EntryWithMeta, error::HolochainError};
 use holochain_net::connection::json_protocol::{
     FetchEntryData, FetchEntryResultData, JsonProtocol,
 };
 use std::sync::Arc;
 
+/// True if the requester named the requested entry as the version it already has
+/// and that still is the latest version, i.e. it is live and has no update.
+fn is_not_modified(get_dht_data: &FetchEntryData, maybe_entry: &Option<EntryWithMeta>) -> bool {
+    match (&get_dht_data.if_newer_than, maybe_entry) {
+        (Some(known_version), Some(entry_with_meta)) => {
+            *known_version == get_dht_data.entry_address
+                && entry_with_meta.crud_status == CrudStatus::Live
+                && entry_with_meta.maybe_crud_link.is_none()
+        }
+        _ => false,
+    }
+}
+
 /// Send back to network a HandleFetchEntryResult, no matter what.
-/// Will return an empty content field if it actually doesn't have the data.
+/// Will return an empty content field if it actually doesn't have the data,
+/// or if the requester already has it, see is_not_modified().
 fn reduce_respond_fetch_data_inner(
     network_state: &mut NetworkState,
     get_dht_data: &FetchEntryData,
@@ -18,6 +32,12 @@ fn reduce_respond_fetch_data_inner(
 ) -> Result<(), HolochainError> {
     network_state.initialized()?;
 
+    let not_modified = is_not_modified(get_dht_data, maybe_entry);
+    let entry_content = if not_modified {
+        serde_json::Value::Null
+    } else {
+        serde_json::from_str(&serde_json::to_string(&maybe_entry).unwrap()).unwrap()
+    };
     send(
         network_state,
         JsonProtocol::HandleFetchEntryResult(FetchEntryResultData {
@@ -26,8 +46,8 @@ fn reduce_respond_fetch_data_inner(
             dna_address: network_state.dna_address.clone().unwrap(),
             provider_agent_id: network_state.agent_id.clone().unwrap(),
             entry_address: get_dht_data.entry_address.clone(),
-            entry_content: serde_json::from_str(&serde_json::to_string(&maybe_entry).unwrap())
-                .unwrap(),
+            entry_content,
+            not_modified,
         }),
     )
 }
@@ -1,13 +1,13 @@
 use crate::{
     action::{ActionWrapper, DirectMessageData},
     context::Context,
-    network::{reducers::send, state::NetworkState},
+    network::{direct_message::DirectMessageOverflow, reducers::send, state::NetworkState},
 };
 use holochain_core_types::error::HolochainError;
 use holochain_net::connection::json_protocol::{JsonProtocol, MessageData};
 use std::sync::Arc;
 
-fn inner(
+fn send_direct_message_data(
     network_state: &mut NetworkState,
     direct_message_data: &DirectMessageData,
 ) -> Result<(), HolochainError> {
@@ -18,24 +18,104 @@ fn inner(
         dna_address: network_state.dna_address.clone().unwrap(),
         to_agent_id: direct_message_data.address.to_string(),
         from_agent_id: network_state.agent_id.clone().unwrap(),
-        content: serde_json::from_str(
-            &serde_json::to_string(&direct_message_data.message).unwrap(),
-        )
-        .unwrap(),
+        content: serde_json::to_value(&direct_message_data.envelope).unwrap(),
     };
 
     let protocol_object = if direct_message_data.is_response {
         JsonProtocol::HandleSendMessageResult(data)
     } else {
-        network_state
-            .direct_message_connections
-            .insert(data.request_id.clone(), direct_message_data.message.clone());
+        network_state.direct_message_connections.insert(
+            data.request_id.clone(),
+            direct_message_data.envelope.message.clone(),
+        );
         JsonProtocol::SendMessage(data)
     };
 
     send(network_state, protocol_object)
 }
 
+fn inner(
+    context: &Context,
+    network_state: &mut NetworkState,
+    direct_message_data: &DirectMessageData,
+) -> Result<(), HolochainError> {
+    network_state.initialized()?;
+
+    let limit = match context.direct_message_limit {
+        Some(limit) if !direct_message_data.is_response => limit,
+        _ => return send_direct_message_data(network_state, direct_message_data),
+    };
+    if network_state.in_flight_direct_messages() < limit.max_in_flight {
+        return send_direct_message_data(network_state, direct_message_data);
+    }
+
+    match limit.overflow {
+        DirectMessageOverflow::Queue
+            if network_state.queued_direct_messages.len() < limit.max_queued =>
+        {
+            network_state
+                .queued_direct_messages
+                .push_back(direct_message_data.clone());
+            Ok(())
+        }
+        DirectMessageOverflow::Queue => reject(
+            network_state,
+            direct_message_data,
+            format!(
+                "Too many direct messages queued (maximum is {})",
+                limit.max_queued
+            ),
+        ),
+        DirectMessageOverflow::Reject => reject(
+            network_state,
+            direct_message_data,
+            format!(
+                "Too many direct messages in flight (maximum is {})",
+                limit.max_in_flight
+            ),
+        ),
+    }
+}
+
+/// Fails sending the given message with the given error message.
+fn reject(
+    network_state: &mut NetworkState,
+    direct_message_data: &DirectMessageData,
+    message: String,
+) -> Result<(), HolochainError> {
+    let error = HolochainError::ErrorGeneric(message);
+    network_state
+        .custom_direct_message_replys
+        .insert(direct_message_data.msg_id.clone(), Err(error.clone()));
+    Err(error)
+}
+
+/// Sends queued direct messages, oldest first, while fewer than the maximum number of
+/// direct messages wait for a response.
+/// Gets called whenever a direct message got answered or timed out.
+/// Queued messages got signed before they were queued, so this does not block.
+pub fn send_queued_direct_messages(context: &Context, network_state: &mut NetworkState) {
+    let max_in_flight = context
+        .direct_message_limit
+        .map(|limit| limit.max_in_flight)
+        .unwrap_or(usize::max_value());
+    while network_state.in_flight_direct_messages() < max_in_flight {
+        let direct_message_data = match network_state.queued_direct_messages.pop_front() {
+            Some(direct_message_data) => direct_message_data,
+            None => return,
+        };
+        if let Err(error) = send_direct_message_data(network_state, &direct_message_data) {
+            context.log(format!(
+                "err/net: Error sending queued direct message: {:?}",
+                error
+            ));
+            network_state
+                .custom_direct_message_replys
+                .insert(direct_message_data.msg_id.clone(), Err(error));
+        }
+    }
+}
+
 pub fn reduce_send_direct_message(
     context: Arc<Context>,
     network_state: &mut NetworkState,
@@ -43,7 +123,7 @@ pub fn reduce_send_direct_message(
 ) {
     let action = action_wrapper.action();
     let dm_data = unwrap_to!(action => crate::action::Action::SendDirectMessage);
-    if let Err(error) = inner(network_state, dm_data) {
+    if let Err(error) = inner(&context, network_state, dm_data) {
         context.log(format!(
             "err/net: Error sending direct message: {:?}",
             error
@@ -51,21 +131,28 @@ pub fn reduce_send_direct_message(
     }
 }
 
+/// Also frees the slot of the timed out message (or drops it from the queue),
+/// so that queued direct messages can get sent.
 pub fn reduce_send_direct_message_timeout(
-    _context: Arc<Context>,
+    context: Arc<Context>,
     network_state: &mut NetworkState,
     action_wrapper: &ActionWrapper,
 ) {
     let action = action_wrapper.action();
     let id = unwrap_to!(action => crate::action::Action::SendDirectMessageTimeout);
 
-    if network_state.custom_direct_message_replys.get(id).is_some() {
-        return;
+    network_state
+        .queued_direct_messages
+        .retain(|direct_message_data| &direct_message_data.msg_id != id);
+    network_state.direct_message_connections.remove(id);
+
+    if network_state.custom_direct_message_replys.get(id).is_none() {
+        network_state
+            .custom_direct_message_replys
+            .insert(id.clone(), Err(HolochainError::Timeout));
     }
 
-    network_state
-        .custom_direct_message_replys
-        .insert(id.clone(), Err(HolochainError::Timeout));
+    send_queued_direct_messages(&context, network_state);
 }
 
 #[cfg(test)]
@@ -73,10 +160,16 @@ mod tests {
 
     use crate::{
         action::{Action, ActionWrapper, DirectMessageData, NetworkSettings},
-        context::test_memory_network_config,
+        context::{test_memory_network_config, Context},
         instance::tests::test_context,
-        network::direct_message::{CustomDirectMessage, DirectMessage},
-        state::test_store,
+        network::{
+            direct_message::{
+                CustomDirectMessage, DirectMessage, DirectMessageEnvelope, DirectMessageLimit,
+                DirectMessageOverflow,
+            },
+            test_utils::NetworkSpy,
+        },
+        state::{test_store, State},
     };
     use holochain_core_types::{cas::content::Address, error::HolochainError};
     use std::sync::{Arc, RwLock};
@@ -108,7 +201,7 @@ mod tests {
         let msg_id = String::from("any");
         let direct_message_data = DirectMessageData {
             address: Address::from("bogus"),
-            message: custom_direct_message,
+            envelope: DirectMessageEnvelope::new(custom_direct_message, "bogus", &context).unwrap(),
             msg_id: msg_id.clone(),
             is_response: false,
         };
@@ -141,4 +234,223 @@ mod tests {
             .cloned();
         assert_eq!(maybe_reply, Some(Err(HolochainError::Timeout)));
     }
+
+    fn initialized_store_with_limit(
+        netname: &str,
+        overflow: DirectMessageOverflow,
+        max_queued: usize,
+    ) -> (Arc<Context>, Arc<RwLock<State>>) {
+        let mut context = test_context("alice", Some(netname));
+        let store = Arc::new(RwLock::new(test_store(context.clone())));
+        {
+            let context = Arc::get_mut(&mut context).unwrap();
+            context.set_state(store.clone());
+            context.direct_message_limit = Some(DirectMessageLimit {
+                max_in_flight: 2,
+                overflow,
+                max_queued,
+            });
+        }
+
+        let action_wrapper = ActionWrapper::new(Action::InitNetwork(NetworkSettings {
+            p2p_config: test_memory_network_config(Some(netname)),
+            dna_address: netname.into(),
+            agent_id: String::from("alice"),
+        }));
+        {
+            let mut new_store = store.write().unwrap();
+            *new_store = new_store.reduce(context.clone(), action_wrapper);
+        }
+        (context, store)
+    }
+
+    fn reduce(context: &Arc<Context>, store: &Arc<RwLock<State>>, action: Action) -> usize {
+        let mut new_store = store.write().unwrap();
+        *new_store = new_store.reduce(context.clone(), ActionWrapper::new(action));
+        new_store.network().in_flight_direct_messages()
+    }
+
+    fn send_custom(context: &Arc<Context>, msg_id: &str) -> Action {
+        let message = DirectMessage::Custom(CustomDirectMessage {
+            zome: String::from("test"),
+            payload: Ok(String::from("test")),
+        });
+        Action::SendDirectMessage(DirectMessageData {
+            address: Address::from("bogus"),
+            envelope: DirectMessageEnvelope::new(message, "bogus", context).unwrap(),
+            msg_id: String::from(msg_id),
+            is_response: false,
+        })
+    }
+
+    #[test]
+    pub fn direct_messages_beyond_the_limit_get_rejected() {
+        let (context, store) = initialized_store_with_limit(
+            "direct_messages_beyond_the_limit_get_rejected",
+            DirectMessageOverflow::Reject,
+            10,
+        );
+
+        for msg_id in &["1", "2", "3"] {
+            assert!(reduce(&context, &store, send_custom(&context, msg_id)) <= 2);
+        }
+
+        let network = store.read().unwrap().network();
+        assert_eq!(network.in_flight_direct_messages(), 2);
+        assert!(network.queued_direct_messages.is_empty());
+        assert_eq!(network.custom_direct_message_replys.get("1"), None);
+        assert_eq!(network.custom_direct_message_replys.get("2"), None);
+        assert_eq!(
+            network.custom_direct_message_replys.get("3"),
+            Some(&Err(HolochainError::ErrorGeneric(String::from(
+                "Too many direct messages in flight (maximum is 2)"
+            ))))
+        );
+
+        // once one got answered there is room again
+        reduce(
+            &context,
+            &store,
+            Action::ResolveDirectConnection(String::from("1")),
+        );
+        assert_eq!(reduce(&context, &store, send_custom(&context, "4")), 2);
+        assert_eq!(
+            store
+                .read()
+                .unwrap()
+                .network()
+                .custom_direct_message_replys
+                .get("4"),
+            None
+        );
+    }
+
+    #[test]
+    pub fn direct_messages_beyond_the_limit_get_queued() {
+        let (context, store) = initialized_store_with_limit(
+            "direct_messages_beyond_the_limit_get_queued",
+            DirectMessageOverflow::Queue,
+            10,
+        );
+
+        for msg_id in &["1", "2", "3", "4"] {
+            assert!(reduce(&context, &store, send_custom(&context, msg_id)) <= 2);
+        }
+        let queued_ids = |store: &Arc<RwLock<State>>| -> Vec<String> {
+            store
+                .read()
+                .unwrap()
+                .network()
+                .queued_direct_messages
+                .iter()
+                .map(|direct_message_data| direct_message_data.msg_id.clone())
+                .collect()
+        };
+        assert_eq!(queued_ids(&store), vec!["3", "4"]);
+
+        // an answer lets the oldest queued message through
+        let in_flight = reduce(
+            &context,
+            &store,
+            Action::ResolveDirectConnection(String::from("1")),
+        );
+        assert_eq!(in_flight, 2);
+        assert_eq!(queued_ids(&store), vec!["4"]);
+        assert!(store
+            .read()
+            .unwrap()
+            .network()
+            .direct_message_connections
+            .contains_key("3"));
+
+        // so does a timeout
+        let in_flight = reduce(
+            &context,
+            &store,
+            Action::SendDirectMessageTimeout(String::from("2")),
+        );
+        assert_eq!(in_flight, 2);
+        assert!(queued_ids(&store).is_empty());
+        assert_eq!(
+            store
+                .read()
+                .unwrap()
+                .network()
+                .custom_direct_message_replys
+                .get("2"),
+            Some(&Err(HolochainError::Timeout))
+        );
+    }
+
+    #[test]
+    pub fn direct_messages_beyond_the_queue_size_get_rejected() {
+        let (context, store) = initialized_store_with_limit(
+            "direct_messages_beyond_the_queue_size_get_rejected",
+            DirectMessageOverflow::Queue,
+            1,
+        );
+
+        for msg_id in &["1", "2", "3", "4"] {
+            assert!(reduce(&context, &store, send_custom(&context, msg_id)) <= 2);
+        }
+
+        let network = store.read().unwrap().network();
+        assert_eq!(network.in_flight_direct_messages(), 2);
+        assert_eq!(network.queued_direct_messages.len(), 1);
+        assert_eq!(network.queued_direct_messages[0].msg_id, "3");
+        assert_eq!(network.custom_direct_message_replys.get("3"), None);
+        assert_eq!(
+            network.custom_direct_message_replys.get("4"),
+            Some(&Err(HolochainError::ErrorGeneric(String::from(
+                "Too many direct messages queued (maximum is 1)"
+            ))))
+        );
+
+        // once the queued one got sent there is room in the queue again
+        reduce(
+            &context,
+            &store,
+            Action::ResolveDirectConnection(String::from("1")),
+        );
+        reduce(&context, &store, send_custom(&context, "5"));
+        let network = store.read().unwrap().network();
+        assert_eq!(network.queued_direct_messages.len(), 1);
+        assert_eq!(network.queued_direct_messages[0].msg_id, "5");
+        assert_eq!(network.custom_direct_message_replys.get("5"), None);
+    }
+
+    #[test]
+    pub fn queued_direct_messages_get_sent_as_signed_when_dispatched() {
+        let netname = "queued_direct_messages_get_sent_as_signed_when_dispatched";
+        let (context, store) =
+            initialized_store_with_limit(netname, DirectMessageOverflow::Queue, 10);
+        let spy = NetworkSpy::new(Some(netname), netname.into());
+        reduce(&context, &store, send_custom(&context, "1"));
+        reduce(&context, &store, send_custom(&context, "2"));
+
+        let message = DirectMessage::Custom(CustomDirectMessage {
+            zome: String::from("test"),
+            payload: Ok(String::from("queued")),
+        });
+        let envelope = DirectMessageEnvelope::new(message, "network-spy", &context).unwrap();
+        let queued = Action::SendDirectMessage(DirectMessageData {
+            address: Address::from("network-spy"),
+            envelope: envelope.clone(),
+            msg_id: String::from("3"),
+            is_response: false,
+        });
+        reduce(&context, &store, queued);
+        reduce(
+            &context,
+            &store,
+            Action::ResolveDirectConnection(String::from("1")),
+        );
+
+        assert!(spy.wait_for_direct_message("3"));
+        let sent = spy.direct_message("3").unwrap();
+        assert_eq!(
+            serde_json::from_value::<DirectMessageEnvelope>(sent.content).unwrap(),
+            envelope
+        );
+    }
 }
@@ -0,0 +1,60 @@
+use crate::{
+    action::ActionWrapper,
+    context::Context,
+    network::{actions::ActionResponse, state::NetworkState},
+};
+use std::sync::Arc;
+
+/// Drops the network proxy object from the state.
+/// The network itself gets stopped by the shutdown_network action before, outside of
+/// the reducers, since stopping blocks until the network thread is done.
+pub fn reduce_shutdown(
+    _context: Arc<Context>,
+    state: &mut NetworkState,
+    action_wrapper: &ActionWrapper,
+) {
+    state.network = None;
+    state.actions.insert(
+        action_wrapper.clone(),
+        ActionResponse::ShutdownNetwork(Ok(())),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        action::{Action, ActionWrapper, NetworkSettings},
+        instance::tests::test_context,
+        network::actions::ActionResponse,
+        state::test_store,
+    };
+    use holochain_core_types::error::HolochainError;
+    use holochain_net::p2p_config::P2pConfig;
+
+    #[test]
+    pub fn reduce_shutdown_drops_network() {
+        let context = test_context("alice", None);
+        let store = test_store(context.clone());
+
+        let store = store.reduce(
+            context.clone(),
+            ActionWrapper::new(Action::InitNetwork(NetworkSettings {
+                p2p_config: P2pConfig::new_with_unique_memory_backend(),
+                dna_address: "reduce_shutdown_stops_network".into(),
+                agent_id: String::from("alice"),
+            })),
+        );
+        assert_eq!(store.network().initialized(), Ok(()));
+
+        let action_wrapper = ActionWrapper::new(Action::ShutdownNetwork);
+        let store = store.reduce(context.clone(), action_wrapper.clone());
+        assert_eq!(
+            store.network().actions().get(&action_wrapper),
+            Some(&ActionResponse::ShutdownNetwork(Ok(())))
+        );
+        assert_eq!(
+            store.network().initialized(),
+            Err(HolochainError::NetworkNotInitialized)
+        );
+    }
+}
@@ -1,16 +1,22 @@
 use crate::{
-    action::{ActionWrapper, GetEntryKey, GetLinksKey},
-    network::{actions::ActionResponse, direct_message::DirectMessage},
+    action::{ActionWrapper, DirectMessageData, GetEntryKey, GetLinksKey},
+    network::{
+        actions::{get_entry::FetchedEntry, ActionResponse},
+        direct_message::DirectMessage,
+    },
 };
 use boolinator::*;
+use chrono::{offset::Utc, DateTime};
 use holochain_core_types::{
-    cas::content::Address, entry::EntryWithMeta, error::HolochainError,
-    validation::ValidationPackage,
+    cas::content::Address, error::HolochainError, validation::ValidationPackage,
+};
+use holochain_net::{
+    neighborhood::{neighborhood, DEFAULT_NEIGHBORHOOD_SIZE},
+    p2p_network::P2pNetwork,
 };
-use holochain_net::p2p_network::P2pNetwork;
 use snowflake;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     sync::{Arc, Mutex},
 };
 
@@ -19,9 +25,10 @@ type Actions = HashMap<ActionWrapper, ActionResponse>;
 /// This represents the state of a get_entry network process:
 /// None: process started, but no response yet from the network
 /// Some(Err(_)): there was a problem at some point
-/// Some(Ok(None)): no problem but also no entry -> it does not exist
-/// Some(Ok(Some(entry_with_meta))): we have it
-type GetEntryWithMetaResult = Option<Result<Option<EntryWithMeta>, HolochainError>>;
+/// Some(Ok(FetchedEntry::Entry(None))): no problem but also no entry -> it does not exist
+/// Some(Ok(FetchedEntry::Entry(Some(entry_with_meta)))): we have it
+/// Some(Ok(FetchedEntry::NotModified)): we already have the latest version
+type GetEntryWithMetaResult = Option<Result<FetchedEntry, HolochainError>>;
 
 /// This represents the state of a get_links network process:
 /// None: process started, but no response yet from the network
@@ -67,6 +74,23 @@ pub struct NetworkState {
 
     pub custom_direct_message_replys: HashMap<String, Result<String, HolochainError>>,
 
+    /// Custom direct messages that did not get sent yet because the maximum number of
+    /// them was already waiting for a response (see Context::direct_message_limit).
+    /// They get sent in order as soon as others get answered or time out.
+    /// Holds at most DirectMessageLimit::max_queued messages since it gets cloned
+    /// with the rest of the state on every reduce.
+    pub queued_direct_messages: VecDeque<DirectMessageData>,
+
+    /// Addresses of entries whose publish could not be handed to the network yet,
+    /// either because it was not initialized or because sending failed.
+    /// They get re-sent (in order) once the network gets (re-)initialized and are removed
+    /// from this list as soon as sending succeeds.
+    pub pending_publishes: Vec<Address>,
+
+    /// The most recent error of a network operation (init, publish, get) and when it happened.
+    /// Does not get cleared by successful operations, only overwritten by the next error.
+    last_error: Option<(DateTime<Utc>, HolochainError)>,
+
     id: snowflake::ProcessUniqueId,
 }
 
@@ -89,6 +113,9 @@ impl NetworkState {
             get_validation_package_results: HashMap::new(),
             direct_message_connections: HashMap::new(),
             custom_direct_message_replys: HashMap::new(),
+            queued_direct_messages: VecDeque::new(),
+            pending_publishes: Vec::new(),
+            last_error: None,
 
             id: snowflake::ProcessUniqueId::new(),
         }
@@ -98,9 +125,63 @@ impl NetworkState {
         self.actions.clone()
     }
 
+    pub fn last_error(&self) -> Option<(DateTime<Utc>, HolochainError)> {
+        self.last_error.clone()
+    }
+
+    pub(crate) fn set_last_error(&mut self, error: HolochainError) {
+        self.last_error = Some((Utc::now(), error));
+    }
+
     pub fn initialized(&self) -> Result<(), HolochainError> {
-        (self.network.is_some() && self.dna_address.is_some() && self.agent_id.is_some()).ok_or(
-            HolochainError::ErrorGeneric("Network not initialized".to_string()),
-        )
+        (self.network.is_some() && self.dna_address.is_some() && self.agent_id.is_some())
+            .ok_or(HolochainError::NetworkNotInitialized)
+    }
+
+    /// Number of custom direct messages that got sent and wait for a response.
+    pub fn in_flight_direct_messages(&self) -> usize {
+        self.direct_message_connections
+            .values()
+            .filter(|message| match message {
+                DirectMessage::Custom(_) => true,
+                _ => false,
+            })
+            .count()
+    }
+
+    /// Number of peers the network currently accepts messages from.
+    /// 0 if the network is not initialized.
+    pub fn peer_count(&self) -> usize {
+        self.network
+            .as_ref()
+            .map(|network| network.lock().unwrap().peer_count())
+            .unwrap_or(0)
+    }
+
+    /// Makes the network hand data published by this instance to other nodes right away
+    /// instead of at the next publish interval.
+    pub fn trigger_publish(&self) -> Result<(), HolochainError> {
+        self.network
+            .as_ref()
+            .ok_or(HolochainError::NetworkNotInitialized)?
+            .lock()
+            .unwrap()
+            .trigger_publish();
+        Ok(())
+    }
+
+    /// The agents, including this one, that we expect to be responsible for holding
+    /// the given address, closest first. Empty if the network is not initialized.
+    pub fn neighborhood_for(&self, address: &Address) -> Vec<String> {
+        let mut agents = match self.network {
+            Some(ref network) => network.lock().unwrap().peers(),
+            None => return Vec::new(),
+        };
+        if let Some(ref agent_id) = self.agent_id {
+            if !agents.contains(agent_id) {
+                agents.push(agent_id.clone());
+            }
+        }
+        neighborhood(address, &agents, DEFAULT_NEIGHBORHOOD_SIZE)
     }
 }
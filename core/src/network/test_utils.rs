@@ -1,9 +1,25 @@
 use crate::{
-    context::Context,
+    context::{test_memory_network_config, Context},
     instance::{tests::test_context, Instance},
+    logger::test_logger,
+    persister::SimplePersister,
 };
+use holochain_cas_implementations::{cas::memory::MemoryStorage, eav::memory::EavMemoryStorage};
 use holochain_core_types::{cas::content::Address, dna::Dna};
-use std::sync::Arc;
+use holochain_net::{
+    connection::{
+        json_protocol::{DhtMetaData, EntryData, JsonProtocol, MessageData, TrackDnaData},
+        net_connection::NetSend,
+    },
+    p2p_network::P2pNetwork,
+};
+use std::{
+    convert::TryFrom,
+    sync::{Arc, Mutex, RwLock},
+    thread,
+    time::{Duration, Instant},
+};
+use test_utils::mock_signing::registered_test_agent;
 
 /// create a test instance
 #[cfg_attr(tarpaulin, skip)]
@@ -30,6 +46,162 @@ pub fn test_instance_with_spoofed_dna(
     Ok((instance, context))
 }
 
+/// Creates an initialized instance for every given agent name and returns their contexts.
+/// All instances share the same in-memory DHT and EAV storage, like agents running
+/// colocated on one node, and are connected through the same memory network.
+/// Every agent keeps its own source chain.
+#[cfg_attr(tarpaulin, skip)]
+pub fn shared_storage_contexts(dna: Dna, names: &[&str]) -> Vec<Arc<Context>> {
+    let dht_storage = Arc::new(RwLock::new(MemoryStorage::new()));
+    let eav_storage = Arc::new(RwLock::new(EavMemoryStorage::new()));
+    let network_name = format!("shared_storage_contexts-{}", dna.uuid);
+    names
+        .iter()
+        .map(|name| {
+            let chain_storage = Arc::new(RwLock::new(MemoryStorage::new()));
+            let context = Arc::new(Context::new(
+                registered_test_agent(name),
+                test_logger(),
+                Arc::new(Mutex::new(SimplePersister::new(chain_storage.clone()))),
+                chain_storage,
+                dht_storage.clone(),
+                eav_storage.clone(),
+                test_memory_network_config(Some(&network_name)),
+                None,
+                None,
+            ));
+            let mut instance = Instance::new(context.clone());
+            instance
+                .initialize(Some(dna.clone()), context)
+                .unwrap_or_else(|error| panic!("Could not initialize instance {}: {}", name, error))
+        })
+        .collect()
+}
+
+/// An extra node on a memory network that tracks a DNA and records what the memory
+/// server asks it to store, i.e. every entry and meta data published by the other nodes.
+/// Lets tests count the network messages that got sent.
+pub struct NetworkSpy {
+    network: P2pNetwork,
+    received: Arc<Mutex<Vec<JsonProtocol>>>,
+}
+
+impl NetworkSpy {
+    pub fn new(network_name: Option<&str>, dna_address: Address) -> Self {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let recorder = received.clone();
+        let mut network = P2pNetwork::new(
+            Box::new(move |message| {
+                if let Ok(message) = JsonProtocol::try_from(message?) {
+                    recorder.lock().unwrap().push(message);
+                }
+                Ok(())
+            }),
+            &test_memory_network_config(network_name),
+        )
+        .expect("Could not create network spy");
+        network
+            .send(
+                JsonProtocol::TrackDna(TrackDnaData {
+                    dna_address,
+                    agent_id: String::from("network-spy"),
+                })
+                .into(),
+            )
+            .expect("Network spy could not track DNA");
+        NetworkSpy { network, received }
+    }
+
+    /// Entries received so far for the given address.
+    pub fn stored_entries(&self, address: &Address) -> Vec<EntryData> {
+        self.received
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|message| match message {
+                JsonProtocol::HandleStoreEntry(entry_data) => Some(entry_data.clone()),
+                _ => None,
+            })
+            .filter(|entry_data| &entry_data.entry_address == address)
+            .collect()
+    }
+
+    /// Meta data received so far for the given address and attribute.
+    pub fn stored_meta(&self, address: &Address, attribute: &str) -> Vec<DhtMetaData> {
+        self.received
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|message| match message {
+                JsonProtocol::HandleStoreMeta(meta_data) => Some(meta_data.clone()),
+                _ => None,
+            })
+            .filter(|meta_data| &meta_data.entry_address == address)
+            .filter(|meta_data| meta_data.attribute == attribute)
+            .collect()
+    }
+
+    /// Waits until the given number of entries for the given address got received,
+    /// plus a bit longer to catch any extra ones, and returns all of them.
+    /// Gives up after a few seconds.
+    pub fn wait_for_stored_entries(&self, address: &Address, count: usize) -> Vec<EntryData> {
+        let start = Instant::now();
+        while self.stored_entries(address).len() < count && start.elapsed() < Duration::from_secs(5)
+        {
+            thread::sleep(Duration::from_millis(10));
+        }
+        thread::sleep(Duration::from_millis(100));
+        self.stored_entries(address)
+    }
+
+    /// Waits until the direct message with the given request id got received and tells
+    /// if it did. The memory server delivers in order, so anything a node sent to the spy
+    /// before that message got received by then, too. Gives up after a few seconds.
+    pub fn wait_for_direct_message(&self, request_id: &str) -> bool {
+        let received = || {
+            self.received
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|message| match message {
+                    JsonProtocol::HandleSendMessage(message_data) => {
+                        message_data.request_id == request_id
+                    }
+                    _ => false,
+                })
+        };
+        let start = Instant::now();
+        while !received() && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        received()
+    }
+
+    /// The direct message with the given request id, if it got received.
+    pub fn direct_message(&self, request_id: &str) -> Option<MessageData> {
+        self.received
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|message| match message {
+                JsonProtocol::HandleSendMessage(message_data) => Some(message_data.clone()),
+                _ => None,
+            })
+            .find(|message_data| message_data.request_id == request_id)
+    }
+
+    /// Forgets everything received so far.
+    pub fn clear(&self) {
+        self.received.lock().unwrap().clear();
+    }
+}
+
+impl Drop for NetworkSpy {
+    fn drop(&mut self) {
+        let _ = self.network.stop();
+    }
+}
+
 pub fn test_wat_always_valid() -> String {
     r#"
 (module
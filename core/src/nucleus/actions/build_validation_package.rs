@@ -2,8 +2,11 @@ use crate::{
     action::{Action, ActionWrapper},
     agent::{self, find_chain_header},
     context::Context,
-    nucleus::ribosome::callback::{
-        validation_package::get_validation_package_definition, CallbackResult,
+    nucleus::{
+        actions::{get_entry::get_raw_content, validate::check_entry_type},
+        ribosome::callback::{
+            validation_package::get_validation_package_definition, CallbackResult,
+        },
     },
 };
 use futures::{
@@ -11,13 +14,19 @@ use futures::{
     task::{LocalWaker, Poll},
 };
 use holochain_core_types::{
+    cas::content::Address,
     chain_header::ChainHeader,
-    entry::{entry_type::EntryType, Entry},
+    entry::Entry,
     error::HolochainError,
     validation::{ValidationPackage, ValidationPackageDefinition::*},
 };
 use snowflake;
-use std::{convert::TryInto, pin::Pin, sync::Arc, thread};
+use std::{
+    convert::{TryFrom, TryInto},
+    pin::Pin,
+    sync::Arc,
+    thread,
+};
 
 pub async fn build_validation_package(
     entry: &Entry,
@@ -25,50 +34,7 @@ pub async fn build_validation_package(
 ) -> Result<ValidationPackage, HolochainError> {
     let id = snowflake::ProcessUniqueId::new();
 
-    match entry.entry_type() {
-        EntryType::App(app_entry_type) => {
-            if context
-                .state()
-                .unwrap()
-                .nucleus()
-                .dna()
-                .unwrap()
-                .get_zome_name_for_app_entry_type(&app_entry_type)
-                .is_none()
-            {
-                return Err(HolochainError::ValidationFailed(format!(
-                    "Unknown app entry type '{}'",
-                    String::from(app_entry_type),
-                )));
-            }
-        }
-
-        EntryType::LinkAdd => {
-            // LinkAdd can always be validated
-        }
-
-        EntryType::LinkRemove => {
-            // LinkAdd can always be validated
-        }
-
-        EntryType::Deletion => {
-            // FIXME
-        }
-
-        EntryType::CapTokenGrant => {
-            // FIXME
-        }
-
-        EntryType::AgentId => {
-            // FIXME
-        }
-        _ => {
-            return Err(HolochainError::ValidationFailed(format!(
-                "Attempted to validate system entry type {:?}",
-                entry.entry_type(),
-            )));
-        }
-    };
+    check_entry_type(entry.entry_type(), &context)?;
 
     {
         let id = id.clone();
@@ -151,6 +117,90 @@ pub async fn build_validation_package(
     })
 }
 
+/// Looks up the entry with the given address in the agent's CAS and builds
+/// the validation package for it.
+pub async fn build_validation_package_for_address(
+    address: &Address,
+    context: Arc<Context>,
+) -> Result<ValidationPackage, HolochainError> {
+    let raw = context
+        .state()
+        .unwrap()
+        .agent()
+        .chain_store()
+        .content_storage()
+        .read()
+        .unwrap()
+        .fetch(address)?
+        .ok_or(HolochainError::ErrorGeneric(
+            "Entry not found when trying to build validation package".to_string(),
+        ))?;
+    let entry = Entry::try_from(raw)?;
+    await!(build_validation_package(&entry, context))
+}
+
+/// Tries to build the validation package for an entry with the given header, which might have
+/// been authored by another agent, from the headers and entries held locally
+/// (in the agent's or the DHT CAS) instead of requesting it from the source.
+/// The source chain gets walked back from the given header.
+///
+/// Returns None if the package definition can't be determined or if any of the
+/// chain headers or public entries the definition asks for are not held locally.
+pub fn build_validation_package_locally(
+    entry: &Entry,
+    header: &ChainHeader,
+    context: &Arc<Context>,
+) -> Option<ValidationPackage> {
+    let package_definition = match get_validation_package_definition(entry, context.clone()) {
+        Ok(CallbackResult::ValidationPackageDefinition(def)) => def,
+        _ => return None,
+    };
+    let mut package = ValidationPackage::only_header(header.clone());
+    match package_definition {
+        Entry => {}
+        ChainEntries => {
+            let headers = local_chain_headers(header, context)?;
+            package.source_chain_entries = Some(local_public_chain_entries(&headers, context)?);
+        }
+        ChainHeaders => {
+            package.source_chain_headers = Some(local_chain_headers(header, context)?);
+        }
+        ChainFull => {
+            let headers = local_chain_headers(header, context)?;
+            package.source_chain_entries = Some(local_public_chain_entries(&headers, context)?);
+            package.source_chain_headers = Some(headers);
+        }
+        Custom(string) => package.custom = Some(string),
+    }
+    Some(package)
+}
+
+fn local_chain_headers(header: &ChainHeader, context: &Arc<Context>) -> Option<Vec<ChainHeader>> {
+    let mut headers = vec![header.clone()];
+    let mut maybe_link = header.link();
+    while let Some(link) = maybe_link {
+        let content = get_raw_content(context, &link).ok()??;
+        let previous = ChainHeader::try_from(content).ok()?;
+        maybe_link = previous.link();
+        headers.push(previous);
+    }
+    Some(headers)
+}
+
+fn local_public_chain_entries(
+    headers: &[ChainHeader],
+    context: &Arc<Context>,
+) -> Option<Vec<Entry>> {
+    headers
+        .iter()
+        .filter(|chain_header| chain_header.entry_type().can_publish())
+        .map(|chain_header| {
+            let content = get_raw_content(context, chain_header.entry_address()).ok()??;
+            Entry::try_from(content).ok()
+        })
+        .collect()
+}
+
 fn all_public_chain_entries(context: &Arc<Context>) -> Vec<Entry> {
     let chain = context.state().unwrap().agent().chain_store();
     let top_header = context.state().unwrap().agent().top_chain_header();
@@ -211,7 +261,7 @@ mod tests {
     use super::*;
     use crate::nucleus::actions::tests::*;
 
-    use holochain_core_types::validation::ValidationPackage;
+    use holochain_core_types::{cas::content::AddressableContent, validation::ValidationPackage};
 
     #[test]
     fn test_building_validation_package_entry() {
@@ -241,6 +291,28 @@ mod tests {
         assert_eq!(maybe_validation_package.unwrap(), expected);
     }
 
+    #[test]
+    fn test_building_validation_package_for_address() {
+        let (_instance, context) = instance(None);
+
+        commit(test_entry_package_chain_entries(), &context);
+        let chain_header = commit(test_entry_package_entry(), &context);
+
+        let validation_package = context
+            .block_on(build_validation_package_for_address(
+                &test_entry_package_entry().address(),
+                context.clone(),
+            ))
+            .expect("Could not build validation package");
+        assert_eq!(validation_package.chain_header, chain_header);
+
+        let result = context.block_on(build_validation_package_for_address(
+            &Address::from("unknown address"),
+            context.clone(),
+        ));
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_building_validation_package_chain_entries() {
         let (_instance, context) = instance(None);
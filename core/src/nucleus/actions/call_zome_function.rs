@@ -14,7 +14,10 @@ use holochain_core_types::error::HolochainError;
 use std::{pin::Pin, sync::Arc};
 
 use holochain_core_types::{
-    dna::capabilities::CapabilityCall, entry::cap_entries::CapTokenGrant, json::JsonString,
+    cas::content::{Address, AddressableContent},
+    dna::capabilities::CapabilityCall,
+    entry::cap_entries::CapTokenGrant,
+    json::JsonString,
 };
 use std::{convert::TryFrom, thread};
 
@@ -63,7 +66,7 @@ pub async fn call_zome_function(
     // If we would leak (and move) the lock into the Ribosome thread below, it would lead to a
     // dead-lock since the existence of this read-lock prevents the redux loop from writing to
     // the state..
-    let (dna_name, code) = {
+    let (dna_name, code, cacheable) = {
         let state = context.state().ok_or(HolochainError::ErrorGeneric(
             "Context not initialized".to_string(),
         ))?;
@@ -75,7 +78,7 @@ pub async fn call_zome_function(
 
         // Validate the call
         // 1. make sure the zome and function exists
-        let _ = dna
+        let fn_declaration = dna
             .get_function_with_zome_name(&zome_call.zome_name, &zome_call.fn_name)
             .map_err(HolochainError::Dna)?;
 
@@ -87,7 +90,9 @@ pub async fn call_zome_function(
         let public = zome.is_fn_public(&zome_call.fn_name);
 
         if !public && !check_capability(context.clone(), &zome_call.clone()) {
-            return Err(HolochainError::CapabilityCheckFailed);
+            let denied = Err(HolochainError::CapabilityCheckFailed);
+            audit(context, &dna.name, &zome_call, &denied);
+            return denied;
         }
 
         // 3. read data needed to execute the zome function
@@ -98,7 +103,14 @@ pub async fn call_zome_function(
             .code
             .clone();
 
-        (dna_name, code)
+        (dna_name, code, fn_declaration.cacheable)
+    };
+
+    // Only functions declared as cacheable may be answered from the zome call cache
+    let cache = if cacheable {
+        context.zome_call_cache.clone()
+    } else {
+        None
     };
 
     // Clone context and call data for the Ribosome thread
@@ -114,16 +126,28 @@ pub async fn call_zome_function(
         .expect("action channel to be open");
 
     let _ = thread::spawn(move || {
-        // Have Ribosome spin up DNA and call the zome function
-        let call_result = ribosome::run_dna(
-            code,
-            Some(zome_call_clone.clone().parameters.into_bytes()),
-            WasmCallData::new_zome_call(
-                context_clone.clone(),
-                dna_name.clone(),
-                zome_call_clone.clone(),
-            ),
-        );
+        let cached_result = cache.as_ref().and_then(|cache| cache.get(&zome_call_clone));
+        let call_result = cached_result.unwrap_or_else(|| {
+            let chain_top_before = chain_top(&context_clone);
+            // Have Ribosome spin up DNA and call the zome function
+            let call_result = ribosome::run_dna(
+                code,
+                Some(zome_call_clone.clone().parameters.into_bytes()),
+                WasmCallData::new_zome_call(
+                    context_clone.clone(),
+                    dna_name.clone(),
+                    zome_call_clone.clone(),
+                ),
+            );
+            if let Some(ref cache) = cache {
+                // A call that committed something is not read-only and must not be cached
+                if chain_top(&context_clone) == chain_top_before {
+                    cache.insert(&zome_call_clone, &call_result);
+                }
+            }
+            call_result
+        });
+        audit(&context_clone, &dna_name, &zome_call_clone, &call_result);
         // Construct response
         let response = ExecuteZomeFnResponse::new(zome_call_clone, call_result);
         // Send ReturnZomeFunctionResult Action
@@ -141,6 +165,26 @@ pub async fn call_zome_function(
     })
 }
 
+/// Records the call in the audit log of the context, if it has one.
+fn audit(context: &Arc<Context>, dna_name: &str, zome_call: &ZomeFnCall, result: &ZomeFnResult) {
+    if let Some(ref audit_log) = context.zome_call_audit_log {
+        if let Err(err) = audit_log.record(dna_name, zome_call, result) {
+            context.log(format!(
+                "err/nucleus: Could not write zome call audit record: {}",
+                err
+            ));
+        }
+    }
+}
+
+/// Address of the newest header in the agent's source chain.
+fn chain_top(context: &Arc<Context>) -> Option<Address> {
+    context
+        .state()
+        .and_then(|state| state.agent().top_chain_header())
+        .map(|header| header.address())
+}
+
 // TODO: check the signature too
 fn is_token_the_agent(context: Arc<Context>, cap: &Option<CapabilityCall>) -> bool {
     match cap {
@@ -166,7 +210,17 @@ fn check_capability(context: Arc<Context>, fn_call: &ZomeFnCall) -> bool {
                 Some(content) => CapTokenGrant::try_from(content).unwrap(),
                 None => return false,
             };
-            grant.verify(call.cap_token.clone(), call.caller, &call.signature)
+            let agent = context.state().unwrap().agent();
+            if context.revocations.is_revoked(&agent, &call.cap_token) {
+                return false;
+            }
+            grant.verify(
+                call.cap_token.clone(),
+                call.caller,
+                &call.signature,
+                &fn_call.zome_name,
+                &fn_call.fn_name,
+            )
         }
     }
 }
@@ -201,9 +255,17 @@ impl Future for CallResultFuture {
 
 #[cfg(test)]
 pub mod tests {
-    use super::is_token_the_agent;
-    use crate::instance::tests::test_instance_and_context;
-    use holochain_core_types::{cas::content::Address, dna::capabilities::CapabilityCall};
+    use super::{check_capability, is_token_the_agent};
+    use crate::{
+        instance::tests::test_instance_and_context,
+        nucleus::ZomeFnCall,
+        workflows::capability_grant::{mint_capability, revoke_capability},
+    };
+    use holochain_core_types::{
+        cas::content::Address,
+        dna::capabilities::{CapabilityCall, CapabilityType},
+        entry::cap_entries::{CapTokenGrant, GrantedFunction},
+    };
 
     #[test]
     fn test_agent_as_token() {
@@ -216,4 +278,49 @@ pub mod tests {
         let cap_call = CapabilityCall::new(Address::from(""), None);
         assert!(!is_token_the_agent(context, &Some(cap_call)));
     }
+
+    #[test]
+    fn test_revoked_capability_gets_rejected() {
+        let dna = test_utils::create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (_, context) =
+            test_instance_and_context(dna, None).expect("Could not initialize test instance");
+
+        let grant = CapTokenGrant::create(CapabilityType::Transferable, None).unwrap();
+        let token = context
+            .block_on(mint_capability(grant, &context))
+            .expect("Could not mint capability");
+        let cap_call = CapabilityCall::new(token.clone(), Some(Address::from("any caller")));
+        let zome_call = ZomeFnCall::new("test_zome", Some(cap_call), "test", "{}");
+        assert!(check_capability(context.clone(), &zome_call));
+
+        context
+            .block_on(revoke_capability(&token, &context))
+            .expect("Could not revoke capability");
+        assert!(!check_capability(context.clone(), &zome_call));
+
+        // only grants can be revoked
+        assert!(context
+            .block_on(revoke_capability(&Address::from("unknown token"), &context))
+            .is_err());
+    }
+
+    #[test]
+    fn test_calls_outside_the_grant_get_rejected() {
+        let dna = test_utils::create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (_, context) =
+            test_instance_and_context(dna, None).expect("Could not initialize test instance");
+
+        let grant = CapTokenGrant::create(CapabilityType::Transferable, None)
+            .unwrap()
+            .with_functions(vec![GrantedFunction::new("test_zome", "public_test_fn")]);
+        let token = context
+            .block_on(mint_capability(grant, &context))
+            .expect("Could not mint capability");
+        let cap_call = CapabilityCall::new(token, Some(Address::from("any caller")));
+
+        let granted = ZomeFnCall::new("test_zome", Some(cap_call.clone()), "public_test_fn", "{}");
+        assert!(check_capability(context.clone(), &granted));
+        let not_granted = ZomeFnCall::new("test_zome", Some(cap_call), "test", "{}");
+        assert!(!check_capability(context.clone(), &not_granted));
+    }
 }
@@ -1,6 +1,9 @@
 use crate::context::Context;
 use holochain_core_types::{
-    cas::{content::Address, storage::ContentAddressableStorage},
+    cas::{
+        content::{Address, Content},
+        storage::ContentAddressableStorage,
+    },
     crud_status::CrudStatus,
     eav::{Attribute, EaviQuery, EntityAttributeValueIndex, IndexFilter},
     entry::{Entry, EntryWithMeta},
@@ -109,6 +112,28 @@ pub(crate) fn get_entry_crud_meta_from_dht(
     Ok(Some((crud_status, maybe_crud_link)))
 }
 
+/// Returns the content stored under the given address in the agent's source chain
+/// or the local DHT shard as is, without deserializing it into an Entry.
+/// This also works for content that this version can't parse,
+/// for instance entries of a newer DNA version.
+pub fn get_raw_content(
+    context: &Arc<Context>,
+    address: &Address,
+) -> Result<Option<Content>, HolochainError> {
+    let chain_cas = context
+        .state()
+        .unwrap()
+        .agent()
+        .chain_store()
+        .content_storage();
+    if let Some(content) = (*chain_cas.read().unwrap()).fetch(address)? {
+        return Ok(Some(content));
+    }
+    let dht_cas = context.state().unwrap().dht().content_storage();
+    let content = (*dht_cas.read().unwrap()).fetch(address)?;
+    Ok(content)
+}
+
 /// FetchEntry Action Creator
 ///
 /// Returns a future that resolves to an Ok(ActionWrapper) or an Err(error_message:String).
@@ -137,7 +162,11 @@ pub fn get_entry_with_meta<'a>(
 #[cfg(test)]
 pub mod tests {
     use crate::instance::tests::test_context_with_state;
-    use holochain_core_types::{cas::content::AddressableContent, entry::test_entry};
+    use holochain_core_types::{
+        cas::content::{AddressableContent, ExampleAddressableContent},
+        entry::test_entry,
+        json::{JsonString, RawString},
+    };
 
     #[test]
     fn get_entry_from_dht_cas() {
@@ -150,4 +179,39 @@ pub mod tests {
         let result = super::get_entry_from_dht(&context, &entry.address());
         assert_eq!(Ok(Some(entry.clone())), result);
     }
+
+    #[test]
+    fn get_raw_content_without_parsing() {
+        let entry = test_entry();
+        let context = test_context_with_state(None);
+        assert_eq!(Ok(None), super::get_raw_content(&context, &entry.address()));
+
+        let chain_storage = &context
+            .state()
+            .unwrap()
+            .agent()
+            .chain_store()
+            .content_storage();
+        (*chain_storage.write().unwrap()).add(&entry).unwrap();
+        assert_eq!(
+            Ok(Some(entry.content())),
+            super::get_raw_content(&context, &entry.address())
+        );
+
+        // Content that is not an Entry gets returned as stored
+        let unparseable = ExampleAddressableContent::try_from_content(&JsonString::from(
+            RawString::from("not an entry"),
+        ))
+        .unwrap();
+        let dht_storage = &context.state().unwrap().dht().content_storage().clone();
+        (*dht_storage.write().unwrap()).add(&unparseable).unwrap();
+        assert_eq!(
+            Ok(None),
+            super::get_entry_from_dht(&context, &unparseable.address())
+        );
+        assert_eq!(
+            Ok(Some(unparseable.content())),
+            super::get_raw_content(&context, &unparseable.address())
+        );
+    }
 }
@@ -12,7 +12,7 @@ use holochain_core_types::{
     cas::content::AddressableContent,
     chain_header::ChainHeader,
     entry::{entry_type::EntryType, Entry},
-    error::HolochainError,
+    error::{DnaError, HolochainError},
     hash::HashString,
     validation::ValidationData,
 };
@@ -21,21 +21,20 @@ use holochain_sodium::secbuf::SecBuf;
 use snowflake::{self, ProcessUniqueId};
 use std::{pin::Pin, sync::Arc, thread};
 
-fn check_entry_type(entry_type: EntryType, context: &Arc<Context>) -> Result<(), HolochainError> {
+/// Checks that the entry type is an app entry type defined in the DNA or a system entry
+/// type that can be validated. App entry types the DNA does not define fail with a
+/// DnaError::EntryTypeNotFound naming the type.
+pub(crate) fn check_entry_type(
+    entry_type: EntryType,
+    context: &Arc<Context>,
+) -> Result<(), HolochainError> {
     match entry_type {
         EntryType::App(app_entry_type) => {
-            // Check if app_entry_type is defined in DNA
-            let _ = context
-                .state()
-                .unwrap()
-                .nucleus()
-                .dna()
-                .unwrap()
-                .get_zome_name_for_app_entry_type(&app_entry_type)
-                .ok_or(HolochainError::ValidationFailed(format!(
-                    "Attempted to validate unknown app entry type {:?}",
-                    app_entry_type,
-                )))?;
+            let entry_type = String::from(app_entry_type);
+            let dna = context.get_dna().ok_or(HolochainError::DnaMissing)?;
+            if dna.get_entry_type_def(&entry_type).is_none() {
+                return Err(HolochainError::Dna(DnaError::EntryTypeNotFound(entry_type)));
+            }
         }
 
         EntryType::LinkAdd => {}
@@ -63,6 +62,8 @@ fn spawn_validation_ribosome(
 ) {
     thread::spawn(move || {
         let address = entry.address();
+        // Queue here if too many validations are running already
+        let _permit = context.validation_limiter.acquire();
         let maybe_validation_result = callback::validate_entry::validate_entry(
             entry.clone(),
             validation_data.clone(),
@@ -201,3 +202,83 @@ impl Future for ValidationFuture {
         }
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::nucleus::{
+        actions::{build_validation_package::build_validation_package, tests::*},
+        validation_limiter::ValidationLimiter,
+    };
+    use holochain_core_types::{
+        json::JsonString,
+        validation::{EntryAction, EntryLifecycle},
+    };
+    use std::{
+        sync::mpsc::channel,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn concurrent_validations_through_the_context_are_bounded() {
+        let netname = Some("concurrent_validations_through_the_context_are_bounded");
+        let (_instance, context) = instance(netname);
+        let mut limited_context = (*context).clone();
+        limited_context.validation_limiter = ValidationLimiter::new(Some(2));
+        let context = Arc::new(limited_context);
+
+        let validations: Vec<(Entry, ValidationData)> = (0..4)
+            .map(|index| {
+                let entry = Entry::App(
+                    "package_entry".into(),
+                    JsonString::from(format!("{{\"stuff\":\"{}\"}}", index)),
+                );
+                commit(entry.clone(), &context);
+                let package = context
+                    .block_on(build_validation_package(&entry, context.clone()))
+                    .expect("Could not build validation package");
+                let validation_data = ValidationData {
+                    package,
+                    lifecycle: EntryLifecycle::Chain,
+                    action: EntryAction::Create,
+                };
+                (entry, validation_data)
+            })
+            .collect();
+
+        // take up both slots so none of the validations can run yet
+        let permits = vec![
+            context.validation_limiter.acquire(),
+            context.validation_limiter.acquire(),
+        ];
+        let (result_tx, result_rx) = channel();
+        let threads: Vec<_> = validations
+            .into_iter()
+            .map(|(entry, validation_data)| {
+                let context = context.clone();
+                let result_tx = result_tx.clone();
+                thread::spawn(move || {
+                    let result = context.block_on(validate_entry(entry, validation_data, &context));
+                    result_tx.send(result).unwrap();
+                })
+            })
+            .collect();
+        assert!(result_rx.recv_timeout(Duration::from_millis(300)).is_err());
+
+        drop(permits);
+        let mut results = Vec::new();
+        let start = Instant::now();
+        while results.len() < threads.len() && start.elapsed() < Duration::from_secs(10) {
+            assert!(context.validation_limiter.running() <= 2);
+            if let Ok(result) = result_rx.recv_timeout(Duration::from_millis(1)) {
+                results.push(result);
+            }
+        }
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(results.len(), 4);
+        assert!(results.iter().all(|result| result.is_ok()));
+        assert_eq!(context.validation_limiter.running(), 0);
+    }
+}
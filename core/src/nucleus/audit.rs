@@ -0,0 +1,351 @@
+//! Optional audit trail of the zome calls an instance executes.
+//! If the context has a ZomeCallAuditLog set, one ZomeCallAuditRecord gets appended to its
+//! AuditSink for every zome function the Ribosome ran, successful or not, and for every
+//! call that got denied by the capability check.
+
+use crate::nucleus::{ZomeFnCall, ZomeFnResult};
+use holochain_core_types::{
+    cas::content::{Address, AddressableContent},
+    error::HolochainError,
+    json::JsonString,
+    time::Iso8601,
+};
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ZomeCallOutcome {
+    Success,
+    Failure(String),
+}
+
+/// Arguments of an audited call. Only their hash gets recorded unless
+/// the audit log was configured to store raw arguments.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AuditedArguments {
+    Raw(JsonString),
+    Hashed(Address),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ZomeCallAuditRecord {
+    pub timestamp: Iso8601,
+    pub dna_name: String,
+    pub zome_name: String,
+    pub fn_name: String,
+    /// caller as given in the capability call, None for calls without capability
+    pub caller: Option<Address>,
+    pub arguments: AuditedArguments,
+    pub outcome: ZomeCallOutcome,
+}
+
+/// Destination of audit records.
+pub trait AuditSink: Send + Sync {
+    fn append(&self, record: ZomeCallAuditRecord) -> Result<(), HolochainError>;
+}
+
+/// Keeps records in memory. Clones share the same records.
+#[derive(Clone, Default)]
+pub struct MemoryAuditSink {
+    records: Arc<Mutex<Vec<ZomeCallAuditRecord>>>,
+}
+
+impl MemoryAuditSink {
+    pub fn new() -> Self {
+        MemoryAuditSink::default()
+    }
+
+    pub fn records(&self) -> Vec<ZomeCallAuditRecord> {
+        self.records.lock().unwrap().clone()
+    }
+}
+
+impl AuditSink for MemoryAuditSink {
+    fn append(&self, record: ZomeCallAuditRecord) -> Result<(), HolochainError> {
+        self.records.lock().unwrap().push(record);
+        Ok(())
+    }
+}
+
+/// Appends records as JSON, one per line, to the given file.
+/// The file gets created if it does not exist.
+pub struct FileAuditSink {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileAuditSink {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        FileAuditSink {
+            path: path.as_ref().into(),
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+impl AuditSink for FileAuditSink {
+    fn append(&self, record: ZomeCallAuditRecord) -> Result<(), HolochainError> {
+        let line = serde_json::to_string(&record)?;
+        let _guard = self.lock.lock().unwrap();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+}
+
+/// Turns executed zome calls into audit records and hands them to the sink.
+#[derive(Clone)]
+pub struct ZomeCallAuditLog {
+    sink: Arc<AuditSink>,
+    raw_arguments: bool,
+}
+
+impl ZomeCallAuditLog {
+    /// Records only the hashes of call arguments.
+    pub fn new(sink: Arc<AuditSink>) -> Self {
+        ZomeCallAuditLog {
+            sink,
+            raw_arguments: false,
+        }
+    }
+
+    /// Records call arguments as they were passed instead of their hashes.
+    pub fn with_raw_arguments(mut self) -> Self {
+        self.raw_arguments = true;
+        self
+    }
+
+    pub fn record(
+        &self,
+        dna_name: &str,
+        zome_call: &ZomeFnCall,
+        result: &ZomeFnResult,
+    ) -> Result<(), HolochainError> {
+        let duration_since_epoch = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("System time must not be before UNIX EPOCH");
+        let arguments = if self.raw_arguments {
+            AuditedArguments::Raw(zome_call.parameters.clone())
+        } else {
+            AuditedArguments::Hashed(zome_call.parameters.address())
+        };
+        self.sink.append(ZomeCallAuditRecord {
+            timestamp: Iso8601::from(duration_since_epoch.as_secs()),
+            dna_name: dna_name.to_string(),
+            zome_name: zome_call.zome_name.clone(),
+            fn_name: zome_call.fn_name.clone(),
+            caller: zome_call.cap.as_ref().and_then(|cap| cap.caller.clone()),
+            arguments,
+            outcome: outcome(result),
+        })
+    }
+}
+
+/// Zome functions return their errors as a JSON object with a single `Err` field
+/// inside an `Ok` result, so such results are failures, too.
+fn outcome(result: &ZomeFnResult) -> ZomeCallOutcome {
+    let json = match result {
+        Ok(json) => String::from(json.to_owned()),
+        Err(err) => return ZomeCallOutcome::Failure(err.to_string()),
+    };
+    match serde_json::from_str(&json) {
+        Ok(serde_json::Value::Object(ref fields)) if fields.len() == 1 => match fields.get("Err") {
+            Some(serde_json::Value::String(message)) => ZomeCallOutcome::Failure(message.clone()),
+            Some(error) => ZomeCallOutcome::Failure(error.to_string()),
+            None => ZomeCallOutcome::Success,
+        },
+        _ => ZomeCallOutcome::Success,
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use self::tempfile::tempdir;
+    use super::*;
+    use crate::{
+        context::Context, instance::tests::test_instance_and_context,
+        nucleus::actions::call_zome_function::call_zome_function,
+    };
+    use holochain_core_types::dna::{capabilities::CapabilityCall, Dna};
+    use std::fs;
+    use tempfile;
+
+    /// Zome with a public function that returns an error inside an Ok result
+    fn failing_zome_wat() -> String {
+        r#"
+(module
+
+    (memory 1)
+    (export "memory" (memory 0))
+
+    (func
+        (export "__hdk_validate_app_entry")
+        (param $allocation i64)
+        (result i64)
+
+        (i64.const 0)
+    )
+
+    (func
+        (export "__hdk_validate_link")
+        (param $allocation i64)
+        (result i64)
+
+        (i64.const 0)
+    )
+
+    (func
+        (export "__list_traits")
+        (param $allocation i64)
+        (result i64)
+
+        (i64.const 0)
+    )
+
+    (func
+        (export "__list_functions")
+        (param $allocation i64)
+        (result i64)
+
+        (i64.const 0)
+    )
+
+    (func
+        (export "public_test_fn")
+        (param $allocation i64)
+        (result i64)
+
+        ;; This writes {"Err":"no"} into memory
+        (i64.store (i32.const 0) (i64.const 123))
+        (i64.store (i32.const 1) (i64.const 34))
+        (i64.store (i32.const 2) (i64.const 69))
+        (i64.store (i32.const 3) (i64.const 114))
+        (i64.store (i32.const 4) (i64.const 114))
+        (i64.store (i32.const 5) (i64.const 34))
+        (i64.store (i32.const 6) (i64.const 58))
+        (i64.store (i32.const 7) (i64.const 34))
+        (i64.store (i32.const 8) (i64.const 110))
+        (i64.store (i32.const 9) (i64.const 111))
+        (i64.store (i32.const 10) (i64.const 34))
+        (i64.store (i32.const 11) (i64.const 125))
+
+        (i64.const 12)
+    )
+)
+                "#
+        .to_string()
+    }
+
+    /// Context of a test instance of the given DNA that audits to the returned sink
+    fn audited_context(dna: Dna) -> (Arc<Context>, MemoryAuditSink) {
+        let (_, context) =
+            test_instance_and_context(dna, None).expect("Could not initialize test instance");
+        let sink = MemoryAuditSink::new();
+        let mut audited_context = (*context).clone();
+        audited_context.zome_call_audit_log = Some(ZomeCallAuditLog::new(Arc::new(sink.clone())));
+        (Arc::new(audited_context), sink)
+    }
+
+    #[test]
+    fn records_successful_and_denied_calls() {
+        let mut dna = test_utils::create_test_dna_with_wat("test_zome", "test_cap", None);
+        // not public, so the unknown token below does not get it called
+        dna.zomes.get_mut("test_zome").unwrap().add_fn_declaration(
+            String::from("restricted_fn"),
+            vec![],
+            vec![],
+        );
+        let (context, sink) = audited_context(dna);
+
+        let caller = Address::from("caller");
+        let cap = CapabilityCall::new(Address::from("unknown token"), Some(caller.clone()));
+        let public_call = ZomeFnCall::new("test_zome", Some(cap.clone()), "public_test_fn", "{}");
+        assert!(context
+            .block_on(call_zome_function(public_call.clone(), &context))
+            .is_ok());
+        let denied_call = ZomeFnCall::new("test_zome", Some(cap), "restricted_fn", "{}");
+        assert_eq!(
+            context.block_on(call_zome_function(denied_call, &context)),
+            Err(HolochainError::CapabilityCheckFailed)
+        );
+
+        let records = sink.records();
+        assert_eq!(records.len(), 2);
+
+        assert_eq!(records[0].zome_name, "test_zome");
+        assert_eq!(records[0].fn_name, "public_test_fn");
+        assert_eq!(records[0].caller, Some(caller.clone()));
+        assert_eq!(records[0].outcome, ZomeCallOutcome::Success);
+        assert_eq!(
+            records[0].arguments,
+            AuditedArguments::Hashed(public_call.parameters.address())
+        );
+
+        assert_eq!(records[1].fn_name, "restricted_fn");
+        assert_eq!(records[1].caller, Some(caller));
+        assert_eq!(
+            records[1].outcome,
+            ZomeCallOutcome::Failure(HolochainError::CapabilityCheckFailed.to_string())
+        );
+    }
+
+    #[test]
+    fn records_errors_returned_by_the_zome_as_failures() {
+        let dna = test_utils::create_test_dna_with_wat(
+            "test_zome",
+            "test_cap",
+            Some(&failing_zome_wat()),
+        );
+        let (context, sink) = audited_context(dna);
+
+        let call = ZomeFnCall::new("test_zome", None, "public_test_fn", "{}");
+        assert_eq!(
+            context.block_on(call_zome_function(call, &context)),
+            Ok(JsonString::from("{\"Err\":\"no\"}"))
+        );
+
+        let records = sink.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0].outcome,
+            ZomeCallOutcome::Failure("no".to_string())
+        );
+    }
+
+    #[test]
+    fn file_sink_appends_json_lines() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let audit_log =
+            ZomeCallAuditLog::new(Arc::new(FileAuditSink::new(&path))).with_raw_arguments();
+
+        let call = ZomeFnCall::new("blog", None, "get_post", "{\"address\":\"Qm\"}");
+        audit_log
+            .record("test dna", &call, &Ok(JsonString::null()))
+            .unwrap();
+        audit_log
+            .record("test dna", &call, &Err(HolochainError::Timeout))
+            .unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let records: Vec<ZomeCallAuditRecord> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].arguments, AuditedArguments::Raw(call.parameters));
+        assert_eq!(records[0].outcome, ZomeCallOutcome::Success);
+        assert_eq!(
+            records[1].outcome,
+            ZomeCallOutcome::Failure("timeout".to_string())
+        );
+    }
+}
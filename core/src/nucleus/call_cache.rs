@@ -0,0 +1,181 @@
+//! Optional memoization of zome call results.
+//! If the context has a ZomeCallCache set, results of zome functions that are declared
+//! as cacheable in the DNA get stored under their zome name, function name, arguments and
+//! capability call, so a result is only served to callers with the same token and caller.
+//! Calls with the same inputs get the stored result back without running the Ribosome
+//! until the entry expires or gets pushed out as the least recently used one.
+//! Only successful calls that did not add anything to the source chain get stored,
+//! so functions that commit never get served from the cache.
+
+use crate::{
+    lru_cache::LruCache,
+    nucleus::{ZomeFnCall, ZomeFnResult},
+};
+use holochain_core_types::{dna::capabilities::CapabilityCall, json::JsonString};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct CacheKey {
+    zome_name: String,
+    cap: Option<CapabilityCall>,
+    fn_name: String,
+    parameters: JsonString,
+}
+
+impl<'a> From<&'a ZomeFnCall> for CacheKey {
+    fn from(zome_call: &'a ZomeFnCall) -> Self {
+        CacheKey {
+            zome_name: zome_call.zome_name.clone(),
+            cap: zome_call.cap.clone(),
+            fn_name: zome_call.fn_name.clone(),
+            parameters: zome_call.parameters.clone(),
+        }
+    }
+}
+
+struct CacheContent {
+    results: LruCache<CacheKey, (Instant, JsonString)>,
+    hits: usize,
+    misses: usize,
+}
+
+/// Bounded cache of zome call results. Clones share the same content.
+#[derive(Clone)]
+pub struct ZomeCallCache {
+    ttl: Duration,
+    content: Arc<Mutex<CacheContent>>,
+}
+
+impl ZomeCallCache {
+    /// Creates a cache that keeps at most `max_entries` results, each for at most `ttl`.
+    pub fn new(max_entries: usize, ttl: Duration) -> Self {
+        ZomeCallCache {
+            ttl,
+            content: Arc::new(Mutex::new(CacheContent {
+                results: LruCache::new(max_entries),
+                hits: 0,
+                misses: 0,
+            })),
+        }
+    }
+
+    /// Returns the stored result for a call with the same zome, function, arguments and
+    /// capability call if there is one that did not expire yet.
+    pub fn get(&self, zome_call: &ZomeFnCall) -> Option<ZomeFnResult> {
+        let key = CacheKey::from(zome_call);
+        let mut content = self.content.lock().unwrap();
+        let cached = match content.results.get(&key) {
+            Some((stored_at, result)) if stored_at.elapsed() < self.ttl => Some(result.clone()),
+            _ => None,
+        };
+        match cached {
+            Some(result) => {
+                content.hits += 1;
+                Some(Ok(result))
+            }
+            None => {
+                content.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Stores the result of the given call. Errors are not stored.
+    /// Drops the least recently used results if the cache is full.
+    pub fn insert(&self, zome_call: &ZomeFnCall, result: &ZomeFnResult) {
+        let result = match result {
+            Ok(result) => result.clone(),
+            Err(_) => return,
+        };
+        let mut content = self.content.lock().unwrap();
+        content
+            .results
+            .insert(CacheKey::from(zome_call), (Instant::now(), result));
+    }
+
+    /// Number of stored results, including expired ones that were not pushed out yet.
+    pub fn len(&self) -> usize {
+        self.content.lock().unwrap().results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of lookups that got answered from the cache.
+    pub fn hits(&self) -> usize {
+        self.content.lock().unwrap().hits
+    }
+
+    /// Number of lookups that found nothing, i.e. calls that had to run the Ribosome.
+    pub fn misses(&self) -> usize {
+        self.content.lock().unwrap().misses
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core_types::{cas::content::Address, error::HolochainError};
+    use std::thread;
+
+    #[test]
+    fn keeps_at_most_max_entries() {
+        let cache = ZomeCallCache::new(2, Duration::from_secs(60));
+        let calls: Vec<ZomeFnCall> = (0..3)
+            .map(|i| ZomeFnCall::new("blog", None, "get_post", format!("{{\"id\":{}}}", i)))
+            .collect();
+        for call in calls.iter() {
+            cache.insert(call, &Ok(call.parameters.clone()));
+        }
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&calls[0]), None);
+        assert_eq!(cache.get(&calls[2]), Some(Ok(calls[2].parameters.clone())));
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(cache.misses(), 1);
+    }
+
+    #[test]
+    fn results_expire_and_errors_are_not_stored() {
+        let cache = ZomeCallCache::new(10, Duration::from_millis(50));
+        let call = ZomeFnCall::new("blog", None, "get_post", "{}");
+        cache.insert(&call, &Err(HolochainError::Timeout));
+        assert_eq!(cache.get(&call), None);
+
+        cache.insert(&call, &Ok(JsonString::null()));
+        assert_eq!(cache.get(&call), Some(Ok(JsonString::null())));
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(cache.get(&call), None);
+    }
+
+    #[test]
+    fn results_are_not_shared_between_capabilities() {
+        let cache = ZomeCallCache::new(10, Duration::from_secs(60));
+        let cap_call = |caller: &str| {
+            ZomeFnCall::new(
+                "blog",
+                Some(CapabilityCall::new(
+                    Address::from("token"),
+                    Some(Address::from(caller)),
+                )),
+                "get_post",
+                "{}",
+            )
+        };
+        cache.insert(&cap_call("alice"), &Ok(JsonString::from("alice's result")));
+
+        assert_eq!(
+            cache.get(&cap_call("alice")),
+            Some(Ok(JsonString::from("alice's result")))
+        );
+        assert_eq!(cache.get(&cap_call("bob")), None);
+        assert_eq!(
+            cache.get(&ZomeFnCall::new("blog", None, "get_post", "{}")),
+            None
+        );
+    }
+}
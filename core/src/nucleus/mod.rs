@@ -1,9 +1,14 @@
 /// Nucleus is the module that handles DNA, including the Ribosome.
 ///
 pub mod actions;
+pub mod audit;
+pub mod call_cache;
+pub mod profiler;
 pub mod reducers;
 pub mod ribosome;
 pub mod state;
+pub mod validation_cache;
+pub mod validation_limiter;
 
 use holochain_core_types::{
     cas::content::Address, dna::capabilities::CapabilityCall, error::HcResult, json::JsonString,
@@ -68,11 +73,13 @@ pub mod tests {
             Instance,
         },
         nucleus::{
+            call_cache::ZomeCallCache,
             call_zome_function,
             state::{NucleusState, NucleusStatus},
         },
     };
     use holochain_core_types::dna::capabilities::CapabilityCall;
+    use std::{sync::Arc, time::Duration};
     use test_utils;
 
     use holochain_core_types::{
@@ -188,6 +195,47 @@ pub mod tests {
         assert_eq!(JsonString::from(RawString::from(1337)), result.unwrap());
     }
 
+    #[test]
+    /// tests that identical calls to a cacheable function run the Ribosome only once
+    fn test_call_zome_function_cached() {
+        let mut dna = test_utils::create_test_dna_with_wat("test_zome", "test_cap", None);
+        dna.zomes
+            .get_mut("test_zome")
+            .unwrap()
+            .fn_declarations
+            .iter_mut()
+            .find(|fn_declaration| fn_declaration.name == "public_test_fn")
+            .expect("test zome should declare public_test_fn")
+            .cacheable = true;
+        let (_, context) =
+            test_instance_and_context(dna, None).expect("Could not initialize test instance");
+        let cache = ZomeCallCache::new(10, Duration::from_secs(60));
+        let mut cached_context = (*context).clone();
+        cached_context.zome_call_cache = Some(cache.clone());
+        let context = Arc::new(cached_context);
+
+        let call = |parameters: &str| {
+            let zome_call = ZomeFnCall::new(
+                "test_zome",
+                Some(test_capability_call()),
+                "public_test_fn",
+                parameters,
+            );
+            context
+                .block_on(call_zome_function(zome_call, &context))
+                .expect("zome call should succeed")
+        };
+
+        let expected = JsonString::from(RawString::from(1337));
+        assert_eq!(call("{}"), expected);
+        assert_eq!(call("{}"), expected);
+        assert_eq!((cache.misses(), cache.hits()), (1, 1));
+
+        assert_eq!(call("{\"other\":\"args\"}"), expected);
+        assert_eq!((cache.misses(), cache.hits()), (2, 1));
+        assert_eq!(cache.len(), 2);
+    }
+
     #[test]
     /// tests that calling an invalid DNA returns the correct error
     fn call_ribosome_wrong_dna() {
@@ -0,0 +1,67 @@
+//! Optional profiling of zome calls.
+//! If the context has a ZomeCallProfiler set, the Ribosome reports the fuel every zome call
+//! consumed, per zome and function. Fuel is counted in units of host calls weighted by
+//! ZomeApiFunction::fuel_cost(), since the wasm interpreter does not expose the number of
+//! executed instructions. It tells which functions are expensive to run for the host,
+//! not how much pure wasm computation they do.
+
+use crate::nucleus::ZomeFnCall;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+/// Fuel consumed by the calls of one zome function.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FunctionProfile {
+    pub calls: u64,
+    pub total_fuel: u64,
+    /// fuel of the most recent call
+    pub last_fuel: u64,
+}
+
+/// Collects the fuel consumed by zome calls. Clones share the same profiles.
+#[derive(Clone, Default)]
+pub struct ZomeCallProfiler {
+    /// (zome name, function name) -> profile
+    profiles: Arc<Mutex<BTreeMap<(String, String), FunctionProfile>>>,
+}
+
+impl ZomeCallProfiler {
+    pub fn new() -> Self {
+        ZomeCallProfiler::default()
+    }
+
+    /// Adds a finished call of the given zome function that consumed `fuel` units.
+    pub fn record(&self, zome_call: &ZomeFnCall, fuel: u64) {
+        let mut profiles = self.profiles.lock().unwrap();
+        let profile = profiles
+            .entry((zome_call.zome_name.clone(), zome_call.fn_name.clone()))
+            .or_insert_with(FunctionProfile::default);
+        profile.calls += 1;
+        profile.total_fuel += fuel;
+        profile.last_fuel = fuel;
+    }
+
+    /// Profile of the given zome function, None if it was not called yet.
+    pub fn profile(&self, zome_name: &str, fn_name: &str) -> Option<FunctionProfile> {
+        self.profiles
+            .lock()
+            .unwrap()
+            .get(&(zome_name.to_string(), fn_name.to_string()))
+            .cloned()
+    }
+
+    /// Profiles of all functions called so far as (zome name, function name, profile),
+    /// sorted by zome and function name.
+    pub fn profiles(&self) -> Vec<(String, String, FunctionProfile)> {
+        self.profiles
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|((zome_name, fn_name), profile)| {
+                (zome_name.clone(), fn_name.clone(), profile.clone())
+            })
+            .collect()
+    }
+}
@@ -27,7 +27,7 @@ impl ZomeFnCall {
 pub fn invoke_call(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
     let zome_call_data = runtime.zome_call_data()?;
     // deserialize args
-    let args_str = runtime.load_json_string_from_args(&args);
+    let args_str = runtime.load_json_string_from_args(&args)?;
 
     let input = match ZomeFnCallArgs::try_from(args_str.clone()) {
         Ok(input) => input,
@@ -234,6 +234,7 @@ pub mod tests {
             name: test_function_name(),
             inputs: Vec::new(),
             outputs: Vec::new(),
+            cacheable: false,
         };
         trait_fns.functions = vec![fn_decl.name.clone()];
         let mut traits = BTreeMap::new();
@@ -0,0 +1,171 @@
+use crate::nucleus::ribosome::{api::ZomeApiResult, Runtime};
+use holochain_core_types::error::HolochainError;
+use wasmi::RuntimeArgs;
+
+/// ZomeApiFunction::ChainLength function code
+/// args: [0] encoded MemoryAllocation as u64
+/// Expected argument: none
+/// Returns an HcApiReturnCode as I64
+pub fn invoke_chain_length(runtime: &mut Runtime, _args: &RuntimeArgs) -> ZomeApiResult {
+    let zome_call_data = runtime.zome_call_data()?;
+    // Commits made earlier in this zome call are already reduced into the agent state
+    // because invoke_commit_app_entry blocks until they are.
+    let result = zome_call_data
+        .context
+        .state()
+        .map(|state| state.agent().iter_chain().count() as u64)
+        .ok_or_else(|| {
+            HolochainError::ErrorGeneric("Context not initialized with a state".to_string())
+        });
+
+    runtime.store_result(result)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use self::wabt::Wat2Wasm;
+    use crate::{
+        instance::tests::test_instance_and_context,
+        nucleus::{
+            ribosome::{
+                api::{
+                    commit::tests::test_commit_args_bytes,
+                    tests::{test_zome_api_function, test_zome_api_function_call, test_zome_name},
+                    ZomeApiFunction,
+                },
+                Defn,
+            },
+            tests::test_capability_name,
+        },
+    };
+    use holochain_core_types::json::JsonString;
+    use test_utils;
+    use wabt;
+
+    /// wasm that commits the entry it gets passed and then returns the chain length,
+    /// all within a single zome call
+    fn commit_then_chain_length_wasm() -> Vec<u8> {
+        Wat2Wasm::new()
+            .canonicalize_lebs(false)
+            .write_debug_names(true)
+            .convert(
+                r#"
+(module
+    (import "env" "hc_commit_entry"
+        (func $commit_entry
+            (param i64)
+            (result i64)
+        )
+    )
+    (import "env" "hc_chain_length"
+        (func $chain_length
+            (param i64)
+            (result i64)
+        )
+    )
+
+    (memory 1)
+    (export "memory" (memory 0))
+
+    (func
+        (export "test")
+            (param $allocation i64)
+            (result i64)
+
+        (drop
+            (call
+                $commit_entry
+                (get_local $allocation)
+            )
+        )
+        (call
+            $chain_length
+            (i64.const 0)
+        )
+    )
+
+    (func
+        (export "__hdk_validate_app_entry")
+        (param $allocation i64)
+        (result i64)
+
+        (i64.const 0)
+    )
+
+    (func
+        (export "__hdk_get_validation_package_for_entry_type")
+        (param $allocation i64)
+        (result i64)
+
+        ;; This writes "Entry" into memory
+        (i64.store (i32.const 0) (i64.const 34))
+        (i64.store (i32.const 1) (i64.const 69))
+        (i64.store (i32.const 2) (i64.const 110))
+        (i64.store (i32.const 3) (i64.const 116))
+        (i64.store (i32.const 4) (i64.const 114))
+        (i64.store (i32.const 5) (i64.const 121))
+        (i64.store (i32.const 6) (i64.const 34))
+
+        (i64.const 7)
+    )
+
+    (func
+        (export "__list_traits")
+        (param $allocation i64)
+        (result i64)
+
+        (i64.const 0)
+    )
+
+    (func
+        (export "__list_functions")
+        (param $allocation i64)
+        (result i64)
+
+        (i64.const 0)
+    )
+)
+                "#,
+            )
+            .unwrap()
+            .as_ref()
+            .to_vec()
+    }
+
+    fn chain_length_result(length: usize) -> JsonString {
+        JsonString::from(format!(r#"{{"ok":true,"value":"{}","error":"null"}}"#, length) + "\u{0}")
+    }
+
+    #[test]
+    fn test_chain_length() {
+        let (call_result, context) =
+            test_zome_api_function(ZomeApiFunction::ChainLength.as_str(), Vec::new());
+
+        let length = context.state().unwrap().agent().iter_chain().count();
+        assert_eq!(call_result, chain_length_result(length));
+    }
+
+    #[test]
+    fn test_chain_length_reflects_commits_of_same_call() {
+        let wasm = commit_then_chain_length_wasm();
+        let dna = test_utils::create_test_dna_with_wasm(
+            &test_zome_name(),
+            &test_capability_name(),
+            wasm.clone(),
+        );
+        let dna_name = dna.name.to_string();
+        let (instance, context) =
+            test_instance_and_context(dna, None).expect("Could not create test instance");
+        let length_before = context.state().unwrap().agent().iter_chain().count();
+
+        let call_result = test_zome_api_function_call(
+            &dna_name,
+            context.clone(),
+            &instance,
+            &wasm,
+            test_commit_args_bytes(),
+        );
+
+        assert_eq!(call_result, chain_length_result(length_before + 1));
+    }
+}
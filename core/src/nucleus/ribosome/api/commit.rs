@@ -1,34 +1,38 @@
 use crate::{
     nucleus::ribosome::{api::ZomeApiResult, Runtime},
-    workflows::author_entry::author_entry,
+    workflows::author_entry::author_entry_with_options,
 };
 use holochain_core_types::{cas::content::Address, entry::Entry, error::HolochainError};
+use holochain_wasm_utils::api_serialization::{CommitEntryArgs, CommitEntryOptions};
 use std::convert::TryFrom;
 use wasmi::{RuntimeArgs, RuntimeValue};
 
 /// ZomeApiFunction::CommitAppEntry function code
 /// args: [0] encoded MemoryAllocation as u64
-/// Expected complex argument: CommitArgs
+/// Expected complex argument: CommitEntryArgs, or an Entry to commit with the default options
 /// Returns an HcApiReturnCode as I64
 pub fn invoke_commit_app_entry(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
     let zome_call_data = runtime.zome_call_data()?;
     // deserialize args
-    let args_str = runtime.load_json_string_from_args(&args);
-    let entry = match Entry::try_from(args_str.clone()) {
-        Ok(entry_input) => entry_input,
-        // Exit on error
-        Err(_) => {
-            zome_call_data.context.log(format!(
-                "err/zome: invoke_commit_app_entry failed to deserialize Entry: {:?}",
-                args_str
-            ));
-            return ribosome_error_code!(ArgumentDeserializationFailed);
-        }
+    let args_str = runtime.load_json_string_from_args(&args)?;
+    let (entry, options) = match CommitEntryArgs::try_from(args_str.clone()) {
+        Ok(commit_entry_args) => (commit_entry_args.entry, commit_entry_args.options),
+        Err(_) => match Entry::try_from(args_str.clone()) {
+            Ok(entry_input) => (entry_input, CommitEntryOptions::default()),
+            // Exit on error
+            Err(_) => {
+                zome_call_data.context.log(format!(
+                    "err/zome: invoke_commit_app_entry failed to deserialize Entry: {:?}",
+                    args_str
+                ));
+                return ribosome_error_code!(ArgumentDeserializationFailed);
+            }
+        },
     };
     // Wait for future to be resolved
-    let task_result: Result<Address, HolochainError> = zome_call_data
-        .context
-        .block_on(author_entry(&entry, None, &zome_call_data.context));
+    let task_result: Result<Address, HolochainError> = zome_call_data.context.block_on(
+        author_entry_with_options(&entry, None, &options, &zome_call_data.context),
+    );
 
     runtime.store_result(task_result)
 }
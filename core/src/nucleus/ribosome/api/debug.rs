@@ -7,7 +7,7 @@ use wasmi::{RuntimeArgs, RuntimeValue};
 /// Returns an HcApiReturnCode as I64
 pub fn invoke_debug(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
     let zome_call_data = runtime.zome_call_data()?;
-    let payload = runtime.load_json_string_from_args(args);
+    let payload = runtime.load_json_string_from_args(args)?;
 
     zome_call_data
         .context
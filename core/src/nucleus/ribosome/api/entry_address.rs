@@ -32,12 +32,14 @@ pub fn get_entry_type(dna: &Dna, entry_type_name: &str) -> Result<EntryType, Opt
 
 /// ZomeApiFunction::entry_address function code
 /// args: [0] encoded MemoryAllocation as u64
-/// Expected complex argument: entry_type_name and entry_value as JsonString
+/// Expected complex argument: the Entry to compute the address of
 /// Returns an HcApiReturnCode as I64
+/// The address is the one commit_entry would return for the same entry.
+/// Nothing gets committed or stored.
 pub fn invoke_entry_address(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
     let zome_call_data = runtime.zome_call_data()?;
     // deserialize args
-    let args_str = runtime.load_json_string_from_args(&args);
+    let args_str = runtime.load_json_string_from_args(&args)?;
     let entry = match Entry::try_from(args_str) {
         Ok(input) => input,
         Err(_) => return ribosome_error_code!(ArgumentDeserializationFailed),
@@ -60,3 +62,41 @@ pub fn invoke_entry_address(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeAp
     // Return result
     runtime.store_result(Ok(entry.address()))
 }
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{
+        agent::actions::commit::commit_entry,
+        nucleus::ribosome::api::{
+            commit::tests::test_commit_args_bytes, tests::test_zome_api_function, ZomeApiFunction,
+        },
+    };
+    use holochain_core_types::{entry::test_entry, error::ZomeApiInternalResult, json::JsonString};
+
+    #[test]
+    /// test that the address is computed without committing and matches the one of the commit
+    fn test_entry_address_matches_commit() {
+        let (call_result, context) = test_zome_api_function(
+            ZomeApiFunction::EntryAddress.as_str(),
+            test_commit_args_bytes(),
+        );
+        assert!(context
+            .state()
+            .unwrap()
+            .agent()
+            .get_most_recent_header_for_entry(&test_entry())
+            .is_none());
+
+        let committed_address = context
+            .block_on(commit_entry(test_entry(), None, &context))
+            .unwrap();
+        assert_eq!(
+            call_result,
+            JsonString::from(
+                String::from(JsonString::from(ZomeApiInternalResult::success(
+                    committed_address
+                ))) + "\u{0}"
+            ),
+        );
+    }
+}
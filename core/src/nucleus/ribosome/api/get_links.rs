@@ -15,7 +15,7 @@ use wasmi::{RuntimeArgs, RuntimeValue};
 pub fn invoke_get_links(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
     let zome_call_data = runtime.zome_call_data()?;
     // deserialize args
-    let args_str = runtime.load_json_string_from_args(&args);
+    let args_str = runtime.load_json_string_from_args(&args)?;
     let input = match GetLinksArgs::try_from(args_str.clone()) {
         Ok(input) => input,
         Err(_) => {
@@ -46,6 +46,7 @@ pub fn invoke_get_links(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiRes
         zome_call_data.context.clone(),
         input.entry_address,
         input.tag,
+        input.options.target_type,
         input.options.timeout,
     ));
 
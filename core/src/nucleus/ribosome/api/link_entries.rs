@@ -17,7 +17,7 @@ use wasmi::{RuntimeArgs, RuntimeValue};
 pub fn invoke_link_entries(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
     let zome_call_data = runtime.zome_call_data()?;
     // deserialize args
-    let args_str = runtime.load_json_string_from_args(&args);
+    let args_str = runtime.load_json_string_from_args(&args)?;
     let input = match LinkEntriesArgs::try_from(args_str.clone()) {
         Ok(entry_input) => entry_input,
         // Exit on error
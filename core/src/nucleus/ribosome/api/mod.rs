@@ -2,6 +2,7 @@
 //! ZomeApiFunctions are the functions provided by the ribosome that are callable by Zomes.
 
 pub mod call;
+pub mod chain_length;
 pub mod commit;
 pub mod debug;
 pub mod entry_address;
@@ -9,21 +10,24 @@ pub mod get_entry;
 pub mod get_links;
 pub mod init_globals;
 pub mod link_entries;
+pub mod publish_entry;
 pub mod query;
 pub mod remove_entry;
 pub mod remove_link;
 pub mod send;
 pub mod sleep;
 pub mod update_entry;
+pub mod zome_config;
 
 use crate::nucleus::ribosome::{
     api::{
-        call::invoke_call, commit::invoke_commit_app_entry, debug::invoke_debug,
-        entry_address::invoke_entry_address, get_entry::invoke_get_entry,
+        call::invoke_call, chain_length::invoke_chain_length, commit::invoke_commit_app_entry,
+        debug::invoke_debug, entry_address::invoke_entry_address, get_entry::invoke_get_entry,
         get_links::invoke_get_links, init_globals::invoke_init_globals,
-        link_entries::invoke_link_entries, query::invoke_query, remove_entry::invoke_remove_entry,
-        remove_link::invoke_remove_link, send::invoke_send, sleep::invoke_sleep,
-        update_entry::invoke_update_entry,
+        link_entries::invoke_link_entries, publish_entry::invoke_publish_entry,
+        query::invoke_query, remove_entry::invoke_remove_entry, remove_link::invoke_remove_link,
+        send::invoke_send, sleep::invoke_sleep, update_entry::invoke_update_entry,
+        zome_config::invoke_zome_config,
     },
     runtime::Runtime,
     Defn,
@@ -93,6 +97,18 @@ pub enum ZomeApiFunction {
     Send,
     Sleep,
     RemoveLink,
+
+    /// Get the number of headers in the agent's source chain
+    /// chain_length() -> u64
+    ChainLength,
+
+    /// Publish an entry of the source chain, e.g. one committed with publishing suppressed
+    /// publish_entry(address: Address) -> Address
+    PublishEntry,
+
+    /// Get the settings of the config of the called zome, as defined in the DNA
+    /// zome_config() -> Config
+    ZomeConfig,
 }
 
 impl Defn for ZomeApiFunction {
@@ -114,6 +130,9 @@ impl Defn for ZomeApiFunction {
             ZomeApiFunction::Send => "hc_send",
             ZomeApiFunction::Sleep => "hc_sleep",
             ZomeApiFunction::RemoveLink => "hc_remove_link",
+            ZomeApiFunction::ChainLength => "hc_chain_length",
+            ZomeApiFunction::PublishEntry => "hc_publish_entry",
+            ZomeApiFunction::ZomeConfig => "hc_zome_config",
         }
     }
 
@@ -147,6 +166,9 @@ impl FromStr for ZomeApiFunction {
             "hc_send" => Ok(ZomeApiFunction::Send),
             "hc_sleep" => Ok(ZomeApiFunction::Sleep),
             "hc_remove_link" => Ok(ZomeApiFunction::RemoveLink),
+            "hc_chain_length" => Ok(ZomeApiFunction::ChainLength),
+            "hc_publish_entry" => Ok(ZomeApiFunction::PublishEntry),
+            "hc_zome_config" => Ok(ZomeApiFunction::ZomeConfig),
             _ => Err("Cannot convert string to ZomeApiFunction"),
         }
     }
@@ -181,6 +203,31 @@ impl ZomeApiFunction {
             ZomeApiFunction::Send => invoke_send,
             ZomeApiFunction::Sleep => invoke_sleep,
             ZomeApiFunction::RemoveLink => invoke_remove_link,
+            ZomeApiFunction::ChainLength => invoke_chain_length,
+            ZomeApiFunction::PublishEntry => invoke_publish_entry,
+            ZomeApiFunction::ZomeConfig => invoke_zome_config,
+        }
+    }
+
+    /// Fuel units a call of this function consumes, see [profiler](../../profiler/index.html).
+    /// Functions that only compute something cost 1, those that work on the local
+    /// source chain or DHT 10 and those that may go to the network or run another
+    /// zome function 100.
+    pub fn fuel_cost(&self) -> u64 {
+        match *self {
+            ZomeApiFunction::CommitAppEntry
+            | ZomeApiFunction::UpdateEntry
+            | ZomeApiFunction::RemoveEntry
+            | ZomeApiFunction::LinkEntries
+            | ZomeApiFunction::RemoveLink
+            | ZomeApiFunction::Query
+            | ZomeApiFunction::ChainLength
+            | ZomeApiFunction::PublishEntry => 10,
+            ZomeApiFunction::GetAppEntry
+            | ZomeApiFunction::GetLinks
+            | ZomeApiFunction::Call
+            | ZomeApiFunction::Send => 100,
+            _ => 1,
         }
     }
 }
@@ -436,6 +483,9 @@ pub mod tests {
             ("hc_send", ZomeApiFunction::Send),
             ("hc_sleep", ZomeApiFunction::Sleep),
             ("hc_remove_link", ZomeApiFunction::RemoveLink),
+            ("hc_chain_length", ZomeApiFunction::ChainLength),
+            ("hc_publish_entry", ZomeApiFunction::PublishEntry),
+            ("hc_zome_config", ZomeApiFunction::ZomeConfig),
         ] {
             assert_eq!(ZomeApiFunction::from_str(input).unwrap(), output);
         }
@@ -467,6 +517,9 @@ pub mod tests {
             (ZomeApiFunction::Send, "hc_send"),
             (ZomeApiFunction::Sleep, "hc_sleep"),
             (ZomeApiFunction::RemoveLink, "hc_remove_link"),
+            (ZomeApiFunction::ChainLength, "hc_chain_length"),
+            (ZomeApiFunction::PublishEntry, "hc_publish_entry"),
+            (ZomeApiFunction::ZomeConfig, "hc_zome_config"),
         ] {
             assert_eq!(output, input.as_str());
         }
@@ -489,6 +542,9 @@ pub mod tests {
             ("hc_send", 13),
             ("hc_sleep", 14),
             ("hc_remove_link", 15),
+            ("hc_chain_length", 16),
+            ("hc_publish_entry", 17),
+            ("hc_zome_config", 18),
         ] {
             assert_eq!(output, ZomeApiFunction::str_to_index(input));
         }
@@ -511,6 +567,9 @@ pub mod tests {
             (13, ZomeApiFunction::Send),
             (14, ZomeApiFunction::Sleep),
             (15, ZomeApiFunction::RemoveLink),
+            (16, ZomeApiFunction::ChainLength),
+            (17, ZomeApiFunction::PublishEntry),
+            (18, ZomeApiFunction::ZomeConfig),
         ] {
             assert_eq!(output, ZomeApiFunction::from_index(input));
         }
@@ -0,0 +1,74 @@
+use crate::{
+    nucleus::{
+        actions::get_entry::get_entry_from_agent,
+        ribosome::{api::ZomeApiResult, Runtime},
+    },
+    workflows::author_entry::publish_authored_entry,
+};
+use holochain_core_types::{cas::content::Address, error::HolochainError};
+use std::convert::TryFrom;
+use wasmi::{RuntimeArgs, RuntimeValue};
+
+/// ZomeApiFunction::PublishEntry function code
+/// args: [0] encoded MemoryAllocation as u64
+/// Expected Address argument of an entry in the agent's source chain,
+/// typically one that got committed with publishing suppressed
+/// Returns an HcApiReturnCode as I64
+pub fn invoke_publish_entry(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
+    let zome_call_data = runtime.zome_call_data()?;
+    // deserialize args
+    let args_str = runtime.load_json_string_from_args(&args)?;
+    let address = match Address::try_from(args_str.clone()) {
+        Ok(address) => address,
+        // Exit on error
+        Err(_) => {
+            zome_call_data.context.log(format!(
+                "err/zome: invoke_publish_entry failed to deserialize Address: {:?}",
+                args_str
+            ));
+            return ribosome_error_code!(ArgumentDeserializationFailed);
+        }
+    };
+
+    let context = &zome_call_data.context;
+    let result: Result<Address, HolochainError> = get_entry_from_agent(context, &address)
+        .and_then(|maybe_entry| {
+            maybe_entry.ok_or_else(|| {
+                HolochainError::ErrorGeneric(format!(
+                    "Entry {} is not in the source chain",
+                    address
+                ))
+            })
+        })
+        .and_then(|entry| {
+            context
+                .block_on(publish_authored_entry(&entry, context))
+                .map(|_| address.clone())
+        });
+
+    runtime.store_result(result)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::nucleus::ribosome::{
+        api::{tests::test_zome_api_function, ZomeApiFunction},
+        Defn,
+    };
+    use holochain_core_types::{
+        cas::content::AddressableContent, entry::test_entry, json::JsonString,
+    };
+
+    #[test]
+    /// publishing an entry that was never committed fails
+    fn test_publish_entry_not_in_chain() {
+        let (call_result, _) = test_zome_api_function(
+            ZomeApiFunction::PublishEntry.as_str(),
+            JsonString::from(test_entry().address()).into_bytes(),
+        );
+
+        let call_result = String::from(call_result);
+        assert!(call_result.contains(r#""ok":false"#));
+        assert!(call_result.contains("is not in the source chain"));
+    }
+}
@@ -59,7 +59,7 @@ use wasmi::{RuntimeArgs, RuntimeValue};
 pub fn invoke_query(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
     let zome_call_data = runtime.zome_call_data()?;
     // deserialize args.
-    let args_str = runtime.load_json_string_from_args(&args);
+    let args_str = runtime.load_json_string_from_args(&args)?;
     let query = match QueryArgs::try_from(args_str) {
         Ok(input) => input,
         Err(..) => return ribosome_error_code!(ArgumentDeserializationFailed),
@@ -25,7 +25,7 @@ use wasmi::{RuntimeArgs, RuntimeValue};
 pub fn invoke_remove_entry(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
     let zome_call_data = runtime.zome_call_data()?;
     // deserialize args
-    let args_str = runtime.load_json_string_from_args(&args);
+    let args_str = runtime.load_json_string_from_args(&args)?;
     let try_address = Address::try_from(args_str.clone());
 
     // Exit on error
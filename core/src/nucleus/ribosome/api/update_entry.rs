@@ -24,7 +24,7 @@ use wasmi::{RuntimeArgs, RuntimeValue};
 pub fn invoke_update_entry(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
     let zome_call_data = runtime.zome_call_data()?;
     // deserialize args
-    let args_str = runtime.load_json_string_from_args(&args);
+    let args_str = runtime.load_json_string_from_args(&args)?;
     let entry_args = match UpdateEntryArgs::try_from(args_str.clone()) {
         Ok(entry_input) => entry_input,
         // Exit on error
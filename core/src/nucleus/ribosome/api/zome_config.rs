@@ -0,0 +1,83 @@
+use crate::nucleus::ribosome::{api::ZomeApiResult, Runtime};
+use holochain_core_types::error::HolochainError;
+use wasmi::RuntimeArgs;
+
+/// ZomeApiFunction::ZomeConfig function code
+/// args: [0] encoded MemoryAllocation as u64
+/// Expected argument: none
+/// Returns an HcApiReturnCode as I64
+pub fn invoke_zome_config(runtime: &mut Runtime, _args: &RuntimeArgs) -> ZomeApiResult {
+    let zome_call_data = runtime.zome_call_data()?;
+    let result: Result<_, HolochainError> = Ok(zome_call_data.zome_config());
+    runtime.store_result(result)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{
+        instance::tests::test_instance_and_context,
+        nucleus::{
+            ribosome::{
+                api::{
+                    tests::{
+                        test_zome_api_function, test_zome_api_function_call,
+                        test_zome_api_function_wasm, test_zome_name,
+                    },
+                    ZomeApiFunction,
+                },
+                Defn,
+            },
+            tests::test_capability_name,
+        },
+    };
+    use holochain_core_types::json::JsonString;
+    use serde_json::json;
+    use test_utils;
+
+    fn zome_config_result(settings: &str) -> JsonString {
+        JsonString::from(
+            format!(
+                r#"{{"ok":true,"value":"{}","error":"null"}}"#,
+                settings.replace('"', "\\\"")
+            ) + "\u{0}",
+        )
+    }
+
+    #[test]
+    fn test_zome_config_of_zome_without_settings() {
+        let (call_result, _) =
+            test_zome_api_function(ZomeApiFunction::ZomeConfig.as_str(), Vec::new());
+        assert_eq!(call_result, zome_config_result("{}"));
+    }
+
+    #[test]
+    fn test_zome_config_returns_settings_of_the_called_zome() {
+        let wasm = test_zome_api_function_wasm(ZomeApiFunction::ZomeConfig.as_str());
+        let mut dna = test_utils::create_test_dna_with_wasm(
+            &test_zome_name(),
+            &test_capability_name(),
+            wasm.clone(),
+        );
+        {
+            let settings = &mut dna
+                .zomes
+                .get_mut(&test_zome_name())
+                .expect("test zome should exist")
+                .config
+                .settings;
+            settings.insert("greeting".to_string(), json!("hello"));
+            settings.insert("max_post_length".to_string(), json!(280));
+        }
+        let dna_name = dna.name.to_string();
+        let (instance, context) =
+            test_instance_and_context(dna, None).expect("Could not create test instance");
+
+        let call_result =
+            test_zome_api_function_call(&dna_name, context, &instance, &wasm, Vec::new());
+
+        assert_eq!(
+            call_result,
+            zome_config_result(r#"{"greeting":"hello","max_post_length":280}"#)
+        );
+    }
+}
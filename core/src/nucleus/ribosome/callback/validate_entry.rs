@@ -64,8 +64,8 @@ pub fn validate_entry(
         // TODO: Specify how Deletion can be commited to chain.
         EntryType::Deletion => Ok(CallbackResult::Pass),
 
-        // a grant should always be private, so it should always pass
-        EntryType::CapTokenGrant => Ok(CallbackResult::Pass),
+        // a grant should always be private, so there is only the DNA to check it against
+        EntryType::CapTokenGrant => Ok(validate_cap_token_grant(&entry, &context)?),
 
         // TODO: actually check agent against app specific membrane validation rule
         // like for instance: validate_agent_id(
@@ -79,6 +79,31 @@ pub fn validate_entry(
     }
 }
 
+/// Grants may only give access to zome functions the DNA declares.
+fn validate_cap_token_grant(
+    entry: &Entry,
+    context: &Arc<Context>,
+) -> Result<CallbackResult, HolochainError> {
+    let grant = match entry {
+        Entry::CapTokenGrant(grant) => grant,
+        _ => {
+            return Err(HolochainError::ValidationFailed(
+                "Could not extract grant from entry".into(),
+            ));
+        }
+    };
+    let dna = context.get_dna().expect("Callback called without DNA set!");
+    for granted in grant.functions() {
+        if let Err(error) = dna.get_function_with_zome_name(&granted.zome, &granted.function) {
+            return Ok(CallbackResult::Fail(format!(
+                "Grant references undeclared function: {}",
+                error
+            )));
+        }
+    }
+    Ok(CallbackResult::Pass)
+}
+
 fn validate_link_entry(
     entry: Entry,
     validation_data: ValidationData,
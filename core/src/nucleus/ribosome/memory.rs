@@ -88,13 +88,14 @@ impl WasmPageManager {
         Ok(mem_buf)
     }
 
-    /// Read data somewhere in stack
-    pub fn read(&self, allocation: WasmAllocation) -> Vec<u8> {
+    /// Read data somewhere in stack.
+    /// Fails with OutOfBounds if the allocation reaches past the wasm memory.
+    pub fn read(&self, allocation: WasmAllocation) -> Result<Vec<u8>, AllocationError> {
         self.wasm_memory
             .get(
                 MemoryInt::from(allocation.offset()),
                 MemoryInt::from(allocation.length()) as usize,
             )
-            .expect("Successfully retrieve the result")
+            .map_err(|_| AllocationError::OutOfBounds)
     }
 }
@@ -3,6 +3,7 @@
 pub mod api;
 pub mod callback;
 pub mod memory;
+pub mod module_cache;
 mod run_dna;
 mod runtime;
 
@@ -0,0 +1,135 @@
+//! Optional cache of compiled wasm modules.
+//! Parsing and validating a zome's wasm makes up a good part of the cost of a zome call.
+//! If the context has a WasmModuleCache set, run_dna keeps the compiled module of every
+//! wasm binary it runs, keyed by the hash of the binary, and reuses it for later calls.
+//! The least recently used modules get dropped once the cache is full.
+
+use crate::lru_cache::LruCache;
+use holochain_core_types::{
+    cas::content::Address,
+    error::{HcResult, HolochainError},
+    hash::HashString,
+};
+use multihash::Hash;
+use std::sync::{Arc, Mutex};
+use wasmi::Module;
+
+/// Parses and validates the given wasm binary.
+pub fn compile_module(wasm: &[u8]) -> HcResult<Module> {
+    Module::from_buffer(wasm).map_err(|e| HolochainError::ErrorGeneric(e.into()))
+}
+
+struct CacheContent {
+    modules: LruCache<Address, Arc<Module>>,
+    compilations: usize,
+}
+
+/// Bounded cache of compiled wasm modules. Clones share the same content.
+#[derive(Clone)]
+pub struct WasmModuleCache {
+    content: Arc<Mutex<CacheContent>>,
+}
+
+impl WasmModuleCache {
+    /// Creates a cache that keeps at most `max_modules` compiled modules.
+    pub fn new(max_modules: usize) -> Self {
+        WasmModuleCache {
+            content: Arc::new(Mutex::new(CacheContent {
+                modules: LruCache::new(max_modules),
+                compilations: 0,
+            })),
+        }
+    }
+
+    /// Returns the compiled module for the given wasm binary,
+    /// compiling and storing it if it is not cached yet.
+    pub fn get_or_compile(&self, wasm: &[u8]) -> HcResult<Arc<Module>> {
+        let key = HashString::encode_from_bytes(wasm, Hash::SHA2256);
+        {
+            let mut content = self.content.lock().unwrap();
+            if let Some(module) = content.modules.get(&key) {
+                return Ok(module.clone());
+            }
+        }
+
+        // Compiling takes a while, so other calls should not have to wait for the lock
+        // in the meantime. Concurrent first calls might compile the same module twice.
+        let module = Arc::new(compile_module(wasm)?);
+        let mut content = self.content.lock().unwrap();
+        content.compilations += 1;
+        content.modules.insert(key, module.clone());
+        Ok(module)
+    }
+
+    /// Number of cached modules.
+    pub fn len(&self) -> usize {
+        self.content.lock().unwrap().modules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of wasm binaries this cache had to compile so far.
+    pub fn compilations(&self) -> usize {
+        self.content.lock().unwrap().compilations
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use wabt::Wat2Wasm;
+
+    fn test_wasm(function_name: &str) -> Vec<u8> {
+        Wat2Wasm::new()
+            .convert(format!(
+                r#"(module (func (export "{}") (result i64) (i64.const 0)))"#,
+                function_name
+            ))
+            .unwrap()
+            .as_ref()
+            .to_vec()
+    }
+
+    #[test]
+    fn compiles_each_wasm_only_once() {
+        let cache = WasmModuleCache::new(10);
+        let wasm = test_wasm("a");
+
+        cache.get_or_compile(&wasm).unwrap();
+        cache.clone().get_or_compile(&wasm).unwrap();
+        assert_eq!(cache.compilations(), 1);
+        assert_eq!(cache.len(), 1);
+
+        cache.get_or_compile(&test_wasm("b")).unwrap();
+        assert_eq!(cache.compilations(), 2);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn drops_least_recently_used_modules() {
+        let cache = WasmModuleCache::new(2);
+        let (a, b, c) = (test_wasm("a"), test_wasm("b"), test_wasm("c"));
+        cache.get_or_compile(&a).unwrap();
+        cache.get_or_compile(&b).unwrap();
+        // using a again makes b the least recently used one
+        cache.get_or_compile(&a).unwrap();
+        cache.get_or_compile(&c).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.compilations(), 3);
+
+        cache.get_or_compile(&a).unwrap();
+        assert_eq!(cache.compilations(), 3);
+        cache.get_or_compile(&b).unwrap();
+        assert_eq!(cache.compilations(), 4);
+    }
+
+    #[test]
+    fn does_not_cache_invalid_wasm() {
+        let cache = WasmModuleCache::new(10);
+        assert!(cache.get_or_compile(&[0, 1, 2, 3]).is_err());
+        assert!(cache.is_empty());
+        assert_eq!(cache.compilations(), 0);
+    }
+}
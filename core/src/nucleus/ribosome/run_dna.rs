@@ -1,5 +1,8 @@
 use crate::nucleus::{
-    ribosome::{api::ZomeApiFunction, memory::WasmPageManager, runtime::WasmCallData, Runtime},
+    ribosome::{
+        api::ZomeApiFunction, memory::WasmPageManager, module_cache::compile_module,
+        runtime::WasmCallData, Runtime,
+    },
     ZomeFnResult,
 };
 use holochain_core_types::{
@@ -9,19 +12,49 @@ use holochain_core_types::{
     json::JsonString,
 };
 use holochain_wasm_utils::memory::allocation::{AllocationError, WasmAllocation};
-use std::{convert::TryFrom, str::FromStr};
+use std::{convert::TryFrom, str::FromStr, sync::Arc};
 use wasmi::{
-    self, Error as InterpreterError, FuncInstance, FuncRef, ImportsBuilder, ModuleImportResolver,
-    ModuleInstance, NopExternals, RuntimeValue, Signature, ValueType,
+    Error as InterpreterError, FuncInstance, FuncRef, ImportsBuilder, ModuleImportResolver,
+    ModuleInstance, NopExternals, RuntimeValue, Signature, TrapKind, ValueType,
 };
 
+/// Names the reason of a wasm trap so zome developers can see why their zome crashed.
+fn trap_reason(trap_kind: &TrapKind) -> String {
+    match trap_kind {
+        TrapKind::Unreachable => "unreachable executed".to_string(),
+        TrapKind::MemoryAccessOutOfBounds => "memory access out of bounds".to_string(),
+        TrapKind::TableAccessOutOfBounds => "table access out of bounds".to_string(),
+        TrapKind::ElemUninitialized => "call of uninitialized table element".to_string(),
+        TrapKind::DivisionByZero => "integer division by zero".to_string(),
+        TrapKind::InvalidConversionToInt => "invalid conversion to integer".to_string(),
+        TrapKind::StackOverflow => "call stack exhausted".to_string(),
+        TrapKind::UnexpectedSignature => "indirect call signature mismatch".to_string(),
+        TrapKind::Host(host_error) => format!("host error: {}", host_error),
+    }
+}
+
+/// Describes an interpreter error, naming the trap reason if the wasm trapped.
+fn describe_interpreter_error(error: &InterpreterError) -> String {
+    match error {
+        InterpreterError::Trap(trap) => format!("wasm trap: {}", trap_reason(trap.kind())),
+        other => other.to_string(),
+    }
+}
+
 /// Executes an exposed zome function in a wasm binary.
 /// Multithreaded function
 /// panics if wasm binary isn't valid.
 pub fn run_dna(wasm: Vec<u8>, parameters: Option<Vec<u8>>, data: WasmCallData) -> ZomeFnResult {
-    // Create wasm module from wasm binary
-    let module =
-        wasmi::Module::from_buffer(wasm).map_err(|e| HolochainError::ErrorGeneric(e.into()))?;
+    // Create wasm module from wasm binary, or take it from the cache if there is one
+    let module = match data {
+        WasmCallData::ZomeCall(ref zome_call_data) => {
+            match zome_call_data.context.wasm_module_cache {
+                Some(ref cache) => cache.get_or_compile(&wasm)?,
+                None => Arc::new(compile_module(&wasm)?),
+            }
+        }
+        WasmCallData::DirectCall(_) => Arc::new(compile_module(&wasm)?),
+    };
 
     // invoke_index and resolve_func work together to enable callable host functions
     // within WASM modules, which is how the core API functions
@@ -78,7 +111,12 @@ pub fn run_dna(wasm: Vec<u8>, parameters: Option<Vec<u8>>, data: WasmCallData) -
     let wasm_instance = ModuleInstance::new(&module, &imports)
         .expect("Failed to instantiate module")
         .run_start(&mut NopExternals)
-        .map_err(|_| HolochainError::RibosomeFailed("Module failed to start".to_string()))?;
+        .map_err(|trap| {
+            HolochainError::RibosomeFailed(format!(
+                "Module failed to start: wasm trap: {}",
+                trap_reason(trap.kind())
+            ))
+        })?;
 
     // write input arguments for module call in memory Buffer
     let input_parameters: Vec<_> = parameters.unwrap_or_default();
@@ -88,6 +126,7 @@ pub fn run_dna(wasm: Vec<u8>, parameters: Option<Vec<u8>>, data: WasmCallData) -
     let mut runtime = Runtime {
         memory_manager: WasmPageManager::new(&wasm_instance),
         data,
+        fuel: 0,
     };
 
     // Write input arguments in wasm memory
@@ -132,13 +171,22 @@ pub fn run_dna(wasm: Vec<u8>, parameters: Option<Vec<u8>>, data: WasmCallData) -
                 mut_runtime,
             )
             .map_err(|err| {
-                HolochainError::RibosomeFailed(format!("WASM invocation failed: {}", err))
+                HolochainError::RibosomeFailed(format!(
+                    "WASM invocation failed: {}",
+                    describe_interpreter_error(&err)
+                ))
             })?
             .unwrap()
             .try_into() // Option<_>
             .ok_or_else(|| HolochainError::RibosomeFailed("WASM return value missing".to_owned()))?
     };
 
+    if let WasmCallData::ZomeCall(ref zome_call_data) = runtime.data {
+        if let Some(ref profiler) = zome_call_data.context.zome_call_profiler {
+            profiler.record(&zome_call_data.zome_call, runtime.fuel);
+        }
+    }
+
     // Handle result returned by called zome function
     let return_code = RibosomeEncodedValue::from(returned_encoding);
 
@@ -160,23 +208,22 @@ pub fn run_dna(wasm: Vec<u8>, parameters: Option<Vec<u8>>, data: WasmCallData) -
         }
 
         RibosomeEncodedValue::Allocation(ribosome_allocation) => {
-            match WasmAllocation::try_from(ribosome_allocation) {
-                Ok(allocation) => {
-                    let result = runtime.memory_manager.read(allocation);
-                    match String::from_utf8(result) {
-                        Ok(json_string) => {
-                            return_log_msg = json_string.clone();
-                            return_result = Ok(JsonString::from(json_string));
-                        }
-                        Err(err) => {
-                            return_log_msg = err.to_string();
-                            return_result = Err(HolochainError::RibosomeFailed(format!(
-                                "WASM failed to return value: {}",
-                                err
-                            )));
-                        }
+            match WasmAllocation::try_from(ribosome_allocation)
+                .and_then(|allocation| runtime.memory_manager.read(allocation))
+            {
+                Ok(result) => match String::from_utf8(result) {
+                    Ok(json_string) => {
+                        return_log_msg = json_string.clone();
+                        return_result = Ok(JsonString::from(json_string));
                     }
-                }
+                    Err(err) => {
+                        return_log_msg = err.to_string();
+                        return_result = Err(HolochainError::RibosomeFailed(format!(
+                            "WASM failed to return value: {}",
+                            err
+                        )));
+                    }
+                },
                 Err(allocation_error) => {
                     return_log_msg = String::from(allocation_error.clone());
                     return_result = Err(HolochainError::RibosomeFailed(format!(
@@ -198,3 +245,234 @@ pub fn run_dna(wasm: Vec<u8>, parameters: Option<Vec<u8>>, data: WasmCallData) -
     let _ = return_log_msg;
     return return_result;
 }
+
+#[cfg(test)]
+pub mod tests {
+    use self::wabt::Wat2Wasm;
+    use super::*;
+    use crate::{
+        instance::tests::test_context,
+        nucleus::{profiler::ZomeCallProfiler, ZomeFnCall},
+    };
+    use wabt;
+
+    fn wat_to_wasm(wat: &str) -> Vec<u8> {
+        Wat2Wasm::new()
+            .canonicalize_lebs(false)
+            .write_debug_names(true)
+            .convert(wat)
+            .unwrap()
+            .as_ref()
+            .to_vec()
+    }
+
+    fn run_test_wat(wat: &str) -> ZomeFnResult {
+        run_dna(
+            wat_to_wasm(wat),
+            None,
+            WasmCallData::DirectCall("test".to_string()),
+        )
+    }
+
+    #[test]
+    fn profiler_gets_fuel_of_zome_calls() {
+        let mut context = (*test_context("alice", None)).clone();
+        let profiler = ZomeCallProfiler::new();
+        context.zome_call_profiler = Some(profiler.clone());
+        // calls hc_debug 5 times in a loop and hc_entry_address once
+        let wasm = wat_to_wasm(
+            r#"
+(module
+    (import "env" "hc_debug" (func $debug (param i64) (result i64)))
+    (import "env" "hc_entry_address" (func $entry_address (param i64) (result i64)))
+    (memory 1)
+    (export "memory" (memory 0))
+
+    (func
+        (export "test")
+        (param $allocation i64)
+        (result i64)
+        (local $i i32)
+
+        (loop $calls
+            (drop (call $debug (i64.const 0)))
+            (set_local $i (i32.add (get_local $i) (i32.const 1)))
+            (br_if $calls (i32.lt_u (get_local $i) (i32.const 5)))
+        )
+        (drop (call $entry_address (i64.const 0)))
+        (i64.const 0)
+    )
+)
+"#,
+        );
+        let call = ZomeFnCall::new("test_zome", None, "test", "");
+        for _ in 0..2 {
+            run_dna(
+                wasm.clone(),
+                None,
+                WasmCallData::new_zome_call(Arc::new(context.clone()), "dna".into(), call.clone()),
+            )
+            .unwrap();
+        }
+
+        let profile = profiler.profile("test_zome", "test").unwrap();
+        assert_eq!(profile.calls, 2);
+        let expected =
+            5 * ZomeApiFunction::Debug.fuel_cost() + ZomeApiFunction::EntryAddress.fuel_cost();
+        assert_eq!(profile.last_fuel, expected);
+        assert_eq!(profile.total_fuel, 2 * expected);
+        assert_eq!(profiler.profile("test_zome", "other"), None);
+    }
+
+    #[test]
+    fn invalid_utf8_arguments_trap_instead_of_panicking() {
+        let context = test_context("alice", None);
+        // calls hc_debug with an allocation of length 2 at offset 8 holding 0xff 0xfe
+        let wasm = wat_to_wasm(
+            r#"
+(module
+    (import "env" "hc_debug" (func $debug (param i64) (result i64)))
+    (memory 1)
+    (export "memory" (memory 0))
+    (data (i32.const 8) "\ff\fe")
+
+    (func
+        (export "test")
+        (param $allocation i64)
+        (result i64)
+
+        (call $debug (i64.const 34359738370))
+    )
+)
+"#,
+        );
+        let call = ZomeFnCall::new("test_zome", None, "test", "");
+        let result = run_dna(
+            wasm,
+            None,
+            WasmCallData::new_zome_call(context, "dna".into(), call),
+        );
+        assert_eq!(
+            result,
+            Err(HolochainError::RibosomeFailed(
+                "WASM invocation failed: wasm trap: host error: invalid UTF-8 in wasm memory"
+                    .to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn unreachable_trap_reason_gets_reported() {
+        let result = run_test_wat(
+            r#"
+(module
+    (memory 1)
+    (export "memory" (memory 0))
+
+    (func
+        (export "test")
+        (param $allocation i64)
+        (result i64)
+
+        unreachable
+    )
+)
+"#,
+        );
+        assert_eq!(
+            result,
+            Err(HolochainError::RibosomeFailed(
+                "WASM invocation failed: wasm trap: unreachable executed".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn memory_access_trap_reason_gets_reported() {
+        let result = run_test_wat(
+            r#"
+(module
+    (memory 1)
+    (export "memory" (memory 0))
+
+    (func
+        (export "test")
+        (param $allocation i64)
+        (result i64)
+
+        (i64.load (i32.const 65536))
+    )
+)
+"#,
+        );
+        assert_eq!(
+            result,
+            Err(HolochainError::RibosomeFailed(
+                "WASM invocation failed: wasm trap: memory access out of bounds".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn out_of_bounds_arguments_trap_instead_of_panicking() {
+        let context = test_context("alice", None);
+        // calls hc_debug with an allocation of length 2 at offset 65536, right past memory
+        let wasm = wat_to_wasm(
+            r#"
+(module
+    (import "env" "hc_debug" (func $debug (param i64) (result i64)))
+    (memory 1)
+    (export "memory" (memory 0))
+
+    (func
+        (export "test")
+        (param $allocation i64)
+        (result i64)
+
+        (call $debug (i64.const 281474976710658))
+    )
+)
+"#,
+        );
+        let call = ZomeFnCall::new("test_zome", None, "test", "");
+        let result = run_dna(
+            wasm,
+            None,
+            WasmCallData::new_zome_call(context, "dna".into(), call),
+        );
+        assert_eq!(
+            result,
+            Err(HolochainError::RibosomeFailed(
+                "WASM invocation failed: wasm trap: host error: invalid allocation in wasm memory: Allocation out of bounds"
+                    .to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn out_of_bounds_return_value_fails_instead_of_panicking() {
+        // returns an allocation of length 2 at offset 65536, right past memory
+        let result = run_test_wat(
+            r#"
+(module
+    (memory 1)
+    (export "memory" (memory 0))
+
+    (func
+        (export "test")
+        (param $allocation i64)
+        (result i64)
+
+        (i64.const 281474976710658)
+    )
+)
+"#,
+        );
+        assert_eq!(
+            result,
+            Err(HolochainError::RibosomeFailed(
+                "WASM return value allocation failed: OutOfBounds".to_string()
+            ))
+        );
+    }
+}
@@ -10,6 +10,7 @@ use crate::{
     },
 };
 use holochain_core_types::{
+    dna::zome::Config,
     error::{
         HolochainError, RibosomeEncodedValue, RibosomeEncodingBits, RibosomeRuntimeBits,
         ZomeApiInternalResult,
@@ -17,8 +18,25 @@ use holochain_core_types::{
     json::JsonString,
 };
 use holochain_wasm_utils::memory::allocation::WasmAllocation;
-use std::{convert::TryFrom, sync::Arc};
-use wasmi::{Externals, RuntimeArgs, RuntimeValue, Trap, TrapKind};
+use std::{convert::TryFrom, fmt, sync::Arc};
+use wasmi::{Externals, HostError, RuntimeArgs, RuntimeValue, Trap, TrapKind};
+
+/// Error of the host that makes the wasm trap, e.g. because the zome passed arguments
+/// the host can't read.
+#[derive(Debug)]
+pub struct HostTrapError(pub String);
+
+impl fmt::Display for HostTrapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl HostError for HostTrapError {}
+
+fn host_trap(message: String) -> Trap {
+    Trap::new(TrapKind::Host(Box::new(HostTrapError(message))))
+}
 
 #[derive(Clone)]
 pub struct ZomeCallData {
@@ -30,6 +48,22 @@ pub struct ZomeCallData {
     pub zome_call: ZomeFnCall,
 }
 
+impl ZomeCallData {
+    /// Config of the zome that is being called, as defined in the DNA.
+    /// Empty if the DNA or the zome can't be found.
+    pub fn zome_config(&self) -> Config {
+        self.context
+            .state()
+            .and_then(|state| state.nucleus().dna())
+            .and_then(|dna| {
+                dna.zomes
+                    .get(&self.zome_call.zome_name)
+                    .map(|zome| zome.config.clone())
+            })
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Clone)]
 pub enum WasmCallData {
     ZomeCall(ZomeCallData),
@@ -61,6 +95,9 @@ pub struct Runtime {
 
     /// data to be made available to the function at runtime
     pub data: WasmCallData,
+
+    /// fuel consumed by the Zome API functions called so far
+    pub fuel: u64,
 }
 
 impl Runtime {
@@ -74,33 +111,48 @@ impl Runtime {
     /// Load a JsonString stored in wasm memory.
     /// Input RuntimeArgs should only have one input which is the encoded allocation holding
     /// the complex data as an utf8 string.
-    /// Returns the utf8 string.
-    pub fn load_json_string_from_args(&self, args: &RuntimeArgs) -> JsonString {
-        // @TODO don't panic in WASM
-        // @see https://github.com/holochain/holochain-rust/issues/159
-        assert_eq!(1, args.len());
+    /// Returns the utf8 string, or a trap if there is not exactly one argument, or if the
+    /// allocation is invalid, reaches past the wasm memory or does not hold valid UTF-8,
+    /// so that a broken zome can't take down the thread.
+    pub fn load_json_string_from_args(&self, args: &RuntimeArgs) -> Result<JsonString, Trap> {
+        if args.len() != 1 {
+            return Err(host_trap(format!(
+                "expected 1 argument, got {}",
+                args.len()
+            )));
+        }
 
         // Read complex argument serialized in memory
         let encoded: RibosomeEncodingBits = args.nth(0);
         let return_code = RibosomeEncodedValue::from(encoded);
         let allocation = match return_code {
-            RibosomeEncodedValue::Success => return JsonString::null(),
+            RibosomeEncodedValue::Success => return Ok(JsonString::null()),
             RibosomeEncodedValue::Failure(_) => {
-                panic!("received error code instead of valid encoded allocation")
+                return Err(host_trap(
+                    "received error code instead of valid encoded allocation".to_string(),
+                ))
             }
             RibosomeEncodedValue::Allocation(ribosome_allocation) => {
-                WasmAllocation::try_from(ribosome_allocation).unwrap()
+                WasmAllocation::try_from(ribosome_allocation).map_err(|error| {
+                    host_trap(format!(
+                        "invalid allocation in wasm memory: {}",
+                        String::from(error)
+                    ))
+                })?
             }
         };
 
-        let bin_arg = self.memory_manager.read(allocation);
+        let bin_arg = self.memory_manager.read(allocation).map_err(|error| {
+            host_trap(format!(
+                "invalid allocation in wasm memory: {}",
+                String::from(error)
+            ))
+        })?;
 
         // convert complex argument
         String::from_utf8(bin_arg)
-            // @TODO don't panic in WASM
-            // @see https://github.com/holochain/holochain-rust/issues/159
-            .unwrap()
-            .into()
+            .map(JsonString::from)
+            .map_err(|_| host_trap("invalid UTF-8 in wasm memory".to_string()))
     }
 
     /// Store anything that implements Into<JsonString> in wasm memory.
@@ -141,7 +193,44 @@ impl Externals for Runtime {
         match zf {
             ZomeApiFunction::MissingNo => panic!("unknown function index"),
             // convert the function to its callable form and call it with the given arguments
-            _ => zf.as_fn()(self, &args),
+            _ => {
+                self.fuel += zf.fuel_cost();
+                zf.as_fn()(self, &args)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::nucleus::actions::tests::*;
+    use serde_json::json;
+
+    #[test]
+    fn zome_call_data_exposes_zome_config() {
+        let mut dna = test_dna();
+        dna.zomes
+            .get_mut("test_zome")
+            .unwrap()
+            .config
+            .settings
+            .insert("max_post_length".to_string(), json!(280));
+        let netname = Some("zome_call_data_exposes_zome_config");
+        let (_instance, context) = instance_by_name("alice", dna.clone(), netname);
+
+        let call = ZomeFnCall::new("test_zome", None, "public_test_fn", "");
+        match WasmCallData::new_zome_call(context.clone(), dna.name.clone(), call) {
+            WasmCallData::ZomeCall(data) => {
+                assert_eq!(data.zome_config().get("max_post_length"), Some(&json!(280)))
+            }
+            WasmCallData::DirectCall(_) => panic!("expected zome call data"),
+        }
+
+        let call = ZomeFnCall::new("unknown_zome", None, "public_test_fn", "");
+        match WasmCallData::new_zome_call(context, dna.name, call) {
+            WasmCallData::ZomeCall(data) => assert_eq!(data.zome_config(), Config::new()),
+            WasmCallData::DirectCall(_) => panic!("expected zome call data"),
         }
     }
 }
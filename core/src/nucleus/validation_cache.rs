@@ -0,0 +1,178 @@
+//! Optional memoization of validation outcomes.
+//! If the context has a ValidationCache set, hold_entry_workflow stores the outcome of
+//! validating an entry under the addresses of the entry and its header.
+//! Gossip of the same entry with the same header then gets the stored outcome back
+//! without fetching the validation package and running the validation callback again.
+//! Outcomes expire after a TTL so that changed validation rules eventually take effect.
+//! Failures get their own, usually shorter TTL and are not stored at all if it is zero.
+
+use crate::lru_cache::LruCache;
+use holochain_core_types::{cas::content::Address, error::HolochainError};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+type CacheKey = (Address, Address);
+
+struct CacheContent {
+    outcomes: LruCache<CacheKey, (Instant, Result<(), HolochainError>)>,
+    hits: usize,
+    misses: usize,
+}
+
+/// Bounded cache of validation outcomes. Clones share the same content.
+#[derive(Clone)]
+pub struct ValidationCache {
+    ttl: Duration,
+    failure_ttl: Duration,
+    content: Arc<Mutex<CacheContent>>,
+}
+
+impl ValidationCache {
+    /// Creates a cache that keeps at most `max_entries` outcomes.
+    /// Passed validations are kept for at most `ttl`, failed ones for at most `failure_ttl`.
+    pub fn new(max_entries: usize, ttl: Duration, failure_ttl: Duration) -> Self {
+        ValidationCache {
+            ttl,
+            failure_ttl,
+            content: Arc::new(Mutex::new(CacheContent {
+                outcomes: LruCache::new(max_entries),
+                hits: 0,
+                misses: 0,
+            })),
+        }
+    }
+
+    fn ttl_of(&self, outcome: &Result<(), HolochainError>) -> Duration {
+        match outcome {
+            Ok(()) => self.ttl,
+            Err(_) => self.failure_ttl,
+        }
+    }
+
+    /// Returns the stored outcome of validating the given entry with the given header
+    /// if there is one that did not expire yet.
+    pub fn get(
+        &self,
+        entry_address: &Address,
+        header_address: &Address,
+    ) -> Option<Result<(), HolochainError>> {
+        let key = (entry_address.clone(), header_address.clone());
+        let mut content = self.content.lock().unwrap();
+        let cached = match content.outcomes.get(&key) {
+            Some((stored_at, outcome)) if stored_at.elapsed() < self.ttl_of(outcome) => {
+                Some(outcome.clone())
+            }
+            _ => None,
+        };
+        if cached.is_some() {
+            content.hits += 1;
+        } else {
+            content.misses += 1;
+        }
+        cached
+    }
+
+    /// Stores the outcome of validating the given entry with the given header.
+    /// Only passed validations and validation failures get stored, other errors
+    /// (e.g. a missing validation package) might not happen the next time.
+    /// Drops the least recently used outcomes if the cache is full.
+    pub fn insert(
+        &self,
+        entry_address: &Address,
+        header_address: &Address,
+        outcome: &Result<(), HolochainError>,
+    ) {
+        match outcome {
+            Ok(()) | Err(HolochainError::ValidationFailed(_)) => (),
+            Err(_) => return,
+        }
+        if self.ttl_of(outcome) == Duration::from_secs(0) {
+            return;
+        }
+        let key = (entry_address.clone(), header_address.clone());
+        let mut content = self.content.lock().unwrap();
+        content
+            .outcomes
+            .insert(key, (Instant::now(), outcome.clone()));
+    }
+
+    /// Number of stored outcomes, including expired ones that were not pushed out yet.
+    pub fn len(&self) -> usize {
+        self.content.lock().unwrap().outcomes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of lookups that got answered from the cache.
+    pub fn hits(&self) -> usize {
+        self.content.lock().unwrap().hits
+    }
+
+    /// Number of lookups that found nothing, i.e. entries that had to be validated.
+    pub fn misses(&self) -> usize {
+        self.content.lock().unwrap().misses
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::thread;
+
+    fn addresses(i: usize) -> (Address, Address) {
+        (
+            Address::from(format!("QmEntry{}", i)),
+            Address::from(format!("QmHeader{}", i)),
+        )
+    }
+
+    #[test]
+    fn keeps_at_most_max_entries() {
+        let cache = ValidationCache::new(2, Duration::from_secs(60), Duration::from_secs(60));
+        for i in 0..3 {
+            let (entry, header) = addresses(i);
+            cache.insert(&entry, &header, &Ok(()));
+        }
+
+        assert_eq!(cache.len(), 2);
+        let (entry, header) = addresses(0);
+        assert_eq!(cache.get(&entry, &header), None);
+        let (entry, header) = addresses(2);
+        assert_eq!(cache.get(&entry, &header), Some(Ok(())));
+        assert_eq!(cache.get(&entry, &addresses(1).1), None);
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(cache.misses(), 2);
+    }
+
+    #[test]
+    fn failures_expire_after_their_own_ttl() {
+        let cache = ValidationCache::new(10, Duration::from_secs(60), Duration::from_millis(50));
+        let (passed_entry, passed_header) = addresses(0);
+        let (failed_entry, failed_header) = addresses(1);
+        let failure = Err(HolochainError::ValidationFailed("FAIL".to_string()));
+        cache.insert(&passed_entry, &passed_header, &Ok(()));
+        cache.insert(&failed_entry, &failed_header, &failure);
+        assert_eq!(cache.get(&failed_entry, &failed_header), Some(failure));
+
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(cache.get(&failed_entry, &failed_header), None);
+        assert_eq!(cache.get(&passed_entry, &passed_header), Some(Ok(())));
+    }
+
+    #[test]
+    fn other_errors_and_failures_without_ttl_are_not_stored() {
+        let cache = ValidationCache::new(10, Duration::from_secs(60), Duration::from_secs(0));
+        let (entry, header) = addresses(0);
+        cache.insert(&entry, &header, &Err(HolochainError::Timeout));
+        cache.insert(
+            &entry,
+            &header,
+            &Err(HolochainError::ValidationFailed("FAIL".to_string())),
+        );
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,101 @@
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Bounds the number of validation callbacks that run concurrently within an instance.
+/// Validations run wasm and are CPU heavy, so a burst of incoming gossip could otherwise
+/// saturate all cores. Validations exceeding the limit block until a running one finishes.
+///
+/// Clones share the same counter, so every clone of a context uses the same limit.
+#[derive(Clone, Debug)]
+pub struct ValidationLimiter {
+    running: Arc<(Mutex<usize>, Condvar)>,
+    max_running: Option<usize>,
+}
+
+/// Marks a running validation. Frees its slot in the ValidationLimiter when dropped.
+pub struct ValidationPermit {
+    limiter: ValidationLimiter,
+}
+
+impl ValidationLimiter {
+    /// None means no limit.
+    pub fn new(max_running: Option<usize>) -> Self {
+        ValidationLimiter {
+            running: Arc::new((Mutex::new(0), Condvar::new())),
+            max_running: max_running.map(|max| max.max(1)),
+        }
+    }
+
+    pub fn max_running(&self) -> Option<usize> {
+        self.max_running
+    }
+
+    /// Number of validations currently holding a permit.
+    pub fn running(&self) -> usize {
+        *self.running.0.lock().unwrap()
+    }
+
+    /// Blocks until there is a free slot and returns a permit for it.
+    pub fn acquire(&self) -> ValidationPermit {
+        let (ref lock, ref condvar) = *self.running;
+        let mut running = lock.lock().unwrap();
+        if let Some(max_running) = self.max_running {
+            while *running >= max_running {
+                running = condvar.wait(running).unwrap();
+            }
+        }
+        *running += 1;
+        ValidationPermit {
+            limiter: self.clone(),
+        }
+    }
+}
+
+impl Drop for ValidationPermit {
+    fn drop(&mut self) {
+        let (ref lock, ref condvar) = *self.limiter.running;
+        *lock.lock().unwrap() -= 1;
+        condvar.notify_one();
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::{thread, time::Duration};
+
+    #[test]
+    fn test_validation_limiter_caps_concurrent_validations() {
+        let limiter = ValidationLimiter::new(Some(2));
+        let max_seen = Arc::new(Mutex::new(0));
+
+        let handles: Vec<_> = (0..10)
+            .map(|_| {
+                let limiter = limiter.clone();
+                let max_seen = max_seen.clone();
+                thread::spawn(move || {
+                    let _permit = limiter.acquire();
+                    {
+                        let mut max_seen = max_seen.lock().unwrap();
+                        *max_seen = (*max_seen).max(limiter.running());
+                    }
+                    thread::sleep(Duration::from_millis(10));
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert!(*max_seen.lock().unwrap() <= 2);
+        assert_eq!(limiter.running(), 0);
+    }
+
+    #[test]
+    fn test_validation_limiter_without_limit() {
+        let limiter = ValidationLimiter::new(None);
+        let permits: Vec<_> = (0..5).map(|_| limiter.acquire()).collect();
+        assert_eq!(limiter.running(), 5);
+        drop(permits);
+        assert_eq!(limiter.running(), 0);
+    }
+}
@@ -0,0 +1,106 @@
+//! Optional profiling of the reduce pipeline.
+//! If the context has a ReduceProfiler set, State::reduce() times the reducers of the four
+//! state slices and records them together with the type of the action that got reduced.
+//! This tells which slice is slow to reduce when the instance falls behind on actions.
+
+use crate::action::{Action, ActionWrapper};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// How long each slice's reducer took for one action.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SliceTimings {
+    pub nucleus: Duration,
+    pub agent: Duration,
+    pub dht: Duration,
+    pub network: Duration,
+}
+
+impl SliceTimings {
+    pub fn total(&self) -> Duration {
+        self.nucleus + self.agent + self.dht + self.network
+    }
+}
+
+/// The most recently reduced action and its slice timings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReduceProfile {
+    /// Variant name of the action, e.g. "Commit"
+    pub action_type: &'static str,
+    pub timings: SliceTimings,
+}
+
+/// Collects the timings of reduced actions. Clones share the same profile.
+#[derive(Clone, Default)]
+pub struct ReduceProfiler {
+    last: Arc<Mutex<Option<ReduceProfile>>>,
+}
+
+impl ReduceProfiler {
+    pub fn new() -> Self {
+        ReduceProfiler::default()
+    }
+
+    /// Replaces the recorded profile with the one of the given action.
+    pub fn record(&self, action_wrapper: &ActionWrapper, timings: SliceTimings) {
+        *self.last.lock().unwrap() = Some(ReduceProfile {
+            action_type: action_type(action_wrapper),
+            timings,
+        });
+    }
+
+    /// Profile of the most recently reduced action, None if nothing got reduced yet.
+    pub fn last(&self) -> Option<ReduceProfile> {
+        self.last.lock().unwrap().clone()
+    }
+}
+
+/// Variant name of the wrapped action.
+fn action_type(action_wrapper: &ActionWrapper) -> &'static str {
+    match action_wrapper.action() {
+        Action::Commit(_) => "Commit",
+        Action::Hold(_) => "Hold",
+        Action::AddLink(_) => "AddLink",
+        Action::RemoveLink(_) => "RemoveLink",
+        Action::InitNetwork(_) => "InitNetwork",
+        Action::ShutdownNetwork => "ShutdownNetwork",
+        Action::Publish(_) => "Publish",
+        Action::PublishBatch(_) => "PublishBatch",
+        Action::FetchEntry(_) => "FetchEntry",
+        Action::RespondFetch(_) => "RespondFetch",
+        Action::HandleFetchResult(_) => "HandleFetchResult",
+        Action::UpdateEntry(_) => "UpdateEntry",
+        Action::RemoveEntry(_) => "RemoveEntry",
+        Action::GetEntryTimeout(_) => "GetEntryTimeout",
+        Action::GetLinks(_) => "GetLinks",
+        Action::GetLinksTimeout(_) => "GetLinksTimeout",
+        Action::RespondGetLinks(_) => "RespondGetLinks",
+        Action::HandleGetLinksResult(_) => "HandleGetLinksResult",
+        Action::SendDirectMessage(_) => "SendDirectMessage",
+        Action::SendDirectMessageTimeout(_) => "SendDirectMessageTimeout",
+        Action::ResolveDirectConnection(_) => "ResolveDirectConnection",
+        Action::GetValidationPackage(_) => "GetValidationPackage",
+        Action::HandleGetValidationPackage(_) => "HandleGetValidationPackage",
+        Action::HandleCustomSendResponse(_) => "HandleCustomSendResponse",
+        Action::InitApplication(_) => "InitApplication",
+        Action::ReturnInitializationResult(_) => "ReturnInitializationResult",
+        Action::SignalZomeFunctionCall(_) => "SignalZomeFunctionCall",
+        Action::ReturnZomeFunctionResult(_) => "ReturnZomeFunctionResult",
+        Action::ReturnValidationResult(_) => "ReturnValidationResult",
+        Action::ReturnValidationPackage(_) => "ReturnValidationPackage",
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core_types::cas::content::Address;
+
+    #[test]
+    fn action_type_is_the_variant_name() {
+        let action_wrapper = ActionWrapper::new(Action::Publish(Address::from("abc")));
+        assert_eq!(action_type(&action_wrapper), "Publish");
+    }
+}
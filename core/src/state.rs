@@ -8,6 +8,7 @@ use crate::{
     dht::dht_store::DhtStore,
     network::state::NetworkState,
     nucleus::state::NucleusState,
+    reduce_profiler::SliceTimings,
 };
 use holochain_core_types::{
     cas::{
@@ -21,11 +22,20 @@ use holochain_core_types::{
     error::{HcResult, HolochainError},
 };
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     convert::TryInto,
     sync::{Arc, RwLock},
+    time::{Duration, Instant},
 };
 
+/// Headers of an entry as returned by State::get_latest_headers(), newest first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CappedHeaders {
+    pub headers: Vec<ChainHeader>,
+    /// True if the entry has more headers than got returned.
+    pub truncated: bool,
+}
+
 /// The Store of the Holochain instance Object, according to Redux pattern.
 /// It's composed of all sub-module's state slices.
 /// To plug in a new module, its state slice needs to be added here.
@@ -51,85 +61,126 @@ impl State {
         State {
             nucleus: Arc::new(NucleusState::new()),
             agent: Arc::new(AgentState::new(ChainStore::new(chain_cas.clone()))),
-            dht: Arc::new(DhtStore::new(dht_cas.clone(), eav)),
+            dht: Arc::new(
+                DhtStore::new(dht_cas.clone(), eav)
+                    .with_chain_storage(chain_cas.clone())
+                    .with_cache_ttl(context.dht_cache_ttl)
+                    .with_link_index(context.link_index_size),
+            ),
             network: Arc::new(NetworkState::new()),
             history: HashSet::new(),
         }
     }
 
+    /// Creates the state from an existing agent state, taking the DNA from its source chain.
+    /// If the chain holds a DNA entry that can't be read, this gets logged as an error
+    /// and the state is created without DNA.
+    /// Use try_new_with_agent() to fail instead.
     pub fn new_with_agent(context: Arc<Context>, agent_state: Arc<AgentState>) -> Self {
+        let dna = get_dna(&agent_state, context.dht_storage.clone()).unwrap_or_else(|error| {
+            context.log(format!(
+                "err/state: DNA entry in source chain could not be read: {}",
+                error
+            ));
+            None
+        });
+        Self::new_with_agent_and_dna(context, agent_state, dna)
+    }
+
+    /// Like new_with_agent() but returns an error if the source chain holds a DNA entry
+    /// that can't be read, instead of creating a state without DNA.
+    pub fn try_new_with_agent(
+        context: Arc<Context>,
+        agent_state: Arc<AgentState>,
+    ) -> HcResult<Self> {
+        let dna = get_dna(&agent_state, context.dht_storage.clone())?;
+        Ok(Self::new_with_agent_and_dna(context, agent_state, dna))
+    }
+
+    fn new_with_agent_and_dna(
+        context: Arc<Context>,
+        agent_state: Arc<AgentState>,
+        dna: Option<Dna>,
+    ) -> Self {
         // @TODO file table
         // @see https://github.com/holochain/holochain-rust/pull/246
 
         let cas = context.dht_storage.clone();
         let eav = context.eav_storage.clone();
 
-        fn get_dna(
-            agent_state: &Arc<AgentState>,
-            cas: Arc<RwLock<dyn ContentAddressableStorage>>,
-        ) -> HcResult<Dna> {
-            let dna_entry_header = agent_state
-                .chain_store()
-                .iter_type(&agent_state.top_chain_header(), &EntryType::Dna)
-                .last()
-                .ok_or(HolochainError::ErrorGeneric(
-                    "No DNA entry found in source chain while creating state from agent"
-                        .to_string(),
-                ))?;
-            let json = (*cas.read().unwrap()).fetch(dna_entry_header.entry_address())?;
-            let entry: Entry =
-                json.map(|e| e.try_into())
-                    .ok_or(HolochainError::ErrorGeneric(
-                        "No DNA entry found in storage while creating state from agent".to_string(),
-                    ))??;
-            match entry {
-                Entry::Dna(dna) => Ok(dna),
-                _ => Err(HolochainError::SerializationError(
-                    "Tried to get Dna from non-Dna Entry".into(),
-                )),
-            }
-        }
-
         let mut nucleus_state = NucleusState::new();
-        nucleus_state.dna = get_dna(&agent_state, cas.clone()).ok();
+        nucleus_state.dna = dna;
         State {
             nucleus: Arc::new(nucleus_state),
             agent: agent_state,
-            dht: Arc::new(DhtStore::new(cas.clone(), eav.clone())),
+            dht: Arc::new(
+                DhtStore::new(cas.clone(), eav.clone())
+                    .with_chain_storage(context.chain_storage.clone())
+                    .with_cache_ttl(context.dht_cache_ttl)
+                    .with_link_index(context.link_index_size),
+            ),
             network: Arc::new(NetworkState::new()),
             history: HashSet::new(),
         }
     }
 
     pub fn reduce(&self, context: Arc<Context>, action_wrapper: ActionWrapper) -> Self {
+        let profiling = context.reduce_profiler.is_some();
+        let mut timings = SliceTimings::default();
         let mut new_state = State {
-            nucleus: crate::nucleus::reduce(
-                Arc::clone(&context),
-                Arc::clone(&self.nucleus),
-                &action_wrapper,
-            ),
-            agent: crate::agent::state::reduce(
-                Arc::clone(&context),
-                Arc::clone(&self.agent),
-                &action_wrapper,
-            ),
-            dht: crate::dht::dht_reducers::reduce(
-                Arc::clone(&context),
-                Arc::clone(&self.dht),
-                &action_wrapper,
-            ),
-            network: crate::network::reducers::reduce(
-                Arc::clone(&context),
-                Arc::clone(&self.network),
-                &action_wrapper,
-            ),
+            nucleus: time_slice(profiling, &mut timings.nucleus, || {
+                crate::nucleus::reduce(
+                    Arc::clone(&context),
+                    Arc::clone(&self.nucleus),
+                    &action_wrapper,
+                )
+            }),
+            agent: time_slice(profiling, &mut timings.agent, || {
+                crate::agent::state::reduce(
+                    Arc::clone(&context),
+                    Arc::clone(&self.agent),
+                    &action_wrapper,
+                )
+            }),
+            dht: time_slice(profiling, &mut timings.dht, || {
+                crate::dht::dht_reducers::reduce(
+                    Arc::clone(&context),
+                    Arc::clone(&self.dht),
+                    &action_wrapper,
+                )
+            }),
+            network: time_slice(profiling, &mut timings.network, || {
+                crate::network::reducers::reduce(
+                    Arc::clone(&context),
+                    Arc::clone(&self.network),
+                    &action_wrapper,
+                )
+            }),
             history: self.history.clone(),
         };
 
+        if let Some(ref profiler) = context.reduce_profiler {
+            profiler.record(&action_wrapper, timings);
+        }
+
         new_state.history.insert(action_wrapper);
         new_state
     }
 
+    /// Returns a snapshot of this state that keeps all state slices but drops the
+    /// action history, so that the history of subsequent reduces starts out empty.
+    /// Note that the instance uses the history to skip actions it already reduced, so
+    /// actions that were reduced before compacting would get reduced again if re-dispatched.
+    pub fn compact(&self) -> State {
+        State {
+            nucleus: Arc::clone(&self.nucleus),
+            agent: Arc::clone(&self.agent),
+            dht: Arc::clone(&self.dht),
+            network: Arc::clone(&self.network),
+            history: HashSet::new(),
+        }
+    }
+
     pub fn nucleus(&self) -> Arc<NucleusState> {
         Arc::clone(&self.nucleus)
     }
@@ -154,10 +205,7 @@ impl State {
             ChainStore::new(context.dht_storage.clone()),
             snapshot.top_chain_header().clone(),
         );
-        Ok(State::new_with_agent(
-            context.clone(),
-            Arc::new(agent_state),
-        ))
+        State::try_new_with_agent(context.clone(), Arc::new(agent_state))
     }
 
     /// Get all headers for an entry by first looking in the DHT meta store
@@ -169,7 +217,7 @@ impl State {
             .filter(|h| h.entry_address() == &entry_address)
             .collect();
         let header_addresses: Vec<Address> = headers.iter().map(|h| h.address()).collect();
-        let mut dht_headers = self
+        let dht_header_addresses: Vec<Address> = self
             .dht()
             .meta_storage()
             .read()
@@ -186,25 +234,606 @@ impl State {
             .map(|eavi| eavi.value())
             // don't include the chain header twice
             .filter(|a| !header_addresses.contains(a))
-            // fetch the header content from CAS
-            .map(|a| self.dht().content_storage().read().unwrap().fetch(&a))
-            // rearrange
-            .collect::<Result<Vec<Option<_>>, _>>()
-            .map(|r| {
-                r.into_iter()
-                    // ignore None values
-                    .flatten()
-                    .map(|content| ChainHeader::try_from_content(&content))
-                    .collect::<Result<Vec<_>, _>>()
-            })??;
+            .collect();
+        let mut dht_headers = self
+            .dht()
+            .content_storage()
+            .read()
+            .unwrap()
+            // fetch all header contents from CAS in one batch
+            .fetch_many(&dht_header_addresses)?
+            .into_iter()
+            // ignore None values
+            .flatten()
+            .map(|content| ChainHeader::try_from_content(&content))
+            .collect::<Result<Vec<_>, _>>()?;
         {
             let mut all_headers = headers;
             all_headers.append(&mut dht_headers);
             Ok(all_headers)
         }
     }
+
+    /// Like get_headers(), but returns at most `max_headers` headers so that entries with
+    /// lots of updates don't make reading them slow.
+    /// The newest headers of the source chain and the headers most recently stored in the
+    /// DHT shard, up to `max_headers` of each, get merged by timestamp, so newer headers of
+    /// other agents win over older ones of our own. Only those DHT headers get fetched from
+    /// the CAS. The returned headers are sorted by timestamp, newest first.
+    pub fn get_latest_headers(
+        &self,
+        entry_address: Address,
+        max_headers: usize,
+    ) -> Result<CappedHeaders, HolochainError> {
+        // The chain gets walked from its top, so these are the newest ones.
+        // One more than needed tells whether there are more.
+        let mut headers: Vec<ChainHeader> = self
+            .agent()
+            .iter_chain()
+            .filter(|h| h.entry_address() == &entry_address)
+            .take(max_headers + 1)
+            .collect();
+        let header_addresses: Vec<Address> = headers.iter().map(|h| h.address()).collect();
+        let mut dht_eavis: Vec<_> = self
+            .dht()
+            .meta_storage()
+            .read()
+            .unwrap()
+            .fetch_eavi(&EaviQuery::new(
+                Some(entry_address).into(),
+                Some(Attribute::EntryHeader).into(),
+                None.into(),
+                IndexFilter::LatestByAttribute,
+            ))?
+            .into_iter()
+            .filter(|eavi| !header_addresses.contains(&eavi.value()))
+            .collect();
+        let truncated = headers.len() + dht_eavis.len() > max_headers;
+        headers.truncate(max_headers);
+
+        // most recently stored first
+        dht_eavis.sort_by(|a, b| b.index().cmp(&a.index()));
+        let dht_header_addresses: Vec<Address> = dht_eavis
+            .into_iter()
+            .take(max_headers)
+            .map(|eavi| eavi.value())
+            .collect();
+        let mut dht_headers = self
+            .dht()
+            .content_storage()
+            .read()
+            .unwrap()
+            .fetch_many(&dht_header_addresses)?
+            .into_iter()
+            .flatten()
+            .map(|content| ChainHeader::try_from_content(&content))
+            .collect::<Result<Vec<_>, _>>()?;
+        headers.append(&mut dht_headers);
+        headers.sort_by(|a, b| b.timestamp().cmp(a.timestamp()));
+        headers.truncate(max_headers);
+        Ok(CappedHeaders { headers, truncated })
+    }
+
+    /// Returns all entries of the given type this instance knows of, i.e. entries from its
+    /// source chain and entries held in its DHT shard, each together with its header.
+    ///
+    /// The order does not depend on storage iteration order, so nodes that hold the same
+    /// entries return the same list: entries are sorted by the timestamp of their header,
+    /// ascending, and entries with equal timestamps by their address.
+    /// Entries with several headers are listed once, with the earliest of them.
+    pub fn query_entries_by_type(
+        &self,
+        entry_type: &EntryType,
+    ) -> Result<Vec<(ChainHeader, Entry)>, HolochainError> {
+        let dht_headers = self.all_dht_headers()?;
+
+        // keep the earliest header per entry
+        let mut headers_by_entry: HashMap<Address, ChainHeader> = HashMap::new();
+        for header in self.agent().iter_chain().chain(dht_headers.into_iter()) {
+            if header.entry_type() != entry_type {
+                continue;
+            }
+            let sort_key = (header.timestamp().clone(), header.address());
+            let is_earlier = headers_by_entry
+                .get(header.entry_address())
+                .map(|known| sort_key < (known.timestamp().clone(), known.address()))
+                .unwrap_or(true);
+            if is_earlier {
+                headers_by_entry.insert(header.entry_address().clone(), header);
+            }
+        }
+
+        let chain_cas = self.agent().chain_store().content_storage();
+        let dht_cas = self.dht().content_storage();
+        let mut entries = Vec::with_capacity(headers_by_entry.len());
+        for (entry_address, header) in headers_by_entry {
+            let content = match chain_cas.read().unwrap().fetch(&entry_address)? {
+                Some(content) => Some(content),
+                None => dht_cas.read().unwrap().fetch(&entry_address)?,
+            };
+            // headers can be held without their entry, e.g. after it got evicted
+            if let Some(content) = content {
+                entries.push((header, Entry::try_from_content(&content)?));
+            }
+        }
+        entries.sort_by(|(header_a, entry_a), (header_b, entry_b)| {
+            header_a
+                .timestamp()
+                .cmp(header_b.timestamp())
+                .then_with(|| entry_a.address().cmp(&entry_b.address()))
+        });
+        Ok(entries)
+    }
+
+    /// Checks whether the source chain of this instance's agent got forked, i.e. whether
+    /// the agent's key authored two different headers at the same chain position, for
+    /// instance because it is used on two machines.
+    /// Compares the headers of the local source chain with the headers of that agent
+    /// held in the DHT shard and reports the first position, if any, that is occupied by
+    /// more than one header.
+    pub fn detect_fork(&self, context: &Arc<Context>) -> Result<Option<ForkInfo>, HolochainError> {
+        let agent_address = context.agent_id.address();
+        let authored_by_agent = |header: &ChainHeader| {
+            header
+                .provenances()
+                .iter()
+                .any(|(address, _)| *address == agent_address)
+        };
+        let dht_headers = self.all_dht_headers()?;
+        let observed_headers = self
+            .agent()
+            .iter_chain()
+            .chain(dht_headers.into_iter().filter(authored_by_agent));
+
+        // chain position = address of the previous header, None for the first header
+        let mut headers_by_position: HashMap<Option<Address>, Vec<Address>> = HashMap::new();
+        for header in observed_headers {
+            let headers = headers_by_position.entry(header.link()).or_default();
+            let address = header.address();
+            if !headers.contains(&address) {
+                headers.push(address);
+            }
+        }
+
+        let mut forks: Vec<ForkInfo> = headers_by_position
+            .into_iter()
+            .filter(|(_, headers)| headers.len() > 1)
+            .map(|(previous_header, mut conflicting_headers)| {
+                conflicting_headers.sort();
+                ForkInfo {
+                    previous_header,
+                    conflicting_headers,
+                }
+            })
+            .collect();
+        forks.sort_by(|a, b| a.previous_header.cmp(&b.previous_header));
+        Ok(forks.into_iter().next())
+    }
+
+    /// All chain headers held in the DHT shard, for any entry and any author.
+    fn all_dht_headers(&self) -> Result<Vec<ChainHeader>, HolochainError> {
+        let header_addresses: Vec<Address> = self
+            .dht()
+            .meta_storage()
+            .read()
+            .unwrap()
+            // fetch all EAV references to chain headers, for any entry
+            .fetch_eavi(&EaviQuery::new(
+                None.into(),
+                Some(Attribute::EntryHeader).into(),
+                None.into(),
+                IndexFilter::LatestByAttribute,
+            ))?
+            .into_iter()
+            .map(|eavi| eavi.value())
+            .collect();
+        self.dht()
+            .content_storage()
+            .read()
+            .unwrap()
+            .fetch_many(&header_addresses)?
+            .into_iter()
+            .flatten()
+            .map(|content| ChainHeader::try_from_content(&content))
+            .collect()
+    }
+}
+
+/// Two or more different headers of the same agent that follow the same previous header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForkInfo {
+    /// address of the header the conflicting headers link to, None if they both claim
+    /// to be the first header of the chain
+    pub previous_header: Option<Address>,
+    /// addresses of the conflicting headers, sorted
+    pub conflicting_headers: Vec<Address>,
+}
+
+/// Looks up the DNA in the given agent's source chain.
+/// Returns Ok(None) if the chain does not contain a DNA entry (yet)
+/// and an error if it does but the entry can't be read.
+fn get_dna(
+    agent_state: &Arc<AgentState>,
+    cas: Arc<RwLock<dyn ContentAddressableStorage>>,
+) -> HcResult<Option<Dna>> {
+    let dna_entry_header = match agent_state
+        .chain_store()
+        .iter_type(&agent_state.top_chain_header(), &EntryType::Dna)
+        .last()
+    {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+    let json = (*cas.read().unwrap())
+        .fetch(dna_entry_header.entry_address())?
+        .ok_or(HolochainError::ErrorGeneric(
+            "DNA entry referenced by source chain not found in storage".to_string(),
+        ))?;
+    let entry: Entry = json.try_into().map_err(|error: HolochainError| {
+        HolochainError::SerializationError(format!("Could not parse DNA entry: {}", error))
+    })?;
+    match entry {
+        Entry::Dna(dna) => Ok(Some(dna)),
+        _ => Err(HolochainError::SerializationError(
+            "Tried to get Dna from non-Dna Entry".into(),
+        )),
+    }
 }
 
 pub fn test_store(context: Arc<Context>) -> State {
     State::new(context)
 }
+
+/// Runs the reducer of a state slice and, if profiling, stores how long it took in `duration`.
+fn time_slice<T, F: FnOnce() -> T>(profiling: bool, duration: &mut Duration, reduce: F) -> T {
+    if !profiling {
+        return reduce();
+    }
+    let start = Instant::now();
+    let new_slice = reduce();
+    *duration = start.elapsed();
+    new_slice
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        action::Action,
+        instance::tests::test_context_and_logger,
+        nucleus::{
+            actions::call_zome_function::ExecuteZomeFnResponse, state::NucleusStatus, ZomeFnCall,
+        },
+        reduce_profiler::ReduceProfiler,
+    };
+    use holochain_core_types::{
+        cas::content::ExampleAddressableContent,
+        chain_header::test_provenances,
+        entry::entry_type::{test_app_entry_type, test_app_entry_type_b},
+        json::{JsonString, RawString},
+        signature::Signature,
+        time::{test_iso_8601, Iso8601},
+    };
+
+    #[test]
+    fn detect_fork_reports_headers_at_same_position() {
+        let (context, _) = test_context_and_logger("alice", None);
+        let state = State::new(context.clone());
+        let provenances = vec![(context.agent_id.address(), Signature::from("sig"))];
+        let header_for = |entry: &Entry, link: &Option<Address>| {
+            ChainHeader::new(
+                &entry.entry_type(),
+                &entry.address(),
+                &provenances,
+                link,
+                &None,
+                &None,
+                &test_iso_8601(),
+            )
+        };
+        let first_entry = Entry::App(test_app_entry_type(), RawString::from("first").into());
+        let first = header_for(&first_entry, &None);
+        state
+            .dht()
+            .add_header_for_entry(&first_entry, &first)
+            .unwrap();
+
+        let entry_a = Entry::App(test_app_entry_type(), RawString::from("a").into());
+        let header_a = header_for(&entry_a, &Some(first.address()));
+        state
+            .dht()
+            .add_header_for_entry(&entry_a, &header_a)
+            .unwrap();
+        assert_eq!(state.detect_fork(&context), Ok(None));
+
+        // another agent's header at that position is no fork
+        let entry_other = Entry::App(test_app_entry_type(), RawString::from("other").into());
+        let header_other = ChainHeader::new(
+            &entry_other.entry_type(),
+            &entry_other.address(),
+            &test_provenances("sig"),
+            &Some(first.address()),
+            &None,
+            &None,
+            &test_iso_8601(),
+        );
+        state
+            .dht()
+            .add_header_for_entry(&entry_other, &header_other)
+            .unwrap();
+        assert_eq!(state.detect_fork(&context), Ok(None));
+
+        // but a second header of the same agent is
+        let entry_b = Entry::App(test_app_entry_type(), RawString::from("b").into());
+        let header_b = header_for(&entry_b, &Some(first.address()));
+        state
+            .dht()
+            .add_header_for_entry(&entry_b, &header_b)
+            .unwrap();
+
+        let mut conflicting_headers = vec![header_a.address(), header_b.address()];
+        conflicting_headers.sort();
+        assert_eq!(
+            state.detect_fork(&context),
+            Ok(Some(ForkInfo {
+                previous_header: Some(first.address()),
+                conflicting_headers,
+            }))
+        );
+    }
+
+    fn hold_with_timestamp(state: &State, entry: &Entry, timestamp: i64) -> ChainHeader {
+        let header = ChainHeader::new(
+            &entry.entry_type(),
+            &entry.address(),
+            &test_provenances("sig"),
+            &None,
+            &None,
+            &None,
+            &Iso8601::from(timestamp),
+        );
+        state
+            .dht()
+            .content_storage()
+            .write()
+            .unwrap()
+            .add(entry)
+            .unwrap();
+        state.dht().add_header_for_entry(entry, &header).unwrap();
+        header
+    }
+
+    #[test]
+    fn query_entries_by_type_orders_by_timestamp_then_address() {
+        let (context, _) = test_context_and_logger("alice", None);
+        let state = State::new(context.clone());
+        let entry = |value: &str| {
+            Entry::App(
+                test_app_entry_type(),
+                JsonString::from(RawString::from(value)),
+            )
+        };
+
+        // held in a different order than their timestamps
+        let late = entry("late");
+        let early = entry("early");
+        let middle_1 = entry("middle 1");
+        let middle_2 = entry("middle 2");
+        let other_type = Entry::App(test_app_entry_type_b(), RawString::from("other").into());
+        hold_with_timestamp(&state, &late, 300);
+        hold_with_timestamp(&state, &middle_1, 200);
+        hold_with_timestamp(&state, &early, 100);
+        hold_with_timestamp(&state, &middle_2, 200);
+        hold_with_timestamp(&state, &other_type, 50);
+        // a later header for an entry that is already known doesn't change its position
+        hold_with_timestamp(&state, &early, 400);
+
+        let mut middle = vec![middle_1, middle_2];
+        middle.sort_by_key(|entry| entry.address());
+        let expected = vec![
+            early.clone(),
+            middle[0].clone(),
+            middle[1].clone(),
+            late.clone(),
+        ];
+
+        let results = state
+            .query_entries_by_type(&EntryType::App(test_app_entry_type()))
+            .unwrap();
+        let entries: Vec<Entry> = results.iter().map(|(_, entry)| entry.clone()).collect();
+        assert_eq!(entries, expected);
+        let timestamps: Vec<Iso8601> = results
+            .iter()
+            .map(|(header, _)| header.timestamp().clone())
+            .collect();
+        assert_eq!(
+            timestamps,
+            vec![
+                Iso8601::from(100),
+                Iso8601::from(200),
+                Iso8601::from(200),
+                Iso8601::from(300)
+            ]
+        );
+    }
+
+    #[test]
+    fn get_latest_headers_returns_newest_headers_up_to_max() {
+        let (context, _) = test_context_and_logger("alice", None);
+        let state = State::new(context.clone());
+        let entry = Entry::App(test_app_entry_type(), RawString::from("updated").into());
+        let headers: Vec<ChainHeader> = (1..=5)
+            .map(|timestamp| hold_with_timestamp(&state, &entry, timestamp * 100))
+            .collect();
+
+        let capped = state.get_latest_headers(entry.address(), 3).unwrap();
+        assert_eq!(
+            capped.headers,
+            vec![headers[4].clone(), headers[3].clone(), headers[2].clone()]
+        );
+        assert!(capped.truncated);
+
+        let all = state.get_latest_headers(entry.address(), 5).unwrap();
+        assert_eq!(all.headers.len(), 5);
+        assert!(!all.truncated);
+        assert_eq!(state.get_headers(entry.address()).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn get_latest_headers_prefers_newer_dht_headers_over_own_ones() {
+        let (context, _) = test_context_and_logger("alice", None);
+        let entry = Entry::App(test_app_entry_type(), RawString::from("updated").into());
+        let own_header = |link: &Option<Address>, timestamp: i64| {
+            let header = ChainHeader::new(
+                &entry.entry_type(),
+                &entry.address(),
+                &vec![(context.agent_id.address(), Signature::from("sig"))],
+                link,
+                &None,
+                &None,
+                &Iso8601::from(timestamp),
+            );
+            context.chain_storage.write().unwrap().add(&header).unwrap();
+            header
+        };
+        let first = own_header(&None, 100);
+        let second = own_header(&Some(first.address()), 200);
+        let agent_state = AgentState::new_with_top_chain_header(
+            ChainStore::new(context.chain_storage.clone()),
+            second.clone(),
+        );
+        let state = State::new_with_agent(context.clone(), Arc::new(agent_state));
+        let newer = hold_with_timestamp(&state, &entry, 300);
+        let newest = hold_with_timestamp(&state, &entry, 400);
+
+        let capped = state.get_latest_headers(entry.address(), 2).unwrap();
+        assert_eq!(capped.headers, vec![newest, newer.clone()]);
+        assert!(capped.truncated);
+
+        let capped = state.get_latest_headers(entry.address(), 3).unwrap();
+        assert_eq!(capped.headers[1..], [newer, second][..]);
+        assert!(capped.truncated);
+    }
+
+    #[test]
+    fn compact_keeps_slices_and_drops_history() {
+        let (context, _) = test_context_and_logger("alice", None);
+        let zome_call = ZomeFnCall::new("zome", None, "fn", "{}");
+        let state = State::new(context.clone())
+            .reduce(
+                context.clone(),
+                ActionWrapper::new(Action::InitApplication(Dna::new())),
+            )
+            .reduce(
+                context.clone(),
+                ActionWrapper::new(Action::ReturnInitializationResult(None)),
+            )
+            .reduce(
+                context.clone(),
+                ActionWrapper::new(Action::ReturnZomeFunctionResult(
+                    ExecuteZomeFnResponse::new(zome_call.clone(), Ok(JsonString::null())),
+                )),
+            );
+        assert_eq!(state.history.len(), 3);
+
+        let compacted = state.compact();
+        assert!(compacted.history.is_empty());
+        assert_eq!(compacted.nucleus(), state.nucleus());
+        assert_eq!(compacted.agent(), state.agent());
+        assert_eq!(compacted.dht(), state.dht());
+        assert_eq!(compacted.network(), state.network());
+        assert_eq!(compacted.nucleus().status(), NucleusStatus::Initialized);
+        assert_eq!(
+            compacted.nucleus().zome_call_result(&zome_call),
+            Some(Ok(JsonString::null()))
+        );
+
+        // reducing continues from the compacted state with a fresh history
+        let other_call = ZomeFnCall::new("zome", None, "other_fn", "{}");
+        let action_wrapper = ActionWrapper::new(Action::ReturnZomeFunctionResult(
+            ExecuteZomeFnResponse::new(other_call.clone(), Ok(JsonString::null())),
+        ));
+        let reduced = compacted.reduce(context.clone(), action_wrapper.clone());
+        assert_eq!(reduced.history.len(), 1);
+        assert!(reduced.history.contains(&action_wrapper));
+        assert_eq!(
+            reduced.nucleus().zome_call_result(&other_call),
+            Some(Ok(JsonString::null()))
+        );
+        assert_eq!(reduced.nucleus().dna(), state.nucleus().dna());
+    }
+
+    #[test]
+    fn new_with_agent_reports_unparseable_dna() {
+        let (context, logger) = test_context_and_logger("bob", None);
+
+        // A chain without DNA entry yields a state without DNA, no error
+        let agent_state = AgentState::new(ChainStore::new(context.dht_storage.clone()));
+        let state = State::try_new_with_agent(context.clone(), Arc::new(agent_state)).unwrap();
+        assert_eq!(state.nucleus().dna(), None);
+
+        // A DNA header pointing to content that is not a DNA entry
+        let garbage =
+            ExampleAddressableContent::try_from_content(&RawString::from("not a DNA").into())
+                .unwrap();
+        let header = ChainHeader::new(
+            &EntryType::Dna,
+            &garbage.address(),
+            &test_provenances("sig"),
+            &None,
+            &None,
+            &None,
+            &test_iso_8601(),
+        );
+        {
+            let mut cas = context.dht_storage.write().unwrap();
+            cas.add(&garbage).unwrap();
+            cas.add(&header).unwrap();
+        }
+        let agent_state = Arc::new(AgentState::new_with_top_chain_header(
+            ChainStore::new(context.dht_storage.clone()),
+            header,
+        ));
+
+        match State::try_new_with_agent(context.clone(), agent_state.clone()) {
+            Err(HolochainError::SerializationError(message)) => {
+                assert!(message.starts_with("Could not parse DNA entry"))
+            }
+            other => panic!("expected a SerializationError, got {:?}", other),
+        }
+
+        let state = State::new_with_agent(context.clone(), agent_state);
+        assert_eq!(state.nucleus().dna(), None);
+        assert!(logger
+            .lock()
+            .unwrap()
+            .log
+            .iter()
+            .any(|line| line.contains("err/state: DNA entry in source chain could not be read")));
+    }
+
+    #[test]
+    fn reduce_records_action_type_and_slice_timings_if_profiling() {
+        let (context, _) = test_context_and_logger("alice", None);
+        let state = State::new(context.clone());
+        let action_wrapper = ActionWrapper::new(Action::Publish(Address::from("abc")));
+
+        let profiler = ReduceProfiler::new();
+        let mut profiled_context = (*context).clone();
+        profiled_context.reduce_profiler = Some(profiler.clone());
+        let profiled_context = Arc::new(profiled_context);
+        let state = state.reduce(profiled_context.clone(), action_wrapper);
+        let profile = profiler.last().expect("reduce should have been profiled");
+        assert_eq!(profile.action_type, "Publish");
+        assert!(profile.timings.total() > Duration::from_nanos(0));
+
+        // reducing with a context without the profiler records nothing
+        let mut unprofiled_context = (*profiled_context).clone();
+        unprofiled_context.reduce_profiler = None;
+        let _ = state.reduce(
+            Arc::new(unprofiled_context),
+            ActionWrapper::new(Action::ResolveDirectConnection("id".to_string())),
+        );
+        assert_eq!(profiler.last(), Some(profile));
+    }
+}
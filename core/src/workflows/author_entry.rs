@@ -13,18 +13,44 @@ use holochain_core_types::{
     error::HolochainError,
     validation::{EntryAction, EntryLifecycle, ValidationData},
 };
+use holochain_wasm_utils::api_serialization::CommitEntryOptions;
 use std::sync::Arc;
 
 pub async fn author_entry<'a>(
     entry: &'a Entry,
     maybe_crud_link: Option<Address>,
     context: &'a Arc<Context>,
+) -> Result<Address, HolochainError> {
+    await!(author_entry_with_options(
+        entry,
+        maybe_crud_link,
+        &CommitEntryOptions::default(),
+        context
+    ))
+}
+
+/// Like author_entry, but the entry only gets published if the options ask for it.
+/// Entries committed without publishing can get published later with publish_authored_entry.
+pub async fn author_entry_with_options<'a>(
+    entry: &'a Entry,
+    maybe_crud_link: Option<Address>,
+    options: &'a CommitEntryOptions,
+    context: &'a Arc<Context>,
 ) -> Result<Address, HolochainError> {
     let address = entry.address();
     context.log(format!(
         "debug/workflow/authoring_entry: {} with content: {:?}",
         address, entry
     ));
+    // 0. Idempotent commits of the entry that is already on top of the chain do nothing
+    if options.idempotent && maybe_crud_link.is_none() && is_top_entry(&address, context) {
+        context.log(format!(
+            "debug/workflow/authoring_entry/{}: already the most recent commit",
+            address
+        ));
+        return Ok(address);
+    }
+
     // 1. Build the context needed for validation of the entry
     let validation_package = await!(build_validation_package(&entry, context.clone()))?;
     let validation_data = ValidationData {
@@ -53,33 +79,139 @@ pub async fn author_entry<'a>(
     ));
 
     // 4. Publish the valid entry to DHT. This will call Hold to itself
-    //TODO: missing a general public/private sharing check here, for now just
-    // using the entry_type can_publish() function which isn't enough
-    if entry.entry_type().can_publish() {
+    if !options.publish {
         context.log(format!(
-            "debug/workflow/authoring_entry/{}: publishing...",
+            "debug/workflow/authoring_entry/{}: publishing suppressed",
             address
         ));
-        await!(publish(entry.address(), &context))?;
+        return Ok(addr);
+    }
+    await!(publish_authored_entry(entry, context))?;
+    Ok(addr)
+}
+
+/// True if the most recent header of the agent's source chain is the one of the given
+/// entry and did not update or remove another entry.
+fn is_top_entry(address: &Address, context: &Arc<Context>) -> bool {
+    context
+        .state()
+        .and_then(|state| state.agent().top_chain_header())
+        .map_or(false, |header| {
+            header.entry_address() == address && header.link_crud().is_none()
+        })
+}
+
+/// Publishes an entry of the agent's source chain to the DHT,
+/// through the publish batcher if the context has one.
+pub async fn publish_authored_entry<'a>(
+    entry: &'a Entry,
+    context: &'a Arc<Context>,
+) -> Result<(), HolochainError> {
+    let address = entry.address();
+    //TODO: missing a general public/private sharing check here, for now just
+    // using the entry_type can_publish() function which isn't enough
+    if !entry.entry_type().can_publish() {
         context.log(format!(
-            "debug/workflow/authoring_entry/{}: published!",
+            "debug/workflow/authoring_entry/{}: entry is private, no publishing",
             address
         ));
-    } else {
+        return Ok(());
+    }
+    if let Some(ref publish_batcher) = context.publish_batcher {
         context.log(format!(
-            "debug/workflow/authoring_entry/{}: entry is private, no publishing",
+            "debug/workflow/authoring_entry/{}: queued for batched publishing",
             address
         ));
+        publish_batcher.add(address, &context);
+        return Ok(());
     }
-    Ok(addr)
+    context.log(format!(
+        "debug/workflow/authoring_entry/{}: publishing...",
+        address
+    ));
+    await!(publish(address.clone(), &context))?;
+    context.log(format!(
+        "debug/workflow/authoring_entry/{}: published!",
+        address
+    ));
+    Ok(())
 }
 
 #[cfg(test)]
 pub mod tests {
-    use super::author_entry;
-    use crate::nucleus::actions::tests::*;
-    use holochain_core_types::{entry::test_entry, json::JsonString};
-    use std::{thread, time};
+    use super::{author_entry, author_entry_with_options, publish_authored_entry};
+    use crate::{context::Context, nucleus::actions::tests::*};
+    use holochain_core_types::{
+        cas::content::{Address, AddressableContent},
+        entry::{test_entry, Entry},
+        error::{DnaError, HolochainError},
+        json::JsonString,
+    };
+    use holochain_wasm_utils::api_serialization::CommitEntryOptions;
+    use std::{sync::Arc, thread, time};
+
+    fn fetch_from_dht(context: &Arc<Context>, address: &Address) -> Option<JsonString> {
+        context
+            .state()
+            .unwrap()
+            .dht()
+            .content_storage()
+            .read()
+            .unwrap()
+            .fetch(address)
+            .expect("could not fetch from CAS")
+    }
+
+    #[test]
+    fn author_entry_fails_early_for_undefined_entry_type() {
+        let (_instance, context) =
+            instance(Some("author_entry_fails_early_for_undefined_entry_type"));
+
+        let undefined = Entry::App("undefined_entry_type".into(), JsonString::from("{}"));
+        assert_eq!(
+            context.block_on(author_entry(&undefined, None, &context)),
+            Err(HolochainError::Dna(DnaError::EntryTypeNotFound(
+                "undefined_entry_type".to_string()
+            )))
+        );
+
+        let defined = test_entry();
+        assert_eq!(
+            context.block_on(author_entry(&defined, None, &context)),
+            Ok(defined.address())
+        );
+    }
+
+    #[test]
+    fn idempotent_commit_of_top_entry_adds_no_header() {
+        let (_instance, context) = instance(Some("idempotent_commit_of_top_entry_adds_no_header"));
+        let chain_length = || context.state().unwrap().agent().iter_chain().count();
+        let options = CommitEntryOptions::new(false).with_idempotent(true);
+        let entry = test_entry();
+
+        let initial_length = chain_length();
+        let first = context
+            .block_on(author_entry_with_options(&entry, None, &options, &context))
+            .unwrap();
+        assert_eq!(chain_length(), initial_length + 1);
+
+        let second = context
+            .block_on(author_entry_with_options(&entry, None, &options, &context))
+            .unwrap();
+        assert_eq!(second, first);
+        assert_eq!(chain_length(), initial_length + 1);
+
+        // without the option, committing the same entry again appends as before
+        context
+            .block_on(author_entry_with_options(
+                &entry,
+                None,
+                &CommitEntryOptions::new(false),
+                &context,
+            ))
+            .unwrap();
+        assert_eq!(chain_length(), initial_length + 2);
+    }
 
     #[test]
     #[cfg(not(windows))]
@@ -122,4 +254,44 @@ pub mod tests {
             "{\"App\":[\"testEntryType\",\"\\\"test entry value\\\"\"]}".to_string(),
         );
     }
+
+    #[test]
+    #[cfg(not(windows))]
+    /// test that an entry committed with publishing suppressed only reaches the dht
+    /// of a connected instance once it gets published explicitly
+    fn test_commit_without_publish() {
+        let mut dna = test_dna();
+        dna.uuid = "test_commit_without_publish".to_string();
+        let netname = Some("test_commit_without_publish, the network");
+        let (_instance1, context1) = instance_by_name("jill", dna.clone(), netname);
+        let (_instance2, context2) = instance_by_name("jack", dna, netname);
+
+        let entry = test_entry();
+        let entry_address = context1
+            .block_on(author_entry_with_options(
+                &entry,
+                None,
+                &CommitEntryOptions::new(false),
+                &context1,
+            ))
+            .unwrap();
+        thread::sleep(time::Duration::from_millis(2000));
+        assert_eq!(fetch_from_dht(&context1, &entry_address), None);
+        assert_eq!(fetch_from_dht(&context2, &entry_address), None);
+
+        context1
+            .block_on(publish_authored_entry(&entry, &context1))
+            .unwrap();
+
+        let mut json: Option<JsonString> = None;
+        let mut tries = 0;
+        while json.is_none() && tries < 120 {
+            tries = tries + 1;
+            json = fetch_from_dht(&context2, &entry_address);
+            if json.is_none() {
+                thread::sleep(time::Duration::from_millis(1000));
+            }
+        }
+        assert_eq!(json, Some(entry.content()));
+    }
 }
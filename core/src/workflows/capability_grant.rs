@@ -0,0 +1,84 @@
+use crate::{
+    agent::actions::commit::commit_entry, context::Context,
+    nucleus::actions::get_entry::get_entry_from_agent, workflows::author_entry::author_entry,
+};
+
+use holochain_core_types::{
+    cas::content::Address,
+    entry::{cap_entries::CapTokenGrant, deletion_entry::DeletionEntry, Entry},
+    error::HolochainError,
+};
+use std::sync::Arc;
+
+/// Commits the given grant to the agent's source chain and returns its token,
+/// i.e. the address of the grant entry.
+/// Grants are private entries, so nothing gets published.
+pub async fn mint_capability<'a>(
+    grant: CapTokenGrant,
+    context: &'a Arc<Context>,
+) -> Result<Address, HolochainError> {
+    await!(author_entry(&Entry::CapTokenGrant(grant), None, context))
+}
+
+/// Marks the grant with the given token as deleted by committing a deletion entry
+/// that links to it. Zome calls presenting a revoked token fail the capability check.
+/// The deletion entry only goes to the source chain and does not get published,
+/// since it contains the token.
+pub async fn revoke_capability<'a>(
+    token: &'a Address,
+    context: &'a Arc<Context>,
+) -> Result<(), HolochainError> {
+    match get_entry_from_agent(context, token)? {
+        Some(Entry::CapTokenGrant(_)) => {}
+        _ => {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "No capability grant with token {} in source chain",
+                token
+            )));
+        }
+    }
+    let deletion_entry = Entry::Deletion(DeletionEntry::new(token.clone()));
+    await!(commit_entry(deletion_entry, Some(token.clone()), context))?;
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::instance::tests::test_instance_and_context;
+    use holochain_core_types::{
+        dna::capabilities::CapabilityType, entry::cap_entries::GrantedFunction,
+    };
+    use test_utils;
+
+    fn test_grant(zome: &str, function: &str) -> CapTokenGrant {
+        CapTokenGrant::create(CapabilityType::Transferable, None)
+            .unwrap()
+            .with_functions(vec![GrantedFunction::new(zome, function)])
+    }
+
+    #[test]
+    fn grants_may_only_reference_declared_functions() {
+        let dna = test_utils::create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (_instance, context) =
+            test_instance_and_context(dna, None).expect("Could not create test instance");
+
+        let result = context.block_on(mint_capability(
+            test_grant("test_zome", "public_test_fn"),
+            &context,
+        ));
+        assert!(result.is_ok(), "mint_capability() result = {:?}", result);
+
+        for (zome, function) in &[
+            ("test_zome", "missing_fn"),
+            ("missing_zome", "public_test_fn"),
+        ] {
+            match context.block_on(mint_capability(test_grant(zome, function), &context)) {
+                Err(HolochainError::ValidationFailed(message)) => {
+                    assert!(message.starts_with("Grant references undeclared function"))
+                }
+                other => panic!("Expected grant to fail validation, got {:?}", other),
+            }
+        }
+    }
+}
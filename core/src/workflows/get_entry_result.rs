@@ -1,11 +1,16 @@
-use crate::{context::Context, network, nucleus};
-use holochain_core_types::{chain_header::ChainHeader, time::Timeout};
+use crate::{
+    context::Context,
+    network::{self, actions::get_entry::FetchedEntry},
+    nucleus,
+};
+use futures::Future;
+use holochain_core_types::time::Timeout;
 
 use holochain_core_types::{
-    cas::content::{Address, AddressableContent},
+    cas::content::{Address, AddressableContent, Content},
     crud_status::CrudStatus,
     entry::EntryWithMeta,
-    error::HolochainError,
+    error::{HcResult, HolochainError},
 };
 use holochain_wasm_utils::api_serialization::get_entry::{
     GetEntryArgs, GetEntryResult, StatusRequestKind,
@@ -26,13 +31,109 @@ pub async fn get_entry_with_meta_workflow<'a>(
         return Ok(maybe_entry_with_meta);
     }
     // 2. No result, so try on the network
-    await!(network::actions::get_entry::get_entry(
-        context.clone(),
-        address.clone(),
-        timeout.clone(),
+    await!(retry_on_network_error(
+        context,
+        context.network_fetch_retries,
+        || network::actions::get_entry::get_entry(
+            context.clone(),
+            address.clone(),
+            timeout.clone(),
+        )
+    ))
+}
+
+/// Like get_entry_with_meta_workflow(), but if a known version is given, the network
+/// answers FetchedEntry::NotModified instead of sending that version again, see
+/// network::actions::get_entry::get_entry_if_newer_than().
+/// Entries found locally are always returned since they cost no bandwidth.
+async fn get_entry_with_meta_if_newer_than_workflow<'a>(
+    context: &'a Arc<Context>,
+    address: &'a Address,
+    if_newer_than: &'a Option<Address>,
+    timeout: &'a Timeout,
+) -> Result<FetchedEntry, HolochainError> {
+    // 1. Try to get the entry locally (i.e. local DHT shard)
+    let maybe_entry_with_meta =
+        nucleus::actions::get_entry::get_entry_with_meta(context, address.clone())?;
+    if maybe_entry_with_meta.is_some() {
+        return Ok(FetchedEntry::Entry(maybe_entry_with_meta));
+    }
+    // 2. No result, so try on the network
+    await!(retry_on_network_error(
+        context,
+        context.network_fetch_retries,
+        || network::actions::get_entry::get_entry_if_newer_than(
+            context.clone(),
+            address.clone(),
+            if_newer_than.clone(),
+            timeout.clone(),
+        )
     ))
 }
 
+/// Errors of network fetches that might not happen again when retrying.
+/// Not-found results are not errors (but Ok(None)) and so never get retried.
+fn is_transient_network_error(error: &HolochainError) -> bool {
+    match error {
+        HolochainError::IoError(_) | HolochainError::Timeout => true,
+        _ => false,
+    }
+}
+
+/// Runs the network fetch returned by the given closure and runs it again,
+/// up to `retries` more times, as long as it fails with a transient network error.
+pub async fn retry_on_network_error<'a, T, F, Fut>(
+    context: &'a Arc<Context>,
+    retries: usize,
+    fetch: F,
+) -> HcResult<T>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = HcResult<T>>,
+{
+    let mut retries_left = retries;
+    loop {
+        match await!(fetch()) {
+            Err(ref error) if retries_left > 0 && is_transient_network_error(error) => {
+                retries_left -= 1;
+                context.log(format!(
+                    "debug/workflow/get_entry: network fetch failed, retrying: {}",
+                    error
+                ));
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Get raw content workflow
+///
+/// Looks up the raw content locally without parsing it.
+/// Content that has to be fetched from the network gets transmitted as a typed entry,
+/// so in that case it is the content of the received entry.
+pub async fn get_raw_content_workflow<'a>(
+    context: &'a Arc<Context>,
+    address: &'a Address,
+    timeout: &'a Timeout,
+) -> Result<Option<Content>, HolochainError> {
+    // 1. Try to get the content locally (i.e. source chain or local DHT shard)
+    let maybe_content = nucleus::actions::get_entry::get_raw_content(context, address)?;
+    if maybe_content.is_some() {
+        return Ok(maybe_content);
+    }
+    // 2. No result, so try on the network
+    let maybe_entry_with_meta = await!(retry_on_network_error(
+        context,
+        context.network_fetch_retries,
+        || network::actions::get_entry::get_entry(
+            context.clone(),
+            address.clone(),
+            timeout.clone(),
+        )
+    ))?;
+    Ok(maybe_entry_with_meta.map(|entry_with_meta| entry_with_meta.entry.content()))
+}
+
 /// Get GetEntryResult workflow
 pub async fn get_entry_result_workflow<'a>(
     context: &'a Arc<Context>,
@@ -41,17 +142,30 @@ pub async fn get_entry_result_workflow<'a>(
     // Setup
     let mut entry_result = GetEntryResult::new(args.options.status_request.clone(), None);
     let mut maybe_address = Some(args.address.clone());
+    // Holders can only tell if a single version is not modified, not a whole history
+    let if_newer_than = match args.options.status_request {
+        StatusRequestKind::All => None,
+        _ => args.options.if_newer_than.clone(),
+    };
 
     // Accumulate entry history in a loop unless only request initial.
     while maybe_address.is_some() {
         let address = maybe_address.unwrap();
         maybe_address = None;
         // Try to get entry
-        let maybe_entry_with_meta = await!(get_entry_with_meta_workflow(
-            context,
-            &address,
-            &args.options.timeout
-        ))?;
+        let maybe_entry_with_meta =
+            match await!(get_entry_with_meta_if_newer_than_workflow(
+                context,
+                &address,
+                &if_newer_than,
+                &args.options.timeout
+            ))? {
+                FetchedEntry::Entry(maybe_entry_with_meta) => maybe_entry_with_meta,
+                FetchedEntry::NotModified => {
+                    entry_result.mark_not_modified();
+                    break;
+                }
+            };
         // Entry found
         if let Some(entry_with_meta) = maybe_entry_with_meta {
             // Erase history if request is for latest
@@ -63,17 +177,26 @@ pub async fn get_entry_result_workflow<'a>(
             }
 
             // Add entry
-            let headers: Vec<ChainHeader> = if args.options.headers {
-                context
-                    .state()
-                    .expect("state uninitialized! :)")
-                    .get_headers(entry_with_meta.entry.address().clone())?
-            } else {
-                Vec::new()
-            };
+            let state = context.state().expect("state uninitialized! :)");
+            let entry_address = entry_with_meta.entry.address().clone();
+            let (headers, headers_truncated) =
+                match (args.options.headers, context.max_headers_per_entry) {
+                    (false, _) => (Vec::new(), false),
+                    (true, None) => (state.get_headers(entry_address)?, false),
+                    (true, Some(max_headers)) => {
+                        let capped = state.get_latest_headers(entry_address, max_headers)?;
+                        (capped.headers, capped.truncated)
+                    }
+                };
             entry_result.push(&entry_with_meta, headers);
+            if headers_truncated {
+                entry_result.mark_headers_truncated();
+            }
 
-            if args.options.status_request == StatusRequestKind::Initial {
+            if args.options.status_request == StatusRequestKind::Initial
+                || (args.options.status_request == StatusRequestKind::Latest
+                    && !args.options.resolve_latest)
+            {
                 break;
             }
 
@@ -86,6 +209,10 @@ pub async fn get_entry_result_workflow<'a>(
             }
         }
     }
+    // Entries found locally or in a history don't get checked by the holder
+    if let Some(ref known_version) = args.options.if_newer_than {
+        entry_result.check_not_modified(known_version);
+    }
     Ok(entry_result)
 }
 
@@ -124,3 +251,256 @@ pub async fn get_entry_result_workflow<'a>(
 ////        assert_eq!(&entry, entry_history.entries.iter().next().unwrap());
 //    }
 //}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        action::GetEntryKey,
+        agent::actions::update_entry::update_entry,
+        instance::tests::{test_context, test_instance_and_context},
+        network::handler::get::handle_fetch_entry_result,
+        workflows::author_entry::author_entry,
+    };
+    use futures::{executor::block_on, future};
+    use holochain_core_types::entry::{entry_type::test_app_entry_type, Entry};
+    use holochain_net::connection::{
+        json_protocol::{FetchEntryResultData, JsonProtocol, TrackDnaData},
+        net_connection::NetSend,
+    };
+    use holochain_wasm_utils::api_serialization::get_entry::GetEntryOptions;
+    use std::{
+        sync::Mutex,
+        thread,
+        time::{Duration, Instant},
+    };
+    use test_utils;
+
+    /// Runs retry_on_network_error() with a fetch that returns the given results in order.
+    /// Returns the final result and the number of fetch attempts.
+    fn run_fetch(
+        retries: usize,
+        results: Vec<HcResult<Option<u32>>>,
+    ) -> (HcResult<Option<u32>>, usize) {
+        let context = test_context("alice", None);
+        let results = Mutex::new(results.into_iter());
+        let attempts = Mutex::new(0);
+        let result = block_on(retry_on_network_error(&context, retries, || {
+            *attempts.lock().unwrap() += 1;
+            future::ready(results.lock().unwrap().next().expect("fetched too often"))
+        }));
+        let attempts = *attempts.lock().unwrap();
+        (result, attempts)
+    }
+
+    #[test]
+    fn fetch_succeeds_after_transient_failure() {
+        assert_eq!(
+            run_fetch(1, vec![Err(HolochainError::Timeout), Ok(Some(42))]),
+            (Ok(Some(42)), 2)
+        );
+        assert_eq!(
+            run_fetch(
+                3,
+                vec![
+                    Err(HolochainError::IoError("connection reset".into())),
+                    Ok(Some(42))
+                ]
+            ),
+            (Ok(Some(42)), 2)
+        );
+    }
+
+    #[test]
+    fn fetch_fails_when_retries_are_used_up() {
+        assert_eq!(
+            run_fetch(0, vec![Err(HolochainError::Timeout)]),
+            (Err(HolochainError::Timeout), 1)
+        );
+        assert_eq!(
+            run_fetch(
+                1,
+                vec![Err(HolochainError::Timeout), Err(HolochainError::Timeout)]
+            ),
+            (Err(HolochainError::Timeout), 2)
+        );
+    }
+
+    #[test]
+    fn not_found_and_other_errors_are_not_retried() {
+        assert_eq!(run_fetch(3, vec![Ok(None)]), (Ok(None), 1));
+        assert_eq!(
+            run_fetch(3, vec![Err(HolochainError::NetworkNotInitialized)]),
+            (Err(HolochainError::NetworkNotInitialized), 1)
+        );
+    }
+
+    /// Stops the memory network from handing fetches of the given context to any node,
+    /// so they stay unanswered until they time out.
+    fn drop_fetches_of(context: &Arc<Context>) {
+        let network_state = context.state().unwrap().network();
+        network_state
+            .network
+            .expect("network should be initialized")
+            .lock()
+            .unwrap()
+            .send(
+                JsonProtocol::UntrackDna(TrackDnaData {
+                    dna_address: network_state.dna_address.unwrap(),
+                    agent_id: network_state.agent_id.unwrap(),
+                })
+                .into(),
+            )
+            .expect("Could not untrack DNA");
+    }
+
+    /// Mock of a node holding the given entry that loses the first fetch of it and
+    /// answers the second one. Returns the number of fetches it saw.
+    fn spawn_flaky_holder(context: Arc<Context>, entry: Entry) -> thread::JoinHandle<usize> {
+        thread::spawn(move || {
+            let mut fetches: Vec<GetEntryKey> = Vec::new();
+            let start = Instant::now();
+            while start.elapsed() < Duration::from_secs(5) {
+                let network_state = context.state().unwrap().network();
+                let new_fetches: Vec<GetEntryKey> = network_state
+                    .get_entry_with_meta_results
+                    .iter()
+                    .filter(|(key, result)| key.address == entry.address() && result.is_none())
+                    .map(|(key, _)| key.clone())
+                    .filter(|key| !fetches.contains(key))
+                    .collect();
+                for key in new_fetches {
+                    fetches.push(key.clone());
+                    if fetches.len() == 2 {
+                        let entry_with_meta = EntryWithMeta {
+                            entry: entry.clone(),
+                            crud_status: CrudStatus::Live,
+                            maybe_crud_link: None,
+                        };
+                        handle_fetch_entry_result(
+                            FetchEntryResultData {
+                                dna_address: network_state.dna_address.clone().unwrap(),
+                                request_id: key.id,
+                                requester_agent_id: network_state.agent_id.clone().unwrap(),
+                                provider_agent_id: String::from("flaky holder"),
+                                entry_address: entry.address(),
+                                entry_content: serde_json::to_value(&entry_with_meta).unwrap(),
+                                not_modified: false,
+                            },
+                            context.clone(),
+                        );
+                    }
+                }
+                if fetches.len() >= 2 {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            fetches.len()
+        })
+    }
+
+    #[test]
+    fn get_entry_retries_a_fetch_that_timed_out() {
+        let dna = test_utils::create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (_instance, context) =
+            test_instance_and_context(dna, None).expect("Could not create test instance");
+        let mut retrying_context = (*context).clone();
+        retrying_context.network_fetch_retries = 1;
+        let context = Arc::new(retrying_context);
+        drop_fetches_of(&context);
+
+        let entry = Entry::App(test_app_entry_type(), "held by someone else".into());
+        let holder = spawn_flaky_holder(context.clone(), entry.clone());
+        let result = context.block_on(get_entry_with_meta_workflow(
+            &context,
+            &entry.address(),
+            &Timeout::new(300),
+        ));
+
+        assert_eq!(
+            result.map(|found| found.map(|found| found.entry)),
+            Ok(Some(entry))
+        );
+        assert_eq!(holder.join().unwrap(), 2);
+    }
+
+    #[test]
+    fn get_entry_follows_updates_only_with_resolve_latest() {
+        let dna = test_utils::create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (_instance, context) =
+            test_instance_and_context(dna, None).expect("Could not create test instance");
+        let original = Entry::App(test_app_entry_type(), "original value".into());
+        let updated = Entry::App(test_app_entry_type(), "updated value".into());
+        for entry in &[&original, &updated] {
+            context
+                .block_on(author_entry(entry, None, &context))
+                .expect("Could not author entry");
+        }
+        context
+            .block_on(update_entry(
+                &context,
+                context.action_channel(),
+                original.address(),
+                updated.address(),
+            ))
+            .expect("Could not update entry");
+
+        let get_original = |resolve_latest| {
+            let args = GetEntryArgs {
+                address: original.address(),
+                options: GetEntryOptions {
+                    resolve_latest,
+                    ..Default::default()
+                },
+            };
+            context
+                .block_on(get_entry_result_workflow(&context, &args))
+                .expect("Could not get entry")
+                .latest()
+        };
+        assert_eq!(get_original(true), Some(updated.clone()));
+        assert_eq!(get_original(false), Some(original.clone()));
+    }
+
+    #[test]
+    fn conditional_get_returns_only_newer_versions() {
+        let dna = test_utils::create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (_instance, context) =
+            test_instance_and_context(dna, None).expect("Could not create test instance");
+        let original = Entry::App(test_app_entry_type(), "original value".into());
+        let updated = Entry::App(test_app_entry_type(), "updated value".into());
+        context
+            .block_on(author_entry(&original, None, &context))
+            .expect("Could not author entry");
+
+        let get_if_newer_than_original = || {
+            let args = GetEntryArgs {
+                address: original.address(),
+                options: GetEntryOptions::default().with_if_newer_than(original.address()),
+            };
+            context
+                .block_on(get_entry_result_workflow(&context, &args))
+                .expect("Could not get entry")
+        };
+        let result = get_if_newer_than_original();
+        assert!(result.is_not_modified());
+        assert_eq!(result.latest(), None);
+
+        context
+            .block_on(author_entry(&updated, None, &context))
+            .expect("Could not author entry");
+        context
+            .block_on(update_entry(
+                &context,
+                context.action_channel(),
+                original.address(),
+                updated.address(),
+            ))
+            .expect("Could not update entry");
+
+        let result = get_if_newer_than_original();
+        assert!(!result.is_not_modified());
+        assert_eq!(result.latest(), Some(updated));
+    }
+}
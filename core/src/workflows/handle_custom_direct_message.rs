@@ -2,7 +2,7 @@ use crate::{
     action::{Action, ActionWrapper, DirectMessageData},
     context::Context,
     instance::dispatch_action,
-    network::direct_message::{CustomDirectMessage, DirectMessage},
+    network::direct_message::{CustomDirectMessage, DirectMessage, DirectMessageEnvelope},
     nucleus::ribosome::callback::{receive::receive, CallbackParams, CallbackResult},
 };
 
@@ -31,9 +31,10 @@ pub async fn handle_custom_direct_message(
         payload: response,
     };
     let direct_message = DirectMessage::Custom(custom_direct_message);
+    let envelope = DirectMessageEnvelope::new(direct_message, &to_agent_id.to_string(), &context)?;
     let direct_message_data = DirectMessageData {
         address: to_agent_id,
-        message: direct_message,
+        envelope,
         msg_id,
         is_response: true,
     };
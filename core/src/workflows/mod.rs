@@ -1,8 +1,11 @@
 pub mod application;
 pub mod author_entry;
+pub mod capability_grant;
 pub mod get_entry_result;
 pub mod handle_custom_direct_message;
 pub mod hold_entry;
 pub mod hold_link;
 pub mod remove_link;
+pub mod resolve_validation_package;
 pub mod respond_validation_package_request;
+pub mod validate_chain;
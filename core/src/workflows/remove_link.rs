@@ -1,10 +1,9 @@
 use crate::{
     context::Context,
     dht::actions::remove_link::remove_link,
-    network::{
-        actions::get_validation_package::get_validation_package, entry_with_header::EntryWithHeader,
-    },
+    network::entry_with_header::EntryWithHeader,
     nucleus::actions::validate::validate_entry,
+    workflows::resolve_validation_package::resolve_validation_package,
 };
 
 use holochain_core_types::{
@@ -18,7 +17,7 @@ pub async fn remove_link_workflow<'a>(
     entry_with_header: &'a EntryWithHeader,
     context: &'a Arc<Context>,
 ) -> Result<(), HolochainError> {
-    let EntryWithHeader { entry, header } = &entry_with_header;
+    let EntryWithHeader { entry, .. } = &entry_with_header;
 
     let link_remove = match entry {
         Entry::LinkRemove(link_remove) => link_remove,
@@ -33,7 +32,7 @@ pub async fn remove_link_workflow<'a>(
     context.log(format!(
         "debug/workflow/remove_link: getting validation package..."
     ));
-    let maybe_validation_package = await!(get_validation_package(header.clone(), &context))?;
+    let maybe_validation_package = await!(resolve_validation_package(entry_with_header, context))?;
     let validation_package = maybe_validation_package
         .ok_or("Could not get validation package from source".to_string())?;
     context.log(format!(
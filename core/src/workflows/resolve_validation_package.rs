@@ -0,0 +1,96 @@
+use crate::{
+    context::Context,
+    network::{
+        actions::get_validation_package::get_validation_package, entry_with_header::EntryWithHeader,
+    },
+    nucleus::actions::build_validation_package::build_validation_package_locally,
+};
+
+use holochain_core_types::{error::HcResult, validation::ValidationPackage};
+use std::sync::Arc;
+
+/// Gets the validation package for a received entry.
+/// Builds it from locally held headers and entries if possible and only asks the
+/// source over the network if some of the data the package needs is missing locally.
+pub async fn resolve_validation_package<'a>(
+    entry_with_header: &'a EntryWithHeader,
+    context: &'a Arc<Context>,
+) -> HcResult<Option<ValidationPackage>> {
+    let EntryWithHeader { entry, header } = entry_with_header;
+    if let Some(validation_package) = build_validation_package_locally(entry, header, context) {
+        context.log("debug/workflow/resolve_validation_package: built validation package locally");
+        return Ok(Some(validation_package));
+    }
+    await!(get_validation_package(header.clone(), context))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::nucleus::actions::tests::*;
+    use holochain_core_types::cas::content::AddressableContent;
+
+    #[test]
+    fn validation_package_gets_built_locally_without_network_request() {
+        let netname = Some("resolve_validation_package_locally");
+        let (_instance, context) = instance_by_name("alice", test_dna(), netname);
+
+        commit(test_entry_package_entry(), &context);
+        let entry = test_entry_package_chain_full();
+        let header = commit(entry.clone(), &context);
+        let entry_with_header = EntryWithHeader {
+            entry: entry.clone(),
+            header: header.clone(),
+        };
+
+        let validation_package = context
+            .block_on(resolve_validation_package(&entry_with_header, &context))
+            .expect("Could not resolve validation package")
+            .expect("There should be a validation package");
+        assert_eq!(validation_package.chain_header, header);
+        let chain_headers = context
+            .state()
+            .unwrap()
+            .agent()
+            .chain_store()
+            .iter(&Some(header.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(validation_package.source_chain_headers, Some(chain_headers));
+
+        // no GetValidationPackage action was sent to the network
+        assert!(!context
+            .state()
+            .unwrap()
+            .network()
+            .get_validation_package_results
+            .contains_key(&entry.address()));
+    }
+
+    #[test]
+    fn validation_package_gets_requested_from_source_if_not_held_locally() {
+        let netname = Some("resolve_validation_package_from_network");
+        let (_instance1, context1) = instance_by_name("alice", test_dna(), netname);
+        let (_instance2, context2) = instance_by_name("bob", test_dna(), netname);
+
+        commit(test_entry_package_entry(), &context1);
+        let entry = test_entry_package_chain_full();
+        let header = commit(entry.clone(), &context1);
+        let entry_with_header = EntryWithHeader {
+            entry: entry.clone(),
+            header: header.clone(),
+        };
+
+        // bob does not hold alice's previous headers, so the package needs to come from alice
+        let validation_package = context2
+            .block_on(resolve_validation_package(&entry_with_header, &context2))
+            .expect("Could not resolve validation package")
+            .expect("Source should have responded with a validation package");
+        assert_eq!(validation_package.chain_header, header);
+        assert!(context2
+            .state()
+            .unwrap()
+            .network()
+            .get_validation_package_results
+            .contains_key(&entry.address()));
+    }
+}
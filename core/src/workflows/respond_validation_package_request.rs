@@ -2,29 +2,12 @@ use crate::{
     action::{Action, ActionWrapper, DirectMessageData},
     context::Context,
     instance::dispatch_action,
-    network::direct_message::DirectMessage,
-    nucleus::actions::build_validation_package::build_validation_package,
+    network::direct_message::{DirectMessage, DirectMessageEnvelope},
+    nucleus::actions::build_validation_package::build_validation_package_for_address,
 };
 
-use holochain_core_types::{cas::content::Address, entry::Entry, error::HolochainError};
-use std::{convert::TryFrom, sync::Arc};
-
-fn get_entry(address: &Address, context: &Arc<Context>) -> Result<Entry, HolochainError> {
-    let raw = context
-        .state()
-        .unwrap()
-        .agent()
-        .chain_store()
-        .content_storage()
-        .read()
-        .unwrap()
-        .fetch(address)?
-        .ok_or(HolochainError::ErrorGeneric(
-            "Entry not found when trying to build validation package".to_string(),
-        ))?;
-
-    Entry::try_from(raw)
-}
+use holochain_core_types::cas::content::Address;
+use std::sync::Arc;
 
 pub async fn respond_validation_package_request(
     to_agent_id: Address,
@@ -32,15 +15,27 @@ pub async fn respond_validation_package_request(
     requested_entry_address: Address,
     context: Arc<Context>,
 ) {
-    let maybe_validation_package = match get_entry(&requested_entry_address, &context) {
-        Ok(entry) => await!(build_validation_package(&entry, context.clone())).ok(),
-        Err(_) => None,
-    };
+    let maybe_validation_package = await!(build_validation_package_for_address(
+        &requested_entry_address,
+        context.clone()
+    ))
+    .ok();
 
     let direct_message = DirectMessage::ValidationPackage(maybe_validation_package);
+    let envelope =
+        match DirectMessageEnvelope::new(direct_message, &to_agent_id.to_string(), &context) {
+            Ok(envelope) => envelope,
+            Err(error) => {
+                context.log(format!(
+                    "err/net: Could not sign validation package response: {}",
+                    error
+                ));
+                return;
+            }
+        };
     let direct_message_data = DirectMessageData {
         address: to_agent_id,
-        message: direct_message,
+        envelope,
         msg_id,
         is_response: true,
     };
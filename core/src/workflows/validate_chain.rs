@@ -0,0 +1,124 @@
+use crate::{
+    context::Context,
+    nucleus::actions::{
+        build_validation_package::build_validation_package, get_entry::get_entry_from_agent,
+        validate::validate_entry,
+    },
+};
+
+use holochain_core_types::{
+    cas::content::Address,
+    chain_header::ChainHeader,
+    entry::entry_type::EntryType,
+    error::HolochainError,
+    validation::{EntryAction, EntryLifecycle, ValidationData},
+};
+use std::sync::Arc;
+
+async fn validate_chain_entry<'a>(
+    header: &'a ChainHeader,
+    context: &'a Arc<Context>,
+) -> Result<(), HolochainError> {
+    let entry = get_entry_from_agent(context, header.entry_address())?.ok_or_else(|| {
+        HolochainError::ErrorGeneric("Entry not found in source chain".to_string())
+    })?;
+    let validation_package = await!(build_validation_package(&entry, context.clone()))?;
+    let validation_data = ValidationData {
+        package: validation_package,
+        lifecycle: EntryLifecycle::Chain,
+        action: match header.link_crud() {
+            Some(_) => EntryAction::Modify,
+            None => EntryAction::Create,
+        },
+    };
+    await!(validate_entry(entry, validation_data, context))?;
+    Ok(())
+}
+
+/// Runs the validation of every app entry in the agent's source chain again against
+/// the current DNA, for instance to find entries that a DNA upgrade made invalid.
+/// Returns the addresses of the entries that don't validate anymore, oldest first,
+/// together with the reason. Nothing gets committed, published or removed.
+pub async fn validate_chain_against_dna<'a>(
+    context: &'a Arc<Context>,
+) -> Result<Vec<(Address, HolochainError)>, HolochainError> {
+    let state = context.state().ok_or_else(|| {
+        HolochainError::ErrorGeneric("Context not initialized with a state".to_string())
+    })?;
+    let mut app_entry_headers: Vec<ChainHeader> = state
+        .agent()
+        .iter_chain()
+        .filter(|header| match header.entry_type() {
+            EntryType::App(_) => true,
+            _ => false,
+        })
+        .collect();
+    app_entry_headers.reverse();
+
+    let mut invalid_entries = Vec::new();
+    for header in app_entry_headers {
+        if let Err(error) = await!(validate_chain_entry(&header, context)) {
+            context.log(format!(
+                "debug/workflow/validate_chain: entry {} does not validate: {}",
+                header.entry_address(),
+                error
+            ));
+            invalid_entries.push((header.entry_address().clone(), error));
+        }
+    }
+    Ok(invalid_entries)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::nucleus::actions::tests::*;
+    use holochain_core_types::{
+        cas::content::AddressableContent, entry::Entry, error::DnaError, json::JsonString,
+    };
+
+    fn package_entry(stuff: &str) -> Entry {
+        Entry::App(
+            "package_entry".into(),
+            JsonString::from(format!("{{\"stuff\":\"{}\"}}", stuff)),
+        )
+    }
+
+    #[test]
+    fn reports_entries_that_dont_validate_against_dna() {
+        let netname = Some("reports_entries_that_dont_validate_against_dna");
+        let (_instance, context) = instance(netname);
+
+        // commit without validation, as if these entries got authored
+        // under a less strict version of the DNA
+        let valid = package_entry("valid");
+        let invalid = package_entry("FAIL");
+        let removed_type = Entry::App("removed_entry_type".into(), JsonString::from("{}"));
+        commit(valid.clone(), &context);
+        commit(invalid.clone(), &context);
+        commit(removed_type.clone(), &context);
+
+        let invalid_entries = context
+            .block_on(validate_chain_against_dna(&context))
+            .expect("Could not validate chain");
+        assert_eq!(
+            invalid_entries
+                .iter()
+                .map(|(address, _)| address.clone())
+                .collect::<Vec<_>>(),
+            vec![invalid.address(), removed_type.address()]
+        );
+        match invalid_entries[0].1 {
+            HolochainError::ValidationFailed(ref message) => {
+                assert!(message.contains("FAIL content is not allowed"))
+            }
+            ref other => panic!("unexpected error: {:?}", other),
+        }
+        assert_eq!(
+            invalid_entries[1].1,
+            HolochainError::Dna(DnaError::EntryTypeNotFound(
+                "removed_entry_type".to_string()
+            ))
+        );
+    }
+}
@@ -6,12 +6,67 @@ use crate::{
 };
 
 use crate::error::HolochainError;
-use std::{convert::TryFrom, str};
+use serde::{de::Deserializer, ser::Serializer, Deserialize, Serialize};
+use std::{convert::TryFrom, fmt, str};
 
 use hcid::*;
 
 pub type Base32 = String;
 
+/// The signature scheme an agent's public signing key belongs to.
+/// Serialized as its lowercase name, e.g. "ed25519".
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SigningAlgorithm {
+    Ed25519,
+    /// An algorithm this version does not know about.
+    /// Such identities can still be stored and passed around but verifying
+    /// signatures against them fails with an error.
+    Unknown(String),
+}
+
+impl SigningAlgorithm {
+    pub fn is_default(&self) -> bool {
+        *self == SigningAlgorithm::default()
+    }
+}
+
+impl Default for SigningAlgorithm {
+    fn default() -> Self {
+        SigningAlgorithm::Ed25519
+    }
+}
+
+impl<'a> From<&'a str> for SigningAlgorithm {
+    fn from(name: &str) -> Self {
+        match name {
+            "ed25519" => SigningAlgorithm::Ed25519,
+            _ => SigningAlgorithm::Unknown(name.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for SigningAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SigningAlgorithm::Ed25519 => write!(f, "ed25519"),
+            SigningAlgorithm::Unknown(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl Serialize for SigningAlgorithm {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for SigningAlgorithm {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(SigningAlgorithm::from(name.as_str()))
+    }
+}
+
 /// AgentId represents an agent in the Holochain framework.
 /// This data struct is meant be stored in the CAS and source-chain.
 /// Its key is the public signing key, and is also used as its address.
@@ -21,6 +76,11 @@ pub struct AgentId {
     pub nick: String,
     /// the encoded public signing key of this agent (the magnifier)
     pub pub_sign_key: Base32,
+    /// the algorithm pub_sign_key is meant for.
+    /// Left out of the serialization if it is the default (Ed25519) so that existing
+    /// identities keep their content and address.
+    #[serde(default, skip_serializing_if = "SigningAlgorithm::is_default")]
+    pub algorithm: SigningAlgorithm,
     // TODO: Add the encoded public encrypting key (the safe / padlock)
     // pub pub_enc_key: Base32,
 }
@@ -45,11 +105,17 @@ impl AgentId {
         Ok(AgentId::new(nick, key_b32))
     }
 
-    /// initialize an Agent struct with `nick` and a HCID encoded key.
+    /// initialize an Agent struct with `nick` and a HCID encoded Ed25519 key.
     pub fn new(nick: &str, key_b32: Base32) -> Self {
+        AgentId::new_with_algorithm(nick, key_b32, SigningAlgorithm::default())
+    }
+
+    /// initialize an Agent struct with `nick` and a HCID encoded key for the given algorithm.
+    pub fn new_with_algorithm(nick: &str, key_b32: Base32, algorithm: SigningAlgorithm) -> Self {
         AgentId {
             nick: nick.to_string(),
             pub_sign_key: key_b32,
+            algorithm,
         }
     }
 
@@ -151,4 +217,31 @@ mod tests {
             AgentId::try_from_content(&expected_content).unwrap(),
         );
     }
+
+    #[test]
+    /// show that only non-default algorithms end up in the content
+    fn agent_algorithm_content_test() {
+        assert_eq!(test_agent_id().algorithm, SigningAlgorithm::Ed25519);
+
+        let agent_id = AgentId::new_with_algorithm(
+            "bob",
+            GOOD_ID.to_string(),
+            SigningAlgorithm::from("secp256k1"),
+        );
+        let expected_content = Content::from(format!(
+            "{{\"AgentId\":{{\"nick\":\"bob\",\"pub_sign_key\":\"{}\",\"algorithm\":\"secp256k1\"}}}}",
+            GOOD_ID
+        ));
+        assert_eq!(expected_content, agent_id.content());
+        assert_eq!(
+            agent_id,
+            AgentId::try_from_content(&expected_content).unwrap()
+        );
+        assert_eq!(
+            agent_id.algorithm,
+            SigningAlgorithm::Unknown("secp256k1".to_string())
+        );
+        // the address only depends on the key
+        assert_eq!(agent_id.address(), test_agent_id().address());
+    }
 }
@@ -0,0 +1,165 @@
+//! Helpers for computing the delays between retries.
+//! Randomizing the delays with jitter keeps many clients that failed at the same time
+//! from retrying in lockstep.
+
+use rand::{thread_rng, Rng};
+use std::{cmp, time::Duration};
+
+/// Longest time any retry waits unless configured otherwise, no matter how many
+/// attempts failed before.
+pub const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// How a backoff delay gets randomized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Jitter {
+    /// Waits exactly the computed delay.
+    None,
+    /// Waits a random time between zero and the computed delay.
+    Full,
+    /// Waits at least half of the computed delay plus a random share of the other half.
+    Equal,
+}
+
+impl Default for Jitter {
+    fn default() -> Self {
+        Jitter::None
+    }
+}
+
+impl Jitter {
+    /// Applies this jitter to the given delay.
+    pub fn apply(&self, delay: Duration) -> Duration {
+        let millis = duration_as_millis(delay);
+        let millis = match self {
+            Jitter::None => millis,
+            Jitter::Full => random_up_to(millis),
+            Jitter::Equal => millis / 2 + random_up_to(millis - millis / 2),
+        };
+        Duration::from_millis(millis)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Growth {
+    /// the delay grows by the base delay with every attempt
+    Linear,
+    /// the delay doubles with every attempt
+    Exponential,
+}
+
+/// Delays between retries that grow with every attempt, up to a maximum.
+/// All retry paths compute their delays with this so they honor the same cap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Backoff {
+    base: Duration,
+    max: Duration,
+    growth: Growth,
+    jitter: Jitter,
+}
+
+impl Backoff {
+    pub fn exponential(base: Duration, max: Duration) -> Self {
+        Backoff {
+            base,
+            max,
+            growth: Growth::Exponential,
+            jitter: Jitter::None,
+        }
+    }
+
+    /// Waits one base delay longer with every attempt.
+    pub fn linear(base: Duration, max: Duration) -> Self {
+        Backoff {
+            base,
+            max,
+            growth: Growth::Linear,
+            jitter: Jitter::None,
+        }
+    }
+
+    pub fn with_jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Replaces the maximum delay.
+    pub fn with_max_delay(mut self, max: Duration) -> Self {
+        self.max = max;
+        self
+    }
+
+    /// Delay to wait before the given retry, counting from 1.
+    /// Never longer than the maximum delay, also with jitter.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = match self.growth {
+            Growth::Linear => attempt,
+            Growth::Exponential => 1_u32.checked_shl(attempt.saturating_sub(1)).unwrap_or(0),
+        };
+        let delay = match self.base.checked_mul(factor) {
+            Some(delay) if factor > 0 => cmp::min(delay, self.max),
+            _ => self.max,
+        };
+        self.jitter.apply(delay)
+    }
+}
+
+fn duration_as_millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
+}
+
+fn random_up_to(max: u64) -> u64 {
+    thread_rng().gen_range(0, max + 1)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn exponential_backoff_doubles_up_to_max() {
+        let backoff = Backoff::exponential(Duration::from_millis(10), Duration::from_millis(50));
+        assert_eq!(backoff.delay(1), Duration::from_millis(10));
+        assert_eq!(backoff.delay(2), Duration::from_millis(20));
+        assert_eq!(backoff.delay(3), Duration::from_millis(40));
+        assert_eq!(backoff.delay(4), Duration::from_millis(50));
+        assert_eq!(backoff.delay(64), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn backoff_gets_clamped_to_max_delay() {
+        let backoff = Backoff::exponential(Duration::from_secs(1), Duration::from_secs(3600))
+            .with_max_delay(DEFAULT_MAX_DELAY);
+        assert_eq!(backoff.delay(1), Duration::from_secs(1));
+        assert_eq!(backoff.delay(1000), DEFAULT_MAX_DELAY);
+        assert_eq!(backoff.delay(u32::max_value()), DEFAULT_MAX_DELAY);
+
+        let backoff =
+            Backoff::linear(Duration::from_secs(1), DEFAULT_MAX_DELAY).with_jitter(Jitter::Full);
+        for attempt in &[10_u32, 100_000, u32::max_value()] {
+            assert!(backoff.delay(*attempt) <= DEFAULT_MAX_DELAY);
+        }
+        let backoff = Backoff::linear(Duration::from_secs(1), DEFAULT_MAX_DELAY);
+        assert_eq!(backoff.delay(3), Duration::from_secs(3));
+        assert_eq!(backoff.delay(100_000), DEFAULT_MAX_DELAY);
+    }
+
+    #[test]
+    fn jitter_spreads_delays_within_bounds() {
+        let delay = Duration::from_millis(1000);
+        for (jitter, min) in &[(Jitter::Full, 0), (Jitter::Equal, 500)] {
+            let delays: HashSet<Duration> = (0..100).map(|_| jitter.apply(delay)).collect();
+            assert!(
+                delays.len() > 1,
+                "{:?} jitter did not spread delays",
+                jitter
+            );
+            for jittered in delays {
+                assert!(jittered >= Duration::from_millis(*min), "{:?}", jittered);
+                assert!(jittered <= delay, "{:?}", jittered);
+            }
+        }
+        assert_eq!(Jitter::None.apply(delay), delay);
+    }
+}
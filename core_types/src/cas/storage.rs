@@ -26,7 +26,7 @@ use uuid::Uuid;
 /// content addressable store (CAS)
 /// implements storage in memory or persistently
 /// anything implementing AddressableContent can be added and fetched by address
-/// CAS is append only
+/// CAS is append only, except for remove() which is meant for evicting cached content
 pub trait ContentAddressableStorage: objekt::Clone + Send + Sync + Debug {
     /// adds AddressableContent to the ContentAddressableStorage by its Address as Content
     fn add(&mut self, content: &AddressableContent) -> Result<(), HolochainError>;
@@ -37,6 +37,18 @@ pub trait ContentAddressableStorage: objekt::Clone + Send + Sync + Debug {
     /// AddressableContent::from_content() can be used to allow the compiler to infer the type
     /// @see the fetch implementation for ExampleCas in the cas module tests
     fn fetch(&self, address: &Address) -> Result<Option<Content>, HolochainError>;
+    /// returns the content for each of the given Addresses in the same order, None for absent ones
+    /// implementations can override this if they can fetch a batch faster than one by one
+    fn fetch_many(&self, addresses: &[Address]) -> Result<Vec<Option<Content>>, HolochainError> {
+        addresses
+            .iter()
+            .map(|address| self.fetch(address))
+            .collect()
+    }
+    /// returns the Addresses of all content in the Store, in no particular order
+    fn addresses(&self) -> Result<Vec<Address>, HolochainError>;
+    /// removes the content stored under the given Address, if there is any
+    fn remove(&mut self, address: &Address) -> Result<(), HolochainError>;
     //needed to find a way to compare two different CAS for partialord derives.
     //easiest solution was to just compare two ids which are based on uuids
     fn get_id(&self) -> Uuid;
@@ -86,6 +98,15 @@ impl ContentAddressableStorage for ExampleContentAddressableStorage {
         Ok(self.content.read()?.unthreadable_fetch(address)?)
     }
 
+    fn addresses(&self) -> Result<Vec<Address>, HolochainError> {
+        Ok(self.content.read()?.unthreadable_addresses())
+    }
+
+    fn remove(&mut self, address: &Address) -> Result<(), HolochainError> {
+        self.content.write()?.unthreadable_remove(address);
+        Ok(())
+    }
+
     fn get_id(&self) -> Uuid {
         Uuid::new_v4()
     }
@@ -120,6 +141,14 @@ impl ExampleContentAddressableStorageContent {
     fn unthreadable_fetch(&self, address: &Address) -> Result<Option<Content>, HolochainError> {
         Ok(self.storage.get(address).cloned())
     }
+
+    fn unthreadable_addresses(&self) -> Vec<Address> {
+        self.storage.keys().cloned().collect()
+    }
+
+    fn unthreadable_remove(&mut self, address: &Address) {
+        self.storage.remove(address);
+    }
 }
 
 // A struct for our test suite that infers a type of ContentAddressableStorage
@@ -194,6 +223,35 @@ where
             );
         }
 
+        // batched fetches keep the order of the addresses and give None for absent content
+        let absent_address = Address::from("absent");
+        assert_eq!(
+            Ok(vec![
+                Some(other_content.clone()),
+                None,
+                Some(content.clone()),
+                Some(other_content.clone()),
+            ]),
+            self.cas.fetch_many(&[
+                other_content.address(),
+                absent_address,
+                content.address(),
+                other_content.address(),
+            ])
+        );
+        assert_eq!(Ok(Vec::new()), self.cas.fetch_many(&[]));
+
+        // removing content only affects the given address and is visible in clones
+        assert_eq!(Ok(()), self.cas.remove(&content.address()));
+        // removing absent content is fine
+        assert_eq!(Ok(()), self.cas_clone.remove(&content.address()));
+
+        for cas in both_cas.iter() {
+            assert_eq!(Ok(false), cas.contains(&content.address()));
+            assert_eq!(Ok(None), cas.fetch(&content.address()));
+            assert_eq!(Ok(true), cas.contains(&other_content.address()));
+        }
+
         // show consistent view on data across threads
 
         let entry = test_entry_unique();
@@ -12,11 +12,21 @@ use std::collections::BTreeMap;
 ///
 /// Bridges can be required or optional. If a required bridge DNA is not installed this DNA
 /// can't run, so required bridges are hard dependencies that have to be enforced by the conductor.
+///
+/// A zome can also declare that it provides a bridge, i.e. that it accepts calls from
+/// the referenced DNA(s), so the conductor can match the DNA requiring a bridge to the
+/// DNA providing it.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Hash)]
 pub struct Bridge {
     /// Required or optional
     pub presence: BridgePresence,
 
+    /// Whether this zome calls the other DNA or gets called by it.
+    /// Left out of the JSON if it is the default, so that DNAs without provided
+    /// bridges keep their address.
+    #[serde(default, skip_serializing_if = "BridgeDirection::is_requires")]
+    pub direction: BridgeDirection,
+
     /// An arbitrary name of this bridge that is used as handle to reference this
     /// bridge in according zome API functions
     pub handle: String,
@@ -62,3 +72,26 @@ pub enum BridgePresence {
     /// This DNA's code can check via API functions if the other DNA is installed and connected.
     Optional,
 }
+
+/// Requires or provides
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum BridgeDirection {
+    /// This DNA calls zome functions of the other DNA, i.e. it is the caller of the bridge.
+    Requires,
+
+    /// The other DNA calls zome functions of this DNA, i.e. this DNA is the callee.
+    Provides,
+}
+
+impl Default for BridgeDirection {
+    fn default() -> Self {
+        BridgeDirection::Requires
+    }
+}
+
+impl BridgeDirection {
+    pub fn is_requires(&self) -> bool {
+        *self == BridgeDirection::Requires
+    }
+}
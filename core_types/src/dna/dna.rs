@@ -2,7 +2,7 @@ use crate::{
     cas::content::{AddressableContent, Content},
     dna::{
         bridges::Bridge,
-        entry_types::EntryTypeDef,
+        entry_types::{EntryTypeDef, EntryTypeSummary},
         fn_declarations::{FnDeclaration, TraitFns},
         wasm, zome,
     },
@@ -11,12 +11,14 @@ use crate::{
     json::JsonString,
 };
 use entry::entry_type::AppEntryType;
+use json_patch;
 use multihash;
 use serde_json::{self, Value};
 use std::{
     collections::BTreeMap,
     convert::TryFrom,
     hash::{Hash, Hasher},
+    str::FromStr,
 };
 
 /// serde helper, provides a default empty object
@@ -179,13 +181,16 @@ impl Dna {
     }
 
     /// Return the name of the zome holding a specified app entry_type
+    /// Returns None if the app entry_type name is not valid.
     pub fn get_zome_name_for_app_entry_type(
         &self,
         app_entry_type: &AppEntryType,
     ) -> Option<String> {
         let entry_type_name = String::from(app_entry_type.to_owned());
-        // pre-condition: must be a valid app entry_type name
-        assert!(EntryType::has_valid_app_name(&entry_type_name));
+        // an invalid app entry_type name can't be defined in any zome
+        if !EntryType::has_valid_app_name(&entry_type_name) {
+            return None;
+        }
         // Browse through the zomes
         for (zome_name, zome) in &self.zomes {
             for zome_entry_type_name in zome.entry_types.keys() {
@@ -200,9 +205,12 @@ impl Dna {
     }
 
     /// Return the entry_type definition of a specified app entry_type
+    /// Returns None if the app entry_type name is not valid.
     pub fn get_entry_type_def(&self, entry_type_name: &str) -> Option<&EntryTypeDef> {
-        // pre-condition: must be a valid app entry_type name
-        assert!(EntryType::has_valid_app_name(entry_type_name));
+        // an invalid app entry_type name can't be defined in any zome
+        if !EntryType::has_valid_app_name(entry_type_name) {
+            return None;
+        }
         // Browse through the zomes
         for zome in self.zomes.values() {
             for (zome_entry_type_name, entry_type_def) in &zome.entry_types {
@@ -216,6 +224,97 @@ impl Dna {
         None
     }
 
+    /// Returns a summary of every entry type definition of every zome,
+    /// ordered by zome name and entry type name.
+    pub fn entry_type_summaries(&self) -> Vec<EntryTypeSummary> {
+        self.zomes
+            .iter()
+            .flat_map(|(zome_name, zome)| {
+                zome.entry_types
+                    .iter()
+                    .map(move |(entry_type, entry_type_def)| {
+                        EntryTypeSummary::new(zome_name, entry_type, entry_type_def)
+                    })
+            })
+            .collect()
+    }
+
+    /// Checks that the references within the DNA resolve:
+    /// entry types named in links_to and linked_from have to be system entry types
+    /// or be defined in some zome, and functions of traits have to be declared
+    /// in the trait's zome.
+    pub fn validate(&self) -> Result<(), DnaError> {
+        for (zome_name, zome) in &self.zomes {
+            let invalid = |detail: String| DnaError::ValidationFailed {
+                zome: Some(zome_name.clone()),
+                detail,
+            };
+            for (entry_type, entry_type_def) in &zome.entry_types {
+                let linked_types = entry_type_def
+                    .links_to
+                    .iter()
+                    .map(|links_to| &links_to.target_type)
+                    .chain(
+                        entry_type_def
+                            .linked_from
+                            .iter()
+                            .map(|linked_from| &linked_from.base_type),
+                    );
+                for linked_type in linked_types {
+                    if !self.defines_entry_type(linked_type) {
+                        return Err(invalid(format!(
+                            "Entry type '{}' is linked with unknown entry type '{}'",
+                            String::from(entry_type.clone()),
+                            linked_type
+                        )));
+                    }
+                }
+            }
+            for (trait_name, trait_fns) in &zome.traits {
+                for fn_name in &trait_fns.functions {
+                    if self.get_function(zome, fn_name).is_none() {
+                        return Err(invalid(format!(
+                            "Trait '{}' contains undeclared function '{}'",
+                            trait_name, fn_name
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// A DNA without zomes can be loaded but has no functions that could be called.
+    /// Conductors use this to tell about such DNAs at instance startup.
+    pub fn check_has_zomes(&self) -> Result<(), DnaError> {
+        if self.zomes.is_empty() {
+            return Err(DnaError::ValidationFailed {
+                zome: None,
+                detail: String::from("DNA has no zomes, so it has no functions to call"),
+            });
+        }
+        Ok(())
+    }
+
+    /// True for system entry types and app entry types defined in any zome.
+    fn defines_entry_type(&self, entry_type_name: &str) -> bool {
+        match EntryType::from_str(entry_type_name) {
+            Ok(EntryType::App(_)) | Err(_) => self.get_entry_type_def(entry_type_name).is_some(),
+            Ok(_) => true,
+        }
+    }
+
+    /// Returns a copy of this DNA with the given overrides deep-merged into its properties,
+    /// following JSON merge patch rules: objects get merged key by key, null removes a key
+    /// and any other value replaces the existing one.
+    /// Since the properties are part of the DNA, the copy has a different address,
+    /// which keeps instances of the original and the overridden DNA on separate networks.
+    pub fn with_property_overrides(&self, overrides: Value) -> Dna {
+        let mut dna = self.clone();
+        json_patch::merge(&mut dna.properties, &overrides);
+        dna
+    }
+
     pub fn multihash(&self) -> Result<Vec<u8>, HolochainError> {
         let s = String::from(JsonString::from(self.to_owned()));
         multihash::encode(multihash::Hash::SHA2256, &s.into_bytes())
@@ -229,6 +328,15 @@ impl Dna {
             .flatten()
             .collect()
     }
+
+    /// Bridges through which zomes of this DNA accept calls from other DNAs.
+    pub fn provided_bridges(&self) -> Vec<Bridge> {
+        self.zomes
+            .values()
+            .map(|zome| zome.provided_bridges())
+            .flatten()
+            .collect()
+    }
 }
 
 impl Hash for Dna {
@@ -248,6 +356,7 @@ impl PartialEq for Dna {
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use dna::entry_types::{LinkedFrom, LinksTo, Sharing};
     fn test_dna() -> Dna {
         let fixture = String::from(
             r#"{
@@ -364,7 +473,7 @@ pub mod tests {
         let fun = dna.get_function(zome, "test").unwrap();
         assert_eq!(
             format!("{:?}", fun),
-            "FnDeclaration { name: \"test\", inputs: [], outputs: [] }"
+            "FnDeclaration { name: \"test\", inputs: [], outputs: [], cacheable: false }"
         );
     }
 
@@ -381,8 +490,114 @@ pub mod tests {
         let fun = dna.get_function_with_zome_name("test", "test").unwrap();
         assert_eq!(
             format!("{:?}", fun),
-            "FnDeclaration { name: \"test\", inputs: [], outputs: [] }"
+            "FnDeclaration { name: \"test\", inputs: [], outputs: [], cacheable: false }"
         );
     }
 
+    #[test]
+    fn test_dna_entry_type_summaries() {
+        let summaries = test_dna().entry_type_summaries();
+        assert_eq!(
+            summaries,
+            vec![EntryTypeSummary {
+                zome: String::from("test"),
+                entry_type: String::from("test"),
+                description: String::from("test"),
+                sharing: Sharing::Public,
+                links_to: vec![LinksTo {
+                    target_type: String::from("test"),
+                    tag: String::from("test"),
+                }],
+                linked_from: Vec::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_dna_check_has_zomes() {
+        assert_eq!(test_dna().check_has_zomes(), Ok(()));
+        assert_eq!(
+            Dna::new().check_has_zomes(),
+            Err(DnaError::ValidationFailed {
+                zome: None,
+                detail: String::from("DNA has no zomes, so it has no functions to call"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_dna_validate() {
+        let mut dna = test_dna();
+        assert_eq!(dna.validate(), Ok(()));
+
+        {
+            let zome = dna.zomes.get_mut("test").unwrap();
+            let entry_type_def = zome.entry_types.get_mut(&"test".into()).unwrap();
+            entry_type_def.linked_from.push(LinkedFrom {
+                base_type: "%agent_id".to_string(),
+                tag: "authored".to_string(),
+            });
+        }
+        assert_eq!(dna.validate(), Ok(()));
+
+        {
+            let zome = dna.zomes.get_mut("test").unwrap();
+            let entry_type_def = zome.entry_types.get_mut(&"test".into()).unwrap();
+            entry_type_def.links_to.push(LinksTo {
+                target_type: "missing".to_string(),
+                tag: "test".to_string(),
+            });
+        }
+        assert_eq!(
+            dna.validate(),
+            Err(DnaError::ValidationFailed {
+                zome: Some("test".to_string()),
+                detail: "Entry type 'test' is linked with unknown entry type 'missing'".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_dna_validate_trait_functions() {
+        let mut dna = test_dna();
+        dna.zomes
+            .get_mut("test")
+            .unwrap()
+            .traits
+            .get_mut("hc_public")
+            .unwrap()
+            .functions
+            .push("undeclared".to_string());
+        assert_eq!(
+            dna.validate(),
+            Err(DnaError::ValidationFailed {
+                zome: Some("test".to_string()),
+                detail: "Trait 'hc_public' contains undeclared function 'undeclared'".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_dna_with_property_overrides() {
+        let dna = test_dna();
+        let overridden = dna.with_property_overrides(json!({
+            "test": "overridden",
+            "network": {"seed": "staging"}
+        }));
+        assert_eq!(
+            overridden.properties,
+            json!({"test": "overridden", "network": {"seed": "staging"}})
+        );
+        assert_eq!(overridden.zomes, dna.zomes);
+        assert_ne!(overridden.address(), dna.address());
+
+        let merged = overridden.with_property_overrides(json!({
+            "test": null,
+            "network": {"port": 1234}
+        }));
+        assert_eq!(
+            merged.properties,
+            json!({"network": {"seed": "staging", "port": 1234}})
+        );
+    }
 }
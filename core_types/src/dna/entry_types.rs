@@ -147,6 +147,40 @@ impl EntryTypeDef {
     }
 }
 
+/// Summary of an entry type definition together with the zome defining it,
+/// for clients that generate documentation or schemas from a DNA.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EntryTypeSummary {
+    /// The name of the zome holding the entry type.
+    pub zome: String,
+
+    /// The name of the entry type.
+    pub entry_type: String,
+
+    pub description: String,
+
+    pub sharing: Sharing,
+
+    /// Link definitions of links from entries of this type.
+    pub links_to: Vec<LinksTo>,
+
+    /// Link definitions of links pointing to entries of this type.
+    pub linked_from: Vec<LinkedFrom>,
+}
+
+impl EntryTypeSummary {
+    pub fn new(zome: &str, entry_type: &EntryType, entry_type_def: &EntryTypeDef) -> Self {
+        EntryTypeSummary {
+            zome: zome.to_string(),
+            entry_type: String::from(entry_type.to_owned()),
+            description: entry_type_def.description.clone(),
+            sharing: entry_type_def.sharing.clone(),
+            links_to: entry_type_def.links_to.clone(),
+            linked_from: entry_type_def.linked_from.clone(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,5 +1,7 @@
 //! File holding all the structs for handling function declarations defined in DNA.
 
+use serde_json::Value;
+
 /// Represents the type declaration for zome function parameter
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Hash)]
 pub struct FnParameter {
@@ -16,6 +18,39 @@ impl FnParameter {
             parameter_type: t.into(),
         }
     }
+
+    /// Whether the declared type allows the parameter to be left out or be null,
+    /// i.e. it is an `Option<..>`.
+    pub fn is_optional(&self) -> bool {
+        self.parameter_type.trim().starts_with("Option<")
+    }
+
+    /// Checks if the given JSON value fits the declared type.
+    /// Types that have no obvious JSON representation, like structs defined by
+    /// the zome, accept any value and are left to the zome to deserialize.
+    pub fn accepts(&self, value: &Value) -> bool {
+        let parameter_type = self.parameter_type.trim();
+        if self.is_optional() {
+            if value.is_null() {
+                return true;
+            }
+            let inner = parameter_type["Option<".len()..].trim_end_matches('>');
+            return FnParameter::new(self.name.as_str(), inner).accepts(value);
+        }
+        if parameter_type.starts_with("Vec<") || parameter_type.starts_with('[') {
+            return value.is_array();
+        }
+        match parameter_type.to_lowercase().as_str() {
+            "string" | "&str" | "str" | "address" | "hashstring" => value.is_string(),
+            "bool" | "boolean" => value.is_boolean(),
+            "u8" | "u16" | "u32" | "u64" | "usize" => value.is_u64(),
+            "i8" | "i16" | "i32" | "i64" | "isize" | "integer" => value.is_i64() || value.is_u64(),
+            "f32" | "f64" | "number" => value.is_number(),
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            _ => true,
+        }
+    }
 }
 
 /// Represents a zome function declaration
@@ -26,6 +61,10 @@ pub struct FnDeclaration {
     pub name: String,
     pub inputs: Vec<FnParameter>,
     pub outputs: Vec<FnParameter>,
+    /// Marks a read-only function whose results may be served from the
+    /// instance's zome call cache for calls with identical arguments.
+    #[serde(default, skip_serializing_if = "FnDeclaration::is_not_cacheable")]
+    pub cacheable: bool,
 }
 
 impl Default for FnDeclaration {
@@ -35,6 +74,7 @@ impl Default for FnDeclaration {
             name: String::new(),
             inputs: Vec::new(),
             outputs: Vec::new(),
+            cacheable: false,
         }
     }
 }
@@ -44,6 +84,50 @@ impl FnDeclaration {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Checks the arguments of a call against the declared inputs before they get
+    /// passed to the zome. Every input that isn't an `Option<..>` has to be present
+    /// and all present inputs have to fit their declared type.
+    /// Returns a description of the first mismatch found.
+    pub fn validate_arguments(&self, arguments: &Value) -> Result<(), String> {
+        if self.inputs.is_empty() {
+            return Ok(());
+        }
+        let empty = serde_json::Map::new();
+        let arguments = match arguments {
+            Value::Object(map) => map,
+            Value::Null => &empty,
+            _ => {
+                return Err(format!(
+                    "Arguments of '{}' have to be given as a JSON object",
+                    self.name
+                ));
+            }
+        };
+        for input in self.inputs.iter() {
+            match arguments.get(&input.name) {
+                None if input.is_optional() => (),
+                None => {
+                    return Err(format!(
+                        "Missing argument '{}' of type {} for '{}'",
+                        input.name, input.parameter_type, self.name
+                    ));
+                }
+                Some(value) if !input.accepts(value) => {
+                    return Err(format!(
+                        "Argument '{}' for '{}' has to be of type {}, got {}",
+                        input.name, self.name, input.parameter_type, value
+                    ));
+                }
+                Some(_) => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn is_not_cacheable(cacheable: &bool) -> bool {
+        !*cacheable
+    }
 }
 
 /// Represents a group of named functions in the Zomes's "traits" array
@@ -134,4 +218,77 @@ mod tests {
 
         assert_eq!(fixture, trt);
     }
+
+    fn test_fn_declaration() -> FnDeclaration {
+        let mut fn_dec = FnDeclaration::new();
+        fn_dec.name = String::from("create_post");
+        fn_dec.inputs.push(FnParameter::new("content", "String"));
+        fn_dec
+            .inputs
+            .push(FnParameter::new("in_reply_to", "Option<Address>"));
+        fn_dec.inputs.push(FnParameter::new("tags", "Vec<String>"));
+        fn_dec.inputs.push(FnParameter::new("timestamp", "u64"));
+        fn_dec.inputs.push(FnParameter::new("meta", "PostMeta"));
+        fn_dec
+    }
+
+    #[test]
+    fn test_validate_arguments_accepts_matching_arguments() {
+        let fn_dec = test_fn_declaration();
+        assert_eq!(
+            fn_dec.validate_arguments(&json!({
+                "content": "hi",
+                "tags": ["a"],
+                "timestamp": 1,
+                "meta": {"anything": true},
+            })),
+            Ok(())
+        );
+        assert_eq!(
+            fn_dec.validate_arguments(&json!({
+                "content": "hi",
+                "in_reply_to": "QmPost",
+                "tags": [],
+                "timestamp": 1,
+                "meta": null,
+            })),
+            Ok(())
+        );
+        assert_eq!(FnDeclaration::new().validate_arguments(&json!("x")), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_arguments_reports_missing_and_mistyped_arguments() {
+        let fn_dec = test_fn_declaration();
+        assert_eq!(
+            fn_dec.validate_arguments(&json!({"tags": [], "timestamp": 1, "meta": {}})),
+            Err(String::from(
+                "Missing argument 'content' of type String for 'create_post'"
+            ))
+        );
+        assert_eq!(
+            fn_dec.validate_arguments(&json!({
+                "content": "hi",
+                "tags": [],
+                "timestamp": -1,
+                "meta": {},
+            })),
+            Err(String::from(
+                "Argument 'timestamp' for 'create_post' has to be of type u64, got -1"
+            ))
+        );
+        assert_eq!(
+            fn_dec.validate_arguments(&json!({
+                "content": "hi",
+                "in_reply_to": 3,
+                "tags": [],
+                "timestamp": 1,
+                "meta": {},
+            })),
+            Err(String::from(
+                "Argument 'in_reply_to' for 'create_post' has to be of type Option<Address>, got 3"
+            ))
+        );
+        assert!(fn_dec.validate_arguments(&json!(["hi"])).is_err());
+    }
 }
@@ -41,7 +41,7 @@ pub mod tests {
     use crate::{
         cas::content::Address,
         dna::{
-            bridges::{Bridge, BridgePresence, BridgeReference},
+            bridges::{Bridge, BridgeDirection, BridgePresence, BridgeReference},
             entry_types::EntryTypeDef,
             fn_declarations::{FnDeclaration, FnParameter, Trait},
             zome::tests::test_zome,
@@ -72,6 +72,29 @@ pub mod tests {
         assert_eq!(Some(&entry_type_def), dna.get_entry_type_def("bar"));
     }
 
+    #[test]
+    fn get_entry_type_def_invalid_name_test() {
+        let mut dna = test_dna();
+        let mut zome = test_zome();
+        zome.entry_types.insert(
+            EntryType::App(AppEntryType::from("bar")),
+            EntryTypeDef::new(),
+        );
+        dna.zomes.insert("zome".to_string(), zome);
+
+        // invalid names must not panic
+        assert_eq!(None, dna.get_entry_type_def(""));
+        assert_eq!(None, dna.get_entry_type_def("%bar"));
+        assert_eq!(
+            None,
+            dna.get_zome_name_for_app_entry_type(&AppEntryType::from(""))
+        );
+        assert_eq!(
+            None,
+            dna.get_zome_name_for_app_entry_type(&AppEntryType::from("%agent_id"))
+        );
+    }
+
     #[test]
     fn can_parse_and_output_json() {
         let dna = test_dna();
@@ -519,6 +542,7 @@ pub mod tests {
             vec![
                 Bridge {
                     presence: BridgePresence::Required,
+                    direction: BridgeDirection::Requires,
                     handle: String::from("DPKI"),
                     reference: BridgeReference::Address {
                         dna_address: Address::from("Qmabcdef1234567890"),
@@ -526,6 +550,7 @@ pub mod tests {
                 },
                 Bridge {
                     presence: BridgePresence::Required,
+                    direction: BridgeDirection::Requires,
                     handle: String::from("HCHC"),
                     reference: BridgeReference::Trait {
                         traits: btreemap! {
@@ -538,6 +563,7 @@ pub mod tests {
                                             name: String::from("happs"),
                                             parameter_type: String::from("json"),
                                         }],
+                                        cacheable: false,
                                     }
                                 ]
                             }
@@ -547,4 +573,72 @@ pub mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_provided_bridges() {
+        let dna = Dna::try_from(JsonString::from(
+            r#"{
+                "name": "test",
+                "description": "test",
+                "version": "test",
+                "uuid": "00000000-0000-0000-0000-000000000000",
+                "dna_spec_version": "2.0",
+                "properties": {},
+                "zomes": {
+                    "test zome": {
+                        "name": "test zome",
+                        "description": "test",
+                        "config": {},
+                        "traits": {},
+                        "fn_declarations": [],
+                        "entry_types": {},
+                        "code": {
+                            "code": ""
+                        },
+                        "bridges": [
+                            {
+                                "presence": "required",
+                                "handle": "DPKI",
+                                "reference": {
+                                    "dna_address": "Qmabcdef1234567890"
+                                }
+                            },
+                            {
+                                "presence": "required",
+                                "direction": "provides",
+                                "handle": "happ-store",
+                                "reference": {
+                                    "dna_address": "Qm0987654321fedcba"
+                                }
+                            }
+                        ]
+                    }
+                }
+            }"#,
+        ))
+        .unwrap();
+
+        assert_eq!(
+            dna.get_required_bridges(),
+            vec![Bridge {
+                presence: BridgePresence::Required,
+                direction: BridgeDirection::Requires,
+                handle: String::from("DPKI"),
+                reference: BridgeReference::Address {
+                    dna_address: Address::from("Qmabcdef1234567890"),
+                }
+            }]
+        );
+        assert_eq!(
+            dna.provided_bridges(),
+            vec![Bridge {
+                presence: BridgePresence::Required,
+                direction: BridgeDirection::Provides,
+                handle: String::from("happ-store"),
+                reference: BridgeReference::Address {
+                    dna_address: Address::from("Qm0987654321fedcba"),
+                }
+            }]
+        );
+    }
 }
@@ -2,7 +2,7 @@
 
 use crate::{
     dna::{
-        bridges::{Bridge, BridgePresence},
+        bridges::{Bridge, BridgeDirection, BridgePresence},
         fn_declarations::{FnDeclaration, FnParameter, TraitFns},
         traits::ReservedTraitNames,
         wasm::DnaWasm,
@@ -12,16 +12,35 @@ use crate::{
     json::JsonString,
 };
 use dna::entry_types::{self, deserialize_entry_types, serialize_entry_types, EntryTypeDef};
-use std::collections::BTreeMap;
+use serde_json;
+use std::{
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+};
 
 /// Represents the "config" object on a "zome".
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Hash)]
-pub struct Config {}
+/// Holds arbitrary settings the zome can read at runtime through the ribosome.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, DefaultJson)]
+pub struct Config {
+    #[serde(flatten)]
+    pub settings: BTreeMap<String, serde_json::Value>,
+}
 
 impl Default for Config {
     /// Provide defaults for the "zome" "config" object.
     fn default() -> Self {
-        Config {}
+        Config {
+            settings: BTreeMap::new(),
+        }
+    }
+}
+
+impl Hash for Config {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for (key, value) in &self.settings {
+            key.hash(state);
+            value.to_string().hash(state);
+        }
     }
 }
 
@@ -30,6 +49,11 @@ impl Config {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Returns the setting with the given name, if the zome's config defines it.
+    pub fn get(&self, key: &str) -> Option<&serde_json::Value> {
+        self.settings.get(key)
+    }
 }
 
 pub type ZomeEntryTypes = BTreeMap<EntryType, EntryTypeDef>;
@@ -44,8 +68,7 @@ pub struct Zome {
     pub description: String,
 
     /// Configuration associated with this zome.
-    /// Note, this should perhaps be a more free-form serde_json::Value,
-    /// "throw-errors" may not make sense for wasm, or other ribosome types.
+    /// Free-form settings that get passed through to the zome's calls.
     #[serde(default)]
     pub config: Config,
 
@@ -110,14 +133,25 @@ impl Zome {
         }
     }
 
+    /// Bridges this zome can't run without, i.e. required bridges to DNAs it calls.
     pub fn get_required_bridges(&self) -> Vec<Bridge> {
         self.bridges
             .iter()
+            .filter(|bridge| bridge.direction == BridgeDirection::Requires)
             .filter(|bridge| bridge.presence == BridgePresence::Required)
             .cloned()
             .collect()
     }
 
+    /// Bridges through which this zome accepts calls from other DNAs.
+    pub fn provided_bridges(&self) -> Vec<Bridge> {
+        self.bridges
+            .iter()
+            .filter(|bridge| bridge.direction == BridgeDirection::Provides)
+            .cloned()
+            .collect()
+    }
+
     pub fn add_fn_declaration(
         &mut self,
         name: String,
@@ -128,6 +162,7 @@ impl Zome {
             name,
             inputs,
             outputs,
+            cacheable: false,
         });
     }
 
@@ -199,6 +234,27 @@ pub mod tests {
         assert_eq!(zome, Zome::try_from(JsonString::from(expected)).unwrap(),);
     }
 
+    #[test]
+    fn zome_config_settings_test() {
+        let zome: Zome = serde_json::from_str(
+            r#"{
+                "config": {
+                    "max_post_length": 280,
+                    "greeting": "hello"
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(zome.config.get("max_post_length"), Some(&json!(280)));
+        assert_eq!(zome.config.get("greeting"), Some(&json!("hello")));
+        assert_eq!(zome.config.get("missing"), None);
+        assert_eq!(
+            serde_json::to_string(&zome.config).unwrap(),
+            "{\"greeting\":\"hello\",\"max_post_length\":280}"
+        );
+    }
+
     #[test]
     fn test_zome_add_fn_declaration() {
         let mut zome = Zome::default();
@@ -213,7 +269,7 @@ pub mod tests {
         );
         assert_eq!(zome.fn_declarations.len(), 1);
 
-        let expected = "[FnDeclaration { name: \"hello\", inputs: [], outputs: [FnParameter { parameter_type: \"String\", name: \"greeting\" }] }]";
+        let expected = "[FnDeclaration { name: \"hello\", inputs: [], outputs: [FnParameter { parameter_type: \"String\", name: \"greeting\" }], cacheable: false }]";
         assert_eq!(expected, format!("{:?}", zome.fn_declarations),);
     }
 }
@@ -39,6 +39,8 @@ pub enum Attribute {
     LinkTag(String),
     RemovedLink(String),
     PendingEntry,
+    /// Records written by storage self tests, see Conductor::self_test()
+    SelfTest,
 }
 
 #[derive(PartialEq, Debug)]
@@ -75,6 +77,7 @@ impl fmt::Display for Attribute {
             Attribute::LinkTag(tag) => write!(f, "link__{}", tag),
             Attribute::RemovedLink(tag) => write!(f, "removed_link__{}", tag),
             Attribute::PendingEntry => write!(f, "pending-entry"),
+            Attribute::SelfTest => write!(f, "self-test"),
         }
     }
 }
@@ -104,6 +107,7 @@ impl TryFrom<&str> for Attribute {
                 "link" => Ok(Link),
                 "link_remove" => Ok(LinkRemove),
                 "pending-entry" => Ok(PendingEntry),
+                "self-test" => Ok(SelfTest),
                 a => Err(AttributeError::Unrecognized(a.to_string())),
             }
         }
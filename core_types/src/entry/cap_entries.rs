@@ -23,15 +23,40 @@ impl CapToken {
     }
 }
 
+/// A zome function a capability grant gives access to
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GrantedFunction {
+    pub zome: String,
+    pub function: String,
+}
+
+impl GrantedFunction {
+    pub fn new<S: Into<String>>(zome: S, function: S) -> Self {
+        GrantedFunction {
+            zome: zome.into(),
+            function: function.into(),
+        }
+    }
+}
+
 /// System entry to hold a capabilities granted by the callee
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, DefaultJson)]
 pub struct CapTokenGrant {
     assignees: Option<Vec<Address>>,
+    /// Zome functions this grant gives access to, all of them if empty.
+    /// Validation checks that the DNA declares all of them.
+    /// Left out of the serialization if empty, so grants from before functions were
+    /// added keep their address and thus their token.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    functions: Vec<GrantedFunction>,
 }
 
 impl CapTokenGrant {
     fn new(assignees: Option<Vec<Address>>) -> Self {
-        CapTokenGrant { assignees }
+        CapTokenGrant {
+            assignees,
+            functions: Vec::new(),
+        }
     }
 
     pub fn create(
@@ -92,13 +117,40 @@ impl CapTokenGrant {
         self.assignees.clone()
     }
 
+    /// Sets the zome functions this grant gives access to.
+    /// Grants without functions give access to all functions.
+    pub fn with_functions(mut self, functions: Vec<GrantedFunction>) -> Self {
+        self.functions = functions;
+        self
+    }
+
+    pub fn functions(&self) -> &Vec<GrantedFunction> {
+        &self.functions
+    }
+
+    /// True if this grant gives access to the given zome function.
+    pub fn grants_function(&self, zome: &str, function: &str) -> bool {
+        self.functions.is_empty()
+            || self
+                .functions
+                .iter()
+                .any(|granted| granted.zome == zome && granted.function == function)
+    }
+
     /// verifies that this grant is valid for a given requester and token value
+    /// and gives access to the given zome function
     pub fn verify(
         &self,
         token: CapTokenValue,
         from: Option<Address>,
         _message: &CallSignature,
+        zome: &str,
+        function: &str,
     ) -> bool {
+        if !self.grants_function(zome, function) {
+            return false;
+        }
+
         let cap_type = self.cap_type();
         if cap_type == CapabilityType::Public {
             return true;
@@ -132,6 +184,8 @@ impl CapTokenGrant {
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use multihash::Hash;
+    use std::convert::TryFrom;
 
     #[test]
     fn test_new_cap_token_grant_entry() {
@@ -190,6 +244,54 @@ pub mod tests {
         assert_eq!(grant.assignees().unwrap()[0], test_address)
     }
 
+    #[test]
+    fn test_cap_grant_functions_roundtrip() {
+        let grant = CapTokenGrant::create(CapabilityType::Transferable, None)
+            .unwrap()
+            .with_functions(vec![GrantedFunction::new("blog", "create_post")]);
+        assert_eq!(
+            grant.functions(),
+            &vec![GrantedFunction {
+                zome: "blog".to_string(),
+                function: "create_post".to_string(),
+            }]
+        );
+        let json = JsonString::from(grant.clone());
+        assert_eq!(CapTokenGrant::try_from(json), Ok(grant));
+
+        // grants from before functions were added grant all functions
+        let old_grant = CapTokenGrant::try_from(JsonString::from(r#"{"assignees":[]}"#)).unwrap();
+        assert!(old_grant.functions().is_empty());
+        assert!(old_grant.grants_function("blog", "create_post"));
+    }
+
+    #[test]
+    fn test_cap_grant_without_functions_keeps_its_token() {
+        // entry content of a transferable grant from before functions were added
+        let old_content = r#"{"CapTokenGrant":{"assignees":[]}}"#;
+        let old_address = Address::encode_from_str(old_content, Hash::SHA2256);
+
+        let grant = CapTokenGrant::create(CapabilityType::Transferable, None).unwrap();
+        assert_eq!(
+            String::from(Entry::CapTokenGrant(grant.clone()).content()),
+            old_content
+        );
+        assert_eq!(grant.token(), old_address);
+
+        let old_grant = match Entry::try_from(JsonString::from(old_content)).unwrap() {
+            Entry::CapTokenGrant(old_grant) => old_grant,
+            entry => panic!("Expected a CapTokenGrant, got {:?}", entry),
+        };
+        assert_eq!(old_grant.token(), old_address);
+        assert!(old_grant.verify(
+            old_address,
+            Some(Address::from("some identity")),
+            &CallSignature {},
+            "blog",
+            "create_post"
+        ));
+    }
+
     #[test]
     fn test_cap_grant_verify() {
         let test_address1 = Address::from("some identity");
@@ -198,52 +300,124 @@ pub mod tests {
 
         let grant = CapTokenGrant::create(CapabilityType::Public, None).unwrap();
         let token = grant.token();
-        assert!(grant.verify(token.clone(), None, test_call_signature));
+        assert!(grant.verify(
+            token.clone(),
+            None,
+            test_call_signature,
+            "blog",
+            "create_post"
+        ));
         assert!(grant.verify(
             token.clone(),
             Some(test_address1.clone()),
-            test_call_signature
+            test_call_signature,
+            "blog",
+            "create_post"
+        ));
+        assert!(grant.verify(
+            Address::from("Bad Token"),
+            None,
+            test_call_signature,
+            "blog",
+            "create_post"
         ));
-        assert!(grant.verify(Address::from("Bad Token"), None, test_call_signature));
 
         let grant = CapTokenGrant::create(CapabilityType::Transferable, None).unwrap();
         let token = grant.token();
-        assert!(!grant.verify(token.clone(), None, test_call_signature));
+        assert!(!grant.verify(
+            token.clone(),
+            None,
+            test_call_signature,
+            "blog",
+            "create_post"
+        ));
         assert!(grant.verify(
             token.clone(),
             Some(test_address1.clone()),
-            test_call_signature
+            test_call_signature,
+            "blog",
+            "create_post"
         ));
         assert!(grant.verify(
             token.clone(),
             Some(test_address2.clone()),
-            test_call_signature
+            test_call_signature,
+            "blog",
+            "create_post"
         ));
         assert!(!grant.verify(
             Address::from("Bad Token"),
             Some(test_address1.clone()),
-            test_call_signature
+            test_call_signature,
+            "blog",
+            "create_post"
         ));
 
         let grant =
             CapTokenGrant::create(CapabilityType::Assigned, Some(vec![test_address1.clone()]))
                 .unwrap();
         let token = grant.token();
-        assert!(!grant.verify(token.clone(), None, test_call_signature));
+        assert!(!grant.verify(
+            token.clone(),
+            None,
+            test_call_signature,
+            "blog",
+            "create_post"
+        ));
         assert!(grant.verify(
             token.clone(),
             Some(test_address1.clone()),
-            test_call_signature
+            test_call_signature,
+            "blog",
+            "create_post"
         ));
         assert!(!grant.verify(
             token.clone(),
             Some(test_address2.clone()),
-            test_call_signature
+            test_call_signature,
+            "blog",
+            "create_post"
         ));
         assert!(!grant.verify(
             Address::from("Bad Token"),
             Some(test_address1.clone()),
-            test_call_signature
+            test_call_signature,
+            "blog",
+            "create_post"
         ));
     }
+
+    #[test]
+    fn test_cap_grant_verify_checks_functions() {
+        let caller = Address::from("some identity");
+        let test_call_signature = &CallSignature {};
+        let grant = CapTokenGrant::create(CapabilityType::Transferable, None)
+            .unwrap()
+            .with_functions(vec![
+                GrantedFunction::new("blog", "create_post"),
+                GrantedFunction::new("blog", "get_post"),
+            ]);
+        let token = grant.token();
+        let verify = |zome: &str, function: &str| {
+            grant.verify(
+                token.clone(),
+                Some(caller.clone()),
+                test_call_signature,
+                zome,
+                function,
+            )
+        };
+        assert!(verify("blog", "create_post"));
+        assert!(verify("blog", "get_post"));
+        assert!(!verify("blog", "delete_post"));
+        assert!(!verify("comments", "create_post"));
+
+        // public grants are restricted to their functions just the same
+        let grant = CapTokenGrant::create(CapabilityType::Public, None)
+            .unwrap()
+            .with_functions(vec![GrantedFunction::new("blog", "get_post")]);
+        let token = grant.token();
+        assert!(grant.verify(token.clone(), None, test_call_signature, "blog", "get_post"));
+        assert!(!grant.verify(token, None, test_call_signature, "blog", "create_post"));
+    }
 }
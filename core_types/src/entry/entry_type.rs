@@ -124,7 +124,7 @@ impl EntryType {
         // - must not contain any glob wildcards
         !entry_type_name.is_empty()
         // Must not have sys_prefix
-            && &entry_type_name[0..1] != "%"
+            && !entry_type_name.starts_with('%')
     }
 }
 
@@ -5,17 +5,34 @@ pub enum DnaError {
     ZomeNotFound(String),
     TraitNotFound(String),
     ZomeFunctionNotFound(String),
+    /// An app entry type that no zome of the DNA defines.
+    EntryTypeNotFound(String),
+    /// A structural problem of the DNA, like a reference to something it does not define.
+    /// zome is the zome the problem was found in, None for problems outside of zomes.
+    ValidationFailed {
+        zome: Option<String>,
+        detail: String,
+    },
 }
 
 impl Error for DnaError {}
 
 impl fmt::Display for DnaError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let msg = match self {
-            DnaError::ZomeNotFound(err_msg) => err_msg,
-            DnaError::TraitNotFound(err_msg) => err_msg,
-            DnaError::ZomeFunctionNotFound(err_msg) => err_msg,
-        };
-        write!(f, "{}", msg)
+        match self {
+            DnaError::ZomeNotFound(err_msg) => write!(f, "{}", err_msg),
+            DnaError::TraitNotFound(err_msg) => write!(f, "{}", err_msg),
+            DnaError::ZomeFunctionNotFound(err_msg) => write!(f, "{}", err_msg),
+            DnaError::EntryTypeNotFound(entry_type) => {
+                write!(f, "Entry type '{}' is not defined in the DNA", entry_type)
+            }
+            DnaError::ValidationFailed {
+                zome: Some(zome),
+                detail,
+            } => write!(f, "Invalid DNA in zome '{}': {}", zome, detail),
+            DnaError::ValidationFailed { zome: None, detail } => {
+                write!(f, "Invalid DNA: {}", detail)
+            }
+        }
     }
 }
@@ -94,6 +94,7 @@ pub enum HolochainError {
     RibosomeFailed(String),
     ConfigError(String),
     Timeout,
+    NetworkNotInitialized,
 }
 
 pub type HcResult<T> = Result<T, HolochainError>;
@@ -123,6 +124,7 @@ impl fmt::Display for HolochainError {
             RibosomeFailed(fail_msg) => write!(f, "{}", fail_msg),
             ConfigError(err_msg) => write!(f, "{}", err_msg),
             Timeout => write!(f, "timeout"),
+            NetworkNotInitialized => write!(f, "Network not initialized"),
         }
     }
 }
@@ -311,6 +313,17 @@ mod tests {
                 HolochainError::Dna(DnaError::ZomeFunctionNotFound(String::from("foo"))),
                 "foo",
             ),
+            (
+                HolochainError::Dna(DnaError::EntryTypeNotFound(String::from("foo"))),
+                "Entry type 'foo' is not defined in the DNA",
+            ),
+            (
+                HolochainError::Dna(DnaError::ValidationFailed {
+                    zome: Some(String::from("foo")),
+                    detail: String::from("bar"),
+                }),
+                "Invalid DNA in zome 'foo': bar",
+            ),
             (HolochainError::IoError(String::from("foo")), "foo"),
             (
                 HolochainError::SerializationError(String::from("foo")),
@@ -325,6 +338,10 @@ mod tests {
                 "Caller does not have Capability to make that call",
             ),
             (HolochainError::Timeout, "timeout"),
+            (
+                HolochainError::NetworkNotInitialized,
+                "Network not initialized",
+            ),
         ] {
             assert_eq!(output, &format!("{}", input));
         }
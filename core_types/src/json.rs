@@ -41,6 +41,12 @@ impl JsonString {
     }
 }
 
+impl AsRef<str> for JsonString {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
 impl From<String> for JsonString {
     fn from(s: String) -> JsonString {
         let cleaned = s
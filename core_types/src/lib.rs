@@ -17,7 +17,9 @@ extern crate chrono;
 extern crate futures;
 #[macro_use]
 extern crate lazy_static;
+extern crate json_patch;
 extern crate multihash;
+extern crate rand;
 extern crate rust_base58;
 extern crate serde;
 #[macro_use]
@@ -43,6 +45,7 @@ pub mod error;
 #[macro_use]
 extern crate objekt;
 pub mod agent;
+pub mod backoff;
 pub mod bits_n_pieces;
 pub mod chain_migrate;
 pub mod dna;
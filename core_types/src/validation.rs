@@ -3,7 +3,10 @@
 //! agent actions between Holochain and Zomes.
 
 use crate::{
-    cas::content::Address, chain_header::ChainHeader, entry::Entry, error::HolochainError,
+    cas::content::{Address, AddressableContent},
+    chain_header::ChainHeader,
+    entry::Entry,
+    error::HolochainError,
     json::JsonString,
 };
 use chain_header::test_chain_header;
@@ -25,6 +28,52 @@ impl ValidationPackage {
             custom: None,
         }
     }
+
+    /// The header of the entry this package is about.
+    pub fn chain_header(&self) -> &ChainHeader {
+        &self.chain_header
+    }
+
+    /// The public source chain entries of the author, empty if the package does not carry them.
+    pub fn source_chain_entries(&self) -> &[Entry] {
+        self.source_chain_entries
+            .as_ref()
+            .map(|entries| entries.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// The source chain headers of the author, empty if the package does not carry them.
+    pub fn source_chain_headers(&self) -> &[ChainHeader] {
+        self.source_chain_headers
+            .as_ref()
+            .map(|headers| headers.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// The custom validation data defined by the zome, if any.
+    pub fn custom(&self) -> Option<&str> {
+        self.custom.as_ref().map(|custom| custom.as_str())
+    }
+
+    pub fn summary(&self) -> ValidationPackageSummary {
+        ValidationPackageSummary {
+            chain_header: self.chain_header.address(),
+            source_chain_entries: self.source_chain_entries().len(),
+            source_chain_headers: self.source_chain_headers().len(),
+            custom_bytes: self.custom().map(|custom| custom.len()),
+        }
+    }
+}
+
+/// What a ValidationPackage contains, without the contents themselves.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, DefaultJson)]
+pub struct ValidationPackageSummary {
+    /// address of the header the package is about
+    pub chain_header: Address,
+    pub source_chain_entries: usize,
+    pub source_chain_headers: usize,
+    /// length of the custom validation data, None if there is none
+    pub custom_bytes: Option<usize>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, DefaultJson)]
@@ -119,3 +168,47 @@ pub enum LinkAction {
     Create,
     Delete,
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        chain_header::test_chain_header_with_sig,
+        entry::{test_entry, test_entry_b},
+    };
+
+    #[test]
+    fn validation_package_accessors() {
+        let package = ValidationPackage::only_header(test_chain_header());
+        assert_eq!(package.chain_header(), &test_chain_header());
+        assert!(package.source_chain_entries().is_empty());
+        assert!(package.source_chain_headers().is_empty());
+        assert_eq!(package.custom(), None);
+
+        let package = ValidationPackage {
+            chain_header: test_chain_header(),
+            source_chain_entries: Some(vec![test_entry(), test_entry_b()]),
+            source_chain_headers: Some(vec![test_chain_header_with_sig("sig")]),
+            custom: Some(String::from("custom")),
+        };
+        assert_eq!(package.chain_header(), &test_chain_header());
+        assert_eq!(
+            package.source_chain_entries(),
+            &[test_entry(), test_entry_b()][..]
+        );
+        assert_eq!(
+            package.source_chain_headers(),
+            &[test_chain_header_with_sig("sig")][..]
+        );
+        assert_eq!(package.custom(), Some("custom"));
+        assert_eq!(
+            package.summary(),
+            ValidationPackageSummary {
+                chain_header: test_chain_header().address(),
+                source_chain_entries: 2,
+                source_chain_headers: 1,
+                custom_bytes: Some(6),
+            }
+        );
+    }
+}
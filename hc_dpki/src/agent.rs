@@ -0,0 +1,77 @@
+//! Derives agent identities from seeds.
+//! core_types can't depend on sodium, so this lives here as an extension of AgentId.
+
+use crate::{
+    keypair::{KeyPair, SigningKeyPair},
+    SEED_SIZE,
+};
+use holochain_core_types::{
+    agent::AgentId,
+    error::{HcResult, HolochainError},
+};
+use holochain_sodium::secbuf::SecBuf;
+
+pub trait AgentIdFromSeed {
+    /// Derives the Ed25519 signing keypair from the given 32 byte seed and builds
+    /// an AgentId with the given nick from its public key.
+    /// Returns the AgentId together with the secret key for signing.
+    /// The same seed always gives the same AgentId.
+    fn from_seed(nick: &str, seed: &mut SecBuf) -> HcResult<(AgentId, SecBuf)>;
+}
+
+impl AgentIdFromSeed for AgentId {
+    fn from_seed(nick: &str, seed: &mut SecBuf) -> HcResult<(AgentId, SecBuf)> {
+        if seed.len() != SEED_SIZE {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Seed has to be {} bytes long, got {}",
+                SEED_SIZE,
+                seed.len()
+            )));
+        }
+        let key_pair = SigningKeyPair::new_from_seed(seed)?;
+        Ok((AgentId::new(nick, key_pair.public), key_pair.private))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{utils::verify_agent_signature, SIGNATURE_SIZE};
+    use holochain_sodium::sign;
+
+    fn test_seed(byte: u8) -> SecBuf {
+        let mut seed = SecBuf::with_insecure(SEED_SIZE);
+        seed.from_array(&[byte; SEED_SIZE]).unwrap();
+        seed
+    }
+
+    #[test]
+    fn it_derives_the_same_agent_from_the_same_seed() {
+        let (agent_id, _) = AgentId::from_seed("bob", &mut test_seed(1)).unwrap();
+        let (same_agent_id, _) = AgentId::from_seed("bob", &mut test_seed(1)).unwrap();
+        let (other_agent_id, _) = AgentId::from_seed("bob", &mut test_seed(2)).unwrap();
+        assert_eq!(agent_id, same_agent_id);
+        assert_ne!(agent_id.pub_sign_key, other_agent_id.pub_sign_key);
+    }
+
+    #[test]
+    fn it_returns_the_secret_key_of_the_agent() {
+        let (agent_id, mut secret_key) = AgentId::from_seed("bob", &mut test_seed(1)).unwrap();
+        let mut message = SecBuf::with_insecure(42);
+        message.randomize();
+        let mut signature = SecBuf::with_insecure(SIGNATURE_SIZE);
+        sign::sign(&mut message, &mut secret_key, &mut signature).unwrap();
+        assert!(verify_agent_signature(&agent_id, &mut message, &mut signature).unwrap());
+    }
+
+    #[test]
+    fn it_rejects_seeds_of_the_wrong_size() {
+        let mut seed = SecBuf::with_insecure(16);
+        assert_eq!(
+            AgentId::from_seed("bob", &mut seed).map(|_| ()),
+            Err(HolochainError::ErrorGeneric(String::from(
+                "Seed has to be 32 bytes long, got 16"
+            )))
+        );
+    }
+}
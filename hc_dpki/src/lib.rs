@@ -16,6 +16,7 @@ lazy_static! {
         hcid::with_hck0().expect("HCID failed miserably with_hck0.");
 }
 
+pub mod agent;
 pub mod key_blob;
 pub mod key_bundle;
 pub mod keypair;
@@ -1,6 +1,9 @@
 use crate::{CODEC_HCS0, SEED_SIZE};
 use hcid::*;
-use holochain_core_types::{agent::Base32, error::HcResult};
+use holochain_core_types::{
+    agent::{AgentId, Base32, SigningAlgorithm},
+    error::{HcResult, HolochainError},
+};
 use holochain_sodium::{secbuf::SecBuf, sign};
 
 /// Decode an HCID-encoded key into a SecBuf
@@ -44,6 +47,26 @@ pub fn verify(
     ))
 }
 
+/// Verify data that was signed by the given agent,
+/// dispatching on the agent's signing algorithm
+/// @param {AgentId} agent_id - Agent whose public signing key to verify with
+/// @param {SecBuf} data - Data buffer to verify
+/// @param {SecBuf} signature - Candidate signature for that data buffer
+/// @return true if verification succeeded, an error if the algorithm is not supported
+pub fn verify_agent_signature(
+    agent_id: &AgentId,
+    data: &mut SecBuf,
+    signature: &mut SecBuf,
+) -> HcResult<bool> {
+    match agent_id.algorithm {
+        SigningAlgorithm::Ed25519 => verify(agent_id.pub_sign_key.clone(), data, signature),
+        SigningAlgorithm::Unknown(ref name) => Err(HolochainError::ErrorGeneric(format!(
+            "Can not verify signature of agent {}: unsupported signing algorithm \"{}\"",
+            agent_id.pub_sign_key, name
+        ))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -84,4 +107,34 @@ mod tests {
         let res = verify(pub_key_b32, &mut message, &mut signature);
         assert!(res.unwrap());
     }
+
+    #[test]
+    fn it_should_verify_by_agent_algorithm() {
+        let codec = with_hcs0().expect("HCID failed miserably with_hcs0");
+        let mut seed = SecBuf::with_insecure(SEED_SIZE);
+        seed.randomize();
+        let mut public_key = SecBuf::with_insecure(sign::PUBLICKEYBYTES);
+        let mut secret_key = SecBuf::with_secure(sign::SECRETKEYBYTES);
+        holochain_sodium::sign::seed_keypair(&mut public_key, &mut secret_key, &mut seed).unwrap();
+        let pub_key_b32 = encode_pub_key(&mut public_key, &codec).unwrap();
+        let mut message = SecBuf::with_insecure(42);
+        message.randomize();
+        let mut signature = SecBuf::with_insecure(SIGNATURE_SIZE);
+        holochain_sodium::sign::sign(&mut message, &mut secret_key, &mut signature).unwrap();
+
+        let agent_id =
+            AgentId::new_with_algorithm("bob", pub_key_b32.clone(), SigningAlgorithm::Ed25519);
+        let res = verify_agent_signature(&agent_id, &mut message, &mut signature);
+        assert!(res.unwrap());
+
+        let agent_id =
+            AgentId::new_with_algorithm("bob", pub_key_b32, SigningAlgorithm::from("rot13"));
+        let res = verify_agent_signature(&agent_id, &mut message, &mut signature);
+        match res {
+            Err(HolochainError::ErrorGeneric(message)) => {
+                assert!(message.contains("unsupported signing algorithm \"rot13\""))
+            }
+            other => panic!("expected an unsupported algorithm error, got {:?}", other),
+        }
+    }
 }
@@ -142,6 +142,16 @@ pub struct FetchEntryData {
 
     #[serde(rename = "address")]
     pub entry_address: Address,
+
+    /// Address of the version of the entry the requester already has. Only set by
+    /// Holochain for conditional gets, the holder then answers with `not_modified`
+    /// instead of the content if that is still the latest version.
+    #[serde(
+        rename = "ifNewerThan",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub if_newer_than: Option<Address>,
 }
 
 /// Generic DHT data message
@@ -177,6 +187,10 @@ pub struct FetchEntryResultData {
     pub entry_address: Address,
     #[serde(rename = "content")]
     pub entry_content: serde_json::Value,
+    /// Set instead of sending the content if the request named the latest version
+    /// of the entry in `if_newer_than`
+    #[serde(rename = "notModified", default)]
+    pub not_modified: bool,
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -199,6 +213,15 @@ pub struct FetchMetaData {
     pub entry_address: Address,
 
     pub attribute: String,
+
+    /// Entry type the targets of requested links have to be of. Only set by
+    /// Holochain for link requests that are filtered by target type.
+    #[serde(
+        rename = "targetType",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub target_type: Option<String>,
 }
 
 /// Generic DHT metadata message
@@ -592,6 +615,7 @@ mod tests {
             request_id: "test_id".to_string(),
             requester_agent_id: "test_to".to_string(),
             entry_address: "Hk42".into(),
+            if_newer_than: None,
         }));
     }
     #[test]
@@ -601,6 +625,7 @@ mod tests {
             request_id: "test_id".to_string(),
             requester_agent_id: "test_to".to_string(),
             entry_address: "Hk42".into(),
+            if_newer_than: None,
         }));
     }
     #[test]
@@ -612,6 +637,7 @@ mod tests {
             provider_agent_id: "test_from".to_string(),
             entry_address: "Hk42".into(),
             entry_content: json!("hello"),
+            not_modified: false,
         }));
     }
     #[test]
@@ -623,9 +649,54 @@ mod tests {
             provider_agent_id: "test_from".to_string(),
             entry_address: "Hk42".into(),
             entry_content: json!("hello"),
+            not_modified: false,
         }));
     }
     #[test]
+    fn it_can_convert_conditional_fetches() {
+        let fetch = JsonProtocol::HandleFetchEntry(FetchEntryData {
+            dna_address: "test_dna".into(),
+            request_id: "test_id".to_string(),
+            requester_agent_id: "test_to".to_string(),
+            entry_address: "Hk42".into(),
+            if_newer_than: Some("Hk41".into()),
+        });
+        assert!(String::from(JsonString::from(&fetch)).contains(r#""ifNewerThan":"Hk41""#));
+        test_convert!(fetch);
+
+        let not_modified = JsonProtocol::HandleFetchEntryResult(FetchEntryResultData {
+            dna_address: "test_dna".into(),
+            request_id: "test_id".to_string(),
+            requester_agent_id: "test_to".to_string(),
+            provider_agent_id: "test_from".to_string(),
+            entry_address: "Hk42".into(),
+            entry_content: json!(null),
+            not_modified: true,
+        });
+        assert!(String::from(JsonString::from(&not_modified)).contains(r#""notModified":true"#));
+        test_convert!(not_modified);
+
+        // results of nodes that don't know about conditional fetches carry the content
+        let w = JsonProtocol::try_from(JsonString::from(
+            r#"{
+            "method": "handleFetchEntryResult",
+            "dnaAddress": "test_dna",
+            "_id": "test_id",
+            "requesterAgentId": "test_to",
+            "providerAgentId": "test_from",
+            "address": "Hk42",
+            "content": "hello"
+        }"#,
+        ))
+        .unwrap();
+        if let JsonProtocol::HandleFetchEntryResult(result) = w {
+            assert!(!result.not_modified);
+            assert_eq!(json!("hello"), result.entry_content);
+        } else {
+            panic!("bad enum type");
+        }
+    }
+    #[test]
     fn it_can_convert_PublishEntry() {
         test_convert!(JsonProtocol::PublishEntry(EntryData {
             dna_address: "test_dna".into(),
@@ -660,6 +731,7 @@ mod tests {
             requester_agent_id: "test_to".to_string(),
             entry_address: "Hk42".into(),
             attribute: "meta_attribute".to_string(),
+            target_type: None,
         }));
     }
     #[test]
@@ -670,6 +742,7 @@ mod tests {
             requester_agent_id: "test_to".to_string(),
             entry_address: "Hk42".into(),
             attribute: "meta_attribute".to_string(),
+            target_type: Some("post".to_string()),
         }));
     }
     #[test]
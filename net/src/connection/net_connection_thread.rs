@@ -48,11 +48,20 @@ impl NetConnectionThread {
         // Spawn worker thread
         let thread = thread::spawn(move || {
             // Create worker
-            let mut worker = worker_factory(handler).unwrap_or_else(|e| panic!("{:?}", e));
+            // If that fails, hand the error over to the owner instead of panicking
+            let mut worker = match worker_factory(handler) {
+                Ok(worker) => worker,
+                Err(error) => {
+                    send_endpoint
+                        .send(Err(error))
+                        .expect("Sending worker creation error should work.");
+                    return;
+                }
+            };
             // Get endpoint and send it to owner (NetConnectionThread)
             let endpoint = worker.endpoint();
             send_endpoint
-                .send(endpoint)
+                .send(Ok(endpoint))
                 .expect("Sending endpoint address should work.");
             drop(send_endpoint);
             // Loop as long owner wants to
@@ -96,6 +105,12 @@ impl NetConnectionThread {
                 // Sleep
                 thread::sleep(time::Duration::from_micros(sleep_duration_us));
             }
+            // Hand over what was sent before stop() got called, e.g. an untrackDna
+            while let Ok(data) = recv_channel.try_recv() {
+                worker
+                    .receive(data)
+                    .unwrap_or_else(|e| eprintln!("Error occured in p2p network module: {:?}", e));
+            }
             // Stop the worker
             worker.stop().unwrap_or_else(|e| panic!("{:?}", e));
         });
@@ -103,7 +118,7 @@ impl NetConnectionThread {
         // Retrieve endpoint from spawned thread
         let endpoint = recv_endpoint
             .recv()
-            .expect("Failed to receive endpoint address from net worker");
+            .expect("Failed to receive endpoint address from net worker")?;
         let endpoint = endpoint
             .expect("Should have an endpoint address")
             .to_string();
@@ -222,4 +237,18 @@ mod tests {
 
         con.stop().unwrap();
     }
+
+    #[test]
+    fn it_returns_worker_creation_errors() {
+        let result = NetConnectionThread::new(
+            Box::new(move |_r| Ok(())),
+            Box::new(|_h| -> NetResult<Box<NetWorker>> { bail!("worker creation failed") }),
+            None,
+        );
+
+        assert_eq!(
+            result.err().map(|error| error.to_string()),
+            Some("worker creation failed".to_string())
+        );
+    }
 }
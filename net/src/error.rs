@@ -2,6 +2,10 @@
 pub enum NetworkError {
     #[fail(display = "Network error: {}", error)]
     GenericError { error: String },
+    #[fail(display = "Invalid TLS configuration: {}", error)]
+    TlsConfigError { error: String },
+    #[fail(display = "TLS connection to {} failed: {}", endpoint, error)]
+    TlsConnectionError { endpoint: String, error: String },
 }
 
 #[cfg(test)]
@@ -60,6 +60,10 @@ pub(crate) struct InMemoryServer {
     // used for making unique request ids
     request_count: usize,
 
+    // State of the pseudo random generator used for peer selection and delivery order.
+    // If None, the (unspecified) HashMap order is used.
+    rng_state: Option<u64>,
+
     // Logger
     log: TweetProxy,
 }
@@ -96,6 +100,46 @@ impl InMemoryServer {
         self.request_book.get(&request_id.clone())
     }
 
+    /// Advance the seeded pseudo random generator (xorshift64).
+    /// Returns None if this server was not created with a seed.
+    fn priv_next_random(&mut self) -> Option<u64> {
+        self.rng_state.as_mut().map(|state| {
+            let mut x = *state;
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            *state = x;
+            x
+        })
+    }
+
+    /// Get the senders of all agents connected with this dna address.
+    /// If seeded, agents are sorted by id and then shuffled with the seeded generator
+    /// so that the order is reproducible.
+    fn priv_ordered_senders(
+        &mut self,
+        dna_address: &Address,
+    ) -> Vec<(String, mpsc::Sender<Protocol>)> {
+        let mut senders: Vec<(String, mpsc::Sender<Protocol>)> = self
+            .senders_by_dna
+            .get(dna_address)
+            .map(|map| {
+                map.iter()
+                    .map(|(agent_id, sender)| (agent_id.clone(), sender.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if self.rng_state.is_some() {
+            senders.sort_by(|a, b| a.0.cmp(&b.0));
+            // Fisher-Yates shuffle
+            for i in (1..senders.len()).rev() {
+                let j = (self.priv_next_random().unwrap() % (i as u64 + 1)) as usize;
+                senders.swap(i, j);
+            }
+        }
+        senders
+    }
+
     /// Send all Get*Lists requests to agent
     fn priv_request_all_lists(&mut self, dna_address: &Address, agent_id: &str) {
         // Entry
@@ -156,6 +200,13 @@ impl InMemoryServer {
 impl InMemoryServer {
     /// create a new in-memory network server
     pub fn new(name: String) -> Self {
+        Self::new_with_seed(name, None)
+    }
+
+    /// create a new in-memory network server.
+    /// If a seed is given, peer selection and delivery order are deterministic
+    /// for that seed.
+    pub fn new_with_seed(name: String, maybe_seed: Option<u64>) -> Self {
         Self {
             name,
             senders: HashMap::new(),
@@ -166,6 +217,8 @@ impl InMemoryServer {
             stored_book: HashMap::new(),
             request_count: 0,
             trackdna_book: HashSet::new(),
+            // xorshift gets stuck on a zero state
+            rng_state: maybe_seed.map(|seed| if seed == 0 { 1 } else { seed }),
             log: TweetProxy::new("memory_server"),
         }
     }
@@ -463,15 +516,15 @@ impl InMemoryServer {
 
     /// send a message to all nodes connected with this dna address
     fn priv_send_all(&mut self, dna_address: &Address, data: Protocol) -> NetResult<()> {
-        if let Some(arr) = self.senders_by_dna.get_mut(dna_address) {
+        if self.senders_by_dna.contains_key(dna_address) {
             self.log.d(&format!(
                 "<<<< '{}' send all: {:?} ({})",
                 self.name.clone(),
                 data.clone(),
                 dna_address.clone()
             ));
-            for (_k, val) in arr.iter_mut() {
-                (*val).send(data.clone())?;
+            for (_k, val) in self.priv_ordered_senders(dna_address) {
+                val.send(data.clone())?;
             }
         }
         Ok(())
@@ -577,20 +630,10 @@ impl InMemoryServer {
         }
         // #fulldht
         // Have the first known cell registered to that DNA respond
-        match self.senders_by_dna.entry(msg.dna_address.to_owned()) {
-            Entry::Occupied(mut e) => {
-                if !e.get().is_empty() {
-                    let (_k, r) = &e
-                        .get_mut()
-                        .iter()
-                        .next()
-                        .expect("No Cell is registered to track the DNA");
-                    r.send(JsonProtocol::HandleFetchEntry(msg.clone()).into())?;
-                    return Ok(());
-                }
-            }
-            _ => unreachable!(),
-        };
+        if let Some((_k, r)) = self.priv_ordered_senders(&msg.dna_address).first() {
+            r.send(JsonProtocol::HandleFetchEntry(msg.clone()).into())?;
+            return Ok(());
+        }
 
         // No node found, send an empty FetchEntryResultData
         // TODO: should send a FailureResult instead?
@@ -601,6 +644,7 @@ impl InMemoryServer {
             provider_agent_id: msg.requester_agent_id.clone(),
             entry_address: msg.entry_address.clone(),
             entry_content: json!(null),
+            not_modified: false,
         });
         self.priv_send_one(&msg.dna_address, &msg.requester_agent_id, response.into())?;
         // Done
@@ -691,20 +735,10 @@ impl InMemoryServer {
         }
         // #fulldht
         // Have the first known cell registered to that DNA respond
-        match self.senders_by_dna.entry(msg.dna_address.to_owned()) {
-            Entry::Occupied(mut e) => {
-                if !e.get().is_empty() {
-                    let (_k, r) = &e
-                        .get_mut()
-                        .iter()
-                        .next()
-                        .expect("senders_by_dna.entry does not hold any value");
-                    r.send(JsonProtocol::HandleFetchMeta(msg.clone()).into())?;
-                    return Ok(());
-                }
-            }
-            _ => unreachable!(),
-        };
+        if let Some((_k, r)) = self.priv_ordered_senders(&msg.dna_address).first() {
+            r.send(JsonProtocol::HandleFetchMeta(msg.clone()).into())?;
+            return Ok(());
+        }
         // No node found, send an empty FetchMetaResultData
         // TODO: should send a FailureResult instead?
         let response = JsonProtocol::FetchMetaResult(FetchMetaResultData {
@@ -815,6 +849,7 @@ impl InMemoryServer {
                     request_id,
                     dna_address: msg.dna_address.clone(),
                     entry_address,
+                    if_newer_than: None,
                 })
                 .into(),
             )?;
@@ -889,6 +924,7 @@ impl InMemoryServer {
                 dna_address: msg.dna_address.clone(),
                 entry_address: meta_tuple.0,
                 attribute: meta_tuple.1,
+                target_type: None,
             };
             self.priv_send_one_with_cell_id(
                 &cell_id,
@@ -924,3 +960,67 @@ impl InMemoryServer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::json_protocol::TrackDnaData;
+
+    fn dna_address() -> Address {
+        "seeded-dna".into()
+    }
+
+    /// Tracks a few agents on a seeded server, has one of them fetch entries
+    /// repeatedly and records which agent the server routed each fetch to.
+    fn record_fetch_routing(seed: u64) -> Vec<String> {
+        let mut server = InMemoryServer::new_with_seed("seeded".to_string(), Some(seed));
+        let agents = vec!["alice1", "bob1", "carol1", "dave1"];
+        let mut receivers = Vec::new();
+        for agent_id in agents.iter() {
+            let (tx, rx) = mpsc::channel();
+            server.register_cell(&dna_address(), agent_id, tx).unwrap();
+            server
+                .serve(
+                    JsonProtocol::TrackDna(TrackDnaData {
+                        dna_address: dna_address(),
+                        agent_id: agent_id.to_string(),
+                    })
+                    .into(),
+                )
+                .unwrap();
+            receivers.push((agent_id.to_string(), rx));
+        }
+
+        let mut log = Vec::new();
+        for i in 0..20 {
+            server
+                .serve(
+                    JsonProtocol::FetchEntry(FetchEntryData {
+                        dna_address: dna_address(),
+                        request_id: format!("fetch_{}", i),
+                        requester_agent_id: "alice1".to_string(),
+                        entry_address: "entry".into(),
+                        if_newer_than: None,
+                    })
+                    .into(),
+                )
+                .unwrap();
+            for (agent_id, rx) in receivers.iter() {
+                while let Ok(data) = rx.try_recv() {
+                    if let Ok(JsonProtocol::HandleFetchEntry(_)) = JsonProtocol::try_from(&data) {
+                        log.push(agent_id.clone());
+                    }
+                }
+            }
+        }
+        log
+    }
+
+    #[test]
+    fn same_seed_gives_same_routing() {
+        let first = record_fetch_routing(42);
+        let second = record_fetch_routing(42);
+        assert_eq!(first.len(), 20);
+        assert_eq!(first, second);
+    }
+}
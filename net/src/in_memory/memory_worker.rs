@@ -118,12 +118,16 @@ impl InMemoryWorker {
             .as_str()
             .unwrap_or("(unnamed)")
             .to_string();
+        let maybe_seed = config["randomSeed"].as_u64();
         // Create server with that name if it doesn't already exist
         let mut server_map = MEMORY_SERVER_MAP.write().unwrap();
         if !server_map.contains_key(&server_name) {
             server_map.insert(
                 server_name.clone(),
-                Mutex::new(InMemoryServer::new(server_name.clone())),
+                Mutex::new(InMemoryServer::new_with_seed(
+                    server_name.clone(),
+                    maybe_seed,
+                )),
             );
         }
         let mut server = server_map
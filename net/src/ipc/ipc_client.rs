@@ -6,6 +6,7 @@ use crate::connection::{
     protocol::{NamedBinaryData, PingData, PongData, Protocol},
     NetResult,
 };
+use holochain_core_types::backoff::{Backoff, Jitter};
 use snowflake::ProcessUniqueId;
 use std::{thread, time};
 
@@ -13,6 +14,9 @@ use std::{thread, time};
 // for the holochain ipc protocol, the server is always 4 0x24 bytes
 static SRV_ID: &'static [u8] = &[0x24, 0x24, 0x24, 0x24];
 
+/// Longest time waited between two connection attempts unless configured otherwise
+pub const CONNECT_MAX_DELAY: time::Duration = time::Duration::from_millis(500);
+
 /// NetWorker for messaging with an ipc p2p node
 pub struct IpcClient {
     handler: NetHandler,
@@ -83,13 +87,25 @@ impl IpcClient {
     /// establish a new ipc connection
     /// for now, the api simplicity is worth blocking the thread on connection
     pub fn new(
+        handler: NetHandler,
+        socket: Box<IpcSocket>,
+        block_connect: bool,
+    ) -> NetResult<Self> {
+        IpcClient::new_with_max_delay(handler, socket, block_connect, CONNECT_MAX_DELAY)
+    }
+
+    /// Same as new() but waits at most `max_delay` between two connection attempts.
+    pub fn new_with_max_delay(
         handler: NetHandler,
         mut socket: Box<IpcSocket>,
         block_connect: bool,
+        max_delay: time::Duration,
     ) -> NetResult<Self> {
         if block_connect {
             let start = get_millis();
-            let mut backoff = 1_u64;
+            let backoff = Backoff::exponential(time::Duration::from_millis(2), max_delay)
+                .with_jitter(Jitter::Equal);
+            let mut attempt = 0;
 
             loop {
                 // wait for any message from server to indicate connect success
@@ -105,12 +121,8 @@ impl IpcClient {
                 let data: NamedBinaryData = data.into();
                 socket.send(&[SRV_ID, &[], &b"ping".to_vec(), &data.data])?;
 
-                backoff *= 2;
-                if backoff > 500 {
-                    backoff = 500;
-                }
-
-                thread::sleep(time::Duration::from_millis(backoff));
+                attempt += 1;
+                thread::sleep(backoff.delay(attempt));
             }
         }
         Ok(Self {
@@ -6,6 +6,9 @@
 //! sockets in a linux or macOs environment. You may need to fall back to
 //! `tcp://` for other operating systems.
 //!
+//! To reach a p2p node on another machine, use the `tls` socket type instead,
+//! which connects to the node's `tls://` uri without ZeroMq.
+//!
 //! The main export you should care about is ZmqIpcClient.
 //!
 
@@ -13,6 +16,7 @@
 pub mod errors;
 pub mod context;
 pub mod socket;
+pub mod tls_socket;
 pub mod util;
 
 pub mod ipc_client;
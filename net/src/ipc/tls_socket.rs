@@ -0,0 +1,440 @@
+//! An IpcSocket that connects to a p2p node over TLS, for deployments where
+//! the node runs on another machine instead of next to the conductor.
+//! Since there is no zmq framing, multipart messages are written as a
+//! big endian u32 frame count, followed by each frame as a big endian u32
+//! length and its bytes. Frames and frame counts are capped so a broken or
+//! hostile peer can not make the socket buffer arbitrary amounts of data.
+
+use super::{errors::*, socket::IpcSocket};
+use crate::error::NetworkError;
+use native_tls::{Certificate, Identity, TlsConnector, TlsStream};
+use std::{
+    fs,
+    io::{ErrorKind, Read, Write},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+/// TLS settings for connecting to a remote p2p node,
+/// read from the "tls" object of an IPC backend config.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsConfig {
+    /// PEM file with a CA certificate to trust in addition to the system's,
+    /// e.g. the one that signed the node's self-issued certificate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert_file: Option<String>,
+    /// PKCS#12 file with the client certificate and key,
+    /// for nodes that require clients to authenticate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_identity_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_identity_password: Option<String>,
+    /// Name the node's certificate gets verified against.
+    /// Defaults to the host of the IPC uri.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+}
+
+fn config_error(error: String) -> failure::Error {
+    NetworkError::TlsConfigError { error }.into()
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|error| config_error(format!("could not read {}: {}", path, error)))
+}
+
+impl TlsConfig {
+    fn connector(&self) -> Result<TlsConnector> {
+        let mut builder = TlsConnector::builder();
+        if let Some(ref path) = self.ca_cert_file {
+            let certificate = Certificate::from_pem(&read_file(path)?)
+                .map_err(|error| config_error(format!("{}: {}", path, error)))?;
+            builder.add_root_certificate(certificate);
+        }
+        if let Some(ref path) = self.client_identity_file {
+            let password = self.client_identity_password.clone().unwrap_or_default();
+            let identity = Identity::from_pkcs12(&read_file(path)?, &password)
+                .map_err(|error| config_error(format!("{}: {}", path, error)))?;
+            builder.identity(identity);
+        }
+        builder
+            .build()
+            .map_err(|error| config_error(error.to_string()))
+    }
+}
+
+/// Most frames a message may have. IPC messages have four.
+const MAX_FRAME_COUNT: usize = 16;
+
+/// Biggest frame a message may have.
+const MAX_FRAME_BYTES: usize = 16 * 1024 * 1024;
+
+/// Encodes a multipart message for the wire.
+fn encode_message(data: &[&[u8]]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    for frame in data {
+        out.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+        out.extend_from_slice(frame);
+    }
+    out
+}
+
+fn read_u32(buffer: &[u8], offset: usize) -> Option<usize> {
+    if buffer.len() < offset + 4 {
+        return None;
+    }
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&buffer[offset..offset + 4]);
+    Some(u32::from_be_bytes(bytes) as usize)
+}
+
+/// Decodes the first multipart message of the buffer.
+/// Returns the message and the number of bytes it took up,
+/// or None if the buffer does not hold a complete message yet.
+/// Fails for messages with more frames or bigger frames than allowed,
+/// before waiting for their data.
+fn decode_message(buffer: &[u8]) -> Result<Option<(Vec<Vec<u8>>, usize)>> {
+    let frame_count = match read_u32(buffer, 0) {
+        Some(frame_count) => frame_count,
+        None => return Ok(None),
+    };
+    if frame_count > MAX_FRAME_COUNT {
+        bail_generic!(
+            "TLS message has {} frames, at most {} are allowed",
+            frame_count,
+            MAX_FRAME_COUNT
+        );
+    }
+    let mut offset = 4;
+    let mut message = Vec::with_capacity(frame_count);
+    for _ in 0..frame_count {
+        let length = match read_u32(buffer, offset) {
+            Some(length) => length,
+            None => return Ok(None),
+        };
+        if length > MAX_FRAME_BYTES {
+            bail_generic!(
+                "TLS message frame has {} bytes, at most {} are allowed",
+                length,
+                MAX_FRAME_BYTES
+            );
+        }
+        offset += 4;
+        if buffer.len() < offset + length {
+            return Ok(None);
+        }
+        message.push(buffer[offset..offset + length].to_vec());
+        offset += length;
+    }
+    Ok(Some((message, offset)))
+}
+
+/// this is the TLS implementation of the IpcSocket trait
+pub struct TlsIpcSocket {
+    config: TlsConfig,
+    stream: Option<TlsStream<TcpStream>>,
+    read_buffer: Vec<u8>,
+    /// start of the first message in the read buffer that is not decoded yet
+    read_offset: usize,
+    resp_queue: Vec<Vec<Vec<u8>>>,
+    maybe_endpoint: Option<String>,
+}
+
+impl TlsIpcSocket {
+    pub fn with_config(config: TlsConfig) -> Result<Box<Self>> {
+        Ok(Box::new(Self {
+            config,
+            stream: None,
+            read_buffer: Vec::new(),
+            read_offset: 0,
+            resp_queue: Vec::new(),
+            maybe_endpoint: None,
+        }))
+    }
+
+    /// move all complete messages from the read buffer to the response queue
+    fn priv_decode_messages(&mut self) -> Result<()> {
+        while let Some((message, length)) = decode_message(&self.read_buffer[self.read_offset..])? {
+            self.read_offset += length;
+            self.resp_queue.push(message);
+        }
+        // drop the decoded bytes once they make up most of the buffer,
+        // instead of moving the rest of it for every message
+        if self.read_offset == self.read_buffer.len() {
+            self.read_buffer.clear();
+            self.read_offset = 0;
+        } else if self.read_offset > self.read_buffer.len() / 2 {
+            self.read_buffer.drain(..self.read_offset);
+            self.read_offset = 0;
+        }
+        Ok(())
+    }
+}
+
+impl IpcSocket for TlsIpcSocket {
+    fn new() -> Result<Box<Self>> {
+        TlsIpcSocket::with_config(TlsConfig::default())
+    }
+
+    fn endpoint(&self) -> Option<String> {
+        self.maybe_endpoint.clone()
+    }
+
+    #[allow(unknown_lints)]
+    #[allow(boxed_local)] // required for sizing on trait IpcSocket
+    fn close(mut self: Box<Self>) -> Result<()> {
+        if let Some(mut stream) = self.stream.take() {
+            // the remote end might already be gone, nothing to do about that
+            let _ = stream.shutdown();
+        }
+        Ok(())
+    }
+
+    /// connect to an endpoint of the form "tls://host:port"
+    fn connect(&mut self, endpoint: &str) -> Result<()> {
+        let connection_error = |error: String| -> failure::Error {
+            NetworkError::TlsConnectionError {
+                endpoint: endpoint.to_string(),
+                error,
+            }
+            .into()
+        };
+        let address = endpoint.trim_start_matches("tls://");
+        let host = match address.rfind(':') {
+            Some(index) => &address[..index],
+            None => return Err(connection_error("missing port".to_string())),
+        };
+        let domain = self
+            .config
+            .domain
+            .clone()
+            .unwrap_or_else(|| host.to_string());
+        let connector = self.config.connector()?;
+        let tcp_stream =
+            TcpStream::connect(address).map_err(|error| connection_error(error.to_string()))?;
+        let stream = connector
+            .connect(&domain, tcp_stream)
+            .map_err(|error| connection_error(error.to_string()))?;
+        self.stream = Some(stream);
+        self.maybe_endpoint = Some(endpoint.to_string());
+        Ok(())
+    }
+
+    /// a negative timeout waits until a message arrives
+    fn poll(&mut self, timeout_ms: i64) -> Result<bool> {
+        self.priv_decode_messages()?;
+        if !self.resp_queue.is_empty() {
+            return Ok(true);
+        }
+        let deadline = if timeout_ms < 0 {
+            None
+        } else {
+            Some(Instant::now() + Duration::from_millis(timeout_ms as u64))
+        };
+        let mut chunk = [0; 4096];
+        loop {
+            let stream = match self.stream {
+                Some(ref mut stream) => stream,
+                None => bail_generic!("TLS socket is not connected"),
+            };
+            // std rejects a zero read timeout, so wait at least a millisecond
+            let read_timeout = deadline.map(|deadline| {
+                let now = Instant::now();
+                if deadline > now {
+                    deadline - now
+                } else {
+                    Duration::from_millis(1)
+                }
+            });
+            stream.get_ref().set_read_timeout(read_timeout)?;
+            match stream.read(&mut chunk) {
+                Ok(0) => bail_generic!("TLS connection closed by the remote end"),
+                Ok(read) => {
+                    self.read_buffer.extend_from_slice(&chunk[..read]);
+                    self.priv_decode_messages()?;
+                    if !self.resp_queue.is_empty() {
+                        return Ok(true);
+                    }
+                }
+                Err(ref error)
+                    if error.kind() == ErrorKind::WouldBlock
+                        || error.kind() == ErrorKind::TimedOut => {}
+                Err(error) => return Err(error.into()),
+            }
+            if let Some(deadline) = deadline {
+                if Instant::now() >= deadline {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
+    fn recv(&mut self) -> Result<Vec<Vec<u8>>> {
+        if !self.poll(-1)? {
+            bail_generic!("no message received");
+        }
+        Ok(self.resp_queue.remove(0))
+    }
+
+    fn send(&mut self, data: &[&[u8]]) -> Result<()> {
+        let stream = match self.stream {
+            Some(ref mut stream) => stream,
+            None => bail_generic!("TLS socket is not connected"),
+        };
+        stream.write_all(&encode_message(data))?;
+        stream.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use native_tls::TlsAcceptor;
+    use std::{net::TcpListener, thread};
+
+    pub const TEST_CA_CERT_FILE: &str = "test_data/tls/localhost.pem";
+    const TEST_IDENTITY_FILE: &str = "test_data/tls/localhost.p12";
+    const TEST_IDENTITY_PASSWORD: &str = "holochain";
+
+    pub fn test_tls_config() -> TlsConfig {
+        TlsConfig {
+            ca_cert_file: Some(TEST_CA_CERT_FILE.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Spawns a TLS server for a single connection that hands every message it
+    /// receives to the given function until that returns false.
+    /// Returns the uri to connect to.
+    pub fn spawn_mock_tls_server<F>(mut on_message: F) -> String
+    where
+        F: FnMut(&mut TlsStream<TcpStream>, Vec<Vec<u8>>) -> bool + Send + 'static,
+    {
+        let identity = Identity::from_pkcs12(
+            &fs::read(TEST_IDENTITY_FILE).unwrap(),
+            TEST_IDENTITY_PASSWORD,
+        )
+        .unwrap();
+        let acceptor = TlsAcceptor::new(identity).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (tcp_stream, _) = listener.accept().unwrap();
+            let mut stream = match acceptor.accept(tcp_stream) {
+                Ok(stream) => stream,
+                // the client rejected our certificate
+                Err(_) => return,
+            };
+            let mut buffer = Vec::new();
+            let mut chunk = [0; 4096];
+            loop {
+                while let Ok(Some((message, length))) = decode_message(&buffer) {
+                    buffer.drain(..length);
+                    if !on_message(&mut stream, message) {
+                        return;
+                    }
+                }
+                match stream.read(&mut chunk) {
+                    Ok(0) | Err(_) => return,
+                    Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+                }
+            }
+        });
+        format!("tls://localhost:{}", port)
+    }
+
+    #[test]
+    fn it_round_trips_message_framing() {
+        let data: &[&[u8]] = &[&[0x24], &[], b"json", b"{}"];
+        let mut encoded = encode_message(data);
+        assert_eq!(decode_message(&encoded[..encoded.len() - 1]).unwrap(), None);
+        encoded.extend_from_slice(&encode_message(&[b"next"]));
+        let (message, length) = decode_message(&encoded).unwrap().unwrap();
+        assert_eq!(
+            message,
+            data.iter().map(|frame| frame.to_vec()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            decode_message(&encoded[length..]).unwrap().unwrap().0,
+            vec![b"next".to_vec()]
+        );
+    }
+
+    #[test]
+    fn it_rejects_oversized_messages_before_their_data_arrives() {
+        let mut too_many_frames = Vec::new();
+        too_many_frames.extend_from_slice(&(MAX_FRAME_COUNT as u32 + 1).to_be_bytes());
+        assert!(decode_message(&too_many_frames).is_err());
+
+        let mut too_big_frame = Vec::new();
+        too_big_frame.extend_from_slice(&1u32.to_be_bytes());
+        too_big_frame.extend_from_slice(&(MAX_FRAME_BYTES as u32 + 1).to_be_bytes());
+        assert!(decode_message(&too_big_frame).is_err());
+    }
+
+    #[test]
+    fn it_decodes_messages_from_where_it_left_off() {
+        let mut c = TlsIpcSocket::with_config(TlsConfig::default()).unwrap();
+        let mut data = encode_message(&[b"first"]);
+        data.extend_from_slice(&encode_message(&[b"second"]));
+        let split = data.len() - 2;
+        c.read_buffer.extend_from_slice(&data[..split]);
+        c.priv_decode_messages().unwrap();
+        assert_eq!(c.resp_queue, vec![vec![b"first".to_vec()]]);
+        c.read_buffer.extend_from_slice(&data[split..]);
+        c.priv_decode_messages().unwrap();
+        assert_eq!(
+            c.resp_queue,
+            vec![vec![b"first".to_vec()], vec![b"second".to_vec()]]
+        );
+        assert!(c.read_buffer.is_empty());
+        assert_eq!(c.read_offset, 0);
+    }
+
+    #[test]
+    fn it_tls_cycle() {
+        let uri = spawn_mock_tls_server(|stream, message| {
+            stream.write_all(&encode_message(&[&message[0]])).unwrap();
+            true
+        });
+        let mut c = TlsIpcSocket::with_config(test_tls_config()).unwrap();
+        c.connect(&uri).unwrap();
+        assert_eq!(Some(uri), c.endpoint());
+        c.send(&[&[42]]).unwrap();
+        assert!(c.poll(5000).unwrap());
+        assert_eq!(vec![vec![42]], c.recv().unwrap());
+        assert_eq!(false, c.poll(0).unwrap());
+        c.close().unwrap();
+    }
+
+    #[test]
+    fn it_fails_with_network_error_for_untrusted_certificate() {
+        let uri = spawn_mock_tls_server(|_, _| false);
+        let mut c = TlsIpcSocket::new().unwrap();
+        let error = c.connect(&uri).unwrap_err();
+        match error.downcast_ref::<NetworkError>() {
+            Some(NetworkError::TlsConnectionError { endpoint, .. }) => assert_eq!(endpoint, &uri),
+            _ => panic!("unexpected error: {:?}", error),
+        }
+    }
+
+    #[test]
+    fn it_fails_with_network_error_for_missing_ca_file() {
+        let mut c = TlsIpcSocket::with_config(TlsConfig {
+            ca_cert_file: Some("test_data/tls/missing.pem".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        match c
+            .connect("tls://localhost:1")
+            .unwrap_err()
+            .downcast::<NetworkError>()
+        {
+            Ok(NetworkError::TlsConfigError { .. }) => (),
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+}
@@ -6,18 +6,22 @@ use crate::ipc::{
     ipc_client::IpcClient,
     socket::{IpcSocket, MockIpcSocket, TestStruct, ZmqIpcSocket},
     spawn,
+    tls_socket::{TlsConfig, TlsIpcSocket},
     util::get_millis,
 };
 
-use crate::connection::{
-    json_protocol::{ConfigData, ConnectData, JsonProtocol, StateData},
-    net_connection::{NetHandler, NetSend, NetShutdown, NetWorker, NetWorkerFactory},
-    net_relay::NetConnectionRelay,
-    protocol::Protocol,
-    NetResult,
+use crate::{
+    connection::{
+        json_protocol::{ConfigData, ConnectData, JsonProtocol, StateData},
+        net_connection::{NetHandler, NetSend, NetShutdown, NetWorker, NetWorkerFactory},
+        net_relay::NetConnectionRelay,
+        protocol::Protocol,
+        NetResult,
+    },
+    error::NetworkError,
 };
 
-use std::{collections::HashMap, convert::TryFrom, sync::mpsc};
+use std::{collections::HashMap, convert::TryFrom, sync::mpsc, time::Duration};
 
 use serde_json;
 
@@ -47,11 +51,16 @@ impl IpcNetWorker {
     ) -> NetResult<Self> {
         // Load config
         let config: serde_json::Value = serde_json::from_str(config.into())?;
-        // Only zmq protocol is handled for now
-        if config["socketType"] != "zmq" {
+        // Only zmq and tls protocols are handled for now
+        let use_tls = config["socketType"] == "tls";
+        if config["socketType"] != "zmq" && !use_tls {
             bail!("unexpected socketType: {}", config["socketType"]);
         }
         let block_connect = config["blockConnect"].as_bool().unwrap_or(true);
+        let max_delay = config["reconnectMaxDelayMs"]
+            .as_u64()
+            .map(Duration::from_millis)
+            .unwrap_or(CONNECT_MAX_DELAY);
         let empty = vec![];
         let bootstrap_nodes: Vec<String> = config["bootstrapNodes"]
             .as_array()
@@ -59,6 +68,30 @@ impl IpcNetWorker {
             .iter()
             .map(|s| s.as_str().unwrap().to_string())
             .collect();
+        if use_tls {
+            // A node reached over TLS is remote, so there is nothing to spawn
+            let uri = match config["ipcUri"].as_str() {
+                Some(uri) => uri.to_string(),
+                None => bail!("socketType tls requires ipcUri"),
+            };
+            let tls_config: TlsConfig = if config["tls"].is_null() {
+                TlsConfig::default()
+            } else {
+                serde_json::from_value(config["tls"].clone()).map_err(|error| {
+                    NetworkError::TlsConfigError {
+                        error: error.to_string(),
+                    }
+                })?
+            };
+            return IpcNetWorker::priv_new_with_tls(
+                handler,
+                uri,
+                tls_config,
+                block_connect,
+                max_delay,
+                bootstrap_nodes,
+            );
+        }
         if config["ipcUri"].as_str().is_none() {
             // No 'ipcUri' config so use 'spawn' config
             if config["spawn"].as_object().is_none() {
@@ -92,6 +125,7 @@ impl IpcNetWorker {
                 enduser_config,
                 env,
                 block_connect,
+                max_delay,
                 bootstrap_nodes,
             );
         }
@@ -103,7 +137,12 @@ impl IpcNetWorker {
             Box::new(move |h| {
                 let mut socket = ZmqIpcSocket::new()?;
                 socket.connect(&uri)?;
-                let out: Box<NetWorker> = Box::new(IpcClient::new(h, socket, block_connect)?);
+                let out: Box<NetWorker> = Box::new(IpcClient::new_with_max_delay(
+                    h,
+                    socket,
+                    block_connect,
+                    max_delay,
+                )?);
                 Ok(out)
             }),
             None,
@@ -141,6 +180,7 @@ impl IpcNetWorker {
         config: String,
         env: HashMap<String, String>,
         block_connect: bool,
+        max_delay: Duration,
         bootstrap_nodes: Vec<String>,
     ) -> NetResult<Self> {
         // Spawn a process with given `cmd` that we will have an IPC connection with
@@ -154,7 +194,12 @@ impl IpcNetWorker {
         let factory = Box::new(move |h| {
             let mut socket = ZmqIpcSocket::new()?;
             socket.connect(&ipc_binding)?;
-            let out: Box<NetWorker> = Box::new(IpcClient::new(h, socket, block_connect)?);
+            let out: Box<NetWorker> = Box::new(IpcClient::new_with_max_delay(
+                h,
+                socket,
+                block_connect,
+                max_delay,
+            )?);
             Ok(out)
         });
 
@@ -162,6 +207,33 @@ impl IpcNetWorker {
         IpcNetWorker::priv_new(handler, factory, kill, bootstrap_nodes, endpoint)
     }
 
+    /// Constructor with IpcNetWorker instance connecting to a remote process over TLS
+    fn priv_new_with_tls(
+        handler: NetHandler,
+        uri: String,
+        tls_config: TlsConfig,
+        block_connect: bool,
+        max_delay: Duration,
+        bootstrap_nodes: Vec<String>,
+    ) -> NetResult<Self> {
+        let endpoint = uri.clone();
+
+        // Create factory: Creates a TLS IPC socket and an IpcClient NetWorker which uses it.
+        let factory = Box::new(move |h| {
+            let mut socket = TlsIpcSocket::with_config(tls_config)?;
+            socket.connect(&uri)?;
+            let out: Box<NetWorker> = Box::new(IpcClient::new_with_max_delay(
+                h,
+                socket,
+                block_connect,
+                max_delay,
+            )?);
+            Ok(out)
+        });
+
+        IpcNetWorker::priv_new(handler, factory, None, bootstrap_nodes, endpoint)
+    }
+
     /// Constructor without config
     /// Using a NetConnectionRelay as socket
     fn priv_new(
@@ -331,8 +403,14 @@ impl IpcNetWorker {
 mod tests {
     use super::*;
     use crate::{
-        connection::protocol::{NamedBinaryData, PongData},
-        ipc::socket::make_test_channels,
+        connection::{
+            json_protocol::TrackDnaData,
+            protocol::{NamedBinaryData, PongData},
+        },
+        ipc::{
+            socket::make_test_channels,
+            tls_socket::tests::{spawn_mock_tls_server, test_tls_config},
+        },
         p2p_config::P2pConfig,
     };
 
@@ -346,6 +424,67 @@ mod tests {
         .unwrap();
     }
 
+    #[test]
+    fn it_ipc_networker_tls_sends_track_dna() {
+        let (track_dna_send, track_dna_recv) = mpsc::channel::<JsonProtocol>();
+        let uri = spawn_mock_tls_server(move |_stream, message| {
+            if message.len() != 4 || message[2] != b"json" {
+                return true;
+            }
+            let json = Protocol::Json(String::from_utf8_lossy(&message[3]).to_string().into());
+            match JsonProtocol::try_from(&json) {
+                Ok(JsonProtocol::TrackDna(track_dna)) => {
+                    track_dna_send
+                        .send(JsonProtocol::TrackDna(track_dna))
+                        .unwrap();
+                    false
+                }
+                _ => true,
+            }
+        });
+        let config = P2pConfig::new_ipc_tls_uri(&uri, &test_tls_config(), &vec![], None);
+
+        let mut cli = Box::new(
+            IpcNetWorker::new(
+                Box::new(|_r| Ok(())),
+                &JsonString::from(config.backend_config.to_string()),
+                P2pConfig::default_end_user_config().to_string(),
+            )
+            .unwrap(),
+        );
+        assert_eq!(Some(uri), cli.endpoint());
+
+        let track_dna = JsonProtocol::TrackDna(TrackDnaData {
+            dna_address: "QmTrackDnaOverTls".into(),
+            agent_id: "alice".into(),
+        });
+        cli.receive(track_dna.clone().into()).unwrap();
+
+        assert_eq!(
+            track_dna,
+            track_dna_recv
+                .recv_timeout(std::time::Duration::from_millis(5000))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn it_ipc_networker_tls_requires_uri() {
+        let res = IpcNetWorker::new(
+            Box::new(|_r| Ok(())),
+            &json!({
+                "socketType": "tls",
+                "blockConnect": false
+            })
+            .into(),
+            P2pConfig::default_end_user_config().to_string(),
+        );
+        match res {
+            Err(e) => assert_eq!("socketType tls requires ipcUri", e.to_string()),
+            Ok(_) => panic!("expected error"),
+        }
+    }
+
     #[test]
     fn it_ipc_networker_spawn() {
         if let Err(e) = IpcNetWorker::new(
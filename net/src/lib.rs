@@ -26,6 +26,9 @@ pub mod error;
 pub mod in_memory;
 pub mod ipc;
 pub mod ipc_net_worker;
+pub mod neighborhood;
 pub mod p2p_config;
 pub mod p2p_network;
+pub mod peer_limit;
+pub mod publish_scheduler;
 pub mod tweetlog;
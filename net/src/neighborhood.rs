@@ -0,0 +1,65 @@
+//! Maps addresses and peers onto a common key space to tell which peers are
+//! responsible for holding an address.
+//! Locations are the SHA2-256 hashes of the address and of the peer's agent id.
+//! The neighborhood of an address consists of the peers closest to it by XOR distance.
+//! The backends of this crate currently sync everything with everyone, so this is the
+//! local node's expectation of where an entry should end up, meant for debugging.
+
+use holochain_core_types::cas::content::Address;
+use multihash::{encode, Hash};
+
+/// Number of peers that are expected to hold an address.
+pub const DEFAULT_NEIGHBORHOOD_SIZE: usize = 5;
+
+fn location(id: &str) -> Vec<u8> {
+    encode(Hash::SHA2256, id.as_bytes()).expect("SHA2-256 encoding can't fail")
+}
+
+fn distance(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b.iter()).map(|(a, b)| a ^ b).collect()
+}
+
+/// Returns up to `size` of the given peers, closest to the address first.
+pub fn neighborhood(address: &Address, peers: &[String], size: usize) -> Vec<String> {
+    let address_location = location(&String::from(address.clone()));
+    let mut peers: Vec<(Vec<u8>, String)> = peers
+        .iter()
+        .map(|peer| (distance(&location(peer), &address_location), peer.clone()))
+        .collect();
+    peers.sort();
+    peers.into_iter().take(size).map(|(_, peer)| peer).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peers() -> Vec<String> {
+        vec!["alice", "bob", "carol", "dave", "eve"]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn neighborhood_is_ordered_by_xor_distance() {
+        let address = Address::from("QmNeighborhoodTestAddress");
+        assert_eq!(
+            neighborhood(&address, &peers(), 5),
+            vec!["bob", "eve", "alice", "dave", "carol"]
+        );
+        assert_eq!(neighborhood(&address, &peers(), 2), vec!["bob", "eve"]);
+        assert!(neighborhood(&address, &[], 2).is_empty());
+    }
+
+    #[test]
+    fn neighborhood_does_not_depend_on_peer_order() {
+        let address = Address::from("QmNeighborhoodTestAddress");
+        let mut reversed = peers();
+        reversed.reverse();
+        assert_eq!(
+            neighborhood(&address, &reversed, 3),
+            neighborhood(&address, &peers(), 3)
+        );
+    }
+}
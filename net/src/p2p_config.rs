@@ -1,3 +1,4 @@
+use crate::ipc::tls_socket::TlsConfig;
 use holochain_core_types::{error::HolochainError, json::JsonString};
 use snowflake;
 use std::{fs::File, io::prelude::*, str::FromStr};
@@ -53,6 +54,15 @@ pub struct P2pConfig {
     pub backend_kind: P2pBackendKind,
     pub backend_config: serde_json::Value,
     pub maybe_end_user_config: Option<serde_json::Value>,
+    /// Maximum number of peers to accept connections from. No limit if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_peers: Option<usize>,
+    /// Data published by this node gets held back and handed to the network in publish rounds
+    /// this many milliseconds apart, or when a round gets triggered with
+    /// P2pNetwork::trigger_publish(), see [publish_scheduler](../publish_scheduler/index.html).
+    /// Published right away if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publish_interval_ms: Option<u64>,
 }
 
 // Conversions
@@ -82,6 +92,8 @@ impl P2pConfig {
             backend_config: serde_json::from_str(backend_config)
                 .expect("Invalid backend_config json on P2pConfig creation."),
             maybe_end_user_config,
+            max_peers: None,
+            publish_interval_ms: None,
         }
     }
 
@@ -118,6 +130,30 @@ impl P2pConfig {
         )
     }
 
+    /// IPC config for a remote p2p node reached over TLS at an uri like "tls://host:port"
+    pub fn new_ipc_tls_uri(
+        ipc_uri: &str,
+        tls_config: &TlsConfig,
+        bootstrap_nodes: &Vec<String>,
+        maybe_end_user_config_filepath: Option<String>,
+    ) -> Self {
+        let backend_config = json!({
+            "socketType": "tls",
+            "blockConnect": false,
+            "bootstrapNodes": bootstrap_nodes,
+            "ipcUri": ipc_uri,
+            "tls": tls_config
+        })
+        .to_string();
+        P2pConfig::new(
+            P2pBackendKind::IPC,
+            &backend_config,
+            Some(P2pConfig::load_end_user_config(
+                maybe_end_user_config_filepath,
+            )),
+        )
+    }
+
     pub fn default_ipc_uri(maybe_ipc_binding: Option<&str>) -> Self {
         match maybe_ipc_binding {
             None => P2pConfig::from_str(P2pConfig::DEFAULT_IPC_URI_CONFIG)
@@ -146,6 +182,16 @@ impl P2pConfig {
         )
     }
 
+    /// Memory backend config whose peer selection and delivery order are
+    /// reproducible for a given seed
+    pub fn new_with_seeded_memory_backend(server_name: &str, seed: u64) -> Self {
+        P2pConfig::new(
+            P2pBackendKind::MEMORY,
+            &Self::seeded_memory_backend_string(server_name, seed),
+            None,
+        )
+    }
+
     pub fn new_with_unique_memory_backend() -> Self {
         Self::new_with_memory_backend(&format!(
             "memory-auto-{}",
@@ -168,6 +214,16 @@ impl P2pConfig {
             server_name
         )
     }
+
+    pub fn seeded_memory_backend_string(server_name: &str, seed: u64) -> String {
+        format!(
+            r#"{{
+            "serverName": "{}",
+            "randomSeed": {}
+            }}"#,
+            server_name, seed
+        )
+    }
 }
 
 /// end_user config
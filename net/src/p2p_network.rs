@@ -8,25 +8,49 @@ use crate::connection::{
     protocol::Protocol,
     NetResult,
 };
-use std::{thread::sleep, time::Duration};
+use std::{
+    sync::{Arc, Mutex},
+    thread::sleep,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    in_memory::memory_worker::InMemoryWorker, ipc_net_worker::IpcNetWorker, p2p_config::*,
+    in_memory::memory_worker::InMemoryWorker,
+    ipc_net_worker::IpcNetWorker,
+    neighborhood::{neighborhood, DEFAULT_NEIGHBORHOOD_SIZE},
+    p2p_config::*,
+    peer_limit::{limit_peers, PeerLimiter, DEFAULT_PEER_IDLE_TIMEOUT},
+    publish_scheduler::{PublishScheduler, PublishTrigger},
 };
+use holochain_core_types::cas::content::Address;
 
 /// Facade handling a p2p module responsable for the network connection
 /// Holds a NetConnectionThread and implements itself the NetSend Trait
 /// `send()` is used for sending Protocol messages to the network
 /// `handler` closure provide on construction for handling Protocol messages received from the network.
 pub struct P2pNetwork {
-    connection: NetConnectionThread,
+    /// None once the network got stopped
+    connection: Option<NetConnectionThread>,
+    endpoint: String,
+    peers: Arc<Mutex<PeerLimiter>>,
+    /// Set if the config has a publish interval
+    publish_trigger: Option<PublishTrigger>,
 }
 
 impl P2pNetwork {
     /// Constructor
     /// `config` is the configuration of the p2p module
     /// `handler` is the closure for handling Protocol messages received from the network.
+    /// If the config sets `max_peers`, the handler does not get any messages
+    /// of peers beyond that limit, except for responses to requests sent through this network.
+    /// If it sets `publish_interval_ms`, published data gets handed to the backend
+    /// in publish rounds, see [publish_scheduler](../publish_scheduler/index.html).
     pub fn new(handler: NetHandler, p2p_config: &P2pConfig) -> NetResult<Self> {
+        let peers = Arc::new(Mutex::new(PeerLimiter::new(
+            p2p_config.max_peers.unwrap_or_else(usize::max_value),
+            DEFAULT_PEER_IDLE_TIMEOUT,
+        )));
+        let handler = limit_peers(handler, peers.clone());
         // Create Config struct
         let backend_config = p2p_config.backend_config.to_string().into();
         // Provide worker factory depending on backend kind
@@ -50,23 +74,74 @@ impl P2pNetwork {
                 Ok(Box::new(InMemoryWorker::new(h, &backend_config)?) as Box<NetWorker>)
             }),
         };
+        // Hold back published data for publish rounds if configured
+        let publish_trigger = p2p_config
+            .publish_interval_ms
+            .map(|_| PublishTrigger::new());
+        let worker_factory: NetWorkerFactory =
+            match (p2p_config.publish_interval_ms, publish_trigger.clone()) {
+                (Some(interval_ms), Some(trigger)) => Box::new(move |h| {
+                    Ok(Box::new(PublishScheduler::new(
+                        worker_factory(h)?,
+                        Duration::from_millis(interval_ms),
+                        trigger,
+                    )) as Box<NetWorker>)
+                }),
+                _ => worker_factory,
+            };
         // Create NetConnectionThread with appropriate worker factory
         let connection = NetConnectionThread::new(handler, worker_factory, None)?;
         if let P2pBackendKind::IPC = p2p_config.backend_kind {
             sleep(Duration::from_millis(1000));
         }
+        let endpoint = connection.endpoint.clone();
         // Done
-        Ok(P2pNetwork { connection })
+        Ok(P2pNetwork {
+            connection: Some(connection),
+            endpoint,
+            peers,
+            publish_trigger,
+        })
     }
 
     /// Stop the network connection (disconnect any sockets, join any threads, etc)
-    pub fn stop(self) -> NetResult<()> {
-        self.connection.stop()
+    /// Messages sent before get handed to the network first.
+    /// Sending fails after the network got stopped, stopping again does nothing.
+    pub fn stop(&mut self) -> NetResult<()> {
+        match self.connection.take() {
+            Some(connection) => connection.stop(),
+            None => Ok(()),
+        }
+    }
+
+    /// Starts a publish round right away instead of waiting for the publish interval to pass.
+    /// Does nothing if the config has no publish interval since everything gets
+    /// published right away then.
+    pub fn trigger_publish(&self) {
+        if let Some(ref trigger) = self.publish_trigger {
+            trigger.trigger();
+        }
     }
 
     /// Getter of the endpoint of its connection
     pub fn endpoint(&self) -> String {
-        self.connection.endpoint.clone()
+        self.endpoint.clone()
+    }
+
+    /// Number of peers we currently accept messages from
+    pub fn peer_count(&self) -> usize {
+        self.peers.lock().unwrap().count()
+    }
+
+    /// Agent ids of the peers we currently accept messages from
+    pub fn peers(&self) -> Vec<String> {
+        self.peers.lock().unwrap().peers()
+    }
+
+    /// The connected peers we expect to be responsible for holding the given address,
+    /// closest first. See [neighborhood](../neighborhood/index.html).
+    pub fn neighborhood_for(&self, address: &Address) -> Vec<String> {
+        neighborhood(address, &self.peers(), DEFAULT_NEIGHBORHOOD_SIZE)
     }
 }
 
@@ -79,7 +154,16 @@ impl std::fmt::Debug for P2pNetwork {
 impl NetSend for P2pNetwork {
     /// send a Protocol message to the p2p network instance
     fn send(&mut self, data: Protocol) -> NetResult<()> {
-        self.connection.send(data)
+        match self.connection.as_mut() {
+            Some(connection) => {
+                self.peers
+                    .lock()
+                    .unwrap()
+                    .request_sent(&data, Instant::now());
+                connection.send(data)
+            }
+            None => bail!("P2pNetwork is stopped"),
+        }
     }
 }
 
@@ -109,4 +193,16 @@ mod tests {
         res.send(Protocol::P2pReady).unwrap();
         res.stop().unwrap();
     }
+
+    #[test]
+    fn it_should_fail_to_send_after_stop() {
+        let mut res = P2pNetwork::new(
+            Box::new(|_r| Ok(())),
+            &P2pConfig::new_with_unique_memory_backend(),
+        )
+        .unwrap();
+        res.stop().unwrap();
+        assert!(res.send(Protocol::P2pReady).is_err());
+        res.stop().unwrap();
+    }
 }
@@ -0,0 +1,334 @@
+//! Caps the number of peers a P2pNetwork accepts connections from.
+//! A PeerLimiter tracks connected peers together with the time we last heard from them.
+//! Once the limit is reached, a newly connecting peer replaces the least recently active
+//! peer if that one has been idle for longer than the idle timeout, and gets rejected
+//! otherwise. A request from a peer we did not hear from before counts as connection
+//! attempt. Messages of rejected peers, including their PeerConnected notifications,
+//! don't get passed on.
+//!
+//! Peers are told apart by the protocol fields naming the agent that talks to us, which
+//! only identify the connected peer for notifications and requests. Store messages name
+//! the author of the data instead of the peer gossiping it, so they are not limited.
+//! Responses to requests this node sent always get through, whoever answers them.
+
+use crate::connection::{
+    json_protocol::JsonProtocol, net_connection::NetHandler, protocol::Protocol,
+};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Time without messages after which a peer can be evicted to make room for a new one.
+pub const DEFAULT_PEER_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Time after which we stop waiting for the response to a request we sent.
+/// Late responses are limited like any other message of their sender.
+pub const SENT_REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+pub struct PeerLimiter {
+    max_peers: usize,
+    idle_timeout: Duration,
+    /// connected peers and when we last heard from them, least recently active first
+    peers: Vec<(String, Instant)>,
+    /// ids of the requests we sent and are waiting for a response to, with the time sent
+    sent_requests: HashMap<String, Instant>,
+}
+
+impl PeerLimiter {
+    pub fn new(max_peers: usize, idle_timeout: Duration) -> Self {
+        PeerLimiter {
+            max_peers,
+            idle_timeout,
+            peers: Vec::new(),
+            sent_requests: HashMap::new(),
+        }
+    }
+
+    /// Remembers the id of a request we send to the network, so that its response
+    /// gets through even if the responding peer is beyond the limit.
+    pub fn request_sent(&mut self, message: &Protocol, now: Instant) {
+        let request_id = match JsonProtocol::try_from(message) {
+            Ok(JsonProtocol::SendMessage(message_data)) => message_data.request_id,
+            Ok(JsonProtocol::FetchEntry(fetch_data)) => fetch_data.request_id,
+            Ok(JsonProtocol::FetchMeta(fetch_data)) => fetch_data.request_id,
+            _ => return,
+        };
+        self.sent_requests
+            .retain(|_, sent| now.duration_since(*sent) < SENT_REQUEST_TIMEOUT);
+        self.sent_requests.insert(request_id, now);
+    }
+
+    /// True if the given id is the one of a request we sent and did not get
+    /// the response to yet. Forgets the request.
+    fn response_received(&mut self, request_id: &str) -> bool {
+        self.sent_requests.remove(request_id).is_some()
+    }
+
+    /// Registers a connecting peer. Returns false if the peer got rejected
+    /// because the limit is reached and no idle peer could be evicted.
+    pub fn connect(&mut self, agent_id: &str, now: Instant) -> bool {
+        if self.is_connected(agent_id) {
+            self.touch(agent_id, now);
+            return true;
+        }
+        if self.peers.len() >= self.max_peers {
+            let least_recent_is_idle = self
+                .peers
+                .first()
+                .map(|(_, last_seen)| now.duration_since(*last_seen) >= self.idle_timeout)
+                .unwrap_or(false);
+            if !least_recent_is_idle {
+                return false;
+            }
+            self.peers.remove(0);
+        }
+        self.peers.push((agent_id.to_string(), now));
+        true
+    }
+
+    /// Marks the given peer as active, if it is connected.
+    pub fn touch(&mut self, agent_id: &str, now: Instant) {
+        if let Some(position) = self.peers.iter().position(|(id, _)| id == agent_id) {
+            self.peers.remove(position);
+            self.peers.push((agent_id.to_string(), now));
+        }
+    }
+
+    pub fn is_connected(&self, agent_id: &str) -> bool {
+        self.peers.iter().any(|(id, _)| id == agent_id)
+    }
+
+    /// Agent ids of the currently connected peers.
+    pub fn peers(&self) -> Vec<String> {
+        self.peers.iter().map(|(id, _)| id.clone()).collect()
+    }
+
+    /// Number of currently connected peers.
+    pub fn count(&self) -> usize {
+        self.peers.len()
+    }
+}
+
+/// The id of the request a message received from the network responds to, if it is a
+/// response.
+fn responded_request(protocol: &JsonProtocol) -> Option<&str> {
+    match protocol {
+        JsonProtocol::SendMessageResult(message_data) => Some(&message_data.request_id),
+        JsonProtocol::FetchEntryResult(result_data) => Some(&result_data.request_id),
+        JsonProtocol::FetchMetaResult(result_data) => Some(&result_data.request_id),
+        _ => None,
+    }
+}
+
+/// The peer a message received from the network comes from.
+/// None for messages that don't name the peer and for requests of the network module
+/// itself, which come with an empty agent id.
+fn sender(protocol: &JsonProtocol) -> Option<&str> {
+    let agent_id = match protocol {
+        JsonProtocol::PeerConnected(peer_data) => &peer_data.agent_id,
+        JsonProtocol::HandleSendMessage(message_data)
+        | JsonProtocol::SendMessageResult(message_data) => &message_data.from_agent_id,
+        JsonProtocol::HandleFetchEntry(fetch_data) => &fetch_data.requester_agent_id,
+        JsonProtocol::FetchEntryResult(result_data) => &result_data.provider_agent_id,
+        JsonProtocol::HandleFetchMeta(fetch_data) => &fetch_data.requester_agent_id,
+        JsonProtocol::FetchMetaResult(result_data) => &result_data.provider_agent_id,
+        _ => return None,
+    };
+    if agent_id.is_empty() {
+        None
+    } else {
+        Some(agent_id)
+    }
+}
+
+/// Wraps the given handler so that it only gets messages of peers the limiter accepted,
+/// and responses to our own requests.
+/// Messages from peers count as activity.
+pub fn limit_peers(mut handler: NetHandler, limiter: Arc<Mutex<PeerLimiter>>) -> NetHandler {
+    Box::new(move |message| {
+        if let Ok(ref protocol) = message {
+            if let Ok(json_protocol) = JsonProtocol::try_from(protocol) {
+                let mut limiter = limiter.lock().unwrap();
+                let is_our_response = responded_request(&json_protocol)
+                    .map(|request_id| limiter.response_received(request_id))
+                    .unwrap_or(false);
+                if let Some(agent_id) = sender(&json_protocol) {
+                    if is_our_response {
+                        limiter.touch(agent_id, Instant::now());
+                    } else if !limiter.connect(agent_id, Instant::now()) {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        handler(message)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::json_protocol::{
+        EntryData, FetchEntryData, FetchEntryResultData, MessageData, PeerData,
+    };
+
+    fn peer_connected(agent_id: &str) -> Protocol {
+        JsonProtocol::PeerConnected(PeerData {
+            agent_id: agent_id.to_string(),
+        })
+        .into()
+    }
+
+    #[test]
+    fn connection_count_stays_capped() {
+        let limiter = Arc::new(Mutex::new(PeerLimiter::new(3, DEFAULT_PEER_IDLE_TIMEOUT)));
+        let forwarded = Arc::new(Mutex::new(Vec::new()));
+        let forwarded_clone = forwarded.clone();
+        // stands in for a backend that reports more peers than the limit
+        let mut handler = limit_peers(
+            Box::new(move |message| {
+                if let Ok(JsonProtocol::PeerConnected(peer_data)) =
+                    JsonProtocol::try_from(message.unwrap())
+                {
+                    forwarded_clone.lock().unwrap().push(peer_data.agent_id);
+                }
+                Ok(())
+            }),
+            limiter.clone(),
+        );
+
+        for i in 0..5 {
+            handler(Ok(peer_connected(&format!("peer{}", i)))).unwrap();
+        }
+        // a peer that is connected already does not count twice
+        handler(Ok(peer_connected("peer0"))).unwrap();
+
+        assert_eq!(limiter.lock().unwrap().count(), 3);
+        assert_eq!(
+            *forwarded.lock().unwrap(),
+            vec!["peer0", "peer1", "peer2", "peer0"]
+        );
+    }
+
+    #[test]
+    fn idle_peers_get_evicted() {
+        let start = Instant::now();
+        let mut limiter = PeerLimiter::new(2, Duration::from_secs(10));
+        assert!(limiter.connect("alice", start));
+        assert!(limiter.connect("bob", start));
+        assert!(!limiter.connect("carol", start + Duration::from_secs(5)));
+
+        limiter.touch("alice", start + Duration::from_secs(8));
+        // bob is the least recently active peer and idle by now
+        assert!(limiter.connect("carol", start + Duration::from_secs(12)));
+        assert_eq!(limiter.count(), 2);
+        assert!(limiter.is_connected("alice"));
+        assert!(!limiter.is_connected("bob"));
+        assert!(limiter.is_connected("carol"));
+    }
+
+    #[test]
+    fn messages_of_rejected_peers_are_not_handled() {
+        let limiter = Arc::new(Mutex::new(PeerLimiter::new(1, DEFAULT_PEER_IDLE_TIMEOUT)));
+        let handled = Arc::new(Mutex::new(Vec::new()));
+        let handled_clone = handled.clone();
+        let mut handler = limit_peers(
+            Box::new(move |message| {
+                handled_clone.lock().unwrap().push(message.unwrap());
+                Ok(())
+            }),
+            limiter.clone(),
+        );
+        let direct_message = |from: &str| -> Protocol {
+            JsonProtocol::HandleSendMessage(MessageData {
+                request_id: String::from("req"),
+                dna_address: "dna".into(),
+                to_agent_id: String::from("alice"),
+                from_agent_id: from.to_string(),
+                content: json!("hello"),
+            })
+            .into()
+        };
+        let store_entry = |from: &str| -> Protocol {
+            JsonProtocol::HandleStoreEntry(EntryData {
+                dna_address: "dna".into(),
+                provider_agent_id: from.to_string(),
+                entry_address: "entry".into(),
+                entry_content: json!("content"),
+            })
+            .into()
+        };
+
+        handler(Ok(peer_connected("bob"))).unwrap();
+        handler(Ok(peer_connected("mallory"))).unwrap();
+        handler(Ok(direct_message("mallory"))).unwrap();
+        // senders we did not get a PeerConnected for are limited just the same
+        handler(Ok(direct_message("eve"))).unwrap();
+        handler(Ok(direct_message("bob"))).unwrap();
+        // store messages name the author, not the peer that passes them on
+        handler(Ok(store_entry("mallory"))).unwrap();
+
+        assert_eq!(
+            *handled.lock().unwrap(),
+            vec![
+                peer_connected("bob"),
+                direct_message("bob"),
+                store_entry("mallory")
+            ]
+        );
+        assert_eq!(limiter.lock().unwrap().peers(), vec!["bob"]);
+    }
+
+    #[test]
+    fn responses_to_our_requests_get_through_when_the_limit_is_full() {
+        let limiter = Arc::new(Mutex::new(PeerLimiter::new(1, DEFAULT_PEER_IDLE_TIMEOUT)));
+        let handled = Arc::new(Mutex::new(Vec::new()));
+        let handled_clone = handled.clone();
+        let mut handler = limit_peers(
+            Box::new(move |message| {
+                handled_clone.lock().unwrap().push(message.unwrap());
+                Ok(())
+            }),
+            limiter.clone(),
+        );
+        let fetch_entry: Protocol = JsonProtocol::FetchEntry(FetchEntryData {
+            dna_address: "dna".into(),
+            request_id: String::from("our_fetch"),
+            requester_agent_id: String::from("alice"),
+            entry_address: "entry".into(),
+            if_newer_than: None,
+        })
+        .into();
+        let fetch_entry_result = |request_id: &str| -> Protocol {
+            JsonProtocol::FetchEntryResult(FetchEntryResultData {
+                dna_address: "dna".into(),
+                request_id: request_id.to_string(),
+                requester_agent_id: String::from("alice"),
+                provider_agent_id: String::from("mallory"),
+                entry_address: "entry".into(),
+                entry_content: json!("content"),
+                not_modified: false,
+            })
+            .into()
+        };
+
+        handler(Ok(peer_connected("bob"))).unwrap();
+        limiter
+            .lock()
+            .unwrap()
+            .request_sent(&fetch_entry, Instant::now());
+        handler(Ok(fetch_entry_result("our_fetch"))).unwrap();
+        // a response only gets through once, later ones are limited again
+        handler(Ok(fetch_entry_result("our_fetch"))).unwrap();
+        handler(Ok(fetch_entry_result("someone_elses_fetch"))).unwrap();
+
+        assert_eq!(
+            *handled.lock().unwrap(),
+            vec![peer_connected("bob"), fetch_entry_result("our_fetch")]
+        );
+        assert_eq!(limiter.lock().unwrap().peers(), vec!["bob"]);
+    }
+}
@@ -0,0 +1,189 @@
+//! Delays publishing of this node's own data so it gets handed to the network in batches,
+//! called publish rounds here, instead of right away.
+//! This does not schedule gossip: how published data spreads between other nodes
+//! is still up to the backend.
+//! A PublishScheduler wraps the worker of a P2pNetwork and holds back PublishEntry and
+//! PublishMeta messages until the publish interval has passed since the last round,
+//! until a round gets triggered through its PublishTrigger, or until
+//! MAX_PENDING_PUBLISHES messages are waiting.
+//! All other messages go to the wrapped worker directly.
+//! Held back messages only live in memory and are lost if the process dies before
+//! the next round.
+
+use crate::connection::{
+    json_protocol::JsonProtocol, net_connection::NetWorker, protocol::Protocol, NetResult,
+};
+use std::{
+    convert::TryFrom,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Number of held back publish messages that starts a round before the interval passed,
+/// so a busy node does not pile up published data in memory.
+pub const MAX_PENDING_PUBLISHES: usize = 1000;
+
+/// Makes the PublishScheduler it was created with start a publish round on its next tick.
+#[derive(Clone, Debug, Default)]
+pub struct PublishTrigger(Arc<AtomicBool>);
+
+impl PublishTrigger {
+    pub fn new() -> Self {
+        PublishTrigger::default()
+    }
+
+    pub fn trigger(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    fn take(&self) -> bool {
+        self.0.swap(false, Ordering::Relaxed)
+    }
+}
+
+fn is_publish(data: &Protocol) -> bool {
+    match JsonProtocol::try_from(data) {
+        Ok(JsonProtocol::PublishEntry(_)) | Ok(JsonProtocol::PublishMeta(_)) => true,
+        _ => false,
+    }
+}
+
+pub struct PublishScheduler {
+    worker: Box<NetWorker>,
+    interval: Duration,
+    trigger: PublishTrigger,
+    last_round: Instant,
+    /// published data waiting for the next round, oldest first,
+    /// never more than MAX_PENDING_PUBLISHES
+    pending: Vec<Protocol>,
+}
+
+impl PublishScheduler {
+    pub fn new(worker: Box<NetWorker>, interval: Duration, trigger: PublishTrigger) -> Self {
+        PublishScheduler {
+            worker,
+            interval,
+            trigger,
+            last_round: Instant::now(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Hands all pending data to the wrapped worker.
+    fn publish(&mut self) -> NetResult<bool> {
+        self.last_round = Instant::now();
+        let did_something = !self.pending.is_empty();
+        for data in self.pending.drain(..) {
+            self.worker.receive(data)?;
+        }
+        Ok(did_something)
+    }
+}
+
+impl NetWorker for PublishScheduler {
+    fn receive(&mut self, data: Protocol) -> NetResult<()> {
+        if is_publish(&data) {
+            self.pending.push(data);
+            if self.pending.len() >= MAX_PENDING_PUBLISHES {
+                self.publish()?;
+            }
+            Ok(())
+        } else {
+            self.worker.receive(data)
+        }
+    }
+
+    fn tick(&mut self) -> NetResult<bool> {
+        let mut did_something = false;
+        if self.trigger.take() || self.last_round.elapsed() >= self.interval {
+            did_something = self.publish()?;
+        }
+        Ok(self.worker.tick()? || did_something)
+    }
+
+    /// Hands over the pending data before stopping, like the worker gets all messages
+    /// sent before the network got stopped.
+    fn stop(mut self: Box<Self>) -> NetResult<()> {
+        self.publish()?;
+        self.worker.stop()
+    }
+
+    fn endpoint(&self) -> Option<String> {
+        self.worker.endpoint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::json_protocol::{EntryData, TrackDnaData};
+    use std::sync::Mutex;
+
+    /// stands in for a backend and records what it received
+    struct RecordingWorker(Arc<Mutex<Vec<Protocol>>>);
+
+    impl NetWorker for RecordingWorker {
+        fn receive(&mut self, data: Protocol) -> NetResult<()> {
+            self.0.lock().unwrap().push(data);
+            Ok(())
+        }
+    }
+
+    fn publish_entry() -> Protocol {
+        JsonProtocol::PublishEntry(EntryData {
+            dna_address: "QmDna".into(),
+            provider_agent_id: "alice".to_string(),
+            entry_address: "QmEntry".into(),
+            entry_content: json!("content"),
+        })
+        .into()
+    }
+
+    #[test]
+    fn holds_back_publishes_until_triggered() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let trigger = PublishTrigger::new();
+        let mut scheduler = PublishScheduler::new(
+            Box::new(RecordingWorker(received.clone())),
+            Duration::from_secs(3600),
+            trigger.clone(),
+        );
+        let track_dna: Protocol = JsonProtocol::TrackDna(TrackDnaData {
+            dna_address: "QmDna".into(),
+            agent_id: "alice".to_string(),
+        })
+        .into();
+
+        scheduler.receive(track_dna.clone()).unwrap();
+        scheduler.receive(publish_entry()).unwrap();
+        scheduler.tick().unwrap();
+        assert_eq!(*received.lock().unwrap(), vec![track_dna.clone()]);
+
+        trigger.trigger();
+        assert!(scheduler.tick().unwrap());
+        assert_eq!(*received.lock().unwrap(), vec![track_dna, publish_entry()]);
+        assert!(!scheduler.tick().unwrap());
+    }
+
+    #[test]
+    fn starts_a_round_when_too_many_publishes_are_pending() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let mut scheduler = PublishScheduler::new(
+            Box::new(RecordingWorker(received.clone())),
+            Duration::from_secs(3600),
+            PublishTrigger::new(),
+        );
+
+        for _ in 1..MAX_PENDING_PUBLISHES {
+            scheduler.receive(publish_entry()).unwrap();
+        }
+        assert!(received.lock().unwrap().is_empty());
+
+        scheduler.receive(publish_entry()).unwrap();
+        assert_eq!(received.lock().unwrap().len(), MAX_PENDING_PUBLISHES);
+        assert!(scheduler.pending.is_empty());
+    }
+}
@@ -1,7 +1,8 @@
 //! This module provides access to libsodium
 
-use super::{check_init, secbuf::SecBuf};
+use super::{check_init, hash, secbuf::SecBuf};
 use crate::error::SodiumError;
+use std::{collections::HashSet, sync::Mutex};
 
 /// Used to set the size of nonce var in the enc fns
 pub const NONCEBYTES: usize =
@@ -111,6 +112,74 @@ pub fn dec(
     Ok(())
 }
 
+/// Number of secret/nonce pairs a [NonceTracker](struct.NonceTracker.html) remembers
+/// if no other limit is given.
+pub const DEFAULT_MAX_TRACKED_NONCES: usize = 100_000;
+
+/// Opt-in guard against nonce reuse for callers that can't guarantee unique nonces
+/// by construction. Encrypting twice with the same nonce and secret breaks the encryption.
+///
+/// Remembers every secret/nonce pair it encrypted with and refuses to encrypt with
+/// a pair it has seen before. Secrets are only remembered by their sha256 hash.
+///
+/// Forgetting pairs would make reuse possible again, so once `max_tracked` pairs are
+/// remembered the tracker refuses to encrypt at all. Callers then have to rotate
+/// the secret and start over with a new tracker.
+pub struct NonceTracker {
+    used: Mutex<HashSet<(Vec<u8>, Vec<u8>)>>,
+    max_tracked: usize,
+}
+
+impl Default for NonceTracker {
+    fn default() -> Self {
+        NonceTracker::with_max_tracked(DEFAULT_MAX_TRACKED_NONCES)
+    }
+}
+
+impl NonceTracker {
+    pub fn new() -> Self {
+        NonceTracker::default()
+    }
+
+    pub fn with_max_tracked(max_tracked: usize) -> Self {
+        NonceTracker {
+            used: Mutex::new(HashSet::new()),
+            max_tracked,
+        }
+    }
+
+    /// Same as [enc](fn.enc.html) but returns an error without encrypting
+    /// if the nonce was already used with this secret or the tracker is full.
+    /// The pair only gets remembered if encrypting succeeded.
+    pub fn enc(
+        &self,
+        message: &mut SecBuf,
+        secret: &mut SecBuf,
+        adata: Option<&mut SecBuf>,
+        nonce: &mut SecBuf,
+        cipher: &mut SecBuf,
+    ) -> Result<(), SodiumError> {
+        let mut secret_hash = SecBuf::with_insecure(hash::BYTES256);
+        hash::sha256(secret, &mut secret_hash)?;
+        let pair = (secret_hash.read_lock().to_vec(), nonce.read_lock().to_vec());
+        // Held until the pair is inserted so no other thread can encrypt with it meanwhile
+        let mut used = self.used.lock().unwrap();
+        if used.contains(&pair) {
+            return Err(SodiumError::new(
+                "Nonce was already used for encrypting with this secret",
+            ));
+        }
+        if used.len() >= self.max_tracked {
+            return Err(SodiumError::new(
+                "Too many nonces tracked, the secret needs to be rotated",
+            ));
+        }
+        enc(message, secret, adata, nonce, cipher)?;
+        used.insert(pair);
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -193,6 +262,80 @@ mod tests {
             format!("{:?}", *decrypted_message)
         );
     }
+    #[test]
+    fn it_should_refuse_nonce_reuse_with_tracker() {
+        let tracker = NonceTracker::new();
+        let mut message = SecBuf::with_secure(16);
+        message.randomize();
+
+        let mut secret = SecBuf::with_secure(32);
+        secret.randomize();
+        let mut other_secret = SecBuf::with_secure(32);
+        other_secret.randomize();
+
+        let mut nonce = SecBuf::with_insecure(NONCEBYTES);
+        nonce.randomize();
+        let mut other_nonce = SecBuf::with_insecure(NONCEBYTES);
+        other_nonce.randomize();
+
+        let mut cipher = SecBuf::with_insecure(message.len() + ABYTES);
+        tracker
+            .enc(&mut message, &mut secret, None, &mut nonce, &mut cipher)
+            .unwrap();
+        tracker
+            .enc(
+                &mut message,
+                &mut secret,
+                None,
+                &mut other_nonce,
+                &mut cipher,
+            )
+            .unwrap();
+        // the same nonce with another secret is fine
+        tracker
+            .enc(
+                &mut message,
+                &mut other_secret,
+                None,
+                &mut nonce,
+                &mut cipher,
+            )
+            .unwrap();
+
+        let result = tracker.enc(&mut message, &mut secret, None, &mut nonce, &mut cipher);
+        match result {
+            Err(SodiumError::Generic(_)) => {}
+            other => panic!("expected nonce reuse to be refused, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_should_refuse_to_encrypt_once_the_tracker_is_full() {
+        let tracker = NonceTracker::with_max_tracked(2);
+        let mut message = SecBuf::with_secure(16);
+        message.randomize();
+
+        let mut secret = SecBuf::with_secure(32);
+        secret.randomize();
+
+        let mut cipher = SecBuf::with_insecure(message.len() + ABYTES);
+        for _ in 0..2 {
+            let mut nonce = SecBuf::with_insecure(NONCEBYTES);
+            nonce.randomize();
+            tracker
+                .enc(&mut message, &mut secret, None, &mut nonce, &mut cipher)
+                .unwrap();
+        }
+
+        let mut nonce = SecBuf::with_insecure(NONCEBYTES);
+        nonce.randomize();
+        let result = tracker.enc(&mut message, &mut secret, None, &mut nonce, &mut cipher);
+        match result {
+            Err(SodiumError::Generic(_)) => {}
+            other => panic!("expected a full tracker to refuse, got {:?}", other),
+        }
+    }
+
     #[test]
     fn it_should_with_bad_aead_encrypt_and_decrypt() {
         let mut message = SecBuf::with_secure(16);
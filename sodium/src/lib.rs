@@ -1,5 +1,6 @@
 #![warn(unused_extern_crates)]
 #![allow(warnings)]
+extern crate base64;
 extern crate rust_sodium_sys;
 #[macro_use]
 extern crate lazy_static;
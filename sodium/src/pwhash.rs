@@ -16,6 +16,58 @@ pub const ALG_ARGON2ID13: i8 = rust_sodium_sys::crypto_pwhash_ALG_ARGON2ID13 as
 
 pub const HASHBYTES: usize = 32 as usize;
 pub const SALTBYTES: usize = rust_sodium_sys::crypto_pwhash_SALTBYTES as usize;
+pub const STRBYTES: usize = rust_sodium_sys::crypto_pwhash_STRBYTES as usize;
+
+/// Predefined strengths of password hashing, weakest first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SecurityLevel {
+    Interactive,
+    Moderate,
+    Sensitive,
+}
+
+impl SecurityLevel {
+    pub fn ops_limit(self) -> u64 {
+        match self {
+            SecurityLevel::Interactive => OPSLIMIT_INTERACTIVE,
+            SecurityLevel::Moderate => OPSLIMIT_MODERATE,
+            SecurityLevel::Sensitive => OPSLIMIT_SENSITIVE,
+        }
+    }
+
+    pub fn mem_limit(self) -> usize {
+        match self {
+            SecurityLevel::Interactive => MEMLIMIT_INTERACTIVE,
+            SecurityLevel::Moderate => MEMLIMIT_MODERATE,
+            SecurityLevel::Sensitive => MEMLIMIT_SENSITIVE,
+        }
+    }
+}
+
+/// Check that the given limits are at least those of the given security level
+///
+/// @param {u64} opslimit - operation scaling for hashing algorithm
+///
+/// @param {usize} memlimit - memory scaling for hashing algorithm
+///
+/// @param {SecurityLevel} min_level - the weakest acceptable security level
+pub fn check_limits(
+    ops_limit: u64,
+    mem_limit: usize,
+    min_level: SecurityLevel,
+) -> Result<(), SodiumError> {
+    if ops_limit < min_level.ops_limit() || mem_limit < min_level.mem_limit() {
+        return Err(SodiumError::Generic(format!(
+            "pwhash limits (ops: {}, mem: {}) are below the minimum security level {:?} (ops: {}, mem: {})",
+            ops_limit,
+            mem_limit,
+            min_level,
+            min_level.ops_limit(),
+            min_level.mem_limit()
+        )));
+    }
+    Ok(())
+}
 
 /// Calculate a password hash
 ///
@@ -63,10 +115,143 @@ pub fn hash(
     }
 }
 
+/// Calculate a password hash like hash() but refuse to do so with limits below
+/// those of the given security level
+///
+/// @param {SecurityLevel} min_level - the weakest acceptable security level
+///
+/// See hash() for the other parameters
+pub fn hash_with_min_level(
+    password: &mut SecBuf,
+    ops_limit: u64,
+    mem_limit: usize,
+    alg: i8,
+    salt: &mut SecBuf,
+    hash_out: &mut SecBuf,
+    min_level: SecurityLevel,
+) -> Result<(), SodiumError> {
+    check_limits(ops_limit, mem_limit, min_level)?;
+    hash(password, ops_limit, mem_limit, alg, salt, hash_out)
+}
+
+/// Calculate a password hash in the ASCII string format of libsodium, which embeds
+/// the algorithm, the limits and a random salt, so it can be stored and verified
+/// with verify_str() without keeping track of them
+///
+/// @param {SecBuf} password - the password to hash
+///
+/// @param {u64} opslimit - operation scaling for hashing algorithm
+///
+/// @param {usize} memlimit - memory scaling for hashing algorithm
+pub fn hash_str(
+    password: &mut SecBuf,
+    ops_limit: u64,
+    mem_limit: usize,
+) -> Result<String, SodiumError> {
+    check_init();
+    let password = password.read_lock();
+    let mut out = [0 as libc::c_char; STRBYTES];
+    let pw_len = password.len() as libc::c_ulonglong;
+    let res = unsafe {
+        rust_sodium_sys::crypto_pwhash_str(
+            out.as_mut_ptr() as *mut _,
+            raw_ptr_ichar_immut!(password),
+            pw_len,
+            ops_limit as libc::c_ulonglong,
+            mem_limit,
+        )
+    };
+    match res {
+        0 => {
+            let hashed: Vec<u8> = out
+                .iter()
+                .take_while(|c| **c != 0)
+                .map(|c| *c as u8)
+                .collect();
+            String::from_utf8(hashed).map_err(|error| SodiumError::Generic(error.to_string()))
+        }
+        -1 => Err(SodiumError::OutOfMemory),
+        _ => unreachable!(),
+    }
+}
+
+/// Check a password against a hash created by hash_str()
+///
+/// @param {&str} hashed - the hash string
+///
+/// @param {SecBuf} password - the password to check
+///
+/// Returns false if the password does not match or if the hash string is malformed
+pub fn verify_str(hashed: &str, password: &mut SecBuf) -> bool {
+    check_init();
+    // libsodium expects a NUL-terminated string within STRBYTES
+    if hashed.len() >= STRBYTES || hashed.bytes().any(|b| b == 0) {
+        return false;
+    }
+    let mut hashed_c = [0 as libc::c_char; STRBYTES];
+    for (c, b) in hashed_c.iter_mut().zip(hashed.bytes()) {
+        *c = b as libc::c_char;
+    }
+    let password = password.read_lock();
+    let pw_len = password.len() as libc::c_ulonglong;
+    let res = unsafe {
+        rust_sodium_sys::crypto_pwhash_str_verify(
+            hashed_c.as_ptr() as *const _,
+            raw_ptr_ichar_immut!(password),
+            pw_len,
+        )
+    };
+    res == 0
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn it_should_enforce_min_security_level() {
+        let mut password = SecBuf::with_secure(HASHBYTES);
+        let mut pw_hash = SecBuf::with_secure(HASHBYTES);
+        let mut salt = SecBuf::with_insecure(SALTBYTES);
+        password.randomize();
+        salt.randomize();
+
+        let below = hash_with_min_level(
+            &mut password,
+            OPSLIMIT_INTERACTIVE,
+            MEMLIMIT_INTERACTIVE,
+            ALG_ARGON2ID13,
+            &mut salt,
+            &mut pw_hash,
+            SecurityLevel::Moderate,
+        );
+        assert!(below.is_err());
+        // a high ops limit does not make up for a low mem limit
+        assert!(check_limits(
+            OPSLIMIT_SENSITIVE,
+            MEMLIMIT_INTERACTIVE,
+            SecurityLevel::Moderate
+        )
+        .is_err());
+
+        hash_with_min_level(
+            &mut password,
+            OPSLIMIT_MODERATE,
+            MEMLIMIT_MODERATE,
+            ALG_ARGON2ID13,
+            &mut salt,
+            &mut pw_hash,
+            SecurityLevel::Moderate,
+        )
+        .unwrap();
+        assert!(check_limits(
+            OPSLIMIT_SENSITIVE,
+            MEMLIMIT_SENSITIVE,
+            SecurityLevel::Moderate
+        )
+        .is_ok());
+    }
+
     #[test]
     fn it_should_generate_with_random_salt() {
         let mut password = SecBuf::with_secure(HASHBYTES);
@@ -138,4 +323,35 @@ mod tests {
         assert_eq!(format!("{:?}", *pw1_hash), format!("{:?}", *pw2_hash));
     }
 
+    #[test]
+    fn it_should_verify_str_hashes() {
+        let mut password = SecBuf::with_secure(HASHBYTES);
+        password.randomize();
+        let hashed = hash_str(&mut password, OPSLIMIT_INTERACTIVE, MEMLIMIT_INTERACTIVE).unwrap();
+        assert!(hashed.starts_with("$argon2"));
+        assert!(verify_str(&hashed, &mut password));
+
+        // the salt is random
+        let hashed_again =
+            hash_str(&mut password, OPSLIMIT_INTERACTIVE, MEMLIMIT_INTERACTIVE).unwrap();
+        assert_ne!(hashed, hashed_again);
+        assert!(verify_str(&hashed_again, &mut password));
+
+        let mut other_password = SecBuf::with_secure(HASHBYTES);
+        other_password.randomize();
+        assert!(!verify_str(&hashed, &mut other_password));
+    }
+
+    #[test]
+    fn it_should_not_verify_malformed_str_hashes() {
+        let mut password = SecBuf::with_secure(HASHBYTES);
+        password.randomize();
+        let hashed = hash_str(&mut password, OPSLIMIT_INTERACTIVE, MEMLIMIT_INTERACTIVE).unwrap();
+
+        assert!(!verify_str("", &mut password));
+        assert!(!verify_str("not a hash", &mut password));
+        assert!(!verify_str(&hashed[..hashed.len() - 4], &mut password));
+        assert!(!verify_str(&format!("{}\0", hashed), &mut password));
+        assert!(!verify_str(&"$".repeat(STRBYTES), &mut password));
+    }
 }
@@ -47,6 +47,27 @@ impl SecBuf {
         unsafe { rust_sodium_sys::sodium_compare(raw_ptr_char!(a), raw_ptr_char!(b), a.len()) }
     }
 
+    /// Compares two SecBuf for equality in constant time, i.e. without stopping at
+    /// the first differing byte, so that secrets like tokens can't be guessed
+    /// byte by byte from how long the comparison takes.
+    /// Buffers of different length are never equal. Their length is not hidden,
+    /// so hash secrets of variable length to a fixed length before comparing them.
+    pub fn constant_time_eq(&mut self, b: &mut SecBuf) -> bool {
+        check_init();
+        if self.len() != b.len() {
+            return false;
+        }
+        let a = self.read_lock();
+        let b = b.read_lock();
+        unsafe {
+            rust_sodium_sys::sodium_memcmp(
+                a.as_ptr() as *const libc::c_void,
+                b.as_ptr() as *const libc::c_void,
+                a.len(),
+            ) == 0
+        }
+    }
+
     /// Load the [u8] into the SecBuf
     pub fn from_array(&mut self, data: &[u8]) -> Result<(), SodiumError> {
         if (data.len() != self.len()) {
@@ -115,6 +136,25 @@ mod tests {
         assert_eq!(0, val_3);
     }
 
+    #[test]
+    fn it_should_constant_time_eq() {
+        let mut a = SecBuf::with_insecure(4);
+        a.from_array(&[1, 2, 3, 4]).unwrap();
+        let mut b = SecBuf::with_insecure(4);
+        b.from_array(&[1, 2, 3, 4]).unwrap();
+        assert!(a.constant_time_eq(&mut b));
+        for position in 0..4 {
+            let mut c = SecBuf::with_insecure(4);
+            let mut data = [1, 2, 3, 4];
+            data[position] = 0;
+            c.from_array(&data).unwrap();
+            assert!(!a.constant_time_eq(&mut c));
+        }
+        let mut shorter = SecBuf::with_insecure(3);
+        shorter.from_array(&[1, 2, 3]).unwrap();
+        assert!(!a.constant_time_eq(&mut shorter));
+    }
+
     #[test]
     fn it_should_be_zero() {
         let mut buf = SecBuf::with_insecure(4);
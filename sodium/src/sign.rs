@@ -4,6 +4,7 @@ use crate::error::SodiumError;
 
 pub const PUBLICKEYBYTES: usize = rust_sodium_sys::crypto_sign_PUBLICKEYBYTES as usize;
 pub const SECRETKEYBYTES: usize = rust_sodium_sys::crypto_sign_SECRETKEYBYTES as usize;
+pub const SIGNATUREBYTES: usize = rust_sodium_sys::crypto_sign_BYTES as usize;
 
 /// Generate a signing keypair from a seed buffer
 ///
@@ -88,10 +89,111 @@ pub fn verify(signature: &mut SecBuf, message: &mut SecBuf, public_key: &mut Sec
     res == 0
 }
 
+/// verify a signature given the message and a publicKey,
+/// checking the buffer lengths before handing them to libsodium
+///
+/// @param {Buffer} signature
+///
+/// @param {Buffer} message
+///
+/// @param {Buffer} publicKey
+pub fn verify_checked(
+    signature: &mut SecBuf,
+    message: &mut SecBuf,
+    public_key: &mut SecBuf,
+) -> Result<bool, SodiumError> {
+    if signature.len() != SIGNATUREBYTES {
+        return Err(SodiumError::OutputLength(format!(
+            "signature must be a Buffer of length: {}.",
+            SIGNATUREBYTES
+        )));
+    }
+    if public_key.len() != PUBLICKEYBYTES {
+        return Err(SodiumError::OutputLength(format!(
+            "public key must be a Buffer of length: {}.",
+            PUBLICKEYBYTES
+        )));
+    }
+    Ok(verify(signature, message, public_key))
+}
+
+/// verify a base64 encoded signature given the message and a base64 encoded publicKey
+///
+/// @param {&str} signature_b64
+///
+/// @param {&[u8]} message
+///
+/// @param {&str} public_key_b64
+pub fn verify_b64(
+    signature_b64: &str,
+    message: &[u8],
+    public_key_b64: &str,
+) -> Result<bool, SodiumError> {
+    let mut signature = secbuf_from_b64(signature_b64, "signature")?;
+    let mut public_key = secbuf_from_b64(public_key_b64, "public key")?;
+    let mut message_buf = SecBuf::with_insecure(message.len());
+    message_buf.write(0, message)?;
+    verify_checked(&mut signature, &mut message_buf, &mut public_key)
+}
+
+/// decode a base64 string into a new insecure SecBuf
+fn secbuf_from_b64(data: &str, name: &str) -> Result<SecBuf, SodiumError> {
+    let bytes = base64::decode(data)
+        .map_err(|e| SodiumError::Generic(format!("Invalid base64 {}: {}", name, e)))?;
+    let mut buf = SecBuf::with_insecure(bytes.len());
+    buf.write(0, &bytes)?;
+    Ok(buf)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn b64_signature_fixture() -> (String, Vec<u8>, String) {
+        let mut seed = SecBuf::with_secure(32);
+        let mut public_key = SecBuf::with_insecure(PUBLICKEYBYTES);
+        let mut secret_key = SecBuf::with_secure(SECRETKEYBYTES);
+        let mut signature = SecBuf::with_insecure(SIGNATUREBYTES);
+        seed.randomize();
+        seed_keypair(&mut public_key, &mut secret_key, &mut seed).unwrap();
+
+        let message_bytes = b"provenance".to_vec();
+        let mut message = SecBuf::with_insecure(message_bytes.len());
+        message.write(0, &message_bytes).unwrap();
+        sign(&mut message, &mut secret_key, &mut signature).unwrap();
+
+        let signature_b64 = base64::encode(&**signature.read_lock());
+        let public_key_b64 = base64::encode(&**public_key.read_lock());
+        (signature_b64, message_bytes, public_key_b64)
+    }
+
+    #[test]
+    fn it_should_verify_b64_signature() {
+        let (signature, message, public_key) = b64_signature_fixture();
+        assert!(verify_b64(&signature, &message, &public_key).unwrap());
+    }
+
+    #[test]
+    fn it_should_not_verify_b64_signature_of_tampered_message() {
+        let (signature, mut message, public_key) = b64_signature_fixture();
+        message[0] ^= 1;
+        assert!(!verify_b64(&signature, &message, &public_key).unwrap());
+    }
+
+    #[test]
+    fn it_should_error_on_malformed_b64() {
+        let (signature, message, public_key) = b64_signature_fixture();
+        match verify_b64("not base64!", &message, &public_key) {
+            Err(SodiumError::Generic(_)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        // valid base64 but wrong length for a public key
+        match verify_b64(&signature, &message, "AAAA") {
+            Err(SodiumError::OutputLength(_)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
     #[test]
     fn it_should_get_true_on_good_verify() {
         let mut seed = SecBuf::with_secure(32);
@@ -0,0 +1,46 @@
+use holochain_core_types::{entry::Entry, error::HolochainError, json::*};
+
+/// Options for commit_entry().
+/// The default is to publish the entry right after it got committed and to add a new
+/// header for every commit, even if the same entry got committed before.
+#[derive(Deserialize, Debug, Serialize, DefaultJson, PartialEq, Clone)]
+pub struct CommitEntryOptions {
+    /// If false, the entry only gets committed to the source chain.
+    /// It can get published later with publish_entry().
+    pub publish: bool,
+    /// If true, committing the same entry as the agent's most recent commit does nothing
+    /// and returns the address of the existing entry instead of adding another header.
+    #[serde(default)]
+    pub idempotent: bool,
+}
+
+impl Default for CommitEntryOptions {
+    fn default() -> Self {
+        CommitEntryOptions {
+            publish: true,
+            idempotent: false,
+        }
+    }
+}
+
+impl CommitEntryOptions {
+    pub fn new(publish: bool) -> Self {
+        CommitEntryOptions {
+            publish,
+            idempotent: false,
+        }
+    }
+
+    pub fn with_idempotent(mut self, idempotent: bool) -> Self {
+        self.idempotent = idempotent;
+        self
+    }
+}
+
+/// Struct for input data received when Zome API function commit_entry() is invoked
+/// with options. A plain Entry is accepted as well and uses the default options.
+#[derive(Deserialize, Debug, Serialize, DefaultJson, PartialEq, Clone)]
+pub struct CommitEntryArgs {
+    pub entry: Entry,
+    pub options: CommitEntryOptions,
+}
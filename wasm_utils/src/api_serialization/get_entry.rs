@@ -7,7 +7,7 @@ use holochain_core_types::{
     json::*,
     time::Timeout,
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, convert::TryFrom};
 
 #[derive(Deserialize, Debug, Serialize, DefaultJson, Clone, PartialEq)]
 pub enum StatusRequestKind {
@@ -21,6 +21,10 @@ impl Default for StatusRequestKind {
     }
 }
 
+fn default_resolve_latest() -> bool {
+    true
+}
+
 /// Structure used to specify what should be returned to a call to get_entry_result()
 /// The default is to return the latest entry.
 #[derive(Deserialize, Debug, Serialize, DefaultJson, PartialEq, Clone)]
@@ -29,6 +33,17 @@ pub struct GetEntryOptions {
     pub entry: bool,
     pub headers: bool,
     pub timeout: Timeout,
+    /// With StatusRequestKind::Latest, follow the CRUD update links from the given
+    /// address to the most recent version, which is what gets returned.
+    /// If false, the version at the given address gets returned even if it was updated.
+    /// Defaults to true.
+    #[serde(default = "default_resolve_latest")]
+    pub resolve_latest: bool,
+    /// Address of a version of the entry the caller already has.
+    /// If that is the version that would be returned, the result is
+    /// GetEntryResultType::NotModified instead of the version itself.
+    #[serde(default)]
+    pub if_newer_than: Option<Address>,
 }
 
 impl Default for GetEntryOptions {
@@ -38,6 +53,8 @@ impl Default for GetEntryOptions {
             entry: true,
             headers: false,
             timeout: Default::default(),
+            resolve_latest: default_resolve_latest(),
+            if_newer_than: None,
         }
     }
 }
@@ -54,8 +71,17 @@ impl GetEntryOptions {
             entry,
             headers,
             timeout,
+            resolve_latest: default_resolve_latest(),
+            if_newer_than: None,
         }
     }
+
+    /// Only returns the entry if its version differs from the one at the given address,
+    /// see if_newer_than.
+    pub fn with_if_newer_than(mut self, version: Address) -> Self {
+        self.if_newer_than = Some(version);
+        self
+    }
 }
 
 #[derive(Deserialize, Debug, Serialize, DefaultJson)]
@@ -80,6 +106,9 @@ pub struct GetEntryResultItem {
     pub meta: Option<EntryResultMeta>,
     pub entry: Option<Entry>,
     pub headers: Vec<ChainHeader>, // headers if requested in options
+    /// True if the entry has more headers than the instance was configured to return
+    #[serde(default)]
+    pub headers_truncated: bool,
 }
 impl GetEntryResultItem {
     pub fn new(maybe_entry_with_meta: Option<(&EntryWithMeta, Vec<ChainHeader>)>) -> Self {
@@ -92,11 +121,13 @@ impl GetEntryResultItem {
                 }),
                 entry: Some(entry_with_meta.entry.clone()),
                 headers,
+                headers_truncated: false,
             },
             _ => GetEntryResultItem {
                 meta: None,
                 entry: None,
                 headers: Vec::new(),
+                headers_truncated: false,
             },
         }
     }
@@ -131,6 +162,9 @@ impl EntryHistory {
 pub enum GetEntryResultType {
     Single(GetEntryResultItem),
     All(EntryHistory),
+    /// The version that would have been returned is the one given with
+    /// GetEntryOptions::if_newer_than.
+    NotModified,
 }
 
 #[derive(Deserialize, Debug, Serialize, DefaultJson, Clone)]
@@ -163,13 +197,21 @@ impl GetEntryResult {
         match self.result {
             GetEntryResultType::Single(ref item) => item.meta.is_some(),
             GetEntryResultType::All(ref history) => !history.items.is_empty(),
+            GetEntryResultType::NotModified => true,
+        }
+    }
+
+    pub fn is_not_modified(&self) -> bool {
+        match self.result {
+            GetEntryResultType::NotModified => true,
+            _ => false,
         }
     }
 
     /// clears the entry result to be equivalent to not found
     pub fn clear(&mut self) {
         match self.result {
-            GetEntryResultType::Single(_) => {
+            GetEntryResultType::Single(_) | GetEntryResultType::NotModified => {
                 self.result = GetEntryResultType::Single(GetEntryResultItem::new(None))
             }
             GetEntryResultType::All(ref mut history) => history.items.clear(),
@@ -179,7 +221,7 @@ impl GetEntryResult {
     /// adds an item to history, or if Single, writes over the current value of the item
     pub fn push(&mut self, entry_with_meta: &EntryWithMeta, headers: Vec<ChainHeader>) {
         match self.result {
-            GetEntryResultType::Single(_) => {
+            GetEntryResultType::Single(_) | GetEntryResultType::NotModified => {
                 self.result = GetEntryResultType::Single(GetEntryResultItem::new(Some((
                     entry_with_meta,
                     headers,
@@ -189,6 +231,18 @@ impl GetEntryResult {
         };
     }
 
+    /// flags the headers of the item pushed last as not being all headers of the entry
+    pub fn mark_headers_truncated(&mut self) {
+        let item = match self.result {
+            GetEntryResultType::Single(ref mut item) => Some(item),
+            GetEntryResultType::All(ref mut history) => history.items.last_mut(),
+            GetEntryResultType::NotModified => None,
+        };
+        if let Some(item) = item {
+            item.headers_truncated = true;
+        }
+    }
+
     /// returns the entry searched for.  Note that if the GetEntryOptions did not
     /// include a request for the entry value, this function will return None even if the
     /// entry was found.
@@ -199,6 +253,43 @@ impl GetEntryResult {
                 let last = history.items.last()?;
                 last.entry.clone()
             }
+            GetEntryResultType::NotModified => None,
+        }
+    }
+
+    /// returns the address of the entry searched for, which is there even if the
+    /// GetEntryOptions did not include a request for the entry value.
+    pub fn latest_address(&self) -> Option<Address> {
+        let item = match self.result {
+            GetEntryResultType::Single(ref item) => item,
+            GetEntryResultType::All(ref history) => history.items.last()?,
+            GetEntryResultType::NotModified => return None,
+        };
+        item.meta.as_ref().map(|meta| meta.address.clone())
+    }
+
+    /// replaces the result with NotModified if the entry searched for is the given version
+    pub fn check_not_modified(&mut self, known_version: &Address) {
+        if self.latest_address().as_ref() == Some(known_version) {
+            self.mark_not_modified();
+        }
+    }
+
+    /// replaces the result with NotModified, e.g. if the holder of the entry told so
+    pub fn mark_not_modified(&mut self) {
+        self.result = GetEntryResultType::NotModified;
+    }
+
+    /// returns the entry searched for (see latest()) converted into the given type.
+    /// Returns Ok(None) if there is no entry and an error if the conversion fails.
+    pub fn latest_as<T>(&self) -> Result<Option<T>, HolochainError>
+    where
+        T: TryFrom<Entry>,
+        T::Error: Into<HolochainError>,
+    {
+        match self.latest() {
+            Some(entry) => T::try_from(entry).map(Some).map_err(Into::into),
+            None => Ok(None),
         }
     }
 }
@@ -211,6 +302,23 @@ mod tests {
         entry::{test_entry, test_entry_a, test_entry_b},
     };
 
+    #[derive(Serialize, Deserialize, Debug, DefaultJson, PartialEq)]
+    struct Post {
+        content: String,
+    }
+
+    impl TryFrom<Entry> for Post {
+        type Error = HolochainError;
+        fn try_from(entry: Entry) -> Result<Self, Self::Error> {
+            match entry {
+                Entry::App(_, value) => Post::try_from(value),
+                _ => Err(HolochainError::SerializationError(
+                    "not an app entry".to_string(),
+                )),
+            }
+        }
+    }
+
     #[test]
     fn test_get_entry_result_found() {
         let result = GetEntryResult::new(StatusRequestKind::Initial, None);
@@ -289,4 +397,38 @@ mod tests {
         result.clear();
         assert!(!result.found());
     }
+
+    #[test]
+    fn test_get_entry_latest_as() {
+        let mut result = GetEntryResult::new(StatusRequestKind::Latest, None);
+        assert_eq!(result.latest_as::<Post>(), Ok(None));
+
+        let post = Post {
+            content: String::from("hello"),
+        };
+        result.push(
+            &EntryWithMeta {
+                entry: Entry::App("post".into(), JsonString::from(post)),
+                crud_status: CrudStatus::Live,
+                maybe_crud_link: None,
+            },
+            vec![test_chain_header()],
+        );
+        assert_eq!(
+            result.latest_as::<Post>(),
+            Ok(Some(Post {
+                content: String::from("hello")
+            }))
+        );
+
+        result.push(
+            &EntryWithMeta {
+                entry: test_entry(),
+                crud_status: CrudStatus::Live,
+                maybe_crud_link: None,
+            },
+            vec![test_chain_header()],
+        );
+        assert!(result.latest_as::<Post>().is_err());
+    }
 }
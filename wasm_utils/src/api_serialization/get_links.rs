@@ -24,6 +24,12 @@ pub struct GetLinksOptions {
     pub status_request: LinksStatusRequestKind,
     pub sources: bool,
     pub timeout: Timeout,
+    /// Only return targets whose entry is of this type.
+    /// Nodes holding the links leave out the targets they hold and know to be of
+    /// another type. The calling node fetches the remaining targets to check their type
+    /// and leaves out those it can't find.
+    #[serde(default)]
+    pub target_type: Option<String>,
 }
 impl Default for GetLinksOptions {
     fn default() -> Self {
@@ -31,6 +37,7 @@ impl Default for GetLinksOptions {
             status_request: LinksStatusRequestKind::default(),
             sources: false,
             timeout: Default::default(),
+            target_type: None,
         }
     }
 }
@@ -1,4 +1,5 @@
 mod call;
+mod commit_entry;
 /// This module holds structs for all arguments and return types
 /// that get serialized and deserialized between core native and
 /// the WASM based ribosome.
@@ -18,4 +19,4 @@ mod update_entry;
 pub mod validation;
 mod zome_api_globals;
 
-pub use self::{call::*, query::*, update_entry::*, zome_api_globals::*};
+pub use self::{call::*, commit_entry::*, query::*, update_entry::*, zome_api_globals::*};